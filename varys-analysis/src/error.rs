@@ -14,12 +14,22 @@ pub enum Error {
     EmptyTrace,
     #[error("At most {0} labels are supported")]
     TooManyLabels(usize),
+    #[error("The labels of the {0} dataset do not match its label map, train the model again")]
+    LabelMapMismatch(String),
     #[error("Dataset proportions must be between 0 and 1")]
     ProportionError,
     #[error("Dataset proportions do not add up to 1")]
     ProportionSumError,
     #[error("Dataset too small for the given proportions (one or more partitions would be empty)")]
     DatasetTooSmall,
+    #[error("No queries were asked to both {0} and {1}")]
+    NoSharedQueries(String, String),
+    #[error("There is no training run called {0}")]
+    UnknownRun(String),
     #[error("Cannot load traffic trace")]
     CannotLoadTrace,
+    #[error("Could not start the inference server: {0}")]
+    ServerError(String),
+    #[error("Could not draw the plot: {0}")]
+    PlotError(String),
 }
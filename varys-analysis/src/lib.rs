@@ -1,4 +1,7 @@
 pub mod error;
 pub mod ml;
+pub mod outlier;
 pub mod plot;
+pub mod regime;
+pub mod timing;
 pub mod trace;
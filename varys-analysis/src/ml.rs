@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::fs;
 use std::fs::{DirEntry, File};
 use std::io::Write;
@@ -14,55 +15,244 @@ use varys_database::database::interaction::Interaction;
 use varys_network::address::MacAddress;
 
 use crate::error::Error;
+use crate::ml::cnn::distillation::{self, DistillationConfig};
 use crate::ml::cnn::training::CNNTrainingConfig;
 use crate::ml::cnn::{inference, CNNModelConfig};
-use crate::ml::data::{NumericTraceDataset, NumericTraceItem};
+use crate::ml::data::{NumericTraceDataset, NumericTraceItem, TraceRepresentation};
+use crate::ml::label_map::LabelMap;
+use crate::ml::metric::MetricPlugins;
+use crate::ml::run::RunDir;
+use crate::ml::stream::ShardedTraceDataset;
 
 mod activation;
 mod cnn;
 pub mod data;
+pub mod label_map;
+pub mod metric;
+pub mod run;
+pub mod server;
+pub mod stream;
+pub mod transfer;
 
 type Backend = Wgpu<AutoGraphicsApi, f32, i32>;
 type AutodiffBackend = Autodiff<Backend>;
 
-pub fn train<P: AsRef<Path>>(data_dir: P, interactions: Vec<Interaction>) -> Result<(), Error> {
+/// Options of a training that are not needed by every caller of [`train`].
+#[derive(Debug, Clone, Default)]
+pub struct TrainingOptions {
+    /// If set, the dataset is streamed from shards of this many traces on disk instead of being loaded into memory,
+    /// see [`ShardedTraceDataset`].
+    pub shard_size: Option<usize>,
+    /// Whether to search for the largest batch size that fits into the memory of the device instead of using the
+    /// default one. The selected size is stored in the training configuration.
+    pub auto_batch_size: bool,
+    /// The capture-environment regime of every session by its id, to split the dataset so that every partition
+    /// contains the regimes in the same proportions. Ignored for sharded datasets.
+    pub regimes: HashMap<i32, i32>,
+    /// How the traces are represented as inputs of the model.
+    pub representation: TraceRepresentation,
+}
+
+/// Train a model on the interactions of a dataset preset.
+///
+/// Every training creates a new [`RunDir`], which inference uses if it is the newest one.
+///
+/// # Arguments
+///
+/// * `data_dir`: The directory in which data files are stored.
+/// * `interactions`: The interactions of the dataset preset.
+/// * `preset`: The name of the dataset preset.
+/// * `metrics`: Custom metrics to compute and log during training, next to the accuracy and loss.
+/// * `options`: How to load the dataset and configure the training, see [`TrainingOptions`].
+pub fn train<P: AsRef<Path>>(
+    data_dir: P,
+    interactions: Vec<Interaction>,
+    preset: &str,
+    metrics: &MetricPlugins,
+    options: &TrainingOptions,
+) -> Result<(), Error> {
+    let TrainingOptions {
+        shard_size,
+        auto_batch_size,
+        ref regimes,
+        representation,
+    } = *options;
     let data_dir_string = data_dir.as_ref().to_string_lossy().to_string();
     fs::create_dir_all(ml_path(&data_dir_string))?;
 
+    let label_map = create_label_map(&data_dir, &interactions, preset)?;
     let device = WgpuDevice::default();
-    let mut dataset = NumericTraceDataset::load_or_new(&data_dir, interactions)?;
+    let run = RunDir::create(&data_dir)?;
+
+    info!("Saving run {} to {}", run.name(), run.path.display());
+
+    let config = training_config(label_map.len())
+        .with_auto_batch_size(auto_batch_size)
+        .with_representation(representation);
+
+    if let Some(shard_size) = shard_size {
+        let dataset = ShardedTraceDataset::load_or_new(
+            &data_dir,
+            interactions,
+            label_map,
+            shard_size,
+            CNNModelConfig::DEFAULT_INPUT_DIMENSIONS,
+            config.seed,
+        )?;
+        let (training_dataset, validation_dataset, _) = dataset.split_default()?;
+
+        info!("Beginning training on sharded dataset...");
+
+        training::train::<AutodiffBackend, _>(
+            &run,
+            config,
+            training_dataset,
+            validation_dataset,
+            false,
+            device,
+            metrics,
+        )?;
+    } else {
+        // a stored dataset might have been created before the regimes were assigned or without packet times
+        let mut dataset = if regimes.is_empty() && representation == TraceRepresentation::Packets {
+            NumericTraceDataset::load_or_new(&data_dir, interactions, label_map)?
+        } else {
+            NumericTraceDataset::with_regimes(&data_dir, interactions, label_map, regimes)?
+        };
+        dataset
+            .normalise()
+            .resize_all(CNNModelConfig::DEFAULT_INPUT_DIMENSIONS)
+            .shuffle();
+        dataset.save(&data_dir)?;
+        let (training_dataset, validation_dataset, _) = dataset.split_default()?;
+
+        info!("Beginning training...");
+
+        training::train::<AutodiffBackend, _>(
+            &run,
+            config,
+            training_dataset,
+            validation_dataset,
+            true,
+            device,
+            metrics,
+        )?;
+    }
+
+    println!("Training complete");
+
+    Ok(())
+}
+
+/// Distil the model of a training run into a smaller student that is faster for live inference.
+///
+/// The student is trained in a new [`RunDir`], so inference uses it from then on.
+///
+/// # Arguments
+///
+/// * `data_dir`: The directory in which data files are stored.
+/// * `interactions`: The interactions of the dataset preset.
+/// * `preset`: The name of the dataset preset, which the teacher has to be trained on.
+/// * `teacher`: The name of the run to distil, the newest one if this is `None`.
+/// * `metrics`: Custom metrics to compute and log during training, next to the accuracy and loss.
+pub fn distil<P: AsRef<Path>>(
+    data_dir: P,
+    interactions: Vec<Interaction>,
+    preset: &str,
+    teacher: Option<&str>,
+    metrics: &MetricPlugins,
+) -> Result<(), Error> {
+    let teacher_run = match teacher {
+        Some(name) => RunDir::named(&data_dir, name)?,
+        None => RunDir::latest(&data_dir)?,
+    };
+    let representation = inference::load_run_representation(&teacher_run)?;
+    let label_map = LabelMap::load(&data_dir, preset)?;
+    let device = WgpuDevice::default();
+    let run = RunDir::create(&data_dir)?;
+
+    info!(
+        "Distilling run {} into run {}",
+        teacher_run.name(),
+        run.name()
+    );
+
+    // a stored dataset might have been created without packet times
+    let mut dataset = if representation == TraceRepresentation::Packets {
+        NumericTraceDataset::load_or_new(&data_dir, interactions, label_map)?
+    } else {
+        NumericTraceDataset::new(&data_dir, interactions, label_map)?
+    };
     dataset
         .normalise()
         .resize_all(CNNModelConfig::DEFAULT_INPUT_DIMENSIONS)
         .shuffle();
-    dataset.save(&data_dir)?;
-    let config = CNNTrainingConfig::new(
-        CNNModelConfig::new(
-            dataset.num_labels(),
-            CNNModelConfig::DEFAULT_INPUT_DIMENSIONS,
-        ),
-        AdamConfig::new(),
-    );
+    let config = DistillationConfig::student(dataset.num_labels(), representation);
     let (training_dataset, validation_dataset, _) = dataset.split_default()?;
 
-    info!("Beginning training...");
-
-    training::train::<AutodiffBackend>(
-        &data_dir_string,
+    distillation::distil::<AutodiffBackend, _>(
+        &run,
+        &teacher_run,
         config,
         training_dataset,
         validation_dataset,
         device,
+        metrics,
     )?;
 
-    println!("Training complete");
+    println!("Distillation complete");
 
     Ok(())
 }
 
-pub fn test_dataset<P: AsRef<Path>>(data_dir: P) -> Result<(), Error> {
+fn training_config(num_labels: usize) -> CNNTrainingConfig {
+    CNNTrainingConfig::new(
+        CNNModelConfig::new(num_labels, CNNModelConfig::DEFAULT_INPUT_DIMENSIONS),
+        AdamConfig::new(),
+    )
+}
+
+/// Create the label map of a dataset preset from its interactions and save it to the data directory.
+///
+/// Returns the created label map.
+///
+/// # Arguments
+///
+/// * `data_dir`: The directory in which data files are stored.
+/// * `interactions`: The interactions of the dataset preset.
+/// * `preset`: The name of the dataset preset.
+pub fn create_label_map<P: AsRef<Path>>(
+    data_dir: P,
+    interactions: &[Interaction],
+    preset: &str,
+) -> Result<LabelMap, Error> {
+    fs::create_dir_all(ml_path(data_dir.as_ref().to_string_lossy().as_ref()))?;
+
+    let label_map = LabelMap::new(
+        preset,
+        interactions
+            .iter()
+            .map(|interaction| interaction.query.clone()),
+    )?;
+    let path = label_map.save(&data_dir)?;
+
+    info!(
+        "Saved {} labels of the {preset} dataset to {} ({})",
+        label_map.len(),
+        path.display(),
+        label_map.hash
+    );
+
+    Ok(label_map)
+}
+
+pub fn test_dataset<P: AsRef<Path>>(data_dir: P, preset: &str) -> Result<(), Error> {
     let device = WgpuDevice::default();
-    let (_, _, testing_dataset) = NumericTraceDataset::load(&data_dir)?.split_default()?;
+    let dataset = NumericTraceDataset::load(&data_dir)?;
+    if dataset.label_map != LabelMap::load(&data_dir, preset)? {
+        return Err(Error::LabelMapMismatch(preset.to_string()));
+    }
+    let (_, _, testing_dataset) = dataset.split_default()?;
     let mut num_correct = 0;
 
     for index in 0..testing_dataset.len() {
@@ -86,10 +276,11 @@ pub fn test_single<P: AsRef<Path>>(
     data_dir: P,
     capture_path: P,
     address: &MacAddress,
+    preset: &str,
 ) -> Result<Vec<(String, f32)>, Error> {
     let device = WgpuDevice::default();
     let trace = NumericTraceDataset::load_trace(capture_path, address)?;
-    let (_, _, testing_dataset) = NumericTraceDataset::load(&data_dir)?.split_default()?;
+    let label_map = LabelMap::load(&data_dir, preset)?;
     let output = inference::infer::<AutodiffBackend>(
         data_dir.as_ref().to_string_lossy().as_ref(),
         trace,
@@ -98,8 +289,11 @@ pub fn test_single<P: AsRef<Path>>(
     .flatten::<1>(0, 1)
     .to_data()
     .value;
+    if output.len() != label_map.len() {
+        return Err(Error::LabelMapMismatch(preset.to_string()));
+    }
 
-    Ok(testing_dataset.queries.into_iter().zip(output).collect())
+    Ok(label_map.labels.into_iter().zip(output).collect())
 }
 
 pub fn infer<P: AsRef<Path>>(
@@ -123,17 +317,20 @@ pub fn infer<P: AsRef<Path>>(
     Ok(recognised)
 }
 
+/// Compile the training and validation logs of the newest run into `.csv` summaries in its metrics directory.
 pub fn compile_all_logs<P: AsRef<Path>>(data_dir: P, id: &str) -> Result<(), Error> {
-    compile_logs(&data_dir, "train", id)?;
-    compile_logs(&data_dir, "valid", id)
+    let run = RunDir::latest(&data_dir)?;
+
+    compile_logs(&run, "train", id)?;
+    compile_logs(&run, "valid", id)
 }
 
-fn compile_logs<P: AsRef<Path>>(data_dir: P, name: &str, id: &str) -> Result<(), Error> {
-    let log_dir = data_dir.as_ref().join("ml").join(name);
+fn compile_logs(run: &RunDir, name: &str, id: &str) -> Result<(), Error> {
+    let log_dir = run.metrics_path(name);
     let mut csv = File::create(
-        data_dir
-            .as_ref()
-            .join("ml")
+        log_dir
+            .parent()
+            .unwrap_or(&run.path)
             .join(format!("{id}-{name}.csv")),
     )?;
     let mut epochs = fs::read_dir(log_dir)?
@@ -185,14 +382,6 @@ fn dataset_path<P: AsRef<Path>>(data_dir: P) -> PathBuf {
     ))
 }
 
-fn model_path(data_dir: &str) -> String {
-    format!("{}/model", ml_path(data_dir))
-}
-
-fn config_path(data_dir: &str) -> String {
-    format!("{}/config.json", ml_path(data_dir))
-}
-
 fn ml_path(data_dir: &str) -> String {
     format!("{data_dir}/ml")
 }
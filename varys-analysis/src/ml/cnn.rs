@@ -8,6 +8,7 @@ use burn::tensor::Tensor;
 
 use crate::ml::activation::{Tanh, ELU, SELU};
 
+pub mod distillation;
 pub mod inference;
 pub mod training;
 
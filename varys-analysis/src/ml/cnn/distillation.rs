@@ -0,0 +1,332 @@
+use std::fs::File;
+
+use burn::config::Config;
+use burn::data::dataloader::batcher::Batcher;
+use burn::data::dataloader::DataLoaderBuilder;
+use burn::data::dataset::Dataset;
+use burn::module::Module;
+use burn::nn::loss::CrossEntropyLossConfig;
+use burn::optim::AdamConfig;
+use burn::record::CompactRecorder;
+use burn::tensor::activation::{log_softmax, softmax};
+use burn::tensor::backend::{AutodiffBackend, Backend};
+use burn::tensor::{Data, Int, Shape, Tensor};
+use burn::train::logger::FileMetricLogger;
+use burn::train::metric::{AccuracyMetric, LossMetric};
+use burn::train::{ClassificationOutput, LearnerBuilder, TrainOutput, TrainStep, ValidStep};
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+use crate::ml::cnn::training::CNNTrainingConfig;
+use crate::ml::cnn::{inference, CNNModel, CNNModelConfig};
+use crate::ml::data::{NumericBatch, NumericTraceItem, TraceRepresentation, TrafficTraceBatcher};
+use crate::ml::metric::MetricPlugins;
+use crate::ml::run::RunDir;
+
+/// How many items the teacher labels at once.
+const TEACHER_BATCH_SIZE: usize = 256;
+
+#[derive(Config)]
+pub struct DistillationConfig {
+    /// The training configuration of the student, which is saved as the configuration of the run so the student is
+    /// used for inference like any other model.
+    pub training: CNNTrainingConfig,
+    /// The temperature the logits of the teacher and student are softened with before they are compared.
+    #[config(default = 4.0)]
+    pub temperature: f64,
+    /// The weight of the loss on the true labels, the loss on the teacher logits gets the rest.
+    #[config(default = 0.5)]
+    pub alpha: f64,
+}
+
+impl DistillationConfig {
+    /// Create the configuration of a student that is a fraction of the size of the default [`CNNModelConfig`].
+    ///
+    /// # Arguments
+    ///
+    /// * `num_classes`: The number of labels of the dataset.
+    /// * `representation`: How the traces are represented, which has to be the same as for the teacher.
+    pub fn student(num_classes: usize, representation: TraceRepresentation) -> Self {
+        Self::new(
+            CNNTrainingConfig::new(
+                CNNModelConfig::new(num_classes, CNNModelConfig::DEFAULT_INPUT_DIMENSIONS)
+                    .with_convolution_number_0(32)
+                    .with_dense_size(128),
+                AdamConfig::new(),
+            )
+            .with_representation(representation),
+        )
+    }
+}
+
+/// What a distillation run was distilled from, saved as `distillation.json` in the run directory.
+#[derive(Serialize, Deserialize, Debug)]
+struct DistillationReport {
+    teacher: String,
+    temperature: f64,
+    alpha: f64,
+}
+
+/// A dataset item with the logits the teacher predicted for it.
+#[derive(Debug, Clone)]
+pub struct DistillationItem {
+    pub item: NumericTraceItem,
+    pub teacher_logits: Vec<f32>,
+}
+
+/// The items of a dataset labelled by a teacher model.
+pub struct DistillationDataset {
+    items: Vec<DistillationItem>,
+}
+
+impl DistillationDataset {
+    /// Label all items of a dataset with the logits of a teacher model.
+    ///
+    /// # Arguments
+    ///
+    /// * `teacher`: The model to label the items with.
+    /// * `representation`: How the teacher represents traces.
+    /// * `dataset`: The items to label.
+    /// * `device`: The device to run the teacher on.
+    pub fn label<B: Backend, D: Dataset<NumericTraceItem>>(
+        teacher: &CNNModel<B>,
+        representation: TraceRepresentation,
+        dataset: &D,
+        device: &B::Device,
+    ) -> Self {
+        let batcher = TrafficTraceBatcher::<B>::with_representation(device.clone(), representation);
+        let mut items = Vec::with_capacity(dataset.len());
+
+        for start in (0..dataset.len()).step_by(TEACHER_BATCH_SIZE) {
+            let chunk: Vec<NumericTraceItem> = (start
+                ..(start + TEACHER_BATCH_SIZE).min(dataset.len()))
+                .filter_map(|index| dataset.get(index))
+                .collect();
+            let logits = teacher.forward(batcher.batch(chunk.clone()).traces);
+            let [_, num_classes] = logits.dims();
+            let logits = logits.to_data().convert::<f32>().value;
+
+            items.extend(chunk.into_iter().zip(logits.chunks(num_classes)).map(
+                |(item, logits)| DistillationItem {
+                    item,
+                    teacher_logits: logits.to_vec(),
+                },
+            ));
+        }
+
+        Self { items }
+    }
+}
+
+impl Dataset<DistillationItem> for DistillationDataset {
+    fn get(&self, index: usize) -> Option<DistillationItem> {
+        self.items.get(index).cloned()
+    }
+
+    fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct DistillationBatch<B: Backend> {
+    pub traces: Tensor<B, 2>,
+    pub targets: Tensor<B, 1, Int>,
+    pub teacher_logits: Tensor<B, 2>,
+}
+
+pub struct DistillationBatcher<B: Backend> {
+    batcher: TrafficTraceBatcher<B>,
+    device: B::Device,
+}
+
+impl<B: Backend> DistillationBatcher<B> {
+    pub fn new(device: B::Device, representation: TraceRepresentation) -> Self {
+        Self {
+            batcher: TrafficTraceBatcher::with_representation(device.clone(), representation),
+            device,
+        }
+    }
+}
+
+impl<B: Backend> Batcher<DistillationItem, DistillationBatch<B>> for DistillationBatcher<B> {
+    fn batch(&self, items: Vec<DistillationItem>) -> DistillationBatch<B> {
+        let num_classes = items
+            .first()
+            .map(|item| item.teacher_logits.len())
+            .unwrap_or_default();
+        let teacher_logits = Data::new(
+            items
+                .iter()
+                .flat_map(|item| item.teacher_logits.iter().copied())
+                .collect::<Vec<f32>>(),
+            Shape::new([items.len(), num_classes]),
+        );
+        let teacher_logits = Tensor::<B, 2>::from_data(teacher_logits.convert(), &self.device);
+        let NumericBatch { traces, targets } = self
+            .batcher
+            .batch(items.into_iter().map(|item| item.item).collect());
+
+        DistillationBatch {
+            traces,
+            targets,
+            teacher_logits,
+        }
+    }
+}
+
+/// A student model with the weights of its loss, trained on the true labels and the logits of a teacher.
+#[derive(Module, Debug)]
+pub struct StudentModel<B: Backend> {
+    model: CNNModel<B>,
+    temperature: f64,
+    alpha: f64,
+}
+
+impl<B: Backend> StudentModel<B> {
+    /// Compute the distillation loss of a batch.
+    ///
+    /// The loss is the cross entropy with the true labels weighted by `alpha` plus the cross entropy with the softened
+    /// teacher probabilities weighted by the rest. The latter is scaled by the squared temperature so its gradients keep
+    /// the same magnitude for every temperature.
+    pub fn forward_distillation(&self, batch: DistillationBatch<B>) -> ClassificationOutput<B> {
+        let output = self.model.forward(batch.traces);
+        let hard_loss = CrossEntropyLossConfig::new()
+            .init(&output.device())
+            .forward(output.clone(), batch.targets.clone());
+        let teacher = softmax(batch.teacher_logits / self.temperature, 1);
+        let student = log_softmax(output.clone() / self.temperature, 1);
+        let soft_loss = (teacher * student).sum_dim(1).mean().neg();
+
+        let loss = hard_loss * self.alpha
+            + soft_loss * ((1. - self.alpha) * self.temperature * self.temperature);
+
+        ClassificationOutput::new(loss, output, batch.targets)
+    }
+}
+
+impl<B: AutodiffBackend> TrainStep<DistillationBatch<B>, ClassificationOutput<B>>
+    for StudentModel<B>
+{
+    fn step(&self, batch: DistillationBatch<B>) -> TrainOutput<ClassificationOutput<B>> {
+        let item = self.forward_distillation(batch);
+
+        TrainOutput::new(self, item.loss.backward(), item)
+    }
+}
+
+impl<B: Backend> ValidStep<NumericBatch<B>, ClassificationOutput<B>> for StudentModel<B> {
+    fn step(&self, batch: NumericBatch<B>) -> ClassificationOutput<B> {
+        self.model
+            .forward_classification(batch.traces, batch.targets)
+    }
+}
+
+/// Distil the model of a run into a smaller student and save it to the directory of a new run.
+///
+/// The student is trained on the true labels and on the logits of the teacher for the training items. It is saved
+/// like the model of any other run, so inference uses it if its run is the newest one.
+///
+/// # Arguments
+///
+/// * `run`: The run to save the student to.
+/// * `teacher_run`: The run of the model to distil.
+/// * `config`: The distillation configuration, including the training configuration of the student.
+/// * `training_dataset`: The dataset to train the student on.
+/// * `validation_dataset`: The dataset to validate the student on, with the true labels only.
+/// * `device`: The device to train on.
+/// * `metrics`: Custom metrics to compute and log during training, next to the accuracy and loss.
+pub fn distil<B: AutodiffBackend, D: Dataset<NumericTraceItem> + 'static>(
+    run: &RunDir,
+    teacher_run: &RunDir,
+    config: DistillationConfig,
+    training_dataset: D,
+    validation_dataset: D,
+    device: B::Device,
+    metrics: &MetricPlugins,
+) -> Result<(), Error> {
+    B::seed(config.training.seed);
+
+    let representation = inference::load_run_representation(teacher_run)?;
+    if representation != config.training.representation {
+        info!("Using the {representation:?} representation of the teacher");
+    }
+    let mut config = config;
+    config.training.representation = representation;
+    config.training.save(run.config_path())?;
+    serde_json::to_writer_pretty(
+        File::create(run.path.join("distillation.json"))?,
+        &DistillationReport {
+            teacher: teacher_run.name(),
+            temperature: config.temperature,
+            alpha: config.alpha,
+        },
+    )?;
+
+    info!(
+        "Labelling the training dataset with the teacher of run {}...",
+        teacher_run.name()
+    );
+
+    let teacher = inference::load_run_model::<B::InnerBackend>(teacher_run, &device)?;
+    let training_dataset =
+        DistillationDataset::label(&teacher, representation, &training_dataset, &device);
+
+    let data_loader_training = DataLoaderBuilder::new(DistillationBatcher::<B>::new(
+        device.clone(),
+        representation,
+    ))
+    .batch_size(config.training.batch_size)
+    .num_workers(config.training.num_workers)
+    .shuffle(config.training.seed)
+    .build(training_dataset);
+    let data_loader_validation = DataLoaderBuilder::new(
+        TrafficTraceBatcher::<B::InnerBackend>::with_representation(device.clone(), representation),
+    )
+    .batch_size(config.training.batch_size)
+    .num_workers(config.training.num_workers)
+    .shuffle(config.training.seed)
+    .build(validation_dataset);
+    let mut learner_builder = LearnerBuilder::new(run.path.to_string_lossy().as_ref())
+        .metric_loggers(
+            FileMetricLogger::new(run.metrics_path("train").to_string_lossy().as_ref()),
+            FileMetricLogger::new(run.metrics_path("valid").to_string_lossy().as_ref()),
+        )
+        .metric_train_numeric(AccuracyMetric::new())
+        .metric_valid_numeric(AccuracyMetric::new())
+        .metric_train_numeric(LossMetric::new())
+        .metric_valid_numeric(LossMetric::new());
+    for (train_metric, valid_metric) in metrics
+        .create::<B>()
+        .into_iter()
+        .zip(metrics.create::<B::InnerBackend>())
+    {
+        learner_builder = learner_builder
+            .metric_train_numeric(train_metric)
+            .metric_valid_numeric(valid_metric);
+    }
+    let learner = learner_builder
+        .with_file_checkpointer(CompactRecorder::new())
+        .devices(vec![device.clone()])
+        .num_epochs(config.training.num_epochs)
+        .build(
+            StudentModel {
+                model: config.training.model.init::<B>(&device),
+                temperature: config.temperature,
+                alpha: config.alpha,
+            },
+            config.training.optimizer.init(),
+            config.training.learning_rate,
+        );
+
+    learner
+        .fit(data_loader_training, data_loader_validation)
+        .model
+        .save_file(run.model_path(), &CompactRecorder::new())
+        .map_err(Error::from)
+}
@@ -7,8 +7,10 @@ use burn::tensor::Tensor;
 
 use crate::error::Error;
 use crate::ml::cnn::training::CNNTrainingConfig;
-use crate::ml::data::{NumericTraceItem, TrafficTraceBatcher};
-use crate::ml::{config_path, model_path, AutodiffBackend};
+use crate::ml::cnn::CNNModel;
+use crate::ml::data::{NumericTraceItem, TraceRepresentation, TrafficTraceBatcher};
+use crate::ml::run::RunDir;
+use crate::ml::AutodiffBackend;
 use crate::trace::NumericTrafficTrace;
 
 pub fn predict(
@@ -27,11 +29,28 @@ pub fn infer<B: Backend<IntElem = i32>>(
     trace: NumericTrafficTrace,
     device: B::Device,
 ) -> Result<Tensor<B, 2>, Error> {
-    let config = CNNTrainingConfig::load(config_path(data_dir))?;
-    let record = CompactRecorder::new().load(model_path(data_dir).into(), &device)?;
-    let model = config.model.init_with::<B>(record);
-    let batcher = TrafficTraceBatcher::new(device);
-    let batch = batcher.batch(vec![NumericTraceItem { trace, label: 0 }]);
+    let run = RunDir::latest(data_dir)?;
+    let model = load_run_model::<B>(&run, &device)?;
+    let batcher = TrafficTraceBatcher::with_representation(device, load_run_representation(&run)?);
+    let batch = batcher.batch(vec![NumericTraceItem {
+        trace,
+        label: 0,
+        regime: 0,
+        times: Vec::new(),
+    }]);
 
     Ok(model.forward(batch.traces))
 }
+
+/// Load the model trained in a run.
+pub fn load_run_model<B: Backend>(run: &RunDir, device: &B::Device) -> Result<CNNModel<B>, Error> {
+    let config = CNNTrainingConfig::load(run.config_path())?;
+    let record = CompactRecorder::new().load(run.model_path(), device)?;
+
+    Ok(config.model.init_with::<B>(record))
+}
+
+/// Load how the traces were represented as inputs of the model trained in a run.
+pub fn load_run_representation(run: &RunDir) -> Result<TraceRepresentation, Error> {
+    Ok(CNNTrainingConfig::load(run.config_path())?.representation)
+}
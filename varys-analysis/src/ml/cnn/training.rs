@@ -1,18 +1,26 @@
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+
 use burn::config::Config;
+use burn::data::dataloader::batcher::Batcher;
 use burn::data::dataloader::DataLoaderBuilder;
+use burn::data::dataset::Dataset;
 use burn::module::Module;
 use burn::nn::loss::CrossEntropyLossConfig;
-use burn::optim::AdamConfig;
+use burn::optim::{AdamConfig, GradientsParams, Optimizer};
 use burn::record::CompactRecorder;
 use burn::tensor::backend::{AutodiffBackend, Backend};
 use burn::tensor::{Int, Tensor};
+use burn::train::logger::FileMetricLogger;
 use burn::train::metric::{AccuracyMetric, LossMetric};
 use burn::train::{ClassificationOutput, LearnerBuilder, TrainOutput, TrainStep, ValidStep};
+use log::{debug, info};
 
 use crate::error::Error;
 use crate::ml::cnn::{CNNModel, CNNModelConfig};
-use crate::ml::data::{NumericBatch, NumericTraceDataset, TrafficTraceBatcher};
-use crate::ml::{config_path, ml_path, model_path};
+use crate::ml::data::{NumericBatch, NumericTraceItem, TraceRepresentation, TrafficTraceBatcher};
+use crate::ml::metric::MetricPlugins;
+use crate::ml::run::RunDir;
 
 impl<B: AutodiffBackend> TrainStep<NumericBatch<B>, ClassificationOutput<B>> for CNNModel<B> {
     fn step(&self, batch: NumericBatch<B>) -> TrainOutput<ClassificationOutput<B>> {
@@ -51,6 +59,13 @@ pub struct CNNTrainingConfig {
     pub num_epochs: usize,
     #[config(default = 70)]
     pub batch_size: usize,
+    /// Search for the largest batch size that fits into the memory of the device before training, replacing
+    /// `batch_size` with it.
+    #[config(default = false)]
+    pub auto_batch_size: bool,
+    /// How the traces are represented as inputs of the model, inference uses the same representation.
+    #[config(default = "TraceRepresentation::Packets")]
+    pub representation: TraceRepresentation,
     #[config(default = 8)]
     pub num_workers: usize,
     #[config(default = 42)]
@@ -61,34 +76,65 @@ pub struct CNNTrainingConfig {
     pub decay: f64,
 }
 
-pub fn train<B: AutodiffBackend>(
-    data_dir: &str,
+/// Train a model and save it with its configuration, checkpoints and metrics to the directory of a run.
+///
+/// Set `shuffle` to `false` for datasets that are expensive to read in a random order, like
+/// [`ShardedTraceDataset`](crate::ml::stream::ShardedTraceDataset).
+pub fn train<B: AutodiffBackend, D: Dataset<NumericTraceItem> + 'static>(
+    run: &RunDir,
     config: CNNTrainingConfig,
-    training_dataset: NumericTraceDataset,
-    validation_dataset: NumericTraceDataset,
+    training_dataset: D,
+    validation_dataset: D,
+    shuffle: bool,
     device: B::Device,
+    metrics: &MetricPlugins,
 ) -> Result<(), Error> {
-    config.save(config_path(data_dir))?;
-
+    let mut config = config;
     B::seed(config.seed);
 
-    let batcher_train = TrafficTraceBatcher::<B>::new(device.clone());
-    let batcher_valid = TrafficTraceBatcher::<B::InnerBackend>::new(device.clone());
-    let data_loader_training = DataLoaderBuilder::new(batcher_train)
+    if config.auto_batch_size {
+        config.batch_size = find_batch_size::<B, _>(&config, &training_dataset, &device);
+        info!("Selected batch size {}", config.batch_size);
+    }
+    config.save(run.config_path())?;
+
+    let batcher_train =
+        TrafficTraceBatcher::<B>::with_representation(device.clone(), config.representation);
+    let batcher_valid = TrafficTraceBatcher::<B::InnerBackend>::with_representation(
+        device.clone(),
+        config.representation,
+    );
+    let mut data_loader_training = DataLoaderBuilder::new(batcher_train)
         .batch_size(config.batch_size)
-        .shuffle(config.seed)
-        .num_workers(config.num_workers)
-        .build(training_dataset);
-    let data_loader_validation = DataLoaderBuilder::new(batcher_valid)
+        .num_workers(config.num_workers);
+    let mut data_loader_validation = DataLoaderBuilder::new(batcher_valid)
         .batch_size(config.batch_size)
-        .shuffle(config.seed)
-        .num_workers(config.num_workers)
-        .build(validation_dataset);
-    let learner = LearnerBuilder::new(&ml_path(data_dir))
+        .num_workers(config.num_workers);
+    if shuffle {
+        data_loader_training = data_loader_training.shuffle(config.seed);
+        data_loader_validation = data_loader_validation.shuffle(config.seed);
+    }
+    let data_loader_training = data_loader_training.build(training_dataset);
+    let data_loader_validation = data_loader_validation.build(validation_dataset);
+    let mut learner_builder = LearnerBuilder::new(run.path.to_string_lossy().as_ref())
+        .metric_loggers(
+            FileMetricLogger::new(run.metrics_path("train").to_string_lossy().as_ref()),
+            FileMetricLogger::new(run.metrics_path("valid").to_string_lossy().as_ref()),
+        )
         .metric_train_numeric(AccuracyMetric::new())
         .metric_valid_numeric(AccuracyMetric::new())
         .metric_train_numeric(LossMetric::new())
-        .metric_valid_numeric(LossMetric::new())
+        .metric_valid_numeric(LossMetric::new());
+    for (train_metric, valid_metric) in metrics
+        .create::<B>()
+        .into_iter()
+        .zip(metrics.create::<B::InnerBackend>())
+    {
+        learner_builder = learner_builder
+            .metric_train_numeric(train_metric)
+            .metric_valid_numeric(valid_metric);
+    }
+    let learner = learner_builder
         .with_file_checkpointer(CompactRecorder::new())
         .devices(vec![device.clone()])
         .num_epochs(config.num_epochs)
@@ -100,6 +146,94 @@ pub fn train<B: AutodiffBackend>(
 
     learner
         .fit(data_loader_training, data_loader_validation)
-        .save_file(model_path(data_dir), &CompactRecorder::new())
+        .save_file(run.model_path(), &CompactRecorder::new())
         .map_err(Error::from)
 }
+
+/// The largest batch size the automatic batch size search tries.
+const MAX_AUTO_BATCH_SIZE: usize = 1024;
+
+/// Parts of the lowercase panic messages with which the backend reports that the device ran out of memory.
+const OUT_OF_MEMORY_MESSAGES: [&str; 4] = [
+    "out of memory",
+    "outofmemory",
+    "not enough memory",
+    "buffer size",
+];
+
+/// Find the largest batch size for which a training step fits into the memory of the device.
+///
+/// Running out of memory panics in the backend, so a training step including the update of the optimizer is tried for
+/// each candidate size and a panic about the memory counts as not fitting, while any other panic is passed on. The
+/// sizes are searched with a binary search between 1 and the smaller of the dataset length and
+/// [`MAX_AUTO_BATCH_SIZE`].
+///
+/// # Arguments
+///
+/// * `config`: The training configuration to create the model from.
+/// * `dataset`: The dataset to take the items of the trial batches from.
+/// * `device`: The device to train on.
+fn find_batch_size<B: AutodiffBackend, D: Dataset<NumericTraceItem>>(
+    config: &CNNTrainingConfig,
+    dataset: &D,
+    device: &B::Device,
+) -> usize {
+    let model = config.model.init::<B>(device);
+    let batcher =
+        TrafficTraceBatcher::<B>::with_representation(device.clone(), config.representation);
+    let fits = |batch_size: usize| {
+        let items = (0..batch_size)
+            .filter_map(|index| dataset.get(index % dataset.len()))
+            .collect::<Vec<_>>();
+
+        let step = panic::catch_unwind(AssertUnwindSafe(|| {
+            let batch = batcher.batch(items);
+            let output = model.forward_classification(batch.traces.clone(), batch.targets);
+            let gradients = GradientsParams::from_grads(output.loss.backward(), &model);
+            let mut optimizer = config.optimizer.init::<B, CNNModel<B>>();
+            let model = optimizer.step(config.learning_rate, model.clone(), gradients);
+            // reading an output of the updated model waits for the step to actually run on the device
+            model.forward(batch.traces).into_data();
+        }));
+
+        match step {
+            Ok(()) => true,
+            Err(payload) if is_out_of_memory(payload.as_ref()) => false,
+            Err(payload) => panic::resume_unwind(payload),
+        }
+    };
+
+    let (mut low, mut high) = (1, dataset.len().clamp(1, MAX_AUTO_BATCH_SIZE));
+    while low < high {
+        let batch_size = (low + high).div_ceil(2);
+        if fits(batch_size) {
+            debug!("Batch size {batch_size} fits into memory");
+            low = batch_size;
+        } else {
+            debug!("Batch size {batch_size} does not fit into memory");
+            high = batch_size - 1;
+        }
+    }
+
+    low
+}
+
+/// Check whether a panic was caused by the device running out of memory, see [`OUT_OF_MEMORY_MESSAGES`].
+///
+/// # Arguments
+///
+/// * `payload`: The payload of the panic.
+fn is_out_of_memory(payload: &(dyn Any + Send)) -> bool {
+    let message = match payload.downcast_ref::<&str>() {
+        Some(message) => message.to_string(),
+        None => payload
+            .downcast_ref::<String>()
+            .cloned()
+            .unwrap_or_default(),
+    }
+    .to_lowercase();
+
+    OUT_OF_MEMORY_MESSAGES
+        .iter()
+        .any(|part| message.contains(part))
+}
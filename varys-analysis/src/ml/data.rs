@@ -1,3 +1,4 @@
+use std::collections::{BTreeMap, HashMap};
 use std::fs::{File, OpenOptions};
 use std::io::{BufReader, Write};
 use std::path::Path;
@@ -18,15 +19,56 @@ use varys_network::packet;
 
 use crate::error::Error;
 use crate::ml;
-use crate::trace::{NumericTrafficTrace, TrafficTrace};
+use crate::ml::label_map::LabelMap;
+use crate::trace::{BurstTrafficTrace, DuplicateFilter, NumericTrafficTrace, TrafficTrace};
+
+/// How the batcher turns the traces of items into the inputs of the model.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TraceRepresentation {
+    /// The signed size of every packet.
+    #[default]
+    Packets,
+    /// The size, packet count and gap of every burst of packets in the same direction, see [`BurstTrafficTrace`].
+    Bursts,
+}
 
 pub struct TrafficTraceBatcher<B: Backend> {
     device: B::Device,
+    representation: TraceRepresentation,
 }
 
 impl<B: Backend> TrafficTraceBatcher<B> {
     pub fn new(device: B::Device) -> Self {
-        Self { device }
+        Self::with_representation(device, TraceRepresentation::Packets)
+    }
+
+    /// Create a batcher that represents the traces of items in the given way.
+    ///
+    /// Burst traces are resized to the length of the packet trace they are created from, so the model inputs have the
+    /// same dimensions for both representations.
+    ///
+    /// # Arguments
+    ///
+    /// * `device`: The device to create the batches on.
+    /// * `representation`: How to represent the traces.
+    pub fn with_representation(device: B::Device, representation: TraceRepresentation) -> Self {
+        Self {
+            device,
+            representation,
+        }
+    }
+
+    /// Get the trace of an item in the representation of this batcher.
+    fn represent(&self, item: &NumericTraceItem) -> NumericTrafficTrace {
+        match self.representation {
+            TraceRepresentation::Packets => item.trace.clone(),
+            TraceRepresentation::Bursts => {
+                let mut trace =
+                    BurstTrafficTrace::from_packets(&item.trace.0, &item.times).as_numeric_trace();
+                trace.resize(item.trace.0.len());
+                trace
+            }
+        }
     }
 }
 
@@ -34,6 +76,13 @@ impl<B: Backend> TrafficTraceBatcher<B> {
 pub struct NumericTraceItem {
     pub trace: NumericTrafficTrace,
     pub label: u8,
+    /// The capture-environment regime of the session the trace was recorded in, see [`crate::regime`].
+    #[serde(default)]
+    pub regime: i32,
+    /// The time of every packet of the trace in seconds, see [`TrafficTrace::as_packet_times`]. Empty for items that
+    /// were stored without them.
+    #[serde(default)]
+    pub times: Vec<f32>,
 }
 
 impl NumericTraceItem {
@@ -48,25 +97,28 @@ impl NumericTraceItem {
     /// See [`NumericTrafficTrace::resize`].
     pub fn resize(&mut self, len: usize) {
         self.trace.resize(len);
+        if !self.times.is_empty() {
+            let last = self.times.last().copied().unwrap_or_default();
+            self.times.resize(len, last);
+        }
     }
 }
 
 #[derive(Deserialize, Serialize)]
 pub struct NumericTraceDataset {
     pub items: Vec<NumericTraceItem>,
-    /// The label of a query is the index of the query in this vector
-    pub queries: Vec<String>,
+    /// The labels of the items
+    pub label_map: LabelMap,
 }
 
 impl NumericTraceDataset {
-    const DEFAULT_TRAINING_PROPORTION: f64 = 0.64;
-    const DEFAULT_VALIDATION_PROPORTION: f64 = 0.16;
-    const DEFAULT_TESTING_PROPORTION: f64 = 0.2;
-    const MAX_LABELS: usize = u8::MAX as usize;
+    pub(crate) const DEFAULT_TRAINING_PROPORTION: f64 = 0.64;
+    pub(crate) const DEFAULT_VALIDATION_PROPORTION: f64 = 0.16;
+    pub(crate) const DEFAULT_TESTING_PROPORTION: f64 = 0.2;
 
     /// Load a dataset from disk, if it is found or create it from a list of [`Interaction`]s.
     ///
-    /// If no existing dataset is found, a new one is created.
+    /// If no existing dataset with the same labels is found, a new one is created.
     ///
     /// Note that this will always prefer loading from disk even if the existing dataset does not
     /// match the given interactions.
@@ -76,15 +128,20 @@ impl NumericTraceDataset {
     /// * `data_path`: The path to the data directory.
     /// * `interactions`: The interactions to create the dataset from if no dataset is found on
     /// disk.
+    /// * `label_map`: The labels of the dataset.
     pub fn load_or_new<P: AsRef<Path>>(
         data_path: P,
         interactions: Vec<Interaction>,
+        label_map: LabelMap,
     ) -> Result<NumericTraceDataset, Error> {
         if ml::dataset_path(&data_path).exists() {
-            NumericTraceDataset::load(data_path)
-        } else {
-            NumericTraceDataset::new(data_path, interactions)
+            match NumericTraceDataset::load(&data_path) {
+                Ok(dataset) if dataset.label_map == label_map => return Ok(dataset),
+                _ => info!("The stored dataset has different labels, creating it again"),
+            }
         }
+
+        NumericTraceDataset::new(data_path, interactions, label_map)
     }
 
     /// Create a dataset of all numeric traffic traces from a list of interactions.
@@ -96,11 +153,31 @@ impl NumericTraceDataset {
     ///
     /// * `data_path`: The path to the data directory.
     /// * `interactions`: The interactions to create the dataset from.
-    ///
-    /// returns: The created dataset or [`Error::TooManyLabels`] if there were too many different queries.
+    /// * `label_map`: The labels of the dataset, interactions whose query has no label are dropped.
     pub fn new<P: AsRef<Path>>(
         data_path: P,
         interactions: Vec<Interaction>,
+        label_map: LabelMap,
+    ) -> Result<Self, Error> {
+        Self::with_regimes(data_path, interactions, label_map, &HashMap::new())
+    }
+
+    /// Create a dataset of all numeric traffic traces from a list of interactions, with the capture-environment regime
+    /// of their session.
+    ///
+    /// The dataset is split so that every partition contains the regimes in the same proportions, see [`Self::split`].
+    ///
+    /// # Arguments
+    ///
+    /// * `data_path`: The path to the data directory.
+    /// * `interactions`: The interactions to create the dataset from.
+    /// * `label_map`: The labels of the dataset, interactions whose query has no label are dropped.
+    /// * `regimes`: The regime of every session by its id, sessions without one are in regime `0`.
+    pub fn with_regimes<P: AsRef<Path>>(
+        data_path: P,
+        interactions: Vec<Interaction>,
+        label_map: LabelMap,
+        regimes: &HashMap<i32, i32>,
     ) -> Result<Self, Error> {
         info!(
             "Creating dataset from {} interactions...",
@@ -110,21 +187,31 @@ impl NumericTraceDataset {
         let interactions = Self::filter_interactions(interactions);
         let mut dataset = Self {
             items: Vec::new(),
-            queries: Self::collect_queries(&interactions)?,
+            label_map,
         };
 
         dataset.items = interactions
             .into_iter()
             .map(|interaction| {
                 (
-                    Self::load_interaction_trace(&data_path, &interaction),
+                    Self::load_interaction_timed_trace(&data_path, &interaction),
                     dataset.get_label(&interaction.query),
+                    regimes
+                        .get(&interaction.session_id)
+                        .copied()
+                        .unwrap_or_default(),
                 )
             })
             // only keep items where the trace could be loaded and the label was found
-            .filter_map(|(trace, label)| trace.ok().zip(label))
-            .map(|(trace, label)| NumericTraceItem { trace, label })
+            .filter_map(|(trace, label, regime)| Some((trace.ok()?, label?, regime)))
+            .map(|((trace, times), label, regime)| NumericTraceItem {
+                trace,
+                label,
+                regime,
+                times,
+            })
             .collect();
+        dataset.deduplicate();
 
         Ok(dataset)
     }
@@ -178,6 +265,9 @@ impl NumericTraceDataset {
 
     /// Split a [`NumericTraceDataset`] into training, validation, and testing datasets.
     ///
+    /// The items of every capture-environment regime are split separately, keeping their order, so every partition
+    /// contains the regimes in the same proportions. The rounding remainder of every regime goes to testing.
+    ///
     /// # Arguments
     ///
     /// * `training_proportion`: The proportion of the dataset to use for training.
@@ -189,58 +279,75 @@ impl NumericTraceDataset {
         validation_proportion: f64,
         testing_proportion: f64,
     ) -> Result<(Self, Self, Self), Error> {
-        if !(0.0..1.0).contains(&training_proportion)
-            || !(0.0..1.0).contains(&validation_proportion)
-            || !(0.0..1.0).contains(&testing_proportion)
-        {
-            return Err(Error::ProportionError);
+        split_counts(
+            self.len(),
+            training_proportion,
+            validation_proportion,
+            testing_proportion,
+        )?;
+
+        let mut regimes: BTreeMap<i32, Vec<NumericTraceItem>> = BTreeMap::new();
+        for item in self.items {
+            regimes.entry(item.regime).or_default().push(item);
         }
-        if (training_proportion + validation_proportion + testing_proportion - 1.).abs() > 0.001 {
-            return Err(Error::ProportionSumError);
+        if regimes.len() > 1 {
+            info!("Stratifying the split over {} regimes", regimes.len());
         }
 
-        let length = self.len() as f64;
-        let training_count = (training_proportion * length) as usize;
-        let validation_count = (validation_proportion * length) as usize;
-        let testing_count = (testing_proportion * length) as usize;
-
-        if training_count < 1 || validation_count < 1 || testing_count < 1 {
-            return Err(Error::DatasetTooSmall);
+        let mut training_items = Vec::new();
+        let mut validation_items = Vec::new();
+        let mut testing_items = Vec::new();
+        for mut items in regimes.into_values() {
+            let length = items.len() as f64;
+            let training_count = (training_proportion * length) as usize;
+            let validation_count = (validation_proportion * length) as usize;
+
+            let mut rest = items.split_off(training_count.min(items.len()));
+            training_items.append(&mut items);
+            testing_items.append(&mut rest.split_off(validation_count.min(rest.len())));
+            validation_items.append(&mut rest);
         }
-
-        info!(
-            "Splitting dataset into training: {:.0}% ({training_count}), validation: {:.0}% ({validation_count}), testing: {:.0}% ({testing_count})",
-            (training_proportion * 100.).round(),
-            (validation_proportion * 100.).round(),
-            (testing_proportion * 100.).round()
-        );
-
-        let mut training_items = self.items;
-        if training_count > training_items.len() {
-            return Err(Error::DatasetTooSmall);
-        }
-        let mut validation_items = training_items.split_off(training_count);
-        if validation_count > validation_items.len() {
+        if training_items.is_empty() || validation_items.is_empty() || testing_items.is_empty() {
             return Err(Error::DatasetTooSmall);
         }
-        let testing_items = validation_items.split_off(validation_count);
 
         Ok((
             Self {
                 items: training_items,
-                queries: self.queries.clone(),
+                label_map: self.label_map.clone(),
             },
             Self {
                 items: validation_items,
-                queries: self.queries.clone(),
+                label_map: self.label_map.clone(),
             },
             Self {
                 items: testing_items,
-                queries: self.queries,
+                label_map: self.label_map,
             },
         ))
     }
 
+    /// Remove near-identical items with the same label, keeping only the first one.
+    ///
+    /// Duplicate samples, e.g. from retried interactions, would otherwise end up in both the training and testing
+    /// partitions. Items are compared with [`NumericTrafficTrace::is_near_duplicate`].
+    ///
+    /// Returns the number of removed items.
+    pub fn deduplicate(&mut self) -> usize {
+        let len = self.items.len();
+        let mut seen = DuplicateFilter::default();
+
+        self.items
+            .retain(|item| seen.insert(item.label, item.trace.clone()));
+
+        let removed = len - self.items.len();
+        if removed > 0 {
+            info!("Removed {removed} duplicate traces from the dataset");
+        }
+
+        removed
+    }
+
     /// Shuffle the items in this dataset.
     pub fn shuffle(&mut self) -> &mut Self {
         self.items.shuffle(&mut rand::thread_rng());
@@ -284,7 +391,7 @@ impl NumericTraceDataset {
         self
     }
 
-    /// Find the query corresponding to a label. The label corresponds to the index of the query in the list of queries.
+    /// Find the query corresponding to a label, as defined by the [`LabelMap`] of the dataset.
     ///
     /// # Arguments
     ///
@@ -292,10 +399,10 @@ impl NumericTraceDataset {
     ///
     /// returns: The query corresponding to the label or `None` if the label could not be found.
     pub fn get_query(&self, label: u8) -> Option<String> {
-        self.queries.get(label as usize).cloned()
+        self.label_map.query(label).map(str::to_string)
     }
 
-    /// Find the label of a query. This will search the list of queries.
+    /// Find the label of a query, as defined by the [`LabelMap`] of the dataset.
     ///
     /// # Arguments
     ///
@@ -303,15 +410,12 @@ impl NumericTraceDataset {
     ///
     /// returns: The label of the query or `None` if the query could not be found.
     pub fn get_label(&self, query: &str) -> Option<u8> {
-        self.queries
-            .iter()
-            .position(|label| label == query)
-            .map(|label| label as u8)
+        self.label_map.label(query)
     }
 
     /// Get the number of labels in the dataset.
     pub fn num_labels(&self) -> usize {
-        self.queries.len()
+        self.label_map.len()
     }
 
     /// Load a [`TrafficTrace`] from a pcap file given an interaction.
@@ -326,6 +430,21 @@ impl NumericTraceDataset {
         data_path: P,
         interaction: &Interaction,
     ) -> Result<NumericTrafficTrace, Error> {
+        Self::load_interaction_timed_trace(data_path, interaction).map(|(trace, _)| trace)
+    }
+
+    /// Load a [`TrafficTrace`] from a pcap file given an interaction, with the time of every packet.
+    ///
+    /// # Arguments
+    ///
+    /// * `data_path`: The path to the data directory.
+    /// * `interaction`: The interaction to load the traffic trace from.
+    ///
+    /// returns: The parsed [`TrafficTrace`] and its packet times, see [`TrafficTrace::as_packet_times`].
+    pub fn load_interaction_timed_trace<P: AsRef<Path>>(
+        data_path: P,
+        interaction: &Interaction,
+    ) -> Result<(NumericTrafficTrace, Vec<f32>), Error> {
         let address =
             MacAddress::from_str(&interaction.assistant_mac).map_err(|_| Error::CannotLoadTrace)?;
 
@@ -335,7 +454,7 @@ impl NumericTraceDataset {
             .map(|path| file::session_path(data_path, interaction.session_id).join(path))
             .ok_or(Error::CannotLoadTrace)?;
 
-        Self::load_trace(capture_path, &address)
+        Self::load_timed_trace(capture_path, &address)
     }
 
     /// Load a [`TrafficTrace`] from a pcap file directly.
@@ -350,11 +469,31 @@ impl NumericTraceDataset {
         capture_path: P,
         address: &MacAddress,
     ) -> Result<NumericTrafficTrace, Error> {
+        Self::load_timed_trace(capture_path, address).map(|(trace, _)| trace)
+    }
+
+    /// Load a [`TrafficTrace`] from a pcap file directly, with the time of every packet.
+    ///
+    /// # Arguments
+    ///
+    /// * `capture_path`: The path to the pcap file.
+    /// * `address`: The address of the assistant.
+    ///
+    /// returns: The parsed [`TrafficTrace`] and its packet times, see [`TrafficTrace::as_packet_times`].
+    pub fn load_timed_trace<P: AsRef<Path>>(
+        capture_path: P,
+        address: &MacAddress,
+    ) -> Result<(NumericTrafficTrace, Vec<f32>), Error> {
         packet::load_packets(capture_path)
             .ok()
             .map(TrafficTrace::try_from)
             .transpose()?
-            .map(|trace| trace.as_numeric_trace(address))
+            .map(|trace| {
+                (
+                    trace.as_numeric_trace(address),
+                    trace.as_packet_times(address),
+                )
+            })
             .ok_or(Error::CannotLoadTrace)
     }
 
@@ -369,32 +508,85 @@ impl NumericTraceDataset {
             .filter(|interaction| interaction.is_complete())
             .collect()
     }
+}
 
-    /// Turns a list of interactions into a list of unique queries. The indices of the returned list will be used as
-    /// labels.
-    ///
-    /// Returns an error if there are more than [`Self::MAX_LABELS`] unique queries.
-    ///
-    /// # Arguments
-    ///
-    /// * `interactions`: The list of interactions to search for queries.
-    fn collect_queries(interactions: &Vec<Interaction>) -> Result<Vec<String>, Error> {
-        let mut labels = Vec::with_capacity(Self::MAX_LABELS);
+/// Filter out all interactions whose query is not in one of the given categories, before loading them into a
+/// [`NumericTraceDataset`] or [`ShardedTraceDataset`](crate::ml::stream::ShardedTraceDataset).
+///
+/// All interactions are kept if no categories are given.
+///
+/// # Arguments
+///
+/// * `interactions`: The interactions to filter.
+/// * `categories`: The query categories to keep.
+pub fn filter_categories<S: AsRef<str>>(
+    interactions: Vec<Interaction>,
+    categories: &[S],
+) -> Vec<Interaction> {
+    if categories.is_empty() {
+        return interactions;
+    }
 
-        for interaction in interactions {
-            if !labels.contains(&interaction.query) {
-                labels.push(interaction.query.clone());
-            }
+    let total = interactions.len();
+    let interactions: Vec<Interaction> = interactions
+        .into_iter()
+        .filter(|interaction| {
+            categories
+                .iter()
+                .any(|category| category.as_ref() == interaction.query_category)
+        })
+        .collect();
+
+    info!(
+        "Kept {} of {total} interactions in {} categories",
+        interactions.len(),
+        categories.len()
+    );
+
+    interactions
+}
 
-            if labels.len() > Self::MAX_LABELS {
-                return Err(Error::TooManyLabels(Self::MAX_LABELS));
-            }
-        }
+/// Compute the number of items in the training, validation, and testing partitions of a dataset.
+///
+/// # Arguments
+///
+/// * `len`: The number of items in the dataset.
+/// * `training_proportion`: The proportion of the dataset to use for training.
+/// * `validation_proportion`: The proportion of the dataset to use for validation.
+/// * `testing_proportion`: The proportion of the dataset to use for testing.
+pub(crate) fn split_counts(
+    len: usize,
+    training_proportion: f64,
+    validation_proportion: f64,
+    testing_proportion: f64,
+) -> Result<(usize, usize, usize), Error> {
+    if !(0.0..1.0).contains(&training_proportion)
+        || !(0.0..1.0).contains(&validation_proportion)
+        || !(0.0..1.0).contains(&testing_proportion)
+    {
+        return Err(Error::ProportionError);
+    }
+    if (training_proportion + validation_proportion + testing_proportion - 1.).abs() > 0.001 {
+        return Err(Error::ProportionSumError);
+    }
 
-        debug!("Found {} unique queries", labels.len());
+    let length = len as f64;
+    let training_count = (training_proportion * length) as usize;
+    let validation_count = (validation_proportion * length) as usize;
+    let testing_count = (testing_proportion * length) as usize;
 
-        Ok(labels)
+    if training_count < 1 || validation_count < 1 || testing_count < 1 {
+        return Err(Error::DatasetTooSmall);
     }
+
+    info!(
+        "Splitting dataset into training: {:.0}% ({training_count}), validation: {:.0}% ({validation_count}), testing: {:.0}% ({testing_count})",
+        (training_proportion * 100.).round(),
+        (validation_proportion * 100.).round(),
+        (testing_proportion * 100.).round()
+    );
+
+    Ok((training_count, validation_count, testing_count))
 }
 
 impl Dataset<NumericTraceItem> for NumericTraceDataset {
@@ -421,7 +613,8 @@ impl<B: Backend> Batcher<NumericTraceItem, NumericBatch<B>> for TrafficTraceBatc
     fn batch(&self, items: Vec<NumericTraceItem>) -> NumericBatch<B> {
         let traces = items
             .iter()
-            .map(|item| Data::<f32, 1>::from(item.trace.0.as_slice()))
+            .map(|item| self.represent(item))
+            .map(|trace| Data::<f32, 1>::from(trace.0.as_slice()))
             // in this step we convert all data to the backend type
             .map(|data| Tensor::<B, 1>::from_data(data.convert(), &self.device))
             .map(|tensor| {
@@ -0,0 +1,168 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use log::debug;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::error::Error;
+use crate::ml;
+
+/// The mapping between the queries of a dataset preset and the labels a model outputs.
+///
+/// The label of a query is its index in the sorted list of unique queries, so it does not depend on the order in
+/// which interactions were loaded or filtered. The same map is used to create the dataset a model is trained on and to
+/// name the outputs of the model during inference.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct LabelMap {
+    /// The name of the dataset preset, e.g. `small`.
+    pub preset: String,
+    /// The label of a query is the index of the query in this vector.
+    pub labels: Vec<String>,
+    /// The SHA-256 hash of the labels, to check that a dataset or model uses the same labels.
+    pub hash: String,
+}
+
+impl LabelMap {
+    /// The largest number of labels a model can output.
+    pub const MAX_LABELS: usize = u8::MAX as usize;
+
+    /// Create the canonical label map of a dataset preset from its queries.
+    ///
+    /// Returns [`Error::TooManyLabels`] if there are more than [`Self::MAX_LABELS`] unique queries.
+    ///
+    /// # Arguments
+    ///
+    /// * `preset`: The name of the dataset preset.
+    /// * `queries`: The queries of the dataset, duplicates and order are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys_analysis::ml::label_map::LabelMap;
+    /// let label_map = LabelMap::new(
+    ///     "binary",
+    ///     ["Call Mary Poppins", "Call John Doe", "Call Mary Poppins"].map(String::from),
+    /// )
+    /// .unwrap();
+    /// let reordered = LabelMap::new("binary", ["Call John Doe", "Call Mary Poppins"].map(String::from))
+    ///     .unwrap();
+    ///
+    /// assert_eq!(label_map.labels, vec!["Call John Doe", "Call Mary Poppins"]);
+    /// assert_eq!(label_map.label("Call Mary Poppins"), Some(1));
+    /// assert_eq!(label_map.query(0), Some("Call John Doe"));
+    /// assert_eq!(label_map.hash, reordered.hash);
+    /// ```
+    pub fn new<I: IntoIterator<Item = String>>(preset: &str, queries: I) -> Result<Self, Error> {
+        let mut labels: Vec<String> = queries.into_iter().collect();
+        labels.sort();
+        labels.dedup();
+
+        if labels.len() > Self::MAX_LABELS {
+            return Err(Error::TooManyLabels(Self::MAX_LABELS));
+        }
+
+        debug!("Found {} unique queries for {preset}", labels.len());
+
+        Ok(Self {
+            preset: preset.to_string(),
+            hash: Self::hash_labels(&labels),
+            labels,
+        })
+    }
+
+    /// Load the label map of a dataset preset and check that its labels match its hash.
+    ///
+    /// # Arguments
+    ///
+    /// * `data_path`: The path to the data directory.
+    /// * `preset`: The name of the dataset preset.
+    pub fn load<P: AsRef<Path>>(data_path: P, preset: &str) -> Result<Self, Error> {
+        let path = label_map_path(&data_path, preset);
+
+        debug!("Loading label map from {}", path.display());
+
+        let label_map: Self = serde_json::from_reader(BufReader::new(File::open(path)?))?;
+        if label_map.hash != Self::hash_labels(&label_map.labels) {
+            return Err(Error::LabelMapMismatch(preset.to_string()));
+        }
+
+        Ok(label_map)
+    }
+
+    /// Save the label map to the JSON file of its preset.
+    ///
+    /// Returns the path of the file.
+    ///
+    /// # Arguments
+    ///
+    /// * `data_path`: The path to the data directory.
+    pub fn save<P: AsRef<Path>>(&self, data_path: P) -> Result<PathBuf, Error> {
+        let path = label_map_path(&data_path, &self.preset);
+
+        debug!("Saving label map to {}", path.display());
+
+        OpenOptions::new()
+            .write(true)
+            .truncate(true)
+            .create(true)
+            .open(&path)?
+            .write_all(serde_json::to_string_pretty(self)?.as_bytes())?;
+
+        Ok(path)
+    }
+
+    /// Find the label of a query.
+    ///
+    /// # Arguments
+    ///
+    /// * `query`: The query to find the label of.
+    pub fn label(&self, query: &str) -> Option<u8> {
+        self.labels
+            .iter()
+            .position(|label| label == query)
+            .map(|label| label as u8)
+    }
+
+    /// Find the query of a label.
+    ///
+    /// # Arguments
+    ///
+    /// * `label`: The label to find the query of.
+    pub fn query(&self, label: u8) -> Option<&str> {
+        self.labels.get(label as usize).map(String::as_str)
+    }
+
+    /// Get the number of labels.
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    /// Whether there are no labels.
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    fn hash_labels(labels: &[String]) -> String {
+        let mut hasher = Sha256::new();
+
+        for label in labels {
+            hasher.update(label.as_bytes());
+            hasher.update(b"\n");
+        }
+
+        format!("{:x}", hasher.finalize())
+    }
+}
+
+/// Get the path of the label map of a dataset preset.
+///
+/// # Arguments
+///
+/// * `data_path`: The path to the data directory.
+/// * `preset`: The name of the dataset preset.
+pub fn label_map_path<P: AsRef<Path>>(data_path: P, preset: &str) -> PathBuf {
+    PathBuf::from(ml::ml_path(data_path.as_ref().to_string_lossy().as_ref()))
+        .join(format!("labels-{preset}.json"))
+}
@@ -0,0 +1,252 @@
+use std::marker::PhantomData;
+
+use burn::tensor::backend::Backend;
+use burn::tensor::{Int, Tensor};
+use burn::train::metric::state::{FormatOptions, NumericMetricState};
+use burn::train::metric::{Adaptor, MetricEntry, MetricMetadata, Numeric};
+use burn::train::ClassificationOutput;
+
+/// The outputs of the model for a batch of traces, with the labels they should have been classified as.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MetricBatch {
+    /// The score of every label for every trace in the batch.
+    pub outputs: Vec<Vec<f32>>,
+    /// The correct label of every trace in the batch.
+    pub targets: Vec<u8>,
+}
+
+impl MetricBatch {
+    /// The labels with the highest scores for every trace in the batch, best first.
+    ///
+    /// # Arguments
+    ///
+    /// * `k`: How many labels to return per trace.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys_analysis::ml::metric::MetricBatch;
+    /// let batch = MetricBatch {
+    ///     outputs: vec![vec![0.1, 0.7, 0.2], vec![0.5, 0.2, 0.3]],
+    ///     targets: vec![2, 0],
+    /// };
+    ///
+    /// assert_eq!(batch.top_k(2), vec![vec![1, 2], vec![0, 2]]);
+    /// ```
+    pub fn top_k(&self, k: usize) -> Vec<Vec<u8>> {
+        self.outputs
+            .iter()
+            .map(|scores| {
+                let mut labels: Vec<u8> = (0..scores.len()).map(|label| label as u8).collect();
+                labels.sort_by(|a, b| scores[*b as usize].total_cmp(&scores[*a as usize]));
+                labels.truncate(k);
+
+                labels
+            })
+            .collect()
+    }
+
+    /// The number of traces in the batch.
+    pub fn len(&self) -> usize {
+        self.targets.len()
+    }
+
+    /// Whether the batch has no traces.
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+}
+
+/// A custom metric that is computed for every batch during training and validation.
+///
+/// Metrics are added to training with [`MetricPlugins`], their values are logged next to the accuracy and loss.
+pub trait Metric: Send + Sync {
+    /// The name of the metric, which is also the name of its log file.
+    fn name(&self) -> String;
+
+    /// The unit of the metric, if any.
+    fn unit(&self) -> Option<String> {
+        None
+    }
+
+    /// Compute the value of the metric for a batch.
+    ///
+    /// # Arguments
+    ///
+    /// * `batch`: The outputs of the model for the batch.
+    fn compute(&mut self, batch: &MetricBatch) -> f64;
+
+    /// Reset any state kept across batches, at the end of every epoch.
+    fn clear(&mut self) {}
+}
+
+/// The share of traces whose correct label is among the `k` labels with the highest scores, in percent.
+pub struct TopKAccuracy {
+    k: usize,
+}
+
+impl TopKAccuracy {
+    /// Create the metric.
+    ///
+    /// # Arguments
+    ///
+    /// * `k`: How many of the best labels may contain the correct one.
+    pub fn new(k: usize) -> Self {
+        TopKAccuracy { k }
+    }
+}
+
+impl Metric for TopKAccuracy {
+    fn name(&self) -> String {
+        format!("Top-{} Accuracy", self.k)
+    }
+
+    fn unit(&self) -> Option<String> {
+        Some("%".to_string())
+    }
+
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys_analysis::ml::metric::{Metric, MetricBatch, TopKAccuracy};
+    /// let batch = MetricBatch {
+    ///     outputs: vec![vec![0.1, 0.7, 0.2], vec![0.5, 0.2, 0.3]],
+    ///     targets: vec![2, 1],
+    /// };
+    ///
+    /// assert_eq!(TopKAccuracy::new(1).compute(&batch), 0.0);
+    /// assert_eq!(TopKAccuracy::new(2).compute(&batch), 50.0);
+    /// ```
+    fn compute(&mut self, batch: &MetricBatch) -> f64 {
+        if batch.is_empty() {
+            return 0.0;
+        }
+
+        let correct = batch
+            .top_k(self.k)
+            .iter()
+            .zip(&batch.targets)
+            .filter(|(labels, target)| labels.contains(target))
+            .count();
+
+        100.0 * correct as f64 / batch.len() as f64
+    }
+}
+
+/// The custom metrics to compute during training.
+///
+/// Each metric is created twice, once for training and once for validation.
+///
+/// # Examples
+///
+/// ```
+/// # use varys_analysis::ml::metric::{MetricPlugins, TopKAccuracy};
+/// let plugins = MetricPlugins::new().with(|| TopKAccuracy::new(3));
+/// ```
+#[derive(Default)]
+pub struct MetricPlugins {
+    factories: Vec<Box<dyn Fn() -> Box<dyn Metric>>>,
+}
+
+impl MetricPlugins {
+    /// Create an empty set of metrics.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a metric.
+    ///
+    /// # Arguments
+    ///
+    /// * `factory`: Creates a new instance of the metric.
+    pub fn with<M: Metric + 'static, F: Fn() -> M + 'static>(mut self, factory: F) -> Self {
+        self.factories
+            .push(Box::new(move || Box::new(factory()) as Box<dyn Metric>));
+
+        self
+    }
+
+    /// Create a new instance of every metric.
+    pub(crate) fn create<B: Backend>(&self) -> Vec<PluginMetric<B>> {
+        self.factories
+            .iter()
+            .map(|factory| PluginMetric::new(factory()))
+            .collect()
+    }
+}
+
+/// The input of a [`PluginMetric`], adapted from the output of the model.
+pub struct PluginInput<B: Backend> {
+    outputs: Tensor<B, 2>,
+    targets: Tensor<B, 1, Int>,
+}
+
+impl<B: Backend> Adaptor<PluginInput<B>> for ClassificationOutput<B> {
+    fn adapt(&self) -> PluginInput<B> {
+        PluginInput {
+            outputs: self.output.clone(),
+            targets: self.targets.clone(),
+        }
+    }
+}
+
+/// Runs a custom [`Metric`] as a metric of the burn learner.
+pub(crate) struct PluginMetric<B: Backend> {
+    metric: Box<dyn Metric>,
+    state: NumericMetricState,
+    _backend: PhantomData<B>,
+}
+
+impl<B: Backend> PluginMetric<B> {
+    fn new(metric: Box<dyn Metric>) -> Self {
+        PluginMetric {
+            metric,
+            state: NumericMetricState::new(),
+            _backend: PhantomData,
+        }
+    }
+}
+
+impl<B: Backend> burn::train::metric::Metric for PluginMetric<B> {
+    const NAME: &'static str = "Plugin";
+
+    type Input = PluginInput<B>;
+
+    fn update(&mut self, input: &PluginInput<B>, _metadata: &MetricMetadata) -> MetricEntry {
+        let [batch_size, num_labels] = input.outputs.dims();
+        let outputs = input.outputs.to_data().convert::<f32>().value;
+        let batch = MetricBatch {
+            outputs: outputs
+                .chunks(num_labels.max(1))
+                .map(<[f32]>::to_vec)
+                .collect(),
+            targets: input
+                .targets
+                .to_data()
+                .convert::<i64>()
+                .value
+                .into_iter()
+                .map(|target| target as u8)
+                .collect(),
+        };
+
+        let value = self.metric.compute(&batch);
+        let mut format = FormatOptions::new(&self.metric.name()).precision(2);
+        if let Some(unit) = self.metric.unit() {
+            format = format.unit(&unit);
+        }
+
+        self.state.update(value, batch_size, format)
+    }
+
+    fn clear(&mut self) {
+        self.metric.clear();
+        self.state.reset();
+    }
+}
+
+impl<B: Backend> Numeric for PluginMetric<B> {
+    fn value(&self) -> f64 {
+        self.state.value()
+    }
+}
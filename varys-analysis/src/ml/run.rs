@@ -0,0 +1,269 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::Utc;
+use log::{debug, info};
+
+use crate::error::Error;
+use crate::ml;
+
+/// The directory of one training run, in `ml/runs` of the data directory and named after when the run started.
+///
+/// A run directory contains:
+///
+/// * `config.json`: The training configuration.
+/// * `model.mpk`: The trained model.
+/// * `experiment.log`: The log of the training.
+/// * `checkpoint/`: The model and optimiser checkpoints of the last and best epochs, as written by burn.
+/// * `metrics/train/` and `metrics/valid/`: The metrics logged during every epoch.
+/// * `figures/`: Plots of the run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunDir {
+    pub path: PathBuf,
+}
+
+impl RunDir {
+    /// Create the directory of a new run.
+    ///
+    /// # Arguments
+    ///
+    /// * `data_dir`: The directory in which data files are stored.
+    pub fn create<P: AsRef<Path>>(data_dir: P) -> Result<Self, Error> {
+        Self::create_in(runs_path(data_dir))
+    }
+
+    /// Create the directory of a new run in a directory other than the one of the runs used for inference.
+    ///
+    /// # Arguments
+    ///
+    /// * `parent`: The directory to create the run in.
+    pub fn create_in<P: AsRef<Path>>(parent: P) -> Result<Self, Error> {
+        let name = Utc::now().format("%Y%m%d-%H%M%S").to_string();
+        let mut path = parent.as_ref().join(&name);
+        // runs started within the same second get a suffix
+        let mut suffix = 1;
+        while path.exists() {
+            suffix += 1;
+            path = parent.as_ref().join(format!("{name}-{suffix}"));
+        }
+
+        let run = RunDir { path };
+        fs::create_dir_all(run.checkpoint_path())?;
+        fs::create_dir_all(run.metrics_path("train"))?;
+        fs::create_dir_all(run.metrics_path("valid"))?;
+        fs::create_dir_all(run.figures_path())?;
+
+        debug!("Created run directory {}", run.path.display());
+
+        Ok(run)
+    }
+
+    /// Get all runs, from the newest to the oldest.
+    ///
+    /// # Arguments
+    ///
+    /// * `data_dir`: The directory in which data files are stored.
+    pub fn all<P: AsRef<Path>>(data_dir: P) -> Result<Vec<Self>, Error> {
+        let runs_path = runs_path(data_dir);
+        if !runs_path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut runs: Vec<Self> = fs::read_dir(runs_path)?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_dir())
+            .map(|entry| RunDir { path: entry.path() })
+            .collect();
+        runs.sort_by_key(|run| run.name());
+        runs.reverse();
+
+        Ok(runs)
+    }
+
+    /// Get the newest run.
+    ///
+    /// Models trained before runs had their own directories are stored directly in `ml` of the data directory, which
+    /// is returned if there are no runs.
+    ///
+    /// # Arguments
+    ///
+    /// * `data_dir`: The directory in which data files are stored.
+    pub fn latest<P: AsRef<Path>>(data_dir: P) -> Result<Self, Error> {
+        Ok(Self::all(&data_dir)?
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| RunDir {
+                path: PathBuf::from(ml::ml_path(data_dir.as_ref().to_string_lossy().as_ref())),
+            }))
+    }
+
+    /// Get the run with the given name, see [`Self::name`].
+    ///
+    /// # Arguments
+    ///
+    /// * `data_dir`: The directory in which data files are stored.
+    /// * `name`: The name of the run.
+    pub fn named<P: AsRef<Path>>(data_dir: P, name: &str) -> Result<Self, Error> {
+        Self::all(data_dir)?
+            .into_iter()
+            .find(|run| run.name() == name)
+            .ok_or_else(|| Error::UnknownRun(name.to_string()))
+    }
+
+    /// The name of the run, which sorts the runs by when they started.
+    pub fn name(&self) -> String {
+        self.path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string()
+    }
+
+    pub fn config_path(&self) -> PathBuf {
+        self.path.join("config.json")
+    }
+
+    /// The path of the trained model, without the extension added by the recorder.
+    pub fn model_path(&self) -> PathBuf {
+        self.path.join("model")
+    }
+
+    /// The directory burn writes its checkpoints to, which is always called `checkpoint`.
+    pub fn checkpoint_path(&self) -> PathBuf {
+        self.path.join("checkpoint")
+    }
+
+    /// The directory of the metrics of the training or validation split.
+    ///
+    /// # Arguments
+    ///
+    /// * `split`: Either `train` or `valid`.
+    pub fn metrics_path(&self, split: &str) -> PathBuf {
+        let path = self.path.join("metrics").join(split);
+
+        // runs from before run directories stored their metrics directly in the run
+        if !path.exists() && self.path.join(split).exists() {
+            return self.path.join(split);
+        }
+
+        path
+    }
+
+    pub fn figures_path(&self) -> PathBuf {
+        self.path.join("figures")
+    }
+
+    /// Get the epoch with the lowest mean validation loss, if any validation loss was logged.
+    pub fn best_epoch(&self) -> Option<usize> {
+        fs::read_dir(self.metrics_path("valid"))
+            .ok()?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let epoch = entry
+                    .file_name()
+                    .to_string_lossy()
+                    .strip_prefix("epoch-")?
+                    .parse::<usize>()
+                    .ok()?;
+                let loss = fs::read_to_string(entry.path().join("Loss.log")).ok()?;
+                let values: Vec<f64> = loss
+                    .lines()
+                    .filter_map(|line| line.parse::<f64>().ok())
+                    .collect();
+                if values.is_empty() {
+                    return None;
+                }
+
+                Some((epoch, values.iter().sum::<f64>() / values.len() as f64))
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(epoch, _)| epoch)
+    }
+
+    /// Remove all checkpoints of the run except the model of its best epoch.
+    ///
+    /// If no validation loss was logged, the model of the last epoch is kept instead. The trained model, the
+    /// configuration, metrics and figures are never removed.
+    ///
+    /// Returns the number of removed files and how many bytes they took up.
+    pub fn prune_checkpoints(&self) -> Result<(usize, u64), Error> {
+        let checkpoint_path = self.checkpoint_path();
+        if !checkpoint_path.exists() {
+            return Ok((0, 0));
+        }
+
+        let checkpoints: Vec<(PathBuf, String, Option<usize>)> = fs::read_dir(&checkpoint_path)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| {
+                let path = entry.path();
+                let stem = path
+                    .file_stem()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .to_string();
+                let (name, epoch) = match stem.rsplit_once('-') {
+                    Some((name, epoch)) => (name.to_string(), epoch.parse::<usize>().ok()),
+                    None => (stem, None),
+                };
+
+                (path, name, epoch)
+            })
+            .collect();
+        let keep = self.best_epoch().or_else(|| {
+            checkpoints
+                .iter()
+                .filter(|(_, name, _)| name == "model")
+                .filter_map(|(_, _, epoch)| *epoch)
+                .max()
+        });
+
+        let mut removed = 0;
+        let mut freed = 0;
+        for (path, name, epoch) in checkpoints {
+            if name == "model" && epoch.is_some() && epoch == keep {
+                continue;
+            }
+
+            freed += fs::metadata(&path)
+                .map(|metadata| metadata.len())
+                .unwrap_or(0);
+            fs::remove_file(&path)?;
+            removed += 1;
+        }
+
+        if removed > 0 {
+            info!(
+                "Removed {removed} checkpoints of run {}, kept the model of epoch {}",
+                self.name(),
+                keep.map_or("none".to_string(), |epoch| epoch.to_string())
+            );
+        }
+
+        Ok((removed, freed))
+    }
+}
+
+/// Remove the checkpoints of all but the newest runs, keeping their best models, trained models and metrics.
+///
+/// Returns the number of removed files and how many bytes they took up.
+///
+/// # Arguments
+///
+/// * `data_dir`: The directory in which data files are stored.
+/// * `keep`: How many of the newest runs to keep all checkpoints of.
+pub fn gc<P: AsRef<Path>>(data_dir: P, keep: usize) -> Result<(usize, u64), Error> {
+    let mut removed = 0;
+    let mut freed = 0;
+
+    for run in RunDir::all(data_dir)?.into_iter().skip(keep) {
+        let (run_removed, run_freed) = run.prune_checkpoints()?;
+        removed += run_removed;
+        freed += run_freed;
+    }
+
+    Ok((removed, freed))
+}
+
+fn runs_path<P: AsRef<Path>>(data_dir: P) -> PathBuf {
+    PathBuf::from(ml::ml_path(data_dir.as_ref().to_string_lossy().as_ref())).join("runs")
+}
@@ -0,0 +1,223 @@
+use std::path::Path;
+
+use burn::backend::wgpu::WgpuDevice;
+use burn::data::dataloader::batcher::Batcher;
+use burn::tensor::activation;
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::error::Error;
+use crate::ml::cnn::inference;
+use crate::ml::cnn::CNNModel;
+use crate::ml::data::{NumericTraceItem, TraceRepresentation, TrafficTraceBatcher};
+use crate::ml::label_map::LabelMap;
+use crate::ml::run::RunDir;
+use crate::ml::Backend;
+use crate::trace::NumericTrafficTrace;
+
+/// The request body of `POST /infer`.
+#[derive(Deserialize, Debug)]
+pub struct InferenceRequest {
+    /// The numeric traffic trace to recognise the query of, see [`NumericTrafficTrace`].
+    pub trace: Vec<f32>,
+    /// The time of every packet of the trace in seconds, used by models trained on burst traces.
+    #[serde(default)]
+    pub times: Vec<f32>,
+    /// How many of the most likely queries to return, all of them if this is `None`.
+    pub top_k: Option<usize>,
+}
+
+/// A query with how likely the model thinks a trace belongs to it.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Prediction {
+    pub query: String,
+    pub label: u8,
+    pub probability: f32,
+}
+
+/// The response body of `GET /health`.
+#[derive(Serialize, Debug)]
+struct Health<'a> {
+    preset: &'a str,
+    label_map_hash: &'a str,
+    labels: usize,
+}
+
+/// A long-running service that loads a trained model once and recognises the queries of traffic traces sent to it
+/// over HTTP, e.g. by a sniffer on a separate monitoring host.
+///
+/// The server answers to two endpoints:
+///
+/// * `GET /health`: The dataset preset and label map hash of the model.
+/// * `POST /infer`: Recognise the query of a trace sent as `{"trace": [...], "top_k": 5}`. Returns the queries
+///   ordered from most to least likely as `[{"query": "...", "label": 0, "probability": 0.9}, ...]`. Models trained
+///   on burst traces also use the packet times sent as `"times": [...]`.
+pub struct InferenceServer {
+    model: CNNModel<Backend>,
+    representation: TraceRepresentation,
+    label_map: LabelMap,
+    device: WgpuDevice,
+}
+
+impl InferenceServer {
+    /// Load the model trained on a dataset preset from the data directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `data_dir`: The directory in which data files are stored.
+    /// * `preset`: The name of the dataset preset the model was trained on.
+    pub fn load<P: AsRef<Path>>(data_dir: P, preset: &str) -> Result<Self, Error> {
+        let device = WgpuDevice::default();
+        let label_map = LabelMap::load(&data_dir, preset)?;
+        let run = RunDir::latest(&data_dir)?;
+        let model = inference::load_run_model::<Backend>(&run, &device)?;
+        let representation = inference::load_run_representation(&run)?;
+
+        info!(
+            "Loaded model for the {preset} dataset with {} labels ({})",
+            label_map.len(),
+            label_map.hash
+        );
+
+        Ok(InferenceServer {
+            model,
+            representation,
+            label_map,
+            device,
+        })
+    }
+
+    /// Recognise the query of a trace.
+    ///
+    /// Returns all queries ordered from most to least likely.
+    ///
+    /// # Arguments
+    ///
+    /// * `trace`: The trace to recognise.
+    /// * `times`: The time of every packet of the trace in seconds, may be empty for models trained on packet traces.
+    pub fn predict(
+        &self,
+        trace: NumericTrafficTrace,
+        times: Vec<f32>,
+    ) -> Result<Vec<Prediction>, Error> {
+        if trace.0.is_empty() {
+            return Err(Error::EmptyTrace);
+        }
+
+        let batch = TrafficTraceBatcher::<Backend>::with_representation(
+            self.device.clone(),
+            self.representation,
+        )
+        .batch(vec![NumericTraceItem {
+            trace,
+            label: 0,
+            regime: 0,
+            times,
+        }]);
+        let output = activation::softmax(self.model.forward(batch.traces), 1)
+            .flatten::<1>(0, 1)
+            .to_data()
+            .value;
+        if output.len() != self.label_map.len() {
+            return Err(Error::LabelMapMismatch(self.label_map.preset.clone()));
+        }
+
+        let mut predictions: Vec<Prediction> = self
+            .label_map
+            .labels
+            .iter()
+            .zip(output)
+            .enumerate()
+            .map(|(label, (query, probability))| Prediction {
+                query: query.clone(),
+                label: label as u8,
+                probability,
+            })
+            .collect();
+        predictions.sort_by(|a, b| b.probability.total_cmp(&a.probability));
+
+        Ok(predictions)
+    }
+
+    /// Listen for requests until the process is stopped.
+    ///
+    /// Requests are handled one at a time, so the model is only used by one request at once.
+    ///
+    /// # Arguments
+    ///
+    /// * `address`: The address to listen on, e.g. `0.0.0.0:8080`.
+    pub fn serve(&self, address: &str) -> Result<(), Error> {
+        let server =
+            Server::http(address).map_err(|error| Error::ServerError(error.to_string()))?;
+
+        info!("Listening for inference requests on {address}");
+
+        for mut request in server.incoming_requests() {
+            debug!("{} {}", request.method(), request.url());
+
+            let (status, body) = match self.handle(&mut request) {
+                Ok(body) => (200, body),
+                Err((status, message)) => {
+                    warn!(
+                        "Could not handle {} {}: {message}",
+                        request.method(),
+                        request.url()
+                    );
+                    (status, serde_json::json!({ "error": message }).to_string())
+                }
+            };
+            let response = Response::from_string(body)
+                .with_status_code(status)
+                .with_header(
+                    Header::from_bytes("Content-Type", "application/json")
+                        .expect("The content type header is valid"),
+                );
+
+            if let Err(error) = request.respond(response) {
+                warn!("Could not send response: {error}");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Handle a request, returning the body of the response or its status code with an error message.
+    fn handle(&self, request: &mut Request) -> Result<String, (u16, String)> {
+        let (method, url) = (request.method().clone(), request.url().to_string());
+
+        match (method, url.as_str()) {
+            (Method::Get, "/health") => serde_json::to_string(&Health {
+                preset: &self.label_map.preset,
+                label_map_hash: &self.label_map.hash,
+                labels: self.label_map.len(),
+            })
+            .map_err(|error| (500, error.to_string())),
+            (Method::Post, "/infer") => {
+                let mut body = String::new();
+                request
+                    .as_reader()
+                    .read_to_string(&mut body)
+                    .map_err(|error| (400, error.to_string()))?;
+                let inference_request: InferenceRequest =
+                    serde_json::from_str(&body).map_err(|error| (400, error.to_string()))?;
+
+                let mut predictions = self
+                    .predict(
+                        NumericTrafficTrace(inference_request.trace),
+                        inference_request.times,
+                    )
+                    .map_err(|error| match error {
+                        Error::EmptyTrace => (400, error.to_string()),
+                        error => (500, error.to_string()),
+                    })?;
+                if let Some(top_k) = inference_request.top_k {
+                    predictions.truncate(top_k);
+                }
+
+                serde_json::to_string(&predictions).map_err(|error| (500, error.to_string()))
+            }
+            (_, url) => Err((404, format!("Unknown endpoint {url}"))),
+        }
+    }
+}
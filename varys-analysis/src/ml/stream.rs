@@ -0,0 +1,402 @@
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use burn::data::dataset::Dataset;
+use log::{debug, info};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+
+use varys_database::database::interaction::Interaction;
+
+use crate::error::Error;
+use crate::ml;
+use crate::ml::data::{self, NumericTraceDataset, NumericTraceItem};
+use crate::ml::label_map::LabelMap;
+use crate::trace::DuplicateFilter;
+
+/// How many shards are kept in memory at once, enough for every data loader worker to keep its current shard.
+const SHARD_CACHE_SIZE: usize = 8;
+
+/// The description of a sharded dataset, stored next to its shards.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct ShardManifest {
+    /// The labels of the items.
+    pub label_map: LabelMap,
+    /// The number of items in each shard, in the order of the shards.
+    pub shard_lens: Vec<usize>,
+    /// The factor that normalises all traces into the range `[-1, 1]`, applied when an item is read.
+    pub scale: f32,
+    /// The length all traces were resized to.
+    pub input_len: usize,
+    /// The seed the items were shuffled with.
+    pub seed: u64,
+}
+
+/// A dataset of numeric traffic traces that is stored in shards on disk and read lazily.
+///
+/// Unlike [`NumericTraceDataset`], only a few shards are held in memory at a time, so models can be trained on more
+/// traces than fit into memory. Items are shuffled once when the shards are written; reading them in a random order
+/// would load a shard for almost every item, so data loaders should not shuffle this dataset.
+///
+/// Splitting the dataset creates views of the same shards, which are cheap to clone.
+#[derive(Clone)]
+pub struct ShardedTraceDataset {
+    shards: Arc<Shards>,
+    /// The index of the first item of this view.
+    start: usize,
+    /// The number of items in this view.
+    len: usize,
+}
+
+impl ShardedTraceDataset {
+    /// The default number of items per shard.
+    pub const DEFAULT_SHARD_SIZE: usize = 1000;
+
+    /// Load a sharded dataset from disk, if it is found with the same labels, trace length and seed, or create it from
+    /// a list of [`Interaction`]s.
+    ///
+    /// # Arguments
+    ///
+    /// * `data_path`: The path to the data directory.
+    /// * `interactions`: The interactions to create the dataset from if no dataset is found on disk.
+    /// * `label_map`: The labels of the dataset.
+    /// * `shard_size`: The number of items per shard of a new dataset.
+    /// * `input_len`: The length to resize all traces to.
+    /// * `seed`: The seed to shuffle the items of a new dataset with.
+    pub fn load_or_new<P: AsRef<Path>>(
+        data_path: P,
+        interactions: Vec<Interaction>,
+        label_map: LabelMap,
+        shard_size: usize,
+        input_len: usize,
+        seed: u64,
+    ) -> Result<Self, Error> {
+        if manifest_path(&data_path).exists() {
+            match Self::load(&data_path) {
+                Ok(dataset)
+                    if dataset.shards.manifest.label_map == label_map
+                        && dataset.shards.manifest.input_len == input_len
+                        && dataset.shards.manifest.seed == seed =>
+                {
+                    return Ok(dataset)
+                }
+                _ => info!("The stored shards are outdated, creating them again"),
+            }
+        }
+
+        Self::new(
+            data_path,
+            interactions,
+            label_map,
+            shard_size,
+            input_len,
+            seed,
+        )
+    }
+
+    /// Create a sharded dataset of all numeric traffic traces from a list of interactions and write it to disk.
+    ///
+    /// The traces are loaded one at a time, shuffled, deduplicated like in [`NumericTraceDataset::deduplicate`] and
+    /// resized, so only one shard is held in memory while writing. Existing shards are replaced.
+    ///
+    /// # Arguments
+    ///
+    /// * `data_path`: The path to the data directory.
+    /// * `interactions`: The interactions to create the dataset from.
+    /// * `label_map`: The labels of the dataset, interactions whose query has no label are dropped.
+    /// * `shard_size`: The number of items per shard.
+    /// * `input_len`: The length to resize all traces to.
+    /// * `seed`: The seed to shuffle the items with, so the same interactions always give the same shards.
+    pub fn new<P: AsRef<Path>>(
+        data_path: P,
+        interactions: Vec<Interaction>,
+        label_map: LabelMap,
+        shard_size: usize,
+        input_len: usize,
+        seed: u64,
+    ) -> Result<Self, Error> {
+        info!(
+            "Creating sharded dataset from {} interactions...",
+            interactions.len()
+        );
+
+        let shards_path = shards_path(&data_path);
+        if shards_path.exists() {
+            fs::remove_dir_all(&shards_path)?;
+        }
+        fs::create_dir_all(&shards_path)?;
+
+        let mut interactions: Vec<Interaction> = interactions
+            .into_iter()
+            .filter(|interaction| interaction.is_complete())
+            .collect();
+        interactions.shuffle(&mut StdRng::seed_from_u64(seed));
+
+        let shard_size = shard_size.max(1);
+        let mut seen = DuplicateFilter::default();
+        let mut shard = Vec::with_capacity(shard_size);
+        let mut shard_lens = Vec::new();
+        let (mut min, mut max) = (f32::MAX, f32::MIN);
+
+        for interaction in interactions {
+            let Some(label) = label_map.label(&interaction.query) else {
+                continue;
+            };
+            let Ok((trace, times)) =
+                NumericTraceDataset::load_interaction_timed_trace(&data_path, &interaction)
+            else {
+                continue;
+            };
+            if !seen.insert(label, trace.clone()) {
+                continue;
+            }
+
+            let mut item = NumericTraceItem {
+                trace,
+                label,
+                regime: 0,
+                times,
+            };
+            item.resize(input_len);
+
+            // the scale only has to cover what is left of the trace after resizing
+            let (trace_min, trace_max) = item.trace.min_max();
+            min = min.min(trace_min);
+            max = max.max(trace_max);
+            shard.push(item);
+
+            if shard.len() == shard_size {
+                write_shard(&shards_path, shard_lens.len(), &shard)?;
+                shard_lens.push(shard.len());
+                shard.clear();
+            }
+        }
+        if !shard.is_empty() {
+            write_shard(&shards_path, shard_lens.len(), &shard)?;
+            shard_lens.push(shard.len());
+        }
+
+        let range = max.abs().max(min.abs());
+        let manifest = ShardManifest {
+            label_map,
+            shard_lens,
+            scale: if range > 0. && range.is_finite() {
+                1. / range
+            } else {
+                1.
+            },
+            input_len,
+            seed,
+        };
+        serde_json::to_writer(
+            BufWriter::new(
+                OpenOptions::new()
+                    .write(true)
+                    .truncate(true)
+                    .create(true)
+                    .open(manifest_path(&data_path))?,
+            ),
+            &manifest,
+        )?;
+
+        let dataset = Self::from_manifest(shards_path, manifest);
+        info!(
+            "Wrote {} traces to {} shards",
+            dataset.len,
+            dataset.shards.manifest.shard_lens.len()
+        );
+
+        Ok(dataset)
+    }
+
+    /// Load a sharded dataset from the data directory, without reading any of its shards.
+    ///
+    /// # Arguments
+    ///
+    /// * `data_path`: The path to the data directory.
+    pub fn load<P: AsRef<Path>>(data_path: P) -> Result<Self, Error> {
+        let manifest_path = manifest_path(&data_path);
+
+        debug!("Loading shard manifest from {}", manifest_path.display());
+
+        let manifest: ShardManifest =
+            serde_json::from_reader(BufReader::new(File::open(manifest_path)?))?;
+
+        Ok(Self::from_manifest(shards_path(&data_path), manifest))
+    }
+
+    /// Split the dataset into training, validation, and testing datasets using the default proportions.
+    pub fn split_default(self) -> Result<(Self, Self, Self), Error> {
+        self.split(
+            NumericTraceDataset::DEFAULT_TRAINING_PROPORTION,
+            NumericTraceDataset::DEFAULT_VALIDATION_PROPORTION,
+            NumericTraceDataset::DEFAULT_TESTING_PROPORTION,
+        )
+    }
+
+    /// Split the dataset into training, validation, and testing datasets.
+    ///
+    /// The datasets share the shards of this dataset.
+    ///
+    /// # Arguments
+    ///
+    /// * `training_proportion`: The proportion of the dataset to use for training.
+    /// * `validation_proportion`: The proportion of the dataset to use for validation.
+    /// * `testing_proportion`: The proportion of the dataset to use for testing.
+    pub fn split(
+        self,
+        training_proportion: f64,
+        validation_proportion: f64,
+        testing_proportion: f64,
+    ) -> Result<(Self, Self, Self), Error> {
+        let (training_count, validation_count, _) = data::split_counts(
+            self.len,
+            training_proportion,
+            validation_proportion,
+            testing_proportion,
+        )?;
+        let view = |start: usize, len: usize| Self {
+            shards: self.shards.clone(),
+            start: self.start + start,
+            len,
+        };
+
+        Ok((
+            view(0, training_count),
+            view(training_count, validation_count),
+            view(
+                training_count + validation_count,
+                self.len - training_count - validation_count,
+            ),
+        ))
+    }
+
+    /// The labels of the dataset.
+    pub fn label_map(&self) -> &LabelMap {
+        &self.shards.manifest.label_map
+    }
+
+    /// Get the number of labels in the dataset.
+    pub fn num_labels(&self) -> usize {
+        self.shards.manifest.label_map.len()
+    }
+
+    fn from_manifest(path: PathBuf, manifest: ShardManifest) -> Self {
+        let len = manifest.shard_lens.iter().sum();
+        let shard_starts = manifest
+            .shard_lens
+            .iter()
+            .scan(0, |start, len| {
+                let shard_start = *start;
+                *start += len;
+                Some(shard_start)
+            })
+            .collect();
+
+        Self {
+            shards: Arc::new(Shards {
+                path,
+                manifest,
+                shard_starts,
+                cache: Mutex::new(VecDeque::with_capacity(SHARD_CACHE_SIZE)),
+            }),
+            start: 0,
+            len,
+        }
+    }
+}
+
+impl Dataset<NumericTraceItem> for ShardedTraceDataset {
+    fn get(&self, index: usize) -> Option<NumericTraceItem> {
+        if index >= self.len {
+            return None;
+        }
+
+        self.shards.get(self.start + index)
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+/// The shards of a [`ShardedTraceDataset`] with the ones that were read last.
+struct Shards {
+    path: PathBuf,
+    manifest: ShardManifest,
+    /// The index of the first item of each shard.
+    shard_starts: Vec<usize>,
+    cache: Mutex<VecDeque<(usize, Arc<Vec<NumericTraceItem>>)>>,
+}
+
+impl Shards {
+    /// Get a normalised item by its index in the whole dataset.
+    ///
+    /// # Panics
+    ///
+    /// If the shard of the item cannot be read, since skipping its items would silently train on a different dataset.
+    fn get(&self, index: usize) -> Option<NumericTraceItem> {
+        let shard = self.shard_starts.partition_point(|start| *start <= index) - 1;
+        let items = self
+            .read(shard)
+            .unwrap_or_else(|error| panic!("Could not read shard {shard}: {error}"));
+        let mut item = items.get(index - self.shard_starts[shard])?.clone();
+        item.trace.scale(self.manifest.scale);
+
+        Some(item)
+    }
+
+    /// Read the items of a shard from the cache or from disk, replacing the shard that was read first.
+    fn read(&self, shard: usize) -> Result<Arc<Vec<NumericTraceItem>>, Error> {
+        if let Some((_, items)) = self
+            .cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .find(|(index, _)| *index == shard)
+        {
+            return Ok(items.clone());
+        }
+
+        let path = shard_path(&self.path, shard);
+        debug!("Reading shard from {}", path.display());
+        let items: Arc<Vec<NumericTraceItem>> =
+            Arc::new(serde_json::from_reader(BufReader::new(File::open(path)?))?);
+
+        let mut cache = self.cache.lock().unwrap_or_else(PoisonError::into_inner);
+        if cache.len() >= SHARD_CACHE_SIZE {
+            cache.pop_front();
+        }
+        cache.push_back((shard, items.clone()));
+
+        Ok(items)
+    }
+}
+
+/// Write the items of a shard to a JSON file.
+fn write_shard(shards_path: &Path, shard: usize, items: &[NumericTraceItem]) -> Result<(), Error> {
+    let path = shard_path(shards_path, shard);
+
+    debug!("Writing {} traces to {}", items.len(), path.display());
+
+    serde_json::to_writer(BufWriter::new(File::create(path)?), items)?;
+
+    Ok(())
+}
+
+fn shards_path<P: AsRef<Path>>(data_dir: P) -> PathBuf {
+    PathBuf::from(ml::ml_path(data_dir.as_ref().to_string_lossy().as_ref())).join("shards")
+}
+
+fn manifest_path<P: AsRef<Path>>(data_dir: P) -> PathBuf {
+    shards_path(data_dir).join("manifest.json")
+}
+
+fn shard_path(shards_path: &Path, shard: usize) -> PathBuf {
+    shards_path.join(format!("shard-{shard:05}.json"))
+}
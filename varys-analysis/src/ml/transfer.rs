@@ -0,0 +1,216 @@
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::path::Path;
+
+use burn::backend::wgpu::WgpuDevice;
+use burn::data::dataloader::batcher::Batcher;
+use log::info;
+use serde::Serialize;
+
+use varys_database::database::interaction::Interaction;
+
+use crate::error::Error;
+use crate::ml;
+use crate::ml::cnn::{inference, training, CNNModel, CNNModelConfig};
+use crate::ml::data::{NumericTraceDataset, TrafficTraceBatcher};
+use crate::ml::label_map::LabelMap;
+use crate::ml::metric::MetricPlugins;
+use crate::ml::run::RunDir;
+use crate::ml::{AutodiffBackend, Backend};
+
+/// How many traces are classified at once during the evaluation.
+const EVALUATION_BATCH_SIZE: usize = 64;
+
+/// How well a model trained on the traces of one voice assistant recognises the same queries asked to another one.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TransferReport {
+    /// The assistant whose traces the model was trained on.
+    pub source: String,
+    /// The assistant whose traces the model was tested on.
+    pub target: String,
+    /// The number of queries asked to both assistants, which are the labels of the model.
+    pub queries: usize,
+    /// The accuracy on the test split of the traces of the source assistant.
+    pub source_accuracy: f32,
+    /// The number of traces in the test split of the source assistant.
+    pub source_samples: usize,
+    /// The accuracy on all traces of the target assistant.
+    pub target_accuracy: f32,
+    /// The number of traces of the target assistant.
+    pub target_samples: usize,
+}
+
+impl TransferReport {
+    /// The share of the accuracy on the source assistant that carries over to the target assistant.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys_analysis::ml::transfer::TransferReport;
+    /// let report = TransferReport {
+    ///     source: "Siri".to_string(),
+    ///     target: "Alexa".to_string(),
+    ///     queries: 13,
+    ///     source_accuracy: 0.8,
+    ///     source_samples: 130,
+    ///     target_accuracy: 0.2,
+    ///     target_samples: 1300,
+    /// };
+    ///
+    /// assert_eq!(report.transferability(), 0.25);
+    /// ```
+    pub fn transferability(&self) -> f32 {
+        if self.source_accuracy > 0. {
+            self.target_accuracy / self.source_accuracy
+        } else {
+            0.
+        }
+    }
+}
+
+impl Display for TransferReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Trained on {} and tested on {} with {} shared queries:\n\
+            {}: {:.2}% of {} traces\n\
+            {}: {:.2}% of {} traces\n\
+            Transferability: {:.2}",
+            self.source,
+            self.target,
+            self.queries,
+            self.source,
+            self.source_accuracy * 100.,
+            self.source_samples,
+            self.target,
+            self.target_accuracy * 100.,
+            self.target_samples,
+            self.transferability()
+        )
+    }
+}
+
+/// Train a model on the traces of one voice assistant and test it on the traces of another one.
+///
+/// Only the queries asked to both assistants are used, so the interactions of both have to be labelled with the
+/// query without the wake word. The model is trained on the training split of the source assistant and tested on its
+/// test split and on all traces of the target assistant.
+///
+/// Transfer runs are stored in `ml/transfer` of the data directory, next to a `transfer.json` with the report, so
+/// they are never used for inference.
+///
+/// # Arguments
+///
+/// * `data_dir`: The directory in which data files are stored.
+/// * `source`: The name of the assistant to train on and its interactions.
+/// * `target`: The name of the assistant to test on and its interactions.
+/// * `metrics`: Custom metrics to compute and log during training, next to the accuracy and loss.
+pub fn evaluate<P: AsRef<Path>>(
+    data_dir: P,
+    source: (&str, Vec<Interaction>),
+    target: (&str, Vec<Interaction>),
+    metrics: &MetricPlugins,
+) -> Result<TransferReport, Error> {
+    let (source_name, source_interactions) = source;
+    let (target_name, target_interactions) = target;
+
+    let target_queries: HashSet<&str> = target_interactions
+        .iter()
+        .map(|interaction| interaction.query.as_str())
+        .collect();
+    let label_map = LabelMap::new(
+        &format!("transfer-{source_name}-{target_name}").to_lowercase(),
+        source_interactions
+            .iter()
+            .filter(|interaction| target_queries.contains(interaction.query.as_str()))
+            .map(|interaction| interaction.query.clone()),
+    )?;
+    if label_map.is_empty() {
+        return Err(Error::NoSharedQueries(
+            source_name.to_string(),
+            target_name.to_string(),
+        ));
+    }
+
+    info!(
+        "Evaluating the transfer from {source_name} to {target_name} on {} shared queries",
+        label_map.len()
+    );
+
+    let mut source_dataset =
+        NumericTraceDataset::new(&data_dir, source_interactions, label_map.clone())?;
+    source_dataset
+        .normalise()
+        .resize_all(CNNModelConfig::DEFAULT_INPUT_DIMENSIONS)
+        .shuffle();
+    let mut target_dataset = NumericTraceDataset::new(&data_dir, target_interactions, label_map)?;
+    target_dataset
+        .normalise()
+        .resize_all(CNNModelConfig::DEFAULT_INPUT_DIMENSIONS);
+
+    let device = WgpuDevice::default();
+    let run = RunDir::create_in(transfer_path(&data_dir))?;
+    let config = ml::training_config(source_dataset.num_labels());
+    let (training_dataset, validation_dataset, testing_dataset) = source_dataset.split_default()?;
+
+    info!("Training on {source_name} in run {}...", run.name());
+
+    training::train::<AutodiffBackend, _>(
+        &run,
+        config,
+        training_dataset,
+        validation_dataset,
+        true,
+        device.clone(),
+        metrics,
+    )?;
+
+    let model = inference::load_run_model::<Backend>(&run, &device)?;
+    let report = TransferReport {
+        source: source_name.to_string(),
+        target: target_name.to_string(),
+        queries: testing_dataset.num_labels(),
+        source_accuracy: accuracy(&model, &testing_dataset, &device),
+        source_samples: testing_dataset.items.len(),
+        target_accuracy: accuracy(&model, &target_dataset, &device),
+        target_samples: target_dataset.items.len(),
+    };
+    serde_json::to_writer_pretty(File::create(run.path.join("transfer.json"))?, &report)?;
+
+    Ok(report)
+}
+
+/// The share of the traces of a dataset whose query the model recognises correctly.
+fn accuracy(model: &CNNModel<Backend>, dataset: &NumericTraceDataset, device: &WgpuDevice) -> f32 {
+    if dataset.items.is_empty() {
+        return 0.;
+    }
+
+    let batcher = TrafficTraceBatcher::<Backend>::new(device.clone());
+    let mut correct = 0;
+    for items in dataset.items.chunks(EVALUATION_BATCH_SIZE) {
+        let batch = batcher.batch(items.to_vec());
+        let predicted = model
+            .forward(batch.traces)
+            .argmax(1)
+            .flatten::<1>(0, 1)
+            .to_data()
+            .value;
+
+        correct += predicted
+            .iter()
+            .zip(items)
+            .filter(|(predicted, item)| **predicted == item.label as i32)
+            .count();
+    }
+
+    correct as f32 / dataset.items.len() as f32
+}
+
+fn transfer_path<P: AsRef<Path>>(data_dir: P) -> String {
+    format!(
+        "{}/transfer",
+        ml::ml_path(data_dir.as_ref().to_string_lossy().as_ref())
+    )
+}
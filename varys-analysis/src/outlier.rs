@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::Path;
+
+use log::{info, warn};
+
+use varys_database::database::interaction::Interaction;
+use varys_database::file;
+
+use crate::ml::data::NumericTraceDataset;
+
+/// The minimum number of samples a class needs for outliers to be detected in it.
+pub const MIN_CLASS_SIZE: usize = 4;
+
+/// A metric of an interaction that is checked for outliers.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Metric {
+    /// The number of packets in the traffic trace.
+    TraceLength,
+    /// The size of the capture file in bytes.
+    CaptureSize,
+    /// The duration of the response in milliseconds.
+    ResponseDuration,
+}
+
+impl Metric {
+    /// All metrics that are checked for outliers.
+    pub const ALL: [Metric; 3] = [
+        Metric::TraceLength,
+        Metric::CaptureSize,
+        Metric::ResponseDuration,
+    ];
+
+    /// Get the value of this metric for an interaction, or `None` if it cannot be determined.
+    ///
+    /// # Arguments
+    ///
+    /// * `data_dir`: The directory in which data files are stored.
+    /// * `interaction`: The interaction to get the value for.
+    pub fn value<P: AsRef<Path>>(&self, data_dir: P, interaction: &Interaction) -> Option<f64> {
+        match self {
+            Metric::TraceLength => {
+                NumericTraceDataset::load_interaction_trace(data_dir, interaction)
+                    .ok()
+                    .map(|trace| trace.0.len() as f64)
+            }
+            Metric::CaptureSize => interaction
+                .capture_file
+                .as_ref()
+                .map(|capture_file| {
+                    file::session_path(data_dir, interaction.session_id).join(capture_file)
+                })
+                .and_then(|path| fs::metadata(path).ok())
+                .map(|metadata| metadata.len() as f64),
+            Metric::ResponseDuration => interaction.response_duration.map(f64::from),
+        }
+    }
+}
+
+impl Display for Metric {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Metric::TraceLength => "trace_length",
+                Metric::CaptureSize => "capture_size",
+                Metric::ResponseDuration => "response_duration",
+            }
+        )
+    }
+}
+
+/// An interaction with a metric that is an outlier within its class.
+pub struct OutlierFlag {
+    pub interaction_id: i32,
+    pub metric: Metric,
+    pub value: f64,
+}
+
+/// Find all interactions with a metric that is an outlier among the interactions with the same query.
+///
+/// Outliers are detected with Tukey's fences, see [`fences`]. Classes with less than [`MIN_CLASS_SIZE`] samples are
+/// skipped.
+///
+/// # Arguments
+///
+/// * `data_dir`: The directory in which data files are stored.
+/// * `interactions`: The interactions to check.
+/// * `factor`: How many interquartile ranges a value must lie outside the quartiles to be an outlier.
+pub fn find_outliers<P: AsRef<Path>>(
+    data_dir: P,
+    interactions: &[Interaction],
+    factor: f64,
+) -> Vec<OutlierFlag> {
+    let mut classes: HashMap<&str, Vec<&Interaction>> = HashMap::new();
+    for interaction in interactions {
+        classes
+            .entry(&interaction.query)
+            .or_default()
+            .push(interaction);
+    }
+
+    info!(
+        "Checking {} interactions in {} classes for outliers...",
+        interactions.len(),
+        classes.len()
+    );
+
+    let mut flags = Vec::new();
+
+    for (query, class) in classes {
+        if class.len() < MIN_CLASS_SIZE {
+            warn!("Skipping \"{query}\", it has only {} samples", class.len());
+            continue;
+        }
+
+        for metric in Metric::ALL {
+            let values: Vec<(i32, f64)> = class
+                .iter()
+                .filter_map(|interaction| {
+                    metric
+                        .value(&data_dir, interaction)
+                        .map(|value| (interaction.id, value))
+                })
+                .collect();
+            let Some((lower, upper)) = fences(
+                &values.iter().map(|(_, value)| *value).collect::<Vec<_>>(),
+                factor,
+            ) else {
+                continue;
+            };
+
+            flags.extend(
+                values
+                    .into_iter()
+                    .filter(|(_, value)| *value < lower || *value > upper)
+                    .map(|(interaction_id, value)| OutlierFlag {
+                        interaction_id,
+                        metric,
+                        value,
+                    }),
+            );
+        }
+    }
+
+    flags
+}
+
+/// Calculate Tukey's fences for a list of values.
+///
+/// Values below the lower or above the upper fence are outliers.
+///
+/// Returns `None` if there are less than [`MIN_CLASS_SIZE`] values.
+///
+/// # Arguments
+///
+/// * `values`: The values to calculate the fences for.
+/// * `factor`: How many interquartile ranges the fences lie outside the quartiles, usually `1.5`.
+///
+/// # Examples
+///
+/// ```
+/// # use varys_analysis::outlier::fences;
+/// let values = [1.0, 2.0, 3.0, 4.0, 5.0];
+///
+/// assert_eq!(fences(&values, 1.5), Some((-1.0, 7.0)));
+/// assert_eq!(fences(&values[..3], 1.5), None);
+/// ```
+pub fn fences(values: &[f64], factor: f64) -> Option<(f64, f64)> {
+    if values.len() < MIN_CLASS_SIZE {
+        return None;
+    }
+
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+
+    let lower_quartile = quantile(&sorted, 0.25);
+    let upper_quartile = quantile(&sorted, 0.75);
+    let range = upper_quartile - lower_quartile;
+
+    Some((
+        lower_quartile - factor * range,
+        upper_quartile + factor * range,
+    ))
+}
+
+/// Get a quantile of sorted values, linearly interpolating between the closest values.
+fn quantile(sorted: &[f64], quantile: f64) -> f64 {
+    let position = quantile * (sorted.len() - 1) as f64;
+    let lower = position.floor() as usize;
+    let upper = position.ceil() as usize;
+
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (position - lower as f64)
+}
@@ -4,12 +4,17 @@ use std::path::Path;
 
 use plotters::prelude::*;
 use plotters::style::SizeDesc;
+use varys_network::address::MacAddress;
 
+use crate::error::Error;
 use crate::ml::data::NumericTraceDataset;
-use crate::trace::NumericTrafficTrace;
+use crate::trace::{NumericTrafficTrace, TrafficTrace};
 
 const MAX_VALUE: i32 = 1514;
 
+/// The characters of a sparkline, from no traffic to the most traffic.
+const SPARK_LEVELS: [char; 9] = [' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
 pub fn plot_queries<P: AsRef<Path>>(
     data_path: P,
     queries: Vec<&str>,
@@ -43,6 +48,116 @@ pub fn plot<P: AsRef<Path>>(data_path: P, query: &str, dataset: &NumericTraceDat
     }
 }
 
+/// Plot the packet sizes of a trace over time, with outgoing packets above and incoming packets below the axis.
+///
+/// # Arguments
+///
+/// * `path`: Where to save the `.png` file.
+/// * `trace`: The trace to plot.
+/// * `relative_to`: The address of the assistant, which determines the direction of packets.
+/// * `title`: The caption of the plot, e.g. the query of the interaction.
+pub fn plot_timeline<P: AsRef<Path>>(
+    path: P,
+    trace: &TrafficTrace,
+    relative_to: &MacAddress,
+    title: &str,
+) -> Result<(), Error> {
+    let plot_error = |error: DrawingAreaErrorKind<_>| Error::PlotError(error.to_string());
+    let duration = (trace.duration().num_milliseconds() as f64 / 1000.).max(0.001);
+
+    let drawing_area = BitMapBackend::new(path.as_ref(), (1200, 600)).into_drawing_area();
+    drawing_area.fill(&WHITE).map_err(plot_error)?;
+    let mut chart = ChartBuilder::on(&drawing_area)
+        .caption(title, ("sans-serif", 20))
+        .margin(10)
+        .x_label_area_size(40)
+        .y_label_area_size(60)
+        .build_cartesian_2d(0_f64..duration, -MAX_VALUE..(MAX_VALUE + 1))
+        .map_err(plot_error)?;
+    chart
+        .configure_mesh()
+        .light_line_style(TRANSPARENT)
+        .bold_line_style(RGBAColor(0, 0, 0, 0.2))
+        .x_desc("Time (s)")
+        .y_desc("Packet size (B, outgoing > 0)")
+        .draw()
+        .map_err(plot_error)?;
+
+    chart
+        .draw_series(trace.packets.iter().filter_map(|packet| {
+            let direction = packet.direction(relative_to)?;
+            let time = (packet.timestamp - trace.start_time).num_microseconds()? as f64 / 1e6;
+            let value = f32::from(direction) * packet.len as f32;
+
+            Some(PathElement::new(
+                [(time, 0), (time, value as i32)],
+                color(value as f64).stroke_width(2),
+            ))
+        }))
+        .map_err(plot_error)?;
+    drawing_area.present().map_err(plot_error)?;
+
+    Ok(())
+}
+
+/// Draw the traffic of a trace as two sparklines for the terminal, the outgoing and the incoming bytes over time.
+///
+/// Both sparklines share their scale, so their heights can be compared.
+///
+/// # Arguments
+///
+/// * `trace`: The trace to draw.
+/// * `relative_to`: The address of the assistant, which determines the direction of packets.
+/// * `width`: The number of characters of each sparkline.
+pub fn sparklines(
+    trace: &TrafficTrace,
+    relative_to: &MacAddress,
+    width: usize,
+) -> (String, String) {
+    let width = width.max(1);
+    let duration = trace
+        .duration()
+        .num_microseconds()
+        .unwrap_or(i64::MAX)
+        .max(1) as f64;
+    let mut outgoing = vec![0_u64; width];
+    let mut incoming = vec![0_u64; width];
+
+    for packet in &trace.packets {
+        let Some(direction) = packet.direction(relative_to) else {
+            continue;
+        };
+        let time = (packet.timestamp - trace.start_time)
+            .num_microseconds()
+            .unwrap_or_default() as f64;
+        let bin = ((time / duration * width as f64) as usize).min(width - 1);
+
+        if f32::from(direction) > 0. {
+            outgoing[bin] += packet.len as u64;
+        } else {
+            incoming[bin] += packet.len as u64;
+        }
+    }
+
+    let max = outgoing
+        .iter()
+        .chain(&incoming)
+        .copied()
+        .max()
+        .unwrap_or_default()
+        .max(1);
+    let line = |bins: &[u64]| -> String {
+        bins.iter()
+            .map(|&bytes| {
+                let level = (bytes as f64 / max as f64 * (SPARK_LEVELS.len() - 1) as f64).ceil();
+                SPARK_LEVELS[level as usize]
+            })
+            .collect()
+    };
+
+    (line(&outgoing), line(&incoming))
+}
+
 fn plot_trace<DB: DrawingBackend, S: SizeDesc>(
     trace: &NumericTrafficTrace,
     drawing_area: &DrawingArea<DB, Shift>,
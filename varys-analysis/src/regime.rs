@@ -0,0 +1,310 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use log::{debug, info};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use varys_database::database::interaction::Interaction;
+
+use crate::outlier::Metric;
+
+/// The minimum mean silhouette of a clustering for its sessions to be split into more than one regime.
+///
+/// Below it, the differences between sessions are not distinct enough to be caused by a change of the environment.
+pub const MIN_SILHOUETTE: f64 = 0.25;
+
+/// The seed of the clustering, so the same sessions are always assigned the same regimes.
+const SEED: u64 = 42;
+
+/// How often the centroids are updated at most.
+const MAX_ITERATIONS: usize = 100;
+
+/// The number of aggregate features of a session.
+const FEATURES: usize = 4;
+
+/// The aggregate features of the interactions of a session.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionFeatures {
+    pub session_id: i32,
+    /// The mean trace length, capture size, response latency and network latency of the interactions of the session,
+    /// or `None` if none of its interactions has a value.
+    pub values: [Option<f64>; FEATURES],
+}
+
+impl SessionFeatures {
+    /// Aggregate the features of the interactions of every session.
+    ///
+    /// # Arguments
+    ///
+    /// * `data_dir`: The directory in which data files are stored.
+    /// * `interactions`: The interactions to aggregate.
+    pub fn of_sessions<P: AsRef<Path>>(data_dir: P, interactions: &[Interaction]) -> Vec<Self> {
+        let mut sessions: BTreeMap<i32, Vec<&Interaction>> = BTreeMap::new();
+        for interaction in interactions {
+            sessions
+                .entry(interaction.session_id)
+                .or_default()
+                .push(interaction);
+        }
+
+        sessions
+            .into_iter()
+            .map(|(session_id, interactions)| {
+                let feature = |value: &dyn Fn(&Interaction) -> Option<f64>| {
+                    mean(
+                        interactions
+                            .iter()
+                            .filter_map(|interaction| value(interaction)),
+                    )
+                };
+
+                SessionFeatures {
+                    session_id,
+                    values: [
+                        feature(&|interaction| Metric::TraceLength.value(&data_dir, interaction)),
+                        feature(&|interaction| Metric::CaptureSize.value(&data_dir, interaction)),
+                        feature(&|interaction| interaction.response_latency_ms.map(f64::from)),
+                        feature(&|interaction| {
+                            interaction.response_network_latency_ms.map(f64::from)
+                        }),
+                    ],
+                }
+            })
+            .collect()
+    }
+}
+
+/// Cluster sessions into capture-environment regimes, e.g. the sessions before and after a change of the router.
+///
+/// The features are standardised and clustered with k-means into up to `max_regimes` regimes. The number of regimes
+/// with the highest mean silhouette is used, but all sessions share one regime if it is below [`MIN_SILHOUETTE`].
+/// Missing features are treated like the mean of the other sessions.
+///
+/// Regimes are numbered in the order in which they were first recorded, so the earliest session is in regime `0`.
+///
+/// Returns the regime of every session by its id.
+///
+/// # Arguments
+///
+/// * `sessions`: The features of the sessions to cluster.
+/// * `max_regimes`: The maximum number of regimes.
+pub fn find_regimes(sessions: &[SessionFeatures], max_regimes: usize) -> HashMap<i32, i32> {
+    let points = standardise(sessions);
+    let mut best: Option<(f64, Vec<usize>)> = None;
+
+    for k in 2..=max_regimes.min(points.len().saturating_sub(1)) {
+        let assignments = k_means(&points, k);
+        let score = silhouette(&points, &assignments);
+
+        debug!(
+            "Clustering {} sessions into {k} regimes has a silhouette of {score:.3}",
+            points.len()
+        );
+
+        if best
+            .as_ref()
+            .is_none_or(|(best_score, _)| score > *best_score)
+        {
+            best = Some((score, assignments));
+        }
+    }
+
+    let assignments = match best {
+        Some((score, assignments)) if score >= MIN_SILHOUETTE => assignments,
+        _ => vec![0; points.len()],
+    };
+
+    // number the regimes by their first session, the sessions are sorted by id
+    let mut numbers: HashMap<usize, i32> = HashMap::new();
+    let regimes: HashMap<i32, i32> = sessions
+        .iter()
+        .zip(assignments)
+        .map(|(session, cluster)| {
+            let next = numbers.len() as i32;
+            (session.session_id, *numbers.entry(cluster).or_insert(next))
+        })
+        .collect();
+
+    info!(
+        "Found {} regimes in {} sessions",
+        numbers.len(),
+        sessions.len()
+    );
+
+    regimes
+}
+
+/// Compute the mean silhouette of a clustering, from `-1` for wrongly assigned points to `1` for dense, well separated
+/// clusters.
+///
+/// Points in clusters of their own have a silhouette of `0`.
+///
+/// # Arguments
+///
+/// * `points`: The clustered points.
+/// * `assignments`: The cluster of every point.
+///
+/// # Examples
+///
+/// ```
+/// # use varys_analysis::regime::silhouette;
+/// let points = vec![vec![0.0], vec![0.1], vec![5.0], vec![5.1]];
+///
+/// assert!(silhouette(&points, &[0, 0, 1, 1]) > 0.9);
+/// assert!(silhouette(&points, &[0, 1, 0, 1]) < 0.0);
+/// ```
+pub fn silhouette(points: &[Vec<f64>], assignments: &[usize]) -> f64 {
+    if points.is_empty() {
+        return 0.;
+    }
+
+    let clusters = assignments.iter().max().map_or(0, |max| max + 1);
+    let total: f64 = points
+        .iter()
+        .zip(assignments)
+        .map(|(point, &cluster)| {
+            let mut distances = vec![(0., 0); clusters];
+            for (other, &other_cluster) in points.iter().zip(assignments) {
+                distances[other_cluster].0 += distance(point, other);
+                distances[other_cluster].1 += 1;
+            }
+
+            let (own_distance, own_count) = distances[cluster];
+            if own_count < 2 {
+                return 0.;
+            }
+            let own = own_distance / (own_count - 1) as f64;
+            let nearest = distances
+                .iter()
+                .enumerate()
+                .filter(|(other_cluster, (_, count))| *other_cluster != cluster && *count > 0)
+                .map(|(_, (distance, count))| distance / *count as f64)
+                .fold(f64::MAX, f64::min);
+            if nearest == f64::MAX {
+                return 0.;
+            }
+
+            (nearest - own) / own.max(nearest).max(f64::EPSILON)
+        })
+        .sum();
+
+    total / points.len() as f64
+}
+
+/// Cluster points into `k` clusters with k-means, using k-means++ to choose the initial centroids.
+///
+/// Returns the cluster of every point.
+fn k_means(points: &[Vec<f64>], k: usize) -> Vec<usize> {
+    let mut rng = StdRng::seed_from_u64(SEED);
+    let mut centroids = vec![points[rng.gen_range(0..points.len())].clone()];
+    while centroids.len() < k {
+        let weights: Vec<f64> = points
+            .iter()
+            .map(|point| nearest_centroid(point, &centroids).1.powi(2))
+            .collect();
+        let total: f64 = weights.iter().sum();
+        if total <= 0. {
+            break;
+        }
+
+        let mut target = rng.gen_range(0.0..total);
+        let index = weights
+            .iter()
+            .position(|weight| {
+                target -= weight;
+                target < 0.
+            })
+            .unwrap_or(points.len() - 1);
+        centroids.push(points[index].clone());
+    }
+
+    let mut assignments = vec![0; points.len()];
+    for _ in 0..MAX_ITERATIONS {
+        let updated: Vec<usize> = points
+            .iter()
+            .map(|point| nearest_centroid(point, &centroids).0)
+            .collect();
+        if updated == assignments {
+            break;
+        }
+        assignments = updated;
+
+        for (cluster, centroid) in centroids.iter_mut().enumerate() {
+            let members: Vec<&Vec<f64>> = points
+                .iter()
+                .zip(&assignments)
+                .filter(|(_, &assignment)| assignment == cluster)
+                .map(|(point, _)| point)
+                .collect();
+            if members.is_empty() {
+                continue;
+            }
+
+            for (dimension, value) in centroid.iter_mut().enumerate() {
+                *value = members.iter().map(|point| point[dimension]).sum::<f64>()
+                    / members.len() as f64;
+            }
+        }
+    }
+
+    assignments
+}
+
+/// Find the closest centroid to a point, returning its index and the distance to it.
+fn nearest_centroid(point: &[f64], centroids: &[Vec<f64>]) -> (usize, f64) {
+    centroids
+        .iter()
+        .map(|centroid| distance(point, centroid))
+        .enumerate()
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .unwrap_or((0, 0.))
+}
+
+fn distance(a: &[f64], b: &[f64]) -> f64 {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| (a - b).powi(2))
+        .sum::<f64>()
+        .sqrt()
+}
+
+/// Standardise every feature to a mean of `0` and a standard deviation of `1` across sessions.
+///
+/// Missing features and features that are the same in all sessions become `0`.
+fn standardise(sessions: &[SessionFeatures]) -> Vec<Vec<f64>> {
+    let mut points = vec![vec![0.; FEATURES]; sessions.len()];
+
+    for feature in 0..FEATURES {
+        let values: Vec<f64> = sessions
+            .iter()
+            .filter_map(|session| session.values[feature])
+            .collect();
+        let Some(mean) = mean(values.iter().copied()) else {
+            continue;
+        };
+        let deviation = (values
+            .iter()
+            .map(|value| (value - mean).powi(2))
+            .sum::<f64>()
+            / values.len() as f64)
+            .sqrt();
+        if deviation <= 0. {
+            continue;
+        }
+
+        for (point, session) in points.iter_mut().zip(sessions) {
+            if let Some(value) = session.values[feature] {
+                point[feature] = (value - mean) / deviation;
+            }
+        }
+    }
+
+    points
+}
+
+fn mean(values: impl Iterator<Item = f64>) -> Option<f64> {
+    let (sum, count) = values.fold((0., 0), |(sum, count), value| (sum + value, count + 1));
+
+    (count > 0).then(|| sum / count as f64)
+}
@@ -0,0 +1,215 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+use std::str::FromStr;
+
+use log::{debug, info};
+use serde::Serialize;
+
+use varys_database::database::interaction::Interaction;
+use varys_network::address::MacAddress;
+use varys_network::packet::PacketDirection;
+
+use crate::trace::TrafficTrace;
+
+/// The class of the correlations over the interactions of all queries.
+pub const ALL_CLASSES: &str = "all";
+
+/// The minimum number of interactions of a class to compute correlations for it.
+const MIN_SAMPLES: usize = 3;
+
+/// How long the spoken query and the response of an interaction took and how many bytes the assistant sent and
+/// received during it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimingFeatures {
+    /// The duration of the spoken query in milliseconds.
+    pub query_duration: f64,
+    /// The duration of the response in milliseconds.
+    pub response_duration: f64,
+    /// The number of bytes the assistant sent.
+    pub upstream_bytes: f64,
+    /// The number of bytes the assistant received.
+    pub downstream_bytes: f64,
+}
+
+impl TimingFeatures {
+    /// Get the timing features of an interaction.
+    ///
+    /// Returns `None` if the interaction has no query or response duration or its traffic trace cannot be loaded.
+    ///
+    /// # Arguments
+    ///
+    /// * `data_dir`: The directory in which data files are stored.
+    /// * `interaction`: The interaction to get the features of.
+    pub fn of_interaction<P: AsRef<Path>>(data_dir: P, interaction: &Interaction) -> Option<Self> {
+        let query_duration = interaction.query_duration?;
+        let response_duration = interaction.response_duration?;
+        let assistant_mac = MacAddress::from_str(&interaction.assistant_mac).ok()?;
+        let trace = TrafficTrace::load_interaction(data_dir, interaction).ok()?;
+
+        let mut upstream_bytes = 0;
+        let mut downstream_bytes = 0;
+        for packet in &trace.packets {
+            match packet.direction(&assistant_mac) {
+                Some(PacketDirection::Out) => upstream_bytes += packet.len,
+                Some(PacketDirection::In) => downstream_bytes += packet.len,
+                None => {}
+            }
+        }
+
+        Some(TimingFeatures {
+            query_duration: f64::from(query_duration),
+            response_duration: f64::from(response_duration),
+            upstream_bytes: upstream_bytes as f64,
+            downstream_bytes: downstream_bytes as f64,
+        })
+    }
+}
+
+/// How strongly the durations of the spoken query and the response correlate with the bytes sent and received by the
+/// assistant, as Pearson correlation coefficients.
+///
+/// A coefficient is `None` if the class has too few interactions or one of the values does not vary.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TimingCorrelation {
+    /// The query the correlations were computed for, or [`ALL_CLASSES`] for the interactions of all queries.
+    pub class: String,
+    /// The number of interactions of the class.
+    pub samples: usize,
+    pub query_upstream: Option<f64>,
+    pub query_downstream: Option<f64>,
+    pub response_upstream: Option<f64>,
+    pub response_downstream: Option<f64>,
+}
+
+impl Display for TimingCorrelation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let format = |coefficient: Option<f64>| {
+            coefficient.map_or("-".to_string(), |coefficient| format!("{coefficient:.2}"))
+        };
+
+        write!(
+            f,
+            "{} ({} samples): query/upstream {}, query/downstream {}, response/upstream {}, response/downstream {}",
+            self.class,
+            self.samples,
+            format(self.query_upstream),
+            format(self.query_downstream),
+            format(self.response_upstream),
+            format(self.response_downstream)
+        )
+    }
+}
+
+/// Correlate the durations of spoken queries and responses with the bytes sent and received by the assistant, over
+/// all interactions and within the interactions of every query.
+///
+/// This quantifies the simplest timing side channels: a strong correlation over all queries means the traffic volume
+/// alone reveals how long the query or response was.
+///
+/// Returns the correlations over all interactions first, followed by those of every query ordered by the query.
+/// Interactions whose features are missing are skipped.
+///
+/// # Arguments
+///
+/// * `data_dir`: The directory in which data files are stored.
+/// * `interactions`: The interactions to correlate.
+pub fn correlate<P: AsRef<Path>>(
+    data_dir: P,
+    interactions: &[Interaction],
+) -> Vec<TimingCorrelation> {
+    let mut classes: BTreeMap<&str, Vec<TimingFeatures>> = BTreeMap::new();
+    for interaction in interactions {
+        match TimingFeatures::of_interaction(&data_dir, interaction) {
+            Some(features) => classes
+                .entry(&interaction.query)
+                .or_default()
+                .push(features),
+            None => debug!("Skipping {interaction} without timing features"),
+        }
+    }
+    let all: Vec<TimingFeatures> = classes.values().flatten().cloned().collect();
+
+    info!(
+        "Correlating the timing of {} of {} interactions",
+        all.len(),
+        interactions.len()
+    );
+
+    std::iter::once(correlation(ALL_CLASSES, &all))
+        .chain(
+            classes
+                .iter()
+                .map(|(class, features)| correlation(class, features)),
+        )
+        .collect()
+}
+
+fn correlation(class: &str, features: &[TimingFeatures]) -> TimingCorrelation {
+    let column = |value: fn(&TimingFeatures) -> f64| features.iter().map(value).collect::<Vec<_>>();
+    let query = column(|features| features.query_duration);
+    let response = column(|features| features.response_duration);
+    let upstream = column(|features| features.upstream_bytes);
+    let downstream = column(|features| features.downstream_bytes);
+    let coefficient = |x: &[f64], y: &[f64]| {
+        if features.len() < MIN_SAMPLES {
+            None
+        } else {
+            pearson(x, y)
+        }
+    };
+
+    TimingCorrelation {
+        class: class.to_string(),
+        samples: features.len(),
+        query_upstream: coefficient(&query, &upstream),
+        query_downstream: coefficient(&query, &downstream),
+        response_upstream: coefficient(&response, &upstream),
+        response_downstream: coefficient(&response, &downstream),
+    }
+}
+
+/// Compute the Pearson correlation coefficient of two series, from `-1` for a perfect negative linear relationship to
+/// `1` for a perfect positive one.
+///
+/// Returns `None` if the series have different lengths, fewer than two values or one of them does not vary.
+///
+/// # Arguments
+///
+/// * `x`: The first series.
+/// * `y`: The second series.
+///
+/// # Examples
+///
+/// ```
+/// # use varys_analysis::timing::pearson;
+/// assert_eq!(pearson(&[1.0, 2.0, 3.0], &[2.0, 4.0, 6.0]), Some(1.0));
+/// assert_eq!(pearson(&[1.0, 2.0, 3.0], &[3.0, 2.0, 1.0]), Some(-1.0));
+/// assert_eq!(pearson(&[1.0, 2.0, 3.0], &[5.0, 5.0, 5.0]), None);
+/// ```
+pub fn pearson(x: &[f64], y: &[f64]) -> Option<f64> {
+    if x.len() != y.len() || x.len() < 2 {
+        return None;
+    }
+
+    let n = x.len() as f64;
+    let mean_x = x.iter().sum::<f64>() / n;
+    let mean_y = y.iter().sum::<f64>() / n;
+    let (covariance, variance_x, variance_y) = x.iter().zip(y).fold(
+        (0., 0., 0.),
+        |(covariance, variance_x, variance_y), (x, y)| {
+            let (dx, dy) = (x - mean_x, y - mean_y);
+            (
+                covariance + dx * dy,
+                variance_x + dx * dx,
+                variance_y + dy * dy,
+            )
+        },
+    );
+
+    if variance_x <= 0. || variance_y <= 0. {
+        return None;
+    }
+
+    Some(covariance / (variance_x * variance_y).sqrt())
+}
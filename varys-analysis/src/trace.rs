@@ -1,9 +1,16 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
 use std::fmt::{Debug, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::path::Path;
 
 use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 
+use varys_database::database::interaction::Interaction;
+use varys_database::file;
 use varys_network::address::MacAddress;
+use varys_network::packet;
 use varys_network::packet::Packet;
 
 use crate::error::Error;
@@ -15,6 +22,27 @@ pub struct TrafficTrace {
 }
 
 impl TrafficTrace {
+    /// Load the trace captured during an interaction.
+    ///
+    /// # Arguments
+    ///
+    /// * `data_path`: The directory in which data files are stored.
+    /// * `interaction`: The interaction to load the trace of.
+    pub fn load_interaction<P: AsRef<Path>>(
+        data_path: P,
+        interaction: &Interaction,
+    ) -> Result<Self, Error> {
+        let capture_path = interaction
+            .capture_file
+            .as_ref()
+            .map(|path| file::session_path(data_path, interaction.session_id).join(path))
+            .ok_or(Error::CannotLoadTrace)?;
+
+        packet::load_packets(capture_path)
+            .map_err(|_| Error::CannotLoadTrace)
+            .and_then(TrafficTrace::try_from)
+    }
+
     pub fn duration(&self) -> Duration {
         self.end_time - self.start_time
     }
@@ -42,6 +70,27 @@ impl TrafficTrace {
         )
     }
 
+    /// Get the time of every packet of [`Self::as_numeric_trace`] in seconds since the start of the trace.
+    pub fn as_packet_times(&self, relative_to: &MacAddress) -> Vec<f32> {
+        self.packets
+            .iter()
+            .filter(|packet| packet.direction(relative_to).is_some())
+            .map(|packet| {
+                (packet.timestamp - self.start_time)
+                    .num_microseconds()
+                    .unwrap_or_default() as f32
+                    / 1000000.
+            })
+            .collect()
+    }
+
+    pub fn as_burst_trace(&self, relative_to: &MacAddress) -> BurstTrafficTrace {
+        BurstTrafficTrace::from_packets(
+            &self.as_numeric_trace(relative_to).0,
+            &self.as_packet_times(relative_to),
+        )
+    }
+
     pub fn as_wang_traffic_trace(&self, relative_to: &MacAddress) -> WangTrafficTrace {
         let start_time = self
             .packets
@@ -144,6 +193,10 @@ impl Display for BinaryTrafficTrace {
 pub struct NumericTrafficTrace(pub Vec<f32>);
 
 impl NumericTrafficTrace {
+    /// The largest difference in bytes between the packet sizes of near-identical traces, see
+    /// [`Self::is_near_duplicate`].
+    pub const SIMILARITY_TOLERANCE: f32 = 16.;
+
     /// Resize the trace, truncating if it is longer than `len` and adding zeroes if it is shorter.
     ///
     /// # Arguments
@@ -192,6 +245,48 @@ impl NumericTrafficTrace {
     pub fn scale(&mut self, scale: f32) {
         self.0.iter_mut().for_each(|value| *value *= scale);
     }
+
+    /// Whether another trace is near-identical to this one, e.g. because the same sample was accidentally collected
+    /// twice.
+    ///
+    /// Near-identical traces have the same number of packets, and their packets have the same directions and sizes
+    /// that differ by at most [`Self::SIMILARITY_TOLERANCE`] bytes.
+    ///
+    /// # Arguments
+    ///
+    /// * `other`: The trace to compare to.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys_analysis::trace::NumericTrafficTrace;
+    /// let trace = NumericTrafficTrace(vec![119., -1514., -870.]);
+    /// let near_duplicate = NumericTrafficTrace(vec![121., -1514., -868.]);
+    /// let other = NumericTrafficTrace(vec![119., -1514., 870.]);
+    ///
+    /// assert!(trace.is_near_duplicate(&near_duplicate));
+    /// assert!(!trace.is_near_duplicate(&other));
+    /// assert!(!trace.is_near_duplicate(&NumericTrafficTrace(vec![119., -1514.])));
+    /// ```
+    pub fn is_near_duplicate(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len()
+            && self.0.iter().zip(&other.0).all(|(value, other)| {
+                (*value >= 0.) == (*other >= 0.)
+                    && (value - other).abs() <= Self::SIMILARITY_TOLERANCE
+            })
+    }
+
+    /// Hash the number of packets and their directions, which near-identical traces have in common.
+    fn shape_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+
+        self.0.len().hash(&mut hasher);
+        for value in &self.0 {
+            (*value >= 0.).hash(&mut hasher);
+        }
+
+        hasher.finish()
+    }
 }
 
 impl Display for NumericTrafficTrace {
@@ -210,6 +305,185 @@ impl Display for NumericTrafficTrace {
     }
 }
 
+/// Detects traces that are near-identical to a trace seen before, see [`NumericTrafficTrace::is_near_duplicate`].
+///
+/// Traces are grouped by a key, e.g. their label, and by their number of packets and their directions, so every trace
+/// is only compared to the traces it can be near-identical to.
+///
+/// # Examples
+///
+/// ```
+/// # use varys_analysis::trace::{DuplicateFilter, NumericTrafficTrace};
+/// let mut filter = DuplicateFilter::default();
+///
+/// assert!(filter.insert(1, NumericTrafficTrace(vec![119., -1514.])));
+/// assert!(!filter.insert(1, NumericTrafficTrace(vec![121., -1510.])));
+/// assert!(filter.insert(2, NumericTrafficTrace(vec![121., -1510.])));
+/// assert!(filter.insert(1, NumericTrafficTrace(vec![150., -1510.])));
+/// ```
+#[derive(Debug)]
+pub struct DuplicateFilter<K> {
+    seen: HashMap<(K, u64), Vec<NumericTrafficTrace>>,
+}
+
+impl<K> Default for DuplicateFilter<K> {
+    fn default() -> Self {
+        DuplicateFilter {
+            seen: HashMap::new(),
+        }
+    }
+}
+
+impl<K: Eq + Hash> DuplicateFilter<K> {
+    /// Remember a trace unless it is near-identical to a trace seen before with the same key.
+    ///
+    /// Returns whether the trace was not seen before.
+    ///
+    /// # Arguments
+    ///
+    /// * `key`: The key of the trace, only traces with the same key are compared.
+    /// * `trace`: The trace to check.
+    pub fn insert(&mut self, key: K, trace: NumericTrafficTrace) -> bool {
+        let group = self.seen.entry((key, trace.shape_hash())).or_default();
+        if group.iter().any(|seen| seen.is_near_duplicate(&trace)) {
+            return false;
+        }
+        group.push(trace);
+
+        true
+    }
+}
+
+/// Consecutive packets in the same direction.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct Burst {
+    /// The total size of the packets in bytes, positive for outgoing and negative for incoming bursts.
+    pub size: f32,
+    /// The number of packets.
+    pub count: f32,
+    /// The time in seconds between the last packet of the previous burst and the first packet of this one.
+    pub gap: f32,
+}
+
+/// Traffic trace of bursts instead of single packets.
+///
+/// How a response is split into packets varies between runs, but the bursts they form are more stable, which makes
+/// this an alternative representation for classification.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct BurstTrafficTrace(pub Vec<Burst>);
+
+impl BurstTrafficTrace {
+    /// Group the packets of a numeric trace into bursts.
+    ///
+    /// Packets of size zero, e.g. the padding added by [`NumericTrafficTrace::resize`], are skipped. If the packet times
+    /// are missing, all gaps are zero.
+    ///
+    /// # Arguments
+    ///
+    /// * `sizes`: The signed packet sizes, see [`TrafficTrace::as_numeric_trace`].
+    /// * `times`: The packet times in seconds, see [`TrafficTrace::as_packet_times`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys_analysis::trace::{Burst, BurstTrafficTrace};
+    /// let trace = BurstTrafficTrace::from_packets(&[100., 50., -1500., -1500., 0.], &[0., 0.5, 1., 1.25, 1.5]);
+    ///
+    /// assert_eq!(
+    ///     trace,
+    ///     BurstTrafficTrace(vec![
+    ///         Burst { size: 150., count: 2., gap: 0. },
+    ///         Burst { size: -3000., count: 2., gap: 0.5 },
+    ///     ])
+    /// );
+    /// ```
+    pub fn from_packets(sizes: &[f32], times: &[f32]) -> Self {
+        let mut bursts: Vec<Burst> = Vec::new();
+        let mut last_time: Option<f32> = None;
+
+        for (index, &size) in sizes.iter().enumerate() {
+            if size == 0. {
+                continue;
+            }
+            let time = times.get(index).copied();
+
+            match bursts.last_mut() {
+                Some(burst) if burst.size.signum() == size.signum() => {
+                    burst.size += size;
+                    burst.count += 1.;
+                }
+                _ => bursts.push(Burst {
+                    size,
+                    count: 1.,
+                    gap: time
+                        .zip(last_time)
+                        .map(|(time, last_time)| (time - last_time).max(0.))
+                        .unwrap_or_default(),
+                }),
+            }
+            last_time = time;
+        }
+
+        Self(bursts)
+    }
+
+    /// The share of bytes sent by the assistant, between `0` and `1`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys_analysis::trace::BurstTrafficTrace;
+    /// let trace = BurstTrafficTrace::from_packets(&[100., -300.], &[]);
+    ///
+    /// assert_eq!(trace.upstream_ratio(), 0.25);
+    /// ```
+    pub fn upstream_ratio(&self) -> f32 {
+        let (upstream, total) = self.0.iter().fold((0., 0.), |(upstream, total), burst| {
+            (upstream + burst.size.max(0.), total + burst.size.abs())
+        });
+
+        if total > 0. {
+            upstream / total
+        } else {
+            0.
+        }
+    }
+
+    /// Flatten the bursts into a numeric trace of their size, count and gap, so it can be used in place of a packet
+    /// trace.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys_analysis::trace::{BurstTrafficTrace, NumericTrafficTrace};
+    /// let trace = BurstTrafficTrace::from_packets(&[100., 50., -1500.], &[0., 0.5, 1.]);
+    ///
+    /// assert_eq!(
+    ///     trace.as_numeric_trace(),
+    ///     NumericTrafficTrace(vec![150., 2., 0., -1500., 1., 0.5])
+    /// );
+    /// ```
+    pub fn as_numeric_trace(&self) -> NumericTrafficTrace {
+        NumericTrafficTrace(
+            self.0
+                .iter()
+                .flat_map(|burst| [burst.size, burst.count, burst.gap])
+                .collect(),
+        )
+    }
+}
+
+impl Display for BurstTrafficTrace {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Burst trace of {} bursts ({:.0}% upstream)",
+            self.0.len(),
+            self.upstream_ratio() * 100.
+        )
+    }
+}
+
 /// Traffic trace for the Wang et al. method, contains (timestamp, size, direction) for each packet.
 /// See section 4.2 in their paper for details.
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
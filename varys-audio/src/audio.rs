@@ -1,18 +1,79 @@
 use std::cmp::min;
+use std::time::Duration;
 
 use audiopus::coder::Encoder;
 use audiopus::{Application, Bitrate, Channels, SampleRate};
 use log::{debug, trace};
 
+use crate::audio::bands::{BandAnalyser, BandEnergies};
 use crate::error::Error;
+use crate::resample::{self, ResampleQuality};
+
+pub mod bands;
 
 const OPUS_FRAME_TIME: usize = 20; // ms (see https://datatracker.ietf.org/doc/html/rfc6716#section-2.1.4)
 const OPUS_FRAME_RATE: usize = 1000 / OPUS_FRAME_TIME; // 1/s
 pub const OPUS_SAMPLE_RATE: usize = 48000; // 1/s (see https://datatracker.ietf.org/doc/html/rfc7845#section-4)
-/// How many silent samples to keep when trimming silence from the start and end of audio.
+/// How many silent samples to keep by default when trimming silence from the start and end of mono audio at the Opus
+/// sample rate, see [`SilencePadding`].
 pub const TRIM_SILENCE_PADDING: usize = OPUS_SAMPLE_RATE / 10; // 0.1s
+/// The length of the windows in which voice activity is detected.
+pub const ONSET_WINDOW_MS: usize = 10;
+/// The most audio is amplified by [`AudioData::gain_control`] in dB.
+pub const MAX_GAIN_DB: f32 = 30.0;
+/// The level in dBFS below which [`AudioData::gain_control`] does not adjust the gain.
+pub const GAIN_GATE_DBFS: f32 = -60.0;
+/// The length of the windows whose gain [`LevelControl::GainControl`] adjusts.
+pub const GAIN_CONTROL_WINDOW_MS: usize = 100;
+
+/// How much silence is kept before and after the sound when trimming the silence of recordings.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SilencePadding {
+    /// The silence kept before the first sound.
+    pub leading: Duration,
+    /// The silence kept after the last sound.
+    pub trailing: Duration,
+}
+
+impl Default for SilencePadding {
+    fn default() -> Self {
+        let padding =
+            Duration::from_millis((TRIM_SILENCE_PADDING * 1000 / OPUS_SAMPLE_RATE) as u64);
+
+        SilencePadding {
+            leading: padding,
+            trailing: padding,
+        }
+    }
+}
+
+/// How the level of recordings is adjusted, so recordings made at different microphone gains are comparable.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LevelControl {
+    /// Scale every recording as a whole to a level in dBFS, see [`AudioData::normalise`].
+    Normalise(f32),
+    /// Continuously adjust the gain of every recording towards a level in dBFS, see [`AudioData::gain_control`].
+    GainControl(f32),
+}
+
+impl LevelControl {
+    /// Adjust the level of a recording.
+    ///
+    /// # Arguments
+    ///
+    /// * `audio`: The recording to adjust.
+    pub fn apply(&self, audio: &mut AudioData) {
+        match self {
+            LevelControl::Normalise(target_dbfs) => audio.normalise(*target_dbfs),
+            LevelControl::GainControl(target_dbfs) => {
+                audio.gain_control(*target_dbfs, GAIN_CONTROL_WINDOW_MS)
+            }
+        };
+    }
+}
 
 /// Holds interleaved audio data for one or more channels.
+#[derive(Clone)]
 pub struct AudioData {
     /// The audio data in interleaved format.
     /// With two channels, this looks like `[l0, r0, l1, r1, ...]`
@@ -67,14 +128,105 @@ impl AudioData {
         self
     }
 
+    /// Get a single channel of the audio data as mono audio.
+    ///
+    /// Returns [`Error::OutOfRange`] if the audio has no channel with this index.
+    ///
+    /// # Arguments
+    ///
+    /// * `index`: The index of the channel, starting at `0`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys_audio::audio::AudioData;
+    /// let audio = AudioData {
+    ///     data: vec![0_f32, 1_f32, 2_f32, 3_f32],
+    ///     channels: 2,
+    ///     sample_rate: 44100,
+    /// };
+    /// assert_eq!(audio.channel(1).unwrap().data, vec![1_f32, 3_f32]);
+    /// assert!(audio.channel(2).is_err());
+    /// ```
+    pub fn channel(&self, index: u8) -> Result<AudioData, Error> {
+        if index >= self.channels {
+            return Err(Error::OutOfRange);
+        }
+
+        Ok(AudioData {
+            data: self
+                .data
+                .iter()
+                .skip(index as usize)
+                .step_by(self.channels as usize)
+                .copied()
+                .collect(),
+            channels: 1,
+            sample_rate: self.sample_rate,
+        })
+    }
+
+    /// Split the audio data into mono audio for each of its channels.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys_audio::audio::AudioData;
+    /// let audio = AudioData {
+    ///     data: vec![0_f32, 1_f32, 2_f32, 3_f32],
+    ///     channels: 2,
+    ///     sample_rate: 44100,
+    /// };
+    /// let channels = audio.split_channels();
+    /// assert_eq!(channels[0].data, vec![0_f32, 2_f32]);
+    /// assert_eq!(channels[1].data, vec![1_f32, 3_f32]);
+    /// ```
+    pub fn split_channels(&self) -> Vec<AudioData> {
+        (0..self.channels)
+            .filter_map(|index| self.channel(index).ok())
+            .collect()
+    }
+
+    /// Reduce the audio data to mono, either by keeping a single channel or by combining all channels into their
+    /// average, see [`AudioData::convert_to_mono`].
+    ///
+    /// Returns [`Error::OutOfRange`] if the audio has no channel with the selected index.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel`: The index of the channel to keep, or `None` to combine all channels.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys_audio::audio::AudioData;
+    /// let mut audio = AudioData {
+    ///     data: vec![0_f32, 1_f32, 2_f32, 3_f32],
+    ///     channels: 2,
+    ///     sample_rate: 44100,
+    /// };
+    /// assert_eq!(audio.clone().select_channel(None).unwrap().data, vec![0.5_f32, 2.5_f32]);
+    /// assert_eq!(audio.select_channel(Some(0)).unwrap().data, vec![0_f32, 2_f32]);
+    /// ```
+    pub fn select_channel(&mut self, channel: Option<u8>) -> Result<&mut Self, Error> {
+        match channel {
+            Some(index) => *self = self.channel(index)?,
+            None => {
+                self.convert_to_mono();
+            }
+        }
+
+        Ok(self)
+    }
+
     /// Downsample the audio data to a lower sample rate.
     ///
     /// Does nothing if the sample rate is the same as the current one.
     ///
     /// Returns an error if the new sample rate is not a divisor of the current sample rate.
     ///
-    /// This uses the nearest-neighbour algorithm, which works well when downsampling by an
-    /// integer factor.
+    /// This uses the nearest-neighbour algorithm without filtering, so frequencies above the new Nyquist frequency
+    /// alias into the result. Use [`AudioData::resample`] to filter them out first.
     ///
     /// # Arguments
     ///
@@ -119,7 +271,87 @@ impl AudioData {
         Ok(self)
     }
 
-    /// Trim silent parts of the audio from the start and the end.
+    /// Resample the audio data to another sample rate with the given quality.
+    ///
+    /// Does nothing if the sample rate is the same as the current one.
+    ///
+    /// Unlike [`AudioData::downsample`], all qualities except [`ResampleQuality::Decimate`] low-pass filter the audio,
+    /// which avoids aliasing artifacts that hurt transcription, and support any pair of sample rates.
+    ///
+    /// # Arguments
+    ///
+    /// * `sample_rate`: The new sample rate.
+    /// * `quality`: How to resample, see [`ResampleQuality`].
+    ///
+    /// # Examples
+    ///
+    /// A 10 kHz tone cannot be represented at 16 kHz. Decimating it aliases it to 6 kHz, while filtering removes it:
+    ///
+    /// ```
+    /// # use std::f32::consts::PI;
+    /// # use varys_audio::audio::AudioData;
+    /// # use varys_audio::resample::ResampleQuality;
+    /// let tone = AudioData {
+    ///     data: (0..48000)
+    ///         .map(|i| (2.0 * PI * 10000.0 * i as f32 / 48000.0).sin())
+    ///         .collect(),
+    ///     channels: 1,
+    ///     sample_rate: 48000,
+    /// };
+    /// let level = |audio: &AudioData| audio.data.iter().map(|sample| sample.abs()).sum::<f32>() / 16000.0;
+    ///
+    /// let mut decimated = tone.clone();
+    /// decimated.resample(16000, ResampleQuality::Decimate).unwrap();
+    /// let mut filtered = tone.clone();
+    /// filtered.resample(16000, ResampleQuality::High).unwrap();
+    ///
+    /// assert_eq!(filtered.data.len(), 16000);
+    /// assert!(level(&decimated) > 0.5);
+    /// assert!(level(&filtered) < 0.05);
+    /// ```
+    pub fn resample(
+        &mut self,
+        sample_rate: u32,
+        quality: ResampleQuality,
+    ) -> Result<&mut Self, Error> {
+        if quality == ResampleQuality::Decimate {
+            return self.downsample(sample_rate);
+        }
+
+        if self.sample_rate == sample_rate || self.data.is_empty() {
+            self.sample_rate = sample_rate;
+            return Ok(self);
+        }
+
+        debug!(
+            "Resampling {}Hz to {}Hz with {quality:?} quality...",
+            self.sample_rate, sample_rate
+        );
+
+        let channel_count = self.channels as usize;
+        let channels: Vec<Vec<f32>> = (0..channel_count)
+            .map(|channel| {
+                self.data
+                    .iter()
+                    .skip(channel)
+                    .step_by(channel_count)
+                    .copied()
+                    .collect()
+            })
+            .collect();
+        let resampled =
+            resample::resample_channels(&channels, self.sample_rate, sample_rate, quality)?;
+
+        let frames = resampled.first().map_or(0, Vec::len);
+        self.data = (0..frames)
+            .flat_map(|frame| resampled.iter().map(move |channel| channel[frame]))
+            .collect();
+        self.sample_rate = sample_rate;
+
+        Ok(self)
+    }
+
+    /// Trim silent parts of the audio from the start and the end, keeping the default [`SilencePadding`].
     ///
     /// If there is no audio above the threshold, the data is cleared.
     ///
@@ -154,40 +386,79 @@ impl AudioData {
     /// assert_eq!(audio.trim_silence(1_f32).data, expected_data);
     /// ```
     pub fn trim_silence(&mut self, threshold: f32) -> &mut Self {
-        debug!("Trimming silence above a threshold of {}...", threshold);
+        self.trim_silence_with_padding(threshold, SilencePadding::default())
+    }
 
-        // find the index of the first sample that is above the threshold
-        let from = self
-            .data
-            .iter()
-            .enumerate()
-            .find(|(_, &sample)| sample >= threshold)
-            .map(|(i, _)| i);
-
-        if let Some(first) = from {
-            // there is at least one sample above the threshold
-            // find the index of the last sample that is above the threshold
-            // we can unwrap because we know there is at least one sample above the threshold
-            let last = self
-                .data
-                .iter()
-                .enumerate()
-                .rev()
-                .find(|(_, &sample)| sample >= threshold)
-                .map(|(i, _)| i)
-                .unwrap();
-            // add padding
-            let first = first.saturating_sub(TRIM_SILENCE_PADDING);
-            let last = min(
-                last.saturating_add(TRIM_SILENCE_PADDING),
-                self.data.len() - 1,
-            );
-            // trim the data
-            self.data = self.data[first..=last].to_vec();
-        } else {
+    /// Trim silent parts of the audio from the start and the end, keeping the given amount of silence before the first
+    /// and after the last sound.
+    ///
+    /// A frame is silent if the magnitude of every one of its samples is below the threshold, so all channels are
+    /// trimmed alike. If there is no audio above the threshold, the data is cleared.
+    ///
+    /// # Arguments
+    ///
+    /// * `threshold`: Determines what samples are considered silent.
+    /// * `padding`: How much silence to keep before and after the sound.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use varys_audio::audio::{AudioData, SilencePadding};
+    /// // one second of stereo audio with sound in the second channel from 0.5s to 0.6s
+    /// let mut audio = AudioData {
+    ///     data: (0..2000)
+    ///         .map(|index| match index {
+    ///             1001..=1199 if index % 2 == 1 => -0.5,
+    ///             _ => 0.0,
+    ///         })
+    ///         .collect(),
+    ///     channels: 2,
+    ///     sample_rate: 1000,
+    /// };
+    ///
+    /// audio.trim_silence_with_padding(
+    ///     0.1,
+    ///     SilencePadding {
+    ///         leading: Duration::ZERO,
+    ///         trailing: Duration::from_millis(200),
+    ///     },
+    /// );
+    ///
+    /// assert_eq!(audio.duration_ms(), 300);
+    /// assert_eq!(audio.data[..2], [0.0, -0.5]);
+    /// ```
+    pub fn trim_silence_with_padding(
+        &mut self,
+        threshold: f32,
+        padding: SilencePadding,
+    ) -> &mut Self {
+        debug!(
+            "Trimming silence above a threshold of {} with {:?} of padding...",
+            threshold, padding
+        );
+
+        let channels = self.channels.max(1) as usize;
+        let is_sound = |frame: &[f32]| frame.iter().any(|sample| sample.abs() >= threshold);
+        // the indices of the first and last frames that are above the threshold
+        let first = self.data.chunks(channels).position(is_sound);
+        let last = self.data.chunks(channels).rposition(is_sound);
+
+        match first.zip(last) {
+            Some((first, last)) => {
+                let frames = |duration: Duration| {
+                    (duration.as_micros() * self.sample_rate as u128 / 1_000_000) as usize
+                };
+                let start = first.saturating_sub(frames(padding.leading)) * channels;
+                let end = min(
+                    last.saturating_add(frames(padding.trailing) + 1)
+                        .saturating_mul(channels),
+                    self.data.len(),
+                );
+                self.data = self.data[start..end].to_vec();
+            }
             // there are no samples above the threshold
-            // clear the data
-            self.data = Vec::new();
+            None => self.data = Vec::new(),
         }
 
         self
@@ -269,6 +540,242 @@ impl AudioData {
         ))
     }
 
+    /// Find when sound starts in the audio.
+    ///
+    /// The audio is split into windows of [`ONSET_WINDOW_MS`] and the first window whose average amplitude is at least
+    /// the threshold is considered the onset.
+    ///
+    /// # Arguments
+    ///
+    /// * `threshold`: The average amplitude from which a window is considered to contain sound.
+    ///
+    /// Returns the start of the first window with sound in milliseconds, or `None` if the audio is silent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys_audio::audio::AudioData;
+    /// let mut data = vec![0_f32; 4800];
+    /// data.append(&mut vec![0.5_f32; 4800]);
+    /// let audio = AudioData {
+    ///     data,
+    ///     channels: 1,
+    ///     sample_rate: 48000,
+    /// };
+    ///
+    /// assert_eq!(audio.onset_ms(0.1), Some(100));
+    /// assert_eq!(audio.onset_ms(1.0), None);
+    /// ```
+    pub fn onset_ms(&self, threshold: f32) -> Option<i32> {
+        let window_size =
+            self.sample_rate as usize * ONSET_WINDOW_MS / 1000 * self.channels as usize;
+
+        if window_size == 0 {
+            return None;
+        }
+
+        self.data
+            .chunks(window_size)
+            .position(|window| {
+                window.iter().map(|sample| sample.abs()).sum::<f32>() / window.len() as f32
+                    >= threshold
+            })
+            .map(|index| (index * ONSET_WINDOW_MS) as i32)
+    }
+
+    /// Get the level of the loudest sample in dBFS, where `0` is the largest level that can be represented.
+    ///
+    /// Returns `None` if the audio is silent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys_audio::audio::AudioData;
+    /// let audio = AudioData {
+    ///     data: vec![0.1, -0.5, 0.25],
+    ///     channels: 1,
+    ///     sample_rate: 48000,
+    /// };
+    ///
+    /// assert!((audio.peak_dbfs().unwrap() + 6.02).abs() < 0.01);
+    /// ```
+    pub fn peak_dbfs(&self) -> Option<f32> {
+        let peak = self
+            .data
+            .iter()
+            .fold(0_f32, |peak, sample| peak.max(sample.abs()));
+
+        (peak > 0.0).then(|| 20.0 * peak.log10())
+    }
+
+    /// Get the root mean square level of the audio in dBFS, where a full-scale square wave has `0`.
+    ///
+    /// Returns `None` if the audio is silent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys_audio::audio::AudioData;
+    /// let audio = AudioData {
+    ///     data: vec![0.1, -0.1, 0.1, -0.1],
+    ///     channels: 1,
+    ///     sample_rate: 48000,
+    /// };
+    ///
+    /// assert!((audio.rms_dbfs().unwrap() + 20.0).abs() < 0.01);
+    /// ```
+    pub fn rms_dbfs(&self) -> Option<f32> {
+        rms_dbfs(&self.data)
+    }
+
+    /// Scale the audio so its root mean square level is the target level, see [`AudioData::rms_dbfs`].
+    ///
+    /// The audio is amplified at most until its loudest sample reaches full scale, so it does not clip. Silent audio is
+    /// not changed.
+    ///
+    /// # Arguments
+    ///
+    /// * `target_dbfs`: The root mean square level to reach in dBFS.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys_audio::audio::AudioData;
+    /// let mut audio = AudioData {
+    ///     data: vec![0.01, -0.01, 0.01, -0.01],
+    ///     channels: 1,
+    ///     sample_rate: 48000,
+    /// };
+    ///
+    /// audio.normalise(-20.0);
+    /// assert!((audio.rms_dbfs().unwrap() + 20.0).abs() < 0.01);
+    ///
+    /// // a louder target would clip the audio
+    /// audio.normalise(6.0);
+    /// assert!(audio.peak_dbfs().unwrap().abs() < 0.01);
+    /// ```
+    pub fn normalise(&mut self, target_dbfs: f32) -> &mut Self {
+        let (Some(rms_dbfs), Some(peak_dbfs)) = (self.rms_dbfs(), self.peak_dbfs()) else {
+            return self;
+        };
+        let gain_db = (target_dbfs - rms_dbfs).min(-peak_dbfs);
+
+        debug!("Normalising audio to {target_dbfs}dBFS with a gain of {gain_db:.1}dB...");
+
+        let gain = gain_from_db(gain_db);
+        self.data.iter_mut().for_each(|sample| *sample *= gain);
+        self
+    }
+
+    /// Continuously adjust the gain of the audio so every window of it has about the target level, which evens out
+    /// recordings made at different microphone gains and distances.
+    ///
+    /// The gain of every window is computed from the window itself and is at most [`MAX_GAIN_DB`]. Windows quieter than
+    /// [`GAIN_GATE_DBFS`], like pauses, keep the gain of the window before them, so noise is not amplified. The gain
+    /// changes linearly to avoid clicks: it drops over the window before a louder one, so onsets are not clipped, and
+    /// rises over a quieter window itself. Samples are limited to full scale.
+    ///
+    /// # Arguments
+    ///
+    /// * `target_dbfs`: The root mean square level to reach in every window in dBFS.
+    /// * `window_ms`: The length of the windows in milliseconds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys_audio::audio::AudioData;
+    /// // a quiet second followed by a loud one
+    /// let mut data = vec![0.01_f32; 48000];
+    /// data.extend(vec![0.5_f32; 48000]);
+    /// let mut audio = AudioData {
+    ///     data,
+    ///     channels: 1,
+    ///     sample_rate: 48000,
+    /// };
+    ///
+    /// audio.gain_control(-20.0, 100);
+    ///
+    /// assert!((audio.data[24000] - 0.1).abs() < 0.001);
+    /// // the onset of the loud second already has its gain
+    /// assert!((audio.data[48000] - 0.1).abs() < 0.001);
+    /// assert!((audio.data[72000] - 0.1).abs() < 0.001);
+    /// ```
+    pub fn gain_control(&mut self, target_dbfs: f32, window_ms: usize) -> &mut Self {
+        let window_size = self.sample_rate as usize * window_ms / 1000 * self.channels as usize;
+        if window_size == 0 {
+            return self;
+        }
+
+        debug!("Controlling the gain of audio towards {target_dbfs}dBFS...");
+
+        let mut gains: Vec<f32> = Vec::with_capacity(self.data.len().div_ceil(window_size));
+        for window in self.data.chunks(window_size) {
+            let gain = match rms_dbfs(window).filter(|level| *level >= GAIN_GATE_DBFS) {
+                Some(level) => gain_from_db((target_dbfs - level).min(MAX_GAIN_DB)),
+                None => gains.last().copied().unwrap_or(1.0),
+            };
+            gains.push(gain);
+        }
+
+        for (index, window) in self.data.chunks_mut(window_size).enumerate() {
+            let gain = gains[index];
+            // lower gains are reached before their window starts and higher ones over their window
+            let start_gain = index
+                .checked_sub(1)
+                .map_or(gain, |previous| gains[previous].min(gain));
+            let end_gain = gains.get(index + 1).map_or(gain, |next| next.min(gain));
+            let length = window.len() as f32;
+
+            for (index, sample) in window.iter_mut().enumerate() {
+                let ramp = start_gain + (end_gain - start_gain) * index as f32 / length;
+                *sample = (*sample * ramp).clamp(-1.0, 1.0);
+            }
+        }
+
+        self
+    }
+
+    /// Get the energy in every octave band of consecutive windows of the audio, see [`BandEnergies`].
+    ///
+    /// Several channels are combined into their average first.
+    ///
+    /// # Arguments
+    ///
+    /// * `window_ms`: The length of the windows in milliseconds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::f32::consts::PI;
+    /// # use varys_audio::audio::AudioData;
+    /// let hum = AudioData {
+    ///     data: (0..48000)
+    ///         .map(|index| 0.5 * (2. * PI * 100. * index as f32 / 48000.).sin())
+    ///         .collect(),
+    ///     channels: 1,
+    ///     sample_rate: 48000,
+    /// };
+    /// let windows = hum.band_energies(20);
+    ///
+    /// assert_eq!(windows.len(), 50);
+    /// assert!(windows.iter().all(|energies| energies.speech_share() < 0.01));
+    /// ```
+    pub fn band_energies(&self, window_ms: usize) -> Vec<BandEnergies> {
+        let window_size = self.sample_rate as usize * window_ms / 1000;
+        if window_size == 0 {
+            return Vec::new();
+        }
+
+        let mut mono = self.clone();
+        mono.convert_to_mono();
+        let mut analyser = BandAnalyser::new(window_size, self.sample_rate);
+
+        mono.data
+            .chunks(window_size)
+            .map(|window| analyser.analyse(window))
+            .collect()
+    }
+
     /// Get the duration of the audio in milliseconds.
     ///
     /// # Examples
@@ -319,3 +826,19 @@ impl From<AudioData> for Vec<f32> {
         value.data
     }
 }
+
+/// Get the root mean square level of samples in dBFS, or `None` if they are silent.
+fn rms_dbfs(samples: &[f32]) -> Option<f32> {
+    if samples.is_empty() {
+        return None;
+    }
+
+    let mean_square =
+        samples.iter().map(|sample| sample * sample).sum::<f32>() / samples.len() as f32;
+
+    (mean_square > 0.0).then(|| 10.0 * mean_square.log10())
+}
+
+fn gain_from_db(gain_db: f32) -> f32 {
+    10_f32.powf(gain_db / 20.0)
+}
@@ -0,0 +1,169 @@
+use std::f32::consts::{PI, SQRT_2};
+use std::sync::Arc;
+
+use rustfft::num_complex::Complex;
+use rustfft::{Fft, FftPlanner};
+
+/// The number of octave bands the spectrum is summarised in.
+pub const BAND_COUNT: usize = 8;
+/// The centre frequencies of the octave bands in Hz. Every band reaches from its centre frequency divided by `√2` to
+/// its centre frequency multiplied by `√2`.
+pub const OCTAVE_BANDS: [f32; BAND_COUNT] = [63., 125., 250., 500., 1000., 2000., 4000., 8000.];
+/// The lowest centre frequency of the bands that carry most of the energy of speech in Hz.
+pub const SPEECH_LOW_FREQUENCY: f32 = 250.;
+/// The highest centre frequency of the bands that carry most of the energy of speech in Hz.
+pub const SPEECH_HIGH_FREQUENCY: f32 = 4000.;
+
+/// The energy of a window of samples in every octave band of [`OCTAVE_BANDS`].
+///
+/// The energy of a band is the mean square of the part of the signal within it, so a sine with amplitude `a` has the
+/// energy `a² / 2` in its band.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BandEnergies(pub [f32; BAND_COUNT]);
+
+impl BandEnergies {
+    /// The energy in all bands.
+    pub fn total(&self) -> f32 {
+        self.0.iter().sum()
+    }
+
+    /// The share of the energy in the bands with a centre frequency from `low` to `high` Hz, from `0` to `1`.
+    ///
+    /// Returns `0` if there is no energy in any band.
+    ///
+    /// # Arguments
+    ///
+    /// * `low`: The lowest centre frequency of the bands to include.
+    /// * `high`: The highest centre frequency of the bands to include.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys_audio::audio::bands::BandEnergies;
+    /// let energies = BandEnergies([4., 2., 1., 1., 1., 1., 0., 0.]);
+    ///
+    /// assert_eq!(energies.share(250., 4000.), 0.4);
+    /// assert_eq!(BandEnergies::default().share(250., 4000.), 0.);
+    /// ```
+    pub fn share(&self, low: f32, high: f32) -> f32 {
+        let total = self.total();
+        if total <= 0. {
+            return 0.;
+        }
+
+        OCTAVE_BANDS
+            .iter()
+            .zip(self.0)
+            .filter(|(frequency, _)| (low..=high).contains(*frequency))
+            .map(|(_, energy)| energy)
+            .sum::<f32>()
+            / total
+    }
+
+    /// The share of the energy in the bands of speech, from [`SPEECH_LOW_FREQUENCY`] to [`SPEECH_HIGH_FREQUENCY`].
+    ///
+    /// Low-frequency hum, like that of a smart speaker, has almost all of its energy below these bands.
+    pub fn speech_share(&self) -> f32 {
+        self.share(SPEECH_LOW_FREQUENCY, SPEECH_HIGH_FREQUENCY)
+    }
+
+    /// The centre frequency of the band with the most energy, or `None` if there is no energy in any band.
+    pub fn dominant_frequency(&self) -> Option<f32> {
+        OCTAVE_BANDS
+            .iter()
+            .zip(self.0)
+            .filter(|(_, energy)| *energy > 0.)
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(frequency, _)| *frequency)
+    }
+}
+
+/// Computes the [`BandEnergies`] of windows of mono samples.
+///
+/// The FFT and buffers are only created once, so an analyser can be used on the audio thread of a recording.
+pub struct BandAnalyser {
+    fft: Arc<dyn Fft<f32>>,
+    /// The Hann window the samples are weighted with.
+    window: Vec<f32>,
+    /// The range of FFT bins of every band.
+    bands: [(usize, usize); BAND_COUNT],
+    /// Scales the power of the bins to the mean square of the signal.
+    scale: f32,
+    buffer: Vec<Complex<f32>>,
+}
+
+impl BandAnalyser {
+    /// Create an analyser for windows of a fixed size.
+    ///
+    /// Bands above the Nyquist frequency of the sample rate are always empty.
+    ///
+    /// # Arguments
+    ///
+    /// * `window_size`: How many samples are analysed at once.
+    /// * `sample_rate`: The sample rate of the samples.
+    pub fn new(window_size: usize, sample_rate: u32) -> Self {
+        let window_size = window_size.max(1);
+        let window: Vec<f32> = (0..window_size)
+            .map(|index| 0.5 - 0.5 * (2. * PI * index as f32 / window_size as f32).cos())
+            .collect();
+        let window_energy = window.iter().map(|weight| weight * weight).sum::<f32>();
+        let bin = |frequency: f32| {
+            ((frequency * window_size as f32 / sample_rate as f32).round() as usize)
+                .min(window_size / 2 + 1)
+        };
+        let bands =
+            OCTAVE_BANDS.map(|frequency| (bin(frequency / SQRT_2), bin(frequency * SQRT_2)));
+
+        Self {
+            fft: FftPlanner::new().plan_fft_forward(window_size),
+            window,
+            bands,
+            // the positive frequencies hold half of the power of a real signal
+            scale: 2. / (window_size as f32 * window_energy.max(f32::EPSILON)),
+            buffer: vec![Complex::default(); window_size],
+        }
+    }
+
+    /// How many samples are analysed at once.
+    pub fn window_size(&self) -> usize {
+        self.window.len()
+    }
+
+    /// Compute the energy of a window of mono samples in every band.
+    ///
+    /// Windows shorter than [`BandAnalyser::window_size`] are padded with silence and longer ones are cut off.
+    ///
+    /// # Arguments
+    ///
+    /// * `samples`: The samples of the window.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::f32::consts::PI;
+    /// # use varys_audio::audio::bands::BandAnalyser;
+    /// let sine: Vec<f32> = (0..1024)
+    ///     .map(|index| 0.5 * (2. * PI * 1000. * index as f32 / 48000.).sin())
+    ///     .collect();
+    /// let energies = BandAnalyser::new(1024, 48000).analyse(&sine);
+    ///
+    /// assert!((energies.total() - 0.125).abs() < 0.01);
+    /// assert_eq!(energies.dominant_frequency(), Some(1000.));
+    /// assert!(energies.speech_share() > 0.99);
+    /// ```
+    pub fn analyse(&mut self, samples: &[f32]) -> BandEnergies {
+        for (index, value) in self.buffer.iter_mut().enumerate() {
+            let sample = samples.get(index).copied().unwrap_or_default();
+            *value = Complex::new(sample * self.window[index], 0.);
+        }
+        self.fft.process(&mut self.buffer);
+
+        BandEnergies(self.bands.map(|(start, end)| {
+            self.buffer[start..end]
+                .iter()
+                .map(|bin| bin.norm_sqr())
+                .sum::<f32>()
+                * self.scale
+        }))
+    }
+}
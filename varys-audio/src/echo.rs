@@ -0,0 +1,239 @@
+use log::debug;
+use rustfft::num_complex::Complex;
+use rustfft::FftPlanner;
+
+use crate::audio::AudioData;
+use crate::error::Error;
+use crate::resample::ResampleQuality;
+
+/// The default length of the echo path in milliseconds, which covers the direct sound and the early reflections from
+/// the speaker to the microphone.
+pub const DEFAULT_FILTER_MS: u32 = 8;
+/// The default of how far the echo may lag behind or lead the reference in milliseconds, e.g. because the microphone
+/// and the reference were started at slightly different times.
+pub const DEFAULT_MAX_DELAY_MS: u32 = 300;
+/// The step size of the adaptive filter, from `0` to `2`.
+///
+/// Smaller steps adapt more slowly, but distort sound that is not part of the echo less.
+const STEP_SIZE: f32 = 0.1;
+/// Keeps the step of the adaptive filter finite while the reference is silent.
+const REGULARISATION: f32 = 1e-6;
+/// How many milliseconds of the echo path to model before the estimated delay, so small errors of the estimate are
+/// covered.
+const PRE_DELAY_MS: u32 = 1;
+
+/// Removes the echo of a reference signal from a recording, e.g. the sound of the speaker that plays the queries from
+/// the recording of the microphone.
+///
+/// The delay of the echo is first estimated by cross-correlating the recording with the reference. A normalised least
+/// mean squares (NLMS) filter then learns the echo path from the aligned reference and subtracts its estimate of the
+/// echo, so only the sound that is not part of the reference is left, e.g. the voice of the assistant.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EchoCanceller {
+    /// The length of the echo path in milliseconds.
+    pub filter_ms: u32,
+    /// How far the echo may lag behind or lead the reference in milliseconds.
+    pub max_delay_ms: u32,
+}
+
+impl Default for EchoCanceller {
+    fn default() -> Self {
+        EchoCanceller {
+            filter_ms: DEFAULT_FILTER_MS,
+            max_delay_ms: DEFAULT_MAX_DELAY_MS,
+        }
+    }
+}
+
+impl EchoCanceller {
+    /// Remove the echo of a reference from a recording.
+    ///
+    /// The reference is converted to mono and resampled to the sample rate of the recording. Every channel of the
+    /// recording is cancelled separately. A silent reference leaves the recording unchanged.
+    ///
+    /// # Arguments
+    ///
+    /// * `recording`: The recording that contains the echo.
+    /// * `reference`: The signal whose echo is removed, e.g. recorded from a loopback device.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::f32::consts::PI;
+    /// # use varys_audio::audio::AudioData;
+    /// # use varys_audio::echo::EchoCanceller;
+    /// let mut seed = 1_u32;
+    /// let mut noise = || {
+    ///     seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+    ///     seed as f32 / u32::MAX as f32 - 0.5
+    /// };
+    /// let reference = AudioData {
+    ///     data: (0..16000).map(|_| noise()).collect(),
+    ///     channels: 1,
+    ///     sample_rate: 16000,
+    /// };
+    /// let voice: Vec<f32> = (0..16000)
+    ///     .map(|index| 0.1 * (2.0 * PI * 300.0 * index as f32 / 16000.0).sin())
+    ///     .collect();
+    ///
+    /// // the reference is heard 50ms late with a reflection 2ms later
+    /// let mut recording = AudioData {
+    ///     data: voice.clone(),
+    ///     channels: 1,
+    ///     sample_rate: 16000,
+    /// };
+    /// for (index, sample) in reference.data.iter().enumerate() {
+    ///     if let Some(echo) = recording.data.get_mut(index + 800) {
+    ///         *echo += 0.5 * sample;
+    ///     }
+    ///     if let Some(echo) = recording.data.get_mut(index + 832) {
+    ///         *echo += 0.2 * sample;
+    ///     }
+    /// }
+    ///
+    /// let cancelled = EchoCanceller::default().cancel(&recording, &reference).unwrap();
+    ///
+    /// let energy = |data: &[f32]| data.iter().map(|sample| sample * sample).sum::<f32>();
+    /// let residual: Vec<f32> = cancelled.data[8000..]
+    ///     .iter()
+    ///     .zip(&voice[8000..])
+    ///     .map(|(cancelled, voice)| cancelled - voice)
+    ///     .collect();
+    /// let echo: Vec<f32> = recording.data[8000..]
+    ///     .iter()
+    ///     .zip(&voice[8000..])
+    ///     .map(|(recorded, voice)| recorded - voice)
+    ///     .collect();
+    ///
+    /// assert!(energy(&residual) < 0.05 * energy(&echo));
+    /// ```
+    pub fn cancel(&self, recording: &AudioData, reference: &AudioData) -> Result<AudioData, Error> {
+        let mut reference = reference.clone();
+        reference
+            .convert_to_mono()
+            .resample(recording.sample_rate, ResampleQuality::default())?;
+        if reference.data.iter().all(|sample| *sample == 0.0) {
+            debug!("The reference is silent, there is no echo to cancel");
+            return Ok(recording.clone());
+        }
+
+        let sample_rate = recording.sample_rate as usize;
+        let mut mono = recording.clone();
+        mono.convert_to_mono();
+        let delay = estimate_delay(
+            &mono.data,
+            &reference.data,
+            self.max_delay_ms as usize * sample_rate / 1000,
+        ) - (PRE_DELAY_MS as usize * sample_rate / 1000) as isize;
+
+        debug!("Cancelling the echo of the reference with a delay of {delay} samples");
+
+        let aligned: Vec<f32> = (0..mono.data.len() as isize)
+            .map(|index| {
+                usize::try_from(index - delay)
+                    .ok()
+                    .and_then(|index| reference.data.get(index))
+                    .copied()
+                    .unwrap_or_default()
+            })
+            .collect();
+        let filter_length = (self.filter_ms as usize * sample_rate / 1000).max(1);
+        let channels: Vec<Vec<f32>> = recording
+            .split_channels()
+            .iter()
+            .map(|channel| cancel_aligned(&channel.data, &aligned, filter_length))
+            .collect();
+
+        let frames = channels.first().map_or(0, Vec::len);
+        Ok(AudioData {
+            data: (0..frames)
+                .flat_map(|frame| channels.iter().map(move |channel| channel[frame]))
+                .collect(),
+            channels: recording.channels,
+            sample_rate: recording.sample_rate,
+        })
+    }
+}
+
+/// Estimate by how many samples the echo of a reference lags behind it in a recording, which is negative if the echo
+/// leads the reference.
+///
+/// # Arguments
+///
+/// * `recording`: The mono recording that contains the echo.
+/// * `reference`: The mono reference with the same sample rate.
+/// * `max_delay`: The largest delay in samples to consider in either direction.
+fn estimate_delay(recording: &[f32], reference: &[f32], max_delay: usize) -> isize {
+    let size = (recording.len() + reference.len()).next_power_of_two();
+    let mut planner = FftPlanner::<f32>::new();
+    let forward = planner.plan_fft_forward(size);
+    let inverse = planner.plan_fft_inverse(size);
+
+    let spectrum = |data: &[f32]| {
+        let mut buffer: Vec<Complex<f32>> = data
+            .iter()
+            .map(|&sample| Complex::new(sample, 0.0))
+            .collect();
+        buffer.resize(size, Complex::default());
+        forward.process(&mut buffer);
+        buffer
+    };
+    let reference_spectrum = spectrum(reference);
+    let mut correlation = spectrum(recording);
+    correlation
+        .iter_mut()
+        .zip(&reference_spectrum)
+        .for_each(|(bin, reference_bin)| *bin *= reference_bin.conj());
+    inverse.process(&mut correlation);
+
+    let max_delay = max_delay.min(size / 2) as isize;
+    (-max_delay..=max_delay)
+        .max_by(|a, b| {
+            let value = |delay: &isize| correlation[delay.rem_euclid(size as isize) as usize].re;
+            value(a).abs().total_cmp(&value(b).abs())
+        })
+        .unwrap_or_default()
+}
+
+/// Subtract the echo of a reference that is aligned with a recording with an NLMS filter.
+///
+/// Returns the recording without the echo.
+///
+/// # Arguments
+///
+/// * `recording`: The mono recording that contains the echo.
+/// * `reference`: The aligned mono reference, at least as long as the recording.
+/// * `filter_length`: The length of the echo path in samples.
+fn cancel_aligned(recording: &[f32], reference: &[f32], filter_length: usize) -> Vec<f32> {
+    let mut weights = vec![0.0_f32; filter_length];
+    let mut energy = 0.0_f32;
+
+    recording
+        .iter()
+        .enumerate()
+        .map(|(index, sample)| {
+            energy += reference[index] * reference[index];
+            if let Some(leaving) = index.checked_sub(filter_length) {
+                energy -= reference[leaving] * reference[leaving];
+            }
+            energy = energy.max(0.0);
+
+            // the most recent sample of the reference is weighted by the first tap
+            let window = &reference[(index + 1).saturating_sub(filter_length)..=index];
+            let echo: f32 = weights
+                .iter()
+                .zip(window.iter().rev())
+                .map(|(weight, sample)| weight * sample)
+                .sum();
+            let error = sample - echo;
+
+            let step = STEP_SIZE * error / (energy + REGULARISATION);
+            weights
+                .iter_mut()
+                .zip(window.iter().rev())
+                .for_each(|(weight, sample)| *weight += step * sample);
+
+            error
+        })
+        .collect()
+}
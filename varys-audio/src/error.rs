@@ -8,6 +8,10 @@ pub enum Error {
     OutOfRange,
     #[error("Audio device not found")]
     AudioDeviceNotFound,
+    #[error("The audio device was disconnected and could not be found again after {0} attempts")]
+    DeviceDisconnected(u32),
+    #[error("The audio device was reconnected with a sample rate of {1} Hz instead of {0} Hz")]
+    SampleRateChanged(u32, u32),
     #[error("Audio device does not support required configuration")]
     ConfigurationNotSupported,
     #[error("Tried to access audio data while recording still running")]
@@ -16,22 +20,34 @@ pub enum Error {
     RecordingFailed,
     #[error("Encountered timeout while recording audio")]
     RecordingTimeout,
+    #[error("No ambient noise was recorded during calibration")]
+    CalibrationFailed,
     #[error(
         "Downsampling requires the target sample rate to be a divisor of the current sample rate"
     )]
     NoDivisor,
+    #[error("Resampling failed: {0}")]
+    Resampling(String),
     #[error(
         "Opus does not support sample rate {0}hz. Use one of 8000, 12000, 16000, 24000 or 48000"
     )]
     UnsupportedSampleRate(u32),
     #[error("Opus does not support more than two channels (got audio data with {0} channels)")]
     UnsupportedChannelCount(u16),
+    #[error("FLAC does not support more than eight channels (got audio data with {0} channels)")]
+    UnsupportedFlacChannelCount(u16),
+    #[error("Reading .{0} files is not supported")]
+    UnsupportedFileType(String),
     #[error("OPUS error: {0}")]
     Opus(String),
     #[error("CPAL error: {0}")]
     Cpal(String),
     #[error("Hound error: {0}")]
     Hound(String),
+    #[error("Ogg error: {0}")]
+    Ogg(String),
+    #[error("Invalid voice profile: {0}")]
+    InvalidVoiceProfile(String),
 
     // tts
     #[error("Required feature {0} is unsupported")]
@@ -54,7 +70,7 @@ pub enum Error {
     Whisper(String),
 }
 
-#[cfg(target_os = "macos")]
+#[cfg(all(feature = "tts", target_os = "macos"))]
 impl From<tts::Error> for Error {
     fn from(value: tts::Error) -> Self {
         match value {
@@ -66,6 +82,7 @@ impl From<tts::Error> for Error {
     }
 }
 
+#[cfg(any(feature = "listen", feature = "tts"))]
 impl From<cpal::BuildStreamError> for Error {
     fn from(value: cpal::BuildStreamError) -> Self {
         match value {
@@ -76,6 +93,7 @@ impl From<cpal::BuildStreamError> for Error {
     }
 }
 
+#[cfg(any(feature = "listen", feature = "tts"))]
 impl From<cpal::SupportedStreamConfigsError> for Error {
     fn from(value: cpal::SupportedStreamConfigsError) -> Self {
         match value {
@@ -99,6 +117,7 @@ impl From<audiopus::Error> for Error {
     }
 }
 
+#[cfg(any(feature = "listen", feature = "tts"))]
 impl From<cpal::PlayStreamError> for Error {
     fn from(value: cpal::PlayStreamError) -> Self {
         match value {
@@ -108,6 +127,18 @@ impl From<cpal::PlayStreamError> for Error {
     }
 }
 
+impl From<rubato::ResampleError> for Error {
+    fn from(value: rubato::ResampleError) -> Self {
+        Error::Resampling(value.to_string())
+    }
+}
+
+impl From<rubato::ResamplerConstructionError> for Error {
+    fn from(value: rubato::ResamplerConstructionError) -> Self {
+        Error::Resampling(value.to_string())
+    }
+}
+
 impl From<hound::Error> for Error {
     fn from(value: hound::Error) -> Self {
         match value {
@@ -117,6 +148,16 @@ impl From<hound::Error> for Error {
     }
 }
 
+impl From<ogg::OggReadError> for Error {
+    fn from(value: ogg::OggReadError) -> Self {
+        match value {
+            ogg::OggReadError::ReadError(err) => err.into(),
+            _ => Error::Ogg(value.to_string()),
+        }
+    }
+}
+
+#[cfg(feature = "stt")]
 impl From<whisper_rs::WhisperError> for Error {
     fn from(value: whisper_rs::WhisperError) -> Self {
         match value {
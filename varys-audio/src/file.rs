@@ -1,21 +1,25 @@
 use std::ffi::OsStr;
-use std::fs::File;
-use std::path::Path;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
 
+use audiopus::coder::Decoder;
+use audiopus::{Channels, MutSignals, SampleRate};
 use hound::WavSpec;
 use log::debug;
-use ogg::{PacketWriteEndInfo, PacketWriter};
+use ogg::{PacketReader, PacketWriteEndInfo, PacketWriter};
 use rand::RngCore;
 
 use crate::audio;
 use crate::audio::AudioData;
 use crate::error::Error;
+use crate::flac;
 
 #[derive(Default)]
 pub enum AudioFileType {
     #[default]
     Wav,
     Opus,
+    Flac,
 }
 
 impl From<&Path> for AudioFileType {
@@ -24,6 +28,7 @@ impl From<&Path> for AudioFileType {
             return match extension {
                 "wav" => AudioFileType::Wav,
                 "opus" => AudioFileType::Opus,
+                "flac" => AudioFileType::Flac,
                 _ => AudioFileType::default(),
             };
         }
@@ -53,14 +58,156 @@ impl From<&Path> for AudioFileType {
 /// };
 /// write_audio(Path::new("audio.wav"), &audio).unwrap();
 /// write_audio(Path::new("audio.opus"), &audio).unwrap();
+/// write_audio(Path::new("audio.flac"), &audio).unwrap();
 /// ```
 pub fn write_audio(file_path: &Path, audio: &AudioData) -> Result<(), Error> {
     match AudioFileType::from(file_path) {
         AudioFileType::Wav => write_wav(file_path, audio),
         AudioFileType::Opus => write_opus(file_path, audio),
+        AudioFileType::Flac => write_flac(file_path, audio),
     }
 }
 
+/// Save every channel of audio data to its own mono file next to `file_path`, see [`channel_path`].
+///
+/// Returns the paths of the files, in the order of the channels.
+///
+/// # Arguments
+///
+/// * `file_path`: The path of the file with all channels, which is not written.
+/// * `audio`: The audio data to save.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use std::path::Path;
+/// # use varys_audio::file::write_channels;
+/// # use varys_audio::audio::AudioData;
+/// let audio = AudioData {
+///     data: vec![0_f32, 1_f32, 2_f32, 3_f32],
+///     channels: 2,
+///     sample_rate: 48000,
+/// };
+/// let paths = write_channels(Path::new("response.opus"), &audio).unwrap();
+/// assert_eq!(paths[1], Path::new("response-ch1.opus"));
+/// ```
+pub fn write_channels(file_path: &Path, audio: &AudioData) -> Result<Vec<PathBuf>, Error> {
+    audio
+        .split_channels()
+        .iter()
+        .enumerate()
+        .map(|(index, channel)| {
+            let path = channel_path(file_path, index);
+            write_audio(&path, channel)?;
+
+            Ok(path)
+        })
+        .collect()
+}
+
+/// The path of the file a single channel of the audio at `file_path` is saved to, with `-ch<index>` appended to its
+/// name.
+///
+/// # Arguments
+///
+/// * `file_path`: The path of the file with all channels.
+/// * `index`: The index of the channel, starting at `0`.
+///
+/// # Examples
+///
+/// ```
+/// # use std::path::Path;
+/// # use varys_audio::file::channel_path;
+/// assert_eq!(
+///     channel_path(Path::new("data/response.opus"), 0),
+///     Path::new("data/response-ch0.opus")
+/// );
+/// ```
+pub fn channel_path(file_path: &Path, index: usize) -> PathBuf {
+    let stem = file_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+    let name = match file_path.extension() {
+        Some(extension) => format!("{stem}-ch{index}.{}", extension.to_string_lossy()),
+        None => format!("{stem}-ch{index}"),
+    };
+
+    file_path.with_file_name(name)
+}
+
+/// Read audio data from a file determined by the file extension.
+///
+/// Returns an error if the file could not be read or decoded, or if it is a `.flac` file, which can only be written.
+///
+/// # Arguments
+///
+/// * `file_path`: The file to read.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use std::path::Path;
+/// # use varys_audio::file::read_audio;
+/// let audio = read_audio(Path::new("query.wav")).unwrap();
+/// let audio = read_audio(Path::new("query.opus")).unwrap();
+/// ```
+pub fn read_audio(file_path: &Path) -> Result<AudioData, Error> {
+    match AudioFileType::from(file_path) {
+        AudioFileType::Wav => read_wav(file_path),
+        AudioFileType::Opus => read_opus(file_path),
+        AudioFileType::Flac => Err(Error::UnsupportedFileType(String::from("flac"))),
+    }
+}
+
+/// Read audio data from a `.wav` file with integer or float samples.
+///
+/// Returns an error if the file could not be read.
+///
+/// # Arguments
+///
+/// * `file_path`: The file to read.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use std::path::Path;
+/// # use varys_audio::file::{read_wav, write_wav};
+/// # use varys_audio::audio::AudioData;
+/// let audio = AudioData {
+///     data: vec![0_f32; 48000],
+///     channels: 1,
+///     sample_rate: 48000,
+/// };
+/// write_wav(Path::new("audio.wav"), &audio).unwrap();
+///
+/// let read = read_wav(Path::new("audio.wav")).unwrap();
+/// assert_eq!(read.data.len(), 48000);
+/// ```
+pub fn read_wav(file_path: &Path) -> Result<AudioData, Error> {
+    debug!("Reading .wav file {:?}", file_path);
+
+    let reader = hound::WavReader::open(file_path)?;
+    let spec = reader.spec();
+    let data = match spec.sample_format {
+        hound::SampleFormat::Float => reader.into_samples::<f32>().collect::<Result<_, _>>()?,
+        hound::SampleFormat::Int => {
+            let max = (1_i64 << (spec.bits_per_sample - 1)) as f32;
+
+            reader
+                .into_samples::<i32>()
+                .map(|sample| sample.map(|sample| sample as f32 / max))
+                .collect::<Result<_, _>>()?
+        }
+    };
+
+    Ok(AudioData {
+        data,
+        channels: spec.channels as u8,
+        sample_rate: spec.sample_rate,
+    })
+}
+
 /// Save audio data to a `.wav` file.
 ///
 /// Returns an error if the file could not be written.
@@ -86,7 +233,7 @@ pub fn write_audio(file_path: &Path, audio: &AudioData) -> Result<(), Error> {
 /// ```
 pub fn write_wav(file_path: &Path, audio: &AudioData) -> Result<(), Error> {
     let wav_config = WavSpec {
-        channels: 1,
+        channels: audio.channels.into(),
         sample_rate: audio.sample_rate,
         bits_per_sample: 32,
         sample_format: hound::SampleFormat::Float,
@@ -180,6 +327,104 @@ pub fn write_opus(file_path: &Path, audio: &AudioData) -> Result<(), Error> {
     Ok(())
 }
 
+/// Save audio data losslessly to a `.flac` file, see [`flac::encode`].
+///
+/// Returns an error if the file could not be written or the audio has more than eight channels.
+///
+/// # Arguments
+///
+/// * `file_path`: Where to save the file. The extension `.flac` will be added if it isn't already in the path.
+/// * `audio`: The audio data to save.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use std::path::Path;
+/// # use varys_audio::file::write_flac;
+/// # use varys_audio::audio::AudioData;
+/// let audio = AudioData {
+///     data: vec![0_f32, 0.5_f32, -0.5_f32],
+///     channels: 1,
+///     sample_rate: 48000,
+/// };
+/// write_flac(Path::new("audio.flac"), &audio).unwrap();
+/// ```
+pub fn write_flac(file_path: &Path, audio: &AudioData) -> Result<(), Error> {
+    let mut file_path = file_path.to_owned();
+    file_path.set_extension("flac");
+
+    debug!("Writing .flac file {:?}", file_path);
+
+    fs::write(file_path, flac::encode(audio)?)?;
+
+    Ok(())
+}
+
+/// Read audio data from an `.opus` file written by [`write_opus`].
+///
+/// The padding that was added to the start of the audio during encoding is removed again.
+///
+/// Returns an error if the file could not be read or decoded.
+///
+/// # Arguments
+///
+/// * `file_path`: The path to the file.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use std::path::Path;
+/// # use varys_audio::file::{read_opus, write_opus};
+/// # use varys_audio::audio::AudioData;
+/// let audio = AudioData {
+///     data: vec![0_f32; 48000],
+///     channels: 1,
+///     sample_rate: 48000,
+/// };
+/// write_opus(Path::new("audio.opus"), &audio).unwrap();
+///
+/// let read = read_opus(Path::new("audio.opus")).unwrap();
+/// assert_eq!(read.sample_rate, 48000);
+/// ```
+pub fn read_opus(file_path: &Path) -> Result<AudioData, Error> {
+    debug!("Reading .opus file {:?}", file_path);
+
+    let mut reader = PacketReader::new(File::open(file_path)?);
+    let header = reader.read_packet_expected()?.data;
+    if header.len() < 19 || !header.starts_with(b"OpusHead") {
+        return Err(Error::Opus("Missing identification header".to_string()));
+    }
+    let channels = header[9];
+    let padding = u16::from_le_bytes([header[10], header[11]]) as usize;
+    let sample_rate = u32::from_le_bytes([header[12], header[13], header[14], header[15]]);
+
+    // skip the comment header
+    reader.read_packet_expected()?;
+
+    let mut decoder = Decoder::new(
+        SampleRate::try_from(i32::try_from(sample_rate).map_err(|_| Error::OutOfRange)?)?,
+        Channels::try_from(channels as i32)?,
+    )?;
+    // opus frames are at most 120ms long
+    let mut frame = vec![0.0; sample_rate as usize * channels as usize * 120 / 1000];
+    let mut data = Vec::new();
+
+    while let Some(packet) = reader.read_packet()? {
+        let samples = decoder.decode_float(
+            Some(packet.data.as_slice().try_into()?),
+            MutSignals::try_from(&mut frame)?,
+            false,
+        )?;
+        data.extend_from_slice(&frame[..samples * channels as usize]);
+    }
+
+    Ok(AudioData {
+        data: data.into_iter().skip(padding * channels as usize).collect(),
+        channels,
+        sample_rate,
+    })
+}
+
 fn opus_id_header(audio: &AudioData, padding: u16) -> Result<Vec<u8>, Error> {
     // the identification header is structured as follows:
     //
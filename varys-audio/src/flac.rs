@@ -0,0 +1,372 @@
+use crate::audio::AudioData;
+use crate::error::Error;
+
+/// The number of bits every sample is stored with, which keeps 16 and 24 bit recordings lossless.
+pub const BITS_PER_SAMPLE: u32 = 24;
+/// The number of samples per channel in every frame.
+const BLOCK_SIZE: usize = 4096;
+/// The most channels a FLAC stream can have.
+const MAX_CHANNELS: u8 = 8;
+/// The highest order of the fixed predictors, see the FLAC specification (RFC 9639).
+const MAX_FIXED_ORDER: usize = 4;
+/// The highest Rice parameter that can be written with the 4-bit parameters of the residual coding method `0`.
+const MAX_RICE_PARAMETER: u32 = 14;
+
+/// Encode audio data losslessly as a FLAC stream.
+///
+/// Samples are quantised to [`BITS_PER_SAMPLE`] bits, so audio that was recorded with at most that many bits is stored
+/// without loss. Every channel of a frame is coded with the fixed predictor that needs the fewest bits, or verbatim if
+/// none of them compresses it.
+///
+/// Returns an error if the audio has more channels than FLAC supports.
+///
+/// # Arguments
+///
+/// * `audio`: The audio data to encode.
+///
+/// # Examples
+///
+/// ```
+/// # use varys_audio::audio::AudioData;
+/// # use varys_audio::flac;
+/// let audio = AudioData {
+///     data: (0..48000).map(|index| (index as f32 / 100.0).sin() / 2.0).collect(),
+///     channels: 1,
+///     sample_rate: 48000,
+/// };
+///
+/// let encoded = flac::encode(&audio).unwrap();
+///
+/// assert!(encoded.starts_with(b"fLaC"));
+/// assert!(encoded.len() < audio.data.len() * 3);
+/// ```
+///
+/// The stream decodes to exactly the quantised samples with an independent decoder, e.g. with several channels and a
+/// last frame that is shorter than the others:
+///
+/// ```
+/// # use std::io::Cursor;
+/// # use varys_audio::audio::AudioData;
+/// # use varys_audio::flac::{self, BITS_PER_SAMPLE};
+/// let mut seed = 1_u32;
+/// let mut noise = || {
+///     seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+///     seed as f32 / u32::MAX as f32 - 0.5
+/// };
+/// // a tone, noise and silence in three channels
+/// let audio = AudioData {
+///     data: (0..10_000)
+///         .flat_map(|index| [(index as f32 / 30.0).sin() * 0.8, noise(), 0.0])
+///         .collect(),
+///     channels: 3,
+///     sample_rate: 44100,
+/// };
+/// let scale = (1 << (BITS_PER_SAMPLE - 1)) as f32;
+/// let quantised: Vec<i32> = audio
+///     .data
+///     .iter()
+///     .map(|sample| (sample * scale).round() as i32)
+///     .collect();
+///
+/// let mut reader = claxon::FlacReader::new(Cursor::new(flac::encode(&audio).unwrap())).unwrap();
+/// let streaminfo = reader.streaminfo();
+/// let decoded: Vec<i32> = reader.samples().map(Result::unwrap).collect();
+///
+/// assert_eq!(streaminfo.channels, 3);
+/// assert_eq!(streaminfo.sample_rate, 44100);
+/// assert_eq!(streaminfo.samples, Some(10_000));
+/// assert_eq!(decoded, quantised);
+/// ```
+pub fn encode(audio: &AudioData) -> Result<Vec<u8>, Error> {
+    if audio.channels == 0 || audio.channels > MAX_CHANNELS {
+        return Err(Error::UnsupportedFlacChannelCount(audio.channels as u16));
+    }
+
+    let channels = audio.channels as usize;
+    let scale = (1_i64 << (BITS_PER_SAMPLE - 1)) as f32;
+    let samples: Vec<i64> = audio
+        .data
+        .iter()
+        .map(|sample| ((sample * scale).round() as i64).clamp(-(scale as i64), scale as i64 - 1))
+        .collect();
+    let total_samples = samples.len() / channels;
+
+    let frames: Vec<Vec<u8>> = samples[..total_samples * channels]
+        .chunks(BLOCK_SIZE * channels)
+        .enumerate()
+        .map(|(index, block)| encode_frame(index as u64, block, channels))
+        .collect();
+
+    let mut stream = BitWriter::default();
+    stream.write_bytes(b"fLaC");
+    // the header of the STREAMINFO block, which is the last metadata block
+    stream.write(1, 1);
+    stream.write(0, 7);
+    stream.write(34, 24);
+    stream.write(BLOCK_SIZE as u64, 16);
+    stream.write(BLOCK_SIZE as u64, 16);
+    stream.write(
+        frames.iter().map(Vec::len).min().unwrap_or_default() as u64,
+        24,
+    );
+    stream.write(
+        frames.iter().map(Vec::len).max().unwrap_or_default() as u64,
+        24,
+    );
+    stream.write(audio.sample_rate as u64, 20);
+    stream.write(channels as u64 - 1, 3);
+    stream.write(BITS_PER_SAMPLE as u64 - 1, 5);
+    stream.write(total_samples as u64, 36);
+    // the MD5 signature of the samples is left unset
+    stream.write_bytes(&[0; 16]);
+
+    let mut bytes = stream.into_bytes();
+    frames
+        .into_iter()
+        .for_each(|frame| bytes.extend_from_slice(&frame));
+
+    Ok(bytes)
+}
+
+/// Encode a block of interleaved samples as a frame with one independent subframe per channel.
+///
+/// # Arguments
+///
+/// * `index`: The number of the frame, starting at `0`.
+/// * `block`: The interleaved samples of the frame.
+/// * `channels`: The number of channels.
+fn encode_frame(index: u64, block: &[i64], channels: usize) -> Vec<u8> {
+    let block_size = block.len() / channels;
+    let mut frame = BitWriter::default();
+
+    frame.write(0b11111111111110, 14);
+    frame.write(0, 1);
+    // fixed block size
+    frame.write(0, 1);
+    // the block size follows the header as a 16-bit number
+    frame.write(0b0111, 4);
+    // the sample rate is the one of the STREAMINFO block
+    frame.write(0b0000, 4);
+    // independent channels
+    frame.write(channels as u64 - 1, 4);
+    // 24 bits per sample
+    frame.write(0b110, 3);
+    frame.write(0, 1);
+    frame.write_utf8(index);
+    frame.write(block_size as u64 - 1, 16);
+    let crc = crc8(&frame.bytes);
+    frame.write(crc as u64, 8);
+
+    for channel in 0..channels {
+        let samples: Vec<i64> = block
+            .iter()
+            .skip(channel)
+            .step_by(channels)
+            .copied()
+            .collect();
+        encode_subframe(&mut frame, &samples);
+    }
+
+    frame.align();
+    let crc = crc16(&frame.bytes);
+    frame.write(crc as u64, 16);
+
+    frame.into_bytes()
+}
+
+/// Encode the samples of one channel of a frame as a subframe.
+///
+/// # Arguments
+///
+/// * `frame`: The frame to write the subframe to.
+/// * `samples`: The samples of the channel.
+fn encode_subframe(frame: &mut BitWriter, samples: &[i64]) {
+    if samples.iter().all(|sample| *sample == samples[0]) {
+        frame.write(0b0000000, 7);
+        frame.write(0, 1);
+        frame.write_signed(samples[0], BITS_PER_SAMPLE);
+
+        return;
+    }
+
+    let verbatim_bits = samples.len() as u64 * BITS_PER_SAMPLE as u64;
+    let best = (0..=MAX_FIXED_ORDER.min(samples.len() - 1))
+        .map(|order| {
+            let residuals = fixed_residuals(samples, order);
+            let (parameter, bits) = rice_parameter(&residuals);
+
+            (
+                order,
+                residuals,
+                parameter,
+                bits + (order as u64 * BITS_PER_SAMPLE as u64),
+            )
+        })
+        .min_by_key(|(_, _, _, bits)| *bits)
+        .filter(|(_, _, _, bits)| *bits < verbatim_bits);
+
+    match best {
+        Some((order, residuals, parameter, _)) => {
+            frame.write(0b001000 | order as u64, 7);
+            frame.write(0, 1);
+            samples[..order]
+                .iter()
+                .for_each(|sample| frame.write_signed(*sample, BITS_PER_SAMPLE));
+            // Rice coding with 4-bit parameters and a single partition
+            frame.write(0b00, 2);
+            frame.write(0, 4);
+            frame.write(parameter as u64, 4);
+            for residual in residuals {
+                let folded = fold(residual);
+                frame.write_unary(folded >> parameter);
+                frame.write(folded, parameter);
+            }
+        }
+        None => {
+            frame.write(0b0000001, 7);
+            frame.write(0, 1);
+            samples
+                .iter()
+                .for_each(|sample| frame.write_signed(*sample, BITS_PER_SAMPLE));
+        }
+    }
+}
+
+/// Get the residuals of the fixed predictor of an order, which predicts every sample from the `order` samples before
+/// it.
+///
+/// # Arguments
+///
+/// * `samples`: The samples to predict.
+/// * `order`: The order of the predictor, from `0` to [`MAX_FIXED_ORDER`].
+fn fixed_residuals(samples: &[i64], order: usize) -> Vec<i64> {
+    (order..samples.len())
+        .map(|index| {
+            let s = |offset: usize| samples[index - offset];
+            let prediction = match order {
+                0 => 0,
+                1 => s(1),
+                2 => 2 * s(1) - s(2),
+                3 => 3 * s(1) - 3 * s(2) + s(3),
+                _ => 4 * s(1) - 6 * s(2) + 4 * s(3) - s(4),
+            };
+
+            samples[index] - prediction
+        })
+        .collect()
+}
+
+/// Find the Rice parameter that codes residuals with the fewest bits.
+///
+/// Returns the parameter and the number of bits of the coded residuals, including the coding method, partition order
+/// and parameter.
+///
+/// # Arguments
+///
+/// * `residuals`: The residuals to code.
+fn rice_parameter(residuals: &[i64]) -> (u32, u64) {
+    let folded: Vec<u64> = residuals.iter().map(|residual| fold(*residual)).collect();
+
+    (0..=MAX_RICE_PARAMETER)
+        .map(|parameter| {
+            let bits: u64 = folded
+                .iter()
+                .map(|value| (value >> parameter) + 1 + parameter as u64)
+                .sum();
+
+            (parameter, bits + 10)
+        })
+        .min_by_key(|(_, bits)| *bits)
+        .unwrap_or((0, 10))
+}
+
+/// Map a signed residual to an unsigned number, so small magnitudes get small numbers.
+fn fold(residual: i64) -> u64 {
+    ((residual << 1) ^ (residual >> 63)) as u64
+}
+
+/// The CRC-8 of a frame header with the polynomial `x^8 + x^2 + x + 1`.
+fn crc8(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0, |crc, byte| {
+        (0..8).fold(crc ^ byte, |crc, _| match crc & 0x80 {
+            0 => crc << 1,
+            _ => (crc << 1) ^ 0x07,
+        })
+    })
+}
+
+/// The CRC-16 of a frame with the polynomial `x^16 + x^15 + x^2 + 1`.
+fn crc16(bytes: &[u8]) -> u16 {
+    bytes.iter().fold(0, |crc, byte| {
+        (0..8).fold(crc ^ ((*byte as u16) << 8), |crc, _| match crc & 0x8000 {
+            0 => crc << 1,
+            _ => (crc << 1) ^ 0x8005,
+        })
+    })
+}
+
+/// Writes numbers with an arbitrary number of bits, most significant bit first.
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    /// The number of bits of the last byte that are used, or `0` if it is full.
+    used: u32,
+}
+
+impl BitWriter {
+    /// Write the lowest `bits` bits of a number.
+    fn write(&mut self, value: u64, bits: u32) {
+        for bit in (0..bits).rev() {
+            if self.used == 0 {
+                self.bytes.push(0);
+            }
+            let byte = self.bytes.last_mut().expect("a byte was just pushed");
+            *byte |= (((value >> bit) & 1) as u8) << (7 - self.used);
+            self.used = (self.used + 1) % 8;
+        }
+    }
+
+    /// Write a signed number in two's complement with `bits` bits.
+    fn write_signed(&mut self, value: i64, bits: u32) {
+        self.write(value as u64 & ((1 << bits) - 1), bits);
+    }
+
+    /// Write a number as that many zeros followed by a one.
+    fn write_unary(&mut self, value: u64) {
+        for _ in 0..value {
+            self.write(0, 1);
+        }
+        self.write(1, 1);
+    }
+
+    /// Write a number of up to 36 bits in the variable length encoding of UTF-8, which FLAC uses for frame numbers.
+    fn write_utf8(&mut self, value: u64) {
+        if value < 0x80 {
+            self.write(value, 8);
+            return;
+        }
+
+        let continuation_bytes = (1..6)
+            .find(|bytes| value < 1 << (6 - bytes + 6 * bytes))
+            .unwrap_or(6);
+        let leading_ones = (0xff_u64 << (7 - continuation_bytes)) & 0xff;
+        self.write(leading_ones | (value >> (6 * continuation_bytes)), 8);
+        for byte in (0..continuation_bytes).rev() {
+            self.write(0x80 | ((value >> (6 * byte)) & 0x3f), 8);
+        }
+    }
+
+    /// Write whole bytes.
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        bytes.iter().for_each(|byte| self.write(*byte as u64, 8));
+    }
+
+    /// Fill the last byte with zeros.
+    fn align(&mut self) {
+        self.used = 0;
+    }
+
+    fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
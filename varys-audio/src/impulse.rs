@@ -0,0 +1,242 @@
+use std::f32::consts::PI;
+
+use log::debug;
+use rustfft::num_complex::Complex;
+use rustfft::FftPlanner;
+
+use crate::audio::AudioData;
+
+/// The amplitude at which sweeps are played.
+const SWEEP_AMPLITUDE: f32 = 0.5;
+/// The duration of the fade in and out of sweeps in milliseconds, which avoids clicks.
+const SWEEP_FADE_MS: usize = 10;
+/// The regularisation of the deconvolution, relative to the peak energy of the sweep spectrum.
+///
+/// This keeps frequencies outside the sweep from being amplified into noise.
+const REGULARISATION: f32 = 1e-3;
+/// How many milliseconds of the impulse response to keep before its peak.
+const PRE_PEAK_MS: usize = 1;
+
+/// Generate an exponential sine sweep to measure an impulse response with.
+///
+/// The frequency of the sweep rises exponentially, so it spends the same time on every octave.
+///
+/// # Arguments
+///
+/// * `start_frequency`: The frequency at the start of the sweep in Hz.
+/// * `end_frequency`: The frequency at the end of the sweep in Hz.
+/// * `duration_ms`: The duration of the sweep in milliseconds.
+/// * `sample_rate`: The sample rate of the sweep.
+///
+/// # Examples
+///
+/// ```
+/// # use varys_audio::impulse::sweep;
+/// let sweep = sweep(20.0, 20000.0, 2000, 48000);
+///
+/// assert_eq!(sweep.duration_ms(), 2000);
+/// assert!(sweep.data.iter().all(|sample| sample.abs() <= 0.5));
+/// ```
+pub fn sweep(
+    start_frequency: f32,
+    end_frequency: f32,
+    duration_ms: u32,
+    sample_rate: u32,
+) -> AudioData {
+    let length = duration_ms as usize * sample_rate as usize / 1000;
+    let duration = duration_ms as f32 / 1000.0;
+    let rate = (end_frequency / start_frequency).ln();
+    let fade = (SWEEP_FADE_MS * sample_rate as usize / 1000).min(length / 2);
+
+    let data = (0..length)
+        .map(|index| {
+            let time = index as f32 / sample_rate as f32;
+            let phase = 2.0 * PI * start_frequency * duration / rate
+                * ((time * rate / duration).exp() - 1.0);
+            let envelope = if index < fade {
+                index as f32 / fade as f32
+            } else if index >= length - fade {
+                (length - index) as f32 / fade as f32
+            } else {
+                1.0
+            };
+
+            SWEEP_AMPLITUDE * envelope * phase.sin()
+        })
+        .collect();
+
+    AudioData {
+        data,
+        channels: 1,
+        sample_rate,
+    }
+}
+
+/// Estimate the impulse response of a room from a recording of a sweep played in it.
+///
+/// The recording is deconvolved with the sweep in the frequency domain. The impulse response starts just before its
+/// peak, so the delay of playback and recording is removed.
+///
+/// Both must be mono audio with the same sample rate.
+///
+/// # Arguments
+///
+/// * `sweep`: The sweep that was played, see [`sweep`].
+/// * `recording`: The recording of the sweep.
+/// * `length_ms`: The length of the impulse response in milliseconds.
+///
+/// Returns the impulse response, normalised to a peak of `1`, and the delay of its peak in the recording in
+/// milliseconds.
+///
+/// # Examples
+///
+/// ```
+/// # use varys_audio::audio::AudioData;
+/// # use varys_audio::impulse::{impulse_response, sweep};
+/// let sweep = sweep(20.0, 4000.0, 1000, 8000);
+///
+/// // the sweep is recorded 250ms late with a quiet echo 50ms later
+/// let mut recording = AudioData {
+///     data: vec![0.0; 2400 + sweep.data.len()],
+///     channels: 1,
+///     sample_rate: 8000,
+/// };
+/// for (index, sample) in sweep.data.iter().enumerate() {
+///     recording.data[2000 + index] += sample;
+///     recording.data[2400 + index] += 0.3 * sample;
+/// }
+///
+/// let (response, delay_ms) = impulse_response(&sweep, &recording, 100);
+///
+/// assert_eq!(delay_ms, 250);
+/// assert_eq!(response.data.len(), 800);
+/// assert!((response.data[8] - 1.0).abs() < 0.01);
+/// assert!((response.data[408] - 0.3).abs() < 0.01);
+/// ```
+pub fn impulse_response(
+    sweep: &AudioData,
+    recording: &AudioData,
+    length_ms: u32,
+) -> (AudioData, i32) {
+    let sample_rate = recording.sample_rate as usize;
+    let size = (sweep.data.len() + recording.data.len()).next_power_of_two();
+    let mut planner = FftPlanner::<f32>::new();
+    let forward = planner.plan_fft_forward(size);
+    let inverse = planner.plan_fft_inverse(size);
+
+    let spectrum = |data: &[f32]| {
+        let mut buffer: Vec<Complex<f32>> = data
+            .iter()
+            .map(|&sample| Complex::new(sample, 0.0))
+            .collect();
+        buffer.resize(size, Complex::default());
+        forward.process(&mut buffer);
+        buffer
+    };
+    let sweep_spectrum = spectrum(&sweep.data);
+    let mut response = spectrum(&recording.data);
+
+    let peak_energy = sweep_spectrum
+        .iter()
+        .map(|bin| bin.norm_sqr())
+        .fold(0.0, f32::max);
+    let regularisation = REGULARISATION * peak_energy;
+    response
+        .iter_mut()
+        .zip(&sweep_spectrum)
+        .for_each(|(bin, sweep_bin)| {
+            *bin = *bin * sweep_bin.conj() / (sweep_bin.norm_sqr() + regularisation)
+        });
+    inverse.process(&mut response);
+
+    let response: Vec<f32> = response.iter().map(|bin| bin.re / size as f32).collect();
+    let peak = response
+        .iter()
+        .enumerate()
+        .max_by(|(_, a), (_, b)| a.abs().total_cmp(&b.abs()))
+        .map(|(index, _)| index)
+        .unwrap_or_default();
+    let peak_amplitude = response[peak].abs().max(f32::EPSILON);
+    let start = peak.saturating_sub(PRE_PEAK_MS * sample_rate / 1000);
+    let length = length_ms as usize * sample_rate / 1000;
+
+    debug!("Found the peak of the impulse response at sample {peak}");
+
+    let data = response
+        .iter()
+        .cycle()
+        .skip(start)
+        .take(length)
+        .map(|sample| sample / peak_amplitude)
+        .collect();
+
+    (
+        AudioData {
+            data,
+            channels: 1,
+            sample_rate: recording.sample_rate,
+        },
+        (peak * 1000 / sample_rate) as i32,
+    )
+}
+
+/// Estimate the reverberation time (RT60) of a room from its impulse response.
+///
+/// The decay is found with Schroeder backward integration. The time it takes to decay from -5dB to -25dB is
+/// extrapolated to a decay of 60dB.
+///
+/// # Arguments
+///
+/// * `impulse_response`: The impulse response of the room.
+///
+/// Returns the reverberation time in milliseconds, or `None` if the impulse response does not decay by 25dB.
+///
+/// # Examples
+///
+/// ```
+/// # use varys_audio::audio::AudioData;
+/// # use varys_audio::impulse::reverberation_time;
+/// // noise that decays by 60dB in 400ms
+/// let mut seed = 1_u32;
+/// let data = (0..8000)
+///     .map(|index| {
+///         seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+///         let noise = seed as f32 / u32::MAX as f32 - 0.5;
+///         noise * 10_f32.powf(-3.0 * index as f32 / 3200.0)
+///     })
+///     .collect();
+/// let impulse_response = AudioData {
+///     data,
+///     channels: 1,
+///     sample_rate: 8000,
+/// };
+///
+/// let rt60 = reverberation_time(&impulse_response).unwrap();
+/// assert!((380..420).contains(&rt60));
+/// ```
+pub fn reverberation_time(impulse_response: &AudioData) -> Option<i32> {
+    let mut energy: Vec<f32> = impulse_response
+        .data
+        .iter()
+        .rev()
+        .scan(0.0, |sum, sample| {
+            *sum += sample * sample;
+            Some(*sum)
+        })
+        .collect();
+    energy.reverse();
+
+    let total = *energy.first()?;
+    if total <= 0.0 {
+        return None;
+    }
+    let decay_index = |decibels: f32| {
+        energy
+            .iter()
+            .position(|&remaining| 10.0 * (remaining / total).log10() <= decibels)
+    };
+    let start = decay_index(-5.0)?;
+    let end = decay_index(-25.0)?;
+
+    Some(((end - start) * 3 * 1000 / impulse_response.sample_rate as usize) as i32)
+}
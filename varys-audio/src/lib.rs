@@ -1,6 +1,14 @@
 pub mod audio;
+pub mod echo;
 pub mod error;
 pub mod file;
+pub mod flac;
+pub mod impulse;
+#[cfg(feature = "listen")]
 pub mod listen;
+pub mod resample;
 pub mod stt;
+#[cfg(feature = "tts")]
 pub mod tts;
+pub mod voice;
+pub mod watermark;
@@ -1,30 +1,224 @@
+use std::collections::VecDeque;
+use std::fmt::{Display, Formatter};
+use std::future::{self, Future};
+use std::pin::Pin;
+use std::str::FromStr;
 use std::sync::{
-    mpsc::{channel, Receiver},
-    Arc, Mutex,
+    atomic::{AtomicBool, Ordering},
+    mpsc::{channel, Receiver, RecvTimeoutError, Sender},
+    Arc, Mutex, MutexGuard, PoisonError,
 };
 use std::thread;
 use std::time::{Duration, Instant};
 
 use cpal::{
     traits::{DeviceTrait, HostTrait, StreamTrait},
-    Device, SampleFormat, SampleRate, Stream, StreamConfig,
+    Device, FromSample, Sample, SampleFormat, SampleRate, SizedSample, Stream, StreamConfig,
+    StreamError, SupportedStreamConfigRange,
 };
 use log::{debug, error, info, trace, warn};
 use simple_moving_average::{NoSumSMA, SMA};
+use tokio::sync::mpsc;
+use tokio::task;
 
+use crate::audio::bands::BandAnalyser;
 use crate::audio::{AudioData, OPUS_SAMPLE_RATE};
 use crate::error::Error;
-use crate::stt::Recogniser;
+use crate::listen::vad::{EnergyDetector, Frame, SilenceDetector};
+use crate::resample::ResampleQuality;
+use crate::stt;
+
+pub mod fake;
+pub mod vad;
 
 const CALIBRATION_TIMEOUT: Duration = Duration::from_secs(5);
 const MOVING_AVERAGE_WINDOW_SIZE: usize = 1024;
 /// How many seconds of audio data should be expected by default when starting a recording.
 const RECORDING_BUFFER_CAPACITY_SECONDS: usize = 10;
+/// The sample formats a [`Listener`] can record with, several USB audio interfaces only offer integer samples.
+const SUPPORTED_SAMPLE_FORMATS: [SampleFormat; 3] =
+    [SampleFormat::F32, SampleFormat::I16, SampleFormat::U16];
+/// How long to wait for a frame before checking whether the device of a recording was disconnected.
+const FRAME_TIMEOUT: Duration = Duration::from_millis(500);
+/// How long to wait before every attempt to find a disconnected device again.
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// How the threshold that distinguishes silence from sound is determined.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SilenceThreshold {
+    /// A fixed threshold.
+    Static(f32),
+    /// The mean ambient noise level plus this many standard deviations.
+    Deviation(f32),
+    /// This percentile from `0` to `100` of the ambient noise levels.
+    Percentile(f32),
+}
+
+impl SilenceThreshold {
+    /// Whether the threshold depends on the ambient noise and has to be recomputed when the noise changes.
+    pub fn is_adaptive(&self) -> bool {
+        !matches!(self, SilenceThreshold::Static(_))
+    }
+
+    /// Compute the threshold from ambient noise levels, as recorded by [`Listen::ambient_levels`].
+    ///
+    /// # Arguments
+    ///
+    /// * `levels`: The ambient noise levels.
+    ///
+    /// Returns `None` if the threshold is adaptive and there are no levels.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys_audio::listen::SilenceThreshold;
+    /// let levels = [0.01, 0.02, 0.03, 0.04, 0.05];
+    ///
+    /// assert_eq!(SilenceThreshold::Static(0.1).compute(&levels), Some(0.1));
+    /// assert_eq!(SilenceThreshold::Percentile(75.0).compute(&levels), Some(0.04));
+    /// let threshold = SilenceThreshold::Deviation(2.0).compute(&levels).unwrap();
+    /// assert!((threshold - 0.0583).abs() < 0.0001);
+    /// assert_eq!(SilenceThreshold::Deviation(2.0).compute(&[]), None);
+    /// ```
+    pub fn compute(&self, levels: &[f32]) -> Option<f32> {
+        match self {
+            SilenceThreshold::Static(threshold) => Some(*threshold),
+            _ if levels.is_empty() => None,
+            SilenceThreshold::Deviation(deviations) => {
+                let count = levels.len() as f32;
+                let mean = levels.iter().sum::<f32>() / count;
+                let deviation = (levels
+                    .iter()
+                    .map(|level| (level - mean).powi(2))
+                    .sum::<f32>()
+                    / count)
+                    .sqrt();
+
+                Some(mean + deviations * deviation)
+            }
+            SilenceThreshold::Percentile(percentile) => {
+                let mut levels = levels.to_vec();
+                levels.sort_by(f32::total_cmp);
+                let index = (percentile.clamp(0.0, 100.0) / 100.0 * (levels.len() - 1) as f32)
+                    .round() as usize;
+
+                Some(levels[index])
+            }
+        }
+    }
+}
+
+impl FromStr for SilenceThreshold {
+    type Err = String;
+
+    /// Parse a silence threshold, either a fixed threshold like `0.01` or an adaptive threshold like `deviation:3` or
+    /// `percentile:95`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys_audio::listen::SilenceThreshold;
+    /// assert_eq!("0.01".parse(), Ok(SilenceThreshold::Static(0.01)));
+    /// assert_eq!("deviation:3".parse(), Ok(SilenceThreshold::Deviation(3.0)));
+    /// assert_eq!("percentile:95".parse(), Ok(SilenceThreshold::Percentile(95.0)));
+    /// assert!("median".parse::<SilenceThreshold>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Expected <threshold>, deviation:<k> or percentile:<p>, got {s}");
+        let parse = |value: &str| value.parse::<f32>().map_err(|_| invalid());
+
+        match s.split_once(':') {
+            None => Ok(SilenceThreshold::Static(parse(s)?)),
+            Some(("deviation", value)) => Ok(SilenceThreshold::Deviation(parse(value)?)),
+            Some(("percentile", value)) => Ok(SilenceThreshold::Percentile(parse(value)?)),
+            Some(_) => Err(invalid()),
+        }
+    }
+}
+
+impl Display for SilenceThreshold {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SilenceThreshold::Static(threshold) => write!(f, "{threshold}"),
+            SilenceThreshold::Deviation(deviations) => write!(f, "deviation:{deviations}"),
+            SilenceThreshold::Percentile(percentile) => write!(f, "percentile:{percentile}"),
+        }
+    }
+}
+
+/// A chunk of audio delivered to the subscribers of a [`ListenerInstance`] while it is recording.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AudioChunk {
+    /// The samples of the chunk as `f32`, interleaved if the device records several channels.
+    pub samples: Vec<f32>,
+    /// The moving average of the absolute sample values at the end of the chunk, which silence is detected with.
+    pub level: f32,
+    /// The highest absolute sample value of the chunk.
+    pub peak: f32,
+}
+
+/// A recording of [`Listen::record_until_silent_or_timeout_async`], which resolves to the recorded audio and whether
+/// the recording was stopped by the timeout.
+pub type RecordingFuture<'a> = Pin<Box<dyn Future<Output = Result<(AudioData, bool), Error>> + 'a>>;
+
+/// A subscriber of a [`ListenerInstance`], which is removed once it returns `false`.
+type Subscriber = Box<dyn FnMut(&AudioChunk) -> bool + Send>;
+
+/// An audio input device of the system, as listed by [`Listener::input_devices`].
+#[derive(Clone, Debug)]
+pub struct InputDevice {
+    /// The position of the device in the list of input devices, which selects it like its name.
+    pub index: usize,
+    /// The name of the device.
+    pub name: String,
+    /// Whether this is the default input device of the system.
+    pub is_default: bool,
+    /// The stream configurations the device supports.
+    pub configs: Vec<SupportedStreamConfigRange>,
+}
+
+impl InputDevice {
+    /// Whether a [`Listener`] can record with the device, i.e. it supports one of the required configurations.
+    pub fn is_supported(&self) -> bool {
+        self.configs.iter().any(is_supported)
+    }
+}
+
+impl Display for InputDevice {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.index, self.name)?;
+        if self.is_default {
+            write!(f, " (default)")?;
+        }
+        if !self.is_supported() {
+            write!(f, " (not supported)")?;
+        }
+
+        for config in &self.configs {
+            write!(
+                f,
+                "\n    {} channels, {}-{} Hz, {}",
+                config.channels(),
+                config.min_sample_rate().0,
+                config.max_sample_rate().0,
+                config.sample_format()
+            )?;
+        }
+
+        Ok(())
+    }
+}
 
 /// A listener that can parse voice input.
 pub struct Listener {
-    device: Device,
-    device_config: StreamConfig,
+    /// The device that is recorded with, which is replaced if it has to be found again after it was disconnected.
+    device: Mutex<Device>,
+    /// The name of the device to find again after it was disconnected, or `None` to use the default input device.
+    device_name: Option<String>,
+    /// The configuration the device records with and the format of its samples, which are converted to `f32`.
+    ///
+    /// They are chosen again whenever the device is found again, since it might support other configurations then.
+    config: Mutex<(StreamConfig, SampleFormat)>,
     /// The optional maximum duration to record for.
     ///
     /// Use this to stop any recording longer than the specified duration.
@@ -33,9 +227,174 @@ pub struct Listener {
     ///
     /// Defaults to [`Listener::DEFAULT_RECORDING_TIMEOUT`].
     pub recording_timeout: Option<Duration>,
+    /// Decides which parts of a recording are sound when waiting for silence.
+    ///
+    /// Defaults to [`EnergyDetector`].
+    pub silence_detector: Box<dyn SilenceDetector>,
+    /// The always-on stream recording the most recent audio, which is prepended to recordings started with
+    /// [`Listener::start`]. While it is set, recordings are fed by it instead of streams of their own.
+    pre_roll: Mutex<Option<PreRoll>>,
+    /// How many times to try finding the device again if it was disconnected, e.g. because a USB microphone was
+    /// unplugged, before giving up with [`Error::DeviceDisconnected`].
+    ///
+    /// Defaults to [`Listener::DEFAULT_RECONNECT_ATTEMPTS`].
+    pub reconnect_attempts: u32,
+}
+
+/// A stream that keeps recording into a circular buffer, see [`Listener::set_pre_roll`].
+struct PreRoll {
+    stream: Stream,
+    buffer: Arc<Mutex<PreRollBuffer>>,
+    disconnected: Arc<AtomicBool>,
+}
+
+impl PreRoll {
+    /// How many samples the buffer of the pre-roll keeps.
+    fn capacity(&self) -> usize {
+        self.buffer
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .capacity
+    }
+}
+
+/// The circular buffer of a [`PreRoll`] and the recordings its stream feeds.
+struct PreRollBuffer {
+    samples: VecDeque<f32>,
+    /// How many samples the buffer keeps.
+    capacity: usize,
+    recorders: Vec<Recorder>,
+}
+
+/// The samples of a recording and the state of its analysis, which are updated with every chunk the device records.
+struct Recorder {
+    writer: Arc<Mutex<Vec<f32>>>,
+    /// Where a [`Frame`] with the moving average of the sample levels, the zero-crossing rate and the octave band
+    /// energies of every window is sent to.
+    frame_sender: Sender<Frame>,
+    subscribers: Arc<Mutex<Vec<Subscriber>>>,
+    running_average: NoSumSMA<f32, f32, MOVING_AVERAGE_WINDOW_SIZE>,
+    sample_count: u32,
+    zero_crossings: u32,
+    previous_sample: f32,
+    channels: usize,
+    band_analyser: BandAnalyser,
+    /// The average of all channels of the current window, which the bands are computed on.
+    mono_window: Vec<f32>,
+    mono_sum: f32,
+    channel: usize,
+}
+
+impl Recorder {
+    /// Create a recorder for a stream.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer`: The recording the samples are written to.
+    /// * `frame_sender`: Where the frames of the recording are sent to.
+    /// * `subscribers`: The subscribers every recorded chunk of samples is delivered to.
+    /// * `config`: The configuration of the stream.
+    fn new(
+        writer: Arc<Mutex<Vec<f32>>>,
+        frame_sender: Sender<Frame>,
+        subscribers: Arc<Mutex<Vec<Subscriber>>>,
+        config: &StreamConfig,
+    ) -> Self {
+        let channels = config.channels.max(1) as usize;
+        let band_analyser =
+            BandAnalyser::new(MOVING_AVERAGE_WINDOW_SIZE / channels, config.sample_rate.0);
+
+        Recorder {
+            writer,
+            frame_sender,
+            subscribers,
+            running_average: NoSumSMA::new(),
+            sample_count: 0,
+            zero_crossings: 0,
+            previous_sample: 0.,
+            channels,
+            mono_window: Vec::with_capacity(band_analyser.window_size()),
+            band_analyser,
+            mono_sum: 0.,
+            channel: 0,
+        }
+    }
+
+    /// Convert a chunk of samples of type `T` to `f32`, write them to the recording and deliver them to the
+    /// subscribers.
+    ///
+    /// # Arguments
+    ///
+    /// * `data`: The samples of whole frames.
+    fn record<T>(&mut self, data: &[T])
+    where
+        T: Sample,
+        f32: FromSample<T>,
+    {
+        let Ok(mut guard) = self.writer.try_lock() else {
+            return;
+        };
+
+        let chunk_start = guard.len();
+        for &sample in data.iter() {
+            let sample = sample.to_sample::<f32>();
+            guard.push(sample);
+            self.running_average.add_sample(sample.abs());
+            if (sample >= 0.) != (self.previous_sample >= 0.) {
+                self.zero_crossings += 1;
+            }
+            self.previous_sample = sample;
+            self.mono_sum += sample;
+            self.channel += 1;
+            if self.channel == self.channels {
+                self.mono_window.push(self.mono_sum / self.channels as f32);
+                self.mono_sum = 0.;
+                self.channel = 0;
+            }
+            self.sample_count += 1;
+            if self.sample_count >= MOVING_AVERAGE_WINDOW_SIZE as u32 {
+                let frame = Frame {
+                    level: self.running_average.get_average(),
+                    zero_crossing_rate: self.zero_crossings as f32 / self.sample_count as f32,
+                    bands: self.band_analyser.analyse(&self.mono_window),
+                };
+                self.mono_window.clear();
+                trace!("{frame:?}");
+                if self.frame_sender.send(frame).is_err() {
+                    warn!("Unable to send recording frame");
+                }
+                self.sample_count = 0;
+                self.zero_crossings = 0;
+            }
+        }
+
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            if !subscribers.is_empty() {
+                let samples = guard[chunk_start..].to_vec();
+                let chunk = AudioChunk {
+                    peak: samples
+                        .iter()
+                        .fold(0., |peak, sample| sample.abs().max(peak)),
+                    samples,
+                    level: self.running_average.get_average(),
+                };
+                subscribers.retain_mut(|subscriber| subscriber(&chunk));
+            }
+        }
+    }
+}
+
+/// Where the samples of a [`ListenerInstance`] come from.
+enum Source {
+    /// A stream of its own.
+    Stream(Stream),
+    /// The stream of the pre-roll, whose buffer holds the recorder of the instance.
+    PreRoll(Arc<Mutex<PreRollBuffer>>),
 }
 
 impl Listener {
+    pub const DEFAULT_RECONNECT_ATTEMPTS: u32 = 3;
+
     /// Create a new listener using the system default input device.
     ///
     /// Returns an error if no input device was found or if it doesn't support the required sample rate and format.
@@ -50,33 +409,381 @@ impl Listener {
         let device = cpal::default_host()
             .default_input_device()
             .ok_or(Error::AudioDeviceNotFound)?;
+
+        Self::from_device(device, None)
+    }
+
+    /// Create a new listener using the input device with the given name or index, see [`Listener::input_devices`].
+    ///
+    /// This allows recording with several microphones at once, e.g. one per voice assistant. A device is selected by
+    /// its index only if no device has the number as its name.
+    ///
+    /// Returns an error if no input device has the name or index or if it doesn't support the required sample rate
+    /// and format.
+    ///
+    /// If the device is disconnected, it is found again by its name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name_or_index`: The name or index of the input device.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys_audio::listen::Listener;
+    /// assert!(Listener::with_device("Invalid Device").is_err());
+    /// assert!(Listener::with_device("999").is_err());
+    /// ```
+    pub fn with_device(name_or_index: &str) -> Result<Self, Error> {
+        let devices: Vec<Device> = cpal::default_host()
+            .input_devices()
+            .map_err(|error| Error::Cpal(error.to_string()))?
+            .collect();
+        let by_name = devices.iter().position(|device| {
+            device
+                .name()
+                .is_ok_and(|device_name| device_name == name_or_index)
+        });
+        let index = by_name
+            .or_else(|| name_or_index.parse().ok())
+            .ok_or(Error::AudioDeviceNotFound)?;
+        let device = devices
+            .into_iter()
+            .nth(index)
+            .ok_or(Error::AudioDeviceNotFound)?;
+        let device_name = device
+            .name()
+            .map_err(|error| Error::Cpal(error.to_string()))?;
+
+        Self::from_device(device, Some(device_name))
+    }
+
+    /// List all audio input devices of the system with the configurations they support.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use varys_audio::listen::Listener;
+    /// for device in Listener::input_devices().unwrap() {
+    ///     println!("{device}");
+    /// }
+    /// ```
+    pub fn input_devices() -> Result<Vec<InputDevice>, Error> {
+        let host = cpal::default_host();
+        let default_name = host
+            .default_input_device()
+            .and_then(|device| device.name().ok());
+
+        host.input_devices()
+            .map_err(|error| Error::Cpal(error.to_string()))?
+            .enumerate()
+            .map(|(index, device)| {
+                let name = device
+                    .name()
+                    .map_err(|error| Error::Cpal(error.to_string()))?;
+
+                Ok(InputDevice {
+                    index,
+                    is_default: default_name.as_ref() == Some(&name),
+                    name,
+                    configs: device.supported_input_configs()?.collect(),
+                })
+            })
+            .collect()
+    }
+
+    fn from_device(device: Device, device_name: Option<String>) -> Result<Self, Error> {
         if let Ok(name) = device.name() {
             debug!("Using audio device {}", name);
         }
 
-        let device_config: StreamConfig = device
-            .supported_input_configs()?
-            .find(|config| {
-                config.sample_format() == SampleFormat::F32
-                    && config.max_sample_rate().0 >= Recogniser::SAMPLE_RATE
-                    && config.max_sample_rate().0 >= OPUS_SAMPLE_RATE as u32
-            })
-            .ok_or(Error::ConfigurationNotSupported)?
-            .with_sample_rate(SampleRate(OPUS_SAMPLE_RATE as u32))
-            .into();
-        debug!("Using audio input config {:?}", device_config);
+        let config = stream_config(&device, None)?;
 
         Ok(Listener {
-            device,
-            device_config,
+            device: Mutex::new(device),
+            device_name,
+            config: Mutex::new(config),
             recording_timeout: None,
+            silence_detector: Box::new(EnergyDetector),
+            pre_roll: Mutex::new(None),
+            reconnect_attempts: Self::DEFAULT_RECONNECT_ATTEMPTS,
         })
     }
 
+    fn device(&self) -> MutexGuard<'_, Device> {
+        self.device.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn config(&self) -> MutexGuard<'_, (StreamConfig, SampleFormat)> {
+        self.config.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn device_config(&self) -> StreamConfig {
+        self.config().0.clone()
+    }
+
+    fn sample_format(&self) -> SampleFormat {
+        self.config().1
+    }
+
+    fn pre_roll(&self) -> MutexGuard<'_, Option<PreRoll>> {
+        self.pre_roll.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Find the device of the listener again, by its name or as the default input device.
+    fn find_device(&self) -> Result<Device, Error> {
+        let host = cpal::default_host();
+
+        match &self.device_name {
+            Some(name) => host
+                .input_devices()
+                .map_err(|error| Error::Cpal(error.to_string()))?
+                .find(|device| device.name().is_ok_and(|device_name| &device_name == name)),
+            None => host.default_input_device(),
+        }
+        .ok_or(Error::AudioDeviceNotFound)
+    }
+
+    /// Find the disconnected device of the listener again and connect to it, trying up to
+    /// [`Listener::reconnect_attempts`] times. The configuration of the device is chosen again with the same number of
+    /// channels, and the pre-roll is restarted with the new device if it is set.
+    ///
+    /// Returns [`Error::DeviceDisconnected`] if all attempts failed.
+    ///
+    /// # Arguments
+    ///
+    /// * `connect`: Builds and plays the streams of the device after it was found.
+    fn reconnect<T>(&self, mut connect: impl FnMut() -> Result<T, Error>) -> Result<T, Error> {
+        warn!("The audio device was disconnected, trying to find it again...");
+
+        for attempt in 1..=self.reconnect_attempts {
+            thread::sleep(RECONNECT_DELAY);
+
+            let connected = self.find_device().and_then(|device| {
+                // the device might support other configurations now, e.g. if another one with its name was plugged in
+                let config = stream_config(&device, Some(self.device_config().channels))?;
+                *self.device() = device;
+                *self.config() = config;
+                self.restart_pre_roll()?;
+                connect()
+            });
+            match connected {
+                Ok(connected) => {
+                    info!("Reconnected to the audio device");
+                    return Ok(connected);
+                }
+                Err(error) => warn!(
+                    "Attempt {attempt}/{} to reconnect failed: {error}",
+                    self.reconnect_attempts
+                ),
+            }
+        }
+
+        Err(Error::DeviceDisconnected(self.reconnect_attempts))
+    }
+
+    /// Record a number of channels, e.g. two channels of a stereo audio interface with one microphone pointed at the
+    /// assistant and one at the speaker. The channels are kept separate in the recorded [`AudioData`], see
+    /// [`AudioData::channel`].
+    ///
+    /// By default, the listener records as many channels as the first supported configuration of the device.
+    ///
+    /// Returns an error if the device cannot record this many channels with the required sample rate and format.
+    ///
+    /// # Arguments
+    ///
+    /// * `channels`: How many channels to record.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use varys_audio::listen::Listener;
+    /// let mut listener = Listener::new().unwrap();
+    /// listener.set_channels(2).unwrap();
+    ///
+    /// let audio = listener.start().unwrap().stop().unwrap();
+    /// assert_eq!(audio.channels, 2);
+    /// ```
+    pub fn set_channels(&mut self, channels: u16) -> Result<(), Error> {
+        let config = stream_config(&self.device(), Some(channels))?;
+        let previous_channels = self.device_config().channels as usize;
+        *self.config() = config;
+
+        // the pre-roll keeps the same duration of audio with the new number of channels
+        let mut pre_roll = self.pre_roll();
+        if let Some(capacity) = pre_roll.as_ref().map(PreRoll::capacity) {
+            *pre_roll = None;
+            *pre_roll =
+                Some(self.start_pre_roll(capacity / previous_channels.max(1) * channels as usize)?);
+        }
+
+        Ok(())
+    }
+
+    /// Keep recording the most recent audio in a circular buffer, which is prepended to every recording started with
+    /// [`Listener::start`].
+    ///
+    /// This captures the onset of sounds that begin right as a recording is started, like the wake word of a query.
+    /// Recordings made by the other methods of the listener, e.g. [`Listener::record_until_silent`], do not include the
+    /// buffered audio.
+    ///
+    /// The input stream of the device is kept open while the pre-roll is set, and recordings are fed by it instead of
+    /// opening streams of their own.
+    ///
+    /// # Arguments
+    ///
+    /// * `duration`: How much audio to keep, or `None` to stop buffering.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use varys_audio::listen::Listener;
+    /// let mut listener = Listener::new().unwrap();
+    /// listener.set_pre_roll(Some(Duration::from_secs(1))).unwrap();
+    ///
+    /// // the recording starts with up to a second of audio from before this call
+    /// let instance = listener.start().unwrap();
+    /// let audio = instance.stop().unwrap();
+    /// ```
+    pub fn set_pre_roll(&mut self, duration: Option<Duration>) -> Result<(), Error> {
+        *self.pre_roll() = None;
+
+        let Some(duration) = duration else {
+            return Ok(());
+        };
+        let channels = self.device_config().channels as usize;
+        let capacity = (duration.as_secs_f32() * self.device_config().sample_rate.0 as f32)
+            as usize
+            * channels;
+        debug!(
+            "Buffering {:.2}s of audio before recordings",
+            duration.as_secs_f32()
+        );
+
+        *self.pre_roll() = Some(self.start_pre_roll(capacity)?);
+
+        Ok(())
+    }
+
+    /// Build and play the stream of a new pre-roll.
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity`: How many samples the buffer of the pre-roll keeps, a multiple of the number of channels.
+    fn start_pre_roll(&self, capacity: usize) -> Result<PreRoll, Error> {
+        let channels = self.device_config().channels as usize;
+        let buffer = Arc::new(Mutex::new(PreRollBuffer {
+            samples: VecDeque::with_capacity(capacity + channels),
+            capacity,
+            recorders: Vec::new(),
+        }));
+        let disconnected = Arc::new(AtomicBool::new(false));
+
+        Ok(PreRoll {
+            stream: self.build_pre_roll_stream(buffer.clone(), disconnected.clone())?,
+            buffer,
+            disconnected,
+        })
+    }
+
+    /// Build the stream of the pre-roll again with the current device, if it is set.
+    ///
+    /// The buffered audio and the recordings fed by the pre-roll are kept, so recordings continue after the device was
+    /// disconnected.
+    fn restart_pre_roll(&self) -> Result<(), Error> {
+        let mut pre_roll = self.pre_roll();
+        let Some(previous) = pre_roll.take() else {
+            return Ok(());
+        };
+        let PreRoll {
+            stream,
+            buffer,
+            disconnected,
+        } = previous;
+        // the old stream has to be dropped before a new one is built on the same device
+        drop(stream);
+        disconnected.store(false, Ordering::Relaxed);
+
+        *pre_roll = Some(PreRoll {
+            stream: self.build_pre_roll_stream(buffer.clone(), disconnected.clone())?,
+            buffer,
+            disconnected,
+        });
+
+        Ok(())
+    }
+
+    /// Build and play the input stream of a pre-roll with the sample format of the device.
+    ///
+    /// # Arguments
+    ///
+    /// See [`Listener::build_pre_roll_stream_of`].
+    fn build_pre_roll_stream(
+        &self,
+        buffer: Arc<Mutex<PreRollBuffer>>,
+        disconnected: Arc<AtomicBool>,
+    ) -> Result<Stream, Error> {
+        let stream = match self.sample_format() {
+            SampleFormat::F32 => self.build_pre_roll_stream_of::<f32>(buffer, disconnected)?,
+            SampleFormat::I16 => self.build_pre_roll_stream_of::<i16>(buffer, disconnected)?,
+            SampleFormat::U16 => self.build_pre_roll_stream_of::<u16>(buffer, disconnected)?,
+            _ => return Err(Error::ConfigurationNotSupported),
+        };
+        stream.play()?;
+
+        Ok(stream)
+    }
+
+    /// Build the input stream of the device that keeps the most recent samples of type `T` in a circular buffer and
+    /// feeds the recordings attached to it.
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer`: The buffer the samples are written to.
+    /// * `disconnected`: Set once the device is no longer available.
+    fn build_pre_roll_stream_of<T>(
+        &self,
+        buffer: Arc<Mutex<PreRollBuffer>>,
+        disconnected: Arc<AtomicBool>,
+    ) -> Result<Stream, Error>
+    where
+        T: SizedSample,
+        f32: FromSample<T>,
+    {
+        Ok(self.device().build_input_stream(
+            &self.device_config(),
+            move |data: &[T], _| {
+                if let Ok(mut guard) = buffer.try_lock() {
+                    let buffer = &mut *guard;
+                    buffer
+                        .samples
+                        .extend(data.iter().map(|sample| sample.to_sample::<f32>()));
+                    // callbacks contain whole frames, so this keeps the channels aligned
+                    let excess = buffer.samples.len().saturating_sub(buffer.capacity);
+                    buffer.samples.drain(..excess);
+
+                    for recorder in &mut buffer.recorders {
+                        recorder.record(data);
+                    }
+                }
+            },
+            move |err| {
+                error!("Pre-roll stream error: {}", err);
+                if matches!(err, StreamError::DeviceNotAvailable) {
+                    disconnected.store(true, Ordering::Relaxed);
+                }
+            },
+            None,
+        )?)
+    }
+
     /// Start recording audio data.
     ///
-    /// Returns an error if the audio stream could not be built or played. This can happen if the device is no longer
-    /// available.
+    /// If a pre-roll is set with [`Listener::set_pre_roll`], the recording starts with the buffered audio.
+    ///
+    /// If the device is no longer available, it is searched for again up to [`Listener::reconnect_attempts`] times
+    /// before an error is returned.
     ///
     /// # Examples
     ///
@@ -87,48 +794,190 @@ impl Listener {
     /// # instance.stop().unwrap();
     /// ```
     pub fn start(&self) -> Result<ListenerInstance, Error> {
+        self.start_recording(true)
+    }
+
+    /// Start recording audio data, optionally without the buffered audio of the pre-roll.
+    ///
+    /// # Arguments
+    ///
+    /// * `with_pre_roll`: Whether to start the recording with the audio buffered by the pre-roll, if it is set.
+    fn start_recording(&self, with_pre_roll: bool) -> Result<ListenerInstance, Error> {
         info!("Listening has begun");
 
         let writer = Arc::new(Mutex::new(Vec::with_capacity(
-            self.device_config.sample_rate.0 as usize * RECORDING_BUFFER_CAPACITY_SECONDS,
+            self.device_config().sample_rate.0 as usize * RECORDING_BUFFER_CAPACITY_SECONDS,
         )));
-        let writer_2 = writer.clone();
-        let (average_sender, average) = channel();
-        let mut running_average = NoSumSMA::<_, f32, { MOVING_AVERAGE_WINDOW_SIZE }>::new();
-        let mut sample_count: u32 = 0;
-
-        let stream = self.device.build_input_stream(
-            &self.device_config,
-            move |data: &[f32], _| {
-                if let Ok(mut guard) = writer_2.try_lock() {
-                    for &sample in data.iter() {
-                        guard.push(sample);
-                        running_average.add_sample(sample.abs());
-                        sample_count += 1;
-                        if sample_count >= MOVING_AVERAGE_WINDOW_SIZE as u32 {
-                            trace!("{}", running_average.get_average());
-                            if average_sender.send(running_average.get_average()).is_err() {
-                                warn!("Unable to send recording average");
-                            }
-                            sample_count = 0;
-                        }
-                    }
+        let (frame_sender, frames) = channel();
+        let subscribers = Arc::new(Mutex::new(Vec::new()));
+
+        let pre_roll_disconnected = self
+            .pre_roll()
+            .as_ref()
+            .is_some_and(|pre_roll| pre_roll.disconnected.load(Ordering::Relaxed));
+        if pre_roll_disconnected {
+            self.reconnect(|| Ok(()))?;
+        }
+        let pre_roll = self
+            .pre_roll()
+            .as_ref()
+            .map(|pre_roll| (pre_roll.buffer.clone(), pre_roll.disconnected.clone()));
+
+        let (source, disconnected) = match pre_roll {
+            Some((buffer, disconnected)) => {
+                // the buffered audio is copied while the stream is blocked, so no samples are lost or repeated
+                let mut guard = buffer.lock().unwrap_or_else(PoisonError::into_inner);
+                if with_pre_roll {
+                    writer
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .extend(guard.samples.iter());
                 }
-            },
-            move |err| error!("Audio stream error: {}", err),
-            self.recording_timeout,
-        )?;
-        stream.play()?;
+                guard.recorders.push(Recorder::new(
+                    writer.clone(),
+                    frame_sender.clone(),
+                    subscribers.clone(),
+                    &self.device_config(),
+                ));
+                drop(guard);
+
+                (Source::PreRoll(buffer), disconnected)
+            }
+            None => {
+                let disconnected = Arc::new(AtomicBool::new(false));
+                let connect = || {
+                    self.build_recording_stream(
+                        writer.clone(),
+                        frame_sender.clone(),
+                        subscribers.clone(),
+                        disconnected.clone(),
+                    )
+                };
+                let stream = match connect() {
+                    Err(Error::AudioDeviceNotFound) => self.reconnect(connect)?,
+                    stream => stream?,
+                };
+
+                (Source::Stream(stream), disconnected)
+            }
+        };
 
         Ok(ListenerInstance {
-            stream,
+            source,
             writer,
-            average,
-            channels: u8::try_from(self.device_config.channels).map_err(|_| Error::OutOfRange)?,
-            sample_rate: self.device_config.sample_rate.0,
+            frames,
+            frame_sender,
+            subscribers,
+            disconnected,
+            channels: u8::try_from(self.device_config().channels).map_err(|_| Error::OutOfRange)?,
+            sample_rate: self.device_config().sample_rate.0,
         })
     }
 
+    /// Build and play the input stream of a recording with the sample format of the device.
+    ///
+    /// # Arguments
+    ///
+    /// See [`Listener::build_stream`].
+    fn build_recording_stream(
+        &self,
+        writer: Arc<Mutex<Vec<f32>>>,
+        frame_sender: Sender<Frame>,
+        subscribers: Arc<Mutex<Vec<Subscriber>>>,
+        disconnected: Arc<AtomicBool>,
+    ) -> Result<Stream, Error> {
+        let stream = match self.sample_format() {
+            SampleFormat::F32 => {
+                self.build_stream::<f32>(writer, frame_sender, subscribers, disconnected)?
+            }
+            SampleFormat::I16 => {
+                self.build_stream::<i16>(writer, frame_sender, subscribers, disconnected)?
+            }
+            SampleFormat::U16 => {
+                self.build_stream::<u16>(writer, frame_sender, subscribers, disconnected)?
+            }
+            _ => return Err(Error::ConfigurationNotSupported),
+        };
+        stream.play()?;
+
+        Ok(stream)
+    }
+
+    /// Rebuild the stream of a recording if its device was disconnected, see [`Listener::reconnect`].
+    ///
+    /// The audio recorded after the device is found again is appended to the recording, so it has a gap where the
+    /// device was disconnected.
+    ///
+    /// Returns [`Error::SampleRateChanged`] if the device records at another sample rate after it was found again.
+    ///
+    /// # Arguments
+    ///
+    /// * `instance`: The [`ListenerInstance`] to restore.
+    fn restore(&self, instance: &mut ListenerInstance) -> Result<(), Error> {
+        if !instance.disconnected.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+
+        if matches!(instance.source, Source::PreRoll(_)) {
+            // restarting the pre-roll keeps feeding the recording
+            self.reconnect(|| Ok(()))?;
+        } else {
+            instance.source = Source::Stream(self.reconnect(|| {
+                self.build_recording_stream(
+                    instance.writer.clone(),
+                    instance.frame_sender.clone(),
+                    instance.subscribers.clone(),
+                    instance.disconnected.clone(),
+                )
+            })?);
+        }
+        instance.disconnected.store(false, Ordering::Relaxed);
+
+        // the recording cannot continue with samples of another rate
+        let sample_rate = self.device_config().sample_rate.0;
+        if sample_rate != instance.sample_rate {
+            return Err(Error::SampleRateChanged(instance.sample_rate, sample_rate));
+        }
+
+        Ok(())
+    }
+
+    /// Build the input stream of the device for samples of type `T`, which are converted to `f32` and written to the
+    /// recording.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer`: The recording the samples are written to.
+    /// * `frame_sender`: Where a [`Frame`] with the moving average of the sample levels, the zero-crossing rate and
+    ///   the octave band energies of every window is sent to.
+    /// * `subscribers`: The subscribers every recorded chunk of samples is delivered to.
+    /// * `disconnected`: Set once the device is no longer available.
+    fn build_stream<T>(
+        &self,
+        writer: Arc<Mutex<Vec<f32>>>,
+        frame_sender: Sender<Frame>,
+        subscribers: Arc<Mutex<Vec<Subscriber>>>,
+        disconnected: Arc<AtomicBool>,
+    ) -> Result<Stream, Error>
+    where
+        T: SizedSample,
+        f32: FromSample<T>,
+    {
+        let mut recorder = Recorder::new(writer, frame_sender, subscribers, &self.device_config());
+
+        Ok(self.device().build_input_stream(
+            &self.device_config(),
+            move |data: &[T], _| recorder.record(data),
+            move |err| {
+                error!("Audio stream error: {}", err);
+                if matches!(err, StreamError::DeviceNotAvailable) {
+                    disconnected.store(true, Ordering::Relaxed);
+                }
+            },
+            self.recording_timeout,
+        )?)
+    }
+
     /// Record for a specified amount of seconds.
     ///
     /// This blocks until it is done.
@@ -152,55 +1001,252 @@ impl Listener {
     pub fn record_for(&self, seconds: u32, silence_threshold: f32) -> Result<AudioData, Error> {
         info!("Listening for {} seconds", seconds);
 
-        let instance = self.start()?;
-        for second in (1..=seconds).rev() {
-            debug!("{}...", second);
-            thread::sleep(Duration::from_secs(1));
+        let mut instance = self.start_recording(false)?;
+        for second in (1..=seconds).rev() {
+            debug!("{}...", second);
+            thread::sleep(Duration::from_secs(1));
+            self.restore(&mut instance)?;
+        }
+
+        let mut audio = instance.stop()?;
+        audio.trim_silence(silence_threshold);
+
+        Ok(audio)
+    }
+
+    /// Record for a specified amount of seconds without blocking the thread, like [`Listener::record_for`].
+    ///
+    /// The returned future is not `Send`, because the audio stream cannot be moved between threads. Await it directly
+    /// within a Tokio runtime instead of spawning it.
+    ///
+    /// # Arguments
+    ///
+    /// * `seconds`: How many seconds to record for.
+    /// * `silence_threshold`: The highest level that is considered silence when trimming the recording.
+    ///
+    /// Returns the recorded [`AudioData`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use varys_audio::listen::Listener;
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let listener = Listener::new().unwrap();
+    /// let audio = listener.record_for_async(3, 0.01).await.unwrap();
+    /// # }
+    /// ```
+    pub async fn record_for_async(
+        &self,
+        seconds: u32,
+        silence_threshold: f32,
+    ) -> Result<AudioData, Error> {
+        info!("Listening for {} seconds", seconds);
+
+        let mut instance = self.start_recording(false)?;
+        for _ in 0..seconds {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            self.restore(&mut instance)?;
+        }
+
+        let mut audio = instance.stop()?;
+        audio.trim_silence(silence_threshold);
+
+        Ok(audio)
+    }
+
+    /// Record until silence is detected for a certain amount of time. The current thread is blocked until recording is
+    /// done.
+    ///
+    /// Returns an error if the audio stream could not be built or played. This can happen if the device is no longer
+    /// available.
+    ///
+    /// # Arguments
+    ///
+    /// * `silence_duration`: How long a silence must be for the recording to be stopped.
+    /// * `silence_threshold`: The highest frequency that is considered silence.
+    ///
+    /// Returns the recorded [`AudioData`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use std::time;
+    /// # use varys_audio::listen::Listener;
+    /// let listener = Listener::new().unwrap();
+    /// let audio = listener.record_until_silent(time::Duration::from_secs(0), 0.01);
+    /// ```
+    pub fn record_until_silent(
+        &self,
+        silence_duration: Duration,
+        silence_threshold: f32,
+    ) -> Result<AudioData, Error> {
+        info!(
+            "Listening until silent for {} seconds...",
+            silence_duration.as_secs()
+        );
+
+        let mut audio = self.record_until_silent_untrimmed(silence_duration, silence_threshold)?;
+        audio.trim_silence(silence_threshold);
+
+        Ok(audio)
+    }
+
+    /// Record until silence is detected for a certain amount of time without blocking the thread, like
+    /// [`Listener::record_until_silent`].
+    ///
+    /// The returned future is not `Send`, because the audio stream cannot be moved between threads. Await it directly
+    /// within a Tokio runtime instead of spawning it.
+    ///
+    /// # Arguments
+    ///
+    /// * `silence_duration`: How long a silence must be for the recording to be stopped.
+    /// * `silence_threshold`: The highest frequency that is considered silence.
+    ///
+    /// Returns the recorded [`AudioData`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use std::time;
+    /// # use varys_audio::listen::Listener;
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let listener = Listener::new().unwrap();
+    /// let audio = listener
+    ///     .record_until_silent_async(time::Duration::from_secs(2), 0.01)
+    ///     .await
+    ///     .unwrap();
+    /// # }
+    /// ```
+    pub async fn record_until_silent_async(
+        &self,
+        silence_duration: Duration,
+        silence_threshold: f32,
+    ) -> Result<AudioData, Error> {
+        info!(
+            "Listening until silent for {} seconds...",
+            silence_duration.as_secs()
+        );
+
+        let mut instance = self.start_recording(false)?;
+        self.run_instance_until_silent_async(
+            &mut instance,
+            silence_duration,
+            silence_threshold,
+            false,
+        )
+        .await?;
+
+        let mut audio = instance.stop()?;
+        audio.trim_silence(silence_threshold);
+
+        Ok(audio)
+    }
+
+    /// Record until silence is detected for a certain amount of time, like [`Listener::record_until_silent`], but
+    /// without trimming silence from the recorded audio.
+    ///
+    /// This keeps the silence before the first sound, so it can be used to measure when sound started.
+    ///
+    /// # Arguments
+    ///
+    /// * `silence_duration`: How long a silence must be for the recording to be stopped.
+    /// * `silence_threshold`: The highest frequency that is considered silence.
+    ///
+    /// Returns the recorded [`AudioData`].
+    pub fn record_until_silent_untrimmed(
+        &self,
+        silence_duration: Duration,
+        silence_threshold: f32,
+    ) -> Result<AudioData, Error> {
+        let mut instance = self.start_recording(false)?;
+        self.run_instance_until_silent(&mut instance, silence_duration, silence_threshold, true)?;
+
+        instance.stop()
+    }
+
+    /// Record until silence is detected for a certain amount of time, like [`Listener::record_until_silent_untrimmed`],
+    /// but keep the audio if the recording timeout passes while there is sound.
+    ///
+    /// A recording that is still going on at the timeout was cut off, e.g. because the response is longer than the
+    /// timeout allows. Without any sound, the timeout is still an error.
+    ///
+    /// # Arguments
+    ///
+    /// * `silence_duration`: How long a silence must be for the recording to be stopped.
+    /// * `silence_threshold`: The highest frequency that is considered silence.
+    ///
+    /// Returns the recorded [`AudioData`] and whether the recording was stopped by the timeout.
+    pub fn record_until_silent_or_timeout(
+        &self,
+        silence_duration: Duration,
+        silence_threshold: f32,
+    ) -> Result<(AudioData, bool), Error> {
+        let mut instance = self.start_recording(false)?;
+        let mut wait = self.silence_wait(silence_duration, true);
+        let mut timed_out = false;
+
+        while let Some(frame) = self.next_frame(&mut instance)? {
+            match wait.update(self.silence_detector.is_sound(&frame, silence_threshold)) {
+                Ok(true) => break,
+                Ok(false) => {}
+                Err(Error::RecordingTimeout) if wait.heard_sound() => {
+                    warn!("The recording timeout passed before silence was detected");
+                    timed_out = true;
+                    break;
+                }
+                Err(error) => return Err(error),
+            }
         }
 
-        let mut audio = instance.stop()?;
-        audio.trim_silence(silence_threshold);
-
-        Ok(audio)
+        Ok((instance.stop()?, timed_out))
     }
 
-    /// Record until silence is detected for a certain amount of time. The current thread is blocked until recording is
-    /// done.
+    /// Record until silence is detected for a certain amount of time without blocking the thread, like
+    /// [`Listener::record_until_silent_or_timeout`].
     ///
-    /// Returns an error if the audio stream could not be built or played. This can happen if the device is no longer
-    /// available.
+    /// The returned future is not `Send`, because the audio stream cannot be moved between threads. Await it directly
+    /// within a Tokio runtime instead of spawning it.
     ///
     /// # Arguments
     ///
     /// * `silence_duration`: How long a silence must be for the recording to be stopped.
     /// * `silence_threshold`: The highest frequency that is considered silence.
     ///
-    /// Returns the recorded [`AudioData`].
+    /// Returns the recorded [`AudioData`] and whether the recording was stopped by the timeout.
     ///
     /// # Examples
     ///
     /// ```no_run
     /// # use std::time;
     /// # use varys_audio::listen::Listener;
-    /// let listener = Listener::new().unwrap();
-    /// let audio = listener.record_until_silent(time::Duration::from_secs(0), 0.01);
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let mut listener = Listener::new().unwrap();
+    /// listener.recording_timeout = Some(time::Duration::from_secs(10));
+    /// let (audio, truncated) = listener
+    ///     .record_until_silent_or_timeout_async(time::Duration::from_secs(2), 0.01)
+    ///     .await
+    ///     .unwrap();
+    /// # }
     /// ```
-    pub fn record_until_silent(
+    pub async fn record_until_silent_or_timeout_async(
         &self,
         silence_duration: Duration,
         silence_threshold: f32,
-    ) -> Result<AudioData, Error> {
-        info!(
-            "Listening until silent for {} seconds...",
-            silence_duration.as_secs()
-        );
-
-        let instance = self.start()?;
-        self.run_instance_until_silent(&instance, silence_duration, silence_threshold, true)?;
-        let mut audio = instance.stop()?;
-        audio.trim_silence(silence_threshold);
+    ) -> Result<(AudioData, bool), Error> {
+        let mut instance = self.start_recording(false)?;
+        let timed_out = self
+            .run_instance_until_silent_async(
+                &mut instance,
+                silence_duration,
+                silence_threshold,
+                true,
+            )
+            .await?;
 
-        Ok(audio)
+        Ok((instance.stop()?, timed_out))
     }
 
     /// Wait until silence is detected for a certain amount of time.
@@ -235,9 +1281,9 @@ impl Listener {
             silence_duration.as_secs()
         );
 
-        let instance = self.start()?;
+        let mut instance = self.start_recording(false)?;
         self.run_instance_until_silent(
-            &instance,
+            &mut instance,
             silence_duration,
             silence_threshold,
             require_sound,
@@ -255,20 +1301,31 @@ impl Listener {
     /// Returns an error if the audio stream could not be built or played. This can happen if the
     /// device is no longer available.
     pub fn calibrate(&self) -> Result<f32, Error> {
+        self.silence_threshold(SilenceThreshold::Deviation(0.0))
+    }
+
+    /// Listen for five seconds and get the levels of the ambient noise, which are the moving averages used to detect
+    /// silence.
+    ///
+    /// This blocks until it is done.
+    ///
+    /// Returns an error if the audio stream could not be built or played. This can happen if the
+    /// device is no longer available.
+    pub fn ambient_levels(&self) -> Result<Vec<f32>, Error> {
         info!("Recording ambient noise...");
 
-        let instance = self.start()?;
+        let mut instance = self.start_recording(false)?;
         let started = Instant::now();
         let mut averages = Vec::new();
-        while let Ok(average) = instance.average.recv() {
-            averages.push(average);
+        while let Some(frame) = self.next_frame(&mut instance)? {
+            averages.push(frame.level);
             if started < Instant::now() - CALIBRATION_TIMEOUT {
                 break;
             }
         }
         instance.stop()?;
 
-        Ok(averages.iter().sum::<f32>() / averages.len() as f32)
+        Ok(averages)
     }
 
     /// Run a [`ListenerInstance`] until silence is detected for a certain amount of time.
@@ -281,53 +1338,516 @@ impl Listener {
     /// * `silence_duration`: How long of a silence to wait for.
     /// * `silence_threshold`: The highest frequency that is considered silence.
     /// * `require_sound`: Whether to require sound to be detected before starting to listen for silence.
+    ///
+    /// Which parts are sound is decided by the [`SilenceDetector`] of the listener.
     fn run_instance_until_silent(
         &self,
-        instance: &ListenerInstance,
+        instance: &mut ListenerInstance,
         silence_duration: Duration,
         silence_threshold: f32,
         require_sound: bool,
     ) -> Result<(), Error> {
-        if self.recording_timeout.is_none() {
-            warn!("No recording timeout set. Recording will continue until silence is detected.");
+        let mut wait = self.silence_wait(silence_duration, require_sound);
+
+        while let Some(frame) = self.next_frame(instance)? {
+            if wait.update(self.silence_detector.is_sound(&frame, silence_threshold))? {
+                break;
+            }
         }
 
-        let started = Instant::now();
-        let mut last_audio_detected = if require_sound { None } else { Some(started) };
+        Ok(())
+    }
 
-        while let Ok(average) = instance.average.recv() {
-            let now = Instant::now();
-            if average > silence_threshold {
-                last_audio_detected = Some(now);
+    /// Wait for the next [`Frame`] of a [`ListenerInstance`], restoring its stream if the device was disconnected.
+    ///
+    /// Returns `None` if the stream stopped sending frames.
+    ///
+    /// # Arguments
+    ///
+    /// * `instance`: The [`ListenerInstance`] to receive the frame from.
+    fn next_frame(&self, instance: &mut ListenerInstance) -> Result<Option<Frame>, Error> {
+        loop {
+            match instance.frames.recv_timeout(FRAME_TIMEOUT) {
+                Ok(frame) => return Ok(Some(frame)),
+                Err(RecvTimeoutError::Timeout) => self.restore(instance)?,
+                Err(RecvTimeoutError::Disconnected) => return Ok(None),
             }
-            if let Some(last_audio_detected) = last_audio_detected {
-                if last_audio_detected < now - silence_duration {
+        }
+    }
+
+    /// Run a [`ListenerInstance`] until silence is detected for a certain amount of time without blocking the thread,
+    /// like [`Listener::run_instance_until_silent`] with sound required.
+    ///
+    /// The frames of the instance are received on a blocking task of the Tokio runtime and forwarded to this future.
+    ///
+    /// Returns whether the recording timeout passed while there was sound, which is only kept if `keep_on_timeout` is
+    /// set and an error otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `instance`: The [`ListenerInstance`] to listen on.
+    /// * `silence_duration`: How long of a silence to wait for.
+    /// * `silence_threshold`: The highest frequency that is considered silence.
+    /// * `keep_on_timeout`: Whether to stop without an error if the recording timeout passes after sound was heard.
+    async fn run_instance_until_silent_async(
+        &self,
+        instance: &mut ListenerInstance,
+        silence_duration: Duration,
+        silence_threshold: f32,
+        keep_on_timeout: bool,
+    ) -> Result<bool, Error> {
+        let (frame_sender, mut frames) = mpsc::unbounded_channel();
+        let (_, closed) = channel();
+        let instance_frames = std::mem::replace(&mut instance.frames, closed);
+        // the task ends once the instance is stopped or this future stops receiving
+        task::spawn_blocking(move || {
+            while let Ok(frame) = instance_frames.recv() {
+                if frame_sender.send(frame).is_err() {
                     break;
                 }
             }
-            if let Some(timeout) = self.recording_timeout {
-                if started < now - timeout {
-                    return Err(Error::RecordingTimeout);
+        });
+
+        let mut wait = self.silence_wait(silence_duration, true);
+
+        loop {
+            let frame = match tokio::time::timeout(FRAME_TIMEOUT, frames.recv()).await {
+                Ok(Some(frame)) => frame,
+                Ok(None) => break,
+                Err(_) => {
+                    // the new stream sends to the same channel, so the forwarding task keeps running
+                    self.restore(instance)?;
+                    continue;
+                }
+            };
+            match wait.update(self.silence_detector.is_sound(&frame, silence_threshold)) {
+                Ok(true) => break,
+                Ok(false) => {}
+                Err(Error::RecordingTimeout) if keep_on_timeout && wait.heard_sound() => {
+                    warn!("The recording timeout passed before silence was detected");
+                    return Ok(true);
                 }
+                Err(error) => return Err(error),
             }
         }
 
-        Ok(())
+        Ok(false)
+    }
+
+    fn silence_wait(&self, silence_duration: Duration, require_sound: bool) -> SilenceWait {
+        if self.recording_timeout.is_none() {
+            warn!("No recording timeout set. Recording will continue until silence is detected.");
+        }
+
+        let started = Instant::now();
+
+        SilenceWait {
+            started,
+            last_audio_detected: if require_sound { None } else { Some(started) },
+            silence_duration,
+            recording_timeout: self.recording_timeout,
+        }
+    }
+}
+
+/// The state of waiting for silence, updated with every [`Frame`] of a recording.
+struct SilenceWait {
+    started: Instant,
+    last_audio_detected: Option<Instant>,
+    silence_duration: Duration,
+    recording_timeout: Option<Duration>,
+}
+
+impl SilenceWait {
+    /// Update the state with the latest frame.
+    ///
+    /// Returns whether the silence has lasted long enough, or an error if the recording timeout passed.
+    ///
+    /// # Arguments
+    ///
+    /// * `is_sound`: Whether the latest frame contains sound.
+    fn update(&mut self, is_sound: bool) -> Result<bool, Error> {
+        let now = Instant::now();
+        if is_sound {
+            self.last_audio_detected = Some(now);
+        }
+        if let Some(last_audio_detected) = self.last_audio_detected {
+            if last_audio_detected < now - self.silence_duration {
+                return Ok(true);
+            }
+        }
+        if let Some(timeout) = self.recording_timeout {
+            if self.started < now - timeout {
+                return Err(Error::RecordingTimeout);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Whether any sound was detected since the wait started.
+    fn heard_sound(&self) -> bool {
+        self.last_audio_detected.is_some()
+    }
+}
+
+/// Choose the configuration a device records with, at [`OPUS_SAMPLE_RATE`] if the device supports it and otherwise at
+/// its highest sample rate, e.g. 44.1 kHz.
+///
+/// Recordings at other sample rates are resampled to [`OPUS_SAMPLE_RATE`] when they are stopped, see
+/// [`ListenerInstance::stop`]. Samples as `f32` need no conversion, so integer samples are only used if the device
+/// offers nothing else.
+///
+/// # Arguments
+///
+/// * `device`: The device to record with.
+/// * `channels`: How many channels to record, or `None` for any number.
+fn stream_config(
+    device: &Device,
+    channels: Option<u16>,
+) -> Result<(StreamConfig, SampleFormat), Error> {
+    let opus_sample_rate = SampleRate(OPUS_SAMPLE_RATE as u32);
+    let supports_opus = |config: &SupportedStreamConfigRange| {
+        (config.min_sample_rate()..=config.max_sample_rate()).contains(&opus_sample_rate)
+    };
+    let supported_config = device
+        .supported_input_configs()?
+        .filter(is_supported)
+        .filter(|config| channels.is_none_or(|channels| config.channels() == channels))
+        .min_by_key(|config| {
+            (
+                !supports_opus(config),
+                config.sample_format() != SampleFormat::F32,
+            )
+        })
+        .ok_or(Error::ConfigurationNotSupported)?;
+    let supported_config = if supports_opus(&supported_config) {
+        supported_config.with_sample_rate(opus_sample_rate)
+    } else {
+        supported_config.with_max_sample_rate()
+    };
+    let sample_format = supported_config.sample_format();
+    let device_config: StreamConfig = supported_config.into();
+    debug!("Using audio input config {device_config:?} with {sample_format} samples");
+
+    Ok((device_config, sample_format))
+}
+
+/// Whether a [`Listener`] can record with a stream configuration: it needs samples as `f32`, `i16` or `u16` at a
+/// sample rate high enough for transcription. Integer samples are converted to `f32`, and any sample rate is resampled
+/// to [`OPUS_SAMPLE_RATE`].
+///
+/// # Arguments
+///
+/// * `config`: The stream configuration supported by an input device.
+fn is_supported(config: &SupportedStreamConfigRange) -> bool {
+    SUPPORTED_SAMPLE_FORMATS.contains(&config.sample_format())
+        && config.max_sample_rate().0 >= stt::SAMPLE_RATE
+}
+
+impl Listen for Listener {
+    fn start(&self) -> Result<Box<dyn ListenInstance>, Error> {
+        Ok(Box::new(Listener::start(self)?))
+    }
+
+    fn record_until_silent_untrimmed(
+        &self,
+        silence_duration: Duration,
+        silence_threshold: f32,
+    ) -> Result<AudioData, Error> {
+        Listener::record_until_silent_untrimmed(self, silence_duration, silence_threshold)
+    }
+
+    fn record_until_silent_or_timeout(
+        &self,
+        silence_duration: Duration,
+        silence_threshold: f32,
+    ) -> Result<(AudioData, bool), Error> {
+        Listener::record_until_silent_or_timeout(self, silence_duration, silence_threshold)
+    }
+
+    fn record_until_silent_or_timeout_async(
+        &self,
+        silence_duration: Duration,
+        silence_threshold: f32,
+    ) -> RecordingFuture<'_> {
+        Box::pin(Listener::record_until_silent_or_timeout_async(
+            self,
+            silence_duration,
+            silence_threshold,
+        ))
+    }
+
+    fn wait_until_silent(
+        &self,
+        silence_duration: Duration,
+        silence_threshold: f32,
+        require_sound: bool,
+    ) -> Result<(), Error> {
+        Listener::wait_until_silent(self, silence_duration, silence_threshold, require_sound)
+    }
+
+    fn set_recording_timeout(&mut self, recording_timeout: Option<Duration>) {
+        self.recording_timeout = recording_timeout;
+    }
+
+    fn set_silence_detector(&mut self, silence_detector: Box<dyn SilenceDetector>) {
+        self.silence_detector = silence_detector;
+    }
+
+    fn set_pre_roll(&mut self, pre_roll: Option<Duration>) -> Result<(), Error> {
+        Listener::set_pre_roll(self, pre_roll)
+    }
+
+    fn set_reconnect_attempts(&mut self, reconnect_attempts: u32) {
+        self.reconnect_attempts = reconnect_attempts;
+    }
+
+    fn ambient_levels(&self) -> Result<Vec<f32>, Error> {
+        Listener::ambient_levels(self)
+    }
+}
+
+/// Anything that can record audio like a [`Listener`].
+///
+/// This allows replacing the microphone with [`fake::FakeListener`] where no sound card is available.
+pub trait Listen {
+    /// Start recording.
+    ///
+    /// Returns a [`ListenInstance`] that can be stopped to get the recorded audio.
+    fn start(&self) -> Result<Box<dyn ListenInstance>, Error>;
+
+    /// Record until silence is detected for a certain amount of time.
+    ///
+    /// See [`Listener::record_until_silent`].
+    fn record_until_silent(
+        &self,
+        silence_duration: Duration,
+        silence_threshold: f32,
+    ) -> Result<AudioData, Error> {
+        let mut audio = self.record_until_silent_untrimmed(silence_duration, silence_threshold)?;
+        audio.trim_silence(silence_threshold);
+
+        Ok(audio)
+    }
+
+    /// Record until silence is detected for a certain amount of time without trimming silence.
+    ///
+    /// See [`Listener::record_until_silent_untrimmed`].
+    fn record_until_silent_untrimmed(
+        &self,
+        silence_duration: Duration,
+        silence_threshold: f32,
+    ) -> Result<AudioData, Error>;
+
+    /// Record until silence is detected for a certain amount of time without trimming silence, keeping the audio if
+    /// the recording timeout passes while there is sound.
+    ///
+    /// Returns the recorded audio and whether the recording was stopped by the timeout. By default, a timeout is
+    /// always an error.
+    ///
+    /// See [`Listener::record_until_silent_or_timeout`].
+    fn record_until_silent_or_timeout(
+        &self,
+        silence_duration: Duration,
+        silence_threshold: f32,
+    ) -> Result<(AudioData, bool), Error> {
+        self.record_until_silent_untrimmed(silence_duration, silence_threshold)
+            .map(|audio| (audio, false))
+    }
+
+    /// Record like [`Listen::record_until_silent_or_timeout`] without blocking the runtime the returned future is
+    /// awaited on.
+    ///
+    /// By default, the recording is made right away with [`Listen::record_until_silent_or_timeout`].
+    ///
+    /// See [`Listener::record_until_silent_or_timeout_async`].
+    fn record_until_silent_or_timeout_async(
+        &self,
+        silence_duration: Duration,
+        silence_threshold: f32,
+    ) -> RecordingFuture<'_> {
+        Box::pin(future::ready(self.record_until_silent_or_timeout(
+            silence_duration,
+            silence_threshold,
+        )))
+    }
+
+    /// Wait until silence is detected for a certain amount of time.
+    ///
+    /// See [`Listener::wait_until_silent`].
+    fn wait_until_silent(
+        &self,
+        silence_duration: Duration,
+        silence_threshold: f32,
+        require_sound: bool,
+    ) -> Result<(), Error>;
+
+    /// Set the maximum duration to record for.
+    ///
+    /// # Arguments
+    ///
+    /// * `recording_timeout`: The optional maximum duration to record for.
+    fn set_recording_timeout(&mut self, recording_timeout: Option<Duration>);
+
+    /// Set what decides which parts of a recording are sound when waiting for silence.
+    ///
+    /// # Arguments
+    ///
+    /// * `silence_detector`: The detector to use, see [`vad`].
+    fn set_silence_detector(&mut self, silence_detector: Box<dyn SilenceDetector>);
+
+    /// Keep recording the most recent audio, which is prepended to recordings started with [`Listen::start`].
+    ///
+    /// See [`Listener::set_pre_roll`].
+    ///
+    /// # Arguments
+    ///
+    /// * `pre_roll`: How much audio to keep, or `None` to stop buffering.
+    fn set_pre_roll(&mut self, pre_roll: Option<Duration>) -> Result<(), Error>;
+
+    /// Set how many times to try finding the device again if it was disconnected during a session.
+    ///
+    /// See [`Listener::reconnect_attempts`].
+    ///
+    /// # Arguments
+    ///
+    /// * `reconnect_attempts`: How many attempts to make before giving up.
+    fn set_reconnect_attempts(&mut self, reconnect_attempts: u32);
+
+    /// Get the levels of the ambient noise.
+    ///
+    /// See [`Listener::ambient_levels`].
+    fn ambient_levels(&self) -> Result<Vec<f32>, Error>;
+
+    /// Get the threshold that distinguishes silence from sound.
+    ///
+    /// If the threshold is adaptive, it is computed from the current ambient noise, which blocks while the noise is
+    /// recorded.
+    ///
+    /// # Arguments
+    ///
+    /// * `threshold`: How the threshold is determined.
+    fn silence_threshold(&self, threshold: SilenceThreshold) -> Result<f32, Error> {
+        let levels = if threshold.is_adaptive() {
+            self.ambient_levels()?
+        } else {
+            Vec::new()
+        };
+
+        threshold.compute(&levels).ok_or(Error::CalibrationFailed)
     }
 }
 
+/// A running recording started with [`Listen::start`].
+pub trait ListenInstance {
+    /// Stop recording and get the recorded audio data.
+    fn stop(self: Box<Self>) -> Result<AudioData, Error>;
+}
+
 /// A handle to a running listener instance. It can be stopped with [`ListenerInstance::stop`].
 pub struct ListenerInstance {
-    stream: Stream,
+    source: Source,
     writer: Arc<Mutex<Vec<f32>>>,
-    average: Receiver<f32>,
+    frames: Receiver<Frame>,
+    /// Sends the frames of the stream, kept to rebuild the stream after the device was disconnected.
+    frame_sender: Sender<Frame>,
+    subscribers: Arc<Mutex<Vec<Subscriber>>>,
+    /// Set by the stream once its device is no longer available.
+    disconnected: Arc<AtomicBool>,
     channels: u8,
     sample_rate: u32,
 }
 
 impl ListenerInstance {
+    /// Call a function with every chunk of audio recorded from now on, e.g. to visualise the recording live.
+    ///
+    /// The function is called on the audio thread of the device, so it has to return quickly or samples are lost. Use
+    /// [`ListenerInstance::subscribe_channel`] for anything slower.
+    ///
+    /// # Arguments
+    ///
+    /// * `callback`: The function to call with every chunk.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use varys_audio::listen::Listener;
+    /// let instance = Listener::new().unwrap().start().unwrap();
+    /// instance.subscribe(|chunk| println!("level {:.3}, peak {:.3}", chunk.level, chunk.peak));
+    /// # instance.stop().unwrap();
+    /// ```
+    pub fn subscribe<F>(&self, mut callback: F)
+    where
+        F: FnMut(&AudioChunk) + Send + 'static,
+    {
+        self.add_subscriber(Box::new(move |chunk| {
+            callback(chunk);
+            true
+        }));
+    }
+
+    /// Get a channel that receives every chunk of audio recorded from now on, e.g. for streaming recognition on
+    /// another thread.
+    ///
+    /// The channel is closed when the instance is stopped and no more chunks are sent once the receiver is dropped.
+    ///
+    /// The chunks only contain the audio recorded after subscribing, at the sample rate of the device, see
+    /// [`ListenerInstance::sample_rate`]. They do not contain the buffered audio of a pre-roll and are not resampled like
+    /// the recording returned by [`ListenerInstance::stop`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use std::thread;
+    /// # use std::time::Duration;
+    /// # use varys_audio::listen::Listener;
+    /// let instance = Listener::new().unwrap().start().unwrap();
+    /// let (channels, sample_rate) = (instance.channels() as usize, instance.sample_rate() as usize);
+    /// let chunks = instance.subscribe_channel();
+    /// let consumer = thread::spawn(move || chunks.iter().collect::<Vec<_>>());
+    ///
+    /// thread::sleep(Duration::from_secs(1));
+    /// instance.stop().unwrap();
+    ///
+    /// let chunks = consumer.join().unwrap();
+    /// assert!(!chunks.is_empty());
+    /// assert!(chunks.iter().all(|chunk| chunk.samples.len() % channels == 0));
+    /// let frames = chunks.iter().map(|chunk| chunk.samples.len()).sum::<usize>() / channels;
+    /// assert!(frames >= sample_rate / 2 && frames <= sample_rate * 2);
+    /// ```
+    pub fn subscribe_channel(&self) -> Receiver<AudioChunk> {
+        let (sender, receiver) = channel();
+        self.add_subscriber(Box::new(move |chunk| sender.send(chunk.clone()).is_ok()));
+
+        receiver
+    }
+
+    /// Get the number of channels of the recorded audio, whose samples are interleaved in the chunks sent to
+    /// subscribers.
+    pub fn channels(&self) -> u8 {
+        self.channels
+    }
+
+    /// Get the sample rate of the device, which the chunks sent to subscribers are recorded at.
+    ///
+    /// The recording returned by [`ListenerInstance::stop`] is resampled to [`OPUS_SAMPLE_RATE`] instead.
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn add_subscriber(&self, subscriber: Subscriber) {
+        match self.subscribers.lock() {
+            Ok(mut subscribers) => subscribers.push(subscriber),
+            Err(_) => warn!("Unable to subscribe to the recording"),
+        }
+    }
+
     /// Stop the running listener consuming the instance and get the recorded audio data.
     ///
+    /// If the device records at another sample rate than [`OPUS_SAMPLE_RATE`], the audio is resampled to it, so every
+    /// recording can be stored as Opus. The chunks sent to subscribers keep the sample rate of the device.
+    ///
     /// Returns the recorded [`AudioData`].
     ///
     /// # Examples
@@ -340,16 +1860,36 @@ impl ListenerInstance {
     pub fn stop(self) -> Result<AudioData, Error> {
         info!("Stopped listening");
 
-        drop(self.stream);
+        match self.source {
+            Source::Stream(stream) => drop(stream),
+            Source::PreRoll(buffer) => buffer
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .recorders
+                .retain(|recorder| !Arc::ptr_eq(&recorder.writer, &self.writer)),
+        }
+        drop(self.frame_sender);
+        drop(self.subscribers);
         let data = Arc::try_unwrap(self.writer)
             .map_err(|_| Error::StillRecording)?
             .into_inner()
             .map_err(|_| Error::RecordingFailed)?;
 
-        Ok(AudioData {
+        let mut audio = AudioData {
             data,
             channels: self.channels,
             sample_rate: self.sample_rate,
-        })
+        };
+        if audio.sample_rate != OPUS_SAMPLE_RATE as u32 {
+            audio.resample(OPUS_SAMPLE_RATE as u32, ResampleQuality::High)?;
+        }
+
+        Ok(audio)
+    }
+}
+
+impl ListenInstance for ListenerInstance {
+    fn stop(self: Box<Self>) -> Result<AudioData, Error> {
+        ListenerInstance::stop(*self)
     }
 }
@@ -0,0 +1,140 @@
+use std::f32::consts::PI;
+use std::time::Duration;
+
+use log::info;
+
+use crate::audio::{AudioData, OPUS_SAMPLE_RATE};
+use crate::error::Error;
+use crate::listen::vad::SilenceDetector;
+use crate::listen::{Listen, ListenInstance, MOVING_AVERAGE_WINDOW_SIZE};
+
+/// A [`Listen`] implementation that does not need a microphone.
+///
+/// Every recording returns the same synthetic audio, which makes it possible to run interactions without a sound card.
+pub struct FakeListener {
+    audio: AudioData,
+    /// The maximum duration to record for. This is stored but has no effect.
+    pub recording_timeout: Option<Duration>,
+}
+
+impl FakeListener {
+    /// Create a fake listener that returns the given audio for every recording.
+    ///
+    /// # Arguments
+    ///
+    /// * `audio`: The audio that is "recorded".
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use varys_audio::audio::AudioData;
+    /// # use varys_audio::listen::Listen;
+    /// # use varys_audio::listen::fake::FakeListener;
+    /// let listener = FakeListener::new(AudioData {
+    ///     data: vec![0.5; 16000],
+    ///     channels: 1,
+    ///     sample_rate: 16000,
+    /// });
+    ///
+    /// let audio = listener
+    ///     .record_until_silent(Duration::from_secs(2), 0.01)
+    ///     .unwrap();
+    /// assert_eq!(audio.data.len(), 16000);
+    /// ```
+    pub fn new(audio: AudioData) -> Self {
+        FakeListener {
+            audio,
+            recording_timeout: None,
+        }
+    }
+
+    /// Create a fake listener that returns a sine tone for every recording.
+    ///
+    /// # Arguments
+    ///
+    /// * `duration`: The duration of the tone.
+    /// * `frequency`: The frequency of the tone in hertz.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use varys_audio::listen::Listen;
+    /// # use varys_audio::listen::fake::FakeListener;
+    /// let listener = FakeListener::with_tone(Duration::from_secs(2), 440.0);
+    ///
+    /// let audio = listener.start().unwrap().stop().unwrap();
+    /// assert_eq!(audio.duration_ms(), 2000);
+    /// ```
+    pub fn with_tone(duration: Duration, frequency: f32) -> Self {
+        let sample_rate = OPUS_SAMPLE_RATE as u32;
+        let sample_count = (duration.as_secs_f32() * sample_rate as f32) as usize;
+        let data = (0..sample_count)
+            .map(|i| (2.0 * PI * frequency * i as f32 / sample_rate as f32).sin() * 0.5)
+            .collect();
+
+        Self::new(AudioData {
+            data,
+            channels: 1,
+            sample_rate,
+        })
+    }
+}
+
+impl Default for FakeListener {
+    /// A fake listener that records one second of a 440hz tone.
+    fn default() -> Self {
+        Self::with_tone(Duration::from_secs(1), 440.0)
+    }
+}
+
+impl Listen for FakeListener {
+    fn start(&self) -> Result<Box<dyn ListenInstance>, Error> {
+        info!("Fake listening has begun");
+
+        Ok(Box::new(FakeListenerInstance(self.audio.clone())))
+    }
+
+    fn record_until_silent_untrimmed(&self, _: Duration, _: f32) -> Result<AudioData, Error> {
+        Ok(self.audio.clone())
+    }
+
+    fn wait_until_silent(&self, _: Duration, _: f32, _: bool) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn set_recording_timeout(&mut self, recording_timeout: Option<Duration>) {
+        self.recording_timeout = recording_timeout;
+    }
+
+    fn set_silence_detector(&mut self, _: Box<dyn SilenceDetector>) {}
+
+    fn set_pre_roll(&mut self, _: Option<Duration>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn set_reconnect_attempts(&mut self, _: u32) {}
+
+    fn ambient_levels(&self) -> Result<Vec<f32>, Error> {
+        Ok(self
+            .audio
+            .data
+            .chunks(MOVING_AVERAGE_WINDOW_SIZE)
+            .map(|window| {
+                window.iter().map(|sample| sample.abs()).sum::<f32>() / window.len() as f32
+            })
+            .collect())
+    }
+}
+
+/// A handle to a running [`FakeListener`] recording.
+pub struct FakeListenerInstance(AudioData);
+
+impl ListenInstance for FakeListenerInstance {
+    fn stop(self: Box<Self>) -> Result<AudioData, Error> {
+        info!("Stopped fake listening");
+
+        Ok(self.0)
+    }
+}
@@ -0,0 +1,252 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::audio::bands::{BandAnalyser, BandEnergies};
+
+/// The statistics of a window of recorded samples, which a [`SilenceDetector`] decides on.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Frame {
+    /// The mean absolute sample value.
+    pub level: f32,
+    /// The share of consecutive samples that change sign, from `0` to `1`.
+    pub zero_crossing_rate: f32,
+    /// The energy of the window in every octave band.
+    pub bands: BandEnergies,
+}
+
+impl Frame {
+    /// Compute the statistics of a window of samples.
+    ///
+    /// # Arguments
+    ///
+    /// * `samples`: The samples of the window.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys_audio::listen::vad::Frame;
+    /// let frame = Frame::from_samples(&[0.5, -0.5, 0.5, 0.5, -0.5]);
+    ///
+    /// assert_eq!(frame.level, 0.5);
+    /// assert_eq!(frame.zero_crossing_rate, 0.75);
+    /// assert_eq!(Frame::from_samples(&[]), Frame::default());
+    /// ```
+    pub fn from_samples(samples: &[f32]) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+
+        let level = samples.iter().map(|sample| sample.abs()).sum::<f32>() / samples.len() as f32;
+        let crossings = samples
+            .windows(2)
+            .filter(|pair| (pair[0] >= 0.) != (pair[1] >= 0.))
+            .count();
+
+        Self {
+            level,
+            zero_crossing_rate: crossings as f32 / (samples.len() - 1).max(1) as f32,
+            bands: BandEnergies::default(),
+        }
+    }
+
+    /// Compute the statistics of a window of mono samples, including the energy in every octave band.
+    ///
+    /// # Arguments
+    ///
+    /// * `samples`: The samples of the window.
+    /// * `sample_rate`: The sample rate of the samples.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::f32::consts::PI;
+    /// # use varys_audio::listen::vad::Frame;
+    /// let hum: Vec<f32> = (0..1024)
+    ///     .map(|index| 0.1 * (2. * PI * 100. * index as f32 / 48000.).sin())
+    ///     .collect();
+    /// let frame = Frame::with_bands(&hum, 48000);
+    ///
+    /// assert_eq!(frame.bands.dominant_frequency(), Some(125.));
+    /// ```
+    pub fn with_bands(samples: &[f32], sample_rate: u32) -> Self {
+        Self {
+            bands: BandAnalyser::new(samples.len(), sample_rate).analyse(samples),
+            ..Self::from_samples(samples)
+        }
+    }
+}
+
+/// Decides whether a window of recorded audio contains sound, which is used to detect the end of responses.
+pub trait SilenceDetector: Send + Sync {
+    /// Whether a frame contains sound.
+    ///
+    /// # Arguments
+    ///
+    /// * `frame`: The statistics of the window of samples.
+    /// * `threshold`: The level above which a frame might be sound, see
+    ///   [`SilenceThreshold`](crate::listen::SilenceThreshold).
+    fn is_sound(&self, frame: &Frame, threshold: f32) -> bool;
+}
+
+/// Detects sound by its level only, which also triggers on steady noise like fans.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EnergyDetector;
+
+impl SilenceDetector for EnergyDetector {
+    fn is_sound(&self, frame: &Frame, threshold: f32) -> bool {
+        frame.level > threshold
+    }
+}
+
+/// Detects sound by its level and zero-crossing rate.
+///
+/// Speech crosses zero far less often than broadband noise like fans or air conditioning, so frames above the
+/// threshold only count as sound if their zero-crossing rate is low enough, unless they are much louder than the
+/// threshold.
+#[derive(Clone, Copy, Debug)]
+pub struct HybridDetector {
+    /// The highest zero-crossing rate of frames that count as sound.
+    pub max_zero_crossing_rate: f32,
+    /// How many times louder than the threshold frames count as sound regardless of their zero-crossing rate.
+    pub loud_factor: f32,
+}
+
+impl HybridDetector {
+    pub const DEFAULT_MAX_ZERO_CROSSING_RATE: f32 = 0.25;
+    pub const DEFAULT_LOUD_FACTOR: f32 = 4.;
+}
+
+impl Default for HybridDetector {
+    fn default() -> Self {
+        Self {
+            max_zero_crossing_rate: Self::DEFAULT_MAX_ZERO_CROSSING_RATE,
+            loud_factor: Self::DEFAULT_LOUD_FACTOR,
+        }
+    }
+}
+
+impl SilenceDetector for HybridDetector {
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys_audio::listen::vad::{Frame, HybridDetector, SilenceDetector};
+    /// let detector = HybridDetector::default();
+    /// let speech = Frame { level: 0.05, zero_crossing_rate: 0.1, ..Frame::default() };
+    /// let fan = Frame { level: 0.05, zero_crossing_rate: 0.5, ..Frame::default() };
+    /// let loud = Frame { level: 0.5, zero_crossing_rate: 0.5, ..Frame::default() };
+    ///
+    /// assert!(detector.is_sound(&speech, 0.01));
+    /// assert!(!detector.is_sound(&fan, 0.01));
+    /// assert!(detector.is_sound(&loud, 0.01));
+    /// ```
+    fn is_sound(&self, frame: &Frame, threshold: f32) -> bool {
+        frame.level > threshold
+            && (frame.zero_crossing_rate <= self.max_zero_crossing_rate
+                || frame.level > threshold * self.loud_factor)
+    }
+}
+
+/// Detects sound by its level and the octave bands its energy lies in.
+///
+/// Speech has most of its energy from 250 Hz to 4 kHz, while the hum of devices like a HomePod lies below, so frames
+/// above the threshold only count as sound if enough of their energy lies in the bands of speech, unless they are much
+/// louder than the threshold.
+#[derive(Clone, Copy, Debug)]
+pub struct SpectralDetector {
+    /// The lowest share of the energy in the bands of speech of frames that count as sound.
+    pub min_speech_share: f32,
+    /// How many times louder than the threshold frames count as sound regardless of their bands.
+    pub loud_factor: f32,
+}
+
+impl SpectralDetector {
+    pub const DEFAULT_MIN_SPEECH_SHARE: f32 = 0.5;
+    pub const DEFAULT_LOUD_FACTOR: f32 = 4.;
+}
+
+impl Default for SpectralDetector {
+    fn default() -> Self {
+        Self {
+            min_speech_share: Self::DEFAULT_MIN_SPEECH_SHARE,
+            loud_factor: Self::DEFAULT_LOUD_FACTOR,
+        }
+    }
+}
+
+impl SilenceDetector for SpectralDetector {
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys_audio::audio::bands::BandEnergies;
+    /// # use varys_audio::listen::vad::{Frame, SilenceDetector, SpectralDetector};
+    /// let detector = SpectralDetector::default();
+    /// let speech = BandEnergies([0., 0.1, 0.3, 0.4, 0.3, 0.2, 0.1, 0.]);
+    /// let hum = BandEnergies([0.2, 0.8, 0.1, 0., 0., 0., 0., 0.]);
+    ///
+    /// assert!(detector.is_sound(&Frame { level: 0.05, bands: speech, ..Frame::default() }, 0.01));
+    /// assert!(!detector.is_sound(&Frame { level: 0.05, bands: hum, ..Frame::default() }, 0.01));
+    /// assert!(detector.is_sound(&Frame { level: 0.5, bands: hum, ..Frame::default() }, 0.01));
+    /// ```
+    fn is_sound(&self, frame: &Frame, threshold: f32) -> bool {
+        frame.level > threshold
+            && (frame.bands.speech_share() >= self.min_speech_share
+                || frame.level > threshold * self.loud_factor)
+    }
+}
+
+/// Which [`SilenceDetector`] a listener uses.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SilenceDetection {
+    /// See [`EnergyDetector`].
+    #[default]
+    Energy,
+    /// See [`HybridDetector`].
+    Hybrid,
+    /// See [`SpectralDetector`].
+    Spectral,
+}
+
+impl SilenceDetection {
+    /// Create the detector with its default parameters.
+    pub fn detector(&self) -> Box<dyn SilenceDetector> {
+        match self {
+            SilenceDetection::Energy => Box::new(EnergyDetector),
+            SilenceDetection::Hybrid => Box::new(HybridDetector::default()),
+            SilenceDetection::Spectral => Box::new(SpectralDetector::default()),
+        }
+    }
+}
+
+impl FromStr for SilenceDetection {
+    type Err = String;
+
+    /// Parse a silence detection, either `energy`, `hybrid` or `spectral`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys_audio::listen::vad::SilenceDetection;
+    /// assert_eq!("energy".parse(), Ok(SilenceDetection::Energy));
+    /// assert_eq!("hybrid".parse(), Ok(SilenceDetection::Hybrid));
+    /// assert_eq!("spectral".parse(), Ok(SilenceDetection::Spectral));
+    /// assert!("webrtc".parse::<SilenceDetection>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "energy" => Ok(SilenceDetection::Energy),
+            "hybrid" => Ok(SilenceDetection::Hybrid),
+            "spectral" => Ok(SilenceDetection::Spectral),
+            _ => Err(format!("Expected energy, hybrid or spectral, got {s}")),
+        }
+    }
+}
+
+impl Display for SilenceDetection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SilenceDetection::Energy => write!(f, "energy"),
+            SilenceDetection::Hybrid => write!(f, "hybrid"),
+            SilenceDetection::Spectral => write!(f, "spectral"),
+        }
+    }
+}
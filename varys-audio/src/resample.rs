@@ -0,0 +1,128 @@
+use rubato::{
+    calculate_cutoff, FftFixedIn, Resampler, SincFixedIn, SincInterpolationParameters,
+    SincInterpolationType, WindowFunction,
+};
+
+use crate::error::Error;
+
+/// How many frames the resamplers process at once.
+const CHUNK_SIZE: usize = 1024;
+
+/// How audio is resampled by [`AudioData::resample`](crate::audio::AudioData::resample).
+///
+/// Apart from [`ResampleQuality::Decimate`], all qualities low-pass filter the audio before reducing its sample rate,
+/// so frequencies above the new Nyquist frequency do not alias into the speech band.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ResampleQuality {
+    /// Keep every n-th sample without filtering, see [`AudioData::downsample`](crate::audio::AudioData::downsample).
+    ///
+    /// This is the fastest, but only works for integer factors and aliases any content above the new Nyquist
+    /// frequency.
+    Decimate,
+    /// FFT based resampling, which is fast and filters well for fixed ratios like 48 kHz to 16 kHz.
+    Fast,
+    /// Sinc interpolation with a filter of 128 taps.
+    #[default]
+    High,
+    /// Sinc interpolation with a filter of 256 taps and a steeper cutoff, which is the slowest.
+    Best,
+}
+
+/// Resample every channel of audio with the given quality.
+///
+/// Returns the resampled channels, aligned with the input and with `ceil(frames * to / from)` frames each.
+///
+/// # Arguments
+///
+/// * `channels`: The samples of every channel, which all need the same length.
+/// * `from`: The current sample rate.
+/// * `to`: The sample rate to resample to.
+/// * `quality`: How to resample, must not be [`ResampleQuality::Decimate`].
+pub(crate) fn resample_channels(
+    channels: &[Vec<f32>],
+    from: u32,
+    to: u32,
+    quality: ResampleQuality,
+) -> Result<Vec<Vec<f32>>, Error> {
+    let ratio = to as f64 / from as f64;
+
+    match quality {
+        ResampleQuality::Decimate => Err(Error::Resampling(
+            "decimation does not use a resampler".to_string(),
+        )),
+        ResampleQuality::Fast => run(
+            FftFixedIn::<f32>::new(from as usize, to as usize, CHUNK_SIZE, 2, channels.len())?,
+            channels,
+            ratio,
+        ),
+        ResampleQuality::High => run(
+            SincFixedIn::<f32>::new(ratio, 1.0, sinc_parameters(128), CHUNK_SIZE, channels.len())?,
+            channels,
+            ratio,
+        ),
+        ResampleQuality::Best => run(
+            SincFixedIn::<f32>::new(ratio, 1.0, sinc_parameters(256), CHUNK_SIZE, channels.len())?,
+            channels,
+            ratio,
+        ),
+    }
+}
+
+fn sinc_parameters(sinc_len: usize) -> SincInterpolationParameters {
+    let window = WindowFunction::BlackmanHarris2;
+
+    SincInterpolationParameters {
+        sinc_len,
+        f_cutoff: calculate_cutoff(sinc_len, window),
+        oversampling_factor: sinc_len,
+        interpolation: SincInterpolationType::Cubic,
+        window,
+    }
+}
+
+/// Feed all channels through a resampler chunk by chunk and remove its delay from the output.
+fn run<R: Resampler<f32>>(
+    mut resampler: R,
+    channels: &[Vec<f32>],
+    ratio: f64,
+) -> Result<Vec<Vec<f32>>, Error> {
+    let frames = channels.first().map_or(0, Vec::len);
+    let expected = (frames as f64 * ratio).ceil() as usize;
+    let delay = resampler.output_delay();
+    let mut output = vec![Vec::with_capacity(expected + delay); channels.len()];
+
+    let mut position = 0;
+    while position + resampler.input_frames_next() <= frames {
+        let end = position + resampler.input_frames_next();
+        let chunk: Vec<&[f32]> = channels
+            .iter()
+            .map(|channel| &channel[position..end])
+            .collect();
+        append(&mut output, resampler.process(&chunk, None)?);
+        position = end;
+    }
+    let rest: Vec<&[f32]> = channels
+        .iter()
+        .map(|channel| &channel[position..])
+        .collect();
+    append(&mut output, resampler.process_partial(Some(&rest), None)?);
+
+    // flush the samples still held back by the delay of the filter
+    while output.first().map_or(0, Vec::len) < expected + delay {
+        append(
+            &mut output,
+            resampler.process_partial::<&[f32]>(None, None)?,
+        );
+    }
+
+    Ok(output
+        .into_iter()
+        .map(|channel| channel.into_iter().skip(delay).take(expected).collect())
+        .collect())
+}
+
+fn append(output: &mut [Vec<f32>], chunk: Vec<Vec<f32>>) {
+    for (channel, samples) in output.iter_mut().zip(chunk) {
+        channel.extend(samples);
+    }
+}
@@ -1,20 +1,305 @@
+#[cfg(feature = "stt")]
+use std::ops::Range;
+
+#[cfg(feature = "stt")]
 use log::{debug, info, trace, warn};
+#[cfg(feature = "stt")]
 use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
 
 use crate::audio::AudioData;
 use crate::error::Error;
+#[cfg(feature = "stt")]
+use crate::resample::ResampleQuality;
 
+pub mod fake;
 pub mod transcribe;
 pub mod transcriber;
 
+/// This sample rate is expected by whisper, so all audio data has to be resampled to this.
+pub const SAMPLE_RATE: u32 = 16_000;
+/// How often a phrase may repeat in a row before it is considered a degenerate loop.
+const MAX_REPETITIONS: usize = 3;
+/// The length in words of the longest phrase that is checked for loops.
+const MAX_LOOP_LENGTH: usize = 16;
+/// The length in words of the longest overlap that is removed when merging the transcripts of consecutive chunks.
+const MAX_OVERLAP_LENGTH: usize = 24;
+/// The duration of the chunks long recordings are split into for recognition in seconds.
+#[cfg(feature = "stt")]
+const CHUNK_DURATION_S: usize = 30;
+/// By how many seconds consecutive chunks overlap, so words at the border of a chunk are not cut off.
+#[cfg(feature = "stt")]
+const CHUNK_OVERLAP_S: usize = 2;
+/// The maximum number of tokens of an initial prompt. Whisper only uses up to half of its text context for prompts.
+#[cfg(feature = "stt")]
+const MAX_PROMPT_TOKENS: usize = 224;
+
+/// How whisper searches for the most likely transcript.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Sampling {
+    /// Pick the most likely token at every step.
+    ///
+    /// When decoding falls back to a higher temperature, the best of `best_of` sampled candidates is kept.
+    Greedy { best_of: i32 },
+    /// Keep the `beam_size` most likely transcripts at every step, which is slower but finds better transcripts of
+    /// uncommon words.
+    BeamSearch { beam_size: i32 },
+}
+
+/// Parameters that control how whisper decodes speech.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecodingParams {
+    /// How to search for the most likely transcript.
+    pub sampling: Sampling,
+    /// The temperature of the first decoding attempt. At `0`, the most likely tokens are always picked.
+    pub temperature: f32,
+    /// By how much to increase the temperature when decoding fails, or `0` to never fall back to a higher
+    /// temperature.
+    pub temperature_increment: f32,
+    /// Text the transcript is conditioned on, as if it had been said right before.
+    ///
+    /// Priming whisper with e.g. the query makes it more likely to spell names the way they are written there.
+    pub initial_prompt: Option<String>,
+    /// The language of the speech as a two-letter code like `de`, or `auto` to detect it. By default, whisper expects
+    /// English.
+    ///
+    /// Other languages require a multilingual model, i.e. one without the `.en` suffix.
+    pub language: Option<String>,
+}
+
+impl DecodingParams {
+    /// Decode with beam search instead of greedily.
+    ///
+    /// # Arguments
+    ///
+    /// * `beam_size`: The number of transcripts to keep at every step.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys_audio::stt::{DecodingParams, Sampling};
+    /// let params = DecodingParams::default()
+    ///     .with_beam_search(5)
+    ///     .with_initial_prompt("Who plays Gandalf?");
+    ///
+    /// assert_eq!(params.sampling, Sampling::BeamSearch { beam_size: 5 });
+    /// assert_eq!(params.initial_prompt.as_deref(), Some("Who plays Gandalf?"));
+    /// ```
+    pub fn with_beam_search(mut self, beam_size: i32) -> Self {
+        self.sampling = Sampling::BeamSearch { beam_size };
+        self
+    }
+
+    /// Condition the transcript on a prompt, see [`DecodingParams::initial_prompt`].
+    ///
+    /// # Arguments
+    ///
+    /// * `initial_prompt`: The text to condition the transcript on.
+    pub fn with_initial_prompt(mut self, initial_prompt: &str) -> Self {
+        self.initial_prompt = Some(initial_prompt.to_string());
+        self
+    }
+
+    /// Recognise speech in another language than English, see [`DecodingParams::language`].
+    ///
+    /// # Arguments
+    ///
+    /// * `language`: The two-letter code of the language.
+    pub fn with_language(mut self, language: &str) -> Self {
+        self.language = Some(language.to_string());
+        self
+    }
+}
+
+impl Default for DecodingParams {
+    /// Greedy decoding that falls back to higher temperatures in steps of `0.2`, like whisper by default.
+    fn default() -> Self {
+        DecodingParams {
+            sampling: Sampling::Greedy { best_of: 1 },
+            temperature: 0.0,
+            temperature_increment: 0.2,
+            initial_prompt: None,
+            language: None,
+        }
+    }
+}
+
+/// Join the segments recognised by whisper into a transcript, removing the repetitions whisper produces when it gets
+/// stuck, e.g. on long music responses.
+///
+/// Segments that repeat the previous segment are dropped. Phrases that repeat more than three times in a row are
+/// degenerate loops and reduced to a single occurrence. Case and punctuation are ignored when comparing phrases.
+///
+/// # Arguments
+///
+/// * `segments`: The recognised segments in order.
+///
+/// # Examples
+///
+/// ```
+/// # use varys_audio::stt::clean_transcript;
+/// let segments = [
+///     " Playing Yesterday by the Beatles.",
+///     " Playing Yesterday by the Beatles.",
+///     " Thank you. Thank you. Thank you. Thank you, thank you.",
+///     " Yeah, yeah, yeah.",
+/// ];
+///
+/// assert_eq!(
+///     clean_transcript(&segments),
+///     "Playing Yesterday by the Beatles. Thank you. Yeah, yeah, yeah."
+/// );
+/// ```
+pub fn clean_transcript<S: AsRef<str>>(segments: &[S]) -> String {
+    let mut words = Vec::new();
+    let mut previous = None;
+    for segment in segments {
+        let segment = segment.as_ref().trim();
+        if segment.is_empty() || previous == Some(segment) {
+            continue;
+        }
+        words.extend(segment.split_whitespace());
+        previous = Some(segment);
+    }
+
+    let normalised: Vec<String> = words
+        .iter()
+        .map(|word| {
+            word.chars()
+                .filter(|c| c.is_alphanumeric())
+                .flat_map(char::to_lowercase)
+                .collect()
+        })
+        .collect();
+    let mut cleaned = Vec::with_capacity(words.len());
+    let mut index = 0;
+
+    while index < words.len() {
+        let remaining = words.len() - index;
+        let repeated =
+            (1..=MAX_LOOP_LENGTH.min(remaining / (MAX_REPETITIONS + 1))).find_map(|length| {
+                let phrase = &normalised[index..index + length];
+                let repetitions = normalised[index..]
+                    .chunks_exact(length)
+                    .take_while(|chunk| *chunk == phrase)
+                    .count();
+
+                (repetitions > MAX_REPETITIONS).then_some((length, repetitions))
+            });
+
+        match repeated {
+            Some((length, repetitions)) => {
+                cleaned.extend(&words[index..index + length]);
+                index += length * repetitions;
+            }
+            None => {
+                cleaned.push(words[index]);
+                index += 1;
+            }
+        }
+    }
+
+    cleaned.join(" ")
+}
+
+/// Append the transcript of a chunk of audio to the transcript of the chunks before it.
+///
+/// Since consecutive chunks overlap, the start of `next` usually repeats the end of `previous`. The longest run of
+/// words that ends `previous` and starts `next` is only kept once. Case and punctuation are ignored when comparing
+/// words.
+///
+/// # Arguments
+///
+/// * `previous`: The transcript of the previous chunks.
+/// * `next`: The transcript of the next chunk.
+///
+/// # Examples
+///
+/// ```
+/// # use varys_audio::stt::merge_transcripts;
+/// assert_eq!(
+///     merge_transcripts("Once upon a time, there was a", "There was a little fox."),
+///     "Once upon a time, there was a little fox."
+/// );
+/// assert_eq!(
+///     merge_transcripts("The end.", "Goodnight."),
+///     "The end. Goodnight."
+/// );
+/// ```
+pub fn merge_transcripts(previous: &str, next: &str) -> String {
+    let normalise = |word: &str| -> String {
+        word.chars()
+            .filter(|c| c.is_alphanumeric())
+            .flat_map(char::to_lowercase)
+            .collect()
+    };
+    let previous_words: Vec<String> = previous.split_whitespace().map(normalise).collect();
+    let next_words: Vec<&str> = next.split_whitespace().collect();
+
+    let overlap = (1..=MAX_OVERLAP_LENGTH
+        .min(previous_words.len())
+        .min(next_words.len()))
+        .rev()
+        .find(|&length| {
+            previous_words[previous_words.len() - length..]
+                .iter()
+                .zip(&next_words[..length])
+                .all(|(previous_word, next_word)| *previous_word == normalise(next_word))
+        })
+        .unwrap_or(0);
+
+    previous
+        .split_whitespace()
+        .chain(next_words[overlap..].iter().copied())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Anything that can convert speech to text like a [`Recogniser`].
+///
+/// This allows replacing whisper with [`fake::FakeRecogniser`] where no model is available.
+pub trait Recognise {
+    /// Convert speech in the given audio data to text.
+    ///
+    /// # Arguments
+    ///
+    /// * `audio`: The audio to recognise.
+    fn recognise(&self, audio: &mut AudioData) -> Result<String, Error>;
+
+    /// Convert speech in the given audio data to text, decoding it with specific parameters.
+    ///
+    /// By default, the parameters are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `audio`: The audio to recognise.
+    /// * `params`: The parameters to decode with.
+    fn recognise_with(
+        &self,
+        audio: &mut AudioData,
+        params: &DecodingParams,
+    ) -> Result<String, Error> {
+        let _ = params;
+        self.recognise(audio)
+    }
+
+    /// The parameters used by [`Recognise::recognise`].
+    fn decoding_params(&self) -> DecodingParams {
+        DecodingParams::default()
+    }
+}
+
 /// Wraps the whisper API.
+#[cfg(feature = "stt")]
 pub struct Recogniser {
     context: WhisperContext,
+    params: DecodingParams,
+    resample_quality: ResampleQuality,
 }
 
+#[cfg(feature = "stt")]
 impl Recogniser {
     /// This sample rate is expected by whisper, so all audio data has to be resampled to this.
-    pub const SAMPLE_RATE: u32 = 16_000;
+    pub const SAMPLE_RATE: u32 = SAMPLE_RATE;
 
     /// Create a new recogniser that uses the model stored at the given file path.
     ///
@@ -41,9 +326,29 @@ impl Recogniser {
 
         Ok(Recogniser {
             context: WhisperContext::new_with_params(model_path, params)?,
+            params: DecodingParams::default(),
+            resample_quality: ResampleQuality::default(),
         })
     }
 
+    /// Set the parameters used to decode speech by [`Recogniser::recognise`].
+    ///
+    /// # Arguments
+    ///
+    /// * `params`: The parameters to decode with.
+    pub fn set_decoding_params(&mut self, params: DecodingParams) {
+        self.params = params;
+    }
+
+    /// Set how audio is resampled to [`Recogniser::SAMPLE_RATE`] before it is recognised.
+    ///
+    /// # Arguments
+    ///
+    /// * `quality`: The quality to resample with, see [`ResampleQuality`].
+    pub fn set_resample_quality(&mut self, quality: ResampleQuality) {
+        self.resample_quality = quality;
+    }
+
     /// Convert speech in the given audio data to text.
     ///
     /// Forwards any errors that whisper returns.
@@ -51,6 +356,9 @@ impl Recogniser {
     /// This method first preprocesses the audio to mono and resamples it to a sample rate of
     /// [`Recogniser::SAMPLE_RATE`].
     ///
+    /// Long recordings are recognised in overlapping chunks of 30 seconds, whose transcripts are merged with
+    /// [`merge_transcripts`]. This bounds the memory whisper needs for each pass.
+    ///
     /// # Arguments
     ///
     /// * `audio`: The audio to recognise.
@@ -70,6 +378,21 @@ impl Recogniser {
     /// let _ = recogniser.recognise(&mut audio);
     /// ```
     pub fn recognise(&self, audio: &mut AudioData) -> Result<String, Error> {
+        self.recognise_with(audio, &self.params)
+    }
+
+    /// Convert speech in the given audio data to text, like [`Recogniser::recognise`], but decode it with specific
+    /// parameters instead of those set on the recogniser.
+    ///
+    /// # Arguments
+    ///
+    /// * `audio`: The audio to recognise.
+    /// * `params`: The parameters to decode with.
+    pub fn recognise_with(
+        &self,
+        audio: &mut AudioData,
+        params: &DecodingParams,
+    ) -> Result<String, Error> {
         if audio.duration_s() < 1.0 {
             warn!("Whisper cannot recognise audio shorter than one second");
 
@@ -78,24 +401,37 @@ impl Recogniser {
 
         debug!("Recognising {:.2} seconds of audio...", audio.duration_s());
 
-        Recogniser::preprocess(audio)?;
+        self.preprocess(audio)?;
 
         let mut state = self.context.create_state()?;
         let mut full_text = String::new();
+        let prompt_tokens = match &params.initial_prompt {
+            Some(prompt) => self.context.tokenize(prompt, MAX_PROMPT_TOKENS)?,
+            None => Vec::new(),
+        };
+
+        for chunk in Recogniser::chunks(audio.data.len()) {
+            trace!("Recognising samples {} to {}...", chunk.start, chunk.end);
+
+            // whisper timestamps are in centiseconds
+            let offset = (chunk.start / (SAMPLE_RATE as usize / 100)) as i64;
+            state.full(self.get_params(params, &prompt_tokens), &audio.data[chunk])?;
 
-        state.full(self.get_params(), &audio.data)?;
-
-        let segment_count = state.full_n_segments()?;
-        for i in 0..segment_count {
-            let segment = state.full_get_segment_text(i)?;
-            full_text.push_str(&segment);
-            let timestamps = (state.full_get_segment_t0(i)?, state.full_get_segment_t1(i)?);
-            trace!(
-                "Recognised segment [{} - {}]: {}",
-                timestamps.0,
-                timestamps.1,
-                segment
-            );
+            let segment_count = state.full_n_segments()?;
+            let mut segments = Vec::with_capacity(segment_count as usize);
+            for i in 0..segment_count {
+                let segment = state.full_get_segment_text(i)?;
+                let timestamps = (state.full_get_segment_t0(i)?, state.full_get_segment_t1(i)?);
+                trace!(
+                    "Recognised segment [{} - {}]: {}",
+                    offset + timestamps.0,
+                    offset + timestamps.1,
+                    segment
+                );
+                segments.push(segment);
+            }
+
+            full_text = merge_transcripts(&full_text, &clean_transcript(&segments));
         }
 
         debug!("Recognised: {}", full_text);
@@ -103,18 +439,54 @@ impl Recogniser {
         Ok(full_text)
     }
 
-    fn preprocess(audio: &mut AudioData) -> Result<(), Error> {
+    fn preprocess(&self, audio: &mut AudioData) -> Result<(), Error> {
         debug!("Preprocessing audio for recognition...");
 
         audio
             .convert_to_mono()
-            .downsample(Recogniser::SAMPLE_RATE)?;
+            .resample(Recogniser::SAMPLE_RATE, self.resample_quality)?;
 
         Ok(())
     }
 
-    fn get_params(&self) -> FullParams {
-        let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+    /// Split a number of samples into overlapping chunks of [`CHUNK_DURATION_S`] seconds.
+    ///
+    /// # Arguments
+    ///
+    /// * `length`: The number of samples at [`SAMPLE_RATE`].
+    fn chunks(length: usize) -> Vec<Range<usize>> {
+        let chunk_length = CHUNK_DURATION_S * SAMPLE_RATE as usize;
+        let step = chunk_length - CHUNK_OVERLAP_S * SAMPLE_RATE as usize;
+
+        let mut chunks = Vec::new();
+        let mut start = 0;
+        loop {
+            let end = length.min(start + chunk_length);
+            chunks.push(start..end);
+            if end >= length {
+                return chunks;
+            }
+            start += step;
+        }
+    }
+
+    fn get_params<'a>(
+        &self,
+        decoding: &'a DecodingParams,
+        prompt_tokens: &'a [i32],
+    ) -> FullParams<'a, 'a> {
+        let mut params = FullParams::new(match decoding.sampling {
+            Sampling::Greedy { best_of } => SamplingStrategy::Greedy { best_of },
+            Sampling::BeamSearch { beam_size } => SamplingStrategy::BeamSearch {
+                beam_size,
+                patience: -1.0,
+            },
+        });
+        params.set_temperature(decoding.temperature);
+        params.set_temperature_inc(decoding.temperature_increment);
+        params.set_tokens(prompt_tokens);
+        // whisper would detect the language if none were set, so fall back to English explicitly
+        params.set_language(Some(decoding.language.as_deref().unwrap_or("en")));
         params.set_print_progress(false);
         params.set_print_realtime(false);
         params.set_print_timestamps(false);
@@ -124,3 +496,22 @@ impl Recogniser {
         params
     }
 }
+
+#[cfg(feature = "stt")]
+impl Recognise for Recogniser {
+    fn recognise(&self, audio: &mut AudioData) -> Result<String, Error> {
+        Recogniser::recognise(self, audio)
+    }
+
+    fn recognise_with(
+        &self,
+        audio: &mut AudioData,
+        params: &DecodingParams,
+    ) -> Result<String, Error> {
+        Recogniser::recognise_with(self, audio, params)
+    }
+
+    fn decoding_params(&self) -> DecodingParams {
+        self.params.clone()
+    }
+}
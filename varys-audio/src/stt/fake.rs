@@ -0,0 +1,52 @@
+use log::info;
+
+use crate::audio::AudioData;
+use crate::error::Error;
+use crate::stt::Recognise;
+
+/// A [`Recognise`] implementation that does not need a whisper model.
+///
+/// Every recognition returns the same text, regardless of the audio.
+pub struct FakeRecogniser {
+    text: String,
+}
+
+impl FakeRecogniser {
+    /// Create a fake recogniser that always recognises the given text.
+    ///
+    /// # Arguments
+    ///
+    /// * `text`: The text that is "recognised".
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys_audio::audio::AudioData;
+    /// # use varys_audio::stt::Recognise;
+    /// # use varys_audio::stt::fake::FakeRecogniser;
+    /// let recogniser = FakeRecogniser::new("It's sunny today.");
+    /// let mut audio = AudioData {
+    ///     data: vec![0_f32],
+    ///     channels: 1,
+    ///     sample_rate: 16000,
+    /// };
+    ///
+    /// assert_eq!(recogniser.recognise(&mut audio).unwrap(), "It's sunny today.");
+    /// ```
+    pub fn new(text: &str) -> Self {
+        FakeRecogniser {
+            text: text.to_string(),
+        }
+    }
+}
+
+impl Recognise for FakeRecogniser {
+    fn recognise(&self, audio: &mut AudioData) -> Result<String, Error> {
+        info!(
+            "Fake recognising {:.2} seconds of audio...",
+            audio.duration_s()
+        );
+
+        Ok(self.text.clone())
+    }
+}
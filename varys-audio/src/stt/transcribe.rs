@@ -5,6 +5,21 @@ pub trait Transcribe: Sync + Send {
     ///
     /// * `text`: The text that was transcribed.
     fn transcribed(&mut self, text: String);
+
+    /// The text to condition the transcription on, see
+    /// [`DecodingParams::initial_prompt`](crate::stt::DecodingParams::initial_prompt).
+    ///
+    /// By default, the transcription is not conditioned on anything.
+    fn initial_prompt(&self) -> Option<String> {
+        None
+    }
+
+    /// The language to transcribe in, see [`DecodingParams::language`](crate::stt::DecodingParams::language).
+    ///
+    /// By default, the language of the recogniser is used.
+    fn language(&self) -> Option<String> {
+        None
+    }
 }
 
 impl Transcribe for Option<String> {
@@ -7,14 +7,14 @@ use log::{debug, error};
 use crate::audio::AudioData;
 use crate::error::Error;
 use crate::stt::transcribe::Transcribe;
-use crate::stt::Recogniser;
+use crate::stt::Recognise;
 
 /// A transcriber that can run in the background to transcribe audio.
 ///
 /// This is always created together with a [`TranscriberHandle`], which is used to communicate with the transcriber once
 /// it has started.
 pub struct Transcriber<T: Transcribe> {
-    recogniser: Recogniser,
+    recogniser: Box<dyn Recognise + Send>,
     audio_receiver: Receiver<(T, AudioData)>,
     result_sender: Sender<T>,
     stop_receiver: Receiver<()>,
@@ -26,14 +26,14 @@ impl<T: Transcribe> Transcriber<T> {
     /// # Arguments
     ///
     /// * `recogniser`: The recogniser to use for audio transcription.
-    pub fn new(recogniser: Recogniser) -> (Self, TranscriberHandle<T>) {
+    pub fn new<R: Recognise + Send + 'static>(recogniser: R) -> (Self, TranscriberHandle<T>) {
         let (audio_sender, audio_receiver) = std::sync::mpsc::channel();
         let (result_sender, result_receiver) = std::sync::mpsc::channel();
         let (stop_sender, stop_receiver) = std::sync::mpsc::channel();
 
         (
             Self {
-                recogniser,
+                recogniser: Box::new(recogniser),
                 audio_receiver,
                 result_sender,
                 stop_receiver,
@@ -74,7 +74,15 @@ impl<T: Transcribe> Transcriber<T> {
 
             match self.audio_receiver.try_recv() {
                 Ok((mut transcribe, mut audio)) => {
-                    match self.recogniser.recognise(&mut audio) {
+                    let mut params = self.recogniser.decoding_params();
+                    if let Some(initial_prompt) = transcribe.initial_prompt() {
+                        params.initial_prompt = Some(initial_prompt);
+                    }
+                    if let Some(language) = transcribe.language() {
+                        params.language = Some(language);
+                    }
+
+                    match self.recogniser.recognise_with(&mut audio, &params) {
                         Ok(text) => {
                             transcribe.transcribed(text);
                         }
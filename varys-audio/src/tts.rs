@@ -1,4 +1,5 @@
 use log::{debug, info, trace};
+use std::process::Command;
 use std::time::Instant;
 
 #[cfg(not(target_os = "macos"))]
@@ -6,7 +7,11 @@ use cpal::SampleRate;
 #[cfg(not(target_os = "macos"))]
 use std::io::Write;
 #[cfg(not(target_os = "macos"))]
-use std::process::{Command, Stdio};
+use std::path::PathBuf;
+#[cfg(not(target_os = "macos"))]
+use std::process::Stdio;
+#[cfg(not(target_os = "macos"))]
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 #[cfg(target_os = "macos")]
 use cocoa_foundation::{
@@ -20,7 +25,58 @@ use std::sync::mpsc::{channel, TryRecvError};
 #[cfg(target_os = "macos")]
 use tts::{Features, Tts, Voice};
 
+use crate::audio::AudioData;
 use crate::error::Error;
+use crate::watermark::Watermark;
+#[cfg(not(target_os = "macos"))]
+use crate::watermark::DEFAULT_AMPLITUDE;
+
+pub mod fake;
+
+/// Anything that can speak like a [`Speaker`].
+///
+/// This allows replacing text-to-speech with [`fake::FakeSpeaker`] where no speakers are available.
+pub trait Speak {
+    /// Set the voice that should be spoken with.
+    ///
+    /// See [`Speaker::set_voice`].
+    fn set_voice(&mut self, id: &str) -> Result<(), Error>;
+
+    /// Say a phrase and return the time in milliseconds it took to say it.
+    ///
+    /// See [`Speaker::say`].
+    fn say(&self, text: &str) -> Result<i32, Error>;
+
+    /// Play audio and return the time in milliseconds it took to play it.
+    ///
+    /// See [`Speaker::play`].
+    fn play(&self, audio: &AudioData) -> Result<i32, Error>;
+
+    /// Set the watermark that is mixed into everything that is said.
+    ///
+    /// See [`Speaker::set_watermark`].
+    fn set_watermark(&mut self, watermark: Option<Watermark>);
+
+    /// Set how fast to speak relative to the normal rate of the voice.
+    ///
+    /// See [`Speaker::set_rate`].
+    fn set_rate(&mut self, rate: f32) -> Result<(), Error>;
+
+    /// The names of all voices that can be spoken with.
+    ///
+    /// By default, no voices are known. See [`Speaker::voices`].
+    fn voices(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// The locale of a voice, e.g. `de-DE`.
+    ///
+    /// By default, the locale of voices is unknown. See [`Speaker::voice_locale`].
+    fn voice_locale(&self, id: &str) -> Option<String> {
+        let _ = id;
+        None
+    }
+}
 
 /// A speaker that can synthesize voices.
 pub struct Speaker {
@@ -30,6 +86,17 @@ pub struct Speaker {
     available_voices: Vec<Voice>,
     #[cfg(not(target_os = "macos"))]
     speaker: usize,
+    #[cfg(not(target_os = "macos"))]
+    watermark: Option<Watermark>,
+    /// The speaking rate relative to the normal rate, see [`Speaker::set_rate`].
+    #[cfg(not(target_os = "macos"))]
+    rate: f32,
+    /// The device speech is played on, see [`Speaker::set_output_device`].
+    #[cfg(not(target_os = "macos"))]
+    output_device: Option<String>,
+    /// Where speech is generated to, unique per speaker so several speakers can talk at once.
+    #[cfg(not(target_os = "macos"))]
+    output_path: PathBuf,
 }
 
 impl Speaker {
@@ -73,7 +140,16 @@ impl Speaker {
         }
         #[cfg(not(target_os = "macos"))]
         {
-            Ok(Self { speaker: 0 })
+            Ok(Self {
+                speaker: 0,
+                watermark: None,
+                rate: 1.0,
+                output_device: None,
+                output_path: PathBuf::from(format!(
+                    "data/voices/output-{}.wav",
+                    NEXT_SPEAKER_ID.fetch_add(1, Ordering::Relaxed)
+                )),
+            })
         }
     }
 
@@ -172,6 +248,126 @@ impl Speaker {
         }
     }
 
+    /// The names of all voices that can be spoken with.
+    pub fn voices(&self) -> Vec<String> {
+        #[cfg(target_os = "macos")]
+        {
+            self.available_voices
+                .iter()
+                .map(|voice| voice.name())
+                .collect()
+        }
+        #[cfg(not(target_os = "macos"))]
+        AVAILABLE_VOICES
+            .iter()
+            .map(|voice| voice.to_string())
+            .collect()
+    }
+
+    /// The locale of a voice, e.g. `de-DE`, or `None` if the voice is not available.
+    ///
+    /// The voices of piper all speak English.
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: The id or name of the voice.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys_audio::tts::Speaker;
+    /// let speaker = Speaker::new().unwrap();
+    ///
+    /// assert_eq!(speaker.voice_locale("Invalid Name"), None);
+    /// ```
+    pub fn voice_locale(&self, id: &str) -> Option<String> {
+        #[cfg(target_os = "macos")]
+        {
+            self.available_voices
+                .iter()
+                .find(|voice| voice.id() == id || voice.name() == id)
+                .map(|voice| voice.language().to_string())
+        }
+        #[cfg(not(target_os = "macos"))]
+        AVAILABLE_VOICES
+            .contains(&id)
+            .then(|| PIPER_LOCALE.to_string())
+    }
+
+    /// Set the watermark that is mixed into everything that is said, see [`Watermark`].
+    ///
+    /// Watermarks are only supported with piper, on macOS speech is played by the system and cannot be watermarked.
+    ///
+    /// # Arguments
+    ///
+    /// * `watermark`: The watermark to use, or `None` to not watermark speech.
+    pub fn set_watermark(&mut self, watermark: Option<Watermark>) {
+        #[cfg(target_os = "macos")]
+        if watermark.is_some() {
+            log::warn!("Speech cannot be watermarked on macOS");
+        }
+        #[cfg(not(target_os = "macos"))]
+        {
+            self.watermark = watermark;
+        }
+    }
+
+    /// Set how fast to speak relative to the normal rate of the voice, e.g. `1.5` to speak 50% faster.
+    ///
+    /// Returns [`Error::OutOfRange`] if the rate is not positive or, on macOS, faster or slower than the system can
+    /// speak.
+    ///
+    /// # Arguments
+    ///
+    /// * `rate`: The speaking rate relative to the normal rate.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys_audio::tts::Speaker;
+    /// let mut speaker = Speaker::new().unwrap();
+    ///
+    /// assert!(speaker.set_rate(1.2).is_ok());
+    /// assert!(speaker.set_rate(0.0).is_err());
+    /// ```
+    pub fn set_rate(&mut self, rate: f32) -> Result<(), Error> {
+        if rate <= 0.0 || !rate.is_finite() {
+            return Err(Error::OutOfRange);
+        }
+
+        #[cfg(target_os = "macos")]
+        {
+            let normal_rate = self.tts.normal_rate();
+            self.tts.set_rate(normal_rate * rate)?;
+        }
+        #[cfg(not(target_os = "macos"))]
+        {
+            self.rate = rate;
+        }
+
+        info!("Speaking at {rate:.2} times the normal rate");
+
+        Ok(())
+    }
+
+    /// Set the device speech is played on, e.g. to talk to several voice assistants at once.
+    ///
+    /// Output devices are only supported with piper, on macOS speech is played on the system output device.
+    ///
+    /// # Arguments
+    ///
+    /// * `output_device`: The ALSA name of the device, or `None` to use the default output device.
+    pub fn set_output_device(&mut self, output_device: Option<String>) {
+        #[cfg(target_os = "macos")]
+        if output_device.is_some() {
+            log::warn!("The output device of speech cannot be chosen on macOS");
+        }
+        #[cfg(not(target_os = "macos"))]
+        {
+            self.output_device = output_device;
+        }
+    }
+
     /// Say a phrase in the current voice, rate and volume. Returns the time in milliseconds it took
     /// to say the phrase.
     ///
@@ -190,7 +386,12 @@ impl Speaker {
         info!("Saying \"{text}\"");
 
         #[cfg(not(target_os = "macos"))]
-        self.generate_wav(text, VOICE_OUTPUT_PATH)?;
+        {
+            self.generate_wav(text, &self.output_path)?;
+            if let Some(watermark) = &self.watermark {
+                watermark_wav(&self.output_path, watermark)?;
+            }
+        }
 
         let start = Instant::now();
 
@@ -212,7 +413,7 @@ impl Speaker {
             }
         }
         #[cfg(not(target_os = "macos"))]
-        self.play_wav(VOICE_OUTPUT_PATH)?;
+        self.play_wav(&self.output_path)?;
 
         let duration = start.elapsed().as_millis() as i32;
         trace!("Spoke for {duration}ms");
@@ -220,6 +421,53 @@ impl Speaker {
         Ok(duration)
     }
 
+    /// Play audio through the output device of the speaker. Returns the time in milliseconds it took to play the
+    /// audio.
+    ///
+    /// The audio is written to a temporary 16 bit `.wav` file, which is played with `aplay`, or `afplay` on macOS.
+    ///
+    /// This blocks the current thread until playing has finished.
+    ///
+    /// # Arguments
+    ///
+    /// * `audio`: The audio to play.
+    pub fn play(&self, audio: &AudioData) -> Result<i32, Error> {
+        debug!("Playing {}ms of audio", audio.duration_ms());
+
+        let spec = hound::WavSpec {
+            channels: audio.channels as u16,
+            sample_rate: audio.sample_rate,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(PLAYBACK_PATH, spec)?;
+        for sample in &audio.data {
+            writer.write_sample((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)?;
+        }
+        writer.finalize()?;
+
+        let start = Instant::now();
+
+        #[cfg(target_os = "macos")]
+        let mut player = Command::new("afplay");
+        #[cfg(not(target_os = "macos"))]
+        let mut player = Command::new("aplay");
+        #[cfg(not(target_os = "macos"))]
+        if let Some(output_device) = &self.output_device {
+            player.arg("-D").arg(output_device);
+        }
+        #[cfg(not(target_os = "macos"))]
+        player.arg("--quiet");
+        player
+            .arg(PLAYBACK_PATH)
+            .spawn()
+            .map_err(|err| Error::Tts(err.to_string()))?
+            .wait()
+            .map_err(|err| Error::Tts(err.to_string()))?;
+
+        Ok(start.elapsed().as_millis() as i32)
+    }
+
     #[cfg(not(target_os = "macos"))]
     fn generate_wav<P: AsRef<std::path::Path>>(&self, text: &str, path: P) -> Result<(), Error> {
         debug!("Writing audio to {}", path.as_ref().display());
@@ -230,9 +478,12 @@ impl Speaker {
             .arg(VOICE_MODEL_PATH)
             .arg("--speaker")
             .arg(self.speaker.to_string())
+            // piper stretches phonemes by the length scale, so speaking faster means shorter phonemes
+            .arg("--length_scale")
+            .arg((1.0 / self.rate).to_string())
             .arg("--quiet")
             .arg("--output_file")
-            .arg(VOICE_OUTPUT_PATH)
+            .arg(path.as_ref())
             .spawn()
             .map_err(|err| Error::Tts(err.to_string()))?;
         piper
@@ -250,7 +501,11 @@ impl Speaker {
     fn play_wav<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), Error> {
         debug!("Playing audio from {}", path.as_ref().display());
 
-        Command::new("aplay")
+        let mut aplay = Command::new("aplay");
+        if let Some(output_device) = &self.output_device {
+            aplay.arg("-D").arg(output_device);
+        }
+        aplay
             .arg("--quiet")
             .arg("-r")
             .arg(VOICE_SAMPLE_RATE.0.to_string())
@@ -268,16 +523,83 @@ impl Speaker {
     }
 }
 
+impl Speak for Speaker {
+    fn set_voice(&mut self, id: &str) -> Result<(), Error> {
+        Speaker::set_voice(self, id)
+    }
+
+    fn say(&self, text: &str) -> Result<i32, Error> {
+        Speaker::say(self, text)
+    }
+
+    fn play(&self, audio: &AudioData) -> Result<i32, Error> {
+        Speaker::play(self, audio)
+    }
+
+    fn set_watermark(&mut self, watermark: Option<Watermark>) {
+        Speaker::set_watermark(self, watermark)
+    }
+
+    fn set_rate(&mut self, rate: f32) -> Result<(), Error> {
+        Speaker::set_rate(self, rate)
+    }
+
+    fn voices(&self) -> Vec<String> {
+        Speaker::voices(self)
+    }
+
+    fn voice_locale(&self, id: &str) -> Option<String> {
+        Speaker::voice_locale(self, id)
+    }
+}
+
+/// Mix a watermark into a 16 bit `.wav` file generated by piper.
+///
+/// # Arguments
+///
+/// * `path`: The path to the file.
+/// * `watermark`: The watermark to mix in.
+#[cfg(not(target_os = "macos"))]
+fn watermark_wav<P: AsRef<std::path::Path>>(path: P, watermark: &Watermark) -> Result<(), Error> {
+    let reader = hound::WavReader::open(&path)?;
+    let spec = reader.spec();
+    let mut audio = AudioData {
+        data: reader
+            .into_samples::<i16>()
+            .map(|sample| sample.map(|sample| sample as f32 / i16::MAX as f32))
+            .collect::<Result<_, _>>()?,
+        channels: spec.channels as u8,
+        sample_rate: spec.sample_rate,
+    };
+
+    watermark.embed(&mut audio, DEFAULT_AMPLITUDE);
+
+    let mut writer = hound::WavWriter::create(&path, spec)?;
+    for sample in audio.data {
+        writer.write_sample((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)?;
+    }
+    writer.finalize()?;
+
+    Ok(())
+}
+
 #[cfg(not(target_os = "macos"))]
 const VOICE_MODEL_PATH: &str = "data/voices/en_US-libritts_r-medium.onnx";
 
+/// The id of the next speaker, which makes its output path unique.
 #[cfg(not(target_os = "macos"))]
-const VOICE_OUTPUT_PATH: &str = "data/voices/output.wav";
+static NEXT_SPEAKER_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Where audio is temporarily stored to play it.
+const PLAYBACK_PATH: &str = "data/playback.wav";
 
 #[cfg(not(target_os = "macos"))]
 const VOICE_SAMPLE_RATE: SampleRate = SampleRate(22050);
 
 #[cfg(not(target_os = "macos"))]
+/// The locale of all piper voices, which are speakers of the LibriTTS dataset.
+#[cfg(not(target_os = "macos"))]
+const PIPER_LOCALE: &str = "en-US";
 const AVAILABLE_VOICES: [&str; 904] = [
     "p3922", "p8699", "p4535", "p6701", "p3638", "p922", "p2531", "p1638", "p8848", "p6544",
     "p3615", "p318", "p6104", "p1382", "p5400", "p5712", "p2769", "p2573", "p1463", "p6458",
@@ -0,0 +1,109 @@
+use std::sync::{Arc, Mutex};
+
+use log::info;
+
+use crate::audio::AudioData;
+use crate::error::Error;
+use crate::tts::Speak;
+use crate::watermark::Watermark;
+
+/// How long the fake speaker pretends to take for each character.
+const MILLISECONDS_PER_CHARACTER: i32 = 60;
+
+/// A [`Speak`] implementation that does not produce any sound.
+///
+/// Everything that is said is remembered instead, so it can be checked afterwards.
+#[derive(Clone, Default)]
+pub struct FakeSpeaker {
+    voice: Option<String>,
+    rate: Option<f32>,
+    spoken: Arc<Mutex<Vec<String>>>,
+}
+
+impl FakeSpeaker {
+    /// Create a new fake speaker.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The voice that was last set, if any.
+    pub fn voice(&self) -> Option<&str> {
+        self.voice.as_deref()
+    }
+
+    /// The speaking rate that was last set, relative to the normal rate.
+    pub fn rate(&self) -> f32 {
+        self.rate.unwrap_or(1.0)
+    }
+
+    /// Everything that was said so far, in order.
+    ///
+    /// Clones of a fake speaker share what was said, so a clone can be kept to check what was said by a speaker that
+    /// was moved elsewhere.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys_audio::tts::Speak;
+    /// # use varys_audio::tts::fake::FakeSpeaker;
+    /// let speaker = FakeSpeaker::new();
+    /// let spoken = speaker.clone();
+    ///
+    /// let duration = speaker.say("Hey Siri").unwrap();
+    ///
+    /// assert_eq!(duration, 480);
+    /// assert_eq!(spoken.spoken(), vec!["Hey Siri".to_string()]);
+    /// ```
+    pub fn spoken(&self) -> Vec<String> {
+        self.spoken
+            .lock()
+            .map(|spoken| spoken.clone())
+            .unwrap_or_default()
+    }
+}
+
+impl Speak for FakeSpeaker {
+    fn set_voice(&mut self, id: &str) -> Result<(), Error> {
+        info!("Using fake voice {id}");
+
+        self.voice = Some(id.to_string());
+
+        Ok(())
+    }
+
+    fn say(&self, text: &str) -> Result<i32, Error> {
+        info!("Fake saying \"{text}\"");
+
+        if let Ok(mut spoken) = self.spoken.lock() {
+            spoken.push(text.to_string());
+        }
+
+        Ok((text.chars().count() as f32 * MILLISECONDS_PER_CHARACTER as f32 / self.rate()) as i32)
+    }
+
+    fn play(&self, audio: &AudioData) -> Result<i32, Error> {
+        info!("Fake playing {}ms of audio", audio.duration_ms());
+
+        if let Ok(mut spoken) = self.spoken.lock() {
+            spoken.push(format!("<{}ms of audio>", audio.duration_ms()));
+        }
+
+        Ok(audio.duration_ms())
+    }
+
+    fn set_watermark(&mut self, _: Option<Watermark>) {
+        // nothing is played, so there is nothing to watermark
+    }
+
+    fn set_rate(&mut self, rate: f32) -> Result<(), Error> {
+        if rate <= 0.0 || !rate.is_finite() {
+            return Err(Error::OutOfRange);
+        }
+
+        info!("Fake speaking at {rate:.2} times the normal rate");
+
+        self.rate = Some(rate);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,296 @@
+use std::f32::consts::PI;
+use std::fs;
+use std::path::Path;
+
+use log::debug;
+use rustfft::num_complex::Complex;
+use rustfft::FftPlanner;
+
+use crate::audio::AudioData;
+use crate::error::Error;
+
+/// The similarity below which a recording is considered not to be spoken by the voice of a profile by default.
+pub const DEFAULT_MIN_SIMILARITY: f32 = 0.8;
+/// The duration of the frames the spectrum of a recording is computed on in milliseconds.
+const FRAME_MS: usize = 25;
+/// The time between the starts of consecutive frames in milliseconds.
+const HOP_MS: usize = 10;
+/// The number of mel bands the spectrum is summarised in.
+const BAND_COUNT: usize = 24;
+/// The lowest frequency covered by the mel bands in Hz.
+const MIN_FREQUENCY: f32 = 80.0;
+/// The highest frequency covered by the mel bands in Hz, if the sample rate allows it.
+const MAX_FREQUENCY: f32 = 7600.0;
+/// How far below the loudest frame the energy of a frame may lie for it to count as voiced, in decibels.
+const VOICED_RANGE_DB: f32 = 30.0;
+
+/// The characteristic spectrum of a voice, used to verify that a recording was spoken by it.
+///
+/// The embedding of a recording consists of the mean and deviation of its log mel spectrum over all voiced frames.
+/// The mean is normalised for loudness, so recordings at different volumes can be compared.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VoiceProfile {
+    /// The normalised embedding of the voice.
+    pub embedding: Vec<f32>,
+}
+
+impl VoiceProfile {
+    /// Create the profile of a voice from recordings of it, e.g. of earlier responses of a voice assistant.
+    ///
+    /// Returns `None` if none of the recordings contain any sound.
+    ///
+    /// # Arguments
+    ///
+    /// * `recordings`: The recordings of the voice.
+    pub fn from_recordings(recordings: &[AudioData]) -> Option<Self> {
+        let embeddings: Vec<Vec<f32>> = recordings.iter().filter_map(embedding).collect();
+        let mut embedding = vec![0.0; embeddings.first()?.len()];
+        for recording_embedding in &embeddings {
+            embedding
+                .iter_mut()
+                .zip(recording_embedding)
+                .for_each(|(sum, value)| *sum += value);
+        }
+        normalise(&mut embedding);
+
+        debug!(
+            "Created a voice profile from {} recordings",
+            embeddings.len()
+        );
+
+        Some(VoiceProfile { embedding })
+    }
+
+    /// Load a profile that was stored with [`VoiceProfile::save`].
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: The path of the profile.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let embedding = fs::read_to_string(path)?
+            .split_whitespace()
+            .map(str::parse)
+            .collect::<Result<Vec<f32>, _>>()
+            .map_err(|_| Error::InvalidVoiceProfile(path.to_string_lossy().to_string()))?;
+
+        if embedding.len() != 2 * BAND_COUNT {
+            return Err(Error::InvalidVoiceProfile(
+                path.to_string_lossy().to_string(),
+            ));
+        }
+
+        Ok(VoiceProfile { embedding })
+    }
+
+    /// Store the profile as a text file with one value per line.
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: Where to store the profile.
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let values: Vec<String> = self.embedding.iter().map(f32::to_string).collect();
+
+        Ok(fs::write(path, values.join("\n"))?)
+    }
+
+    /// How similar the voice in a recording is to the voice of the profile.
+    ///
+    /// Returns the cosine similarity of their embeddings, where `1` means the spectra are identical, or `None` if the
+    /// recording does not contain any sound.
+    ///
+    /// # Arguments
+    ///
+    /// * `audio`: The recording to compare.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys_audio::audio::AudioData;
+    /// # use varys_audio::voice::VoiceProfile;
+    /// // a voice with a pitch of `pitch` whose harmonics fall off by `falloff`
+    /// let voice = |pitch: f32, falloff: f32| {
+    ///     let data = (0..16000)
+    ///         .map(|index| {
+    ///             let time = index as f32 / 16000.0;
+    ///             (1..40)
+    ///                 .map(|harmonic| {
+    ///                     let frequency = pitch * harmonic as f32;
+    ///                     falloff.powi(harmonic) * (2.0 * std::f32::consts::PI * frequency * time).sin()
+    ///                 })
+    ///                 .sum::<f32>()
+    ///                 * (0.5 + 0.5 * (2.0 * std::f32::consts::PI * 4.0 * time).sin())
+    ///         })
+    ///         .collect();
+    ///     AudioData {
+    ///         data,
+    ///         channels: 1,
+    ///         sample_rate: 16000,
+    ///     }
+    /// };
+    /// let profile = VoiceProfile::from_recordings(&[voice(180.0, 0.8), voice(190.0, 0.8)]).unwrap();
+    ///
+    /// let assistant = profile.similarity(&voice(185.0, 0.8)).unwrap();
+    /// let human = profile.similarity(&voice(110.0, 0.95)).unwrap();
+    /// assert!(assistant > 0.9);
+    /// assert!(human < assistant);
+    /// ```
+    pub fn similarity(&self, audio: &AudioData) -> Option<f32> {
+        let embedding = embedding(audio)?;
+
+        Some(
+            self.embedding
+                .iter()
+                .zip(&embedding)
+                .map(|(a, b)| a * b)
+                .sum(),
+        )
+    }
+}
+
+/// Compute the normalised embedding of a recording, see [`VoiceProfile`].
+///
+/// Returns `None` if the recording does not contain any sound.
+///
+/// # Arguments
+///
+/// * `audio`: The recording.
+fn embedding(audio: &AudioData) -> Option<Vec<f32>> {
+    let mut audio = audio.clone();
+    audio.convert_to_mono();
+
+    let sample_rate = audio.sample_rate as usize;
+    let frame_length = FRAME_MS * sample_rate / 1000;
+    let hop_length = HOP_MS * sample_rate / 1000;
+    if frame_length == 0 || hop_length == 0 || audio.data.len() < frame_length {
+        return None;
+    }
+    let size = frame_length.next_power_of_two();
+    let fft = FftPlanner::<f32>::new().plan_fft_forward(size);
+    let window: Vec<f32> = (0..frame_length)
+        .map(|index| 0.5 - 0.5 * (2.0 * PI * index as f32 / frame_length as f32).cos())
+        .collect();
+    let bands = mel_bands(size, audio.sample_rate);
+
+    // the energy and log mel spectrum of every frame
+    let frames: Vec<(f32, Vec<f32>)> = (0..=(audio.data.len() - frame_length) / hop_length)
+        .map(|frame| {
+            let samples = &audio.data[frame * hop_length..frame * hop_length + frame_length];
+            let mut buffer: Vec<Complex<f32>> = samples
+                .iter()
+                .zip(&window)
+                .map(|(sample, weight)| Complex::new(sample * weight, 0.0))
+                .collect();
+            buffer.resize(size, Complex::default());
+            fft.process(&mut buffer);
+
+            let power: Vec<f32> = buffer[..size / 2 + 1]
+                .iter()
+                .map(|bin| bin.norm_sqr())
+                .collect();
+            let spectrum = bands
+                .iter()
+                .map(|band| {
+                    let energy: f32 = band.iter().map(|(bin, weight)| power[*bin] * weight).sum();
+                    10.0 * (energy + 1e-10).log10()
+                })
+                .collect();
+
+            (samples.iter().map(|sample| sample * sample).sum(), spectrum)
+        })
+        .collect();
+
+    let loudest = frames.iter().map(|(energy, _)| *energy).fold(0.0, f32::max);
+    if loudest <= 0.0 {
+        return None;
+    }
+    let voiced: Vec<&Vec<f32>> = frames
+        .iter()
+        .filter(|(energy, _)| 10.0 * (energy / loudest).log10() >= -VOICED_RANGE_DB)
+        .map(|(_, spectrum)| spectrum)
+        .collect();
+    let count = voiced.len() as f32;
+
+    let mut mean: Vec<f32> = (0..BAND_COUNT)
+        .map(|band| voiced.iter().map(|spectrum| spectrum[band]).sum::<f32>() / count)
+        .collect();
+    let deviation: Vec<f32> = (0..BAND_COUNT)
+        .map(|band| {
+            (voiced
+                .iter()
+                .map(|spectrum| (spectrum[band] - mean[band]).powi(2))
+                .sum::<f32>()
+                / count)
+                .sqrt()
+        })
+        .collect();
+
+    // remove the loudness of the recording
+    let level = mean.iter().sum::<f32>() / BAND_COUNT as f32;
+    mean.iter_mut().for_each(|value| *value -= level);
+
+    let mut embedding = mean;
+    embedding.extend(deviation);
+    normalise(&mut embedding);
+
+    Some(embedding)
+}
+
+/// Get triangular mel filters over the bins of a spectrum.
+///
+/// Returns the bins and their weights for each of the [`BAND_COUNT`] bands.
+///
+/// # Arguments
+///
+/// * `size`: The size of the FFT the spectrum was computed with.
+/// * `sample_rate`: The sample rate of the audio.
+fn mel_bands(size: usize, sample_rate: u32) -> Vec<Vec<(usize, f32)>> {
+    let to_mel = |frequency: f32| 2595.0 * (1.0 + frequency / 700.0).log10();
+    let from_mel = |mel: f32| 700.0 * (10_f32.powf(mel / 2595.0) - 1.0);
+    let min_mel = to_mel(MIN_FREQUENCY);
+    let max_mel = to_mel(MAX_FREQUENCY.min(sample_rate as f32 / 2.0));
+    let edges: Vec<f32> = (0..BAND_COUNT + 2)
+        .map(|index| {
+            from_mel(min_mel + (max_mel - min_mel) * index as f32 / (BAND_COUNT + 1) as f32)
+                * size as f32
+                / sample_rate as f32
+        })
+        .collect();
+
+    edges
+        .windows(3)
+        .map(|edges| {
+            let (start, centre, end) = (edges[0], edges[1], edges[2]);
+            let band: Vec<(usize, f32)> = (start.ceil() as usize..=end.floor() as usize)
+                .map(|bin| {
+                    let position = bin as f32;
+                    let weight = if position <= centre {
+                        (position - start) / (centre - start).max(f32::EPSILON)
+                    } else {
+                        (end - position) / (end - centre).max(f32::EPSILON)
+                    };
+                    (bin, weight)
+                })
+                .filter(|(_, weight)| *weight > 0.0)
+                .collect();
+
+            // bands that are narrower than a bin use the nearest bin
+            if band.is_empty() {
+                vec![(centre.round() as usize, 1.0)]
+            } else {
+                band
+            }
+        })
+        .collect()
+}
+
+/// Scale a vector to unit length.
+///
+/// # Arguments
+///
+/// * `vector`: The vector to scale.
+fn normalise(vector: &mut [f32]) {
+    let length = vector.iter().map(|value| value * value).sum::<f32>().sqrt();
+    if length > 0.0 {
+        vector.iter_mut().for_each(|value| *value /= length);
+    }
+}
@@ -0,0 +1,164 @@
+use log::{debug, warn};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::audio::AudioData;
+
+/// The duration of each chip of a watermark in milliseconds.
+pub const CHIP_DURATION_MS: usize = 1;
+/// The number of chips of a watermark.
+pub const CHIP_COUNT: usize = 512;
+/// The amplitude at which watermarks are embedded by default, low enough to be masked by speech.
+pub const DEFAULT_AMPLITUDE: f32 = 0.005;
+/// How many standard deviations the correlation with a watermark must lie above the mean for it to be detected.
+const DETECTION_SCORE: f32 = 6.0;
+
+/// A pseudo-random noise sequence that is mixed into audio at a low amplitude.
+///
+/// The watermark can later be found in a recording of the audio by correlating the recording with the sequence, which
+/// also tells at which point in the recording the audio was played back.
+#[derive(Clone, Debug)]
+pub struct Watermark {
+    chips: Vec<f32>,
+}
+
+impl Watermark {
+    /// Create a watermark from a seed. Watermarks with the same seed are identical.
+    ///
+    /// # Arguments
+    ///
+    /// * `seed`: The seed of the pseudo-random sequence.
+    pub fn new(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        Watermark {
+            chips: (0..CHIP_COUNT)
+                .map(|_| if rng.gen() { 1.0 } else { -1.0 })
+                .collect(),
+        }
+    }
+
+    /// The duration of the watermark in milliseconds.
+    pub fn duration_ms(&self) -> usize {
+        self.chips.len() * CHIP_DURATION_MS
+    }
+
+    /// Mix the watermark into the start of audio.
+    ///
+    /// If the audio is shorter than the watermark, it is extended with silence.
+    ///
+    /// # Arguments
+    ///
+    /// * `audio`: The audio to watermark.
+    /// * `amplitude`: The amplitude of the watermark.
+    pub fn embed(&self, audio: &mut AudioData, amplitude: f32) {
+        let channels = audio.channels.max(1) as usize;
+        let frames = self.duration_ms() * audio.sample_rate as usize / 1000;
+        if audio.data.len() < frames * channels {
+            audio.data.resize(frames * channels, 0.0);
+        }
+
+        for (index, frame) in audio
+            .data
+            .chunks_exact_mut(channels)
+            .take(frames)
+            .enumerate()
+        {
+            let chip = self.chips[index * 1000 / (audio.sample_rate as usize * CHIP_DURATION_MS)];
+            frame
+                .iter_mut()
+                .for_each(|sample| *sample += chip * amplitude);
+        }
+    }
+
+    /// Find the watermark in a recording.
+    ///
+    /// The recording is split into blocks of one chip, which are correlated with the watermark at every offset. The
+    /// watermark is detected at the offset with the highest correlation if it clearly stands out from all others.
+    ///
+    /// # Arguments
+    ///
+    /// * `audio`: The recording to search.
+    ///
+    /// Returns the start of the watermark in the recording in milliseconds, or `None` if it was not found.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys_audio::audio::AudioData;
+    /// # use varys_audio::watermark::{Watermark, DEFAULT_AMPLITUDE};
+    /// let watermark = Watermark::new(42);
+    /// let mut prompt = AudioData {
+    ///     data: vec![0.0; 22050],
+    ///     channels: 1,
+    ///     sample_rate: 22050,
+    /// };
+    /// watermark.embed(&mut prompt, DEFAULT_AMPLITUDE);
+    ///
+    /// // the prompt is played back 300ms into the recording
+    /// let mut recording = AudioData {
+    ///     data: vec![0.0; 6615],
+    ///     channels: 1,
+    ///     sample_rate: 22050,
+    /// };
+    /// recording.data.extend(&prompt.data);
+    ///
+    /// assert_eq!(watermark.detect(&recording), Some(300));
+    /// assert_eq!(Watermark::new(7).detect(&recording), None);
+    /// ```
+    pub fn detect(&self, audio: &AudioData) -> Option<i32> {
+        let channels = audio.channels.max(1) as usize;
+        let chip_start = |chip: usize| chip * CHIP_DURATION_MS * audio.sample_rate as usize / 1000;
+        if chip_start(1) == 0 {
+            warn!("Cannot detect watermarks at {}hz", audio.sample_rate);
+
+            return None;
+        }
+
+        // the average of each chip-long block of the recording
+        let frames = audio.data.len() / channels;
+        let blocks: Vec<f32> = (0..)
+            .take_while(|&chip| chip_start(chip + 1) <= frames)
+            .map(|chip| {
+                let block =
+                    &audio.data[chip_start(chip) * channels..chip_start(chip + 1) * channels];
+                block.iter().sum::<f32>() / block.len() as f32
+            })
+            .collect();
+        if blocks.len() < self.chips.len() {
+            return None;
+        }
+
+        let correlations: Vec<f32> = (0..=blocks.len() - self.chips.len())
+            .map(|offset| {
+                blocks[offset..]
+                    .iter()
+                    .zip(&self.chips)
+                    .map(|(block, chip)| block * chip)
+                    .sum()
+            })
+            .collect();
+
+        let count = correlations.len() as f32;
+        let mean = correlations.iter().sum::<f32>() / count;
+        let deviation = (correlations
+            .iter()
+            .map(|correlation| (correlation - mean).powi(2))
+            .sum::<f32>()
+            / count)
+            .sqrt();
+        let (offset, peak) = correlations
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))?;
+        let score = if deviation > 0.0 {
+            (peak - mean) / deviation
+        } else {
+            0.0
+        };
+
+        debug!("Best watermark match at chip {offset} with a score of {score:.1}");
+
+        (score >= DETECTION_SCORE).then_some((offset * CHIP_DURATION_MS) as i32)
+    }
+}
@@ -0,0 +1,11 @@
+create table crash (
+    id serial primary key,
+    session_id int,
+    interaction_id int,
+    message text not null,
+    version text not null,
+    occurred timestamptz not null,
+
+    constraint fk_session foreign key (session_id) references session(id),
+    constraint fk_interaction foreign key (interaction_id) references interaction(id)
+);
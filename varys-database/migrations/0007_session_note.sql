@@ -0,0 +1,8 @@
+create table session_note (
+    id serial primary key,
+    session_id int not null,
+    note text not null,
+    created timestamptz not null,
+
+    constraint fk_session foreign key (session_id) references session(id)
+);
@@ -0,0 +1 @@
+alter table interaction add column response_latency_ms int;
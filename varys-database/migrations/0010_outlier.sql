@@ -0,0 +1,9 @@
+create table outlier (
+    id serial primary key,
+    interaction_id int not null,
+    metric text not null,
+    value double precision not null,
+    flagged timestamptz not null,
+
+    constraint fk_interaction foreign key (interaction_id) references interaction(id)
+);
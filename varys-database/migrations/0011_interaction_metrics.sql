@@ -0,0 +1,15 @@
+create table interaction_metrics (
+    id serial primary key,
+    interaction_id int not null,
+    cpu_usage real not null,
+    process_cpu_usage real not null,
+    memory_usage bigint not null,
+    disk_written bigint not null,
+    disk_write_rate double precision not null,
+    packets_received int not null,
+    buffer_dropped int not null,
+    interface_dropped int not null,
+    recorded timestamptz not null,
+
+    constraint fk_interaction foreign key (interaction_id) references interaction(id)
+);
@@ -0,0 +1,10 @@
+create table relabel (
+    id serial primary key,
+    interaction_id int not null,
+    transcript text not null,
+    similarity real not null,
+    label text,
+    relabelled timestamptz not null,
+
+    constraint fk_interaction foreign key (interaction_id) references interaction(id)
+);
@@ -0,0 +1 @@
+alter table interaction add column mic_muted boolean not null default false;
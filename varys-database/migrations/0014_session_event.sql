@@ -0,0 +1,10 @@
+create table session_event (
+    id serial primary key,
+    session_id int not null,
+    kind text not null,
+    detail text not null,
+    started timestamptz not null,
+    ended timestamptz,
+
+    constraint fk_session foreign key (session_id) references session(id)
+);
@@ -0,0 +1 @@
+alter table interaction add column query_watermark_ms int;
@@ -0,0 +1,10 @@
+create table room_response (
+    id serial primary key,
+    session_id int not null,
+    file text not null,
+    delay_ms int not null,
+    reverberation_ms int,
+    measured timestamptz not null,
+
+    constraint fk_session foreign key (session_id) references session(id)
+);
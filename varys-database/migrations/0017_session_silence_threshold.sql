@@ -0,0 +1 @@
+alter table session add column silence_threshold real;
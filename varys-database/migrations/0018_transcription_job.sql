@@ -0,0 +1,7 @@
+create table transcription_job (
+    id serial primary key,
+    interaction_id int not null unique,
+    created timestamptz not null,
+
+    constraint fk_interaction foreign key (interaction_id) references interaction(id)
+);
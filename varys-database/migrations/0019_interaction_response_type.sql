@@ -0,0 +1 @@
+alter table interaction add column response_type text;
@@ -0,0 +1,11 @@
+create table conversation (
+    id serial primary key,
+    session_id int not null,
+    started timestamptz not null,
+    ended timestamptz,
+
+    constraint fk_session foreign key (session_id) references session(id)
+);
+
+alter table interaction add column conversation_id int references conversation(id);
+alter table interaction add column conversation_turn int;
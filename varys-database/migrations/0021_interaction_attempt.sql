@@ -0,0 +1 @@
+alter table interaction add column attempt int not null default 1;
@@ -0,0 +1 @@
+alter table interaction add column status text not null default 'valid';
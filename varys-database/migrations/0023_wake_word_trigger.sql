@@ -0,0 +1,12 @@
+create table wake_word_trigger (
+    id serial primary key,
+    session_id int not null,
+    interaction_id int,
+    assistant text not null,
+    voice text not null,
+    triggered boolean not null,
+    recorded timestamptz not null,
+
+    constraint fk_session foreign key (session_id) references session(id),
+    constraint fk_interaction foreign key (interaction_id) references interaction(id)
+);
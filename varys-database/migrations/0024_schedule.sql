@@ -0,0 +1,23 @@
+create table schedule (
+    id serial primary key,
+    name text not null unique,
+    expression text not null,
+    assistant text not null,
+    mac text not null,
+    queries text not null,
+    data_dir text not null,
+    next_run timestamptz not null,
+    created timestamptz not null
+);
+
+create table schedule_run (
+    id serial primary key,
+    schedule_id int not null,
+    session_id int,
+    started timestamptz not null,
+    ended timestamptz,
+    error text,
+
+    constraint fk_schedule foreign key (schedule_id) references schedule(id) on delete cascade,
+    constraint fk_session foreign key (session_id) references session(id)
+);
@@ -0,0 +1 @@
+alter table interaction add column idle_gap_ms int;
@@ -0,0 +1 @@
+alter table interaction add column silence_threshold real;
@@ -0,0 +1 @@
+alter table interaction add column query_recording text;
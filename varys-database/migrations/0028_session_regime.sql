@@ -0,0 +1,8 @@
+create table session_regime (
+    id serial primary key,
+    session_id int not null unique,
+    regime int not null,
+    assigned timestamptz not null,
+
+    constraint fk_session foreign key (session_id) references session(id)
+);
@@ -0,0 +1,18 @@
+create table dataset (
+    id serial primary key,
+    name text not null unique,
+    note text,
+    created timestamptz not null
+);
+
+create table dataset_query (
+    id serial primary key,
+    dataset_id int not null,
+    text text not null,
+    category text not null,
+    note text,
+    created timestamptz not null,
+
+    constraint fk_dataset foreign key (dataset_id) references dataset(id) on delete cascade,
+    unique (dataset_id, text)
+);
@@ -0,0 +1 @@
+alter table interaction add column conditions text;
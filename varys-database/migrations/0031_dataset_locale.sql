@@ -0,0 +1,2 @@
+alter table dataset add column locale text;
+alter table dataset_query add column locale text;
@@ -0,0 +1 @@
+alter table interaction add column speaking_rate real;
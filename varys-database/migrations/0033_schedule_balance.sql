@@ -0,0 +1 @@
+alter table schedule add column balance int;
@@ -0,0 +1 @@
+alter table session add column dataset_version text;
@@ -0,0 +1,8 @@
+create table rig (
+    id serial primary key,
+    name text not null unique,
+    room text not null,
+    mac text not null,
+    registered timestamptz not null,
+    last_seen timestamptz not null
+);
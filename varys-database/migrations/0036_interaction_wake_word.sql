@@ -0,0 +1,2 @@
+alter table interaction add column wake_word text;
+update interaction set wake_word = substring(query from '^(Hey Siri|Alexa|Hey Google|OK Google)\. '), query = regexp_replace(query, '^(Hey Siri|Alexa|Hey Google|OK Google)\. ', '');
@@ -0,0 +1,2 @@
+alter table schedule add column quiet_hours text;
+alter table schedule add column idle_capture boolean not null default false;
@@ -0,0 +1 @@
+alter table interaction add column channel text not null default 'voice';
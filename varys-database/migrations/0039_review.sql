@@ -0,0 +1,8 @@
+create table review (
+    id serial primary key,
+    interaction_id int not null unique,
+    accepted boolean not null,
+    reviewed timestamptz not null,
+
+    constraint fk_interaction foreign key (interaction_id) references interaction(id)
+);
@@ -0,0 +1 @@
+alter table interaction add column query_loopback_file text;
@@ -0,0 +1,4 @@
+alter table interaction add column query_peak_dbfs real;
+alter table interaction add column query_rms_dbfs real;
+alter table interaction add column response_peak_dbfs real;
+alter table interaction add column response_rms_dbfs real;
@@ -0,0 +1 @@
+alter table interaction add column radio_capture_file text;
@@ -0,0 +1 @@
+alter table interaction add column response_lossless_file text;
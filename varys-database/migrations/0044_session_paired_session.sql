@@ -0,0 +1,2 @@
+alter table session add column paired_session_id integer;
+alter table session add constraint fk_paired_session foreign key (paired_session_id) references session(id);
@@ -0,0 +1 @@
+alter table schedule add column allow_repeats boolean not null default false;
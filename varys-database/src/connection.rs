@@ -1,5 +1,6 @@
 use sqlx::PgPool;
 
+#[derive(Clone)]
 pub struct DatabaseConnection {
     pub(crate) pool: PgPool,
 }
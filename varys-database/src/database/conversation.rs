@@ -0,0 +1,151 @@
+use std::fmt::{Display, Formatter};
+
+use chrono::{DateTime, Utc};
+use log::info;
+use sqlx::FromRow;
+
+use crate::connection::DatabaseConnection;
+use crate::database;
+use crate::database::interaction::Interaction;
+use crate::error::Error;
+
+/// The representation of a conversation in the database.
+///
+/// A conversation links the interactions of a multi-turn dialogue, where each follow-up query relies on the context
+/// of the previous ones, e.g. "Who is Ian McKellen?" followed by "How old is he?". Each conversation belongs to a
+/// [`Session`](crate::database::session::Session) and each of its turns is an [`Interaction`].
+#[derive(FromRow, Debug)]
+pub struct Conversation {
+    pub id: i32,
+    /// The id of the session this conversation was held in.
+    pub session_id: i32,
+    /// When this conversation was started.
+    pub started: DateTime<Utc>,
+    /// When this conversation was ended.
+    ///
+    /// If this is `None`, the conversation is still running or was aborted.
+    pub ended: Option<DateTime<Utc>>,
+}
+
+impl Conversation {
+    /// Create a new conversation in the database, starting now.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    /// * `session_id`: The id of the session the conversation is held in.
+    pub async fn create(connection: &DatabaseConnection, session_id: i32) -> Result<Self, Error> {
+        let started = Utc::now();
+        let query = sqlx::query!(
+            "INSERT INTO conversation (session_id, started) VALUES ($1, $2) RETURNING id",
+            session_id,
+            started,
+        );
+
+        database::log_query(&query);
+        let id = query.fetch_one(&connection.pool).await?.id;
+
+        Ok(Conversation {
+            id,
+            session_id,
+            started,
+            ended: None,
+        })
+    }
+
+    /// Get a conversation from the database.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    /// * `id`: The id of the conversation.
+    pub async fn get(connection: &DatabaseConnection, id: i32) -> Result<Option<Self>, Error> {
+        let query = sqlx::query_as!(Self, "SELECT * FROM conversation WHERE id = $1", id);
+
+        database::log_query(&query);
+        Ok(query.fetch_optional(&connection.pool).await?)
+    }
+
+    /// Get all conversations held in a session from the database, oldest first.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    /// * `session_id`: The id of the session.
+    pub async fn get_by_session(
+        connection: &DatabaseConnection,
+        session_id: i32,
+    ) -> Result<Vec<Self>, Error> {
+        let query = sqlx::query_as!(
+            Self,
+            "SELECT * FROM conversation WHERE session_id = $1 ORDER BY started",
+            session_id
+        );
+
+        database::log_query(&query);
+        Ok(query.fetch_all(&connection.pool).await?)
+    }
+
+    /// Update all values of a conversation in the database.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    pub async fn update(&mut self, connection: &DatabaseConnection) -> Result<&mut Self, Error> {
+        let query = sqlx::query!(
+            "UPDATE conversation SET (session_id, started, ended) = ($1, $2, $3) WHERE id = $4",
+            self.session_id,
+            self.started,
+            self.ended,
+            self.id
+        );
+
+        database::log_query(&query);
+        query.execute(&connection.pool).await?;
+
+        Ok(self)
+    }
+
+    /// Mark a conversation as completed by setting its end time.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    pub async fn complete(&mut self, connection: &DatabaseConnection) -> Result<&mut Self, Error> {
+        self.ended = Some(Utc::now());
+        self.update(connection).await?;
+
+        info!("Completed {self}");
+
+        Ok(self)
+    }
+
+    /// Get the turns of the conversation from the database, in the order they were held.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    pub async fn interactions(
+        &self,
+        connection: &DatabaseConnection,
+    ) -> Result<Vec<Interaction>, Error> {
+        let query = sqlx::query_as!(
+            Interaction,
+            "SELECT * FROM interaction WHERE conversation_id = $1 ORDER BY conversation_turn",
+            self.id
+        );
+
+        database::log_query(&query);
+        Ok(query.fetch_all(&connection.pool).await?)
+    }
+}
+
+impl Display for Conversation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Conversation {} on session {} ({})",
+            self.id, self.session_id, self.started
+        )
+    }
+}
@@ -0,0 +1,86 @@
+use std::fmt::{Display, Formatter};
+
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+
+use crate::connection::DatabaseConnection;
+use crate::database;
+use crate::error::Error;
+
+/// The representation of a crash in the database.
+///
+/// A crash is recorded when varys panics, together with the session and interaction that were
+/// running at the time.
+#[derive(FromRow, Debug)]
+pub struct Crash {
+    pub id: i32,
+    /// The id of the session that was running when the crash occurred.
+    pub session_id: Option<i32>,
+    /// The id of the interaction that was running when the crash occurred.
+    pub interaction_id: Option<i32>,
+    /// The panic message, including the location of the panic.
+    pub message: String,
+    /// What version of varys crashed.
+    pub version: String,
+    /// When the crash occurred.
+    pub occurred: DateTime<Utc>,
+}
+
+impl Crash {
+    /// Create a new crash in the database.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    /// * `session_id`: The id of the session that was running, if any.
+    /// * `interaction_id`: The id of the interaction that was running, if any.
+    /// * `message`: The panic message.
+    /// * `version`: The version of varys that crashed.
+    pub async fn create(
+        connection: &DatabaseConnection,
+        session_id: Option<i32>,
+        interaction_id: Option<i32>,
+        message: &str,
+        version: String,
+    ) -> Result<Self, Error> {
+        let occurred = Utc::now();
+        let query = sqlx::query!(
+            "INSERT INTO crash (session_id, interaction_id, message, version, occurred) VALUES ($1, $2, $3, $4, $5) RETURNING id",
+            session_id,
+            interaction_id,
+            message,
+            version,
+            occurred,
+        );
+
+        database::log_query(&query);
+        let id = query.fetch_one(&connection.pool).await?.id;
+
+        Ok(Crash {
+            id,
+            session_id,
+            interaction_id,
+            message: message.to_string(),
+            version,
+            occurred,
+        })
+    }
+
+    /// Get all crashes from the database.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    pub async fn get_all(connection: &DatabaseConnection) -> Result<Vec<Self>, Error> {
+        let query = sqlx::query_as!(Self, "SELECT * FROM crash");
+
+        database::log_query(&query);
+        Ok(query.fetch_all(&connection.pool).await?)
+    }
+}
+
+impl Display for Crash {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Crash {} (occurred {})", self.id, self.occurred)
+    }
+}
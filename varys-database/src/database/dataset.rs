@@ -0,0 +1,197 @@
+use std::fmt::{Display, Formatter};
+
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+
+use crate::connection::DatabaseConnection;
+use crate::database;
+use crate::error::Error;
+
+/// The representation of a named dataset in the database.
+///
+/// Named datasets are sets of queries curated by researchers, which sessions can ask by referencing the dataset by its
+/// name instead of a queries file. Its queries are stored as [`NamedDatasetQuery`]s.
+#[derive(FromRow, Debug, Clone)]
+pub struct NamedDataset {
+    pub id: i32,
+    /// The unique name of the dataset.
+    pub name: String,
+    /// A description of the dataset, e.g. what it was curated for.
+    pub note: Option<String>,
+    /// When the dataset was created.
+    pub created: DateTime<Utc>,
+    /// The locale its queries are asked in unless they have their own, e.g. `de-DE`.
+    pub locale: Option<String>,
+}
+
+impl NamedDataset {
+    /// Create a new named dataset without queries in the database.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    /// * `name`: The unique name of the dataset.
+    /// * `note`: A description of the dataset.
+    /// * `locale`: The locale its queries are asked in unless they have their own.
+    pub async fn create(
+        connection: &DatabaseConnection,
+        name: &str,
+        note: Option<&str>,
+        locale: Option<&str>,
+    ) -> Result<Self, Error> {
+        let created = Utc::now();
+        let query = sqlx::query!(
+            "INSERT INTO dataset (name, note, created, locale) VALUES ($1, $2, $3, $4) RETURNING id",
+            name,
+            note,
+            created,
+            locale,
+        );
+
+        database::log_query(&query);
+        let id = query.fetch_one(&connection.pool).await?.id;
+
+        Ok(NamedDataset {
+            id,
+            name: name.to_string(),
+            note: note.map(str::to_string),
+            created,
+            locale: locale.map(str::to_string),
+        })
+    }
+
+    /// Get a named dataset by its name from the database.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    /// * `name`: The name of the dataset.
+    pub async fn get_by_name(
+        connection: &DatabaseConnection,
+        name: &str,
+    ) -> Result<Option<Self>, Error> {
+        let query = sqlx::query_as!(Self, "SELECT * FROM dataset WHERE name = $1", name);
+
+        database::log_query(&query);
+        Ok(query.fetch_optional(&connection.pool).await?)
+    }
+
+    /// Get all named datasets from the database, ordered by name.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    pub async fn get_all(connection: &DatabaseConnection) -> Result<Vec<Self>, Error> {
+        let query = sqlx::query_as!(Self, "SELECT * FROM dataset ORDER BY name");
+
+        database::log_query(&query);
+        Ok(query.fetch_all(&connection.pool).await?)
+    }
+
+    /// Add a query to the dataset in the database.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    /// * `text`: The text of the query.
+    /// * `category`: The category of the query.
+    /// * `note`: A note on the query, e.g. why it was added.
+    /// * `locale`: The locale the query is asked in, or `None` to use the locale of the dataset.
+    pub async fn add_query(
+        &self,
+        connection: &DatabaseConnection,
+        text: &str,
+        category: &str,
+        note: Option<&str>,
+        locale: Option<&str>,
+    ) -> Result<NamedDatasetQuery, Error> {
+        let created = Utc::now();
+        let query = sqlx::query!(
+            "INSERT INTO dataset_query (dataset_id, text, category, note, created, locale) VALUES ($1, $2, $3, $4, $5, $6) RETURNING id",
+            self.id,
+            text,
+            category,
+            note,
+            created,
+            locale,
+        );
+
+        database::log_query(&query);
+        let id = query.fetch_one(&connection.pool).await?.id;
+
+        Ok(NamedDatasetQuery {
+            id,
+            dataset_id: self.id,
+            text: text.to_string(),
+            category: category.to_string(),
+            note: note.map(str::to_string),
+            created,
+            locale: locale.map(str::to_string),
+        })
+    }
+
+    /// Get the queries of the dataset from the database, in the order they were added.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    pub async fn queries(
+        &self,
+        connection: &DatabaseConnection,
+    ) -> Result<Vec<NamedDatasetQuery>, Error> {
+        let query = sqlx::query_as!(
+            NamedDatasetQuery,
+            "SELECT * FROM dataset_query WHERE dataset_id = $1 ORDER BY id",
+            self.id
+        );
+
+        database::log_query(&query);
+        Ok(query.fetch_all(&connection.pool).await?)
+    }
+}
+
+impl Display for NamedDataset {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Dataset {}", self.name)?;
+        if let Some(locale) = &self.locale {
+            write!(f, " [{locale}]")?;
+        }
+        if let Some(note) = &self.note {
+            write!(f, ": {note}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The representation of a query of a [`NamedDataset`] in the database.
+#[derive(FromRow, Debug, Clone)]
+pub struct NamedDatasetQuery {
+    pub id: i32,
+    /// The id of the dataset this query belongs to.
+    pub dataset_id: i32,
+    /// The text of the query.
+    pub text: String,
+    /// The category of the query.
+    pub category: String,
+    /// A note on the query, e.g. why it was added.
+    pub note: Option<String>,
+    /// When the query was added.
+    pub created: DateTime<Utc>,
+    /// The locale the query is asked in, e.g. `de-DE`, or `None` to use the locale of the dataset.
+    pub locale: Option<String>,
+}
+
+impl Display for NamedDatasetQuery {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.text, self.category)?;
+        if let Some(locale) = &self.locale {
+            write!(f, " [{locale}]")?;
+        }
+        if let Some(note) = &self.note {
+            write!(f, ": {note}")?;
+        }
+
+        Ok(())
+    }
+}
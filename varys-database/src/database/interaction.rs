@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::fmt::Display;
 
 use chrono::{DateTime, Utc};
@@ -20,8 +21,12 @@ pub struct Interaction {
     ///
     /// Session ids are sequenced.
     pub session_id: i32,
-    /// The query that was asked for this interaction.
+    /// The query that was asked for this interaction, without the wake word.
     pub query: String,
+    /// The wake word the query was asked with, e.g. `Hey Siri`.
+    ///
+    /// If this is `None`, the query was asked without a wake word.
+    pub wake_word: Option<String>,
     /// The category of the query.
     pub query_category: String,
     /// The duration of the query in milliseconds.
@@ -32,6 +37,16 @@ pub struct Interaction {
     ///
     /// Stored inside the session `data_dir`.
     pub query_file: Option<String>,
+    /// The file with the pre-recorded query that was played instead of synthesising the query.
+    ///
+    /// If this is `None`, the query was synthesised with text-to-speech.
+    pub query_recording: Option<String>,
+    /// The file with the loopback recording of what the speaker played while the query was asked.
+    ///
+    /// If this is `None`, the output of the speaker was not recorded.
+    pub query_loopback_file: Option<String>,
+    /// How the query was asked, `voice` if it was spoken or `text` if it was typed to the assistant.
+    pub channel: String,
     /// The recorded response from the voice assistant.
     ///
     /// Currently, short responses are sometimes not recognised accurately. Watch `response_duration`
@@ -47,12 +62,90 @@ pub struct Interaction {
     ///
     /// Stored inside the session `data_dir`.
     pub response_file: Option<String>,
+    /// The file with a lossless copy of the recorded response, which is only stored for exemplar queries.
+    ///
+    /// Stored inside the session `data_dir`. If this is `None`, only the compressed response was stored.
+    pub response_lossless_file: Option<String>,
+    /// The kind of the response, e.g. `answered` or `clarification`, classified from its transcript.
+    ///
+    /// If this is `None`, the response has not been transcribed yet.
+    pub response_type: Option<String>,
+    /// The time between the end of the spoken query and the start of the audible response in milliseconds.
+    ///
+    /// If this is `None`, the interaction is still running, was aborted or no response was heard.
+    pub response_latency_ms: Option<i32>,
+    /// The time between the end of the spoken query and the first large packet sent to the assistant in
+    /// milliseconds.
+    ///
+    /// If this is `None`, the interaction is still running, was aborted or no such packet was captured.
+    pub response_network_latency_ms: Option<i32>,
     /// The file with the captured traffic.
     ///
     /// Stored inside the session `data_dir`.
     pub capture_file: Option<String>,
+    /// The file with the frames observed over the air by a radio capture, e.g. of Zigbee or Thread traffic.
+    ///
+    /// Stored inside the session `data_dir`. If this is `None`, no radio capture was running.
+    pub radio_capture_file: Option<String>,
     /// The MAC address of the assistant.
     pub assistant_mac: String,
+    /// Whether the microphone of the assistant was muted during this interaction.
+    ///
+    /// This is only ever `true` for interactions held during a mute experiment.
+    pub mic_muted: bool,
+    /// The time in milliseconds from the start of the query recording until the watermark of the spoken query was
+    /// detected, if the query was watermarked and its watermark was found.
+    pub query_watermark_ms: Option<i32>,
+    /// The id of the [`Conversation`](crate::database::conversation::Conversation) this interaction is a turn of.
+    ///
+    /// If this is `None`, the interaction was a single query.
+    pub conversation_id: Option<i32>,
+    /// The position of this interaction in its conversation, starting at `0`.
+    pub conversation_turn: Option<i32>,
+    /// How often the query had been asked when this interaction was started, starting at `1`.
+    ///
+    /// Queries are asked again if their interaction failed and retries are enabled.
+    pub attempt: i32,
+    /// The randomised idle time in milliseconds that was waited before this interaction was started.
+    ///
+    /// If this is `None`, no idle gap was waited, e.g. because idle gaps were disabled or this was the first
+    /// interaction of its session or a follow-up in a conversation.
+    pub idle_gap_ms: Option<i32>,
+    /// The threshold that distinguished silence from sound while the response was recorded.
+    ///
+    /// This can differ between the interactions of a session if the sensitivity is calibrated again during it. If
+    /// this is `None`, the interaction was held before thresholds were stored per interaction.
+    pub silence_threshold: Option<f32>,
+    /// The level of the loudest sample of the recorded query in dBFS, before its level was adjusted.
+    ///
+    /// If this is `None`, the query was not recorded, was silent or the interaction was held before levels were
+    /// stored.
+    pub query_peak_dbfs: Option<f32>,
+    /// The root mean square level of the recorded query in dBFS, before its level was adjusted.
+    ///
+    /// If this is `None`, the query was not recorded, was silent or the interaction was held before levels were
+    /// stored.
+    pub query_rms_dbfs: Option<f32>,
+    /// The level of the loudest sample of the recorded response in dBFS, before its level was adjusted.
+    ///
+    /// If this is `None`, no response was heard or the interaction was held before levels were stored.
+    pub response_peak_dbfs: Option<f32>,
+    /// The root mean square level of the recorded response in dBFS, before its level was adjusted.
+    ///
+    /// If this is `None`, no response was heard or the interaction was held before levels were stored.
+    pub response_rms_dbfs: Option<f32>,
+    /// The speaking rate the query was synthesised at, relative to the normal rate of the voice.
+    ///
+    /// If this is `None`, the query was played from a recording or the interaction was held before speaking rates were
+    /// stored.
+    pub speaking_rate: Option<f32>,
+    /// The random choices made for this interaction and the parameters it was collected with, as JSON.
+    ///
+    /// If this is `None`, the interaction was held before the conditions were stored.
+    pub conditions: Option<String>,
+    /// Whether the interaction is a usable sample, e.g. `valid` or `failed_no_response` if the assistant did not
+    /// respond.
+    pub status: String,
     /// When this interaction was started.
     pub started: DateTime<Utc>,
     /// When this interaction was ended.
@@ -80,7 +173,7 @@ impl Interaction {
     ) -> Result<Self, Error> {
         let started = Utc::now();
         let query = sqlx::query!(
-            "INSERT INTO interaction (started, session_id, query, query_category, assistant_mac) VALUES ($1, $2, $3, $4, $5) RETURNING id",
+            "INSERT INTO interaction (started, session_id, query, query_category, assistant_mac) VALUES ($1, $2, $3, $4, $5) RETURNING id, status",
             started,
             session.id,
             text,
@@ -89,20 +182,43 @@ impl Interaction {
         );
 
         database::log_query(&query);
-        let id = query.fetch_one(&connection.pool).await?.id;
+        let row = query.fetch_one(&connection.pool).await?;
 
         Ok(Interaction {
-            id,
+            id: row.id,
             session_id: session.id,
             query: text.to_string(),
+            wake_word: None,
             query_category: category.to_string(),
             query_duration: None,
             query_file: None,
+            query_recording: None,
+            query_loopback_file: None,
+            channel: String::from("voice"),
             response: None,
             response_duration: None,
             response_file: None,
+            response_lossless_file: None,
+            response_type: None,
+            response_latency_ms: None,
+            response_network_latency_ms: None,
             capture_file: None,
+            radio_capture_file: None,
             assistant_mac,
+            mic_muted: false,
+            query_watermark_ms: None,
+            conversation_id: None,
+            conversation_turn: None,
+            attempt: 1,
+            idle_gap_ms: None,
+            silence_threshold: None,
+            query_peak_dbfs: None,
+            query_rms_dbfs: None,
+            response_peak_dbfs: None,
+            response_rms_dbfs: None,
+            speaking_rate: None,
+            conditions: None,
+            status: row.status,
             started,
             ended: None,
         })
@@ -160,17 +276,40 @@ impl Interaction {
     /// * `connection`: The connection to use.
     pub async fn update(&mut self, connection: &DatabaseConnection) -> Result<&mut Self, Error> {
         let query = sqlx::query!(
-            "UPDATE interaction SET (session_id, query, query_category, query_duration, query_file, response, response_duration, response_file, capture_file, assistant_mac, started, ended) = ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12) WHERE id = $13",
+            "UPDATE interaction SET (session_id, query, wake_word, query_category, query_duration, query_file, response, response_duration, response_file, response_lossless_file, response_type, response_latency_ms, response_network_latency_ms, capture_file, radio_capture_file, assistant_mac, mic_muted, query_watermark_ms, conversation_id, conversation_turn, attempt, idle_gap_ms, silence_threshold, query_peak_dbfs, query_rms_dbfs, response_peak_dbfs, response_rms_dbfs, query_recording, query_loopback_file, channel, conditions, speaking_rate, status, started, ended) = ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22, $23, $24, $25, $26, $27, $28, $29, $30, $31, $32, $33, $34, $35) WHERE id = $36",
             self.session_id,
             self.query,
+            self.wake_word,
             self.query_category,
             self.query_duration,
             self.query_file,
             self.response,
             self.response_duration,
             self.response_file,
+            self.response_lossless_file,
+            self.response_type,
+            self.response_latency_ms,
+            self.response_network_latency_ms,
             self.capture_file,
+            self.radio_capture_file,
             self.assistant_mac,
+            self.mic_muted,
+            self.query_watermark_ms,
+            self.conversation_id,
+            self.conversation_turn,
+            self.attempt,
+            self.idle_gap_ms,
+            self.silence_threshold,
+            self.query_peak_dbfs,
+            self.query_rms_dbfs,
+            self.response_peak_dbfs,
+            self.response_rms_dbfs,
+            self.query_recording,
+            self.query_loopback_file,
+            self.channel,
+            self.conditions,
+            self.speaking_rate,
+            self.status,
             self.started,
             self.ended,
             self.id
@@ -196,9 +335,47 @@ impl Interaction {
         Ok(self)
     }
 
+    /// Count the completed interactions with an assistant that have a status and are not flagged as outliers, per
+    /// query.
+    ///
+    /// Queries without such interactions are missing from the counts.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    /// * `assistant_mac`: The MAC address of the assistant.
+    /// * `status`: The status of the counted interactions, e.g. `valid`.
+    pub async fn count_by_query(
+        connection: &DatabaseConnection,
+        assistant_mac: &str,
+        status: &str,
+    ) -> Result<HashMap<String, i64>, Error> {
+        let query = sqlx::query!(
+            r#"SELECT query, COUNT(*) AS "count!" FROM interaction WHERE assistant_mac = $1 AND status = $2 AND ended IS NOT NULL AND id NOT IN (SELECT interaction_id FROM outlier) GROUP BY query"#,
+            assistant_mac,
+            status
+        );
+
+        database::log_query(&query);
+        Ok(query
+            .fetch_all(&connection.pool)
+            .await?
+            .into_iter()
+            .map(|row| (row.query, row.count))
+            .collect())
+    }
+
     pub fn is_complete(&self) -> bool {
         self.ended.is_some()
     }
+
+    /// The query as it was spoken, prefixed with the wake word it was asked with.
+    pub fn spoken_query(&self) -> String {
+        match &self.wake_word {
+            Some(wake_word) => format!("{wake_word}. {}", self.query),
+            None => self.query.clone(),
+        }
+    }
 }
 
 impl Display for Interaction {
@@ -0,0 +1,122 @@
+use std::fmt::{Display, Formatter};
+
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+
+use crate::connection::DatabaseConnection;
+use crate::database;
+use crate::error::Error;
+
+/// The representation of host metrics recorded during an interaction in the database.
+///
+/// These describe the load on the machine running varys, to correlate it with data quality issues like dropped
+/// packets. Each entry belongs to an [`Interaction`](crate::database::interaction::Interaction).
+#[derive(FromRow, Debug)]
+pub struct InteractionMetrics {
+    pub id: i32,
+    /// The id of the interaction the metrics were recorded during.
+    pub interaction_id: i32,
+    /// The average usage of all CPUs in percent.
+    pub cpu_usage: f32,
+    /// The average CPU usage of varys in percent, where 100% is one fully used core.
+    pub process_cpu_usage: f32,
+    /// The memory used by varys at the end of the interaction in bytes.
+    pub memory_usage: i64,
+    /// How many bytes varys has written to disk during the interaction.
+    pub disk_written: i64,
+    /// How many bytes varys has written to disk per second during the interaction.
+    pub disk_write_rate: f64,
+    /// The number of packets received by the sniffer.
+    ///
+    /// The sniffer statistics are 0 if the whole session was captured at once.
+    pub packets_received: i32,
+    /// The number of packets dropped because the capture buffer was full.
+    pub buffer_dropped: i32,
+    /// The number of packets dropped by the network interface.
+    pub interface_dropped: i32,
+    /// When these metrics were recorded.
+    pub recorded: DateTime<Utc>,
+}
+
+impl InteractionMetrics {
+    /// Store new metrics in the database.
+    ///
+    /// The `id` and `recorded` fields of `metrics` are ignored and set by the database.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    /// * `metrics`: The metrics to store.
+    pub async fn create(
+        connection: &DatabaseConnection,
+        mut metrics: InteractionMetrics,
+    ) -> Result<Self, Error> {
+        metrics.recorded = Utc::now();
+        let query = sqlx::query!(
+            "INSERT INTO interaction_metrics (interaction_id, cpu_usage, process_cpu_usage, memory_usage, disk_written, disk_write_rate, packets_received, buffer_dropped, interface_dropped, recorded) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10) RETURNING id",
+            metrics.interaction_id,
+            metrics.cpu_usage,
+            metrics.process_cpu_usage,
+            metrics.memory_usage,
+            metrics.disk_written,
+            metrics.disk_write_rate,
+            metrics.packets_received,
+            metrics.buffer_dropped,
+            metrics.interface_dropped,
+            metrics.recorded,
+        );
+
+        database::log_query(&query);
+        metrics.id = query.fetch_one(&connection.pool).await?.id;
+
+        Ok(metrics)
+    }
+
+    /// Get the metrics recorded during an interaction from the database.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    /// * `interaction_id`: The id of the interaction.
+    pub async fn get_by_interaction(
+        connection: &DatabaseConnection,
+        interaction_id: i32,
+    ) -> Result<Option<Self>, Error> {
+        let query = sqlx::query_as!(
+            Self,
+            "SELECT * FROM interaction_metrics WHERE interaction_id = $1",
+            interaction_id
+        );
+
+        database::log_query(&query);
+        Ok(query.fetch_optional(&connection.pool).await?)
+    }
+
+    /// Get all recorded metrics from the database.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    pub async fn get_all(connection: &DatabaseConnection) -> Result<Vec<Self>, Error> {
+        let query = sqlx::query_as!(Self, "SELECT * FROM interaction_metrics");
+
+        database::log_query(&query);
+        Ok(query.fetch_all(&connection.pool).await?)
+    }
+}
+
+impl Display for InteractionMetrics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Interaction {}: {:.1}% CPU ({:.1}% varys), {} MB memory, {:.1} kB/s written, {} of {} packets dropped",
+            self.interaction_id,
+            self.cpu_usage,
+            self.process_cpu_usage,
+            self.memory_usage / 1_000_000,
+            self.disk_write_rate / 1000.,
+            self.buffer_dropped + self.interface_dropped,
+            self.packets_received,
+        )
+    }
+}
@@ -0,0 +1,133 @@
+use std::fmt::{Display, Formatter};
+
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+
+use crate::connection::DatabaseConnection;
+use crate::database;
+use crate::error::Error;
+
+/// The metric of interactions whose response does not sound like the voice of the assistant, e.g. because a person in
+/// the room talked over it. Its value is the similarity to the voice profile of the assistant.
+pub const VOICE_SIMILARITY_METRIC: &str = "voice_similarity";
+/// The metric of interactions whose response was still going on when the recording timeout stopped recording it. Its
+/// value is the duration of the recorded response in milliseconds.
+pub const TRUNCATED_AT_TIMEOUT_METRIC: &str = "truncated_at_timeout";
+/// The metric of interactions whose traffic burst continued after the recorded response ended, so the recording was
+/// probably cut off. Its value is how long the burst continued in milliseconds.
+pub const TRAFFIC_AFTER_RESPONSE_METRIC: &str = "traffic_after_response";
+/// The metrics that interactions are flagged in while they are collected, which cannot be recomputed later.
+pub const COLLECTION_METRICS: &[&str] = &[
+    VOICE_SIMILARITY_METRIC,
+    TRUNCATED_AT_TIMEOUT_METRIC,
+    TRAFFIC_AFTER_RESPONSE_METRIC,
+];
+
+/// The representation of an outlier flag in the database.
+///
+/// An interaction is flagged as an outlier if one of its metrics, e.g. the length of its traffic trace, is unusual
+/// compared to other interactions with the same query. Flagged interactions are excluded from datasets.
+#[derive(FromRow, Debug)]
+pub struct Outlier {
+    pub id: i32,
+    /// The id of the flagged interaction.
+    pub interaction_id: i32,
+    /// The name of the metric that is an outlier.
+    pub metric: String,
+    /// The value of the metric.
+    pub value: f64,
+    /// When the interaction was flagged.
+    pub flagged: DateTime<Utc>,
+}
+
+impl Outlier {
+    /// Flag an interaction as an outlier in the database.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    /// * `interaction_id`: The id of the interaction to flag.
+    /// * `metric`: The name of the metric that is an outlier.
+    /// * `value`: The value of the metric.
+    pub async fn create(
+        connection: &DatabaseConnection,
+        interaction_id: i32,
+        metric: &str,
+        value: f64,
+    ) -> Result<Self, Error> {
+        let flagged = Utc::now();
+        let query = sqlx::query!(
+            "INSERT INTO outlier (interaction_id, metric, value, flagged) VALUES ($1, $2, $3, $4) RETURNING id",
+            interaction_id,
+            metric,
+            value,
+            flagged,
+        );
+
+        database::log_query(&query);
+        let id = query.fetch_one(&connection.pool).await?.id;
+
+        Ok(Outlier {
+            id,
+            interaction_id,
+            metric: metric.to_string(),
+            value,
+            flagged,
+        })
+    }
+
+    /// Get all outlier flags from the database.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    pub async fn get_all(connection: &DatabaseConnection) -> Result<Vec<Self>, Error> {
+        let query = sqlx::query_as!(Self, "SELECT * FROM outlier");
+
+        database::log_query(&query);
+        Ok(query.fetch_all(&connection.pool).await?)
+    }
+
+    /// Remove all outlier flags from the database.
+    ///
+    /// Returns the number of removed flags.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    pub async fn delete_all(connection: &DatabaseConnection) -> Result<u64, Error> {
+        let query = sqlx::query!("DELETE FROM outlier");
+
+        database::log_query(&query);
+        Ok(query.execute(&connection.pool).await?.rows_affected())
+    }
+
+    /// Remove all outlier flags from the database, except those of some metrics.
+    ///
+    /// Returns the number of removed flags.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    /// * `metrics`: The names of the metrics whose flags are kept.
+    pub async fn delete_all_except(
+        connection: &DatabaseConnection,
+        metrics: &[&str],
+    ) -> Result<u64, Error> {
+        let metrics: Vec<String> = metrics.iter().map(ToString::to_string).collect();
+        let query = sqlx::query!("DELETE FROM outlier WHERE metric != ALL($1)", &metrics);
+
+        database::log_query(&query);
+        Ok(query.execute(&connection.pool).await?.rows_affected())
+    }
+}
+
+impl Display for Outlier {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Interaction {} is an outlier in {} ({})",
+            self.interaction_id, self.metric, self.value
+        )
+    }
+}
@@ -0,0 +1,113 @@
+use std::fmt::{Display, Formatter};
+
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+
+use crate::connection::DatabaseConnection;
+use crate::database;
+use crate::error::Error;
+
+/// The representation of a relabelled interaction in the database.
+///
+/// An interaction is relabelled if the transcript of its recorded query differs too much from the query that was
+/// supposed to be spoken. Datasets then either use the new label or exclude the interaction.
+#[derive(FromRow, Debug)]
+pub struct Relabel {
+    pub id: i32,
+    /// The id of the relabelled interaction.
+    pub interaction_id: i32,
+    /// The transcript of the recorded query.
+    pub transcript: String,
+    /// How similar the transcript is to the original query, from `0` to `1`.
+    pub similarity: f32,
+    /// The query the interaction is reassigned to.
+    ///
+    /// If this is `None`, the interaction is invalid and excluded from datasets.
+    pub label: Option<String>,
+    /// When the interaction was relabelled.
+    pub relabelled: DateTime<Utc>,
+}
+
+impl Relabel {
+    /// Relabel an interaction in the database.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    /// * `interaction_id`: The id of the interaction to relabel.
+    /// * `transcript`: The transcript of the recorded query.
+    /// * `similarity`: How similar the transcript is to the original query.
+    /// * `label`: The query to reassign the interaction to, or `None` to invalidate it.
+    pub async fn create(
+        connection: &DatabaseConnection,
+        interaction_id: i32,
+        transcript: &str,
+        similarity: f32,
+        label: Option<String>,
+    ) -> Result<Self, Error> {
+        let relabelled = Utc::now();
+        let query = sqlx::query!(
+            "INSERT INTO relabel (interaction_id, transcript, similarity, label, relabelled) VALUES ($1, $2, $3, $4, $5) RETURNING id",
+            interaction_id,
+            transcript,
+            similarity,
+            label,
+            relabelled,
+        );
+
+        database::log_query(&query);
+        let id = query.fetch_one(&connection.pool).await?.id;
+
+        Ok(Relabel {
+            id,
+            interaction_id,
+            transcript: transcript.to_string(),
+            similarity,
+            label,
+            relabelled,
+        })
+    }
+
+    /// Get all relabelled interactions from the database.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    pub async fn get_all(connection: &DatabaseConnection) -> Result<Vec<Self>, Error> {
+        let query = sqlx::query_as!(Self, "SELECT * FROM relabel");
+
+        database::log_query(&query);
+        Ok(query.fetch_all(&connection.pool).await?)
+    }
+
+    /// Remove all relabels from the database.
+    ///
+    /// Returns the number of removed relabels.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    pub async fn delete_all(connection: &DatabaseConnection) -> Result<u64, Error> {
+        let query = sqlx::query!("DELETE FROM relabel");
+
+        database::log_query(&query);
+        Ok(query.execute(&connection.pool).await?.rows_affected())
+    }
+}
+
+impl Display for Relabel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.label {
+            Some(label) => write!(
+                f,
+                "Interaction {} was reassigned to \"{label}\" (heard \"{}\", {:.2})",
+                self.interaction_id, self.transcript, self.similarity
+            ),
+            None => write!(
+                f,
+                "Interaction {} was invalidated (heard \"{}\", {:.2})",
+                self.interaction_id, self.transcript, self.similarity
+            ),
+        }
+    }
+}
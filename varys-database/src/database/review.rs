@@ -0,0 +1,87 @@
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+
+use crate::connection::DatabaseConnection;
+use crate::database;
+use crate::error::Error;
+
+/// The representation of a manual review of an interaction in the database.
+///
+/// Interactions are reviewed by looking at their recordings, transcripts and traces, e.g. in the viewer of the cli.
+/// Rejected interactions are excluded from datasets.
+#[derive(FromRow, Debug)]
+pub struct Review {
+    pub id: i32,
+    /// The id of the reviewed interaction, every interaction is reviewed at most once.
+    pub interaction_id: i32,
+    /// Whether the interaction is a usable sample.
+    pub accepted: bool,
+    /// When the interaction was last reviewed.
+    pub reviewed: DateTime<Utc>,
+}
+
+impl Review {
+    /// Review an interaction in the database, replacing any earlier review of it.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    /// * `interaction_id`: The id of the interaction to review.
+    /// * `accepted`: Whether the interaction is a usable sample.
+    pub async fn set(
+        connection: &DatabaseConnection,
+        interaction_id: i32,
+        accepted: bool,
+    ) -> Result<Self, Error> {
+        let reviewed = Utc::now();
+        let query = sqlx::query!(
+            "INSERT INTO review (interaction_id, accepted, reviewed) VALUES ($1, $2, $3) ON CONFLICT (interaction_id) DO UPDATE SET accepted = $2, reviewed = $3 RETURNING id",
+            interaction_id,
+            accepted,
+            reviewed,
+        );
+
+        database::log_query(&query);
+        let id = query.fetch_one(&connection.pool).await?.id;
+
+        Ok(Review {
+            id,
+            interaction_id,
+            accepted,
+            reviewed,
+        })
+    }
+
+    /// Get all reviews from the database.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    pub async fn get_all(connection: &DatabaseConnection) -> Result<Vec<Self>, Error> {
+        let query = sqlx::query_as!(Self, "SELECT * FROM review");
+
+        database::log_query(&query);
+        Ok(query.fetch_all(&connection.pool).await?)
+    }
+
+    /// Remove the review of an interaction from the database.
+    ///
+    /// Returns whether the interaction had been reviewed.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    /// * `interaction_id`: The id of the interaction.
+    pub async fn delete(
+        connection: &DatabaseConnection,
+        interaction_id: i32,
+    ) -> Result<bool, Error> {
+        let query = sqlx::query!(
+            "DELETE FROM review WHERE interaction_id = $1",
+            interaction_id
+        );
+
+        database::log_query(&query);
+        Ok(query.execute(&connection.pool).await?.rows_affected() > 0)
+    }
+}
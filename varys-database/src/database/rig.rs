@@ -0,0 +1,120 @@
+use std::fmt::{Display, Formatter};
+
+use chrono::{DateTime, Utc};
+use log::info;
+use sqlx::FromRow;
+
+use crate::connection::DatabaseConnection;
+use crate::database;
+use crate::error::Error;
+
+/// The representation of a rig in the database, one varys instance of a multi-room deployment.
+///
+/// Rigs register in the shared database and keep their registration alive while they run, so every rig can find the
+/// others and take its share of the queries and time slots.
+#[derive(FromRow, Debug, Clone)]
+pub struct Rig {
+    pub id: i32,
+    /// The unique name of the rig.
+    pub name: String,
+    /// The room the rig is set up in.
+    pub room: String,
+    /// The MAC address of the assistant of the rig.
+    pub mac: String,
+    /// When the rig first registered.
+    pub registered: DateTime<Utc>,
+    /// When the rig was last seen running.
+    pub last_seen: DateTime<Utc>,
+}
+
+impl Rig {
+    /// Register a rig in the database, or update the room, assistant and last seen time of the rig if it is already
+    /// registered.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    /// * `name`: The unique name of the rig.
+    /// * `room`: The room the rig is set up in.
+    /// * `mac`: The MAC address of the assistant of the rig.
+    pub async fn register(
+        connection: &DatabaseConnection,
+        name: &str,
+        room: &str,
+        mac: &str,
+    ) -> Result<Self, Error> {
+        let now = Utc::now();
+        let query = sqlx::query_as!(
+            Self,
+            "INSERT INTO rig (name, room, mac, registered, last_seen) VALUES ($1, $2, $3, $4, $4) ON CONFLICT (name) DO UPDATE SET (room, mac, last_seen) = ($2, $3, $4) RETURNING *",
+            name,
+            room,
+            mac,
+            now,
+        );
+
+        database::log_query(&query);
+        Ok(query.fetch_one(&connection.pool).await?)
+    }
+
+    /// Get all rigs from the database, in the order they first registered.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    pub async fn get_all(connection: &DatabaseConnection) -> Result<Vec<Self>, Error> {
+        let query = sqlx::query_as!(Self, "SELECT * FROM rig ORDER BY id");
+
+        database::log_query(&query);
+        Ok(query.fetch_all(&connection.pool).await?)
+    }
+
+    /// Get all rigs that were seen running since a time, in the order they first registered.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    /// * `since`: The time after which the rigs were last seen.
+    pub async fn get_active(
+        connection: &DatabaseConnection,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<Self>, Error> {
+        let query = sqlx::query_as!(
+            Self,
+            "SELECT * FROM rig WHERE last_seen >= $1 ORDER BY id",
+            since
+        );
+
+        database::log_query(&query);
+        Ok(query.fetch_all(&connection.pool).await?)
+    }
+
+    /// Remove a rig from the database, so its queries and time slots are taken over by the other rigs.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    pub async fn unregister(self, connection: &DatabaseConnection) -> Result<(), Error> {
+        let query = sqlx::query!("DELETE FROM rig WHERE id = $1", self.id);
+
+        database::log_query(&query);
+        query.execute(&connection.pool).await?;
+
+        info!("Unregistered {self}");
+
+        Ok(())
+    }
+}
+
+impl Display for Rig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Rig {} in {} ({}), last seen at {}",
+            self.name,
+            self.room,
+            self.mac,
+            self.last_seen.format("%Y-%m-%d %H:%M %Z")
+        )
+    }
+}
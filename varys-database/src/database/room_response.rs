@@ -0,0 +1,105 @@
+use std::fmt::{Display, Formatter};
+
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+
+use crate::connection::DatabaseConnection;
+use crate::database;
+use crate::error::Error;
+
+/// The representation of a measured room impulse response in the database.
+///
+/// Impulse responses characterise the acoustic conditions of a [`Session`](crate::database::session::Session), so
+/// sessions recorded in different rooms or setups can be told apart.
+#[derive(FromRow, Debug)]
+pub struct RoomResponse {
+    pub id: i32,
+    /// The id of the session the response was measured for.
+    pub session_id: i32,
+    /// The `.wav` file in which the impulse response is stored.
+    pub file: String,
+    /// The delay between playing and recording the measurement in milliseconds.
+    pub delay_ms: i32,
+    /// The estimated reverberation time (RT60) of the room in milliseconds.
+    ///
+    /// If this is `None`, the impulse response did not decay enough to estimate it.
+    pub reverberation_ms: Option<i32>,
+    /// When the response was measured.
+    pub measured: DateTime<Utc>,
+}
+
+impl RoomResponse {
+    /// Create a new room response in the database, measured now.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    /// * `session_id`: The id of the session the response was measured for.
+    /// * `file`: The `.wav` file in which the impulse response is stored.
+    /// * `delay_ms`: The delay between playing and recording the measurement in milliseconds.
+    /// * `reverberation_ms`: The estimated reverberation time of the room in milliseconds.
+    pub async fn create(
+        connection: &DatabaseConnection,
+        session_id: i32,
+        file: &str,
+        delay_ms: i32,
+        reverberation_ms: Option<i32>,
+    ) -> Result<Self, Error> {
+        let measured = Utc::now();
+        let query = sqlx::query!(
+            "INSERT INTO room_response (session_id, file, delay_ms, reverberation_ms, measured) VALUES ($1, $2, $3, $4, $5) RETURNING id",
+            session_id,
+            file,
+            delay_ms,
+            reverberation_ms,
+            measured,
+        );
+
+        database::log_query(&query);
+        let id = query.fetch_one(&connection.pool).await?.id;
+
+        Ok(RoomResponse {
+            id,
+            session_id,
+            file: file.to_string(),
+            delay_ms,
+            reverberation_ms,
+            measured,
+        })
+    }
+
+    /// Get all room responses measured for a session from the database, oldest first.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    /// * `session_id`: The id of the session.
+    pub async fn get_by_session(
+        connection: &DatabaseConnection,
+        session_id: i32,
+    ) -> Result<Vec<Self>, Error> {
+        let query = sqlx::query_as!(
+            Self,
+            "SELECT * FROM room_response WHERE session_id = $1 ORDER BY measured",
+            session_id
+        );
+
+        database::log_query(&query);
+        Ok(query.fetch_all(&connection.pool).await?)
+    }
+}
+
+impl Display for RoomResponse {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Room response of session {} ({}): {}ms delay, ",
+            self.session_id, self.measured, self.delay_ms
+        )?;
+        match self.reverberation_ms {
+            Some(reverberation_ms) => write!(f, "{reverberation_ms}ms RT60")?,
+            None => write!(f, "unknown RT60")?,
+        }
+        write!(f, ", stored in {}", self.file)
+    }
+}
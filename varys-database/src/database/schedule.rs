@@ -0,0 +1,338 @@
+use std::fmt::{Display, Formatter};
+
+use chrono::{DateTime, Utc};
+use log::info;
+use sqlx::FromRow;
+
+use crate::connection::DatabaseConnection;
+use crate::database;
+use crate::error::Error;
+
+/// The representation of a schedule in the database.
+///
+/// A schedule runs a session with the queries of a file whenever its cron expression matches, e.g. every 6 hours.
+/// Every time it runs, a [`ScheduleRun`] is stored.
+#[derive(FromRow, Debug, Clone)]
+pub struct Schedule {
+    pub id: i32,
+    /// The unique name of the schedule.
+    pub name: String,
+    /// The cron expression of the times the schedule runs at.
+    pub expression: String,
+    /// The name of the assistant to interact with.
+    pub assistant: String,
+    /// The MAC address of the assistant.
+    pub mac: String,
+    /// The path to the file with the queries to ask.
+    pub queries: String,
+    /// The path to the directory in which data files are stored.
+    pub data_dir: String,
+    /// When the schedule runs next.
+    pub next_run: DateTime<Utc>,
+    /// When the schedule was created.
+    pub created: DateTime<Utc>,
+    /// How many of its queries every session asks, those with the fewest valid samples first.
+    ///
+    /// If this is `None`, every session asks all queries.
+    pub balance: Option<i32>,
+    /// The daily hours in local time in which no queries are spoken, e.g. `22:00-07:00`.
+    ///
+    /// If this is `None`, queries are spoken at any time.
+    pub quiet_hours: Option<String>,
+    /// Whether to capture the idle traffic of the assistant during the quiet hours.
+    pub idle_capture: bool,
+    /// Whether the file may list a query more than once, to ask it more often.
+    pub allow_repeats: bool,
+}
+
+impl Schedule {
+    /// Create a new schedule in the database.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    /// * `name`: The unique name of the schedule.
+    /// * `expression`: The cron expression of the times the schedule runs at.
+    /// * `assistant`: The name of the assistant to interact with.
+    /// * `mac`: The MAC address of the assistant.
+    /// * `queries`: The path to the file with the queries to ask.
+    /// * `data_dir`: The path to the directory in which data files are stored.
+    /// * `next_run`: When the schedule runs first.
+    /// * `balance`: How many of its queries every session asks, or `None` to ask all.
+    /// * `quiet_hours`: The daily hours in which no queries are spoken, or `None` to speak at any time.
+    /// * `idle_capture`: Whether to capture the idle traffic of the assistant during the quiet hours.
+    /// * `allow_repeats`: Whether the file may list a query more than once.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create(
+        connection: &DatabaseConnection,
+        name: &str,
+        expression: &str,
+        assistant: &str,
+        mac: &str,
+        queries: &str,
+        data_dir: &str,
+        next_run: DateTime<Utc>,
+        balance: Option<i32>,
+        quiet_hours: Option<String>,
+        idle_capture: bool,
+        allow_repeats: bool,
+    ) -> Result<Self, Error> {
+        let created = Utc::now();
+        let query = sqlx::query!(
+            "INSERT INTO schedule (name, expression, assistant, mac, queries, data_dir, next_run, created, balance, quiet_hours, idle_capture, allow_repeats) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12) RETURNING id",
+            name,
+            expression,
+            assistant,
+            mac,
+            queries,
+            data_dir,
+            next_run,
+            created,
+            balance,
+            quiet_hours,
+            idle_capture,
+            allow_repeats,
+        );
+
+        database::log_query(&query);
+        let id = query.fetch_one(&connection.pool).await?.id;
+
+        Ok(Schedule {
+            id,
+            name: name.to_string(),
+            expression: expression.to_string(),
+            assistant: assistant.to_string(),
+            mac: mac.to_string(),
+            queries: queries.to_string(),
+            data_dir: data_dir.to_string(),
+            next_run,
+            created,
+            balance,
+            quiet_hours,
+            idle_capture,
+            allow_repeats,
+        })
+    }
+
+    /// Get a schedule by its name from the database.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    /// * `name`: The name of the schedule.
+    pub async fn get_by_name(
+        connection: &DatabaseConnection,
+        name: &str,
+    ) -> Result<Option<Self>, Error> {
+        let query = sqlx::query_as!(Self, "SELECT * FROM schedule WHERE name = $1", name);
+
+        database::log_query(&query);
+        Ok(query.fetch_optional(&connection.pool).await?)
+    }
+
+    /// Get all schedules from the database, the one that runs next first.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    pub async fn get_all(connection: &DatabaseConnection) -> Result<Vec<Self>, Error> {
+        let query = sqlx::query_as!(Self, "SELECT * FROM schedule ORDER BY next_run");
+
+        database::log_query(&query);
+        Ok(query.fetch_all(&connection.pool).await?)
+    }
+
+    /// Update all values of a schedule in the database.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    pub async fn update(&mut self, connection: &DatabaseConnection) -> Result<&mut Self, Error> {
+        let query = sqlx::query!(
+            "UPDATE schedule SET (name, expression, assistant, mac, queries, data_dir, next_run, balance, quiet_hours, idle_capture, allow_repeats) = ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11) WHERE id = $12",
+            self.name,
+            self.expression,
+            self.assistant,
+            self.mac,
+            self.queries,
+            self.data_dir,
+            self.next_run,
+            self.balance,
+            self.quiet_hours,
+            self.idle_capture,
+            self.allow_repeats,
+            self.id
+        );
+
+        database::log_query(&query);
+        query.execute(&connection.pool).await?;
+
+        Ok(self)
+    }
+
+    /// Remove a schedule and its history from the database.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    pub async fn delete(self, connection: &DatabaseConnection) -> Result<(), Error> {
+        let query = sqlx::query!("DELETE FROM schedule WHERE id = $1", self.id);
+
+        database::log_query(&query);
+        query.execute(&connection.pool).await?;
+
+        info!("Removed {self}");
+
+        Ok(())
+    }
+
+    /// Get the runs of the schedule from the database, the most recent first.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    pub async fn history(
+        &self,
+        connection: &DatabaseConnection,
+    ) -> Result<Vec<ScheduleRun>, Error> {
+        let query = sqlx::query_as!(
+            ScheduleRun,
+            "SELECT * FROM schedule_run WHERE schedule_id = $1 ORDER BY started DESC",
+            self.id
+        );
+
+        database::log_query(&query);
+        Ok(query.fetch_all(&connection.pool).await?)
+    }
+}
+
+impl Display for Schedule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Schedule {} ({}): {} with {}",
+            self.name, self.expression, self.assistant, self.queries
+        )?;
+        if let Some(balance) = self.balance {
+            write!(f, ", balancing {balance} queries per session")?;
+        }
+        if let Some(quiet_hours) = &self.quiet_hours {
+            write!(f, ", quiet from {quiet_hours}")?;
+        }
+        write!(
+            f,
+            ", next run at {}",
+            self.next_run.format("%Y-%m-%d %H:%M %Z")
+        )
+    }
+}
+
+/// The representation of one run of a [`Schedule`] in the database.
+#[derive(FromRow, Debug)]
+pub struct ScheduleRun {
+    pub id: i32,
+    /// The id of the schedule this run belongs to.
+    pub schedule_id: i32,
+    /// The id of the session that was run.
+    ///
+    /// If this is `None`, the run has not finished or failed before its session was created.
+    pub session_id: Option<i32>,
+    /// When the run started.
+    pub started: DateTime<Utc>,
+    /// When the run ended.
+    ///
+    /// If this is `None`, the run has not finished, e.g. because varys was stopped during it.
+    pub ended: Option<DateTime<Utc>>,
+    /// Why the run failed, if it did.
+    pub error: Option<String>,
+}
+
+impl ScheduleRun {
+    /// Create a new run of a schedule in the database, starting now.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    /// * `schedule_id`: The id of the schedule the run belongs to.
+    pub async fn create(connection: &DatabaseConnection, schedule_id: i32) -> Result<Self, Error> {
+        let started = Utc::now();
+        let query = sqlx::query!(
+            "INSERT INTO schedule_run (schedule_id, started) VALUES ($1, $2) RETURNING id",
+            schedule_id,
+            started,
+        );
+
+        database::log_query(&query);
+        let id = query.fetch_one(&connection.pool).await?.id;
+
+        Ok(ScheduleRun {
+            id,
+            schedule_id,
+            session_id: None,
+            started,
+            ended: None,
+            error: None,
+        })
+    }
+
+    /// Update all values of a run in the database.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    pub async fn update(&mut self, connection: &DatabaseConnection) -> Result<&mut Self, Error> {
+        let query = sqlx::query!(
+            "UPDATE schedule_run SET (schedule_id, session_id, started, ended, error) = ($1, $2, $3, $4, $5) WHERE id = $6",
+            self.schedule_id,
+            self.session_id,
+            self.started,
+            self.ended,
+            self.error,
+            self.id
+        );
+
+        database::log_query(&query);
+        query.execute(&connection.pool).await?;
+
+        Ok(self)
+    }
+
+    /// Mark a run as completed by setting its end time, the session it ran and why it failed.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    /// * `session_id`: The id of the session that was run, if it was created.
+    /// * `error`: Why the run failed, if it did.
+    pub async fn complete(
+        &mut self,
+        connection: &DatabaseConnection,
+        session_id: Option<i32>,
+        error: Option<String>,
+    ) -> Result<&mut Self, Error> {
+        self.session_id = session_id;
+        self.error = error;
+        self.ended = Some(Utc::now());
+        self.update(connection).await
+    }
+}
+
+impl Display for ScheduleRun {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.started.format("%Y-%m-%d %H:%M %Z"))?;
+
+        if let Some(session_id) = self.session_id {
+            write!(f, " session {session_id}")?;
+        }
+
+        match (&self.ended, &self.error) {
+            (None, _) => write!(f, " did not finish"),
+            (Some(ended), None) => write!(
+                f,
+                " completed after {} min",
+                (*ended - self.started).num_minutes()
+            ),
+            (Some(_), Some(error)) => write!(f, " failed: {error}"),
+        }
+    }
+}
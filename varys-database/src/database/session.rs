@@ -8,6 +8,9 @@ use crate::connection::DatabaseConnection;
 use crate::database;
 use crate::database::interaction::Interaction;
 use crate::database::interactor_config::InteractorConfig;
+use crate::database::room_response::RoomResponse;
+use crate::database::session_event::SessionEvent;
+use crate::database::session_note::SessionNote;
 use crate::error::Error;
 
 /// The representation of a session in the database.
@@ -18,9 +21,23 @@ pub struct Session {
     pub id: i32,
     /// What version of varys this session was run on.
     pub version: String,
+    /// Which queries were asked in this session with which version of varys, so sessions of different datasets are
+    /// not mixed unnoticed.
+    ///
+    /// If this is `None`, the session was run before dataset versions were recorded.
+    pub dataset_version: Option<String>,
     interactor_config_id: i32,
     /// The directory where the session data is stored.
     pub data_dir: Option<String>,
+    /// The threshold that distinguished silence from sound during this session.
+    ///
+    /// If this is `None`, the threshold was not recorded.
+    pub silence_threshold: Option<f32>,
+    /// The session whose queries this session asked again in the same order and with the same timings, e.g. against
+    /// another assistant, so the two can be compared as a pair.
+    ///
+    /// If this is `None`, the session asked its own queries.
+    pub paired_session_id: Option<i32>,
     /// When this session was started.
     pub started: DateTime<Utc>,
     /// When this session was ended.
@@ -37,17 +54,20 @@ impl Session {
     /// * `connection`: The connection to use.
     /// * `config`: The config to use.
     /// * `version`: The version of varys this session was run on.
+    /// * `dataset_version`: The version of the queries asked in this session.
     pub async fn create(
         connection: &DatabaseConnection,
         config: &InteractorConfig,
         version: String,
+        dataset_version: String,
     ) -> Result<Self, Error> {
         let started = Utc::now();
         let interactor_config_id = config.get_or_create(connection).await?;
         let query = sqlx::query!(
-            "INSERT INTO session (started, version, interactor_config_id) VALUES ($1, $2, $3) RETURNING id",
+            "INSERT INTO session (started, version, dataset_version, interactor_config_id) VALUES ($1, $2, $3, $4) RETURNING id",
             started,
             version,
+            dataset_version,
             interactor_config_id,
         );
 
@@ -57,8 +77,11 @@ impl Session {
         Ok(Session {
             id,
             version,
+            dataset_version: Some(dataset_version),
             interactor_config_id,
             data_dir: None,
+            silence_threshold: None,
+            paired_session_id: None,
             started,
             ended: None,
         })
@@ -89,6 +112,26 @@ impl Session {
         Ok(query.fetch_all(&connection.pool).await?)
     }
 
+    /// Get all dataset versions of the sessions in the database, with how many sessions were run with each, ordered
+    /// by when they were first used.
+    ///
+    /// Sessions run before dataset versions were recorded are not included.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    pub async fn dataset_versions(
+        connection: &DatabaseConnection,
+    ) -> Result<Vec<DatasetVersion>, Error> {
+        let query = sqlx::query_as!(
+            DatasetVersion,
+            r#"SELECT dataset_version AS "dataset_version!", COUNT(*) AS "sessions!", MIN(started) AS "first_started!", MAX(started) AS "last_started!" FROM session WHERE dataset_version IS NOT NULL GROUP BY dataset_version ORDER BY MIN(started)"#
+        );
+
+        database::log_query(&query);
+        Ok(query.fetch_all(&connection.pool).await?)
+    }
+
     /// Update all values of a session in the database.
     ///
     /// # Arguments
@@ -96,10 +139,13 @@ impl Session {
     /// * `connection`: The connection to use.
     pub async fn update(&mut self, connection: &DatabaseConnection) -> Result<&mut Self, Error> {
         let query = sqlx::query!(
-            "UPDATE session SET (version, interactor_config_id, data_dir, started, ended) = ($1, $2, $3, $4, $5) WHERE id = $6",
+            "UPDATE session SET (version, dataset_version, interactor_config_id, data_dir, silence_threshold, paired_session_id, started, ended) = ($1, $2, $3, $4, $5, $6, $7, $8) WHERE id = $9",
             self.version,
+            self.dataset_version,
             self.interactor_config_id,
             self.data_dir,
+            self.silence_threshold,
+            self.paired_session_id,
             self.started,
             self.ended,
             self.id
@@ -148,6 +194,69 @@ impl Session {
     ) -> Result<Vec<Interaction>, Error> {
         Interaction::get_by_session(connection, self.id).await
     }
+
+    /// Add a note to this session.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    /// * `note`: The text of the note.
+    pub async fn add_note(
+        &self,
+        connection: &DatabaseConnection,
+        note: &str,
+    ) -> Result<SessionNote, Error> {
+        SessionNote::create(connection, self.id, note).await
+    }
+
+    /// Get all notes for this session, oldest first.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    pub async fn notes(&self, connection: &DatabaseConnection) -> Result<Vec<SessionNote>, Error> {
+        SessionNote::get_by_session(connection, self.id).await
+    }
+
+    /// Add an event to this session, starting now.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    /// * `kind`: What kind of event this is.
+    /// * `detail`: Details about the event.
+    pub async fn add_event(
+        &self,
+        connection: &DatabaseConnection,
+        kind: &str,
+        detail: &str,
+    ) -> Result<SessionEvent, Error> {
+        SessionEvent::create(connection, self.id, kind, detail).await
+    }
+
+    /// Get all events of this session, oldest first.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    pub async fn events(
+        &self,
+        connection: &DatabaseConnection,
+    ) -> Result<Vec<SessionEvent>, Error> {
+        SessionEvent::get_by_session(connection, self.id).await
+    }
+
+    /// Get all room impulse responses measured for this session, oldest first.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    pub async fn room_responses(
+        &self,
+        connection: &DatabaseConnection,
+    ) -> Result<Vec<RoomResponse>, Error> {
+        RoomResponse::get_by_session(connection, self.id).await
+    }
 }
 
 impl Display for Session {
@@ -155,3 +264,25 @@ impl Display for Session {
         write!(f, "Session {} (started {})", self.id, self.started)
     }
 }
+
+/// A version of the queries asked in sessions, see [`Session::dataset_version`].
+#[derive(FromRow, Debug, Clone, PartialEq)]
+pub struct DatasetVersion {
+    pub dataset_version: String,
+    /// The number of sessions run with this dataset version.
+    pub sessions: i64,
+    /// When the first session with this dataset version was started.
+    pub first_started: DateTime<Utc>,
+    /// When the last session with this dataset version was started.
+    pub last_started: DateTime<Utc>,
+}
+
+impl Display for DatasetVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ({} sessions from {} to {})",
+            self.dataset_version, self.sessions, self.first_started, self.last_started
+        )
+    }
+}
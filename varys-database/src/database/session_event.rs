@@ -0,0 +1,170 @@
+use std::fmt::{Display, Formatter};
+
+use chrono::{DateTime, Utc};
+use log::info;
+use sqlx::FromRow;
+
+use crate::connection::DatabaseConnection;
+use crate::database;
+use crate::error::Error;
+
+/// The representation of a session event in the database.
+///
+/// Events are recorded automatically while a session is running, for example when collection is paused because of
+/// unrelated traffic. Each event belongs to a [`Session`](crate::database::session::Session).
+#[derive(FromRow, Debug)]
+pub struct SessionEvent {
+    pub id: i32,
+    /// The id of the session this event belongs to.
+    pub session_id: i32,
+    /// What kind of event this is.
+    pub kind: String,
+    /// Details about the event.
+    pub detail: String,
+    /// When this event started.
+    pub started: DateTime<Utc>,
+    /// When this event ended.
+    ///
+    /// If this is `None`, the event is still ongoing or happened at a single point in time.
+    pub ended: Option<DateTime<Utc>>,
+}
+
+impl SessionEvent {
+    /// Create a new session event in the database, starting now.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    /// * `session_id`: The id of the session the event belongs to.
+    /// * `kind`: What kind of event this is.
+    /// * `detail`: Details about the event.
+    pub async fn create(
+        connection: &DatabaseConnection,
+        session_id: i32,
+        kind: &str,
+        detail: &str,
+    ) -> Result<Self, Error> {
+        let started = Utc::now();
+        let query = sqlx::query!(
+            "INSERT INTO session_event (session_id, kind, detail, started) VALUES ($1, $2, $3, $4) RETURNING id",
+            session_id,
+            kind,
+            detail,
+            started,
+        );
+
+        database::log_query(&query);
+        let id = query.fetch_one(&connection.pool).await?.id;
+
+        Ok(SessionEvent {
+            id,
+            session_id,
+            kind: kind.to_string(),
+            detail: detail.to_string(),
+            started,
+            ended: None,
+        })
+    }
+
+    /// Get all events belonging to a session from the database, oldest first.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    /// * `session_id`: The id of the session.
+    pub async fn get_by_session(
+        connection: &DatabaseConnection,
+        session_id: i32,
+    ) -> Result<Vec<Self>, Error> {
+        let query = sqlx::query_as!(
+            Self,
+            "SELECT * FROM session_event WHERE session_id = $1 ORDER BY started",
+            session_id
+        );
+
+        database::log_query(&query);
+        Ok(query.fetch_all(&connection.pool).await?)
+    }
+
+    /// Get all session events of a kind from the database, oldest first.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    /// * `kind`: The kind of the events.
+    pub async fn get_by_kind(
+        connection: &DatabaseConnection,
+        kind: &str,
+    ) -> Result<Vec<Self>, Error> {
+        let query = sqlx::query_as!(
+            Self,
+            "SELECT * FROM session_event WHERE kind = $1 ORDER BY started",
+            kind
+        );
+
+        database::log_query(&query);
+        Ok(query.fetch_all(&connection.pool).await?)
+    }
+
+    /// Update all values of a session event in the database.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    pub async fn update(&mut self, connection: &DatabaseConnection) -> Result<&mut Self, Error> {
+        let query = sqlx::query!(
+            "UPDATE session_event SET (session_id, kind, detail, started, ended) = ($1, $2, $3, $4, $5) WHERE id = $6",
+            self.session_id,
+            self.kind,
+            self.detail,
+            self.started,
+            self.ended,
+            self.id
+        );
+
+        database::log_query(&query);
+        query.execute(&connection.pool).await?;
+
+        Ok(self)
+    }
+
+    /// Mark a session event as ended by setting its end time.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    pub async fn end(&mut self, connection: &DatabaseConnection) -> Result<&mut Self, Error> {
+        self.ended = Some(Utc::now());
+        self.update(connection).await?;
+
+        info!("Ended {self}");
+
+        Ok(self)
+    }
+
+    /// Whether this event overlaps with a time span.
+    ///
+    /// Events without an end are treated as happening at a single point in time.
+    ///
+    /// # Arguments
+    ///
+    /// * `start`: The start of the time span.
+    /// * `end`: The end of the time span.
+    pub fn overlaps(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> bool {
+        self.started <= end && self.ended.unwrap_or(self.started) >= start
+    }
+}
+
+impl Display for SessionEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Event {} on session {} ({}",
+            self.kind, self.session_id, self.started
+        )?;
+        if let Some(ended) = self.ended {
+            write!(f, " to {ended}")?;
+        }
+        write!(f, "): {}", self.detail)
+    }
+}
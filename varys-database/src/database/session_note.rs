@@ -0,0 +1,98 @@
+use std::fmt::{Display, Formatter};
+
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+
+use crate::connection::DatabaseConnection;
+use crate::database;
+use crate::error::Error;
+
+/// The representation of a session note in the database.
+///
+/// Notes are added by the operator to record changes to the physical setup, for example moving the voice assistant.
+/// Each note belongs to a [`Session`](crate::database::session::Session).
+#[derive(FromRow, Debug)]
+pub struct SessionNote {
+    pub id: i32,
+    /// The id of the session this note belongs to.
+    pub session_id: i32,
+    /// The text of the note.
+    pub note: String,
+    /// When this note was added.
+    pub created: DateTime<Utc>,
+}
+
+impl SessionNote {
+    /// Create a new session note in the database.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    /// * `session_id`: The id of the session to add the note to.
+    /// * `note`: The text of the note.
+    pub async fn create(
+        connection: &DatabaseConnection,
+        session_id: i32,
+        note: &str,
+    ) -> Result<Self, Error> {
+        let created = Utc::now();
+        let query = sqlx::query!(
+            "INSERT INTO session_note (session_id, note, created) VALUES ($1, $2, $3) RETURNING id",
+            session_id,
+            note,
+            created,
+        );
+
+        database::log_query(&query);
+        let id = query.fetch_one(&connection.pool).await?.id;
+
+        Ok(SessionNote {
+            id,
+            session_id,
+            note: note.to_string(),
+            created,
+        })
+    }
+
+    /// Get all notes belonging to a session from the database, oldest first.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    /// * `session_id`: The id of the session.
+    pub async fn get_by_session(
+        connection: &DatabaseConnection,
+        session_id: i32,
+    ) -> Result<Vec<Self>, Error> {
+        let query = sqlx::query_as!(
+            Self,
+            "SELECT * FROM session_note WHERE session_id = $1 ORDER BY created",
+            session_id
+        );
+
+        database::log_query(&query);
+        Ok(query.fetch_all(&connection.pool).await?)
+    }
+
+    /// Get all session notes from the database, oldest first.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    pub async fn get_all(connection: &DatabaseConnection) -> Result<Vec<Self>, Error> {
+        let query = sqlx::query_as!(Self, "SELECT * FROM session_note ORDER BY created");
+
+        database::log_query(&query);
+        Ok(query.fetch_all(&connection.pool).await?)
+    }
+}
+
+impl Display for SessionNote {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Note on session {} ({}): {}",
+            self.session_id, self.created, self.note
+        )
+    }
+}
@@ -0,0 +1,93 @@
+use std::fmt::{Display, Formatter};
+
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+
+use crate::connection::DatabaseConnection;
+use crate::database;
+use crate::error::Error;
+
+/// The representation of the capture-environment regime of a session in the database.
+///
+/// Sessions are clustered by aggregate features of their interactions, so sessions recorded in the same environment,
+/// e.g. before a change of the router, share a regime. Datasets can be split so that every partition contains the
+/// regimes in the same proportions.
+#[derive(FromRow, Debug)]
+pub struct SessionRegime {
+    pub id: i32,
+    /// The id of the session.
+    pub session_id: i32,
+    /// The regime of the session, numbered in the order in which the regimes were first recorded.
+    pub regime: i32,
+    /// When the regime was assigned.
+    pub assigned: DateTime<Utc>,
+}
+
+impl SessionRegime {
+    /// Assign a regime to a session in the database.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    /// * `session_id`: The id of the session.
+    /// * `regime`: The regime of the session.
+    pub async fn create(
+        connection: &DatabaseConnection,
+        session_id: i32,
+        regime: i32,
+    ) -> Result<Self, Error> {
+        let assigned = Utc::now();
+        let query = sqlx::query!(
+            "INSERT INTO session_regime (session_id, regime, assigned) VALUES ($1, $2, $3) RETURNING id",
+            session_id,
+            regime,
+            assigned,
+        );
+
+        database::log_query(&query);
+        let id = query.fetch_one(&connection.pool).await?.id;
+
+        Ok(SessionRegime {
+            id,
+            session_id,
+            regime,
+            assigned,
+        })
+    }
+
+    /// Get the regimes of all sessions from the database.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    pub async fn get_all(connection: &DatabaseConnection) -> Result<Vec<Self>, Error> {
+        let query = sqlx::query_as!(Self, "SELECT * FROM session_regime ORDER BY session_id");
+
+        database::log_query(&query);
+        Ok(query.fetch_all(&connection.pool).await?)
+    }
+
+    /// Remove the regimes of all sessions from the database.
+    ///
+    /// Returns the number of removed regimes.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    pub async fn delete_all(connection: &DatabaseConnection) -> Result<u64, Error> {
+        let query = sqlx::query!("DELETE FROM session_regime");
+
+        database::log_query(&query);
+        Ok(query.execute(&connection.pool).await?.rows_affected())
+    }
+}
+
+impl Display for SessionRegime {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Session {} is in regime {}",
+            self.session_id, self.regime
+        )
+    }
+}
@@ -0,0 +1,93 @@
+use std::fmt::{Display, Formatter};
+
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+
+use crate::connection::DatabaseConnection;
+use crate::database;
+use crate::error::Error;
+
+/// The representation of a pending transcription in the database.
+///
+/// A job is created when the response of an interaction is queued for transcription in the background and removed
+/// once the transcript is stored. Jobs that remain after the process stopped can be caught up later.
+#[derive(FromRow, Debug)]
+pub struct TranscriptionJob {
+    pub id: i32,
+    /// The id of the interaction whose response is transcribed.
+    pub interaction_id: i32,
+    /// When the response was queued for transcription.
+    pub created: DateTime<Utc>,
+}
+
+impl TranscriptionJob {
+    /// Create a new transcription job in the database, queued now.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    /// * `interaction_id`: The id of the interaction whose response is transcribed.
+    pub async fn create(
+        connection: &DatabaseConnection,
+        interaction_id: i32,
+    ) -> Result<Self, Error> {
+        let created = Utc::now();
+        let query = sqlx::query!(
+            "INSERT INTO transcription_job (interaction_id, created) VALUES ($1, $2) RETURNING id",
+            interaction_id,
+            created,
+        );
+
+        database::log_query(&query);
+        let id = query.fetch_one(&connection.pool).await?.id;
+
+        Ok(TranscriptionJob {
+            id,
+            interaction_id,
+            created,
+        })
+    }
+
+    /// Get all pending transcription jobs from the database, oldest first.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    pub async fn get_all(connection: &DatabaseConnection) -> Result<Vec<Self>, Error> {
+        let query = sqlx::query_as!(Self, "SELECT * FROM transcription_job ORDER BY created");
+
+        database::log_query(&query);
+        Ok(query.fetch_all(&connection.pool).await?)
+    }
+
+    /// Remove the transcription job of an interaction from the database, if there is one.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    /// * `interaction_id`: The id of the interaction whose response was transcribed.
+    pub async fn delete_by_interaction(
+        connection: &DatabaseConnection,
+        interaction_id: i32,
+    ) -> Result<(), Error> {
+        let query = sqlx::query!(
+            "DELETE FROM transcription_job WHERE interaction_id = $1",
+            interaction_id
+        );
+
+        database::log_query(&query);
+        query.execute(&connection.pool).await?;
+
+        Ok(())
+    }
+}
+
+impl Display for TranscriptionJob {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Transcription of interaction {} (queued {})",
+            self.interaction_id, self.created
+        )
+    }
+}
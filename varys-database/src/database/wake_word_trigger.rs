@@ -0,0 +1,153 @@
+use std::fmt::{Display, Formatter};
+
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+
+use crate::connection::DatabaseConnection;
+use crate::database;
+use crate::error::Error;
+
+/// The share of queries below which a voice triggers an assistant too rarely to be used for collection.
+pub const MIN_TRIGGER_RATE: f32 = 0.75;
+
+/// The representation of a wake word trigger in the database.
+///
+/// Every time a voice says the wake word of an assistant, it is recorded whether the assistant responded, either with
+/// sound or with traffic. Each trigger belongs to a [`Session`](crate::database::session::Session).
+#[derive(FromRow, Debug)]
+pub struct WakeWordTrigger {
+    pub id: i32,
+    /// The id of the session this trigger belongs to.
+    pub session_id: i32,
+    /// The id of the interaction in which the wake word was said.
+    ///
+    /// If this is `None`, the interaction failed before it was stored.
+    pub interaction_id: Option<i32>,
+    /// The name of the assistant.
+    pub assistant: String,
+    /// The voice that said the wake word.
+    pub voice: String,
+    /// Whether the assistant responded.
+    pub triggered: bool,
+    /// When this trigger was recorded.
+    pub recorded: DateTime<Utc>,
+}
+
+impl WakeWordTrigger {
+    /// Create a new wake word trigger in the database.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    /// * `session_id`: The id of the session the trigger belongs to.
+    /// * `interaction_id`: The id of the interaction in which the wake word was said, if it was stored.
+    /// * `assistant`: The name of the assistant.
+    /// * `voice`: The voice that said the wake word.
+    /// * `triggered`: Whether the assistant responded.
+    pub async fn create(
+        connection: &DatabaseConnection,
+        session_id: i32,
+        interaction_id: Option<i32>,
+        assistant: &str,
+        voice: &str,
+        triggered: bool,
+    ) -> Result<Self, Error> {
+        let recorded = Utc::now();
+        let query = sqlx::query!(
+            "INSERT INTO wake_word_trigger (session_id, interaction_id, assistant, voice, triggered, recorded) VALUES ($1, $2, $3, $4, $5, $6) RETURNING id",
+            session_id,
+            interaction_id,
+            assistant,
+            voice,
+            triggered,
+            recorded,
+        );
+
+        database::log_query(&query);
+        let id = query.fetch_one(&connection.pool).await?.id;
+
+        Ok(WakeWordTrigger {
+            id,
+            session_id,
+            interaction_id,
+            assistant: assistant.to_string(),
+            voice: voice.to_string(),
+            triggered,
+            recorded,
+        })
+    }
+
+    /// Get how often each voice triggered each assistant across all sessions, ordered by assistant and voice.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    pub async fn rates(connection: &DatabaseConnection) -> Result<Vec<TriggerRate>, Error> {
+        let query = sqlx::query_as!(
+            TriggerRate,
+            r#"SELECT assistant, voice, count(*) FILTER (WHERE triggered) AS "triggered!", count(*) AS "total!"
+            FROM wake_word_trigger GROUP BY assistant, voice ORDER BY assistant, voice"#
+        );
+
+        database::log_query(&query);
+        Ok(query.fetch_all(&connection.pool).await?)
+    }
+}
+
+/// How often a voice triggered an assistant.
+#[derive(FromRow, Debug, PartialEq)]
+pub struct TriggerRate {
+    /// The name of the assistant.
+    pub assistant: String,
+    /// The voice that said the wake word.
+    pub voice: String,
+    /// How often the assistant responded.
+    pub triggered: i64,
+    /// How often the wake word was said.
+    pub total: i64,
+}
+
+impl TriggerRate {
+    /// The share of wake words the assistant responded to.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys_database::database::wake_word_trigger::TriggerRate;
+    /// let rate = TriggerRate {
+    ///     assistant: "Siri".to_string(),
+    ///     voice: "Zoe".to_string(),
+    ///     triggered: 3,
+    ///     total: 4,
+    /// };
+    ///
+    /// assert_eq!(rate.rate(), 0.75);
+    /// assert!(rate.is_reliable());
+    /// ```
+    pub fn rate(&self) -> f32 {
+        if self.total == 0 {
+            return 0.0;
+        }
+
+        self.triggered as f32 / self.total as f32
+    }
+
+    /// Whether the voice triggers the assistant often enough to be used for collection.
+    pub fn is_reliable(&self) -> bool {
+        self.rate() >= MIN_TRIGGER_RATE
+    }
+}
+
+impl Display for TriggerRate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} with {}: {:.0}% ({} of {})",
+            self.voice,
+            self.assistant,
+            self.rate() * 100.0,
+            self.triggered,
+            self.total
+        )
+    }
+}
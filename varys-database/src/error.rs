@@ -8,4 +8,6 @@ pub enum Error {
     DatabaseMigration(#[from] sqlx::migrate::MigrateError),
     #[error("Environment variable DATABASE_URL is missing")]
     MissingDatabaseUrl,
+    #[error("The database url {0} is invalid")]
+    InvalidDatabaseUrl(String),
 }
@@ -7,6 +7,7 @@ use crate::database::interaction::Interaction;
 
 pub enum DataType {
     Capture,
+    RadioCapture,
     Audio(String),
 }
 
@@ -26,6 +27,58 @@ pub fn session_path<P: AsRef<Path>>(data_path: P, session_id: i32) -> PathBuf {
         .join(format!("sessions/session_{}", session_id))
 }
 
+/// Get the path of the capture that spans a whole session.
+///
+/// This is only used when capturing all interactions of a session at once. The capture is split into one capture per
+/// interaction at the end of the session.
+///
+/// # Arguments
+///
+/// * `data_path`: The path to the data directory.
+/// * `session_id`: The id of the session.
+pub fn session_capture_path<P: AsRef<Path>>(data_path: P, session_id: i32) -> PathBuf {
+    session_path(data_path, session_id).join(format!("s{session_id}-session-capture.pcap"))
+}
+
+/// Get the path of the event log of a session.
+///
+/// # Arguments
+///
+/// * `data_path`: The path to the data directory.
+/// * `session_id`: The id of the session.
+pub fn session_log_path<P: AsRef<Path>>(data_path: P, session_id: i32) -> PathBuf {
+    session_path(data_path, session_id).join(format!("s{session_id}-events.jsonl"))
+}
+
+/// Map a stored path from one directory to another.
+///
+/// This is used to update stored paths after a data directory was moved.
+///
+/// # Arguments
+///
+/// * `path`: The stored path.
+/// * `from`: The directory the data was moved from.
+/// * `to`: The directory the data was moved to.
+///
+/// Returns the mapped path or `None` if `path` is not inside `from`.
+///
+/// # Examples
+///
+/// ```
+/// # use varys_database::file::relocate_path;
+/// assert_eq!(
+///     relocate_path("/mnt/old/sessions/session_1", "/mnt/old", "/mnt/new"),
+///     Some("/mnt/new/sessions/session_1".to_string())
+/// );
+/// assert_eq!(relocate_path("s1i1-capture.pcap", "/mnt/old", "/mnt/new"), None);
+/// ```
+pub fn relocate_path<P: AsRef<Path>, Q: AsRef<Path>>(path: &str, from: P, to: Q) -> Option<String> {
+    Path::new(path)
+        .strip_prefix(from)
+        .ok()
+        .map(|relative| to.as_ref().join(relative).to_string_lossy().to_string())
+}
+
 pub fn artefact_path<P: AsRef<Path>>(
     data_path: P,
     data_type: DataType,
@@ -33,6 +86,7 @@ pub fn artefact_path<P: AsRef<Path>>(
 ) -> PathBuf {
     session_path(data_path, interaction.session_id).join(match data_type {
         DataType::Capture => data_file_name(interaction, "capture", "pcap"),
+        DataType::RadioCapture => data_file_name(interaction, "radio-capture", "frames"),
         DataType::Audio(prefix) => data_file_name(interaction, &format!("{prefix}-audio"), "opus"),
     })
 }
@@ -14,6 +14,10 @@ pub enum Error {
     CannotStop,
     #[error("Did not receive sniffer stats")]
     NoStatsReceived,
+    #[error("Invalid capture: {0}")]
+    InvalidCapture(String),
+    #[error("Invalid radio frame: {0:?}")]
+    InvalidFrame(String),
     #[error("Pcap error: {0}")]
     Pcap(String),
 }
@@ -0,0 +1,346 @@
+use std::fs;
+use std::fs::File;
+use std::io::{BufReader, ErrorKind, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::{DateTime, TimeZone, Utc};
+use log::{debug, info};
+
+use crate::error::Error;
+use crate::packet::Packet;
+
+/// The length of the global header of a pcap file in bytes.
+const PCAP_HEADER_LEN: u64 = 24;
+/// The length of the header of each packet record in a pcap file in bytes.
+const RECORD_HEADER_LEN: u64 = 16;
+
+/// An index of the packets in a pcap file, grouped into windows of time.
+///
+/// The index stores the byte offset of the first packet of each window, which allows reading only the packets of a
+/// certain time span without scanning the whole capture. It is stored next to the capture, see [`index_path`].
+#[derive(Debug, PartialEq)]
+pub struct CaptureIndex {
+    /// The duration of each window.
+    pub window: Duration,
+    /// One entry for each window that contains packets, in the order of the capture.
+    pub entries: Vec<IndexEntry>,
+}
+
+/// The packets of one window in a [`CaptureIndex`].
+#[derive(Debug, PartialEq)]
+pub struct IndexEntry {
+    /// The start of the window.
+    pub start: DateTime<Utc>,
+    /// The byte offset of the first packet of the window in the capture.
+    pub offset: u64,
+    /// The number of packets in the window.
+    pub packets: u32,
+}
+
+impl CaptureIndex {
+    /// The window duration used for indices created at the end of a capture.
+    pub const DEFAULT_WINDOW: Duration = Duration::from_secs(1);
+
+    /// Index a pcap file by scanning all of its packets.
+    ///
+    /// # Arguments
+    ///
+    /// * `capture_path`: The path to the pcap file.
+    /// * `window`: The duration of each window.
+    pub fn build<P: AsRef<Path>>(capture_path: P, window: Duration) -> Result<Self, Error> {
+        let window_micros = (window.as_micros() as i64).max(1);
+        let mut reader = PcapReader::open(&capture_path, PCAP_HEADER_LEN)?;
+        let mut entries: Vec<IndexEntry> = Vec::new();
+
+        while let Some((offset, packet)) = reader.next_packet()? {
+            let micros = packet.timestamp.timestamp_micros();
+            let start = Utc
+                .timestamp_micros(micros - micros.rem_euclid(window_micros))
+                .single()
+                .ok_or(Error::InvalidCapture(
+                    "Invalid packet timestamp".to_string(),
+                ))?;
+
+            match entries.last_mut() {
+                Some(entry) if entry.start == start => entry.packets += 1,
+                _ => entries.push(IndexEntry {
+                    start,
+                    offset,
+                    packets: 1,
+                }),
+            }
+        }
+
+        debug!(
+            "Indexed {} windows of {}",
+            entries.len(),
+            capture_path.as_ref().display()
+        );
+
+        Ok(CaptureIndex { window, entries })
+    }
+
+    /// Index a pcap file and store the index next to it.
+    ///
+    /// # Arguments
+    ///
+    /// * `capture_path`: The path to the pcap file.
+    pub fn create<P: AsRef<Path>>(capture_path: P) -> Result<Self, Error> {
+        let index = Self::build(&capture_path, Self::DEFAULT_WINDOW)?;
+        index.save(&capture_path)?;
+
+        info!("Stored index of {}", capture_path.as_ref().display());
+
+        Ok(index)
+    }
+
+    /// Load the stored index of a pcap file, creating it if it does not exist or is older than the capture.
+    ///
+    /// # Arguments
+    ///
+    /// * `capture_path`: The path to the pcap file.
+    pub fn load_or_create<P: AsRef<Path>>(capture_path: P) -> Result<Self, Error> {
+        let index_path = index_path(&capture_path);
+        let is_current = fs::metadata(&index_path)
+            .and_then(|index| index.modified())
+            .ok()
+            .zip(
+                fs::metadata(&capture_path)
+                    .and_then(|capture| capture.modified())
+                    .ok(),
+            )
+            .is_some_and(|(index, capture)| index >= capture);
+
+        if is_current {
+            Self::load(&capture_path)
+        } else {
+            Self::create(&capture_path)
+        }
+    }
+
+    /// Load the stored index of a pcap file.
+    ///
+    /// # Arguments
+    ///
+    /// * `capture_path`: The path to the pcap file, not the index.
+    pub fn load<P: AsRef<Path>>(capture_path: P) -> Result<Self, Error> {
+        let contents = fs::read_to_string(index_path(capture_path))?;
+        let invalid = || Error::InvalidCapture("Invalid capture index".to_string());
+        let mut lines = contents.lines().filter(|line| !line.starts_with('#'));
+        let window = lines
+            .next()
+            .and_then(|line| line.strip_prefix("window_ms "))
+            .and_then(|window| window.parse().ok())
+            .map(Duration::from_millis)
+            .ok_or_else(invalid)?;
+        let entries = lines
+            .map(|line| {
+                let mut values = line.split(' ').map(|value| value.parse::<i64>().ok());
+                let start = Utc.timestamp_micros(values.next()??).single()?;
+                let offset = values.next()?? as u64;
+                let packets = values.next()?? as u32;
+
+                Some(IndexEntry {
+                    start,
+                    offset,
+                    packets,
+                })
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(invalid)?;
+
+        Ok(CaptureIndex { window, entries })
+    }
+
+    /// Store this index next to a pcap file.
+    ///
+    /// # Arguments
+    ///
+    /// * `capture_path`: The path to the indexed pcap file.
+    pub fn save<P: AsRef<Path>>(&self, capture_path: P) -> Result<(), Error> {
+        let mut contents = format!(
+            "# varys capture index: window start (µs), byte offset, packets\nwindow_ms {}\n",
+            self.window.as_millis()
+        );
+        for entry in &self.entries {
+            contents.push_str(&format!(
+                "{} {} {}\n",
+                entry.start.timestamp_micros(),
+                entry.offset,
+                entry.packets
+            ));
+        }
+
+        Ok(fs::write(index_path(capture_path), contents)?)
+    }
+
+    /// Get the byte offset from which to read to get all packets captured at or after a point in time.
+    ///
+    /// # Arguments
+    ///
+    /// * `time`: The point in time.
+    pub fn offset(&self, time: DateTime<Utc>) -> u64 {
+        self.entries
+            .iter()
+            .take_while(|entry| entry.start <= time)
+            .last()
+            .or(self.entries.first())
+            .map(|entry| entry.offset)
+            .unwrap_or(PCAP_HEADER_LEN)
+    }
+}
+
+/// Get the path of the index of a pcap file, e.g. `capture.pcap.idx` for `capture.pcap`.
+///
+/// # Arguments
+///
+/// * `capture_path`: The path to the pcap file.
+///
+/// # Examples
+///
+/// ```
+/// # use std::path::Path;
+/// # use varys_network::index::index_path;
+/// assert_eq!(
+///     index_path("data/capture.pcap"),
+///     Path::new("data/capture.pcap.idx")
+/// );
+/// ```
+pub fn index_path<P: AsRef<Path>>(capture_path: P) -> PathBuf {
+    let mut path = capture_path.as_ref().as_os_str().to_owned();
+    path.push(".idx");
+
+    PathBuf::from(path)
+}
+
+/// Load all packets captured in a time span from a pcap file.
+///
+/// The index of the capture is used to seek to the first relevant packet. It is created if it does not exist yet.
+///
+/// # Arguments
+///
+/// * `capture_path`: The path to the pcap file.
+/// * `start`: The start of the time span.
+/// * `end`: The end of the time span.
+///
+/// # Examples
+///
+/// ```
+/// # use std::env;
+/// # use chrono::{Duration, TimeZone, Utc};
+/// # use varys_network::index::load_packets_between;
+/// # let capture_path = env::temp_dir().join("varys-index.pcap");
+/// # let mut bytes = vec![0xd4, 0xc3, 0xb2, 0xa1, 2, 0, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xff, 0xff, 0, 0, 1, 0, 0, 0];
+/// # for seconds in [10_u32, 11, 12, 13] {
+/// #     bytes.extend(seconds.to_le_bytes());
+/// #     bytes.extend([0, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 0]);
+/// # }
+/// # std::fs::write(&capture_path, bytes).unwrap();
+/// // a capture with one packet at 10, 11, 12 and 13 seconds after the epoch
+/// let start = Utc.timestamp_opt(11, 0).unwrap();
+/// let end = Utc.timestamp_opt(12, 0).unwrap();
+///
+/// let packets = load_packets_between(&capture_path, start, end).unwrap();
+///
+/// assert_eq!(packets.len(), 2);
+/// assert_eq!(packets[0].timestamp, start);
+/// ```
+pub fn load_packets_between<P: AsRef<Path>>(
+    capture_path: P,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Vec<Packet>, Error> {
+    let offset = CaptureIndex::load_or_create(&capture_path)?.offset(start);
+    let mut reader = PcapReader::open(&capture_path, offset)?;
+    let mut packets = Vec::new();
+
+    while let Some((_, packet)) = reader.next_packet()? {
+        if packet.timestamp > end {
+            break;
+        }
+        if packet.timestamp >= start {
+            packets.push(packet);
+        }
+    }
+
+    Ok(packets)
+}
+
+/// A minimal reader for pcap files that keeps track of the byte offset of each packet.
+struct PcapReader {
+    reader: BufReader<File>,
+    offset: u64,
+    big_endian: bool,
+    nanoseconds: bool,
+}
+
+impl PcapReader {
+    /// Open a pcap file and start reading packets at a byte offset.
+    fn open<P: AsRef<Path>>(capture_path: P, offset: u64) -> Result<Self, Error> {
+        let mut reader = BufReader::new(File::open(capture_path)?);
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+
+        let (big_endian, nanoseconds) = match u32::from_le_bytes(magic) {
+            0xa1b2c3d4 => (false, false),
+            0xa1b23c4d => (false, true),
+            0xd4c3b2a1 => (true, false),
+            0x4d3cb2a1 => (true, true),
+            _ => return Err(Error::InvalidCapture("Unknown pcap format".to_string())),
+        };
+        let offset = offset.max(PCAP_HEADER_LEN);
+        reader.seek(SeekFrom::Start(offset))?;
+
+        Ok(PcapReader {
+            reader,
+            offset,
+            big_endian,
+            nanoseconds,
+        })
+    }
+
+    /// Read the next packet and its byte offset, or `None` at the end of the file.
+    fn next_packet(&mut self) -> Result<Option<(u64, Packet)>, Error> {
+        let mut header = [0; RECORD_HEADER_LEN as usize];
+        match self.reader.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(error) if error.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(error) => return Err(error.into()),
+        }
+
+        let field = |index: usize| {
+            let bytes = header[index * 4..index * 4 + 4].try_into().unwrap();
+            if self.big_endian {
+                u32::from_be_bytes(bytes)
+            } else {
+                u32::from_le_bytes(bytes)
+            }
+        };
+        let nanoseconds = if self.nanoseconds {
+            field(1)
+        } else {
+            field(1) * 1000
+        };
+        let captured_len = field(2);
+        let mut data = vec![0; captured_len as usize];
+        self.reader.read_exact(&mut data)?;
+
+        let offset = self.offset;
+        self.offset += RECORD_HEADER_LEN + captured_len as u64;
+
+        Ok(Some((
+            offset,
+            Packet {
+                timestamp: Utc
+                    .timestamp_opt(field(0) as i64, nanoseconds)
+                    .single()
+                    .ok_or(Error::InvalidCapture(
+                        "Invalid packet timestamp".to_string(),
+                    ))?,
+                len: field(3) as usize,
+                data,
+            },
+        )))
+    }
+}
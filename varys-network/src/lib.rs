@@ -1,4 +1,5 @@
 pub mod address;
 pub mod error;
+pub mod index;
 pub mod packet;
 pub mod sniff;
@@ -1,4 +1,5 @@
 use std::fmt::{Display, Formatter};
+use std::fs;
 use std::path::Path;
 use std::time;
 use std::time::Duration;
@@ -11,6 +12,11 @@ use pnet::packet::ethernet::EthernetPacket;
 use crate::address::MacAddress;
 use crate::error::Error;
 
+/// The length in bytes from which an incoming packet is considered to carry response data.
+///
+/// Smaller packets are mostly acknowledgements and keep-alives.
+pub const LARGE_PACKET_LEN: usize = 1000;
+
 #[derive(Copy, Clone, Debug)]
 pub enum PacketDirection {
     In,
@@ -119,3 +125,194 @@ pub fn load_packets<P: AsRef<Path>>(path: P) -> Result<Vec<Packet>, Error> {
 
     Ok(packets)
 }
+
+/// Save packets to a pcap file with ethernet link type.
+///
+/// # Arguments
+///
+/// * `path`: The path to the pcap file.
+/// * `packets`: The packets to save.
+///
+/// # Examples
+///
+/// ```
+/// # use std::env;
+/// # use chrono::{DateTime, Utc};
+/// # use varys_network::packet::{load_packets, save_packets, Packet};
+/// let path = env::temp_dir().join("varys-save-packets.pcap");
+/// let timestamp = DateTime::from_timestamp(10, 500_000_000).unwrap();
+/// let packet = Packet {
+///     timestamp,
+///     len: 3,
+///     data: vec![1, 2, 3],
+/// };
+///
+/// save_packets(&path, &[packet]).unwrap();
+///
+/// let packets = load_packets(&path).unwrap();
+/// assert_eq!(packets[0].timestamp, timestamp);
+/// assert_eq!(packets[0].data, vec![1, 2, 3]);
+/// ```
+pub fn save_packets<P: AsRef<Path>>(path: P, packets: &[Packet]) -> Result<(), Error> {
+    let mut bytes = Vec::new();
+    // global header: magic number, version 2.4, timezone, accuracy, snapshot length, link type ethernet
+    for field in [0xa1b2c3d4_u32, 0x00040002, 0, 0, 0xffff, 1] {
+        bytes.extend(field.to_le_bytes());
+    }
+
+    for packet in packets {
+        for field in [
+            packet.timestamp.timestamp() as u32,
+            packet.timestamp.timestamp_subsec_micros(),
+            packet.captured_len() as u32,
+            packet.len as u32,
+        ] {
+            bytes.extend(field.to_le_bytes());
+        }
+        bytes.extend(&packet.data);
+    }
+
+    Ok(fs::write(path, bytes)?)
+}
+
+/// Find the first large packet sent to a device after a point in time.
+///
+/// Packets of at least [`LARGE_PACKET_LEN`] bytes are considered large.
+///
+/// # Arguments
+///
+/// * `packets`: The packets to search, in the order they were captured.
+/// * `relative_to`: The MAC address of the device receiving the packet.
+/// * `after`: The time from which to search.
+///
+/// # Examples
+///
+/// ```
+/// # use chrono::{Duration, Utc};
+/// # use varys_network::address::MacAddress;
+/// # use varys_network::packet::{first_large_incoming, Packet};
+/// let device = MacAddress(1, 2, 3, 4, 5, 6);
+/// let now = Utc::now();
+/// let mut data = vec![1, 2, 3, 4, 5, 6, 0, 0, 0, 0, 0, 0, 8, 0];
+/// data.resize(1500, 0);
+/// let packets = vec![
+///     Packet {
+///         timestamp: now,
+///         len: 1500,
+///         data: data.clone(),
+///     },
+///     Packet {
+///         timestamp: now + Duration::milliseconds(200),
+///         len: 1500,
+///         data,
+///     },
+/// ];
+///
+/// let packet = first_large_incoming(&packets, &device, now + Duration::milliseconds(100));
+/// assert_eq!(packet.unwrap().timestamp, now + Duration::milliseconds(200));
+/// ```
+pub fn first_large_incoming<'a>(
+    packets: &'a [Packet],
+    relative_to: &MacAddress,
+    after: DateTime<Utc>,
+) -> Option<&'a Packet> {
+    packets.iter().find(|packet| {
+        packet.timestamp >= after
+            && packet.len >= LARGE_PACKET_LEN
+            && matches!(packet.direction(relative_to), Some(PacketDirection::In))
+    })
+}
+
+/// Get when the burst of large packets sent to a device after a point in time ended.
+///
+/// The burst starts with the [`first_large_incoming`] packet and lasts as long as the following large packets sent to
+/// the device are at most `max_gap` apart.
+///
+/// Returns `None` if no large packet was sent to the device after `after`.
+///
+/// # Arguments
+///
+/// * `packets`: The packets to search, in the order they were captured.
+/// * `relative_to`: The MAC address of the device receiving the packets.
+/// * `after`: The time from which to search.
+/// * `max_gap`: The longest time between two packets of the burst.
+///
+/// # Examples
+///
+/// ```
+/// # use chrono::{Duration, Utc};
+/// # use varys_network::address::MacAddress;
+/// # use varys_network::packet::{burst_end, Packet};
+/// let device = MacAddress(1, 2, 3, 4, 5, 6);
+/// let now = Utc::now();
+/// let mut data = vec![1, 2, 3, 4, 5, 6, 0, 0, 0, 0, 0, 0, 8, 0];
+/// data.resize(1500, 0);
+/// let packets: Vec<Packet> = [0, 100, 200, 2000]
+///     .into_iter()
+///     .map(|offset| Packet {
+///         timestamp: now + Duration::milliseconds(offset),
+///         len: 1500,
+///         data: data.clone(),
+///     })
+///     .collect();
+///
+/// let end = burst_end(&packets, &device, now, Duration::milliseconds(500));
+/// assert_eq!(end, Some(now + Duration::milliseconds(200)));
+/// ```
+pub fn burst_end(
+    packets: &[Packet],
+    relative_to: &MacAddress,
+    after: DateTime<Utc>,
+    max_gap: chrono::Duration,
+) -> Option<DateTime<Utc>> {
+    let first = first_large_incoming(packets, relative_to, after)?;
+    let mut end = first.timestamp;
+
+    for packet in packets.iter().filter(|packet| {
+        packet.timestamp > first.timestamp
+            && packet.len >= LARGE_PACKET_LEN
+            && matches!(packet.direction(relative_to), Some(PacketDirection::In))
+    }) {
+        if packet.timestamp - end > max_gap {
+            break;
+        }
+        end = packet.timestamp;
+    }
+
+    Some(end)
+}
+
+/// Get the total number of bytes of all packets sent to a device.
+///
+/// # Arguments
+///
+/// * `packets`: The packets to count.
+/// * `relative_to`: The MAC address of the device.
+///
+/// # Examples
+///
+/// ```
+/// # use chrono::Utc;
+/// # use varys_network::address::MacAddress;
+/// # use varys_network::packet::{incoming_bytes, Packet};
+/// let device = MacAddress(1, 2, 3, 4, 5, 6);
+/// let incoming = Packet {
+///     timestamp: Utc::now(),
+///     len: 1500,
+///     data: vec![1, 2, 3, 4, 5, 6, 0, 0, 0, 0, 0, 0, 8, 0],
+/// };
+/// let outgoing = Packet {
+///     timestamp: Utc::now(),
+///     len: 100,
+///     data: vec![0, 0, 0, 0, 0, 0, 1, 2, 3, 4, 5, 6, 8, 0],
+/// };
+///
+/// assert_eq!(incoming_bytes(&[incoming, outgoing], &device), 1500);
+/// ```
+pub fn incoming_bytes(packets: &[Packet], relative_to: &MacAddress) -> usize {
+    packets
+        .iter()
+        .filter(|packet| matches!(packet.direction(relative_to), Some(PacketDirection::In)))
+        .map(|packet| packet.len)
+        .sum()
+}
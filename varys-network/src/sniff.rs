@@ -1,5 +1,5 @@
 use std::fmt::{Display, Formatter};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::mpsc::{channel, Sender, TryRecvError};
 use std::time::Duration;
 use std::{thread, thread::JoinHandle};
@@ -9,8 +9,29 @@ pub use pcap::ConnectionStatus;
 use pcap::{Capture, Device, Stat};
 
 use crate::error::Error;
+use crate::index::CaptureIndex;
 use crate::packet::Packet;
 
+pub mod fake;
+pub mod radio;
+pub mod replay;
+
+/// Anything that can capture network traffic like a [`Sniffer`].
+///
+/// This allows replacing packet capture with [`fake::FakeSniffer`] where no network privileges are available.
+pub trait Sniff {
+    /// Start capturing traffic, writing it to the given file.
+    ///
+    /// See [`Sniffer::start`].
+    fn start(&self, file_path: &Path) -> Result<Box<dyn SniffInstance>, Error>;
+}
+
+/// A running capture started with [`Sniff::start`].
+pub trait SniffInstance {
+    /// Stop capturing and get the statistics from the run.
+    fn stop(self: Box<Self>) -> Result<SnifferStats, Error>;
+}
+
 /// A sniffer is used to capture network packets on a specific network device.
 pub struct Sniffer {
     device: Device,
@@ -55,7 +76,7 @@ impl Sniffer {
             .buffer_size(100_000_000)
             .open()?
             .setnonblock()?;
-        let mut file = capture.savefile(file_path)?;
+        let mut file = capture.savefile(&file_path)?;
         let (shutdown_channel, receiver) = channel();
 
         let join_handle = thread::spawn(move || {
@@ -73,6 +94,7 @@ impl Sniffer {
         });
 
         Ok(SnifferInstance {
+            file_path,
             shutdown_channel,
             join_handle,
         })
@@ -115,6 +137,12 @@ impl Sniffer {
     }
 }
 
+impl Sniff for Sniffer {
+    fn start(&self, file_path: &Path) -> Result<Box<dyn SniffInstance>, Error> {
+        Ok(Box::new(Sniffer::start(self, file_path)?))
+    }
+}
+
 impl From<Device> for Sniffer {
     fn from(device: Device) -> Self {
         Sniffer { device }
@@ -133,6 +161,7 @@ impl Display for Sniffer {
 
 /// A handle to a running sniffer instance. It can be stopped with [`SnifferInstance::stop`].
 pub struct SnifferInstance {
+    file_path: PathBuf,
     shutdown_channel: Sender<()>,
     join_handle: JoinHandle<Result<Stat, Error>>,
 }
@@ -140,6 +169,8 @@ pub struct SnifferInstance {
 impl SnifferInstance {
     /// Stop the running sniffer consuming the instance and get the statistics from the run.
     ///
+    /// This also stores a [`CaptureIndex`] next to the capture.
+    ///
     /// Returns [`SnifferStats`] with statistics about the capture.
     ///
     /// # Examples
@@ -159,10 +190,21 @@ impl SnifferInstance {
         self.shutdown_channel
             .send(())
             .map_err(|_| Error::CannotStop)?;
-        self.join_handle
+        let stats = self
+            .join_handle
             .join()
             .map_err(|_| Error::NoStatsReceived)?
-            .map(SnifferStats::from)
+            .map(SnifferStats::from)?;
+
+        CaptureIndex::create(&self.file_path)?;
+
+        Ok(stats)
+    }
+}
+
+impl SniffInstance for SnifferInstance {
+    fn stop(self: Box<Self>) -> Result<SnifferStats, Error> {
+        SnifferInstance::stop(*self)
     }
 }
 
@@ -0,0 +1,98 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use log::info;
+
+use crate::error::Error;
+use crate::packet;
+use crate::sniff::{Sniff, SniffInstance, SnifferStats};
+
+/// The global header of an empty pcap file with ethernet link type.
+const EMPTY_PCAP: [u8; 24] = [
+    0xd4, 0xc3, 0xb2, 0xa1, // magic number
+    0x02, 0x00, 0x04, 0x00, // version 2.4
+    0x00, 0x00, 0x00, 0x00, // timezone offset
+    0x00, 0x00, 0x00, 0x00, // timestamp accuracy
+    0xff, 0xff, 0x00, 0x00, // snapshot length
+    0x01, 0x00, 0x00, 0x00, // link type ethernet
+];
+
+/// A [`Sniff`] implementation that does not need network privileges.
+///
+/// Instead of capturing traffic, every capture writes a canned pcap file.
+#[derive(Default)]
+pub struct FakeSniffer {
+    capture: Option<PathBuf>,
+}
+
+impl FakeSniffer {
+    /// Create a fake sniffer that writes an empty pcap file for every capture.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::env;
+    /// # use varys_network::sniff::Sniff;
+    /// # use varys_network::sniff::fake::FakeSniffer;
+    /// let capture_path = env::temp_dir().join("varys-fake-sniffer.pcap");
+    /// let sniffer = FakeSniffer::new();
+    ///
+    /// let stats = sniffer.start(&capture_path).unwrap().stop().unwrap();
+    ///
+    /// assert_eq!(stats.received, 0);
+    /// assert!(capture_path.exists());
+    /// ```
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a fake sniffer that copies an existing pcap file for every capture.
+    ///
+    /// # Arguments
+    ///
+    /// * `capture`: The path to the pcap file to copy.
+    pub fn with_capture<P: AsRef<Path>>(capture: P) -> Self {
+        FakeSniffer {
+            capture: Some(capture.as_ref().to_path_buf()),
+        }
+    }
+}
+
+impl Sniff for FakeSniffer {
+    fn start(&self, file_path: &Path) -> Result<Box<dyn SniffInstance>, Error> {
+        let mut file_path = file_path.to_owned();
+        file_path.set_extension("pcap");
+
+        info!("Fake sniffer starting (writing to {:?})...", file_path);
+
+        let received = match &self.capture {
+            Some(capture) => {
+                fs::copy(capture, &file_path)?;
+                packet::load_packets(capture)?.len() as u32
+            }
+            None => {
+                fs::write(&file_path, EMPTY_PCAP)?;
+                0
+            }
+        };
+
+        Ok(Box::new(FakeSnifferInstance { received }))
+    }
+}
+
+/// A handle to a running [`FakeSniffer`] capture.
+pub struct FakeSnifferInstance {
+    received: u32,
+}
+
+impl SniffInstance for FakeSnifferInstance {
+    fn stop(self: Box<Self>) -> Result<SnifferStats, Error> {
+        info!("Fake sniffer stopping");
+
+        Ok(SnifferStats {
+            received: self.received,
+            buffer_dropped: 0,
+            interface_dropped: 0,
+        })
+    }
+}
@@ -0,0 +1,322 @@
+use std::fmt::{Display, Formatter};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::process::{Child, ChildStdout, Command, Stdio};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use log::{info, trace, warn};
+
+use crate::error::Error;
+use crate::sniff::{Sniff, SniffInstance, SnifferStats};
+
+/// The extension of files with radio frames.
+pub const FRAMES_EXTENSION: &str = "frames";
+/// How long to wait for the output of a stopped radio sniffer to be closed.
+const STOP_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The metadata of a frame observed over the air, e.g. a Zigbee or Thread frame received by a software defined radio.
+///
+/// Frames are stored one per line with tab separated fields in the order of this struct, e.g.
+/// `2024-03-01T12:00:00.250Z\tzigbee\t15\t42\t-61\t0x1a2b\t0xffff`. The timestamp is written in RFC 3339 format and
+/// fields that are unknown are left empty.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RadioFrame {
+    /// When the frame was received.
+    pub timestamp: DateTime<Utc>,
+    /// The protocol of the frame, e.g. `zigbee` or `thread`.
+    pub protocol: String,
+    /// The radio channel the frame was received on.
+    pub channel: u16,
+    /// The length of the frame in bytes.
+    pub length: u32,
+    /// The received signal strength in dBm.
+    pub rssi: Option<i16>,
+    /// The address of the sender, as written by the radio.
+    pub source: Option<String>,
+    /// The address of the receiver, as written by the radio.
+    pub destination: Option<String>,
+}
+
+impl FromStr for RadioFrame {
+    type Err = Error;
+
+    /// Parse a frame from a line of tab separated fields.
+    ///
+    /// # Arguments
+    ///
+    /// * `line`: The line to parse.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys_network::sniff::radio::RadioFrame;
+    /// let frame: RadioFrame = "2024-03-01T12:00:00.250Z\tzigbee\t15\t42\t\t0x1a2b\t0xffff"
+    ///     .parse()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(frame.protocol, "zigbee");
+    /// assert_eq!(frame.channel, 15);
+    /// assert_eq!(frame.rssi, None);
+    /// assert_eq!(frame.source.as_deref(), Some("0x1a2b"));
+    /// assert!("2024-03-01T12:00:00.250Z\tzigbee".parse::<RadioFrame>().is_err());
+    /// ```
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::InvalidFrame(line.to_string());
+        let fields: Vec<&str> = line.trim_end_matches(['\r', '\n']).split('\t').collect();
+        let [timestamp, protocol, channel, length, rssi, source, destination] = fields[..] else {
+            return Err(invalid());
+        };
+        let optional = |field: &str| (!field.is_empty()).then(|| field.to_string());
+
+        Ok(RadioFrame {
+            timestamp: DateTime::parse_from_rfc3339(timestamp)
+                .map_err(|_| invalid())?
+                .with_timezone(&Utc),
+            protocol: protocol.to_string(),
+            channel: channel.parse().map_err(|_| invalid())?,
+            length: length.parse().map_err(|_| invalid())?,
+            rssi: optional(rssi)
+                .map(|rssi| rssi.parse())
+                .transpose()
+                .map_err(|_| invalid())?,
+            source: optional(source),
+            destination: optional(destination),
+        })
+    }
+}
+
+impl Display for RadioFrame {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            self.timestamp.to_rfc3339(),
+            self.protocol,
+            self.channel,
+            self.length,
+            self.rssi.map(|rssi| rssi.to_string()).unwrap_or_default(),
+            self.source.as_deref().unwrap_or_default(),
+            self.destination.as_deref().unwrap_or_default(),
+        )
+    }
+}
+
+/// Load the frames of a radio capture, e.g. one written by [`RadioSniffer`].
+///
+/// # Arguments
+///
+/// * `path`: The path to the file with one frame per line.
+///
+/// # Examples
+///
+/// ```
+/// # use std::{env, fs};
+/// # use varys_network::sniff::radio;
+/// let path = env::temp_dir().join("varys-radio-capture.frames");
+/// fs::write(&path, "2024-03-01T12:00:00Z\tthread\t25\t80\t-70\t\t\n").unwrap();
+///
+/// let frames = radio::load_frames(&path).unwrap();
+///
+/// assert_eq!(frames.len(), 1);
+/// assert_eq!(frames[0].protocol, "thread");
+/// ```
+pub fn load_frames<P: AsRef<Path>>(path: P) -> Result<Vec<RadioFrame>, Error> {
+    fs::read_to_string(path)?
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(RadioFrame::from_str)
+        .collect()
+}
+
+/// A [`Sniff`] implementation for captures that do not come from pcap, e.g. an external process that controls a
+/// software defined radio and observes Zigbee or Thread frames over the air.
+///
+/// The process is started for every capture and writes one [`RadioFrame`] per line to its standard output. Valid
+/// frames are written to the capture file, so they can be linked to the interaction they were observed during. The
+/// process is killed when the capture is stopped, and frames it still writes afterwards, e.g. from a process it
+/// started itself, are ignored.
+pub struct RadioSniffer {
+    program: String,
+    arguments: Vec<String>,
+}
+
+impl RadioSniffer {
+    /// Create a sniffer that runs an external program for every capture.
+    ///
+    /// # Arguments
+    ///
+    /// * `program`: The program that writes frames to its standard output.
+    /// * `arguments`: The arguments to pass to the program.
+    pub fn new<S: Into<String>>(program: S, arguments: Vec<String>) -> Self {
+        RadioSniffer {
+            program: program.into(),
+            arguments,
+        }
+    }
+
+    /// Start the external program, writing the frames it observes to the given file.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_path`: The path to which the frames are written. The extension `.frames` will be added if it isn't
+    ///   already in the path.
+    ///
+    /// Returns a [`RadioSnifferInstance`], on which [`RadioSnifferInstance::stop`] can be called to stop the
+    /// program.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use std::path::Path;
+    /// # use varys_network::sniff::radio::RadioSniffer;
+    /// let sniffer = RadioSniffer::new("zigbee-sniffer", vec![String::from("--channel=15")]);
+    ///
+    /// let instance = sniffer.start(Path::new("capture.frames")).unwrap();
+    /// # instance.stop().unwrap();
+    /// ```
+    pub fn start(&self, file_path: &Path) -> Result<RadioSnifferInstance, Error> {
+        let mut file_path = file_path.to_owned();
+        file_path.set_extension(FRAMES_EXTENSION);
+
+        info!(
+            "Radio sniffer {} starting (writing to {:?})...",
+            self.program, file_path
+        );
+
+        let file = BufWriter::new(File::create(&file_path)?);
+        let mut child = Command::new(&self.program)
+            .args(&self.arguments)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| io::Error::other("The output of the radio sniffer is not piped"))?;
+
+        let stopped = Arc::new(AtomicBool::new(false));
+        let received = Arc::new(AtomicU32::new(0));
+        let (finished_channel, finished) = channel();
+
+        let writer = FrameWriter {
+            stopped: stopped.clone(),
+            received: received.clone(),
+        };
+        thread::spawn(move || writer.write(stdout, file, finished_channel));
+
+        Ok(RadioSnifferInstance {
+            child,
+            stopped,
+            received,
+            finished,
+        })
+    }
+}
+
+impl Sniff for RadioSniffer {
+    fn start(&self, file_path: &Path) -> Result<Box<dyn SniffInstance>, Error> {
+        Ok(Box::new(RadioSniffer::start(self, file_path)?))
+    }
+}
+
+impl Display for RadioSniffer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Radio sniffer {}", self.program)
+    }
+}
+
+/// A handle to a running [`RadioSniffer`] capture.
+pub struct RadioSnifferInstance {
+    child: Child,
+    stopped: Arc<AtomicBool>,
+    received: Arc<AtomicU32>,
+    finished: Receiver<Result<(), Error>>,
+}
+
+impl RadioSnifferInstance {
+    /// Stop the external program and get the statistics from the capture.
+    ///
+    /// Returns [`SnifferStats`], where `received` is the number of valid frames that were written.
+    pub fn stop(mut self) -> Result<SnifferStats, Error> {
+        info!("Radio sniffer stopping");
+
+        // the program might already have exited on its own
+        let _ = self.child.kill();
+        self.child.wait()?;
+
+        match self.finished.recv_timeout(STOP_TIMEOUT) {
+            Ok(result) => result?,
+            Err(_) => warn!(
+                "The output of the radio sniffer is still open, ignoring what is written to it"
+            ),
+        }
+        self.stopped.store(true, Ordering::SeqCst);
+
+        Ok(SnifferStats {
+            received: self.received.load(Ordering::SeqCst),
+            buffer_dropped: 0,
+            interface_dropped: 0,
+        })
+    }
+}
+
+impl SniffInstance for RadioSnifferInstance {
+    fn stop(self: Box<Self>) -> Result<SnifferStats, Error> {
+        RadioSnifferInstance::stop(*self)
+    }
+}
+
+/// Writes the frames an external program writes to its standard output to a file.
+struct FrameWriter {
+    stopped: Arc<AtomicBool>,
+    received: Arc<AtomicU32>,
+}
+
+impl FrameWriter {
+    /// Write frames until the output of the program is closed or the capture is stopped, then report the result.
+    ///
+    /// Lines that are not valid frames are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `stdout`: The standard output of the program.
+    /// * `file`: The file to write the frames to.
+    /// * `finished_channel`: The channel on which the result is reported.
+    fn write(
+        self,
+        stdout: ChildStdout,
+        file: BufWriter<File>,
+        finished_channel: Sender<Result<(), Error>>,
+    ) {
+        // the capture might already have stopped waiting for the result
+        let _ = finished_channel.send(self.write_frames(stdout, file));
+    }
+
+    fn write_frames(&self, stdout: ChildStdout, mut file: BufWriter<File>) -> Result<(), Error> {
+        for line in BufReader::new(stdout).lines() {
+            let line = line?;
+            if self.stopped.load(Ordering::SeqCst) {
+                break;
+            }
+
+            match line.parse::<RadioFrame>() {
+                Ok(frame) => {
+                    writeln!(file, "{frame}")?;
+                    file.flush()?;
+                    trace!("{frame}");
+                    self.received.fetch_add(1, Ordering::SeqCst);
+                }
+                Err(error) => warn!("Skipping output of the radio sniffer: {error}"),
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,163 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::thread;
+use std::thread::JoinHandle;
+
+use chrono::{DateTime, Utc};
+use log::{info, trace};
+use pcap::{Capture, PacketHeader};
+
+use crate::error::Error;
+use crate::packet::Packet;
+use crate::sniff::{Sniff, SniffInstance, SnifferStats};
+
+/// A [`Sniff`] implementation that replays a stored capture instead of capturing live traffic.
+///
+/// Packets are written at the pacing they were originally captured with, so a replay takes as long as the original
+/// capture. This allows running stored captures through the same path as live traffic.
+pub struct ReplaySniffer {
+    capture: PathBuf,
+}
+
+impl ReplaySniffer {
+    /// Create a sniffer that replays a pcap file.
+    ///
+    /// # Arguments
+    ///
+    /// * `capture`: The path to the pcap file to replay.
+    pub fn new<P: AsRef<Path>>(capture: P) -> Self {
+        ReplaySniffer {
+            capture: capture.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Start replaying the capture, writing it to the given file.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_path`: The path to which the replayed traffic is written. The extension `.pcap` will be added if it
+    ///   isn't already in the path.
+    ///
+    /// Returns a [`ReplaySnifferInstance`], on which [`ReplaySnifferInstance::wait`] can be called to wait until the
+    /// whole capture was replayed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::env;
+    /// # use varys_network::sniff::Sniff;
+    /// # use varys_network::sniff::fake::FakeSniffer;
+    /// # use varys_network::sniff::replay::ReplaySniffer;
+    /// let capture_path = env::temp_dir().join("varys-replay-original.pcap");
+    /// let replay_path = env::temp_dir().join("varys-replay.pcap");
+    /// FakeSniffer::new().start(&capture_path).unwrap().stop().unwrap();
+    ///
+    /// let sniffer = ReplaySniffer::new(&capture_path);
+    /// let stats = sniffer.start(&replay_path).unwrap().wait().unwrap();
+    ///
+    /// assert_eq!(stats.received, 0);
+    /// assert!(replay_path.exists());
+    /// ```
+    pub fn start(&self, file_path: &Path) -> Result<ReplaySnifferInstance, Error> {
+        let mut file_path = file_path.to_owned();
+        file_path.set_extension("pcap");
+
+        info!(
+            "Replaying {:?} (writing to {:?})...",
+            self.capture, file_path
+        );
+
+        let mut capture = Capture::from_file(&self.capture)?;
+        let mut file = capture.savefile(file_path)?;
+        let mut packets: Vec<(PacketHeader, Vec<u8>)> = Vec::new();
+
+        loop {
+            match capture.next_packet() {
+                Ok(packet) => packets.push((*packet.header, packet.data.to_vec())),
+                Err(pcap::Error::NoMorePackets) => break,
+                Err(error) => return Err(Error::from(error)),
+            }
+        }
+
+        let (shutdown_channel, receiver) = channel();
+
+        let join_handle = thread::spawn(move || {
+            let mut previous: Option<DateTime<Utc>> = None;
+            let mut received = 0;
+
+            for (header, data) in &packets {
+                let packet = pcap::Packet::new(header, data);
+                let timestamp = Packet::from(packet).timestamp;
+                let gap = previous
+                    .and_then(|previous| (timestamp - previous).to_std().ok())
+                    .unwrap_or_default();
+
+                // wait for the original gap between packets unless the replay is stopped
+                if receiver.recv_timeout(gap) != Err(RecvTimeoutError::Timeout) {
+                    break;
+                }
+
+                let packet = pcap::Packet::new(header, data);
+                file.write(&packet);
+                trace!("{}", Packet::from(packet));
+                received += 1;
+                previous = Some(timestamp);
+            }
+
+            file.flush()?;
+
+            Ok(SnifferStats {
+                received,
+                buffer_dropped: 0,
+                interface_dropped: 0,
+            })
+        });
+
+        Ok(ReplaySnifferInstance {
+            shutdown_channel,
+            join_handle,
+        })
+    }
+}
+
+impl Sniff for ReplaySniffer {
+    fn start(&self, file_path: &Path) -> Result<Box<dyn SniffInstance>, Error> {
+        Ok(Box::new(ReplaySniffer::start(self, file_path)?))
+    }
+}
+
+/// A handle to a running [`ReplaySniffer`] replay.
+pub struct ReplaySnifferInstance {
+    shutdown_channel: Sender<()>,
+    join_handle: JoinHandle<Result<SnifferStats, Error>>,
+}
+
+impl ReplaySnifferInstance {
+    /// Wait until the whole capture was replayed and get the statistics from the replay.
+    pub fn wait(self) -> Result<SnifferStats, Error> {
+        let stats = self
+            .join_handle
+            .join()
+            .map_err(|_| Error::NoStatsReceived)?;
+
+        info!("Replay finished");
+
+        stats
+    }
+
+    /// Stop the replay, even if not all packets were replayed yet, and get the statistics from the replay.
+    pub fn stop(self) -> Result<SnifferStats, Error> {
+        info!("Replay stopping");
+
+        // the replay might already be finished, in which case nobody is listening anymore
+        let _ = self.shutdown_channel.send(());
+
+        self.wait()
+    }
+}
+
+impl SniffInstance for ReplaySnifferInstance {
+    fn stop(self: Box<Self>) -> Result<SnifferStats, Error> {
+        ReplaySnifferInstance::stop(*self)
+    }
+}
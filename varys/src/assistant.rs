@@ -1,19 +1,50 @@
+use std::sync::{Arc, OnceLock, PoisonError, RwLock};
 use std::time::Duration;
 
-use log::warn;
+use log::{info, warn};
 
 use crate::assistant::alexa::Alexa;
+use crate::assistant::google::GoogleAssistant;
+#[cfg(feature = "collection")]
 use crate::assistant::interactor::Interactor;
 use crate::assistant::siri::Siri;
+#[cfg(feature = "collection")]
 use crate::error::Error;
-use crate::query::Query;
 
 pub mod alexa;
+pub mod conditions;
+#[cfg(feature = "collection")]
+pub mod coordinator;
+#[cfg(feature = "collection")]
+pub mod event_log;
+#[cfg(feature = "collection")]
+pub mod fleet;
+pub mod google;
+pub mod idle_gap;
+#[cfg(feature = "collection")]
 pub mod interactor;
+#[cfg(feature = "collection")]
+pub mod mute;
+#[cfg(feature = "collection")]
+pub mod power;
+pub mod quiescence;
+pub mod quiet_hours;
+pub mod rate_sweep;
+pub mod recalibration;
+pub mod replay;
+pub mod retry;
+#[cfg(feature = "collection")]
+pub mod shutdown;
+#[cfg(feature = "collection")]
+pub mod simulation;
 pub mod siri;
+#[cfg(feature = "collection")]
+pub mod voice_matrix;
 
 /// This trait is implemented by all voice assistants supported by varys.
-pub trait VoiceAssistant {
+///
+/// Assistants are shared through the [`AssistantRegistry`], so they must be thread-safe.
+pub trait VoiceAssistant: Send + Sync {
     /// The name of the voice assistant.
     ///
     /// # Examples
@@ -45,15 +76,34 @@ pub trait VoiceAssistant {
     /// # let assistant = from("Siri");
     /// assistant.setup().unwrap();
     /// ```
+    #[cfg(feature = "collection")]
     fn setup(&self) -> Result<(), Error>;
 
-    fn prepare_queries(&self, queries: &mut Vec<Query>);
+    /// The phrase to say to ask the voice assistant a query: the query prefixed with the wake word.
+    ///
+    /// Queries are stored without a wake word, so the same queries can be asked to every assistant.
+    ///
+    /// # Arguments
+    ///
+    /// * `text`: The text of the query.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use varys::assistant::{from, VoiceAssistant};
+    /// # use varys::assistant::siri::Siri;
+    /// assert_eq!(Siri {}.wake_phrase("Play some music."), "Hey Siri. Play some music.");
+    /// ```
+    fn wake_phrase(&self, text: &str) -> String {
+        format!("{}. {text}", self.wake_word())
+    }
 
     /// Stop the current interaction with the voice assistant.
     ///
     /// # Arguments
     ///
     /// * `interactor`: The interactor to use to reset the assistant.
+    #[cfg(feature = "collection")]
     fn stop_assistant(&self, interactor: &Interactor) -> Result<(), Error>;
 
     /// Reset the voice assistant to a state in which it can be used again. This is used when there are timeouts that
@@ -62,6 +112,7 @@ pub trait VoiceAssistant {
     /// # Arguments
     ///
     /// * `interactor`: The interactor to use to reset the assistant.
+    #[cfg(feature = "collection")]
     fn reset_assistant(&self, interactor: &Interactor) -> Result<(), Error>;
 
     /// Test a number of voices by saying an example sentence for each one.
@@ -80,6 +131,7 @@ pub trait VoiceAssistant {
     /// let voices = vec!["Zoe".to_string(), "Isha".to_string()];
     /// assistant.test_voices(voices).unwrap();
     /// ```
+    #[cfg(feature = "collection")]
     fn test_voices(&self, voices: Vec<String>) -> Result<(), Error>;
 
     /// The length of silence indicating that the assistant is done talking.
@@ -92,13 +144,128 @@ pub trait VoiceAssistant {
     fn recording_timeout(&self) -> Duration;
 }
 
-/// Create a voice assistant from its name.
+/// The voice assistants that can be selected by name.
+///
+/// Siri, Alexa and the Google Assistant are registered by default. Crates that use varys as a library can register
+/// their own assistants before running the CLI with [`cli::run`](crate::cli::run).
+pub struct AssistantRegistry;
+
+impl AssistantRegistry {
+    /// The registered assistants, in the order they were registered.
+    fn assistants() -> &'static RwLock<Vec<Arc<dyn VoiceAssistant>>> {
+        static ASSISTANTS: OnceLock<RwLock<Vec<Arc<dyn VoiceAssistant>>>> = OnceLock::new();
+
+        ASSISTANTS.get_or_init(|| {
+            RwLock::new(vec![
+                Arc::new(Siri {}),
+                Arc::new(Alexa {}),
+                Arc::new(GoogleAssistant::default()),
+                Arc::new(GoogleAssistant {
+                    wake_word: GoogleAssistant::WAKE_WORDS[1].to_string(),
+                }),
+            ])
+        })
+    }
+
+    /// Register a voice assistant, so it can be selected by its name or wake word.
+    ///
+    /// An assistant with the same name or wake word as one that was registered before takes precedence over it.
+    ///
+    /// # Arguments
+    ///
+    /// * `assistant`: The assistant to register.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use varys::assistant::{from, AssistantRegistry, VoiceAssistant};
+    /// struct Bixby;
+    ///
+    /// impl VoiceAssistant for Bixby {
+    ///     fn name(&self) -> String {
+    ///         "Bixby".to_string()
+    ///     }
+    ///     fn wake_word(&self) -> String {
+    ///         "Hi Bixby".to_string()
+    ///     }
+    ///     // ...
+    /// #     fn setup(&self) -> Result<(), varys::error::Error> {
+    /// #         Ok(())
+    /// #     }
+    /// #     fn stop_assistant(&self, _: &varys::assistant::interactor::Interactor) -> Result<(), varys::error::Error> {
+    /// #         Ok(())
+    /// #     }
+    /// #     fn reset_assistant(&self, _: &varys::assistant::interactor::Interactor) -> Result<(), varys::error::Error> {
+    /// #         Ok(())
+    /// #     }
+    /// #     fn test_voices(&self, _: Vec<String>) -> Result<(), varys::error::Error> {
+    /// #         Ok(())
+    /// #     }
+    /// #     fn silence_after_talking(&self) -> Duration {
+    /// #         Duration::from_secs(2)
+    /// #     }
+    /// #     fn silence_between_interactions(&self) -> Duration {
+    /// #         Duration::from_secs(5)
+    /// #     }
+    /// #     fn recording_timeout(&self) -> Duration {
+    /// #         Duration::from_secs(120)
+    /// #     }
+    /// }
+    ///
+    /// AssistantRegistry::register(Box::new(Bixby));
+    ///
+    /// assert_eq!(from("bixby").name().as_str(), "Bixby");
+    /// assert!(AssistantRegistry::all().iter().any(|assistant| assistant.name() == "Bixby"));
+    /// ```
+    pub fn register(assistant: Box<dyn VoiceAssistant>) {
+        info!("Registering voice assistant {}", assistant.name());
+
+        Self::assistants()
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(assistant.into());
+    }
+
+    /// Get a registered voice assistant by its name or wake word, ignoring case.
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: The name or wake word of the assistant.
+    pub fn get(name: &str) -> Option<Arc<dyn VoiceAssistant>> {
+        let assistants = Self::assistants()
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        assistants
+            .iter()
+            .rev()
+            .find(|assistant| assistant.name().eq_ignore_ascii_case(name))
+            .or_else(|| {
+                assistants
+                    .iter()
+                    .rev()
+                    .find(|assistant| assistant.wake_word().eq_ignore_ascii_case(name))
+            })
+            .cloned()
+    }
+
+    /// Get all registered voice assistants, in the order they were registered.
+    pub fn all() -> Vec<Arc<dyn VoiceAssistant>> {
+        Self::assistants()
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+}
+
+/// Get a registered voice assistant by its name or wake word, see [`AssistantRegistry::get`].
 ///
-/// Pass `None` to get the default assistant.
+/// Falls back to the first registered assistant, Siri, if no assistant is registered under the name.
 ///
 /// # Arguments
 ///
-/// * `name`: The optional name of the voice assistant.
+/// * `name`: The name or wake word of the voice assistant.
 ///
 /// # Examples
 ///
@@ -109,15 +276,16 @@ pub trait VoiceAssistant {
 /// assert_eq!(from("siri").name().as_str(), "Siri");
 /// assert_eq!(from("Alexa").name().as_str(), "Alexa");
 /// assert_eq!(from("alexa").name().as_str(), "Alexa");
+/// assert_eq!(from("Google").name().as_str(), "Google");
+/// assert_eq!(from("OK Google").wake_word().as_str(), "OK Google");
 /// ```
-pub fn from(name: &str) -> Box<dyn VoiceAssistant> {
-    match name.to_lowercase().as_str() {
-        "siri" => Box::new(Siri {}),
-        "alexa" => Box::new(Alexa {}),
-        _ => {
-            warn!("Unknown voice assistant: {name}, assuming default");
-
-            Box::new(Siri {})
-        }
-    }
+pub fn from(name: &str) -> Arc<dyn VoiceAssistant> {
+    AssistantRegistry::get(name).unwrap_or_else(|| {
+        warn!("Unknown voice assistant: {name}, assuming default");
+
+        AssistantRegistry::all()
+            .first()
+            .cloned()
+            .unwrap_or_else(|| Arc::new(Siri {}))
+    })
 }
@@ -1,14 +1,20 @@
 use std::time::Duration;
 
+#[cfg(feature = "collection")]
 use colored::Colorize;
+#[cfg(feature = "collection")]
 use log::info;
 
+#[cfg(feature = "collection")]
 use varys_audio::tts::Speaker;
 
+#[cfg(feature = "collection")]
 use crate::assistant::interactor::Interactor;
-use crate::assistant::{Error, VoiceAssistant};
+use crate::assistant::VoiceAssistant;
+#[cfg(feature = "collection")]
 use crate::cli::{interact, key_type::KeyType};
-use crate::query::Query;
+#[cfg(feature = "collection")]
+use crate::error::Error;
 
 /// The [`VoiceAssistant`] implementation for Alexa. Tested with the Echo Dot.
 pub struct Alexa {}
@@ -27,6 +33,7 @@ impl VoiceAssistant for Alexa {
         "Alexa".to_string()
     }
 
+    #[cfg(feature = "collection")]
     fn setup(&self) -> Result<(), Error> {
         info!("Starting Alexa setup...");
 
@@ -79,14 +86,7 @@ impl VoiceAssistant for Alexa {
         Ok(())
     }
 
-    fn prepare_queries(&self, queries: &mut Vec<Query>) {
-        info!("Preparing queries for Alexa...");
-
-        queries.iter_mut().for_each(|q| {
-            q.text = format!("Alexa. {}", q.text);
-        });
-    }
-
+    #[cfg(feature = "collection")]
     fn stop_assistant(&self, interactor: &Interactor) -> Result<(), Error> {
         info!("Telling Alexa to stop...");
 
@@ -100,6 +100,7 @@ impl VoiceAssistant for Alexa {
         Ok(())
     }
 
+    #[cfg(feature = "collection")]
     fn reset_assistant(&self, interactor: &Interactor) -> Result<(), Error> {
         info!("Telling Alexa to stop everything...");
 
@@ -123,6 +124,7 @@ impl VoiceAssistant for Alexa {
         Ok(())
     }
 
+    #[cfg(feature = "collection")]
     fn test_voices(&self, voices: Vec<String>) -> Result<(), Error> {
         info!("Testing Alexa voices...");
 
@@ -0,0 +1,142 @@
+use std::collections::VecDeque;
+use std::fmt::{Display, Formatter};
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+/// The random choices made for an interaction and the parameters it was collected with, stored with every interaction
+/// so the conditions of any sample can be reproduced.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Conditions {
+    /// The seed of the random number generator that made the random choices of the interaction, see [`rng`].
+    pub seed: u64,
+    /// The seed the queries of the session were shuffled with, or `None` if they were asked in order.
+    pub shuffle_seed: Option<u64>,
+    /// The position of the query in the order the queries of the session were asked in, starting at `0`.
+    pub position: usize,
+    /// The number of queries of the session.
+    pub queries: usize,
+    /// How often the query had been asked, starting at `1`.
+    pub attempt: u32,
+    /// The voice the query was asked with.
+    pub voice: String,
+    /// The locale the query was asked in, which the voice was chosen for, see
+    /// [`Query::locale`](crate::query::Query::locale).
+    pub locale: Option<String>,
+    /// Whether the query was an exemplar sample, see [`Query::exemplar`](crate::query::Query::exemplar).
+    #[serde(default)]
+    pub exemplar: bool,
+    /// Whether the query was synthesised or played from a recording, see [`QuerySource`](crate::query::QuerySource).
+    pub query_source: String,
+    /// Whether a watermark was mixed into the query.
+    pub watermarked: bool,
+    /// The randomised idle time in milliseconds waited before the query, drawn from the random number generator.
+    pub idle_gap_ms: Option<u64>,
+    /// Whether the microphone of the assistant was muted.
+    pub mic_muted: bool,
+    /// The speaking rate of the query relative to the normal rate of the voice, see
+    /// [`RateSweep`](crate::assistant::rate_sweep::RateSweep).
+    #[serde(default = "normal_rate")]
+    pub speaking_rate: f32,
+    /// The threshold that distinguished silence from sound.
+    pub sensitivity: f32,
+    /// How the threshold is computed from the ambient noise, e.g. `deviation:3`.
+    pub silence_threshold: String,
+}
+
+impl Display for Conditions {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            serde_json::to_string(self).map_err(|_| std::fmt::Error)?
+        )
+    }
+}
+
+/// The speaking rate of interactions whose conditions were stored before speaking rates were swept.
+fn normal_rate() -> f32 {
+    1.0
+}
+
+/// Create the random number generator of an interaction, which makes the same choices every time for the same seed.
+///
+/// # Arguments
+///
+/// * `seed`: The seed of the interaction.
+///
+/// # Examples
+///
+/// ```
+/// # use std::time::Duration;
+/// # use varys::assistant::conditions;
+/// # use varys::assistant::idle_gap::IdleGap;
+/// let idle_gap = IdleGap::new(Duration::from_secs(5), Duration::from_secs(30));
+///
+/// assert_eq!(
+///     idle_gap.sample(&mut conditions::rng(42)),
+///     idle_gap.sample(&mut conditions::rng(42))
+/// );
+/// ```
+pub fn rng(seed: u64) -> StdRng {
+    StdRng::seed_from_u64(seed)
+}
+
+/// Where the seeds of the random choices of a session come from, i.e. the seed its queries are shuffled with and the
+/// seed of every interaction.
+#[derive(Debug, Default)]
+pub enum Seeds {
+    /// Draw a fresh random seed every time.
+    #[default]
+    Random,
+    /// Draw the seeds from a random number generator with a fixed seed, so a session asking the same queries makes the
+    /// same choices every time.
+    Seeded(StdRng),
+    /// Reuse the seeds of the interactions of a collected session in order, continuing with random seeds once they
+    /// run out.
+    Replayed(VecDeque<u64>),
+}
+
+impl Seeds {
+    /// Draw all seeds from a random number generator with the given seed, see [`Seeds::Seeded`].
+    ///
+    /// # Arguments
+    ///
+    /// * `seed`: The seed of the session.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys::assistant::conditions::Seeds;
+    /// let mut first = Seeds::from_seed(42);
+    /// let mut second = Seeds::from_seed(42);
+    ///
+    /// assert_eq!(first.next_seed(), second.next_seed());
+    /// assert_eq!(first.next_seed(), second.next_seed());
+    /// ```
+    pub fn from_seed(seed: u64) -> Self {
+        Seeds::Seeded(rng(seed))
+    }
+
+    /// Get the next seed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys::assistant::conditions::Seeds;
+    /// let mut seeds = Seeds::Replayed([3, 5].into());
+    ///
+    /// assert_eq!(seeds.next_seed(), 3);
+    /// assert_eq!(seeds.next_seed(), 5);
+    /// ```
+    pub fn next_seed(&mut self) -> u64 {
+        match self {
+            Seeds::Random => rand::thread_rng().gen(),
+            Seeds::Seeded(rng) => rng.gen(),
+            Seeds::Replayed(seeds) => seeds
+                .pop_front()
+                .unwrap_or_else(|| rand::thread_rng().gen()),
+        }
+    }
+}
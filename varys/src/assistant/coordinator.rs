@@ -0,0 +1,262 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+use varys_database::connection::DatabaseConnection;
+use varys_database::database::rig::Rig;
+
+use crate::assistant::shutdown::Shutdown;
+use crate::error::Error;
+use crate::query::Query;
+
+/// How long before the end of its time slot a rig stops its session, so the interaction it aborts does not overlap
+/// with the first interaction of the next rig.
+const SLOT_GUARD: Duration = Duration::from_secs(30);
+
+/// How long a rig waits at most before registering again while it waits for its time slot, so rigs that join or
+/// leave are noticed.
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Time divided into slots of equal length, counted from the Unix epoch, which the rigs of a multi-room deployment take
+/// turns in.
+///
+/// All rigs have to use the same slot length, otherwise their slots overlap.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimeSlots {
+    /// The length of every slot.
+    pub length: Duration,
+}
+
+impl TimeSlots {
+    /// The next time slot of a rig that ends after a given time, as its start and end.
+    ///
+    /// Slot `k` belongs to the rig at index `k % rigs`. If the slot at the given time belongs to the rig, it starts at
+    /// the given time.
+    ///
+    /// # Arguments
+    ///
+    /// * `index`: The index of the rig among all active rigs.
+    /// * `rigs`: The number of active rigs.
+    /// * `after`: The time after which the slot ends.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use chrono::{TimeZone, Utc};
+    /// # use varys::assistant::coordinator::TimeSlots;
+    /// let slots = TimeSlots { length: Duration::from_secs(600) };
+    /// let time = |minute| Utc.with_ymd_and_hms(2024, 1, 1, 0, minute, 0).unwrap();
+    ///
+    /// assert_eq!(slots.next(0, 3, time(5)), (time(5), time(10)));
+    /// assert_eq!(slots.next(1, 3, time(5)), (time(10), time(20)));
+    /// assert_eq!(slots.next(2, 3, time(5)), (time(20), time(30)));
+    /// assert_eq!(slots.next(0, 3, time(10)), (time(30), time(40)));
+    /// assert_eq!(slots.next(0, 1, time(10)), (time(10), time(20)));
+    /// ```
+    pub fn next(
+        &self,
+        index: usize,
+        rigs: usize,
+        after: DateTime<Utc>,
+    ) -> (DateTime<Utc>, DateTime<Utc>) {
+        let length = self.length.as_millis().max(1) as i64;
+        let rigs = rigs.max(1) as i64;
+        let after_millis = after.timestamp_millis();
+        let current = after_millis.div_euclid(length);
+        let slot = current + (index as i64 - current).rem_euclid(rigs);
+
+        let start = after + chrono::Duration::milliseconds(slot * length - after_millis);
+        let end = start + chrono::Duration::milliseconds(length);
+
+        (start.max(after), end)
+    }
+}
+
+/// Split queries into non-overlapping blocks, one for every rig, and get the block of a rig.
+///
+/// The queries are sorted by their text first, so every rig gets the same blocks regardless of the order it read or
+/// shuffled them in. The sizes of the blocks differ by at most one.
+///
+/// # Arguments
+///
+/// * `queries`: The queries of all rigs.
+/// * `index`: The index of the rig among all active rigs.
+/// * `rigs`: The number of active rigs.
+///
+/// # Examples
+///
+/// ```
+/// # use varys::assistant::coordinator::query_block;
+/// # use varys::query::Query;
+/// let query = |text: &str| Query {
+///     text: text.to_string(),
+///     category: "test".to_string(),
+///     recording_timeout: None,
+///     follow_ups: Vec::new(),
+///     locale: None,
+///     exemplar: false,
+/// };
+/// let queries = vec![query("e"), query("b"), query("d"), query("a"), query("c")];
+///
+/// let first: Vec<String> = query_block(&queries, 0, 2).into_iter().map(|query| query.text).collect();
+/// let second: Vec<String> = query_block(&queries, 1, 2).into_iter().map(|query| query.text).collect();
+///
+/// assert_eq!(first, vec!["a", "b"]);
+/// assert_eq!(second, vec!["c", "d", "e"]);
+/// assert_eq!(query_block(&queries, 0, 1).len(), 5);
+/// ```
+pub fn query_block(queries: &[Query], index: usize, rigs: usize) -> Vec<Query> {
+    let mut queries = queries.to_vec();
+    queries.sort_by(|a, b| a.text.cmp(&b.text));
+
+    let rigs = rigs.max(1);
+    let start = (queries.len() * index / rigs).min(queries.len());
+    let end = (queries.len() * (index + 1) / rigs).min(queries.len());
+
+    queries[start..end].to_vec()
+}
+
+/// A time slot of a rig with the queries it asks in it.
+#[derive(Clone, Debug)]
+pub struct Slot {
+    /// The block of queries of the rig, see [`query_block`].
+    pub queries: Vec<Query>,
+    /// When the session of the slot has to be completed.
+    pub ends: DateTime<Utc>,
+}
+
+impl Slot {
+    /// Get a shutdown that is requested when the slot ends or another shutdown is requested, whichever happens first.
+    ///
+    /// This has to be called from within a tokio runtime.
+    ///
+    /// # Arguments
+    ///
+    /// * `shutdown`: The shutdown that also ends the slot.
+    pub fn shutdown(&self, shutdown: &Shutdown) -> Shutdown {
+        let slot_shutdown = Shutdown::default();
+        let requester = slot_shutdown.clone();
+        let shutdown = shutdown.clone();
+        let remaining = (self.ends - Utc::now()).to_std().unwrap_or_default();
+
+        tokio::spawn(async move {
+            shutdown.sleep(remaining).await;
+            requester.request();
+        });
+
+        slot_shutdown
+    }
+}
+
+/// One rig of a multi-room deployment, which takes turns with the other rigs registered in the same database.
+///
+/// Active rigs are ordered by when they first registered. The rig at index `i` of `n` rigs asks the `i`-th of `n`
+/// blocks of the queries, see [`query_block`], and runs its sessions only in its own time slots, see [`TimeSlots`].
+/// This way no two rigs collect the same queries and no rig speaks while another one listens. When a rig joins or
+/// leaves, the blocks and slots of all rigs change with their next slot.
+pub struct Coordinator {
+    name: String,
+    room: String,
+    mac: String,
+    slots: TimeSlots,
+}
+
+impl Coordinator {
+    /// Create a coordinator for a rig.
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: The unique name of the rig.
+    /// * `room`: The room the rig is set up in.
+    /// * `mac`: The MAC address of the assistant of the rig.
+    /// * `slots`: The time slots the rigs take turns in.
+    pub fn new(name: String, room: String, mac: String, slots: TimeSlots) -> Self {
+        Coordinator {
+            name,
+            room,
+            mac,
+            slots,
+        }
+    }
+
+    /// Register the rig and wait for its next time slot.
+    ///
+    /// Returns `None` if the shutdown was requested while waiting.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to the database shared by all rigs.
+    /// * `queries`: The queries of all rigs.
+    /// * `shutdown`: The shutdown that stops waiting.
+    pub async fn next_slot(
+        &self,
+        connection: &DatabaseConnection,
+        queries: &[Query],
+        shutdown: &Shutdown,
+    ) -> Result<Option<Slot>, Error> {
+        // rigs register at least once per slot and while waiting
+        let timeout = self.slots.length + 2 * POLL_INTERVAL;
+
+        while !shutdown.is_requested() {
+            let rig = Rig::register(connection, &self.name, &self.room, &self.mac).await?;
+            let since = Utc::now() - chrono::Duration::milliseconds(timeout.as_millis() as i64);
+            let rigs = Rig::get_active(connection, since).await?;
+            let index = rigs
+                .iter()
+                .position(|other| other.id == rig.id)
+                .unwrap_or_default();
+
+            let now = Utc::now();
+            let (start, end) = self.slots.next(index, rigs.len(), now);
+            let ends = end - chrono::Duration::milliseconds(SLOT_GUARD.as_millis() as i64);
+            let queries = query_block(queries, index, rigs.len());
+
+            if start <= now && ends > now {
+                if !queries.is_empty() {
+                    info!(
+                        "Rig {} is rig {} of {} and asks {} queries until {}",
+                        self.name,
+                        index + 1,
+                        rigs.len(),
+                        queries.len(),
+                        ends.format("%H:%M:%S %Z")
+                    );
+
+                    return Ok(Some(Slot { queries, ends }));
+                }
+
+                warn!(
+                    "Rig {} has no queries left since there are more rigs than queries",
+                    self.name
+                );
+            }
+
+            let next = if start <= now { end } else { start };
+            info!(
+                "Rig {} is waiting for its time slot at {}",
+                self.name,
+                next.format("%H:%M:%S %Z")
+            );
+            shutdown
+                .sleep((next - now).to_std().unwrap_or_default().min(POLL_INTERVAL))
+                .await;
+        }
+
+        Ok(None)
+    }
+
+    /// Unregister the rig, so its queries and time slots are taken over by the other rigs right away.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to the database shared by all rigs.
+    pub async fn leave(&self, connection: &DatabaseConnection) -> Result<(), Error> {
+        Rig::register(connection, &self.name, &self.room, &self.mac)
+            .await?
+            .unregister(connection)
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,53 @@
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use chrono::Utc;
+use serde_json::{Map, Value};
+
+use crate::error::Error;
+
+/// An append-only log of everything that happens during a session, stored next to its data with one JSON object per
+/// line.
+///
+/// Every event is synced to disk before [`EventLog::write`] returns, so a session can be reconstructed from its log
+/// even if writing to the database failed.
+pub struct EventLog {
+    file: File,
+}
+
+impl EventLog {
+    /// Open the log at a path, appending to it if it exists already.
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: The path of the log.
+    pub fn open(path: &Path) -> Result<Self, Error> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+
+        Ok(EventLog { file })
+    }
+
+    /// Append an event to the log.
+    ///
+    /// The event is stored with the current time and its kind, followed by its details.
+    ///
+    /// # Arguments
+    ///
+    /// * `event`: The kind of the event, e.g. `query_spoken`.
+    /// * `details`: An object with the details of the event.
+    pub fn write(&self, event: &str, details: Value) -> Result<(), Error> {
+        let mut entry = Map::new();
+        entry.insert("time".to_string(), Value::from(Utc::now().to_rfc3339()));
+        entry.insert("event".to_string(), Value::from(event));
+        if let Value::Object(details) = details {
+            entry.extend(details);
+        }
+
+        let mut file = &self.file;
+        writeln!(file, "{}", Value::Object(entry))?;
+        file.sync_data()?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,246 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use log::{error, info};
+use serde::Deserialize;
+use tokio::runtime::Handle;
+use varys_audio::listen::Listener;
+#[cfg(feature = "transcription")]
+use varys_audio::stt::transcriber::Transcriber;
+#[cfg(feature = "transcription")]
+use varys_audio::stt::Recogniser;
+use varys_audio::tts::Speaker;
+use varys_database::connection::DatabaseConnection;
+use varys_database::database;
+use varys_network::sniff::{self, Sniffer};
+
+use crate::assistant;
+use crate::assistant::interactor::{Backends, Interactor};
+use crate::assistant::shutdown::Shutdown;
+use crate::error::Error;
+use crate::query::validation;
+use crate::query::Query;
+
+/// One voice assistant of a [`Fleet`] with the hardware used to talk to it.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct FleetMember {
+    /// A name for the assistant, used in the logs.
+    pub name: String,
+    /// The network interface the traffic of the assistant is captured on.
+    pub interface: String,
+    /// The MAC address of the assistant.
+    pub mac: String,
+    /// The name or index of the microphone that records the assistant. If this is `None`, the default input device is
+    /// used.
+    pub input_device: Option<String>,
+    /// The device queries are played on, only supported on Linux. If this is `None`, the default output device is
+    /// used.
+    pub output_device: Option<String>,
+    /// The voices queries are asked with.
+    pub voices: Vec<String>,
+}
+
+/// Several voice assistants that are interacted with at the same time, e.g. smart speakers on separate networks.
+///
+/// Every member gets its own [`Interactor`], running in its own thread with its own listener, speaker and sniffer.
+/// All interactors share one database connection pool and each runs its own sessions, so interactions of different
+/// members never end up in the same session.
+pub struct Fleet {
+    members: Vec<FleetMember>,
+    sensitivity: f32,
+    model: PathBuf,
+    data_dir: PathBuf,
+}
+
+impl Fleet {
+    /// Create a fleet.
+    ///
+    /// # Arguments
+    ///
+    /// * `members`: The voice assistants to interact with.
+    /// * `sensitivity`: The sensitivity of the listeners.
+    /// * `model`: The path to the whisper model used to transcribe responses.
+    /// * `data_dir`: The path to the data directory shared by all members.
+    pub fn new(
+        members: Vec<FleetMember>,
+        sensitivity: f32,
+        model: PathBuf,
+        data_dir: PathBuf,
+    ) -> Self {
+        Fleet {
+            members,
+            sensitivity,
+            model,
+            data_dir,
+        }
+    }
+
+    /// Read the members of a fleet from a TOML file with a `[[member]]` table per voice assistant.
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: The path to the TOML file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys::assistant::fleet::Fleet;
+    /// let members = Fleet::read_toml("../data/test_fleet.toml").unwrap();
+    ///
+    /// assert_eq!(members.len(), 2);
+    /// assert_eq!(members[0].name, "kitchen");
+    /// assert_eq!(members[0].output_device, None);
+    /// assert_eq!(members[1].voices, vec!["Isha"]);
+    /// ```
+    pub fn read_toml<P: AsRef<Path>>(path: P) -> Result<Vec<FleetMember>, Error> {
+        #[derive(Deserialize)]
+        struct FleetFile {
+            member: Vec<FleetMember>,
+        }
+
+        info!("Reading fleet from {}", path.as_ref().display());
+
+        Ok(toml::from_str::<FleetFile>(&fs::read_to_string(path)?)?.member)
+    }
+
+    /// Interact with all members at the same time until varys is stopped.
+    ///
+    /// Each member asks all queries in every session, in its own order. A member that cannot be set up, e.g. because
+    /// its microphone is missing, stops without affecting the others. Pressing Ctrl-C finalises the current session of
+    /// every member, leaving it open so it can be resumed, and stops the fleet.
+    ///
+    /// # Arguments
+    ///
+    /// * `queries`: The queries to ask, without the wake word of the assistant.
+    /// * `assistant`: The name of the voice assistant all members run.
+    /// * `allow_repeats`: Whether the queries may list a query more than once, to ask it more often.
+    pub async fn run(
+        &self,
+        queries: Vec<Query>,
+        assistant: &str,
+        allow_repeats: bool,
+    ) -> Result<(), Error> {
+        validation::validate(
+            queries.iter().map(|query| query.text.as_str()),
+            Some(&assistant::from(assistant).wake_word()),
+        )
+        .allow_repeats(allow_repeats)
+        .check()?;
+
+        let database = database::connect().await?;
+        let shutdown = Shutdown::on_ctrl_c();
+
+        info!("Starting a fleet of {} assistants", self.members.len());
+
+        let members: Vec<_> = self
+            .members
+            .iter()
+            .cloned()
+            .map(|member| {
+                let queries = queries.clone();
+                let assistant = assistant.to_string();
+                let database = database.clone();
+                let sensitivity = self.sensitivity;
+                let model = self.model.clone();
+                let data_dir = self.data_dir.clone();
+                let shutdown = shutdown.clone();
+                let handle = Handle::current();
+
+                // interactors block while speaking and listening, so each gets a thread of its own
+                tokio::task::spawn_blocking(move || {
+                    let name = member.name.clone();
+                    let result = run_member(
+                        member,
+                        queries,
+                        &assistant,
+                        database,
+                        sensitivity,
+                        &model,
+                        data_dir,
+                        shutdown,
+                        &handle,
+                    );
+                    if let Err(error) = &result {
+                        error!("[{name}] Stopped interacting: {error}");
+                    }
+
+                    result
+                })
+            })
+            .collect();
+
+        for member in members {
+            // errors were already logged by the member
+            let _ = member.await;
+        }
+
+        Ok(())
+    }
+}
+
+/// Interact with one member of a fleet in a loop of sessions.
+///
+/// Returns once the shutdown was requested, or if the hardware of the member could not be set up.
+#[allow(clippy::too_many_arguments)]
+fn run_member(
+    member: FleetMember,
+    mut queries: Vec<Query>,
+    assistant: &str,
+    database: DatabaseConnection,
+    sensitivity: f32,
+    model: &Path,
+    data_dir: PathBuf,
+    shutdown: Shutdown,
+    handle: &Handle,
+) -> Result<(), Error> {
+    let listener = match &member.input_device {
+        Some(input_device) => Listener::with_device(input_device)?,
+        None => Listener::new()?,
+    };
+    let mut speaker = Speaker::new()?;
+    speaker.set_output_device(member.output_device.clone());
+
+    let mut interactor = Interactor::with_backends(
+        Backends {
+            listener: Box::new(listener),
+            speaker: Box::new(speaker),
+            sniffer: Box::new(Sniffer::from(sniff::device_by_name(&member.interface)?)),
+        },
+        member.interface.clone(),
+        member.voices.clone(),
+        sensitivity,
+        model.to_string_lossy().to_string(),
+        data_dir,
+        member.mac.clone(),
+    );
+    interactor.set_database(Some(database));
+    interactor.set_shutdown(Some(shutdown.clone()));
+
+    let assistant = assistant::from(assistant);
+
+    while !shutdown.is_requested() {
+        #[cfg(feature = "transcription")]
+        let transcriber_handle = {
+            let (transcriber, transcriber_handle) =
+                Transcriber::new(Recogniser::with_model_path(&model.to_string_lossy())?);
+
+            let _ = std::thread::spawn(move || transcriber.start());
+            Some(transcriber_handle)
+        };
+        #[cfg(not(feature = "transcription"))]
+        let transcriber_handle = None;
+
+        info!("[{}] Starting a session", member.name);
+
+        if let Err(error) =
+            handle.block_on(interactor.start(&mut queries, assistant.as_ref(), transcriber_handle))
+        {
+            error!(
+                "[{}] A session did not complete successfully: {error}",
+                member.name
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,198 @@
+use std::time::Duration;
+
+#[cfg(feature = "collection")]
+use colored::Colorize;
+#[cfg(feature = "collection")]
+use log::info;
+
+#[cfg(feature = "collection")]
+use varys_audio::tts::Speaker;
+
+#[cfg(feature = "collection")]
+use crate::assistant::interactor::Interactor;
+use crate::assistant::VoiceAssistant;
+#[cfg(feature = "collection")]
+use crate::cli::{interact, key_type::KeyType};
+use crate::error::Error;
+
+/// The [`VoiceAssistant`] implementation for the Google Assistant. Tested with the Nest Mini.
+pub struct GoogleAssistant {
+    /// The wake word that is put in front of every query, one of [`GoogleAssistant::WAKE_WORDS`].
+    pub wake_word: String,
+}
+
+impl GoogleAssistant {
+    pub const PREMIUM_VOICES: &'static [&'static str] =
+        &["Ava", "Karen", "Jamie", "Matilda", "Serena", "Zoe"];
+    /// The wake words the Google Assistant listens for.
+    pub const WAKE_WORDS: &'static [&'static str] = &["Hey Google", "OK Google"];
+
+    /// Create a Google Assistant that is woken with a specific wake word.
+    ///
+    /// Returns [`Error::UnknownWakeWord`] if the wake word is not one of [`GoogleAssistant::WAKE_WORDS`], ignoring case.
+    ///
+    /// # Arguments
+    ///
+    /// * `wake_word`: The wake word, one of [`GoogleAssistant::WAKE_WORDS`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys::assistant::google::GoogleAssistant;
+    /// # use varys::assistant::VoiceAssistant;
+    /// let assistant = GoogleAssistant::with_wake_word("ok google").unwrap();
+    ///
+    /// assert_eq!(assistant.wake_word().as_str(), "OK Google");
+    /// assert!(GoogleAssistant::with_wake_word("Hey Siri").is_err());
+    /// ```
+    pub fn with_wake_word(wake_word: &str) -> Result<Self, Error> {
+        let wake_word = GoogleAssistant::WAKE_WORDS
+            .iter()
+            .find(|known| known.eq_ignore_ascii_case(wake_word))
+            .ok_or_else(|| Error::UnknownWakeWord(wake_word.to_string(), "Google".to_string()))?;
+
+        Ok(GoogleAssistant {
+            wake_word: wake_word.to_string(),
+        })
+    }
+}
+
+impl Default for GoogleAssistant {
+    fn default() -> Self {
+        GoogleAssistant {
+            wake_word: GoogleAssistant::WAKE_WORDS[0].to_string(),
+        }
+    }
+}
+
+impl VoiceAssistant for GoogleAssistant {
+    fn name(&self) -> String {
+        "Google".to_string()
+    }
+
+    fn wake_word(&self) -> String {
+        self.wake_word.clone()
+    }
+
+    #[cfg(feature = "collection")]
+    fn setup(&self) -> Result<(), Error> {
+        info!("Starting Google Assistant setup...");
+
+        let mut speaker = Speaker::new()?;
+
+        let voice = interact::user_input(
+            &format!(
+                "Choose the voice to set up (The highest quality voices on macOS are {}):",
+                GoogleAssistant::PREMIUM_VOICES.join(", ")
+            ),
+            |i| speaker.set_voice(i).is_ok(),
+            "Voice not found, enter a voice that can be used on this system:",
+        )?;
+        println!("Setting up the Google Assistant for {}", voice);
+        interact::user_confirmation(
+            "This requires a number of sentences to be said. To continue, press",
+        )?;
+        interact::user_confirmation(
+            "Make sure your phone is close enough to this computer to hear it.",
+        )?;
+        interact::user_confirmation(&format!(
+            "In your Google Home App, go to {} > {} and choose {} (you might have to remove the old model first)",
+            "Settings".bright_blue(),
+            "Google Assistant".bright_blue(),
+            "Hey Google & Voice Match".bright_blue(),
+        ))?;
+        interact::user_confirmation(&format!(
+            "The sentences will now be said. Press {} on your device and then",
+            "I agree".bright_blue()
+        ))?;
+        for sentence in [
+            "OK Google.",
+            "Hey Google.",
+            "OK Google. What's the weather today?",
+            "Hey Google. Set a timer for five minutes.",
+        ] {
+            loop {
+                speaker.say(sentence)?;
+                if interact::user_choice(
+                    "Confirm that the Google Assistant recognised the sentence or repeat it",
+                    &[KeyType::Enter, KeyType::Key('r')],
+                )? == KeyType::Enter
+                {
+                    break;
+                }
+            }
+        }
+        println!("Finished setting up the Google Assistant");
+
+        Ok(())
+    }
+
+    #[cfg(feature = "collection")]
+    fn stop_assistant(&self, interactor: &Interactor) -> Result<(), Error> {
+        info!("Telling the Google Assistant to stop...");
+
+        interactor
+            .speaker
+            .say(&format!("{}, stop.", self.wake_word))?;
+        interactor.listener.wait_until_silent(
+            self.silence_between_interactions(),
+            interactor.sensitivity,
+            false,
+        )?;
+
+        Ok(())
+    }
+
+    #[cfg(feature = "collection")]
+    fn reset_assistant(&self, interactor: &Interactor) -> Result<(), Error> {
+        info!("Telling the Google Assistant to stop everything...");
+
+        let wait = || {
+            interactor.listener.wait_until_silent(
+                self.silence_after_talking(),
+                interactor.sensitivity,
+                false,
+            )
+        };
+
+        for command in ["stop", "turn off the music", "cancel all alarms"] {
+            interactor
+                .speaker
+                .say(&format!("{}, {command}.", self.wake_word))?;
+            wait()?;
+        }
+
+        info!("The Google Assistant has been told to stop everything");
+
+        Ok(())
+    }
+
+    #[cfg(feature = "collection")]
+    fn test_voices(&self, voices: Vec<String>) -> Result<(), Error> {
+        info!("Testing Google Assistant voices...");
+
+        let mut speaker = Speaker::new()?;
+
+        for voice in voices {
+            interact::user_confirmation(&format!("Test {}", voice))?;
+            speaker.set_voice(&voice)?;
+            speaker.say(&format!("{}, what is my name?", self.wake_word))?;
+        }
+
+        Ok(())
+    }
+
+    /// The Google Assistant pauses longer than other assistants within responses, e.g. after announcing search
+    /// results, so a longer silence is needed to tell that it is done.
+    fn silence_after_talking(&self) -> Duration {
+        Duration::from_secs(3)
+    }
+
+    fn silence_between_interactions(&self) -> Duration {
+        Duration::from_secs(5)
+    }
+
+    fn recording_timeout(&self) -> Duration {
+        Duration::from_secs(120)
+    }
+}
@@ -0,0 +1,55 @@
+use std::time::Duration;
+
+use rand::Rng;
+
+/// A randomised idle time between interactions, so the traffic of a session does not follow a fixed rhythm.
+///
+/// Every gap is drawn uniformly between the minimum and maximum duration.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IdleGap {
+    /// The shortest gap.
+    pub min: Duration,
+    /// The longest gap.
+    pub max: Duration,
+}
+
+impl IdleGap {
+    /// Create an idle gap, swapping the durations if the minimum is larger than the maximum.
+    ///
+    /// # Arguments
+    ///
+    /// * `min`: The shortest gap.
+    /// * `max`: The longest gap.
+    pub fn new(min: Duration, max: Duration) -> Self {
+        IdleGap {
+            min: min.min(max),
+            max: max.max(min),
+        }
+    }
+
+    /// Draw the duration of a gap.
+    ///
+    /// # Arguments
+    ///
+    /// * `rng`: The random number generator to draw with.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use varys::assistant::idle_gap::IdleGap;
+    /// let idle_gap = IdleGap::new(Duration::from_secs(30), Duration::from_secs(5));
+    ///
+    /// for _ in 0..100 {
+    ///     let gap = idle_gap.sample(&mut rand::thread_rng());
+    ///     assert!(gap >= Duration::from_secs(5) && gap <= Duration::from_secs(30));
+    /// }
+    /// assert_eq!(
+    ///     IdleGap::new(Duration::from_secs(5), Duration::from_secs(5)).sample(&mut rand::thread_rng()),
+    ///     Duration::from_secs(5)
+    /// );
+    /// ```
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> Duration {
+        rng.gen_range(self.min..=self.max)
+    }
+}
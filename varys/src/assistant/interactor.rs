@@ -1,53 +1,204 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
+use std::iter;
 use std::path::{Path, PathBuf};
-use std::time::Duration;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use chrono::{DateTime, Local, Utc};
 use log::{error, info, warn};
 use rand::prelude::SliceRandom;
+use serde_json::{json, Value};
 
-use varys_audio::audio::AudioData;
-use varys_audio::listen::Listener;
+use varys_audio::audio::{AudioData, LevelControl, SilencePadding, OPUS_SAMPLE_RATE};
+use varys_audio::echo::EchoCanceller;
+use varys_audio::listen::{Listen, Listener, SilenceThreshold};
 use varys_audio::stt::transcribe::Transcribe;
 use varys_audio::stt::transcriber::{TranscriberHandle, TranscriberReceiver, TranscriberSender};
-use varys_audio::tts::Speaker;
+use varys_audio::tts::{Speak, Speaker};
+use varys_audio::voice::{VoiceProfile, DEFAULT_MIN_SIMILARITY};
+use varys_audio::watermark::{Watermark, DEFAULT_AMPLITUDE};
 use varys_database::connection::DatabaseConnection;
+use varys_database::database::conversation::Conversation;
 use varys_database::database::interaction::Interaction;
+use varys_database::database::interaction_metrics::InteractionMetrics;
 use varys_database::database::interactor_config::InteractorConfig;
+use varys_database::database::outlier::{Outlier, VOICE_SIMILARITY_METRIC};
 use varys_database::database::session::Session;
+use varys_database::database::transcription_job::TranscriptionJob;
+use varys_database::database::wake_word_trigger::WakeWordTrigger;
 use varys_database::file::DataType;
 use varys_database::{database, file};
-use varys_network::sniff;
-use varys_network::sniff::Sniffer;
+use varys_network::address::MacAddress;
+use varys_network::sniff::{Sniff, SniffInstance, Sniffer};
+use varys_network::{index, packet, sniff};
 
+use crate::assistant::conditions::{self, Conditions, Seeds};
+use crate::assistant::event_log::EventLog;
+use crate::assistant::idle_gap::IdleGap;
+use crate::assistant::mute::MuteExperiment;
+use crate::assistant::power::{PowerCycle, POWER_CYCLE_EVENT};
+use crate::assistant::quiescence::{QuiescenceDetector, BACKGROUND_DOWNLOAD_EVENT};
+use crate::assistant::quiet_hours::{QuietHours, QUIET_HOURS_EVENT};
+use crate::assistant::rate_sweep::RateSweep;
+use crate::assistant::recalibration::Recalibration;
+use crate::assistant::replay::Replay;
+use crate::assistant::retry::RetryPolicy;
+use crate::assistant::shutdown::Shutdown;
 use crate::assistant::VoiceAssistant;
 use crate::error::Error;
-use crate::monitoring;
-use crate::query::Query;
+use crate::host::{HostMonitor, ThermalMonitor};
+use crate::query::{self, Query, QuerySource};
+use crate::redact::Redactor;
+use crate::response::truncation::{Truncation, BURST_GAP_MS};
+use crate::response::{InteractionStatus, ResponseType};
+use crate::{crash, monitoring};
 
-pub struct TranscribeInteraction(Interaction);
+/// An interaction whose response is transcribed, optionally conditioned on an initial prompt, in the language of its
+/// query and redacted before it is stored.
+pub struct TranscribeInteraction {
+    pub interaction: Interaction,
+    /// The prompt to condition the transcription on.
+    pub initial_prompt: Option<String>,
+    /// Redacts the transcribed response before it is stored.
+    pub redactor: Option<Arc<Redactor>>,
+    /// The language of the query, which the response is expected in.
+    pub language: Option<String>,
+}
 
 impl Transcribe for TranscribeInteraction {
     fn transcribed(&mut self, text: String) {
-        self.0.response = Some(text);
+        self.interaction.response_type = Some(ResponseType::classify(&text).to_string());
+        InteractionStatus::validate(&mut self.interaction, &text);
+        self.interaction.response = Some(match &self.redactor {
+            Some(redactor) => redactor.redact(&text),
+            None => text,
+        });
+    }
+
+    fn initial_prompt(&self) -> Option<String> {
+        self.initial_prompt.clone()
+    }
+
+    fn language(&self) -> Option<String> {
+        self.language.clone()
     }
 }
 
 impl From<Interaction> for TranscribeInteraction {
     fn from(interaction: Interaction) -> Self {
-        Self(interaction)
+        Self {
+            interaction,
+            initial_prompt: None,
+            redactor: None,
+            language: None,
+        }
+    }
+}
+
+/// The queries of a session, in the order they were given, and how many of them were already asked.
+struct SessionQueries<'a> {
+    /// All queries of the session, which are shuffled in place if the session shuffles them.
+    queries: &'a mut [Query],
+    /// The seed the queries are shuffled with, or `None` if they are asked in order.
+    shuffle_seed: Option<u64>,
+    /// The number of completed interactions of every query text, which are not asked again, see [`take_completed`].
+    completed: HashMap<String, usize>,
+}
+
+/// The hardware an [`Interactor`] uses to talk to a voice assistant.
+///
+/// Use [`Backends::system`] for the real microphone, speakers and network interface, or fill in fakes to run
+/// interactions without them.
+pub struct Backends {
+    pub listener: Box<dyn Listen>,
+    pub speaker: Box<dyn Speak>,
+    pub sniffer: Box<dyn Sniff>,
+}
+
+impl Backends {
+    /// Create the backends for the system microphone, speakers and the given network interface.
+    ///
+    /// # Arguments
+    ///
+    /// * `interface`: The interface to create the sniffer on.
+    pub fn system(interface: &str) -> Result<Backends, Error> {
+        Self::with_input_device(interface, None, None)
+    }
+
+    /// Create the backends for a microphone, the system speakers and the given network interface.
+    ///
+    /// # Arguments
+    ///
+    /// * `interface`: The interface to create the sniffer on.
+    /// * `input_device`: The name or index of the microphone, see [`Listener::with_device`], or `None` for the system
+    ///   default.
+    /// * `channels`: How many channels to record, see [`Listener::set_channels`], or `None` for the default of the
+    ///   microphone.
+    pub fn with_input_device(
+        interface: &str,
+        input_device: Option<&str>,
+        channels: Option<u16>,
+    ) -> Result<Backends, Error> {
+        let mut listener = match input_device {
+            Some(input_device) => Listener::with_device(input_device)?,
+            None => Listener::new()?,
+        };
+        if let Some(channels) = channels {
+            listener.set_channels(channels)?;
+        }
+
+        Ok(Backends {
+            listener: Box::new(listener),
+            speaker: Box::new(Speaker::new()?),
+            sniffer: Box::new(Sniffer::from(sniff::device_by_name(interface)?)),
+        })
     }
 }
 
 pub struct Interactor {
-    pub listener: Listener,
-    sniffer: Sniffer,
+    pub listener: Box<dyn Listen>,
+    loopback: Option<Box<dyn Listen>>,
+    echo_canceller: Option<EchoCanceller>,
+    level_control: Option<LevelControl>,
+    silence_padding: SilencePadding,
+    sniffer: Box<dyn Sniff>,
+    radio_sniffer: Option<Box<dyn Sniff>>,
     interface: String,
-    pub speaker: Speaker,
+    pub speaker: Box<dyn Speak>,
     voices: VecDeque<String>,
+    query_source: QuerySource,
     pub sensitivity: f32,
+    silence_threshold: SilenceThreshold,
+    recalibration: Option<Recalibration>,
     model: String,
     data_dir: PathBuf,
     assistant_mac: String,
+    combined_capture: bool,
+    mute_experiment: Option<MuteExperiment>,
+    rate_sweep: Option<RateSweep>,
+    quiescence_detector: Option<QuiescenceDetector>,
+    quiet_hours: Option<QuietHours>,
+    idle_capture: bool,
+    power_cycle: Option<PowerCycle>,
+    retry_policy: Option<RetryPolicy>,
+    interaction_timeout: Option<Duration>,
+    shuffle_queries: bool,
+    seeds: Seeds,
+    idle_gap: Option<IdleGap>,
+    replay: Option<Replay>,
+    thermal_monitor: Option<ThermalMonitor>,
+    watermark: Option<Watermark>,
+    prime_transcription: bool,
+    export_channels: bool,
+    lossless_exemplars: bool,
+    recognition_channel: Option<u8>,
+    vocabulary_prompt: Option<String>,
+    voice_profile: Option<VoiceProfile>,
+    min_voice_similarity: f32,
+    redactor: Option<Arc<Redactor>>,
+    event_log: Option<EventLog>,
+    database: Option<DatabaseConnection>,
+    shutdown: Option<Shutdown>,
 }
 
 impl Interactor {
@@ -86,17 +237,504 @@ impl Interactor {
         data_dir: PathBuf,
         assistant_mac: String,
     ) -> Result<Interactor, Error> {
-        Ok(Interactor {
-            listener: Listener::new()?,
-            sniffer: Sniffer::from(sniff::device_by_name(interface.as_str())?),
+        Ok(Self::with_backends(
+            Backends::system(&interface)?,
+            interface,
+            voices,
+            sensitivity,
+            model,
+            data_dir,
+            assistant_mac,
+        ))
+    }
+
+    /// Create an interactor that uses the given backends instead of the system hardware.
+    ///
+    /// # Arguments
+    ///
+    /// * `backends`: The listener, speaker and sniffer to use.
+    /// * `interface`: The name of the interface, which is stored with each session.
+    /// * `voices`: The voices to use for the speaker.
+    /// * `sensitivity`: The sensitivity of the listener.
+    /// * `model`: The model to use for the recogniser.
+    /// * `data_dir`: The path to the data directory.
+    /// * `assistant_mac`: The MAC address of the assistant.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::path::PathBuf;
+    /// # use varys::assistant::interactor::{Backends, Interactor};
+    /// # use varys_audio::listen::fake::FakeListener;
+    /// # use varys_audio::tts::fake::FakeSpeaker;
+    /// # use varys_network::sniff::fake::FakeSniffer;
+    /// let interactor = Interactor::with_backends(
+    ///     Backends {
+    ///         listener: Box::new(FakeListener::default()),
+    ///         speaker: Box::new(FakeSpeaker::new()),
+    ///         sniffer: Box::new(FakeSniffer::new()),
+    ///     },
+    ///     "fake0".to_string(),
+    ///     vec!["Ava".to_string()],
+    ///     0.01,
+    ///     "fake".to_string(),
+    ///     PathBuf::from("./data"),
+    ///     "00:00:00:00:00:00".to_string(),
+    /// );
+    /// ```
+    pub fn with_backends(
+        backends: Backends,
+        interface: String,
+        voices: Vec<String>,
+        sensitivity: f32,
+        model: String,
+        data_dir: PathBuf,
+        assistant_mac: String,
+    ) -> Interactor {
+        Interactor {
+            listener: backends.listener,
+            loopback: None,
+            echo_canceller: None,
+            level_control: None,
+            silence_padding: SilencePadding::default(),
+            sniffer: backends.sniffer,
+            radio_sniffer: None,
             interface,
-            speaker: Speaker::new()?,
+            speaker: backends.speaker,
             voices: voices.into(),
+            query_source: QuerySource::Synthesised,
             sensitivity,
+            silence_threshold: SilenceThreshold::Static(sensitivity),
+            recalibration: None,
             model,
             data_dir,
             assistant_mac,
-        })
+            combined_capture: false,
+            mute_experiment: None,
+            rate_sweep: None,
+            quiescence_detector: None,
+            quiet_hours: None,
+            idle_capture: false,
+            power_cycle: None,
+            retry_policy: None,
+            interaction_timeout: None,
+            shuffle_queries: true,
+            seeds: Seeds::Random,
+            idle_gap: None,
+            replay: None,
+            thermal_monitor: None,
+            watermark: None,
+            prime_transcription: false,
+            export_channels: false,
+            lossless_exemplars: false,
+            recognition_channel: None,
+            vocabulary_prompt: None,
+            voice_profile: None,
+            min_voice_similarity: DEFAULT_MIN_SIMILARITY,
+            redactor: None,
+            event_log: None,
+            database: None,
+            shutdown: None,
+        }
+    }
+
+    /// Set whether to capture the traffic of a whole session at once instead of starting a capture per interaction.
+    ///
+    /// The session capture is split into one capture per interaction at the end of the session, using the start times
+    /// of the interactions. This avoids the overhead of starting a capture for every interaction, which might miss the
+    /// first packets of a response.
+    ///
+    /// # Arguments
+    ///
+    /// * `combined_capture`: Whether to capture whole sessions at once.
+    pub fn set_combined_capture(&mut self, combined_capture: bool) {
+        self.combined_capture = combined_capture;
+    }
+
+    /// Set how the threshold that distinguishes silence from sound is determined.
+    ///
+    /// An adaptive threshold is recomputed from the ambient noise at the start of every session, replacing the
+    /// sensitivity. The threshold used is stored with each session.
+    ///
+    /// # Arguments
+    ///
+    /// * `silence_threshold`: How the threshold is determined.
+    pub fn set_silence_threshold(&mut self, silence_threshold: SilenceThreshold) {
+        if let SilenceThreshold::Static(threshold) = silence_threshold {
+            self.sensitivity = threshold;
+        }
+        self.silence_threshold = silence_threshold;
+    }
+
+    /// Set when to calibrate the sensitivity again during a session, so it follows changes in the ambient noise.
+    ///
+    /// An adaptive silence threshold is computed again from the ambient noise, a static one is replaced by a
+    /// calibrated one. The sensitivity used is stored with every interaction.
+    ///
+    /// # Arguments
+    ///
+    /// * `recalibration`: When to calibrate again, or `None` to keep the sensitivity for the whole session.
+    pub fn set_recalibration(&mut self, recalibration: Option<Recalibration>) {
+        self.recalibration = recalibration;
+    }
+
+    /// Set an experiment that alternates the microphone of the assistant between unmuted and muted.
+    ///
+    /// Each interaction stores whether the microphone was muted. A muted assistant is not expected to respond, so
+    /// interactions with a muted microphone are completed even if no response is heard before the recording times out.
+    ///
+    /// # Arguments
+    ///
+    /// * `mute_experiment`: The experiment to run, or `None` to keep the microphone unmuted.
+    pub fn set_mute_experiment(&mut self, mute_experiment: Option<MuteExperiment>) {
+        self.mute_experiment = mute_experiment;
+    }
+
+    /// Set an experiment that sweeps the speaking rate of synthesised queries through a list of rates.
+    ///
+    /// Each interaction stores the rate its query was spoken at.
+    ///
+    /// # Arguments
+    ///
+    /// * `rate_sweep`: The experiment to run, or `None` to always speak at the normal rate.
+    pub fn set_rate_sweep(&mut self, rate_sweep: Option<RateSweep>) {
+        self.rate_sweep = rate_sweep;
+    }
+
+    /// Set a detector for background downloads to the assistant, e.g. OS or firmware updates.
+    ///
+    /// Before each interaction, a sample of traffic is captured. While it shows a download, collection is paused and
+    /// the paused period is recorded as a session event.
+    ///
+    /// # Arguments
+    ///
+    /// * `quiescence_detector`: The detector to use, or `None` to never pause collection.
+    pub fn set_quiescence_detector(&mut self, quiescence_detector: Option<QuiescenceDetector>) {
+        self.quiescence_detector = quiescence_detector;
+    }
+
+    /// Set daily quiet hours in which no queries are spoken.
+    ///
+    /// A session that reaches its quiet hours pauses before the next interaction until they end. The paused period is
+    /// recorded as a session event.
+    ///
+    /// # Arguments
+    ///
+    /// * `quiet_hours`: The quiet hours in local time, or `None` to speak at any time.
+    pub fn set_quiet_hours(&mut self, quiet_hours: Option<QuietHours>) {
+        self.quiet_hours = quiet_hours;
+    }
+
+    /// Set whether to capture the idle traffic of the assistant while a session is paused for its quiet hours.
+    ///
+    /// The traffic of every pause is stored in its own file in the session directory, which is named in the session
+    /// event of the pause. With a combined capture, the idle traffic is always part of the session capture.
+    ///
+    /// # Arguments
+    ///
+    /// * `idle_capture`: Whether to capture traffic during quiet hours.
+    pub fn set_idle_capture(&mut self, idle_capture: bool) {
+        self.idle_capture = idle_capture;
+    }
+
+    /// Set when to power-cycle the assistant with a smart plug to recover it from hanging.
+    ///
+    /// Every power cycle is recorded as a session event.
+    ///
+    /// # Arguments
+    ///
+    /// * `power_cycle`: When to power-cycle the assistant, or `None` to never do it.
+    pub fn set_power_cycle(&mut self, power_cycle: Option<PowerCycle>) {
+        self.power_cycle = power_cycle;
+    }
+
+    /// Set when to ask queries again whose interaction failed.
+    ///
+    /// Every attempt is stored as its own interaction, numbered by its `attempt`.
+    ///
+    /// # Arguments
+    ///
+    /// * `retry_policy`: When to retry failed queries, or `None` to skip them.
+    pub fn set_retry_policy(&mut self, retry_policy: Option<RetryPolicy>) {
+        self.retry_policy = retry_policy;
+    }
+
+    /// Set the longest time a single interaction may take, from speaking the query to recording the response.
+    ///
+    /// The recording of the response is cut short to fit into the remaining time. An interaction that exceeds it is
+    /// stopped after its current step, with its capture written, and marked as timed out. It then counts as failed, so
+    /// it is retried according to the retry policy.
+    ///
+    /// # Arguments
+    ///
+    /// * `interaction_timeout`: The time budget of every interaction, or `None` to let interactions take as long as
+    ///   they need.
+    pub fn set_interaction_timeout(&mut self, interaction_timeout: Option<Duration>) {
+        self.interaction_timeout = interaction_timeout;
+    }
+
+    /// Set whether to ask the queries of every session in a new random order.
+    ///
+    /// Queries are shuffled by default, so their order does not correlate with the time they are asked at.
+    ///
+    /// # Arguments
+    ///
+    /// * `shuffle_queries`: Whether to shuffle the queries.
+    pub fn set_shuffle_queries(&mut self, shuffle_queries: bool) {
+        self.shuffle_queries = shuffle_queries;
+    }
+
+    /// Set where the seeds of the random choices of sessions come from, i.e. the order of the queries and the random
+    /// choices of every interaction like its idle time.
+    ///
+    /// Fresh random seeds are used by default. A fixed seed makes sessions that ask the same queries reproducible,
+    /// e.g. for dry runs with fake backends.
+    ///
+    /// # Arguments
+    ///
+    /// * `seeds`: Where the seeds come from.
+    pub fn set_seeds(&mut self, seeds: Seeds) {
+        self.seeds = seeds;
+    }
+
+    /// Set a randomised idle time to wait between the queries of a session, to produce more realistic traffic.
+    ///
+    /// The time waited before each interaction is stored with it as its `idle_gap_ms`. The turns of a conversation
+    /// follow each other without a gap.
+    ///
+    /// # Arguments
+    ///
+    /// * `idle_gap`: The range to draw the idle time from, or `None` to not wait between queries.
+    pub fn set_idle_gap(&mut self, idle_gap: Option<IdleGap>) {
+        self.idle_gap = idle_gap;
+    }
+
+    /// Set a collected session to replay, e.g. against a different assistant.
+    ///
+    /// The queries are asked in the order they are given. The first attempt of each query is asked with the seed it was
+    /// first asked with in the replayed session and preceded by the idle time it was preceded by there instead of a
+    /// randomised idle gap, so both sessions ask every query under the same conditions even if they retry different
+    /// queries. Retries draw fresh seeds.
+    ///
+    /// Sessions store the replayed session as their `paired_session_id`, so the two can be compared as a pair.
+    ///
+    /// # Arguments
+    ///
+    /// * `replay`: The session to replay, whose [`Replay::queries`] should be the queries of the sessions.
+    pub fn set_replay(&mut self, replay: Option<Replay>) {
+        if replay.is_some() {
+            self.shuffle_queries = false;
+        }
+        self.replay = replay;
+    }
+
+    /// Set a monitor that periodically checks the temperature and throttling state of the host during sessions.
+    ///
+    /// # Arguments
+    ///
+    /// * `thermal_monitor`: The monitor to use, or `None` to not check the host.
+    pub fn set_thermal_monitor(&mut self, thermal_monitor: Option<ThermalMonitor>) {
+        self.thermal_monitor = thermal_monitor;
+    }
+
+    /// Set a watermark that is mixed into every spoken query and searched for in its recording.
+    ///
+    /// Finding the watermark verifies that the query recording captured the actual playback of the query, and its
+    /// position in the recording is stored with each interaction to align the clocks of playback and recording.
+    ///
+    /// # Arguments
+    ///
+    /// * `watermark`: The watermark to use, or `None` to not watermark queries.
+    pub fn set_watermark(&mut self, watermark: Option<Watermark>) {
+        self.speaker.set_watermark(watermark.clone());
+        self.watermark = watermark;
+    }
+
+    /// Set where the audio of spoken queries comes from, or whether queries are typed instead.
+    ///
+    /// Playing queries recorded by humans instead of synthesising them shows whether synthetic voices bias the
+    /// traffic of the assistant. The recording played is stored with every interaction, and the watermark is mixed
+    /// into recordings as well. Typing queries instead of speaking them shows how the traffic of the text channel
+    /// differs from that of the voice channel, which is stored with every interaction.
+    ///
+    /// # Arguments
+    ///
+    /// * `query_source`: Where the audio of queries comes from.
+    pub fn set_query_source(&mut self, query_source: QuerySource) {
+        self.query_source = query_source;
+    }
+
+    /// Set whether to condition the transcription of each response on its query.
+    ///
+    /// This makes whisper more likely to spell names in the response the way they are written in the query.
+    ///
+    /// # Arguments
+    ///
+    /// * `prime_transcription`: Whether to condition transcriptions on the query.
+    pub fn set_prime_transcription(&mut self, prime_transcription: bool) {
+        self.prime_transcription = prime_transcription;
+    }
+
+    /// Set a listener that records the output of the speaker, e.g. from a loopback or monitor device, in parallel to
+    /// the microphone while a query is asked.
+    ///
+    /// The recording is stored with every interaction as its `query_loopback_file`, so it can be verified what exactly
+    /// was played to the assistant. Typed queries are not played, so nothing is recorded for them.
+    ///
+    /// # Arguments
+    ///
+    /// * `loopback`: The listener of the output of the speaker, or `None` to not record it.
+    pub fn set_loopback(&mut self, loopback: Option<Box<dyn Listen>>) {
+        self.loopback = loopback;
+    }
+
+    /// Set how to remove what the speaker plays from the recorded responses, so they only contain the voice of the
+    /// assistant.
+    ///
+    /// The output of the speaker is recorded with the listener set with [`Interactor::set_loopback`] while the
+    /// response is recorded, and its echo is removed from the response. Without a loopback listener, nothing is
+    /// removed.
+    ///
+    /// # Arguments
+    ///
+    /// * `echo_canceller`: The echo canceller to use, or `None` to keep the responses as they were recorded.
+    pub fn set_echo_canceller(&mut self, echo_canceller: Option<EchoCanceller>) {
+        self.echo_canceller = echo_canceller;
+    }
+
+    /// Set how to adjust the level of the recorded queries and responses, so recordings made at different microphone
+    /// gains are comparable.
+    ///
+    /// The peak and RMS levels of every recording are stored with its interaction before the level is adjusted.
+    ///
+    /// # Arguments
+    ///
+    /// * `level_control`: How to adjust the level, or `None` to keep the recordings at the level they were recorded.
+    pub fn set_level_control(&mut self, level_control: Option<LevelControl>) {
+        self.level_control = level_control;
+    }
+
+    /// Set how much silence to keep before and after the recorded responses when their silence is trimmed.
+    ///
+    /// Responses are trimmed before they are stored, so their files and durations do not include the silence that was
+    /// recorded while waiting for the assistant to respond or to finish.
+    ///
+    /// # Arguments
+    ///
+    /// * `silence_padding`: The silence to keep before and after the response.
+    pub fn set_silence_padding(&mut self, silence_padding: SilencePadding) {
+        self.silence_padding = silence_padding;
+    }
+
+    /// Set a capture that runs next to the network capture of every interaction, e.g. a software defined radio that
+    /// observes the Zigbee or Thread frames a smart home device sends in response to a query.
+    ///
+    /// The capture is always started and stopped with the interaction, even if the network traffic is captured for the
+    /// whole session, and its file is stored with the interaction, see [`varys_network::sniff::radio`].
+    ///
+    /// # Arguments
+    ///
+    /// * `radio_sniffer`: The capture to run, or `None` to only capture network traffic.
+    pub fn set_radio_sniffer(&mut self, radio_sniffer: Option<Box<dyn Sniff>>) {
+        self.radio_sniffer = radio_sniffer;
+    }
+
+    /// Set whether to also store every channel of the recorded queries and responses in its own mono file, e.g. to
+    /// separate a microphone pointed at the assistant from one pointed at the speaker.
+    ///
+    /// The files are named like the recording with `-ch<index>` appended, see
+    /// [`channel_path`](varys_audio::file::channel_path). Mono recordings are not split.
+    ///
+    /// # Arguments
+    ///
+    /// * `export_channels`: Whether to store every channel in its own file.
+    pub fn set_export_channels(&mut self, export_channels: bool) {
+        self.export_channels = export_channels;
+    }
+
+    /// Set whether to also store a lossless FLAC copy of the recorded responses to exemplar queries, see
+    /// [`Query::exemplar`], e.g. for the artefact release of a paper.
+    ///
+    /// The copy is stored next to the compressed response with the extension `.flac`. It is the response as it was
+    /// recorded, at the sample rate of the stored recordings, before its echo is cancelled, its silence trimmed or its
+    /// level adjusted.
+    ///
+    /// # Arguments
+    ///
+    /// * `lossless_exemplars`: Whether to store the lossless copies.
+    pub fn set_lossless_exemplars(&mut self, lossless_exemplars: bool) {
+        self.lossless_exemplars = lossless_exemplars;
+    }
+
+    /// Set which channel of the recorded responses is transcribed.
+    ///
+    /// # Arguments
+    ///
+    /// * `recognition_channel`: The index of the channel, starting at `0`, or `None` to transcribe the average of all
+    ///   channels.
+    pub fn set_recognition_channel(&mut self, recognition_channel: Option<u8>) {
+        self.recognition_channel = recognition_channel;
+    }
+
+    /// Set a prompt listing the names that responses are expected to contain, which conditions the transcription of
+    /// every response.
+    ///
+    /// This makes whisper more likely to spell names the way they are written in the prompt, see
+    /// [`vocabulary_prompt`](crate::query::vocabulary_prompt).
+    ///
+    /// # Arguments
+    ///
+    /// * `vocabulary_prompt`: The prompt to use, or `None` to not condition transcriptions on a vocabulary.
+    pub fn set_vocabulary_prompt(&mut self, vocabulary_prompt: Option<String>) {
+        self.vocabulary_prompt = vocabulary_prompt;
+    }
+
+    /// Set what to remove from the transcripts of responses before they are stored, e.g. names and phone numbers of
+    /// people in the household.
+    ///
+    /// # Arguments
+    ///
+    /// * `redactor`: The redactor to use, or `None` to store transcripts as they are.
+    pub fn set_redactor(&mut self, redactor: Option<Redactor>) {
+        self.redactor = redactor.map(Arc::new);
+    }
+
+    /// Set the voice profile of the assistant, which every response is compared to.
+    ///
+    /// Responses that do not sound like the assistant, e.g. because a person in the room talked over it, are flagged
+    /// as outliers and excluded from datasets.
+    ///
+    /// # Arguments
+    ///
+    /// * `voice_profile`: The voice profile of the assistant, or `None` to not check responses.
+    /// * `min_similarity`: The similarity to the profile below which a response is flagged.
+    pub fn set_voice_profile(&mut self, voice_profile: Option<VoiceProfile>, min_similarity: f32) {
+        self.voice_profile = voice_profile;
+        self.min_voice_similarity = min_similarity;
+    }
+
+    /// Set the database connection to use instead of connecting at the start of every session.
+    ///
+    /// This allows several interactors to share one connection pool.
+    ///
+    /// # Arguments
+    ///
+    /// * `database`: The connection to use, or `None` to connect for every session.
+    pub fn set_database(&mut self, database: Option<DatabaseConnection>) {
+        self.database = database;
+    }
+
+    /// Set the shutdown that stops the current session early, e.g. when Ctrl-C is pressed.
+    ///
+    /// Once it is requested, the current interaction is aborted after its current step, with its capture and
+    /// recordings written, and marked as aborted. The session is then finalised without asking the remaining queries,
+    /// but not completed, so it can be continued with [`Interactor::resume_session`].
+    ///
+    /// # Arguments
+    ///
+    /// * `shutdown`: The shutdown to watch, or `None` to always ask all queries.
+    pub fn set_shutdown(&mut self, shutdown: Option<Shutdown>) {
+        self.shutdown = shutdown;
     }
 
     /// Set up a database connection and begin a new session of interactions with a list of queries.
@@ -107,8 +745,11 @@ impl Interactor {
     /// # Arguments
     ///
     /// * `queries`: The queries to ask during this session.
+    /// * `assistant`: The voice assistant to interact with.
+    /// * `transcriber_handle`: The handle used to transcribe responses. If this is `None`, interactions are completed
+    ///   without a transcribed response.
     ///
-    /// Returns an [`Interactor`] with which a new session can be begun.
+    /// Returns the id of the session.
     ///
     /// # Examples
     ///
@@ -134,10 +775,18 @@ impl Interactor {
     ///     Query {
     ///         text: "How are you?".to_string(),
     ///         category: "greeting".to_string(),
+    ///         recording_timeout: None,
+    ///         follow_ups: Vec::new(),
+    ///         locale: None,
+    ///         exemplar: false,
     ///     },
     ///     Query {
     ///         text: "What is your name?".to_string(),
     ///         category: "greeting".to_string(),
+    ///         recording_timeout: None,
+    ///         follow_ups: Vec::new(),
+    ///         locale: None,
+    ///         exemplar: false,
     ///     },
     /// ];
     /// # tokio::runtime::Builder::new_current_thread()
@@ -146,75 +795,938 @@ impl Interactor {
     /// #     .unwrap()
     /// #     .block_on(async {
     /// interactor
-    ///     .start(&mut queries, assistant::from("Siri").as_ref(), transcriber_handle)
+    ///     .start(&mut queries, assistant::from("Siri").as_ref(), Some(transcriber_handle))
     ///     .await
     ///     .unwrap();
     /// #     })
     /// ```
     pub async fn start(
         &mut self,
-        queries: &mut Vec<Query>,
+        queries: &mut [Query],
         assistant: &dyn VoiceAssistant,
-        mut transcriber_handle: TranscriberHandle<TranscribeInteraction>,
-    ) -> Result<(), Error> {
+        transcriber_handle: Option<TranscriberHandle<TranscribeInteraction>>,
+    ) -> Result<i32, Error> {
         let voice = self.next_voice()?;
-        let (mut session, database_pool) = self.create_session(voice.clone()).await?;
-        self.listener.recording_timeout = Some(assistant.recording_timeout());
-        queries.shuffle(&mut rand::thread_rng());
-
-        info!("Starting {}", session);
+        let (session, database_pool) = self.create_session(voice.clone(), queries).await?;
+        let session_id = session.id;
+        let shuffle_seed = self.shuffle_queries.then(|| self.seeds.next_seed());
 
-        for query in queries {
-            if let Err(error) = monitoring::ping(&format!("Interaction started: {query}")).await {
-                warn!("Failed to notify monitoring about interaction: {}", error);
-            }
+        self.run_session(
+            session,
+            database_pool,
+            &voice,
+            SessionQueries {
+                queries,
+                shuffle_seed,
+                completed: HashMap::new(),
+            },
+            assistant,
+            transcriber_handle,
+        )
+        .await?;
 
-            match self
-                .interaction(
-                    query,
-                    &session,
-                    &database_pool,
-                    assistant.silence_after_talking(),
-                )
+        Ok(session_id)
+    }
+
+    /// Continue a session that was interrupted, e.g. because varys crashed or was killed.
+    ///
+    /// The session is continued with its voice and silence threshold. Queries that already have completed
+    /// interactions in the session are skipped, all others are asked and stored in the same session directory. The
+    /// queries are shuffled with the same seed as before, so every query keeps its position and the seeds of a replay.
+    ///
+    /// # Arguments
+    ///
+    /// * `session_id`: The id of the session to continue.
+    /// * `queries`: The queries the session was started with.
+    /// * `assistant`: The voice assistant to interact with.
+    /// * `transcriber_handle`: The handle used to transcribe responses. If this is `None`, interactions are completed
+    ///   without a transcribed response.
+    pub async fn resume_session(
+        &mut self,
+        session_id: i32,
+        queries: &[Query],
+        assistant: &dyn VoiceAssistant,
+        transcriber_handle: Option<TranscriberHandle<TranscribeInteraction>>,
+    ) -> Result<(), Error> {
+        let database_connection = self.connect().await?;
+        let mut session = Session::get(&database_connection, session_id)
+            .await?
+            .ok_or(Error::SessionNotFound(session_id))?;
+        if session.ended.is_some() {
+            return Err(Error::SessionAlreadyCompleted(session_id));
+        }
+
+        let voice = match session.config(&database_connection).await? {
+            Some(config) => config.voice,
+            None => self.next_voice()?,
+        };
+        self.speaker.set_voice(&voice)?;
+        self.sensitivity = match session.silence_threshold {
+            Some(sensitivity) => sensitivity,
+            None => self.listener.silence_threshold(self.silence_threshold)?,
+        };
+
+        session.data_dir = Some(
+            file::create_session_dir(&self.data_dir, session.id)?
+                .to_string_lossy()
+                .to_string(),
+        );
+        session.silence_threshold = Some(self.sensitivity);
+        session.update(&database_connection).await?;
+
+        let interactions = session.interactions(&database_connection).await?;
+        // the queries are asked in the same order as before, so every query keeps its position
+        let shuffle_seed = match interactions
+            .iter()
+            .filter_map(|interaction| interaction.conditions.as_deref())
+            .find_map(|conditions| serde_json::from_str::<Conditions>(conditions).ok())
+        {
+            Some(conditions) => conditions.shuffle_seed,
+            None => self.shuffle_queries.then(|| self.seeds.next_seed()),
+        };
+        let mut completed: HashMap<String, usize> = HashMap::new();
+        for interaction in interactions.into_iter().filter(Interaction::is_complete) {
+            *completed.entry(interaction.query).or_default() += 1;
+        }
+        let remaining = {
+            let mut completed = completed.clone();
+            queries
+                .iter()
+                .filter(|query| !take_completed(&mut completed, query))
+                .count()
+        };
+
+        info!(
+            "Resuming {session} with {remaining} of {} queries remaining",
+            queries.len()
+        );
+        self.event_log = EventLog::open(&file::session_log_path(&self.data_dir, session.id))
+            .map_err(|error| warn!("Could not open the event log of {session}: {error}"))
+            .ok();
+        self.log_event(
+            "session_resumed",
+            json!({
+                "session_id": session.id,
+                "completed": queries.len() - remaining,
+                "remaining": remaining,
+            }),
+        );
+
+        // the traffic captured before the interruption would be overwritten by the new session capture
+        let session_capture_path = file::session_capture_path(&self.data_dir, session.id);
+        if self.combined_capture && session_capture_path.exists() {
+            if let Err(error) = self
+                .split_session_capture(&session, &database_connection, session.started)
                 .await
             {
-                Ok((interaction, audio)) => {
-                    transcriber_handle = match transcriber_handle {
-                        TranscriberHandle::Sender(sender) => sender,
-                        TranscriberHandle::Receiver(receiver) => {
-                            Self::complete_interaction(receiver, &database_pool).await?
+                warn!("Could not split the capture from before the interruption: {error}");
+            }
+            let _ = std::fs::remove_file(index::index_path(&session_capture_path));
+        }
+
+        self.run_session(
+            session,
+            database_connection,
+            &voice,
+            SessionQueries {
+                queries: &mut queries.to_vec(),
+                shuffle_seed,
+                completed,
+            },
+            assistant,
+            transcriber_handle,
+        )
+        .await
+    }
+
+    /// Ask queries in a session that was already created.
+    ///
+    /// # Arguments
+    ///
+    /// * `session`: The session to ask the queries in.
+    /// * `database_pool`: The connection to use.
+    /// * `voice`: The voice the queries are asked with.
+    /// * `queries`: The queries of the session and which of them were already asked.
+    /// * `assistant`: The voice assistant to interact with.
+    /// * `transcriber_handle`: The handle used to transcribe responses.
+    async fn run_session(
+        &mut self,
+        mut session: Session,
+        database_pool: DatabaseConnection,
+        voice: &str,
+        queries: SessionQueries<'_>,
+        assistant: &dyn VoiceAssistant,
+        mut transcriber_handle: Option<TranscriberHandle<TranscribeInteraction>>,
+    ) -> Result<(), Error> {
+        let SessionQueries {
+            queries,
+            shuffle_seed,
+            mut completed,
+        } = queries;
+        crash::set_session(Some(session.id));
+        self.event_log = EventLog::open(&file::session_log_path(&self.data_dir, session.id))
+            .map_err(|error| warn!("Could not open the event log of {session}: {error}"))
+            .ok();
+        self.log_event(
+            "session_started",
+            json!({
+                "session_id": session.id,
+                "version": session.version,
+                "assistant": assistant.name(),
+                "assistant_mac": self.assistant_mac,
+                "interface": self.interface,
+                "voice": voice,
+                "model": self.model,
+                "query_source": self.query_source.to_string(),
+                "sensitivity": self.sensitivity,
+                "silence_threshold": session.silence_threshold,
+                "queries": queries.len(),
+                "shuffle_queries": self.shuffle_queries,
+                "shuffle_seed": shuffle_seed,
+                "rate_sweep": self.rate_sweep.as_ref().map(|rate_sweep| json!({
+                    "rates": rate_sweep.rates,
+                    "block_size": rate_sweep.block_size,
+                })),
+                "idle_gap_ms": self.idle_gap.as_ref().map(|idle_gap| {
+                    [idle_gap.min.as_millis() as u64, idle_gap.max.as_millis() as u64]
+                }),
+                "quiet_hours": self.quiet_hours.map(|quiet_hours| quiet_hours.to_string()),
+                "idle_capture": self.idle_capture,
+                "silence_padding_ms": [
+                    self.silence_padding.leading.as_millis() as u64,
+                    self.silence_padding.trailing.as_millis() as u64,
+                ],
+                "paired_session_id": session.paired_session_id,
+            }),
+        );
+        self.listener
+            .set_recording_timeout(Some(assistant.recording_timeout()));
+        if let Some(shuffle_seed) = shuffle_seed {
+            queries.shuffle(&mut conditions::rng(shuffle_seed));
+        }
+
+        info!("Starting {}", session);
+
+        // stops checking the host when dropped at the end of the session
+        let _thermal_monitor_handle = self
+            .thermal_monitor
+            .as_ref()
+            .map(|monitor| monitor.start(session.id, database_pool.clone()));
+
+        let capture_started = Utc::now();
+        let session_capture = if self.combined_capture {
+            Some(
+                self.sniffer
+                    .start(&file::session_capture_path(&self.data_dir, session.id))?,
+            )
+        } else {
+            None
+        };
+
+        // the state to restore at the end of the session, even if asking the queries fails
+        let mut mic_muted = false;
+        let mut speaking_rate = 1.0;
+        let mut interrupted = false;
+        let result = async {
+            if self
+                .power_cycle
+                .as_ref()
+                .is_some_and(|power_cycle| power_cycle.every_session)
+            {
+                self.power_cycle_assistant(&session, &database_pool, "Before the session")
+                    .await?;
+            }
+
+            let mut failures = 0;
+            // interactions and consecutive failed interactions since the sensitivity was last calibrated
+            let mut calibration_interactions = 0;
+            let mut calibration_failures = 0;
+
+            // the queries with their position, attempt and when they may be asked, failed queries are added again
+            let mut pending: VecDeque<(usize, &Query, u32, Instant)> = queries
+                .iter()
+                .enumerate()
+                .filter(|(_, query)| !take_completed(&mut completed, query))
+                .map(|(index, query)| (index, query, 1, Instant::now()))
+                .collect();
+
+            let mut first = true;
+            let mut current_voice = voice.to_string();
+            while let Some((index, query, attempt, retry_at)) = pending.pop_front() {
+                self.sleep(retry_at.saturating_duration_since(Instant::now()))
+                    .await;
+
+                // every random choice of the interaction is drawn from its own seed, so it can be reproduced
+                let seed = match self
+                    .replay
+                    .as_ref()
+                    .and_then(|replay| replay.seed(index, attempt))
+                {
+                    Some(seed) => seed,
+                    None => self.seeds.next_seed(),
+                };
+                let mut rng = conditions::rng(seed);
+
+                // the first query of a session is asked right away
+                let mut idle_gap = match &self.replay {
+                    Some(replay) => replay.idle_gap(index, attempt),
+                    None => self
+                        .idle_gap
+                        .as_ref()
+                        .filter(|_| !first)
+                        .map(|idle_gap| idle_gap.sample(&mut rng)),
+                };
+                first = false;
+                if let Some(idle_gap) = idle_gap {
+                    info!("Idling for {:.1}s", idle_gap.as_secs_f32());
+                    self.sleep(idle_gap).await;
+                }
+
+                self.wait_for_quiet_hours(&session, &database_pool).await?;
+                self.wait_for_quiescence(&session, &database_pool).await?;
+
+                if self.is_shutting_down() {
+                    info!(
+                        "Stopping {session} early, {} queries were not asked",
+                        pending.len() + 1
+                    );
+                    self.log_event(
+                        "session_interrupted",
+                        json!({ "session_id": session.id, "remaining": pending.len() + 1 }),
+                    );
+                    interrupted = true;
+                    break;
+                }
+
+                if self.recalibration.as_ref().is_some_and(|recalibration| {
+                    recalibration.is_due(calibration_interactions, calibration_failures)
+                }) {
+                    self.recalibrate(calibration_interactions, calibration_failures)?;
+                    calibration_interactions = 0;
+                    calibration_failures = 0;
+                }
+
+                if let Some(experiment) = &self.mute_experiment {
+                    if experiment.is_muted(index) != mic_muted {
+                        mic_muted = !mic_muted;
+                        experiment.control.set_muted(mic_muted).await?;
+                    }
+                }
+
+                if let Some(rate_sweep) = &self.rate_sweep {
+                    if rate_sweep.rate(index) != speaking_rate {
+                        speaking_rate = rate_sweep.rate(index);
+                        self.speaker.set_rate(speaking_rate)?;
+                    }
+                }
+
+                if let Err(error) = monitoring::ping(&format!("Interaction started: {query}")).await
+                {
+                    warn!("Failed to notify monitoring about interaction: {}", error);
+                }
+
+                // the turns of a conversation are linked to each other and asked without stopping the assistant
+                let mut conversation = if query.is_conversation() {
+                    let conversation = Conversation::create(&database_pool, session.id).await?;
+                    self.log_event(
+                        "conversation_started",
+                        json!({
+                            "conversation_id": conversation.id,
+                            "turns": query.follow_ups.len() + 1,
+                        }),
+                    );
+                    Some(conversation)
+                } else {
+                    None
+                };
+                let mut failed = false;
+                let mut aborted = false;
+                let query_voice = self.query_voice(query, voice);
+                if query_voice != current_voice {
+                    self.speaker.set_voice(&query_voice)?;
+                    current_voice = query_voice.clone();
+                }
+                let conditions = Conditions {
+                    seed,
+                    shuffle_seed,
+                    position: index,
+                    queries: queries.len(),
+                    attempt,
+                    voice: query_voice.clone(),
+                    locale: query.locale.clone(),
+                    exemplar: query.exemplar,
+                    query_source: self.query_source.to_string(),
+                    watermarked: self.watermark.is_some(),
+                    idle_gap_ms: idle_gap.map(|idle_gap| idle_gap.as_millis() as u64),
+                    mic_muted,
+                    speaking_rate,
+                    sensitivity: self.sensitivity,
+                    silence_threshold: self.silence_threshold.to_string(),
+                };
+
+                for (turn, query) in query.turns().iter().enumerate() {
+                    let result = self
+                        .interaction(
+                            query,
+                            &session,
+                            &database_pool,
+                            assistant,
+                            // the category of the query can allow longer responses than the assistant usually gives
+                            query
+                                .recording_timeout
+                                .unwrap_or(assistant.recording_timeout()),
+                            mic_muted,
+                            conversation
+                                .as_ref()
+                                .map(|conversation| (conversation, turn as i32)),
+                            attempt,
+                            idle_gap.take(),
+                            &conditions,
+                        )
+                        .await;
+                    calibration_interactions += 1;
+                    self.listener
+                        .set_recording_timeout(Some(assistant.recording_timeout()));
+
+                    // the assistant was triggered if it said or sent anything, a muted microphone prevents it
+                    let trigger = match &result {
+                        _ if mic_muted => None,
+                        Ok((interaction, _)) => Some((
+                            Some(interaction.id),
+                            interaction.response_latency_ms.is_some()
+                                || interaction.response_network_latency_ms.is_some(),
+                        )),
+                        Err(Error::AudioError(varys_audio::error::Error::RecordingTimeout)) => {
+                            Some((None, false))
+                        }
+                        Err(_) => None,
+                    };
+                    if let Some((interaction_id, triggered)) = trigger {
+                        WakeWordTrigger::create(
+                            &database_pool,
+                            session.id,
+                            interaction_id,
+                            &assistant.name(),
+                            &query_voice,
+                            triggered,
+                        )
+                        .await?;
+                    }
+
+                    match result {
+                        Ok((mut interaction, mut audio)) => {
+                            failures = 0;
+                            calibration_failures = 0;
+
+                            match transcriber_handle.take() {
+                                Some(handle) => {
+                                    let sender = match handle {
+                                        TranscriberHandle::Sender(sender) => sender,
+                                        TranscriberHandle::Receiver(receiver) => {
+                                            self.complete_interaction(receiver, &database_pool)
+                                                .await?
+                                        }
+                                    };
+
+                                    // keep track of the transcription in case the process stops before it is done
+                                    TranscriptionJob::create(&database_pool, interaction.id)
+                                        .await?;
+                                    self.log_event(
+                                        "transcription_queued",
+                                        json!({ "interaction_id": interaction.id }),
+                                    );
+                                    let initial_prompt = self.initial_prompt(query);
+                                    audio.select_channel(self.recognition_channel)?;
+                                    transcriber_handle = Some(
+                                        sender
+                                            .transcribe(
+                                                TranscribeInteraction {
+                                                    interaction,
+                                                    initial_prompt,
+                                                    redactor: self.redactor.clone(),
+                                                    language: query.language(),
+                                                },
+                                                audio,
+                                            )
+                                            .into(),
+                                    );
+                                }
+                                None => {
+                                    self.log_event(
+                                        "interaction_completed",
+                                        json!({ "interaction_id": interaction.id }),
+                                    );
+                                    interaction.complete(&database_pool).await?;
+                                }
+                            }
+                        }
+                        Err(Error::InteractionAborted) => {
+                            aborted = true;
+                            break;
+                        }
+                        Err(error) => {
+                            error!("An interaction did not complete successfully: {error}");
+                            self.log_event(
+                                "interaction_failed",
+                                json!({ "query": query.text, "error": error.to_string() }),
+                            );
+                            failures += 1;
+                            calibration_failures += 1;
+
+                            if self
+                                .power_cycle
+                                .as_ref()
+                                .is_some_and(|power_cycle| power_cycle.is_due(failures))
+                            {
+                                self.power_cycle_assistant(
+                                    &session,
+                                    &database_pool,
+                                    &format!("After {failures} failed interactions"),
+                                )
+                                .await?;
+                                failures = 0;
+                            } else if matches!(
+                                error,
+                                Error::AudioError(varys_audio::error::Error::RecordingTimeout)
+                                    | Error::InteractionTimedOut(_)
+                            ) {
+                                assistant.reset_assistant(self)?;
+                            }
+
+                            // the follow-ups rely on the context of the failed turn
+                            failed = true;
+                            break;
                         }
                     }
-                    .transcribe(interaction.into(), audio)
-                    .into();
                 }
-                Err(error) => {
-                    error!("An interaction did not complete successfully: {error}");
 
-                    if let Error::AudioError(varys_audio::error::Error::RecordingTimeout) = error {
-                        assistant.reset_assistant(self)?;
+                if aborted {
+                    info!(
+                        "Stopping {session} early, {} queries were not asked",
+                        pending.len()
+                    );
+                    self.log_event(
+                        "session_interrupted",
+                        json!({ "session_id": session.id, "remaining": pending.len() }),
+                    );
+                    assistant.stop_assistant(self)?;
+                    interrupted = true;
+                    break;
+                }
+
+                if let Some(retry_policy) = self
+                    .retry_policy
+                    .as_ref()
+                    .filter(|retry_policy| failed && retry_policy.should_retry(attempt))
+                {
+                    let delay = retry_policy.delay(attempt);
+                    info!(
+                        "Retrying \"{query}\" in {}s (attempt {} of {})",
+                        delay.as_secs(),
+                        attempt + 1,
+                        retry_policy.max_attempts
+                    );
+                    self.log_event(
+                        "retry_scheduled",
+                        json!({
+                            "query": query.text,
+                            "attempt": attempt + 1,
+                            "delay_ms": delay.as_millis() as u64,
+                            "requeued": retry_policy.requeue,
+                        }),
+                    );
+
+                    let retry = (index, query, attempt + 1, Instant::now() + delay);
+                    if retry_policy.requeue {
+                        pending.push_back(retry);
+                    } else {
+                        pending.push_front(retry);
                     }
+                } else if failed {
+                    warn!("Skipping \"{query}\" after {attempt} failed attempts");
+                }
+
+                // conversations with a failed turn stay incomplete like the failed interaction
+                if let Some(conversation) = conversation.as_mut().filter(|_| !failed) {
+                    self.log_event(
+                        "conversation_completed",
+                        json!({ "conversation_id": conversation.id }),
+                    );
+                    conversation.complete(&database_pool).await?;
                 }
+
+                assistant.stop_assistant(self)?;
             }
 
-            assistant.stop_assistant(self)?;
+            Ok::<_, Error>(())
+        }
+        .await;
+
+        // restore the assistant and the speaker and keep what was captured, even if the session failed
+        let mut finalised = Vec::new();
+        if let Some(experiment) = self.mute_experiment.as_ref().filter(|_| mic_muted) {
+            finalised.push(experiment.control.set_muted(false).await);
+        }
+        if speaking_rate != 1.0 {
+            finalised.push(self.speaker.set_rate(1.0).map_err(Error::from));
         }
 
         // complete the last interaction and stop the transcriber
-        match transcriber_handle {
-            TranscriberHandle::Sender(sender) => sender,
-            TranscriberHandle::Receiver(receiver) => {
-                Self::complete_interaction(receiver, &database_pool).await?
+        if let Some(handle) = transcriber_handle {
+            finalised.push(
+                match handle {
+                    TranscriberHandle::Sender(sender) => Ok(sender),
+                    TranscriberHandle::Receiver(receiver) => {
+                        self.complete_interaction(receiver, &database_pool).await
+                    }
+                }
+                .map(TranscriberSender::stop),
+            );
+        }
+
+        if let Some(session_capture) = session_capture {
+            finalised.push(match session_capture.stop() {
+                Ok(stats) => {
+                    info!("{stats}");
+                    self.split_session_capture(&session, &database_pool, capture_started)
+                        .await
+                }
+                Err(error) => Err(error.into()),
+            });
+        }
+
+        crash::set_session(None);
+        let finalised: Result<(), Error> = finalised.into_iter().collect();
+        if let (Err(_), Err(error)) = (&result, &finalised) {
+            error!("Could not finalise {session}: {error}");
+        }
+        let result = result.and(finalised);
+
+        // an interrupted session stays open, so it can be resumed
+        let completed = result.is_ok() && !interrupted;
+        if completed {
+            self.log_event("session_completed", json!({ "session_id": session.id }));
+        }
+        self.event_log = None;
+        result?;
+        if completed {
+            session.complete(&database_pool).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Whether the current session should stop early.
+    fn is_shutting_down(&self) -> bool {
+        self.shutdown
+            .as_ref()
+            .is_some_and(|shutdown| shutdown.is_requested())
+    }
+
+    /// Get the reason to stop the current interaction before its next step, if varys is shutting down or the time
+    /// budget of the interaction is used up.
+    ///
+    /// # Arguments
+    ///
+    /// * `deadline`: When the time budget of the interaction is used up.
+    fn interruption(&self, deadline: Option<Instant>) -> Option<Error> {
+        if self.is_shutting_down() {
+            Some(Error::InteractionAborted)
+        } else if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            Some(Error::InteractionTimedOut(
+                self.interaction_timeout.unwrap_or_default().as_secs(),
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// Sleep for a duration, waking up early if the session should stop.
+    async fn sleep(&self, duration: Duration) {
+        match &self.shutdown {
+            Some(shutdown) => shutdown.sleep(duration).await,
+            None => tokio::time::sleep(duration).await,
+        }
+    }
+
+    /// Write an event to the log of the current session.
+    ///
+    /// Failing to write the event only raises a warning, so the session can continue.
+    ///
+    /// # Arguments
+    ///
+    /// * `event`: The kind of the event.
+    /// * `details`: An object with the details of the event.
+    fn log_event(&self, event: &str, details: Value) {
+        if let Some(event_log) = &self.event_log {
+            if let Err(error) = event_log.write(event, details) {
+                warn!("Could not write {event} to the event log: {error}");
+            }
+        }
+    }
+
+    /// Store every channel of a recording in its own file next to it, if channels are exported and the recording has
+    /// several of them.
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: The path of the recording.
+    /// * `audio`: The recorded audio.
+    fn write_channels(&self, path: &Path, audio: &AudioData) -> Result<(), Error> {
+        if self.export_channels && audio.channels > 1 {
+            varys_audio::file::write_channels(path, audio)?;
+        }
+
+        Ok(())
+    }
+
+    /// Get the prompt to condition the transcription of the response to a query on, made of the vocabulary prompt
+    /// and the query itself, if they are enabled.
+    fn initial_prompt(&self, query: &Query) -> Option<String> {
+        let query_prompt = self.prime_transcription.then(|| query.text.clone());
+
+        match (&self.vocabulary_prompt, query_prompt) {
+            (Some(vocabulary_prompt), Some(query_prompt)) => {
+                Some(format!("{vocabulary_prompt} {query_prompt}"))
+            }
+            (vocabulary_prompt, query_prompt) => query_prompt.or(vocabulary_prompt.clone()),
+        }
+    }
+
+    /// Choose the voice to ask a query with, which speaks the locale of the query.
+    ///
+    /// The voice of the session is kept if it speaks the locale or the query has none. Otherwise, the voices of the
+    /// interactor are preferred over the other voices of the speaker, and voices of the same locale over voices of
+    /// the same language.
+    ///
+    /// # Arguments
+    ///
+    /// * `query`: The query to ask.
+    /// * `session_voice`: The voice of the session.
+    fn query_voice(&self, query: &Query, session_voice: &str) -> String {
+        let Some(locale) = &query.locale else {
+            return session_voice.to_string();
+        };
+        let normalise = |locale: &str| locale.replace('_', "-").to_lowercase();
+        let locale = normalise(locale);
+        let language = query::language(&locale);
+        let voices: Vec<(String, String)> = iter::once(session_voice.to_string())
+            .chain(self.voices.iter().cloned())
+            .chain(self.speaker.voices())
+            .filter_map(|voice| {
+                let voice_locale = normalise(&self.speaker.voice_locale(&voice)?);
+                Some((voice, voice_locale))
+            })
+            .collect();
+
+        voices
+            .iter()
+            .find(|(_, voice_locale)| *voice_locale == locale)
+            .or_else(|| {
+                voices
+                    .iter()
+                    .find(|(_, voice_locale)| query::language(voice_locale) == language)
+            })
+            .map(|(voice, _)| voice.clone())
+            .unwrap_or_else(|| {
+                warn!("No voice speaks {locale}, asking {query} with {session_voice}");
+                session_voice.to_string()
+            })
+    }
+
+    /// Wait until the quiet hours are over, capturing the idle traffic of the assistant in the meantime if enabled.
+    ///
+    /// The paused period is recorded as a session event.
+    async fn wait_for_quiet_hours(
+        &self,
+        session: &Session,
+        connection: &DatabaseConnection,
+    ) -> Result<(), Error> {
+        let Some(quiet_hours) = &self.quiet_hours else {
+            return Ok(());
+        };
+        let remaining = quiet_hours.remaining(Local::now().time());
+        if remaining.is_zero() {
+            return Ok(());
+        }
+
+        info!(
+            "Pausing for the quiet hours {quiet_hours}, resuming in {:.0} minutes...",
+            remaining.as_secs_f32() / 60.0
+        );
+
+        // the combined capture of the session already contains the idle traffic
+        let idle_capture = if self.idle_capture && !self.combined_capture {
+            let capture_path = file::session_path(&self.data_dir, session.id).join(format!(
+                "idle-{}.pcap",
+                Local::now().format("%Y%m%d-%H%M%S")
+            ));
+            Some((self.sniffer.start(&capture_path)?, capture_path))
+        } else {
+            None
+        };
+        let idle_file = idle_capture
+            .as_ref()
+            .map(|(_, capture_path)| file_name_or_full(capture_path));
+
+        self.log_event(
+            "quiet_hours_started",
+            json!({ "quiet_hours": quiet_hours.to_string(), "idle_file": idle_file }),
+        );
+        let mut event = session
+            .add_event(
+                connection,
+                QUIET_HOURS_EVENT,
+                &format!("Quiet hours {quiet_hours}"),
+            )
+            .await?;
+
+        self.sleep(remaining).await;
+
+        if let Some((instance, _)) = idle_capture {
+            instance.stop()?;
+        }
+        if let Some(idle_file) = &idle_file {
+            event.detail = format!("Quiet hours {quiet_hours}, idle traffic in {idle_file}");
+        }
+        event.end(connection).await?;
+        self.log_event("quiet_hours_finished", json!({ "idle_file": idle_file }));
+
+        info!("The quiet hours are over, resuming collection");
+
+        Ok(())
+    }
+
+    /// Wait until the assistant is not downloading anything in the background anymore.
+    ///
+    /// If a download is detected, it is recorded as a session event spanning the whole paused period.
+    async fn wait_for_quiescence(
+        &self,
+        session: &Session,
+        connection: &DatabaseConnection,
+    ) -> Result<(), Error> {
+        let Some(detector) = &self.quiescence_detector else {
+            return Ok(());
+        };
+        let assistant_mac: MacAddress = self.assistant_mac.parse()?;
+        let sample_path =
+            file::session_path(&self.data_dir, session.id).join("traffic-sample.pcap");
+
+        let mut rate = detector.sample(self.sniffer.as_ref(), &sample_path, &assistant_mac)?;
+        if !detector.is_downloading(rate) {
+            return Ok(());
+        }
+
+        warn!(
+            "The assistant is downloading at {:.1} kB/s, pausing until traffic settles...",
+            rate / 1000.0
+        );
+
+        self.log_event(
+            "background_download_started",
+            json!({ "bytes_per_second": rate }),
+        );
+        let mut event = session
+            .add_event(
+                connection,
+                BACKGROUND_DOWNLOAD_EVENT,
+                &format!("Downloading at {:.1} kB/s", rate / 1000.0),
+            )
+            .await?;
+        let mut peak = rate;
+        let mut quiet_samples = 0;
+
+        while quiet_samples < detector.quiet_samples {
+            rate = detector.sample(self.sniffer.as_ref(), &sample_path, &assistant_mac)?;
+            peak = peak.max(rate);
+
+            if detector.is_downloading(rate) {
+                quiet_samples = 0;
+            } else {
+                quiet_samples += 1;
             }
         }
-        .stop();
 
-        // complete the session
-        session.complete(&database_pool).await?;
+        self.log_event(
+            "background_download_finished",
+            json!({ "peak_bytes_per_second": peak }),
+        );
+        event.detail = format!("Downloaded at up to {:.1} kB/s", peak / 1000.0);
+        event.end(connection).await?;
+
+        info!("Traffic has settled, resuming collection");
+
+        Ok(())
+    }
+
+    /// Power-cycle the assistant and record it as a session event.
+    ///
+    /// # Arguments
+    ///
+    /// * `session`: The current session.
+    /// * `connection`: The connection to use.
+    /// * `reason`: Why the assistant is power-cycled.
+    async fn power_cycle_assistant(
+        &self,
+        session: &Session,
+        connection: &DatabaseConnection,
+        reason: &str,
+    ) -> Result<(), Error> {
+        let Some(power_cycle) = &self.power_cycle else {
+            return Ok(());
+        };
+
+        self.log_event("power_cycle", json!({ "reason": reason }));
+        let mut event = session
+            .add_event(connection, POWER_CYCLE_EVENT, reason)
+            .await?;
+        power_cycle.run().await?;
+        event.end(connection).await?;
 
         Ok(())
     }
 
+    /// Calibrate the sensitivity again from the current ambient noise.
+    ///
+    /// # Arguments
+    ///
+    /// * `interactions`: The number of interactions since the last calibration.
+    /// * `failures`: The number of consecutive failed interactions since the last calibration.
+    fn recalibrate(&mut self, interactions: usize, failures: usize) -> Result<(), Error> {
+        let previous = self.sensitivity;
+        // a static threshold is replaced by the calibrated ambient noise, like `Listener::calibrate` does
+        let threshold = if self.silence_threshold.is_adaptive() {
+            self.silence_threshold
+        } else {
+            SilenceThreshold::Deviation(0.0)
+        };
+        self.sensitivity = self.listener.silence_threshold(threshold)?;
+
+        info!(
+            "Recalibrated the sensitivity from {previous} to {} after {interactions} interactions",
+            self.sensitivity
+        );
+        self.log_event(
+            "recalibrated",
+            json!({
+                "previous": previous,
+                "sensitivity": self.sensitivity,
+                "interactions": interactions,
+                "failures": failures,
+            }),
+        );
+
+        Ok(())
+    }
+
+    /// Get the shared database connection or connect to the database if there is none.
+    async fn connect(&self) -> Result<DatabaseConnection, Error> {
+        match &self.database {
+            Some(database) => Ok(database.clone()),
+            None => Ok(database::connect().await?),
+        }
+    }
+
     fn next_voice(&mut self) -> Result<String, Error> {
         let voice = self.voices.pop_front().ok_or(Error::NoVoiceProvided)?;
 
@@ -223,17 +1735,30 @@ impl Interactor {
         Ok(voice)
     }
 
-    async fn create_session(&self, voice: String) -> Result<(Session, DatabaseConnection), Error> {
-        let database_connection = database::connect().await?;
+    async fn create_session(
+        &mut self,
+        voice: String,
+        queries: &[Query],
+    ) -> Result<(Session, DatabaseConnection), Error> {
+        let database_connection = self.connect().await?;
+        self.sensitivity = self.listener.silence_threshold(self.silence_threshold)?;
+        if self.silence_threshold.is_adaptive() {
+            info!(
+                "Calibrated a silence threshold of {} ({})",
+                self.sensitivity, self.silence_threshold
+            );
+        }
+
         let mut session = Session::create(
             &database_connection,
             &InteractorConfig {
                 interface: self.interface.to_string(),
                 voice,
-                sensitivity: self.sensitivity.to_string(),
+                sensitivity: self.silence_threshold.to_string(),
                 model: self.model.to_string(),
             },
             crate::version(),
+            query::dataset_version(queries),
         )
         .await?;
 
@@ -242,19 +1767,42 @@ impl Interactor {
                 .to_string_lossy()
                 .to_string(),
         );
+        session.silence_threshold = Some(self.sensitivity);
+        session.paired_session_id = self.replay.as_ref().map(|replay| replay.session_id);
         session.update(&database_connection).await?;
 
         Ok((session, database_connection))
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn interaction(
         &mut self,
         query: &Query,
         session: &Session,
         connection: &DatabaseConnection,
-        silence_after_talking: Duration,
+        assistant: &dyn VoiceAssistant,
+        recording_timeout: Duration,
+        mic_muted: bool,
+        conversation: Option<(&Conversation, i32)>,
+        attempt: u32,
+        idle_gap: Option<Duration>,
+        conditions: &Conditions,
     ) -> Result<(Interaction, AudioData), Error> {
         info!("Starting interaction with \"{query}\"");
+        // the wake word is only added when the query is spoken, recordings include it
+        let wake_phrase = assistant.wake_phrase(&query.text);
+        let query_recording = match &self.query_source {
+            QuerySource::Synthesised | QuerySource::Typed(_) => None,
+            QuerySource::Recorded(_) => Some(
+                self.query_source
+                    .recording(&wake_phrase)
+                    .ok_or_else(|| Error::QueryRecordingMissing(wake_phrase.clone()))?,
+            ),
+        };
+        let host_monitor = HostMonitor::start();
+        let deadline = self
+            .interaction_timeout
+            .map(|interaction_timeout| Instant::now() + interaction_timeout);
 
         // prepare the interaction
         let mut interaction = Interaction::create(
@@ -265,70 +1813,655 @@ impl Interactor {
             self.assistant_mac.clone(),
         )
         .await?;
+        // typed queries go to the assistant directly and need no wake word
+        let typing_command = match &self.query_source {
+            QuerySource::Typed(command) => Some(command),
+            _ => None,
+        };
+        interaction.wake_word = typing_command.is_none().then(|| assistant.wake_word());
+        interaction.channel = self.query_source.channel().to_string();
+        interaction.mic_muted = mic_muted;
+        interaction.attempt = attempt as i32;
+        interaction.idle_gap_ms = idle_gap.map(|idle_gap| idle_gap.as_millis() as i32);
+        interaction.silence_threshold = Some(self.sensitivity);
+        interaction.query_recording = query_recording.as_deref().map(file_name_or_full);
+        // recorded queries are played as they were recorded and typed queries are not spoken at all
+        interaction.speaking_rate = (query_recording.is_none() && typing_command.is_none())
+            .then_some(conditions.speaking_rate);
+        interaction.conditions = Some(conditions.to_string());
+        if let Some((conversation, turn)) = conversation {
+            interaction.conversation_id = Some(conversation.id);
+            interaction.conversation_turn = Some(turn);
+        }
+        let _in_flight = crash::set_interaction(interaction.id);
+        self.log_event(
+            "interaction_started",
+            json!({
+                "interaction_id": interaction.id,
+                "query": query.text,
+                "wake_word": interaction.wake_word,
+                "channel": interaction.channel,
+                "query_category": query.category,
+                "mic_muted": mic_muted,
+                "conversation_id": interaction.conversation_id,
+                "conversation_turn": interaction.conversation_turn,
+                "attempt": attempt,
+                "idle_gap_ms": interaction.idle_gap_ms,
+                "silence_threshold": interaction.silence_threshold,
+                "query_recording": interaction.query_recording,
+                "speaking_rate": interaction.speaking_rate,
+            }),
+        );
         let capture_path = file::artefact_path(&self.data_dir, DataType::Capture, &interaction);
+        let radio_capture_path =
+            file::artefact_path(&self.data_dir, DataType::RadioCapture, &interaction);
         let query_audio_path = file::artefact_path(
             &self.data_dir,
             DataType::Audio(String::from("query")),
             &interaction,
         );
+        let query_loopback_path = file::artefact_path(
+            &self.data_dir,
+            DataType::Audio(String::from("query-loopback")),
+            &interaction,
+        );
         let response_audio_path = file::artefact_path(
             &self.data_dir,
             DataType::Audio(String::from("response")),
             &interaction,
         );
 
-        // start the sniffer
-        let sniffer_instance = self.sniffer.start(&capture_path)?;
+        // start the sniffer, unless the whole session is captured at once
+        let sniffer_instance = if self.combined_capture {
+            None
+        } else {
+            Some(self.sniffer.start(&capture_path)?)
+        };
+        let radio_instance = match &self.radio_sniffer {
+            Some(radio_sniffer) => Some(radio_sniffer.start(&radio_capture_path)?),
+            None => None,
+        };
 
-        // begin recording the query
+        // begin recording the query, and what the speaker plays if it is recorded
         let query_instance = self.listener.start()?;
+        let loopback_instance = match self.loopback.as_ref().filter(|_| typing_command.is_none()) {
+            Some(loopback) => Some(loopback.start()?),
+            None => None,
+        };
 
-        // say the query
-        interaction.query_duration = Some(self.speaker.say(&query.text)?);
+        // say the query, play its recording or type it
+        interaction.query_duration = Some(match (&query_recording, typing_command) {
+            (_, Some(typing_command)) => typing_command.type_query(&query.text)?,
+            (Some(path), None) => {
+                info!("Playing recording {}", path.display());
+
+                let mut audio = varys_audio::file::read_audio(path)?;
+                if let Some(watermark) = &self.watermark {
+                    watermark.embed(&mut audio, DEFAULT_AMPLITUDE);
+                }
+                self.speaker.play(&audio)?
+            }
+            (None, None) => self.speaker.say(&wake_phrase)?,
+        });
+        let query_ended = Instant::now();
+        let query_ended_at = Utc::now();
 
         // stop recording the query
-        let query_audio = query_instance.stop()?;
+        let mut query_audio = query_instance.stop()?;
+        if let Some(loopback_instance) = loopback_instance {
+            varys_audio::file::write_audio(&query_loopback_path, &loopback_instance.stop()?)?;
+            interaction.query_loopback_file = Some(file_name_or_full(&query_loopback_path));
+        }
+
+        if let Some(watermark) = self.watermark.as_ref().filter(|_| typing_command.is_none()) {
+            interaction.query_watermark_ms = watermark.detect(&query_audio);
+            if interaction.query_watermark_ms.is_none() {
+                warn!("The watermark was not found in the recording of the query");
+            }
+        }
 
+        interaction.query_peak_dbfs = query_audio.peak_dbfs();
+        interaction.query_rms_dbfs = query_audio.rms_dbfs();
+        if let Some(level_control) = &self.level_control {
+            level_control.apply(&mut query_audio);
+        }
         varys_audio::file::write_audio(&query_audio_path, &query_audio)?;
+        self.write_channels(&query_audio_path, &query_audio)?;
         interaction.query_file = Some(file_name_or_full(&query_audio_path));
+        self.log_event(
+            "query_spoken",
+            json!({
+                "interaction_id": interaction.id,
+                "query_duration": interaction.query_duration,
+                "query_file": interaction.query_file,
+                "query_loopback_file": interaction.query_loopback_file,
+                "query_watermark_ms": interaction.query_watermark_ms,
+                "query_peak_dbfs": interaction.query_peak_dbfs,
+                "query_rms_dbfs": interaction.query_rms_dbfs,
+            }),
+        );
         interaction.update(connection).await?;
 
-        // record the response
-        let response_audio = self
+        if let Some(reason) = self.interruption(deadline) {
+            return self
+                .abort_interaction(
+                    &mut interaction,
+                    sniffer_instance,
+                    radio_instance,
+                    connection,
+                    reason,
+                )
+                .await;
+        }
+
+        // record the response, keeping leading silence to measure when the response started, at most until the time
+        // budget of the interaction is used up
+        self.listener
+            .set_recording_timeout(Some(deadline.map_or(recording_timeout, |deadline| {
+                recording_timeout.min(deadline.saturating_duration_since(Instant::now()))
+            })));
+        let response_started = query_ended.elapsed();
+        // record what the speaker plays during the response, so its echo can be removed
+        let reference_instance = match self
+            .loopback
+            .as_ref()
+            .filter(|_| self.echo_canceller.is_some() && typing_command.is_none())
+        {
+            Some(loopback) => Some(loopback.start()?),
+            None => None,
+        };
+        let (mut response_audio, truncated) = match self
             .listener
-            .record_until_silent(silence_after_talking, self.sensitivity)?;
+            .record_until_silent_or_timeout_async(
+                assistant.silence_after_talking(),
+                self.sensitivity,
+            )
+            .await
+        {
+            Err(varys_audio::error::Error::RecordingTimeout) | Ok((_, true))
+                if deadline.is_some_and(|deadline| Instant::now() >= deadline) =>
+            {
+                let reason = self
+                    .interruption(deadline)
+                    .unwrap_or(Error::InteractionTimedOut(
+                        self.interaction_timeout.unwrap_or_default().as_secs(),
+                    ));
+                return self
+                    .abort_interaction(
+                        &mut interaction,
+                        sniffer_instance,
+                        radio_instance,
+                        connection,
+                        reason,
+                    )
+                    .await;
+            }
+            Err(varys_audio::error::Error::RecordingTimeout) if mic_muted => {
+                info!("No response was heard while the microphone was muted");
+
+                (
+                    AudioData {
+                        data: Vec::new(),
+                        channels: 1,
+                        sample_rate: OPUS_SAMPLE_RATE as u32,
+                    },
+                    false,
+                )
+            }
+            result => result?,
+        };
+        if self.lossless_exemplars && query.exemplar {
+            let lossless_path = response_audio_path.with_extension("flac");
+            varys_audio::file::write_flac(&lossless_path, &response_audio)?;
+            interaction.response_lossless_file = Some(file_name_or_full(&lossless_path));
+        }
+        if let Some((reference_instance, echo_canceller)) =
+            reference_instance.zip(self.echo_canceller.as_ref())
+        {
+            let reference = reference_instance.stop()?;
+            response_audio = echo_canceller.cancel(&response_audio, &reference)?;
+        }
 
+        interaction.response_latency_ms = response_audio
+            .onset_ms(self.sensitivity)
+            .map(|onset| response_started.as_millis() as i32 + onset);
+        if interaction.response_latency_ms.is_none() && !mic_muted {
+            warn!("The response stayed below the silence threshold, the assistant did not respond");
+            interaction.status = InteractionStatus::FailedNoResponse.to_string();
+        }
+        response_audio.trim_silence_with_padding(self.sensitivity, self.silence_padding);
+        interaction.response_peak_dbfs = response_audio.peak_dbfs();
+        interaction.response_rms_dbfs = response_audio.rms_dbfs();
+        if let Some(level_control) = &self.level_control {
+            level_control.apply(&mut response_audio);
+        }
         interaction.response_duration = Some(response_audio.duration_ms());
         varys_audio::file::write_audio(&response_audio_path, &response_audio)?;
+        self.write_channels(&response_audio_path, &response_audio)?;
         interaction.response_file = Some(file_name_or_full(&response_audio_path));
+        self.log_event(
+            "response_recorded",
+            json!({
+                "interaction_id": interaction.id,
+                "response_duration": interaction.response_duration,
+                "response_latency_ms": interaction.response_latency_ms,
+                "response_file": interaction.response_file,
+                "response_lossless_file": interaction.response_lossless_file,
+                "response_peak_dbfs": interaction.response_peak_dbfs,
+                "response_rms_dbfs": interaction.response_rms_dbfs,
+                "status": interaction.status,
+            }),
+        );
         interaction.update(connection).await?;
 
+        if truncated {
+            let truncation = Truncation::AtTimeout {
+                response_duration_ms: interaction.response_duration.unwrap_or_default(),
+            };
+            self.flag_truncation(&interaction, truncation, connection)
+                .await?;
+
+            // the assistant is still talking, which would be recorded as part of the next interaction
+            assistant.reset_assistant(self)?;
+        }
+
+        if let Some(similarity) = self
+            .voice_profile
+            .as_ref()
+            .and_then(|profile| profile.similarity(&response_audio))
+        {
+            if similarity < self.min_voice_similarity {
+                warn!(
+                    "The response does not sound like the assistant (similarity {similarity:.2})"
+                );
+                self.log_event(
+                    "voice_mismatch",
+                    json!({ "interaction_id": interaction.id, "similarity": similarity }),
+                );
+
+                Outlier::create(
+                    connection,
+                    interaction.id,
+                    VOICE_SIMILARITY_METRIC,
+                    similarity as f64,
+                )
+                .await?;
+            }
+        }
+
+        if let Some(reason) = self.interruption(deadline) {
+            return self
+                .abort_interaction(
+                    &mut interaction,
+                    sniffer_instance,
+                    radio_instance,
+                    connection,
+                    reason,
+                )
+                .await;
+        }
+
         // finish the sniffer
-        let stats = sniffer_instance.stop()?;
+        let stats = match sniffer_instance {
+            Some(sniffer_instance) => Some(sniffer_instance.stop()?),
+            None => None,
+        };
+        if let Some(radio_instance) = radio_instance {
+            self.stop_radio_capture(&mut interaction, radio_instance)?;
+            interaction.update(connection).await?;
+        }
+        let host_usage = host_monitor.finish();
 
-        info!("{stats}");
-        interaction.capture_file = Some(file_name_or_full(&capture_path));
-        interaction.update(connection).await?;
+        let metrics = InteractionMetrics::create(
+            connection,
+            InteractionMetrics {
+                id: 0,
+                interaction_id: interaction.id,
+                cpu_usage: host_usage.cpu_usage,
+                process_cpu_usage: host_usage.process_cpu_usage,
+                memory_usage: host_usage.memory_usage as i64,
+                disk_written: host_usage.disk_written as i64,
+                disk_write_rate: host_usage.disk_write_rate,
+                packets_received: stats.as_ref().map_or(0, |stats| stats.received as i32),
+                buffer_dropped: stats
+                    .as_ref()
+                    .map_or(0, |stats| stats.buffer_dropped as i32),
+                interface_dropped: stats
+                    .as_ref()
+                    .map_or(0, |stats| stats.interface_dropped as i32),
+                recorded: Utc::now(),
+            },
+        )
+        .await?;
+        info!("{metrics}");
+        self.log_event(
+            "metrics_recorded",
+            json!({
+                "interaction_id": interaction.id,
+                "cpu_usage": metrics.cpu_usage,
+                "process_cpu_usage": metrics.process_cpu_usage,
+                "memory_usage": metrics.memory_usage,
+                "disk_written": metrics.disk_written,
+                "disk_write_rate": metrics.disk_write_rate,
+                "packets_received": metrics.packets_received,
+                "buffer_dropped": metrics.buffer_dropped,
+                "interface_dropped": metrics.interface_dropped,
+            }),
+        );
+
+        if let Some(stats) = stats {
+            info!("{stats}");
+            interaction.response_network_latency_ms =
+                network_latency(&capture_path, &self.assistant_mac, query_ended_at).unwrap_or_else(
+                    |error| {
+                        warn!("Could not compute network latency: {error}");
+                        None
+                    },
+                );
+            if let (Some(latency), Some(duration)) = (
+                interaction.response_latency_ms,
+                interaction.response_duration,
+            ) {
+                let response_ended = query_ended_at
+                    + chrono::Duration::milliseconds(latency as i64 + duration as i64);
+                let truncation = burst_end(&capture_path, &self.assistant_mac, query_ended_at)
+                    .map(|burst_ended| Truncation::from_traffic(response_ended, burst_ended))
+                    .unwrap_or_else(|error| {
+                        warn!("Could not find the end of the traffic burst: {error}");
+                        None
+                    });
+                if let Some(truncation) = truncation {
+                    self.flag_truncation(&interaction, truncation, connection)
+                        .await?;
+                }
+            }
+            interaction.capture_file = Some(file_name_or_full(&capture_path));
+            self.log_event(
+                "capture_finished",
+                json!({
+                    "interaction_id": interaction.id,
+                    "capture_file": interaction.capture_file,
+                    "response_network_latency_ms": interaction.response_network_latency_ms,
+                }),
+            );
+            interaction.update(connection).await?;
+        }
 
         // at this point, the interaction is not yet complete because the response will later be
         // transcribed in a separate thread
         Ok((interaction, response_audio))
     }
 
+    /// Flag an interaction whose response was probably truncated as an outlier, so it is excluded from datasets.
+    ///
+    /// # Arguments
+    ///
+    /// * `interaction`: The interaction to flag.
+    /// * `truncation`: The sign that its response was truncated.
+    /// * `connection`: The connection to the database.
+    async fn flag_truncation(
+        &self,
+        interaction: &Interaction,
+        truncation: Truncation,
+        connection: &DatabaseConnection,
+    ) -> Result<(), Error> {
+        warn!("{truncation}");
+        self.log_event(
+            "response_truncated",
+            json!({
+                "interaction_id": interaction.id,
+                "metric": truncation.metric(),
+                "value": truncation.value(),
+            }),
+        );
+
+        Outlier::create(
+            connection,
+            interaction.id,
+            truncation.metric(),
+            truncation.value(),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Stop the radio capture of an interaction and store its file with the interaction.
+    ///
+    /// # Arguments
+    ///
+    /// * `interaction`: The interaction that was captured.
+    /// * `radio_instance`: The running radio capture.
+    fn stop_radio_capture(
+        &self,
+        interaction: &mut Interaction,
+        radio_instance: Box<dyn SniffInstance>,
+    ) -> Result<(), Error> {
+        let radio_capture_path =
+            file::artefact_path(&self.data_dir, DataType::RadioCapture, interaction);
+        let stats = radio_instance.stop()?;
+        info!("Radio capture received {} frames", stats.received);
+        interaction.radio_capture_file = Some(file_name_or_full(&radio_capture_path));
+        self.log_event(
+            "radio_capture_finished",
+            json!({
+                "interaction_id": interaction.id,
+                "radio_capture_file": interaction.radio_capture_file,
+                "frames_received": stats.received,
+            }),
+        );
+
+        Ok(())
+    }
+
+    /// Stop an interaction that was interrupted by a shutdown or ran out of time, and mark it as aborted or timed out.
+    ///
+    /// The capture of the interaction is stopped, so the packets captured so far are written to its file.
+    ///
+    /// Always returns the reason, unless the interaction could not be stored.
+    ///
+    /// # Arguments
+    ///
+    /// * `interaction`: The interaction to abort.
+    /// * `sniffer_instance`: The capture of the interaction, if it was captured on its own.
+    /// * `radio_instance`: The radio capture of the interaction, if one is running.
+    /// * `connection`: The connection to use.
+    /// * `reason`: Why the interaction is stopped, see [`Interactor::interruption`].
+    async fn abort_interaction(
+        &self,
+        interaction: &mut Interaction,
+        sniffer_instance: Option<Box<dyn SniffInstance>>,
+        radio_instance: Option<Box<dyn SniffInstance>>,
+        connection: &DatabaseConnection,
+        reason: Error,
+    ) -> Result<(Interaction, AudioData), Error> {
+        warn!("Stopping {interaction}: {reason}");
+
+        if let Some(sniffer_instance) = sniffer_instance {
+            let capture_path = file::artefact_path(&self.data_dir, DataType::Capture, interaction);
+            info!("{}", sniffer_instance.stop()?);
+            interaction.capture_file = Some(file_name_or_full(&capture_path));
+        }
+        if let Some(radio_instance) = radio_instance {
+            self.stop_radio_capture(interaction, radio_instance)?;
+        }
+        let status = match reason {
+            Error::InteractionTimedOut(_) => InteractionStatus::TimedOut,
+            _ => InteractionStatus::Aborted,
+        };
+        interaction.status = status.to_string();
+        interaction.update(connection).await?;
+        self.log_event(
+            "interaction_aborted",
+            json!({
+                "interaction_id": interaction.id,
+                "status": interaction.status,
+                "capture_file": interaction.capture_file,
+            }),
+        );
+
+        Err(reason)
+    }
+
+    /// Split the capture of a whole session into one capture per interaction.
+    ///
+    /// Each interaction gets the packets captured from its start until the start of the next interaction. Since the
+    /// end of the query is not stored, the network latency is measured from the start of the interaction plus the
+    /// duration of the query.
+    ///
+    /// # Arguments
+    ///
+    /// * `session`: The session whose capture to split.
+    /// * `connection`: The connection to use.
+    /// * `since`: When the capture was started, earlier interactions are not part of it.
+    async fn split_session_capture(
+        &self,
+        session: &Session,
+        connection: &DatabaseConnection,
+        since: DateTime<Utc>,
+    ) -> Result<(), Error> {
+        let session_capture_path = file::session_capture_path(&self.data_dir, session.id);
+        let mut interactions = session.interactions(connection).await?;
+        interactions.retain(|interaction| interaction.started >= since);
+        interactions.sort_by_key(|interaction| interaction.started);
+        let ends: Vec<DateTime<Utc>> = interactions
+            .iter()
+            .skip(1)
+            .map(|next| next.started - chrono::Duration::microseconds(1))
+            .chain(iter::once(Utc::now()))
+            .collect();
+
+        for (interaction, end) in interactions.iter_mut().zip(ends) {
+            let capture_path = file::artefact_path(&self.data_dir, DataType::Capture, interaction);
+            let packets =
+                index::load_packets_between(&session_capture_path, interaction.started, end)?;
+            packet::save_packets(&capture_path, &packets)?;
+
+            info!(
+                "Split {} packets of {interaction} from the session capture",
+                packets.len()
+            );
+
+            let query_ended = interaction.started
+                + chrono::Duration::milliseconds(interaction.query_duration.unwrap_or(0) as i64);
+            interaction.response_network_latency_ms =
+                network_latency(&capture_path, &self.assistant_mac, query_ended).unwrap_or_else(
+                    |error| {
+                        warn!("Could not compute network latency: {error}");
+                        None
+                    },
+                );
+            interaction.capture_file = Some(file_name_or_full(&capture_path));
+            interaction.update(connection).await?;
+        }
+
+        Ok(())
+    }
+
     async fn complete_interaction(
+        &self,
         receiver: TranscriberReceiver<TranscribeInteraction>,
         database_connection: &DatabaseConnection,
     ) -> Result<TranscriberSender<TranscribeInteraction>, Error> {
         let (sender, interaction) = receiver.receive();
         let mut interaction = interaction?;
 
-        info!("Transcription of {} done, completing it...", interaction.0);
+        info!(
+            "Transcription of {} done, completing it...",
+            interaction.interaction
+        );
+        self.log_event(
+            "interaction_completed",
+            json!({
+                "interaction_id": interaction.interaction.id,
+                "response": interaction.interaction.response,
+                "response_type": interaction.interaction.response_type,
+            }),
+        );
 
-        interaction.0.complete(database_connection).await?;
+        interaction
+            .interaction
+            .complete(database_connection)
+            .await?;
+        TranscriptionJob::delete_by_interaction(database_connection, interaction.interaction.id)
+            .await?;
         Ok(sender)
     }
 }
 
+/// Take one completed interaction for every turn of a query, if all of them have one left.
+///
+/// Returns whether the query was already asked, in which case it is not asked again. Every completed interaction only
+/// accounts for one of several identical queries, and a conversation is only done if all of its turns are.
+///
+/// # Arguments
+///
+/// * `completed`: The number of completed interactions left for every query text.
+/// * `query`: The query to check.
+fn take_completed(completed: &mut HashMap<String, usize>, query: &Query) -> bool {
+    let mut turns: HashMap<String, usize> = HashMap::new();
+    for turn in query.turns() {
+        *turns.entry(turn.text).or_default() += 1;
+    }
+    if !turns
+        .iter()
+        .all(|(text, count)| completed.get(text).is_some_and(|left| left >= count))
+    {
+        return false;
+    }
+
+    for (text, count) in turns {
+        if let Some(left) = completed.get_mut(&text) {
+            *left -= count;
+        }
+    }
+    true
+}
+
+/// Get the time from the end of the query to the first large packet sent to the assistant in milliseconds.
+///
+/// # Arguments
+///
+/// * `capture_path`: The path to the capture of the interaction.
+/// * `assistant_mac`: The MAC address of the assistant.
+/// * `query_ended`: When the query was done being spoken.
+fn network_latency(
+    capture_path: &Path,
+    assistant_mac: &str,
+    query_ended: DateTime<Utc>,
+) -> Result<Option<i32>, Error> {
+    let assistant_mac: MacAddress = assistant_mac.parse()?;
+    let packets = packet::load_packets(capture_path)?;
+
+    Ok(
+        packet::first_large_incoming(&packets, &assistant_mac, query_ended)
+            .map(|packet| (packet.timestamp - query_ended).num_milliseconds() as i32),
+    )
+}
+
+/// Get when the burst of large packets sent to the assistant after the query ended, see [`packet::burst_end`].
+///
+/// # Arguments
+///
+/// * `capture_path`: The path to the capture of the interaction.
+/// * `assistant_mac`: The MAC address of the assistant.
+/// * `query_ended`: When the query was done being spoken.
+fn burst_end(
+    capture_path: &Path,
+    assistant_mac: &str,
+    query_ended: DateTime<Utc>,
+) -> Result<Option<DateTime<Utc>>, Error> {
+    let assistant_mac: MacAddress = assistant_mac.parse()?;
+    let packets = packet::load_packets(capture_path)?;
+
+    Ok(packet::burst_end(
+        &packets,
+        &assistant_mac,
+        query_ended,
+        chrono::Duration::milliseconds(BURST_GAP_MS),
+    ))
+}
+
 /// Returns the file name if it exists. Otherwise, returns the full path.
 ///
 /// # Arguments
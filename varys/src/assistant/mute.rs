@@ -0,0 +1,111 @@
+use colored::Colorize;
+use log::info;
+use reqwest::Url;
+
+use crate::cli::interact;
+use crate::error::Error;
+
+/// How the microphone of a voice assistant is muted and unmuted.
+pub enum MuteControl {
+    /// Ask the user to mute or unmute the microphone and wait for their confirmation.
+    Manual,
+    /// Request a url to mute or unmute the microphone, e.g. of a smart plug that switches the mute button.
+    Http { mute_url: Url, unmute_url: Url },
+}
+
+impl MuteControl {
+    /// Create a control that requests a url to mute and another one to unmute the microphone.
+    ///
+    /// Returns an error if one of the urls is invalid.
+    ///
+    /// # Arguments
+    ///
+    /// * `mute_url`: The url to request to mute the microphone.
+    /// * `unmute_url`: The url to request to unmute the microphone.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys::assistant::mute::MuteControl;
+    /// assert!(MuteControl::http("http://plug.local/on", "http://plug.local/off").is_ok());
+    /// assert!(MuteControl::http("plug", "http://plug.local/off").is_err());
+    /// ```
+    pub fn http(mute_url: &str, unmute_url: &str) -> Result<Self, Error> {
+        let parse = |url: &str| Url::parse(url).map_err(|_| Error::InvalidMuteUrl(url.to_string()));
+
+        Ok(MuteControl::Http {
+            mute_url: parse(mute_url)?,
+            unmute_url: parse(unmute_url)?,
+        })
+    }
+
+    /// Mute or unmute the microphone of the assistant.
+    ///
+    /// This will block until the user has confirmed the change if the control is [`MuteControl::Manual`].
+    ///
+    /// # Arguments
+    ///
+    /// * `muted`: Whether the microphone should be muted.
+    pub async fn set_muted(&self, muted: bool) -> Result<(), Error> {
+        info!(
+            "{} the microphone of the assistant...",
+            if muted { "Muting" } else { "Unmuting" }
+        );
+
+        match self {
+            MuteControl::Manual => interact::user_confirmation(&format!(
+                "{} the microphone of the assistant and then",
+                if muted { "Mute" } else { "Unmute" }.bright_blue()
+            )),
+            MuteControl::Http {
+                mute_url,
+                unmute_url,
+            } => {
+                reqwest::get(if muted { mute_url } else { unmute_url }.clone())
+                    .await
+                    .and_then(|response| response.error_for_status())
+                    .map_err(Error::MuteControlFailed)?;
+
+                Ok(())
+            }
+        }
+    }
+}
+
+/// An experiment that alternates the microphone of the assistant between unmuted and muted in blocks of interactions.
+///
+/// This shows which traffic persists while the assistant is not supposed to listen.
+pub struct MuteExperiment {
+    /// How the microphone is muted and unmuted.
+    pub control: MuteControl,
+    /// The number of consecutive interactions with the same microphone state.
+    pub block_size: usize,
+}
+
+impl MuteExperiment {
+    /// Whether the microphone should be muted for an interaction.
+    ///
+    /// The first block of every session is unmuted.
+    ///
+    /// # Arguments
+    ///
+    /// * `index`: The index of the interaction in its session.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys::assistant::mute::{MuteControl, MuteExperiment};
+    /// let experiment = MuteExperiment {
+    ///     control: MuteControl::Manual,
+    ///     block_size: 2,
+    /// };
+    ///
+    /// assert!(!experiment.is_muted(1));
+    /// assert!(experiment.is_muted(2));
+    /// assert!(experiment.is_muted(3));
+    /// assert!(!experiment.is_muted(4));
+    /// ```
+    pub fn is_muted(&self, index: usize) -> bool {
+        (index / self.block_size.max(1)) % 2 == 1
+    }
+}
@@ -0,0 +1,217 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::time::Duration;
+
+use log::{debug, info};
+use reqwest::Url;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use crate::error::Error;
+
+/// The kind of the session events recorded when the assistant is power-cycled.
+pub const POWER_CYCLE_EVENT: &str = "power_cycle";
+
+/// The port on which Kasa smart plugs accept commands.
+const KASA_PORT: u16 = 9999;
+
+/// A smart plug that powers the voice assistant.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SmartPlug {
+    /// A plug running Tasmota, controlled through its HTTP api.
+    Tasmota { host: String },
+    /// A TP-Link Kasa plug, controlled through its local TCP protocol.
+    Kasa { host: String },
+}
+
+impl SmartPlug {
+    /// Switch the power of the plug on or off.
+    ///
+    /// # Arguments
+    ///
+    /// * `on`: Whether to switch the power on.
+    pub async fn set_power(&self, on: bool) -> Result<(), Error> {
+        debug!("Switching {self} {}", if on { "on" } else { "off" });
+
+        match self {
+            SmartPlug::Tasmota { host } => {
+                let url = format!(
+                    "http://{host}/cm?cmnd=Power%20{}",
+                    if on { "On" } else { "Off" }
+                );
+                let url = Url::parse(&url).map_err(|_| Error::SmartPlugFailed(url))?;
+
+                reqwest::get(url)
+                    .await
+                    .and_then(|response| response.error_for_status())
+                    .map_err(|error| Error::SmartPlugFailed(error.to_string()))?;
+            }
+            SmartPlug::Kasa { host } => {
+                let command = format!(
+                    r#"{{"system":{{"set_relay_state":{{"state":{}}}}}}}"#,
+                    u8::from(on)
+                );
+                let response = kasa_request(host, &command).await?;
+
+                if !response.contains(r#""err_code":0"#) {
+                    return Err(Error::SmartPlugFailed(response));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for SmartPlug {
+    type Err = String;
+
+    /// Parse a smart plug from its kind and host, e.g. `tasmota:192.168.1.20` or `kasa:plug.local`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys::assistant::power::SmartPlug;
+    /// assert_eq!(
+    ///     "kasa:192.168.1.20".parse(),
+    ///     Ok(SmartPlug::Kasa {
+    ///         host: "192.168.1.20".to_string()
+    ///     })
+    /// );
+    /// assert!("hue:192.168.1.20".parse::<SmartPlug>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Expected tasmota:<host> or kasa:<host>, got {s}");
+        let (kind, host) = s.split_once(':').ok_or_else(invalid)?;
+        let host = host.to_string();
+
+        match kind {
+            "tasmota" => Ok(SmartPlug::Tasmota { host }),
+            "kasa" => Ok(SmartPlug::Kasa { host }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl Display for SmartPlug {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SmartPlug::Tasmota { host } => write!(f, "Tasmota plug {host}"),
+            SmartPlug::Kasa { host } => write!(f, "Kasa plug {host}"),
+        }
+    }
+}
+
+/// When to power-cycle the voice assistant to recover it from hanging.
+pub struct PowerCycle {
+    /// The plug that powers the assistant.
+    pub plug: SmartPlug,
+    /// Whether to power-cycle the assistant before every session.
+    pub every_session: bool,
+    /// After how many consecutive failed interactions to power-cycle the assistant.
+    pub after_failures: Option<usize>,
+    /// How long the power stays off.
+    pub off_duration: Duration,
+    /// How long to wait for the assistant to boot after the power is back on.
+    pub boot_duration: Duration,
+}
+
+impl PowerCycle {
+    /// Create a power cycle that keeps the power off for ten seconds and waits a minute for the assistant to boot.
+    ///
+    /// # Arguments
+    ///
+    /// * `plug`: The plug that powers the assistant.
+    /// * `every_session`: Whether to power-cycle the assistant before every session.
+    /// * `after_failures`: After how many consecutive failed interactions to power-cycle the assistant.
+    pub fn new(plug: SmartPlug, every_session: bool, after_failures: Option<usize>) -> Self {
+        PowerCycle {
+            plug,
+            every_session,
+            after_failures,
+            off_duration: Duration::from_secs(10),
+            boot_duration: Duration::from_secs(60),
+        }
+    }
+
+    /// Switch the assistant off and on again and wait until it has booted.
+    pub async fn run(&self) -> Result<(), Error> {
+        info!("Power-cycling the assistant with {}...", self.plug);
+
+        self.plug.set_power(false).await?;
+        tokio::time::sleep(self.off_duration).await;
+        self.plug.set_power(true).await?;
+        tokio::time::sleep(self.boot_duration).await;
+
+        info!("Power-cycled the assistant");
+
+        Ok(())
+    }
+
+    /// Whether the assistant should be power-cycled after a number of consecutive failed interactions.
+    ///
+    /// # Arguments
+    ///
+    /// * `failures`: The number of consecutive failed interactions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys::assistant::power::{PowerCycle, SmartPlug};
+    /// let plug = "tasmota:plug.local".parse::<SmartPlug>().unwrap();
+    /// let power_cycle = PowerCycle::new(plug, false, Some(3));
+    ///
+    /// assert!(!power_cycle.is_due(2));
+    /// assert!(power_cycle.is_due(3));
+    /// ```
+    pub fn is_due(&self, failures: usize) -> bool {
+        self.after_failures
+            .is_some_and(|after_failures| failures >= after_failures)
+    }
+}
+
+/// Send a command to a Kasa plug and get its response.
+///
+/// Kasa plugs accept JSON commands that are prefixed with their length and obfuscated with an autokey XOR cipher.
+///
+/// # Arguments
+///
+/// * `host`: The host of the plug.
+/// * `command`: The JSON command to send.
+async fn kasa_request(host: &str, command: &str) -> Result<String, Error> {
+    let smart_plug_error = |error: std::io::Error| Error::SmartPlugFailed(error.to_string());
+    let mut stream = TcpStream::connect((host, KASA_PORT))
+        .await
+        .map_err(smart_plug_error)?;
+
+    let mut key = 171;
+    let mut request = (command.len() as u32).to_be_bytes().to_vec();
+    request.extend(command.bytes().map(|byte| {
+        key ^= byte;
+        key
+    }));
+    stream.write_all(&request).await.map_err(smart_plug_error)?;
+
+    let mut length = [0; 4];
+    stream
+        .read_exact(&mut length)
+        .await
+        .map_err(smart_plug_error)?;
+    let mut response = vec![0; u32::from_be_bytes(length) as usize];
+    stream
+        .read_exact(&mut response)
+        .await
+        .map_err(smart_plug_error)?;
+
+    let mut key = 171;
+    let response = response
+        .into_iter()
+        .map(|byte| {
+            let plain = key ^ byte;
+            key = byte;
+            plain
+        })
+        .collect::<Vec<_>>();
+
+    Ok(String::from_utf8_lossy(&response).to_string())
+}
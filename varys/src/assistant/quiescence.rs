@@ -0,0 +1,91 @@
+use std::fs;
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+use log::debug;
+use varys_network::address::MacAddress;
+use varys_network::index;
+use varys_network::packet;
+use varys_network::sniff::Sniff;
+
+use crate::error::Error;
+
+/// The kind of the session events recorded while collection is paused for a background download.
+pub const BACKGROUND_DOWNLOAD_EVENT: &str = "background_download";
+
+/// Detects large background downloads to the assistant, e.g. OS or firmware updates, from short samples of its
+/// traffic.
+///
+/// Collection should be paused while a download is running, since its traffic would be part of the captured traces.
+pub struct QuiescenceDetector {
+    /// The incoming traffic in bytes per second above which the assistant is considered to be downloading.
+    pub threshold: f64,
+    /// How long to capture traffic for each sample.
+    pub sample_duration: Duration,
+    /// How many consecutive samples must be below the threshold for a download to be considered finished.
+    pub quiet_samples: usize,
+}
+
+impl QuiescenceDetector {
+    /// Create a detector that samples two seconds of traffic and needs three quiet samples after a download.
+    ///
+    /// # Arguments
+    ///
+    /// * `threshold`: The incoming traffic in bytes per second above which the assistant is considered to be
+    ///   downloading.
+    pub fn new(threshold: f64) -> Self {
+        QuiescenceDetector {
+            threshold,
+            sample_duration: Duration::from_secs(2),
+            quiet_samples: 3,
+        }
+    }
+
+    /// Capture a sample of traffic and get the rate at which the assistant received data in bytes per second.
+    ///
+    /// # Arguments
+    ///
+    /// * `sniffer`: The sniffer to capture the sample with.
+    /// * `sample_path`: Where to temporarily store the sample. It is removed afterwards.
+    /// * `assistant_mac`: The MAC address of the assistant.
+    pub fn sample(
+        &self,
+        sniffer: &dyn Sniff,
+        sample_path: &Path,
+        assistant_mac: &MacAddress,
+    ) -> Result<f64, Error> {
+        let instance = sniffer.start(sample_path)?;
+        thread::sleep(self.sample_duration);
+        instance.stop()?;
+
+        let packets = packet::load_packets(sample_path)?;
+        let _ = fs::remove_file(sample_path);
+        let _ = fs::remove_file(index::index_path(sample_path));
+
+        let rate = packet::incoming_bytes(&packets, assistant_mac) as f64
+            / self.sample_duration.as_secs_f64();
+        debug!("The assistant received {rate:.0} bytes/s");
+
+        Ok(rate)
+    }
+
+    /// Whether a rate of incoming traffic indicates a background download.
+    ///
+    /// # Arguments
+    ///
+    /// * `rate`: The incoming traffic in bytes per second.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys::assistant::quiescence::QuiescenceDetector;
+    /// let detector = QuiescenceDetector::new(50_000.0);
+    ///
+    /// assert!(detector.is_downloading(120_000.0));
+    /// assert!(!detector.is_downloading(2_000.0));
+    /// ```
+    pub fn is_downloading(&self, rate: f64) -> bool {
+        rate > self.threshold
+    }
+}
@@ -0,0 +1,131 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::time::Duration;
+
+use chrono::NaiveTime;
+
+/// The kind of the session events recorded while speaking is paused for quiet hours.
+pub const QUIET_HOURS_EVENT: &str = "quiet_hours";
+
+/// A daily period in local time in which no queries are spoken, so rigs in shared spaces do not talk at night.
+///
+/// The period can span midnight, e.g. from 22:00 to 07:00.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct QuietHours {
+    /// When the quiet hours start.
+    pub start: NaiveTime,
+    /// When the quiet hours end.
+    pub end: NaiveTime,
+}
+
+impl QuietHours {
+    /// Whether a time of day is in the quiet hours. The start is part of them, the end is not.
+    ///
+    /// # Arguments
+    ///
+    /// * `time`: The time of day in local time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use chrono::NaiveTime;
+    /// # use varys::assistant::quiet_hours::QuietHours;
+    /// let quiet_hours: QuietHours = "22:00-07:00".parse().unwrap();
+    /// let time = |hour| NaiveTime::from_hms_opt(hour, 0, 0).unwrap();
+    ///
+    /// assert!(quiet_hours.contains(time(22)));
+    /// assert!(quiet_hours.contains(time(3)));
+    /// assert!(!quiet_hours.contains(time(7)));
+    /// assert!(!quiet_hours.contains(time(12)));
+    /// ```
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+
+    /// How long it is from a time of day until the quiet hours end, zero if the time is not in the quiet hours.
+    ///
+    /// # Arguments
+    ///
+    /// * `time`: The time of day in local time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use chrono::NaiveTime;
+    /// # use varys::assistant::quiet_hours::QuietHours;
+    /// let quiet_hours: QuietHours = "22:00-07:00".parse().unwrap();
+    /// let time = |hour, minute| NaiveTime::from_hms_opt(hour, minute, 0).unwrap();
+    ///
+    /// assert_eq!(quiet_hours.remaining(time(23, 30)), Duration::from_secs(27000));
+    /// assert_eq!(quiet_hours.remaining(time(6, 0)), Duration::from_secs(3600));
+    /// assert_eq!(quiet_hours.remaining(time(12, 0)), Duration::ZERO);
+    /// ```
+    pub fn remaining(&self, time: NaiveTime) -> Duration {
+        if !self.contains(time) {
+            return Duration::ZERO;
+        }
+
+        let remaining = self.end.signed_duration_since(time);
+        let remaining = if remaining < chrono::Duration::zero() {
+            remaining + chrono::Duration::days(1)
+        } else {
+            remaining
+        };
+
+        remaining.to_std().unwrap_or_default()
+    }
+}
+
+impl FromStr for QuietHours {
+    type Err = String;
+
+    /// Parse quiet hours from their start and end in local time, e.g. `22:00-07:00`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use chrono::NaiveTime;
+    /// # use varys::assistant::quiet_hours::QuietHours;
+    /// assert_eq!(
+    ///     "22:00-07:30".parse(),
+    ///     Ok(QuietHours {
+    ///         start: NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+    ///         end: NaiveTime::from_hms_opt(7, 30, 0).unwrap(),
+    ///     })
+    /// );
+    /// assert!("22:00".parse::<QuietHours>().is_err());
+    /// assert!("07:00-07:00".parse::<QuietHours>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Expected quiet hours as <HH:MM>-<HH:MM>, got {s}");
+        let (start, end) = s.trim().split_once('-').ok_or_else(invalid)?;
+        let parse =
+            |time: &str| NaiveTime::parse_from_str(time.trim(), "%H:%M").map_err(|_| invalid());
+        let quiet_hours = QuietHours {
+            start: parse(start)?,
+            end: parse(end)?,
+        };
+
+        if quiet_hours.start == quiet_hours.end {
+            return Err(invalid());
+        }
+
+        Ok(quiet_hours)
+    }
+}
+
+impl Display for QuietHours {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}-{}",
+            self.start.format("%H:%M"),
+            self.end.format("%H:%M")
+        )
+    }
+}
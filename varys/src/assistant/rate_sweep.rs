@@ -0,0 +1,67 @@
+use crate::error::Error;
+
+/// An experiment that cycles the speaking rate of the queries through a list of rates in blocks of interactions.
+///
+/// The same query spoken faster or slower takes a different time to say, which shows whether the duration of the
+/// spoken query leaks through the length of the traffic.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RateSweep {
+    /// The speaking rates relative to the normal rate of the voice, in the order they are swept through.
+    pub rates: Vec<f32>,
+    /// The number of consecutive interactions with the same speaking rate.
+    pub block_size: usize,
+}
+
+impl RateSweep {
+    /// Create a sweep through speaking rates.
+    ///
+    /// Returns [`Error::InvalidSpeakingRate`] if a rate is not positive and [`Error::NoSpeakingRates`] if there are no
+    /// rates.
+    ///
+    /// # Arguments
+    ///
+    /// * `rates`: The speaking rates relative to the normal rate of the voice.
+    /// * `block_size`: The number of consecutive interactions with the same speaking rate.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys::assistant::rate_sweep::RateSweep;
+    /// assert!(RateSweep::new(vec![0.8, 1.0, 1.25], 10).is_ok());
+    /// assert!(RateSweep::new(vec![1.0, -1.0], 10).is_err());
+    /// assert!(RateSweep::new(Vec::new(), 10).is_err());
+    /// ```
+    pub fn new(rates: Vec<f32>, block_size: usize) -> Result<Self, Error> {
+        if let Some(rate) = rates.iter().find(|rate| **rate <= 0.0 || !rate.is_finite()) {
+            return Err(Error::InvalidSpeakingRate(*rate));
+        }
+        if rates.is_empty() {
+            return Err(Error::NoSpeakingRates);
+        }
+
+        Ok(RateSweep { rates, block_size })
+    }
+
+    /// The speaking rate of an interaction.
+    ///
+    /// Every session starts with the first rate and starts over after the last one.
+    ///
+    /// # Arguments
+    ///
+    /// * `index`: The index of the interaction in its session.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys::assistant::rate_sweep::RateSweep;
+    /// let sweep = RateSweep::new(vec![0.8, 1.0, 1.25], 2).unwrap();
+    ///
+    /// assert_eq!(sweep.rate(1), 0.8);
+    /// assert_eq!(sweep.rate(2), 1.0);
+    /// assert_eq!(sweep.rate(5), 1.25);
+    /// assert_eq!(sweep.rate(6), 0.8);
+    /// ```
+    pub fn rate(&self, index: usize) -> f32 {
+        self.rates[(index / self.block_size.max(1)) % self.rates.len()]
+    }
+}
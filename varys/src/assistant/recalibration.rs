@@ -0,0 +1,49 @@
+/// When to calibrate the sensitivity of the listener again during a session, so it follows changes in the ambient noise.
+///
+/// The sensitivity used is stored with every interaction.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Recalibration {
+    /// After how many interactions to calibrate again.
+    pub every: Option<usize>,
+    /// After how many consecutive failed interactions to calibrate again, e.g. because no response was detected.
+    pub after_failures: Option<usize>,
+}
+
+impl Recalibration {
+    /// Create a recalibration.
+    ///
+    /// # Arguments
+    ///
+    /// * `every`: After how many interactions to calibrate again.
+    /// * `after_failures`: After how many consecutive failed interactions to calibrate again.
+    pub fn new(every: Option<usize>, after_failures: Option<usize>) -> Self {
+        Recalibration {
+            every,
+            after_failures,
+        }
+    }
+
+    /// Whether the listener should be calibrated again.
+    ///
+    /// # Arguments
+    ///
+    /// * `interactions`: The number of interactions since the last calibration.
+    /// * `failures`: The number of consecutive failed interactions since the last calibration.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys::assistant::recalibration::Recalibration;
+    /// let recalibration = Recalibration::new(Some(50), Some(3));
+    ///
+    /// assert!(!recalibration.is_due(10, 2));
+    /// assert!(recalibration.is_due(10, 3));
+    /// assert!(recalibration.is_due(50, 0));
+    /// ```
+    pub fn is_due(&self, interactions: usize, failures: usize) -> bool {
+        self.every.is_some_and(|every| interactions >= every)
+            || self
+                .after_failures
+                .is_some_and(|after_failures| failures >= after_failures)
+    }
+}
@@ -0,0 +1,192 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::time::Duration;
+
+use log::{debug, info};
+use varys_database::connection::DatabaseConnection;
+use varys_database::database::interaction::Interaction;
+use varys_database::database::session::Session;
+
+use crate::assistant::conditions::Conditions;
+use crate::error::Error;
+use crate::query::Query;
+
+/// The queries of a collected session in the order they were asked in, with the seeds they were first asked with and the
+/// idle times waited before them.
+///
+/// A session that replays another one asks the same queries at the same pace, e.g. against a different assistant, so
+/// the two sessions can be compared as a pair that only differs in the assistant.
+#[derive(Clone, Debug)]
+pub struct Replay {
+    /// The id of the replayed session.
+    pub session_id: i32,
+    /// The queries of the session, with the turns of every conversation as the follow-ups of its first turn.
+    pub queries: Vec<Query>,
+    /// The seed each of the [`Replay::queries`] was first asked with, or `None` if its conditions were not stored.
+    pub seeds: Vec<Option<u64>>,
+    /// The idle time waited before each of the [`Replay::queries`] was first asked, or `None` if it was asked right
+    /// away.
+    pub idle_gaps: Vec<Option<Duration>>,
+}
+
+impl Replay {
+    /// Reconstruct how a session was collected from its interactions.
+    ///
+    /// Only the first attempts of queries are replayed, retries depend on how the assistant responded. Queries are
+    /// exemplars if they were asked as exemplars or their response was stored losslessly.
+    ///
+    /// # Arguments
+    ///
+    /// * `session_id`: The id of the session.
+    /// * `interactions`: The interactions of the session, ordered by when they started.
+    pub fn new(session_id: i32, interactions: &[Interaction]) -> Self {
+        let mut queries: Vec<Query> = Vec::new();
+        let mut seeds = Vec::new();
+        let mut idle_gaps = Vec::new();
+        let mut conversations: BTreeMap<i32, usize> = BTreeMap::new();
+
+        for interaction in interactions
+            .iter()
+            .filter(|interaction| interaction.attempt <= 1)
+        {
+            match (interaction.conversation_id, interaction.conversation_turn) {
+                (Some(conversation_id), Some(turn)) if turn > 0 => {
+                    if let Some(index) = conversations.get(&conversation_id) {
+                        queries[*index].follow_ups.push(interaction.query.clone());
+                    }
+                }
+                (conversation_id, _) => {
+                    if let Some(conversation_id) = conversation_id {
+                        conversations.insert(conversation_id, queries.len());
+                    }
+
+                    let conditions = conditions(interaction);
+                    queries.push(Query {
+                        text: interaction.query.clone(),
+                        category: interaction.query_category.clone(),
+                        recording_timeout: None,
+                        follow_ups: Vec::new(),
+                        locale: conditions
+                            .as_ref()
+                            .and_then(|conditions| conditions.locale.clone()),
+                        exemplar: conditions
+                            .as_ref()
+                            .is_some_and(|conditions| conditions.exemplar)
+                            || interaction.response_lossless_file.is_some(),
+                    });
+                    seeds.push(conditions.map(|conditions| conditions.seed));
+                    idle_gaps.push(
+                        interaction
+                            .idle_gap_ms
+                            .map(|idle_gap| Duration::from_millis(idle_gap.max(0) as u64)),
+                    );
+                }
+            }
+        }
+
+        Replay {
+            session_id,
+            queries,
+            seeds,
+            idle_gaps,
+        }
+    }
+
+    /// Load a collected session from the database to replay it.
+    ///
+    /// Returns an error if the session does not exist or has no interactions.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    /// * `session_id`: The id of the session.
+    pub async fn load(connection: &DatabaseConnection, session_id: i32) -> Result<Self, Error> {
+        info!("Reading queries from session {session_id}");
+
+        let session = Session::get(connection, session_id)
+            .await?
+            .ok_or(Error::SessionNotFound(session_id))?;
+        let mut interactions = session.interactions(connection).await?;
+        interactions.sort_by_key(|interaction| interaction.started);
+        if interactions.is_empty() {
+            return Err(Error::NothingToReplay(session_id));
+        }
+
+        let replay = Replay::new(session_id, &interactions);
+        debug!("Found {} queries", replay.queries.len());
+
+        Ok(replay)
+    }
+
+    /// Get the seed to ask a query with.
+    ///
+    /// Returns `None` for retries, which are asked with fresh seeds since the replayed session might have retried other
+    /// queries, and for queries whose seed was not stored.
+    ///
+    /// # Arguments
+    ///
+    /// * `position`: The position of the query in [`Replay::queries`].
+    /// * `attempt`: How often the query has been asked, starting at `1`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys::assistant::replay::Replay;
+    /// let replay = Replay {
+    ///     session_id: 1,
+    ///     queries: Vec::new(),
+    ///     seeds: vec![Some(3), None, Some(5)],
+    ///     idle_gaps: Vec::new(),
+    /// };
+    ///
+    /// assert_eq!(replay.seed(2, 1), Some(5));
+    /// assert_eq!(replay.seed(2, 2), None);
+    /// assert_eq!(replay.seed(1, 1), None);
+    /// ```
+    pub fn seed(&self, position: usize, attempt: u32) -> Option<u64> {
+        self.seeds
+            .get(position)
+            .copied()
+            .flatten()
+            .filter(|_| attempt <= 1)
+    }
+
+    /// Get the idle time to wait before asking a query.
+    ///
+    /// Returns `None` for retries and for queries that were asked right away in the replayed session.
+    ///
+    /// # Arguments
+    ///
+    /// * `position`: The position of the query in [`Replay::queries`].
+    /// * `attempt`: How often the query has been asked, starting at `1`.
+    pub fn idle_gap(&self, position: usize, attempt: u32) -> Option<Duration> {
+        self.idle_gaps
+            .get(position)
+            .copied()
+            .flatten()
+            .filter(|_| attempt <= 1)
+    }
+}
+
+/// Get the seeds every attempt of the queries of a session was asked with, in the order they were asked in, e.g. to
+/// simulate the session with the same retries, see
+/// [`Seeds::Replayed`](crate::assistant::conditions::Seeds::Replayed).
+///
+/// The turns of a conversation are asked with the seed of its first turn, so they are left out.
+///
+/// # Arguments
+///
+/// * `interactions`: The interactions of the session, ordered by when they started.
+pub fn attempt_seeds(interactions: &[Interaction]) -> VecDeque<u64> {
+    interactions
+        .iter()
+        .filter(|interaction| interaction.conversation_turn.unwrap_or_default() == 0)
+        .filter_map(|interaction| conditions(interaction).map(|conditions| conditions.seed))
+        .collect()
+}
+
+fn conditions(interaction: &Interaction) -> Option<Conditions> {
+    interaction
+        .conditions
+        .as_deref()
+        .and_then(|conditions| serde_json::from_str(conditions).ok())
+}
@@ -0,0 +1,80 @@
+use std::time::Duration;
+
+/// When and how often to retry queries whose interaction failed, e.g. because the assistant did not answer.
+///
+/// The delay before each retry doubles with every failed attempt, so an assistant that is briefly unavailable is not
+/// asked the same query over and over.
+pub struct RetryPolicy {
+    /// How often to ask a query at most, including the first attempt.
+    pub max_attempts: u32,
+    /// How long to wait before the first retry.
+    pub initial_delay: Duration,
+    /// How long to wait before a retry at most.
+    pub max_delay: Duration,
+    /// Whether to retry failed queries at the end of the session instead of right away.
+    pub requeue: bool,
+}
+
+impl RetryPolicy {
+    /// Create a policy whose delay before a retry is capped at five minutes.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_attempts`: How often to ask a query at most, including the first attempt.
+    /// * `initial_delay`: How long to wait before the first retry.
+    /// * `requeue`: Whether to retry failed queries at the end of the session instead of right away.
+    pub fn new(max_attempts: u32, initial_delay: Duration, requeue: bool) -> Self {
+        RetryPolicy {
+            max_attempts,
+            initial_delay,
+            max_delay: Duration::from_secs(300),
+            requeue,
+        }
+    }
+
+    /// Whether a query should be asked again after an attempt failed.
+    ///
+    /// # Arguments
+    ///
+    /// * `attempt`: The number of the failed attempt, starting at `1`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use varys::assistant::retry::RetryPolicy;
+    /// let retry_policy = RetryPolicy::new(3, Duration::from_secs(5), false);
+    ///
+    /// assert!(retry_policy.should_retry(2));
+    /// assert!(!retry_policy.should_retry(3));
+    /// ```
+    pub fn should_retry(&self, attempt: u32) -> bool {
+        attempt < self.max_attempts
+    }
+
+    /// How long to wait before asking a query again after an attempt failed.
+    ///
+    /// # Arguments
+    ///
+    /// * `attempt`: The number of the failed attempt, starting at `1`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use varys::assistant::retry::RetryPolicy;
+    /// let retry_policy = RetryPolicy::new(10, Duration::from_secs(5), true);
+    ///
+    /// assert_eq!(retry_policy.delay(1), Duration::from_secs(5));
+    /// assert_eq!(retry_policy.delay(3), Duration::from_secs(20));
+    /// assert_eq!(retry_policy.delay(9), Duration::from_secs(300));
+    /// ```
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2_u32.saturating_pow(attempt.saturating_sub(1));
+
+        self.initial_delay
+            .checked_mul(factor)
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay)
+    }
+}
@@ -0,0 +1,82 @@
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::warn;
+use tokio::sync::Notify;
+
+/// A request to stop collecting data, shared by everything that should stop.
+///
+/// Once requested, an [`Interactor`](crate::assistant::interactor::Interactor) aborts its current interaction and
+/// finalises its session instead of starting the next interaction, leaving it open so it can be resumed.
+#[derive(Clone, Debug, Default)]
+pub struct Shutdown {
+    requested: Arc<AtomicBool>,
+    notify: Arc<Notify>,
+}
+
+impl Shutdown {
+    /// Create a shutdown that is requested by pressing Ctrl-C.
+    ///
+    /// Pressing Ctrl-C a second time exits right away, without finalising the current session. This has to be called
+    /// from within a tokio runtime.
+    pub fn on_ctrl_c() -> Self {
+        let shutdown = Self::default();
+        let handler = shutdown.clone();
+
+        tokio::spawn(async move {
+            while tokio::signal::ctrl_c().await.is_ok() {
+                if handler.is_requested() {
+                    warn!("Received SIGINT again, exiting without completing the session...");
+                    process::exit(130);
+                }
+
+                warn!("Received SIGINT, completing the session before exiting (press Ctrl-C again to exit now)...");
+                handler.request();
+            }
+        });
+
+        shutdown
+    }
+
+    /// Request the shutdown, waking up everything that waits in [`Shutdown::sleep`].
+    pub fn request(&self) {
+        self.requested.store(true, Ordering::SeqCst);
+        self.notify.notify_waiters();
+    }
+
+    /// Whether the shutdown was requested.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys::assistant::shutdown::Shutdown;
+    /// let shutdown = Shutdown::default();
+    /// let clone = shutdown.clone();
+    ///
+    /// assert!(!clone.is_requested());
+    /// shutdown.request();
+    /// assert!(clone.is_requested());
+    /// ```
+    pub fn is_requested(&self) -> bool {
+        self.requested.load(Ordering::SeqCst)
+    }
+
+    /// Sleep for a duration or until the shutdown is requested, whichever happens first.
+    ///
+    /// # Arguments
+    ///
+    /// * `duration`: How long to sleep.
+    pub async fn sleep(&self, duration: Duration) {
+        let notified = self.notify.notified();
+        if self.is_requested() {
+            return;
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(duration) => {}
+            _ = notified => {}
+        }
+    }
+}
@@ -0,0 +1,381 @@
+use std::collections::{HashMap, VecDeque};
+use std::f32::consts::PI;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use log::info;
+use varys_audio::audio::{AudioData, OPUS_SAMPLE_RATE};
+use varys_audio::error::Error;
+use varys_audio::listen::vad::SilenceDetector;
+use varys_audio::listen::{Listen, ListenInstance};
+use varys_audio::stt::Recognise;
+use varys_audio::tts::Speak;
+use varys_audio::watermark::Watermark;
+use varys_database::database::interaction::Interaction;
+
+/// How long the simulated speaker takes for each character of phrases that were not said in the simulated session.
+const MILLISECONDS_PER_CHARACTER: u64 = 60;
+/// The frequency of the tone that stands in for a response in hertz.
+const RESPONSE_FREQUENCY: f32 = 440.0;
+/// The amplitude of the tone that stands in for a response, which is far above any sensible sensitivity.
+const RESPONSE_AMPLITUDE: f32 = 0.5;
+/// The level of the simulated ambient noise.
+const AMBIENT_LEVEL: f32 = 0.001;
+/// How many ambient noise levels a calibration of the simulated listener returns.
+const AMBIENT_LEVEL_COUNT: usize = 100;
+
+/// How a collected interaction went, which a [`Simulation`] replays.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InteractionTimings {
+    /// How long it took to say the query.
+    pub query_duration: Duration,
+    /// How long after the end of the query the response started, or `None` if there was no response.
+    pub response_latency: Option<Duration>,
+    /// How long the response lasted.
+    pub response_duration: Duration,
+    /// The transcript of the response.
+    pub response: Option<String>,
+}
+
+impl From<&Interaction> for InteractionTimings {
+    fn from(interaction: &Interaction) -> Self {
+        let millis = |value: Option<i32>| Duration::from_millis(value.unwrap_or_default() as u64);
+
+        InteractionTimings {
+            query_duration: millis(interaction.query_duration),
+            response_latency: interaction
+                .response_latency_ms
+                .map(|latency| millis(Some(latency))),
+            response_duration: millis(interaction.response_duration),
+            response: interaction.response.clone(),
+        }
+    }
+}
+
+/// What the backends of a [`Simulation`] share.
+#[derive(Default)]
+struct SimulationState {
+    /// The timings of the interactions by their spoken query, in the order they were asked in.
+    timings: HashMap<String, VecDeque<InteractionTimings>>,
+    /// The interaction whose query was said last.
+    current: Option<InteractionTimings>,
+    /// The transcripts of the recorded responses that were not recognised yet, in the order they were recorded in.
+    transcripts: VecDeque<String>,
+}
+
+/// A collected session replayed by fake backends, so changes to the scheduling, timing and database logic of varys
+/// can be tested against realistic workloads without any hardware.
+///
+/// The [`SimulatedSpeaker`] takes as long to say a query as it took in the session. The [`SimulatedListener`] then
+/// records a tone that starts and lasts like the response to the query did, which the [`SimulatedRecogniser`]
+/// transcribes as the transcript of the response. Queries that were not asked in the session are never answered.
+///
+/// The backends share the state of the simulation, so they have to be used with the same interactor.
+#[derive(Clone)]
+pub struct Simulation {
+    state: Arc<Mutex<SimulationState>>,
+    /// How many times faster than in the session the backends run.
+    speed: f32,
+}
+
+impl Simulation {
+    /// Create a simulation of the interactions of a session.
+    ///
+    /// # Arguments
+    ///
+    /// * `interactions`: The interactions of the session.
+    /// * `speed`: How many times faster than in the session the backends run. The audio they record is as long as in
+    ///   the session, so the durations and latencies measured by the interactor are not affected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys::assistant::simulation::Simulation;
+    /// # use varys_audio::tts::Speak;
+    /// let simulation = Simulation::new(&[], 10.0);
+    ///
+    /// // nothing was asked in the session, so the query is not answered
+    /// assert_eq!(simulation.speaker().say("Hey Siri. Hello").unwrap(), 900);
+    /// ```
+    pub fn new(interactions: &[Interaction], speed: f32) -> Self {
+        let mut interactions: Vec<&Interaction> = interactions.iter().collect();
+        interactions.sort_by_key(|interaction| interaction.started);
+
+        let mut timings: HashMap<String, VecDeque<InteractionTimings>> = HashMap::new();
+        for interaction in interactions {
+            timings
+                .entry(interaction.spoken_query())
+                .or_default()
+                .push_back(interaction.into());
+        }
+
+        Simulation {
+            state: Arc::new(Mutex::new(SimulationState {
+                timings,
+                ..SimulationState::default()
+            })),
+            speed: speed.max(f32::EPSILON),
+        }
+    }
+
+    /// Create a speaker that says queries like in the session.
+    pub fn speaker(&self) -> SimulatedSpeaker {
+        SimulatedSpeaker {
+            simulation: self.clone(),
+        }
+    }
+
+    /// Create a listener that records the responses of the session.
+    pub fn listener(&self) -> SimulatedListener {
+        SimulatedListener {
+            simulation: self.clone(),
+            recording_timeout: None,
+        }
+    }
+
+    /// Create a recogniser that transcribes recorded responses with their transcripts from the session.
+    pub fn recogniser(&self) -> SimulatedRecogniser {
+        SimulatedRecogniser {
+            simulation: self.clone(),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, SimulationState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Wait for a duration of the session, shortened by the speed of the simulation.
+    fn wait(&self, duration: Duration) {
+        thread::sleep(duration.div_f32(self.speed));
+    }
+}
+
+/// A [`Speak`] implementation that takes as long to say a query as it took in a simulated session, see
+/// [`Simulation`].
+pub struct SimulatedSpeaker {
+    simulation: Simulation,
+}
+
+impl Speak for SimulatedSpeaker {
+    fn set_voice(&mut self, id: &str) -> Result<(), Error> {
+        info!("Using simulated voice {id}");
+
+        Ok(())
+    }
+
+    fn say(&self, text: &str) -> Result<i32, Error> {
+        let timings = self
+            .simulation
+            .state()
+            .timings
+            .get_mut(text)
+            .and_then(|timings| timings.pop_front());
+        let duration = match &timings {
+            Some(timings) => timings.query_duration,
+            None => {
+                info!("\"{text}\" was not said in the simulated session");
+                Duration::from_millis(text.chars().count() as u64 * MILLISECONDS_PER_CHARACTER)
+            }
+        };
+        self.simulation.state().current = timings;
+
+        info!("Simulating saying \"{text}\"");
+        self.simulation.wait(duration);
+
+        Ok(duration.as_millis() as i32)
+    }
+
+    fn play(&self, audio: &AudioData) -> Result<i32, Error> {
+        info!("Simulating playing {}ms of audio", audio.duration_ms());
+
+        // recordings cannot be matched to the interactions of the session
+        self.simulation.state().current = None;
+        self.simulation
+            .wait(Duration::from_millis(audio.duration_ms() as u64));
+
+        Ok(audio.duration_ms())
+    }
+
+    fn set_watermark(&mut self, _: Option<Watermark>) {
+        // nothing is played, so there is nothing to watermark
+    }
+
+    fn set_rate(&mut self, rate: f32) -> Result<(), Error> {
+        if rate <= 0.0 || !rate.is_finite() {
+            return Err(Error::OutOfRange);
+        }
+
+        // the durations of the session already include the rates the queries were said at
+        Ok(())
+    }
+}
+
+/// A [`Listen`] implementation that records the response to the query that was said last like in a simulated
+/// session, see [`Simulation`].
+pub struct SimulatedListener {
+    simulation: Simulation,
+    recording_timeout: Option<Duration>,
+}
+
+impl Listen for SimulatedListener {
+    fn start(&self) -> Result<Box<dyn ListenInstance>, Error> {
+        Ok(Box::new(SimulatedListenerInstance {
+            started: Instant::now(),
+            speed: self.simulation.speed,
+        }))
+    }
+
+    fn record_until_silent_untrimmed(
+        &self,
+        silence_duration: Duration,
+        silence_threshold: f32,
+    ) -> Result<AudioData, Error> {
+        match self.record_until_silent_or_timeout(silence_duration, silence_threshold)? {
+            (_, true) => Err(Error::RecordingTimeout),
+            (audio, false) => Ok(audio),
+        }
+    }
+
+    fn record_until_silent_or_timeout(
+        &self,
+        silence_duration: Duration,
+        _: f32,
+    ) -> Result<(AudioData, bool), Error> {
+        let current = self.simulation.state().current.take();
+        let Some((latency, timings)) =
+            current.and_then(|timings| timings.response_latency.map(|latency| (latency, timings)))
+        else {
+            // without a response, the listener keeps waiting for sound
+            return match self.recording_timeout {
+                Some(recording_timeout) => {
+                    self.simulation.wait(recording_timeout);
+                    Err(Error::RecordingTimeout)
+                }
+                None => {
+                    self.simulation.wait(silence_duration);
+                    Ok((silence(silence_duration), false))
+                }
+            };
+        };
+
+        let length = latency + timings.response_duration + silence_duration;
+        if let Some(recording_timeout) = self
+            .recording_timeout
+            .filter(|recording_timeout| length > *recording_timeout)
+        {
+            self.simulation.wait(recording_timeout);
+            if recording_timeout <= latency {
+                return Err(Error::RecordingTimeout);
+            }
+
+            // the response is cut off by the timeout
+            self.simulation
+                .state()
+                .transcripts
+                .push_back(timings.response.unwrap_or_default());
+
+            let mut audio = silence(latency);
+            audio
+                .data
+                .extend(tone(timings.response_duration.min(recording_timeout - latency)).data);
+            return Ok((audio, true));
+        }
+        self.simulation.wait(length);
+
+        self.simulation
+            .state()
+            .transcripts
+            .push_back(timings.response.unwrap_or_default());
+
+        let mut audio = silence(latency);
+        audio.data.extend(tone(timings.response_duration).data);
+        audio.data.extend(silence(silence_duration).data);
+
+        Ok((audio, false))
+    }
+
+    fn wait_until_silent(&self, silence_duration: Duration, _: f32, _: bool) -> Result<(), Error> {
+        self.simulation.wait(silence_duration);
+
+        Ok(())
+    }
+
+    fn set_recording_timeout(&mut self, recording_timeout: Option<Duration>) {
+        self.recording_timeout = recording_timeout;
+    }
+
+    fn set_silence_detector(&mut self, _: Box<dyn SilenceDetector>) {}
+
+    fn set_pre_roll(&mut self, _: Option<Duration>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn set_reconnect_attempts(&mut self, _: u32) {}
+
+    fn ambient_levels(&self) -> Result<Vec<f32>, Error> {
+        Ok(vec![AMBIENT_LEVEL; AMBIENT_LEVEL_COUNT])
+    }
+}
+
+/// A running [`SimulatedListener`] recording, which records silence.
+pub struct SimulatedListenerInstance {
+    started: Instant,
+    speed: f32,
+}
+
+impl ListenInstance for SimulatedListenerInstance {
+    fn stop(self: Box<Self>) -> Result<AudioData, Error> {
+        Ok(silence(self.started.elapsed().mul_f32(self.speed)))
+    }
+}
+
+/// A [`Recognise`] implementation that transcribes the responses recorded by a [`SimulatedListener`] with their
+/// transcripts from a simulated session, see [`Simulation`].
+pub struct SimulatedRecogniser {
+    simulation: Simulation,
+}
+
+impl Recognise for SimulatedRecogniser {
+    fn recognise(&self, audio: &mut AudioData) -> Result<String, Error> {
+        // silent recordings are not responses of the session
+        if audio
+            .data
+            .iter()
+            .all(|sample| sample.abs() < RESPONSE_AMPLITUDE / 2.0)
+        {
+            return Ok(String::new());
+        }
+
+        Ok(self
+            .simulation
+            .state()
+            .transcripts
+            .pop_front()
+            .unwrap_or_default())
+    }
+}
+
+fn silence(duration: Duration) -> AudioData {
+    samples(duration, |_| 0.0)
+}
+
+fn tone(duration: Duration) -> AudioData {
+    samples(duration, |time| {
+        (2.0 * PI * RESPONSE_FREQUENCY * time).sin() * RESPONSE_AMPLITUDE
+    })
+}
+
+/// Create mono audio of a duration with the sample of every point in time in seconds.
+fn samples(duration: Duration, sample: impl Fn(f32) -> f32) -> AudioData {
+    let sample_rate = OPUS_SAMPLE_RATE as u32;
+    let sample_count = (duration.as_secs_f32() * sample_rate as f32) as usize;
+
+    AudioData {
+        data: (0..sample_count)
+            .map(|index| sample(index as f32 / sample_rate as f32))
+            .collect(),
+        channels: 1,
+        sample_rate,
+    }
+}
@@ -1,14 +1,20 @@
 use std::time::Duration;
 
+#[cfg(feature = "collection")]
 use colored::Colorize;
+#[cfg(feature = "collection")]
 use log::info;
 
+#[cfg(feature = "collection")]
 use varys_audio::tts::Speaker;
 
+#[cfg(feature = "collection")]
 use crate::assistant::interactor::Interactor;
-use crate::assistant::{Error, VoiceAssistant};
+use crate::assistant::VoiceAssistant;
+#[cfg(feature = "collection")]
 use crate::cli::{interact, key_type::KeyType};
-use crate::query::Query;
+#[cfg(feature = "collection")]
+use crate::error::Error;
 
 /// The [`VoiceAssistant`] implementation for Siri. Tested with the HomePod.
 pub struct Siri {}
@@ -27,6 +33,7 @@ impl VoiceAssistant for Siri {
         "Hey Siri".to_string()
     }
 
+    #[cfg(feature = "collection")]
     fn setup(&self) -> Result<(), Error> {
         info!("Starting Siri setup...");
 
@@ -80,14 +87,7 @@ impl VoiceAssistant for Siri {
         Ok(())
     }
 
-    fn prepare_queries(&self, queries: &mut Vec<Query>) {
-        info!("Preparing queries for Siri...");
-
-        queries.iter_mut().for_each(|q| {
-            q.text = format!("Hey Siri. {}", q.text);
-        });
-    }
-
+    #[cfg(feature = "collection")]
     fn stop_assistant(&self, interactor: &Interactor) -> Result<(), Error> {
         info!("Telling Siri to stop...");
 
@@ -101,6 +101,7 @@ impl VoiceAssistant for Siri {
         Ok(())
     }
 
+    #[cfg(feature = "collection")]
     fn reset_assistant(&self, interactor: &Interactor) -> Result<(), Error> {
         info!("Telling Siri to stop everything...");
 
@@ -124,6 +125,7 @@ impl VoiceAssistant for Siri {
         Ok(())
     }
 
+    #[cfg(feature = "collection")]
     fn test_voices(&self, voices: Vec<String>) -> Result<(), Error> {
         info!("Testing Siri voices...");
 
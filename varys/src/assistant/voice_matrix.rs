@@ -0,0 +1,201 @@
+use std::fmt::{Display, Formatter};
+use std::thread;
+use std::time::Duration;
+
+use log::{info, warn};
+use varys_audio::listen::Listener;
+use varys_audio::tts::Speaker;
+use varys_database::database::wake_word_trigger::MIN_TRIGGER_RATE;
+
+use crate::assistant::VoiceAssistant;
+use crate::error::Error;
+
+/// The queries each voice asks by default, which most assistants answer in any language setting.
+pub const PROBE_QUERIES: &[&str] = &[
+    "What time is it?",
+    "What is my name?",
+    "How tall is Mount Everest?",
+    "What is the weather like today?",
+];
+
+/// Whether a voice triggered the assistant with a probe query.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Probe {
+    /// The voice that asked the query.
+    pub voice: String,
+    /// The query that was asked, without the wake word.
+    pub query: String,
+    /// Whether the assistant responded.
+    pub triggered: bool,
+}
+
+/// Which voices trigger an assistant, found by asking a number of probe queries with every voice.
+///
+/// Some combinations of assistant and voice never trigger the wake word, e.g. because of the accent of the voice.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct VoiceMatrix {
+    /// The result of every probe, in the order they were asked.
+    pub probes: Vec<Probe>,
+}
+
+impl VoiceMatrix {
+    /// Ask every probe query with every voice and record whether the assistant responded.
+    ///
+    /// The assistant counts as triggered if any sound above the sensitivity is heard before the timeout.
+    ///
+    /// # Arguments
+    ///
+    /// * `assistant`: The assistant to test.
+    /// * `voices`: The voices to test.
+    /// * `queries`: The probe queries to ask with each voice, without the wake word.
+    /// * `sensitivity`: The sensitivity of the listener.
+    /// * `timeout`: How long to wait for the assistant to respond.
+    pub fn run(
+        assistant: &dyn VoiceAssistant,
+        voices: &[String],
+        queries: &[String],
+        sensitivity: f32,
+        timeout: Duration,
+    ) -> Result<Self, Error> {
+        info!(
+            "Testing {} voices with {} probes for {}...",
+            voices.len(),
+            queries.len(),
+            assistant.name()
+        );
+
+        let mut speaker = Speaker::new()?;
+        let mut listener = Listener::new()?;
+        listener.recording_timeout = Some(timeout);
+
+        let mut matrix = VoiceMatrix::default();
+        for voice in voices {
+            speaker.set_voice(voice)?;
+
+            for query in queries {
+                speaker.say(&assistant.wake_phrase(query))?;
+                let triggered = match listener
+                    .record_until_silent_untrimmed(assistant.silence_after_talking(), sensitivity)
+                {
+                    Ok(audio) => audio.onset_ms(sensitivity).is_some(),
+                    Err(varys_audio::error::Error::RecordingTimeout) => false,
+                    Err(error) => return Err(error.into()),
+                };
+
+                if triggered {
+                    info!("{voice} triggered {} with \"{query}\"", assistant.name());
+                } else {
+                    warn!(
+                        "{voice} did not trigger {} with \"{query}\"",
+                        assistant.name()
+                    );
+                }
+
+                matrix.probes.push(Probe {
+                    voice: voice.clone(),
+                    query: query.clone(),
+                    triggered,
+                });
+                thread::sleep(assistant.silence_between_interactions());
+            }
+        }
+
+        Ok(matrix)
+    }
+
+    /// The share of probes with which a voice triggered the assistant.
+    ///
+    /// Returns `None` if the voice was not tested.
+    ///
+    /// # Arguments
+    ///
+    /// * `voice`: The voice.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys::assistant::voice_matrix::{Probe, VoiceMatrix};
+    /// let probe = |voice: &str, triggered| Probe {
+    ///     voice: voice.to_string(),
+    ///     query: "What time is it?".to_string(),
+    ///     triggered,
+    /// };
+    /// let matrix = VoiceMatrix {
+    ///     probes: vec![probe("Zoe", true), probe("Zoe", false), probe("Isha", false)],
+    /// };
+    ///
+    /// assert_eq!(matrix.trigger_rate("Zoe"), Some(0.5));
+    /// assert_eq!(matrix.trigger_rate("Isha"), Some(0.0));
+    /// assert_eq!(matrix.trigger_rate("Ava"), None);
+    /// assert_eq!(matrix.unreliable_voices(), vec!["Zoe", "Isha"]);
+    /// ```
+    pub fn trigger_rate(&self, voice: &str) -> Option<f32> {
+        let probes: Vec<&Probe> = self
+            .probes
+            .iter()
+            .filter(|probe| probe.voice == voice)
+            .collect();
+
+        (!probes.is_empty()).then(|| {
+            probes.iter().filter(|probe| probe.triggered).count() as f32 / probes.len() as f32
+        })
+    }
+
+    /// The voices that triggered the assistant with too few probes to be used for collection.
+    pub fn unreliable_voices(&self) -> Vec<&str> {
+        self.voices()
+            .into_iter()
+            .filter(|voice| {
+                self.trigger_rate(voice)
+                    .is_some_and(|rate| rate < MIN_TRIGGER_RATE)
+            })
+            .collect()
+    }
+
+    /// The tested voices, in the order they were tested.
+    fn voices(&self) -> Vec<&str> {
+        let mut voices: Vec<&str> = Vec::new();
+        for probe in &self.probes {
+            if !voices.contains(&probe.voice.as_str()) {
+                voices.push(&probe.voice);
+            }
+        }
+
+        voices
+    }
+}
+
+impl Display for VoiceMatrix {
+    /// A compatibility report with a row per voice, marking the probes that triggered the assistant.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let width = self
+            .voices()
+            .iter()
+            .map(|voice| voice.len())
+            .max()
+            .unwrap_or_default();
+
+        for voice in self.voices() {
+            let marks: String = self
+                .probes
+                .iter()
+                .filter(|probe| probe.voice == voice)
+                .map(|probe| if probe.triggered { '✓' } else { '✗' })
+                .collect();
+            let rate = self.trigger_rate(voice).unwrap_or_default();
+
+            writeln!(f, "{voice:width$}  {marks}  {:.0}%", rate * 100.0)?;
+        }
+
+        let unreliable = self.unreliable_voices();
+        if !unreliable.is_empty() {
+            write!(
+                f,
+                "Consider removing {} from the voice rotation",
+                unreliable.join(", ")
+            )?;
+        }
+
+        Ok(())
+    }
+}
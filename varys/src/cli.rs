@@ -1,34 +1,182 @@
+use chrono::Utc;
 use clap::Parser;
-use log::{debug, error, info};
+#[cfg(feature = "analysis")]
+use colored::Colorize;
+#[cfg(feature = "collection")]
+use log::error;
+#[cfg(feature = "analysis")]
+use log::warn;
+use log::{debug, info};
+#[cfg(feature = "analysis")]
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+#[cfg(feature = "analysis")]
+use std::fs;
+#[cfg(any(feature = "collection", feature = "analysis"))]
 use std::path::Path;
+#[cfg(feature = "analysis")]
+use std::path::PathBuf;
+#[cfg(feature = "analysis")]
 use std::str::FromStr;
-use std::{thread, time};
-use varys_analysis::ml::data::NumericTraceDataset;
-use varys_analysis::{ml, plot};
-use varys_audio::listen::Listener;
+#[cfg(all(feature = "collection", feature = "transcription"))]
+use std::thread;
+#[cfg(feature = "collection")]
+use std::time;
+#[cfg(feature = "analysis")]
+use varys_analysis::ml::data::{NumericTraceDataset, TraceRepresentation};
+#[cfg(feature = "analysis")]
+use varys_analysis::ml::label_map::{self, LabelMap};
+#[cfg(feature = "analysis")]
+use varys_analysis::ml::metric::{MetricPlugins, TopKAccuracy};
+#[cfg(feature = "analysis")]
+use varys_analysis::ml::run;
+#[cfg(feature = "analysis")]
+use varys_analysis::ml::server::InferenceServer;
+#[cfg(feature = "analysis")]
+use varys_analysis::ml::transfer;
+#[cfg(feature = "analysis")]
+use varys_analysis::ml::TrainingOptions;
+#[cfg(feature = "analysis")]
+use varys_analysis::regime::{self, SessionFeatures};
+#[cfg(feature = "analysis")]
+use varys_analysis::trace::TrafficTrace;
+#[cfg(feature = "analysis")]
+use varys_analysis::{ml, outlier, plot, timing};
+#[cfg(feature = "collection")]
+use varys_audio::audio::{LevelControl, SilencePadding};
+#[cfg(feature = "collection")]
+use varys_audio::echo::EchoCanceller;
+#[cfg(feature = "collection")]
+use varys_audio::listen::{Listen, Listener};
+#[cfg(feature = "collection")]
+use varys_audio::resample::ResampleQuality;
+#[cfg(all(feature = "collection", feature = "transcription"))]
 use varys_audio::stt::transcriber::Transcriber;
+#[cfg(all(feature = "collection", feature = "transcription"))]
 use varys_audio::stt::Recogniser;
+#[cfg(all(feature = "collection", feature = "transcription"))]
 use varys_audio::tts::Speaker;
+#[cfg(feature = "collection")]
+use varys_audio::voice::VoiceProfile;
+#[cfg(feature = "collection")]
+use varys_audio::watermark::Watermark;
 use varys_database::database;
+use varys_database::database::dataset::NamedDataset;
+#[cfg(feature = "analysis")]
 use varys_database::database::interaction::Interaction;
+#[cfg(feature = "analysis")]
+use varys_database::database::outlier::{Outlier, COLLECTION_METRICS};
+#[cfg(feature = "analysis")]
+use varys_database::database::relabel::Relabel;
+#[cfg(feature = "analysis")]
+use varys_database::database::review::Review;
+use varys_database::database::schedule::Schedule;
+use varys_database::database::session::Session;
+#[cfg(feature = "analysis")]
+use varys_database::database::session_event::SessionEvent;
+#[cfg(feature = "analysis")]
+use varys_database::database::session_regime::SessionRegime;
+use varys_database::database::wake_word_trigger::WakeWordTrigger;
+#[cfg(feature = "analysis")]
 use varys_network::address::MacAddress;
 use varys_network::sniff;
+#[cfg(feature = "collection")]
+use varys_network::sniff::radio::RadioSniffer;
+#[cfg(feature = "analysis")]
+use varys_network::sniff::replay::ReplaySniffer;
+#[cfg(feature = "collection")]
+use varys_network::sniff::Sniff;
 use varys_network::sniff::{ConnectionStatus, Sniffer};
 
 use crate::assistant;
-use crate::assistant::interactor::Interactor;
+#[cfg(feature = "collection")]
+use crate::assistant::conditions::Seeds;
+#[cfg(feature = "collection")]
+use crate::assistant::coordinator::{Coordinator, TimeSlots};
+#[cfg(feature = "collection")]
+use crate::assistant::fleet::Fleet;
+#[cfg(feature = "collection")]
+use crate::assistant::idle_gap::IdleGap;
+#[cfg(feature = "collection")]
+use crate::assistant::interactor::{Backends, Interactor};
+#[cfg(feature = "collection")]
+use crate::assistant::mute::{MuteControl, MuteExperiment};
+#[cfg(feature = "collection")]
+use crate::assistant::power::PowerCycle;
+#[cfg(feature = "collection")]
+use crate::assistant::quiescence::QuiescenceDetector;
+#[cfg(feature = "analysis")]
+use crate::assistant::quiescence::BACKGROUND_DOWNLOAD_EVENT;
+#[cfg(feature = "collection")]
+use crate::assistant::rate_sweep::RateSweep;
+#[cfg(feature = "collection")]
+use crate::assistant::recalibration::Recalibration;
+#[cfg(feature = "collection")]
+use crate::assistant::replay::Replay;
+#[cfg(feature = "collection")]
+use crate::assistant::retry::RetryPolicy;
+#[cfg(feature = "collection")]
+use crate::assistant::shutdown::Shutdown;
+#[cfg(feature = "collection")]
+use crate::assistant::voice_matrix::{VoiceMatrix, PROBE_QUERIES};
+use crate::assistant::AssistantRegistry;
+#[cfg(feature = "analysis")]
+use crate::assistant::VoiceAssistant;
+#[cfg(feature = "analysis")]
+use crate::cli::arguments::AnalyseSubcommand;
+#[cfg(feature = "analysis")]
+use crate::cli::arguments::PipelineSubcommand;
 use crate::cli::arguments::{
-    AnalyseSubcommand, Arguments, AssistantCommand, AssistantSubcommand, Command, ListenCommand,
-    SniffCommand,
+    Arguments, AssistantsSubcommand, Command, DatasetSubcommand, ScheduleSubcommand,
+    SessionSubcommand, SniffCommand,
 };
-use crate::dataset::DatasetSize;
+#[cfg(feature = "collection")]
+use crate::cli::arguments::{
+    AssistantCommand, AssistantSubcommand, AudioSubcommand, DevicesSubcommand, ListenCommand,
+    VoiceProfileCommand,
+};
+#[cfg(feature = "analysis")]
+use crate::dataset::{Dataset, DatasetSampler, DatasetSize};
 use crate::error::Error;
+#[cfg(feature = "collection")]
+use crate::host::ThermalMonitor;
+#[cfg(feature = "collection")]
+use crate::query;
 use crate::query::Query;
+#[cfg(feature = "collection")]
+use crate::query::QuerySource;
+#[cfg(any(
+    feature = "collection",
+    feature = "transcription",
+    feature = "analysis"
+))]
+use crate::redact::Redactor;
+#[cfg(feature = "analysis")]
+use crate::response::{InteractionStatus, ResponseType};
+use crate::schedule::Expression;
+#[cfg(feature = "collection")]
+use crate::schedule::Scheduler;
 
 pub mod arguments;
+mod doctor;
+#[cfg(feature = "analysis")]
 mod export;
 pub mod interact;
 pub mod key_type;
+#[cfg(feature = "analysis")]
+mod pipeline;
+#[cfg(all(feature = "analysis", feature = "transcription"))]
+mod relabel;
+mod relocate;
+#[cfg(feature = "collection")]
+mod room;
+#[cfg(feature = "collection")]
+mod selftest;
+#[cfg(feature = "collection")]
+mod simulate;
+#[cfg(feature = "transcription")]
+mod transcribe;
+#[cfg(feature = "viewer")]
+mod viewer;
 
 /// Start the cli program.
 ///
@@ -37,51 +185,210 @@ pub async fn run() -> Result<(), Error> {
     let arguments = Arguments::parse();
 
     match arguments.command {
-        Command::Assistant(command) => assistant_command(command),
+        #[cfg(feature = "collection")]
+        Command::Assistant(command) => assistant_command(command, arguments.sensitivity),
+        #[cfg(feature = "collection")]
         Command::Listen(command) => listen_command(
             arguments.voices.first().ok_or(Error::NoVoiceProvided)?,
             arguments.sensitivity,
             arguments.model,
             command,
         ),
+        Command::Assistants(command) => assistants_command(command.command).await,
         Command::Sniff(command) => sniff_command(&arguments.interface, command),
+        #[cfg(feature = "collection")]
+        Command::Audio(command) => audio_command(command.command).await,
+        #[cfg(feature = "collection")]
+        Command::Devices(command) => devices_command(command.command),
+        #[cfg(feature = "collection")]
         Command::Run(command) => {
             run_command(
                 &arguments.interface,
                 arguments.voices,
                 arguments.sensitivity,
                 arguments.model,
-                command,
+                *command,
+            )
+            .await
+        }
+        #[cfg(feature = "collection")]
+        Command::Fleet(command) => {
+            Fleet::new(
+                Fleet::read_toml(&command.fleet)?,
+                arguments.sensitivity,
+                arguments.model,
+                command.data_dir,
+            )
+            .run(
+                Query::read(&command.queries).await?,
+                &command.assistant,
+                command.allow_repeats,
+            )
+            .await
+        }
+        Command::Schedule(command) => {
+            schedule_command(
+                command.command,
+                #[cfg(feature = "collection")]
+                Scheduler::new(
+                    arguments.interface,
+                    arguments.voices,
+                    arguments.sensitivity,
+                    arguments.model,
+                ),
+            )
+            .await
+        }
+        Command::Session(command) => session_command(command.command).await,
+        Command::Dataset(command) => dataset_command(command.command).await,
+        Command::Relocate(command) => {
+            relocate::run(&command.from, &command.to, command.dry_run).await
+        }
+        #[cfg(feature = "transcription")]
+        Command::Transcribe(command) => {
+            transcribe::run(
+                &command.data_dir,
+                &arguments.model,
+                command.pending,
+                &command.ids,
+                command.bias_vocabulary,
+                command
+                    .redact
+                    .map(Redactor::read_toml)
+                    .transpose()?
+                    .as_ref(),
+            )
+            .await
+        }
+        #[cfg(feature = "collection")]
+        Command::Selftest(command) => selftest::run(arguments.sensitivity, &command.data_dir).await,
+        #[cfg(feature = "collection")]
+        Command::Simulate(command) => {
+            simulate::run(
+                command.session_id,
+                command.speed,
+                command
+                    .min_idle_gap
+                    .zip(command.max_idle_gap)
+                    .map(|(min, max)| {
+                        IdleGap::new(
+                            time::Duration::from_secs(min),
+                            time::Duration::from_secs(max),
+                        )
+                    }),
+                arguments.sensitivity,
+            )
+            .await
+        }
+        Command::Doctor(command) => {
+            doctor::run(
+                &arguments.interface,
+                &arguments.voices,
+                &arguments.model,
+                &command.data_dir,
             )
             .await
         }
+        #[cfg(feature = "analysis")]
         Command::Analyse(command) => {
-            analyse_command(command.dataset, command.command, &arguments.interface).await
+            analyse_command(
+                dataset(
+                    command.dataset,
+                    command.dataset_file,
+                    command
+                        .assistant
+                        .map(|assistant| assistant::from(&assistant).wake_word())
+                        .as_deref(),
+                    command.categories,
+                    command.per_category,
+                    command.sample_seed,
+                )?,
+                command.command,
+                &arguments.interface,
+                #[cfg(feature = "transcription")]
+                &arguments.model,
+            )
+            .await
         }
+        #[cfg(feature = "analysis")]
         Command::Export(export_command) => {
             export_command
                 .format
                 .export(
                     export_command.data_dir,
-                    &export_command.dataset,
+                    &dataset(
+                        export_command.dataset,
+                        export_command.dataset_file,
+                        Some(&assistant::from(&export_command.assistant).wake_word()),
+                        export_command.categories,
+                        export_command.per_category,
+                        export_command.sample_seed,
+                    )?,
                     assistant::from(&export_command.assistant),
+                    export_command
+                        .redact
+                        .map(Redactor::read_toml)
+                        .transpose()?
+                        .as_ref(),
+                )
+                .await
+        }
+        #[cfg(feature = "analysis")]
+        Command::Pipeline(command) => match command.command {
+            PipelineSubcommand::Run { config, restart } => {
+                pipeline::run(
+                    &config,
+                    restart,
+                    #[cfg(feature = "collection")]
+                    pipeline::Rig {
+                        interface: arguments.interface,
+                        voices: arguments.voices,
+                        sensitivity: arguments.sensitivity,
+                        model: arguments.model,
+                    },
                 )
                 .await
+            }
+        },
+        #[cfg(feature = "viewer")]
+        Command::Review(command) => {
+            viewer::run(&command.data_dir, &command.address, command.session).await
         }
     }
 }
 
-fn assistant_command(command: AssistantCommand) -> Result<(), Error> {
+#[cfg(feature = "collection")]
+fn assistant_command(command: AssistantCommand, sensitivity: f32) -> Result<(), Error> {
     let assistant = assistant::from(command.assistant.as_str());
 
     match command.command {
         AssistantSubcommand::Setup => assistant.setup()?,
+        AssistantSubcommand::Test(test) if test.matrix => {
+            let probes = if test.probe.is_empty() {
+                PROBE_QUERIES
+                    .iter()
+                    .map(|query| query.to_string())
+                    .collect()
+            } else {
+                test.probe
+            };
+            let matrix = VoiceMatrix::run(
+                assistant.as_ref(),
+                &test.voices,
+                &probes,
+                sensitivity,
+                time::Duration::from_secs(test.timeout),
+            )?;
+
+            println!("{matrix}");
+        }
         AssistantSubcommand::Test(test) => assistant.test_voices(test.voices)?,
     };
 
     Ok(())
 }
 
+#[cfg(feature = "collection")]
 fn listen_command<P: AsRef<Path>>(
     voice: &str,
     sensitivity: f32,
@@ -95,6 +402,7 @@ fn listen_command<P: AsRef<Path>>(
     }
 }
 
+#[cfg(feature = "collection")]
 fn calibrate() -> Result<(), Error> {
     interact::user_confirmation("Calibration will record the average ambient noise. Stay quiet for five seconds. To begin, press")?;
 
@@ -104,6 +412,8 @@ fn calibrate() -> Result<(), Error> {
     Ok(())
 }
 
+#[cfg(feature = "collection")]
+#[cfg_attr(not(feature = "transcription"), allow(unused_variables))]
 fn listen<P: AsRef<Path>>(
     voice: &str,
     sensitivity: f32,
@@ -117,11 +427,12 @@ fn listen<P: AsRef<Path>>(
     } else {
         listener.record_until_silent(time::Duration::from_secs(2), sensitivity)?
     };
-    audio.downsample(16000)?;
+    audio.resample(16000, ResampleQuality::default())?;
     if let Some(file) = command.file {
         varys_audio::file::write_audio(&file, &audio)?;
     }
 
+    #[cfg(feature = "transcription")]
     if command.parrot {
         info!("Recognising...");
         let recogniser = Recogniser::with_model_path(&model.as_ref().to_string_lossy())?;
@@ -135,6 +446,84 @@ fn listen<P: AsRef<Path>>(
     Ok(())
 }
 
+#[cfg(feature = "collection")]
+async fn audio_command(command: AudioSubcommand) -> Result<(), Error> {
+    match command {
+        AudioSubcommand::MeasureRir(command) => room::measure_rir(command).await,
+        AudioSubcommand::VoiceProfile(command) => create_voice_profile(command),
+    }
+}
+
+#[cfg(feature = "collection")]
+fn devices_command(command: DevicesSubcommand) -> Result<(), Error> {
+    match command {
+        DevicesSubcommand::List => {
+            for device in Listener::input_devices()? {
+                println!("{device}");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Create the voice profile of an assistant from recordings of its responses and store it.
+///
+/// # Arguments
+///
+/// * `command`: The recordings and where to store the profile.
+#[cfg(feature = "collection")]
+fn create_voice_profile(command: VoiceProfileCommand) -> Result<(), Error> {
+    let recordings = command
+        .recordings
+        .iter()
+        .map(|path| varys_audio::file::read_opus(path))
+        .collect::<Result<Vec<_>, _>>()?;
+    let profile = VoiceProfile::from_recordings(&recordings).ok_or(Error::NoVoiceRecorded)?;
+
+    profile.save(&command.file)?;
+
+    info!(
+        "Stored the voice profile of {} recordings at {}",
+        recordings.len(),
+        command.file.display()
+    );
+
+    Ok(())
+}
+
+async fn assistants_command(command: AssistantsSubcommand) -> Result<(), Error> {
+    match command {
+        AssistantsSubcommand::List => {
+            for assistant in AssistantRegistry::all() {
+                println!("{} (\"{}\")", assistant.name(), assistant.wake_word());
+            }
+        }
+        AssistantsSubcommand::Triggers => {
+            let connection = database::connect().await?;
+            let rates = WakeWordTrigger::rates(&connection).await?;
+
+            for rate in &rates {
+                println!("{rate}");
+            }
+
+            let unreliable: Vec<String> = rates
+                .iter()
+                .filter(|rate| !rate.is_reliable())
+                .map(|rate| format!("{} with {}", rate.voice, rate.assistant))
+                .collect();
+            if !unreliable.is_empty() {
+                println!(
+                    "Consider removing {} from the voice rotation",
+                    unreliable.join(", ")
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
 fn sniff_command(interface: &str, command: SniffCommand) -> Result<(), Error> {
     info!("Sniffing...");
 
@@ -149,6 +538,7 @@ fn sniff_command(interface: &str, command: SniffCommand) -> Result<(), Error> {
     Ok(())
 }
 
+#[cfg(feature = "collection")]
 async fn run_command<P: AsRef<Path>>(
     interface: &str,
     voices: Vec<String>,
@@ -156,61 +546,886 @@ async fn run_command<P: AsRef<Path>>(
     model: P,
     command: arguments::RunCommand,
 ) -> Result<(), Error> {
-    let mut interactor = Interactor::new(
+    let coordinator = command.rig.map(|rig| {
+        Coordinator::new(
+            rig,
+            command.room.unwrap_or_default(),
+            command.mac.clone(),
+            TimeSlots {
+                length: time::Duration::from_secs(command.slot_minutes * 60),
+            },
+        )
+    });
+    let mut interactor = Interactor::with_backends(
+        Backends::with_input_device(interface, command.input_device.as_deref(), command.channels)?,
         interface.to_string(),
         voices,
         sensitivity,
         model.as_ref().to_string_lossy().to_string(),
         command.data_dir,
         command.mac,
-    )?;
+    );
+    interactor.set_combined_capture(command.combined_capture);
+    interactor.set_query_source(match (command.type_with, command.recordings) {
+        (Some(typing_command), _) => QuerySource::Typed(typing_command),
+        (None, recordings) => recordings.map_or(QuerySource::Synthesised, QuerySource::Recorded),
+    });
+    interactor.set_recalibration(
+        (command.recalibrate_every.is_some() || command.recalibrate_after_failures.is_some()).then(
+            || {
+                Recalibration::new(
+                    command.recalibrate_every,
+                    command.recalibrate_after_failures,
+                )
+            },
+        ),
+    );
+    interactor.set_quiescence_detector(
+        command
+            .pause_above
+            .map(|threshold| QuiescenceDetector::new(threshold * 1000.0)),
+    );
+    interactor.set_quiet_hours(command.quiet_hours);
+    interactor.set_idle_capture(command.idle_capture);
+    interactor.set_power_cycle(command.plug.map(|plug| {
+        PowerCycle::new(
+            plug,
+            command.power_cycle_sessions,
+            command.power_cycle_failures,
+        )
+    }));
+    interactor.set_retry_policy(command.max_attempts.map(|max_attempts| {
+        RetryPolicy::new(
+            max_attempts,
+            time::Duration::from_secs(command.retry_delay),
+            command.requeue_failed,
+        )
+    }));
+    interactor.set_interaction_timeout(command.interaction_timeout.map(time::Duration::from_secs));
+    interactor.set_shuffle_queries(!command.no_shuffle);
+    if let Some(seed) = command.seed {
+        interactor.set_seeds(Seeds::from_seed(seed));
+    }
+    interactor.set_idle_gap(
+        command
+            .min_idle_gap
+            .zip(command.max_idle_gap)
+            .map(|(min, max)| {
+                IdleGap::new(
+                    time::Duration::from_secs(min),
+                    time::Duration::from_secs(max),
+                )
+            }),
+    );
+    interactor.set_thermal_monitor((command.thermal_interval > 0).then(|| ThermalMonitor {
+        interval: time::Duration::from_secs(command.thermal_interval),
+        temperature_limit: command.temperature_limit,
+    }));
+    interactor.set_watermark(command.watermark.map(Watermark::new));
+    interactor.set_prime_transcription(command.prime_transcription);
+    interactor.set_loopback(
+        command
+            .loopback_device
+            .as_deref()
+            .map(Listener::with_device)
+            .transpose()?
+            .map(|listener| Box::new(listener) as Box<dyn Listen>),
+    );
+    interactor.set_echo_canceller(command.echo_cancellation.then(EchoCanceller::default));
+    interactor.set_level_control(command.normalise.map(|target_dbfs| {
+        if command.gain_control {
+            LevelControl::GainControl(target_dbfs)
+        } else {
+            LevelControl::Normalise(target_dbfs)
+        }
+    }));
+    interactor.set_silence_padding(SilencePadding {
+        leading: time::Duration::from_millis(command.leading_padding),
+        trailing: time::Duration::from_millis(command.trailing_padding),
+    });
+    interactor.set_radio_sniffer(command.radio_sniffer.as_deref().and_then(|radio_sniffer| {
+        let mut words = radio_sniffer.split_whitespace();
+        words.next().map(|program| {
+            Box::new(RadioSniffer::new(
+                program,
+                words.map(String::from).collect(),
+            )) as Box<dyn Sniff>
+        })
+    }));
+    interactor.set_export_channels(command.export_channels);
+    interactor.set_lossless_exemplars(command.lossless_exemplars);
+    interactor.set_recognition_channel(command.recognition_channel);
+    interactor.set_redactor(command.redact.map(Redactor::read_toml).transpose()?);
+    interactor.set_voice_profile(
+        command
+            .voice_profile
+            .as_deref()
+            .map(VoiceProfile::load)
+            .transpose()?,
+        command.min_voice_similarity,
+    );
+    if let Some(silence_threshold) = command.silence_threshold {
+        interactor.set_silence_threshold(silence_threshold);
+    }
+    interactor
+        .listener
+        .set_silence_detector(command.silence_detection.detector());
+    if let Some(pre_roll) = command.pre_roll {
+        interactor
+            .listener
+            .set_pre_roll(Some(time::Duration::from_secs_f32(pre_roll)))?;
+    }
+    interactor
+        .listener
+        .set_reconnect_attempts(command.reconnect_attempts);
+    if let Some(block_size) = command.mute_blocks {
+        let control = match (command.mute_url, command.unmute_url) {
+            (Some(mute_url), Some(unmute_url)) => MuteControl::http(&mute_url, &unmute_url)?,
+            _ => MuteControl::Manual,
+        };
+        interactor.set_mute_experiment(Some(MuteExperiment {
+            control,
+            block_size,
+        }));
+    }
+    if let Some(block_size) = command.rate_blocks {
+        interactor.set_rate_sweep(Some(RateSweep::new(command.rate_sweep, block_size)?));
+    }
     let assistant = assistant::from(command.assistant.as_str());
-    let mut queries = Query::read_toml(&command.queries)?;
-    assistant.prepare_queries(&mut queries);
+    let replay = match query::session_reference(&command.queries)? {
+        Some(session_id) => Some(Replay::load(&database::connect().await?, session_id).await?),
+        None => None,
+    };
+    let replaying = replay.is_some();
+    let mut queries = match &replay {
+        Some(replay) => replay.queries.clone(),
+        None => Query::read(&command.queries).await?,
+    };
+    interactor.set_replay(replay);
+    if command.bias_vocabulary {
+        interactor.set_vocabulary_prompt(query::vocabulary_prompt(
+            queries
+                .iter()
+                .flat_map(|query| std::iter::once(&query.text).chain(&query.follow_ups))
+                .map(String::as_str),
+        ));
+    }
+    // a replayed session asks the queries of the original one again, repeats included
+    query::validation::validate(
+        queries.iter().map(|query| query.text.as_str()),
+        Some(&assistant.wake_word()),
+    )
+    .allow_repeats(command.allow_repeats || replaying)
+    .check()?;
+    let shutdown = Shutdown::on_ctrl_c();
+    interactor.set_shutdown(Some(shutdown.clone()));
+    let connection = match coordinator {
+        Some(_) => Some(database::connect().await?),
+        None => None,
+    };
 
-    loop {
-        let (transcriber, transcriber_handle) = Transcriber::new(Recogniser::with_model_path(
-            &model.as_ref().to_string_lossy(),
-        )?);
+    let mut resume = command.resume;
+    while !shutdown.is_requested() {
+        // a coordinated rig only asks its block of the queries during its own time slot
+        let mut slot_queries = None;
+        if let Some((coordinator, connection)) = coordinator.as_ref().zip(connection.as_ref()) {
+            let Some(slot) = coordinator.next_slot(connection, &queries, &shutdown).await? else {
+                break;
+            };
+            interactor.set_shutdown(Some(slot.shutdown(&shutdown)));
+            slot_queries = Some(slot.queries);
+        }
 
-        let _ = thread::spawn(move || transcriber.start());
+        #[cfg(feature = "transcription")]
+        let transcriber_handle = {
+            let (transcriber, transcriber_handle) = Transcriber::new(Recogniser::with_model_path(
+                &model.as_ref().to_string_lossy(),
+            )?);
 
-        if let Err(error) = interactor
-            .start(&mut queries, assistant.as_ref(), transcriber_handle)
-            .await
-        {
+            let _ = thread::spawn(move || transcriber.start());
+            Some(transcriber_handle)
+        };
+        #[cfg(not(feature = "transcription"))]
+        let transcriber_handle = None;
+
+        let result = match resume.take() {
+            Some(session_id) => {
+                interactor
+                    .resume_session(session_id, &queries, assistant.as_ref(), transcriber_handle)
+                    .await
+            }
+            None => interactor
+                .start(
+                    slot_queries.as_deref_mut().unwrap_or(&mut queries),
+                    assistant.as_ref(),
+                    transcriber_handle,
+                )
+                .await
+                .map(|_| ()),
+        };
+        if let Err(error) = result {
             error!("A session did not complete successfully: {error}");
         }
+
+        // a replayed session is only asked again once
+        if replaying {
+            break;
+        }
+    }
+
+    if let Some((coordinator, connection)) = coordinator.as_ref().zip(connection.as_ref()) {
+        coordinator.leave(connection).await?;
+    }
+
+    Ok(())
+}
+
+async fn schedule_command(
+    command: ScheduleSubcommand,
+    #[cfg(feature = "collection")] scheduler: Scheduler,
+) -> Result<(), Error> {
+    let connection = database::connect().await?;
+
+    match command {
+        ScheduleSubcommand::Add {
+            name,
+            expression,
+            mac,
+            assistant,
+            queries,
+            data_dir,
+            balance,
+            quiet_hours,
+            idle_capture,
+            allow_repeats,
+        } => {
+            let expression: Expression = expression.parse()?;
+            let next_run = expression
+                .next_after(Utc::now())
+                .ok_or_else(|| Error::InvalidScheduleExpression(expression.to_string()))?;
+            Query::read(&queries).await?;
+
+            let schedule = Schedule::create(
+                &connection,
+                &name,
+                &expression.to_string(),
+                &assistant::from(&assistant).name(),
+                &mac,
+                &queries.to_string_lossy(),
+                &data_dir.to_string_lossy(),
+                next_run,
+                balance.map(|balance| balance as i32),
+                quiet_hours.map(|quiet_hours| quiet_hours.to_string()),
+                idle_capture,
+                allow_repeats,
+            )
+            .await?;
+
+            println!("Added {schedule}");
+        }
+        ScheduleSubcommand::List => {
+            for schedule in Schedule::get_all(&connection).await? {
+                println!("{schedule}");
+            }
+        }
+        ScheduleSubcommand::History { name } => {
+            let schedule = Schedule::get_by_name(&connection, &name)
+                .await?
+                .ok_or(Error::ScheduleNotFound(name))?;
+
+            for run in schedule.history(&connection).await? {
+                println!("{run}");
+            }
+        }
+        ScheduleSubcommand::Remove { name } => {
+            Schedule::get_by_name(&connection, &name)
+                .await?
+                .ok_or(Error::ScheduleNotFound(name))?
+                .delete(&connection)
+                .await?;
+        }
+        #[cfg(feature = "collection")]
+        ScheduleSubcommand::Run => scheduler.run().await?,
+    }
+
+    Ok(())
+}
+
+async fn session_command(command: SessionSubcommand) -> Result<(), Error> {
+    let connection = database::connect().await?;
+
+    match command {
+        SessionSubcommand::Note { id, note } => {
+            let session = Session::get(&connection, id)
+                .await?
+                .ok_or(Error::SessionNotFound(id))?;
+            let note = session.add_note(&connection, &note).await?;
+
+            info!("Added note to {session}");
+            println!("{note}");
+        }
+        SessionSubcommand::Notes { id } => {
+            let session = Session::get(&connection, id)
+                .await?
+                .ok_or(Error::SessionNotFound(id))?;
+
+            for note in session.notes(&connection).await? {
+                println!("{note}");
+            }
+        }
+        SessionSubcommand::Events { id } => {
+            let session = Session::get(&connection, id)
+                .await?
+                .ok_or(Error::SessionNotFound(id))?;
+
+            for event in session.events(&connection).await? {
+                println!("{event}");
+            }
+        }
+        SessionSubcommand::Rooms { id } => {
+            let session = Session::get(&connection, id)
+                .await?
+                .ok_or(Error::SessionNotFound(id))?;
+
+            for room_response in session.room_responses(&connection).await? {
+                println!("{room_response}");
+            }
+        }
+        SessionSubcommand::Versions => {
+            for dataset_version in Session::dataset_versions(&connection).await? {
+                println!("{dataset_version}");
+            }
+        }
     }
+
+    Ok(())
 }
 
+async fn dataset_command(command: DatasetSubcommand) -> Result<(), Error> {
+    let connection = database::connect().await?;
+
+    match command {
+        DatasetSubcommand::Create { name, note, locale } => {
+            let dataset =
+                NamedDataset::create(&connection, &name, note.as_deref(), locale.as_deref())
+                    .await?;
+
+            println!("Created {dataset}");
+        }
+        DatasetSubcommand::Add {
+            name,
+            query,
+            category,
+            note,
+            locale,
+        } => {
+            let query = NamedDataset::get_by_name(&connection, &name)
+                .await?
+                .ok_or(Error::DatasetNotFound(name))?
+                .add_query(
+                    &connection,
+                    &query,
+                    &category,
+                    note.as_deref(),
+                    locale.as_deref(),
+                )
+                .await?;
+
+            println!("Added {query}");
+        }
+        DatasetSubcommand::List { name: Some(name) } => {
+            let dataset = NamedDataset::get_by_name(&connection, &name)
+                .await?
+                .ok_or(Error::DatasetNotFound(name))?;
+
+            println!("{dataset}");
+            for query in dataset.queries(&connection).await? {
+                println!("{query}");
+            }
+        }
+        DatasetSubcommand::List { name: None } => {
+            for dataset in NamedDataset::get_all(&connection).await? {
+                println!("{dataset}");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "analysis")]
 async fn analyse_command(
-    dataset_size: DatasetSize,
+    dataset_size: Dataset,
     analyse_subcommand: AnalyseSubcommand,
     interface: &str,
+    #[cfg(feature = "transcription")] model: &Path,
 ) -> Result<(), Error> {
     match analyse_subcommand {
-        AnalyseSubcommand::Train { data_dir } => {
-            ml::train(data_dir, get_filtered_interactions(&dataset_size).await?)?
+        AnalyseSubcommand::Train {
+            data_dir,
+            top_k,
+            shard_size,
+            auto_batch_size,
+            stratify_regimes,
+            bursts,
+        } => {
+            let mut metrics = MetricPlugins::new();
+            if let Some(k) = top_k {
+                metrics = metrics.with(move || TopKAccuracy::new(k));
+            }
+            let regimes = if stratify_regimes {
+                session_regimes().await?
+            } else {
+                HashMap::new()
+            };
+
+            ml::train(
+                data_dir,
+                get_filtered_interactions(&dataset_size).await?,
+                &dataset_size.to_string(),
+                &metrics,
+                &TrainingOptions {
+                    shard_size,
+                    auto_batch_size,
+                    regimes,
+                    representation: if bursts {
+                        TraceRepresentation::Bursts
+                    } else {
+                        TraceRepresentation::Packets
+                    },
+                },
+            )?
         }
-        AnalyseSubcommand::Test { data_dir } => ml::test_dataset(data_dir)?,
-        AnalyseSubcommand::Demo { data_dir, mac } => demo(data_dir, interface, mac)?,
-        AnalyseSubcommand::CompileLogs { data_dir, id } => ml::compile_all_logs(data_dir, &id)?,
-        AnalyseSubcommand::Plot { data_dir } => {
-            let mut dataset = NumericTraceDataset::new(
+        AnalyseSubcommand::Distil { data_dir, teacher } => ml::distil(
+            data_dir,
+            get_filtered_interactions(&dataset_size).await?,
+            &dataset_size.to_string(),
+            teacher.as_deref(),
+            &MetricPlugins::new(),
+        )?,
+        AnalyseSubcommand::Test { data_dir } => {
+            ml::test_dataset(data_dir, &dataset_size.to_string())?
+        }
+        AnalyseSubcommand::Labels { data_dir } => {
+            let label_map = ml::create_label_map(
                 &data_dir,
+                &get_filtered_interactions(&dataset_size).await?,
+                &dataset_size.to_string(),
+            )?;
+
+            println!(
+                "{}",
+                label_map::label_map_path(&data_dir, &label_map.preset).display()
+            );
+        }
+        AnalyseSubcommand::Serve { data_dir, address } => {
+            InferenceServer::load(data_dir, &dataset_size.to_string())?.serve(&address)?
+        }
+        AnalyseSubcommand::Transfer {
+            data_dir,
+            source,
+            target,
+        } => {
+            let (source, target) = (assistant::from(&source), assistant::from(&target));
+            let (source_interactions, interactions) = asked_to(
                 get_filtered_interactions(&dataset_size).await?,
+                source.as_ref(),
+            );
+            let (target_interactions, _) = asked_to(interactions, target.as_ref());
+
+            let report = transfer::evaluate(
+                data_dir,
+                (&source.name(), source_interactions),
+                (&target.name(), target_interactions),
+                &MetricPlugins::new(),
             )?;
+            println!("{report}");
+        }
+        AnalyseSubcommand::Demo { data_dir, mac } => demo(data_dir, interface, mac, &dataset_size)?,
+        AnalyseSubcommand::Replay {
+            data_dir,
+            mac,
+            pcap,
+        } => replay(data_dir, pcap, mac, &dataset_size)?,
+        AnalyseSubcommand::Gc { data_dir, keep } => {
+            let (removed, freed) = run::gc(data_dir, keep)?;
+
+            println!(
+                "Removed {removed} checkpoints, freeing {:.1} MB",
+                freed as f64 / 1_000_000.
+            );
+        }
+        AnalyseSubcommand::CompileLogs { data_dir, id } => ml::compile_all_logs(data_dir, &id)?,
+        AnalyseSubcommand::Plot { data_dir } => {
+            let interactions = get_filtered_interactions(&dataset_size).await?;
+            let label_map = LabelMap::new(
+                &dataset_size.to_string(),
+                interactions
+                    .iter()
+                    .map(|interaction| interaction.query.clone()),
+            )?;
+            let mut dataset = NumericTraceDataset::new(&data_dir, interactions, label_map)?;
             dataset.resize_all(475).shuffle();
 
             plot::plot_queries(&data_dir, dataset_size.queries(), &dataset);
         }
+        AnalyseSubcommand::Show {
+            data_dir,
+            interaction,
+            width,
+        } => show_interaction(data_dir, interaction, width).await?,
+        AnalyseSubcommand::Responses { reclassify } => {
+            response_types(&dataset_size, reclassify).await?
+        }
+        AnalyseSubcommand::Regimes {
+            data_dir,
+            max_regimes,
+            clear,
+        } => assign_regimes(data_dir, &dataset_size, max_regimes, clear).await?,
+        AnalyseSubcommand::Outliers {
+            data_dir,
+            factor,
+            clear,
+        } => flag_outliers(data_dir, &dataset_size, factor, clear).await?,
+        AnalyseSubcommand::Timing { data_dir, csv } => {
+            timing_correlations(data_dir, &dataset_size, csv).await?
+        }
+        #[cfg(feature = "transcription")]
+        AnalyseSubcommand::Relabel {
+            data_dir,
+            threshold,
+            action,
+            clear,
+        } => relabel::run(&data_dir, model, &dataset_size, threshold, action, clear).await?,
+    }
+
+    Ok(())
+}
+
+/// Get the dataset loaded from a file if one is given, or the built-in dataset otherwise, restricted to some query
+/// categories if any are given and sampled per category if a sample size is given.
+///
+/// # Arguments
+///
+/// * `preset`: The built-in dataset.
+/// * `file`: The file to load the dataset from.
+/// * `wake_word`: The wake word of the assistant the dataset file was collected with, see [`Dataset::from_file`].
+/// * `categories`: The query categories to use, or all categories if empty.
+/// * `per_category`: The number of interactions to sample from every query category.
+/// * `seed`: The seed to sample with.
+#[cfg(feature = "analysis")]
+fn dataset(
+    preset: DatasetSize,
+    file: Option<PathBuf>,
+    wake_word: Option<&str>,
+    categories: Vec<String>,
+    per_category: Option<usize>,
+    seed: u64,
+) -> Result<Dataset, Error> {
+    let mut dataset = match file {
+        Some(file) => Dataset::from_file(file, wake_word)?,
+        None => preset.into(),
+    };
+    if !categories.is_empty() {
+        dataset = dataset.in_categories(categories)?;
+    }
+
+    Ok(match per_category {
+        Some(per_category) => dataset.sampled(DatasetSampler { per_category, seed }),
+        None => dataset,
+    })
+}
+
+/// Count the response types of the interactions of a dataset per query category and print them.
+///
+/// Transcribed responses that have no type yet are classified and stored first.
+///
+/// # Arguments
+///
+/// * `dataset_size`: The dataset to count.
+/// * `reclassify`: Whether to classify all transcribed responses again.
+#[cfg(feature = "analysis")]
+async fn response_types(dataset_size: &Dataset, reclassify: bool) -> Result<(), Error> {
+    let connection = database::connect().await?;
+    let mut counts: BTreeMap<String, HashMap<ResponseType, usize>> = BTreeMap::new();
+    let mut classified = 0;
+
+    for mut interaction in dataset_size.filter(Interaction::get_all(&connection).await?) {
+        let Some(response) = interaction.response.clone() else {
+            continue;
+        };
+        let response_type = match interaction.response_type.as_deref().map(str::parse) {
+            Some(Ok(response_type)) if !reclassify => response_type,
+            _ => {
+                let response_type = ResponseType::classify(&response);
+                interaction.response_type = Some(response_type.to_string());
+                InteractionStatus::validate(&mut interaction, &response);
+                interaction.update(&connection).await?;
+                classified += 1;
+                response_type
+            }
+        };
+
+        *counts
+            .entry(interaction.query_category.clone())
+            .or_default()
+            .entry(response_type)
+            .or_default() += 1;
+    }
+
+    info!("Classified {classified} responses");
+
+    for (category, counts) in counts {
+        let total: usize = counts.values().sum();
+        let shares: Vec<String> = ResponseType::ALL
+            .iter()
+            .map(|response_type| {
+                let count = counts.get(response_type).copied().unwrap_or_default();
+                format!(
+                    "{response_type} {count} ({:.0}%)",
+                    100.0 * count as f64 / total as f64
+                )
+            })
+            .collect();
+
+        println!("{category} ({total} responses): {}", shares.join(", "));
+    }
+
+    Ok(())
+}
+
+/// Split interactions into the ones asked to a voice assistant and all others.
+///
+/// Interactions are asked to the assistant if they were asked with its wake word.
+///
+/// # Arguments
+///
+/// * `interactions`: The interactions to split.
+/// * `assistant`: The assistant whose interactions to find.
+#[cfg(feature = "analysis")]
+fn asked_to(
+    interactions: Vec<Interaction>,
+    assistant: &dyn VoiceAssistant,
+) -> (Vec<Interaction>, Vec<Interaction>) {
+    let wake_word = assistant.wake_word();
+    let (asked, others): (Vec<Interaction>, Vec<Interaction>) =
+        interactions.into_iter().partition(|interaction| {
+            interaction
+                .wake_word
+                .as_ref()
+                .is_some_and(|other| other.eq_ignore_ascii_case(&wake_word))
+        });
+
+    info!(
+        "Found {} interactions asked to {}",
+        asked.len(),
+        assistant.name()
+    );
+
+    (asked, others)
+}
+
+/// Print the traffic of an interaction as sparklines alongside its query and transcript, and plot it to
+/// `plots/interaction-<id>.png` in the data directory.
+///
+/// # Arguments
+///
+/// * `data_dir`: The directory in which data files are stored.
+/// * `id`: The id of the interaction to show.
+/// * `width`: The number of characters of each sparkline.
+#[cfg(feature = "analysis")]
+async fn show_interaction<P: AsRef<Path>>(data_dir: P, id: i32, width: usize) -> Result<(), Error> {
+    let connection = database::connect().await?;
+    let interaction = Interaction::get(&connection, id)
+        .await?
+        .ok_or(Error::InteractionNotFound(id))?;
+    let address = MacAddress::from_str(&interaction.assistant_mac)?;
+    let trace = TrafficTrace::load_interaction(&data_dir, &interaction)?;
+
+    let plot_path = data_dir
+        .as_ref()
+        .join(format!("plots/interaction-{}.png", interaction.id));
+    if let Some(plots_dir) = plot_path.parent() {
+        fs::create_dir_all(plots_dir)?;
+    }
+    plot::plot_timeline(&plot_path, &trace, &address, &interaction.query)?;
+
+    let (outgoing, incoming) = plot::sparklines(&trace, &address, width);
+    println!("{interaction} [{}]", interaction.status);
+    println!("Query:    {}", interaction.query);
+    println!(
+        "Response: {}",
+        interaction
+            .response
+            .as_deref()
+            .unwrap_or("(not transcribed)")
+    );
+    if let Some(conditions) = &interaction.conditions {
+        println!("Conditions: {conditions}");
+    }
+    println!("{trace}");
+    println!("out {}", outgoing.blue());
+    println!("in  {}", incoming.red());
+    println!("Plotted to {}", plot_path.display());
+
+    Ok(())
+}
+
+/// Cluster the sessions of a dataset into capture-environment regimes, replacing previous regimes.
+///
+/// # Arguments
+///
+/// * `data_dir`: The directory in which data files are stored.
+/// * `dataset_size`: The dataset whose sessions to cluster.
+/// * `max_regimes`: The maximum number of regimes to find.
+/// * `clear`: Whether to only remove the previous regimes.
+#[cfg(feature = "analysis")]
+async fn assign_regimes<P: AsRef<Path>>(
+    data_dir: P,
+    dataset_size: &Dataset,
+    max_regimes: usize,
+    clear: bool,
+) -> Result<(), Error> {
+    let connection = database::connect().await?;
+    let removed = SessionRegime::delete_all(&connection).await?;
+
+    info!("Removed {removed} previous regimes");
+
+    if clear {
+        return Ok(());
+    }
+
+    let interactions: Vec<Interaction> = dataset_size
+        .filter(Interaction::get_all(&connection).await?)
+        .into_iter()
+        .filter(|interaction| interaction.is_complete())
+        .collect();
+    let regimes = regime::find_regimes(
+        &SessionFeatures::of_sessions(data_dir, &interactions),
+        max_regimes,
+    );
+
+    let mut sessions: Vec<(i32, i32)> = regimes.into_iter().collect();
+    sessions.sort();
+    for (session_id, regime) in sessions {
+        let session_regime = SessionRegime::create(&connection, session_id, regime).await?;
+
+        println!("{session_regime}");
+    }
+
+    Ok(())
+}
+
+/// Get the capture-environment regime of every session by its id.
+#[cfg(feature = "analysis")]
+async fn session_regimes() -> Result<HashMap<i32, i32>, Error> {
+    let connection = database::connect().await?;
+    let regimes: HashMap<i32, i32> = SessionRegime::get_all(&connection)
+        .await?
+        .into_iter()
+        .map(|session_regime| (session_regime.session_id, session_regime.regime))
+        .collect();
+
+    if regimes.is_empty() {
+        warn!("No regimes have been assigned to sessions, the split is not stratified");
+    }
+
+    Ok(regimes)
+}
+
+/// Flag all interactions of a dataset whose metrics are outliers within their query, replacing previous flags.
+///
+/// Interactions flagged during collection, because their response did not match the voice of the assistant or was
+/// probably truncated, stay flagged.
+///
+/// # Arguments
+///
+/// * `data_dir`: The directory in which data files are stored.
+/// * `dataset_size`: The dataset to check.
+/// * `factor`: How many interquartile ranges a value must lie outside the quartiles to be an outlier.
+/// * `clear`: Whether to only remove the previous flags.
+#[cfg(feature = "analysis")]
+async fn flag_outliers<P: AsRef<Path>>(
+    data_dir: P,
+    dataset_size: &Dataset,
+    factor: f64,
+    clear: bool,
+) -> Result<(), Error> {
+    let connection = database::connect().await?;
+    let removed = Outlier::delete_all_except(&connection, COLLECTION_METRICS).await?;
+
+    info!("Removed {removed} previous outlier flags");
+
+    if clear {
+        return Ok(());
+    }
+
+    let interactions: Vec<Interaction> = dataset_size
+        .filter(Interaction::get_all(&connection).await?)
+        .into_iter()
+        .filter(|interaction| interaction.is_complete())
+        .collect();
+
+    for flag in outlier::find_outliers(data_dir, &interactions, factor) {
+        let outlier = Outlier::create(
+            &connection,
+            flag.interaction_id,
+            &flag.metric.to_string(),
+            flag.value,
+        )
+        .await?;
+
+        println!("{outlier}");
+    }
+
+    Ok(())
+}
+
+/// Correlate the durations of the queries and responses of a dataset with the bytes sent and received by the
+/// assistant and print the correlations.
+///
+/// # Arguments
+///
+/// * `data_dir`: The directory in which data files are stored.
+/// * `dataset_size`: The dataset to correlate.
+/// * `csv`: The CSV file to also write the correlations to.
+#[cfg(feature = "analysis")]
+async fn timing_correlations<P: AsRef<Path>>(
+    data_dir: P,
+    dataset_size: &Dataset,
+    csv: Option<PathBuf>,
+) -> Result<(), Error> {
+    let interactions: Vec<Interaction> = get_filtered_interactions(dataset_size)
+        .await?
+        .into_iter()
+        .filter(|interaction| interaction.is_complete())
+        .collect();
+    let correlations = timing::correlate(data_dir, &interactions);
+
+    for correlation in &correlations {
+        println!("{correlation}");
+    }
+
+    if let Some(csv) = csv {
+        let mut writer = csv::Writer::from_path(&csv)?;
+        for correlation in &correlations {
+            writer.serialize(correlation)?;
+        }
+        writer.flush()?;
+
+        info!("Wrote the correlations to {}", csv.display());
     }
 
     Ok(())
 }
 
-fn demo<P: AsRef<Path>>(data_dir: P, interface: &str, address: String) -> Result<(), Error> {
+#[cfg(feature = "analysis")]
+fn demo<P: AsRef<Path>>(
+    data_dir: P,
+    interface: &str,
+    address: String,
+    dataset_size: &Dataset,
+) -> Result<(), Error> {
     let sniffer = Sniffer::from(sniff::device_by_name(interface)?);
     let capture_path = data_dir.as_ref().join("captures/demo.pcap");
     let data_dir = data_dir.as_ref().to_path_buf();
@@ -223,15 +1438,222 @@ fn demo<P: AsRef<Path>>(data_dir: P, interface: &str, address: String) -> Result
     let sniffer = sniffer.start(&capture_path)?;
     interact::user_confirmation("Confirm when the voice assistant has finished speaking.")?;
     let _ = sniffer.stop()?;
-    let output = ml::test_single(&data_dir, &capture_path, &MacAddress::from_str(&address)?)?;
+    let output = ml::test_single(
+        &data_dir,
+        &capture_path,
+        &MacAddress::from_str(&address)?,
+        &dataset_size.to_string(),
+    )?;
     println!("{output:?}");
 
     Ok(())
 }
 
-async fn get_filtered_interactions(dataset_size: &DatasetSize) -> Result<Vec<Interaction>, Error> {
+/// Replay all captures in a directory like live traffic, classify them like in the demo and compare the predictions to
+/// the ones of the original captures.
+///
+/// Returns [`Error::ReplayMismatch`] if any predictions differ.
+///
+/// # Arguments
+///
+/// * `data_dir`: The directory in which data files are stored.
+/// * `pcap_dir`: The directory with the captures to replay.
+/// * `address`: The MAC address of the assistant.
+/// * `dataset_size`: The dataset the model was trained on.
+#[cfg(feature = "analysis")]
+fn replay(
+    data_dir: PathBuf,
+    pcap_dir: PathBuf,
+    address: String,
+    dataset_size: &Dataset,
+) -> Result<(), Error> {
+    let address = MacAddress::from_str(&address)?;
+    let preset = dataset_size.to_string();
+    let replay_dir = data_dir.join("captures/replay");
+    let mut captures: Vec<PathBuf> = fs::read_dir(pcap_dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "pcap")
+        })
+        .collect();
+    captures.sort();
+    fs::create_dir_all(&replay_dir)?;
+
+    let mut mismatches = 0;
+
+    for capture in &captures {
+        let replay_path = replay_dir.join(capture.file_name().unwrap_or_default());
+        let stats = ReplaySniffer::new(capture).start(&replay_path)?.wait()?;
+
+        info!("{stats}");
+
+        let offline = best_prediction(ml::test_single(&data_dir, capture, &address, &preset)?);
+        let online = best_prediction(ml::test_single(&data_dir, &replay_path, &address, &preset)?);
+
+        if offline == online {
+            println!("{}: \"{online}\"", capture.display());
+        } else {
+            mismatches += 1;
+            println!(
+                "{}: \"{online}\" live but \"{offline}\" offline",
+                capture.display()
+            );
+        }
+    }
+
+    println!(
+        "{} of {} replayed captures matched",
+        captures.len() - mismatches,
+        captures.len()
+    );
+
+    if mismatches > 0 {
+        return Err(Error::ReplayMismatch(mismatches));
+    }
+
+    Ok(())
+}
+
+/// Get the query with the highest score from the output of a classification.
+#[cfg(feature = "analysis")]
+fn best_prediction(output: Vec<(String, f32)>) -> String {
+    output
+        .into_iter()
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(query, _)| query)
+        .unwrap_or_default()
+}
+
+#[cfg(feature = "analysis")]
+async fn get_filtered_interactions(dataset_size: &Dataset) -> Result<Vec<Interaction>, Error> {
     let connection = database::connect().await?;
-    let all_interactions = Interaction::get_all(&connection).await?;
+    let mut all_interactions = Interaction::get_all(&connection).await?;
     log::info!("Fetched all interactions: {}", all_interactions.len()); // Debugging
-    Ok(dataset_size.filter(all_interactions))
+    let mut excluded: HashSet<i32> = Outlier::get_all(&connection)
+        .await?
+        .into_iter()
+        .map(|outlier| outlier.interaction_id)
+        .collect();
+
+    if !excluded.is_empty() {
+        info!(
+            "Excluding {} interactions flagged as outliers",
+            excluded.len()
+        );
+    }
+
+    // use the new labels of reassigned interactions and exclude invalidated ones
+    let relabels: HashMap<i32, Option<String>> = Relabel::get_all(&connection)
+        .await?
+        .into_iter()
+        .map(|relabel| (relabel.interaction_id, relabel.label))
+        .collect();
+    let categories: HashMap<String, String> = all_interactions
+        .iter()
+        .map(|interaction| {
+            (
+                interaction.query.clone(),
+                interaction.query_category.clone(),
+            )
+        })
+        .collect();
+
+    if !relabels.is_empty() {
+        info!("Applying {} relabels", relabels.len());
+    }
+
+    // interactions during a background download contain its traffic as well
+    let downloads = SessionEvent::get_by_kind(&connection, BACKGROUND_DOWNLOAD_EVENT).await?;
+    excluded.extend(
+        all_interactions
+            .iter()
+            .filter(|interaction| {
+                let ended = interaction.ended.unwrap_or(interaction.started);
+                downloads
+                    .iter()
+                    .any(|download| download.overlaps(interaction.started, ended))
+            })
+            .map(|interaction| interaction.id),
+    );
+
+    // interactions with a muted microphone are not expected to contain the traffic of their query
+    excluded.extend(
+        all_interactions
+            .iter()
+            .filter(|interaction| interaction.mic_muted)
+            .map(|interaction| interaction.id),
+    );
+
+    // interactions the assistant did not respond to do not contain the traffic of a response
+    let failed: Vec<i32> = all_interactions
+        .iter()
+        .filter(|interaction| interaction.status != InteractionStatus::Valid.to_string())
+        .map(|interaction| interaction.id)
+        .collect();
+    if !failed.is_empty() {
+        info!(
+            "Excluding {} interactions the assistant did not respond to",
+            failed.len()
+        );
+    }
+    excluded.extend(failed);
+
+    // interactions rejected in manual review are not usable samples
+    let rejected: Vec<i32> = Review::get_all(&connection)
+        .await?
+        .into_iter()
+        .filter(|review| !review.accepted)
+        .map(|review| review.interaction_id)
+        .collect();
+    if !rejected.is_empty() {
+        info!(
+            "Excluding {} interactions rejected in review",
+            rejected.len()
+        );
+    }
+    excluded.extend(rejected);
+
+    for interaction in &mut all_interactions {
+        match relabels.get(&interaction.id) {
+            Some(Some(label)) => {
+                interaction.query_category = categories
+                    .get(label)
+                    .cloned()
+                    .unwrap_or(interaction.query_category.clone());
+                interaction.query = label.clone();
+            }
+            Some(None) => {
+                excluded.insert(interaction.id);
+            }
+            None => {}
+        }
+    }
+
+    let interactions: Vec<Interaction> = dataset_size
+        .filter(all_interactions)
+        .into_iter()
+        .filter(|interaction| !excluded.contains(&interaction.id))
+        .collect();
+
+    // traces collected with different query sets may not be comparable
+    let session_ids: HashSet<i32> = interactions
+        .iter()
+        .map(|interaction| interaction.session_id)
+        .collect();
+    let dataset_versions: BTreeSet<String> = Session::get_all(&connection)
+        .await?
+        .into_iter()
+        .filter(|session| session_ids.contains(&session.id))
+        .filter_map(|session| session.dataset_version)
+        .collect();
+    if dataset_versions.len() > 1 {
+        warn!(
+            "The interactions were collected with {} different dataset versions: {}",
+            dataset_versions.len(),
+            dataset_versions.into_iter().collect::<Vec<_>>().join(", ")
+        );
+    }
+
+    Ok(interactions)
 }
@@ -1,10 +1,26 @@
 use std::path::PathBuf;
 
 use clap::{Args, Parser, Subcommand};
+#[cfg(feature = "collection")]
+use varys_audio::listen::vad::SilenceDetection;
+#[cfg(feature = "collection")]
+use varys_audio::listen::Listener;
+#[cfg(feature = "collection")]
+use varys_audio::listen::SilenceThreshold;
+#[cfg(feature = "collection")]
+use varys_audio::voice::DEFAULT_MIN_SIMILARITY;
 
+#[cfg(feature = "collection")]
+use crate::assistant::power::SmartPlug;
+use crate::assistant::quiet_hours::QuietHours;
+#[cfg(feature = "analysis")]
 use crate::dataset::DatasetSize;
+use crate::query::typing::TypingCommand;
 
+#[cfg(feature = "analysis")]
 use super::export::ExportType;
+#[cfg(all(feature = "analysis", feature = "transcription"))]
+use super::relabel::RelabelAction;
 
 #[derive(Debug, Parser)]
 #[command(author, version, about)]
@@ -33,19 +49,65 @@ pub struct Arguments {
 #[derive(Debug, Subcommand)]
 pub enum Command {
     /// Interact with a voice assistant
+    #[cfg(feature = "collection")]
     Assistant(AssistantCommand),
+    /// Show the voice assistants varys can interact with
+    Assistants(AssistantsCommand),
     /// Listen for something that was said and optionally repeat it
+    #[cfg(feature = "collection")]
     Listen(ListenCommand),
     /// Record network traffic on a specified interface
     Sniff(SniffCommand),
+    /// Measure the acoustic conditions of the setup
+    #[cfg(feature = "collection")]
+    Audio(AudioCommand),
+    /// Show the audio input devices of this machine
+    #[cfg(feature = "collection")]
+    Devices(DevicesCommand),
     /// Start varys
-    Run(RunCommand),
+    #[cfg(feature = "collection")]
+    Run(Box<RunCommand>),
+    /// Start varys with several voice assistants at once, each with its own interface, microphone and voices
+    #[cfg(feature = "collection")]
+    Fleet(FleetCommand),
+    /// Run sessions at configurable times, e.g. every 6 hours, and manage their schedules
+    Schedule(ScheduleCommand),
+    /// Manage sessions recorded with varys
+    Session(SessionCommand),
+    /// Curate named datasets of queries in the database, which sessions can ask as `dataset:<name>`
+    Dataset(DatasetCommand),
+    /// Update the stored paths of data files after moving a data directory
+    ///
+    /// Only sessions whose files are all found at the new location are updated.
+    Relocate(RelocateCommand),
+    /// Transcribe recorded responses, e.g. those still pending after varys was stopped during transcription
+    #[cfg(feature = "transcription")]
+    Transcribe(TranscribeCommand),
+    /// Run a complete interaction with fake hardware to check that this machine is set up correctly
+    #[cfg(feature = "collection")]
+    Selftest(SelftestCommand),
+    /// Replay a collected session against simulated hardware and compare the simulated interactions to the collected
+    /// ones, to check changes to the scheduling, timing and database logic
+    #[cfg(feature = "collection")]
+    Simulate(SimulateCommand),
+    /// Check the database, network capture, audio devices, voices, whisper model and disk space of this machine and
+    /// explain how to fix any problems
+    Doctor(DoctorCommand),
     /// Analyse data captured with varys
+    #[cfg(feature = "analysis")]
     Analyse(AnalyseCommand),
     /// Export data captured with varys in different formats
+    #[cfg(feature = "analysis")]
     Export(ExportCommand),
+    /// Run a whole experiment from collection to the report of the trained model
+    #[cfg(feature = "analysis")]
+    Pipeline(PipelineCommand),
+    /// Review interactions in a local web viewer and accept or reject them for datasets
+    #[cfg(feature = "viewer")]
+    Review(ReviewCommand),
 }
 
+#[cfg(feature = "collection")]
 #[derive(Debug, Args)]
 pub struct AssistantCommand {
     /// Which voice assistant to interact with
@@ -55,6 +117,7 @@ pub struct AssistantCommand {
     pub command: AssistantSubcommand,
 }
 
+#[cfg(feature = "collection")]
 #[derive(Debug, Subcommand)]
 pub enum AssistantSubcommand {
     /// Setup voice recognition
@@ -63,13 +126,25 @@ pub enum AssistantSubcommand {
     Test(TestCommand),
 }
 
+#[cfg(feature = "collection")]
 #[derive(Debug, Args)]
 pub struct TestCommand {
     #[arg(required(true))]
     /// The names of the system voices to test with
     pub voices: Vec<String>,
+    /// Ask a number of probe queries with every voice, detect whether the assistant responds and report which voices
+    /// trigger it reliably
+    #[arg(long)]
+    pub matrix: bool,
+    /// The probe queries to ask with every voice, instead of the default ones
+    #[arg(long, requires = "matrix")]
+    pub probe: Vec<String>,
+    /// How long to wait for the assistant to respond to a probe in seconds
+    #[arg(long, default_value_t = 10, requires = "matrix")]
+    pub timeout: u64,
 }
 
+#[cfg(feature = "collection")]
 #[derive(Debug, Args)]
 pub struct ListenCommand {
     /// Optional duration in seconds to listen for. If omitted, listen until silence is detected
@@ -79,12 +154,71 @@ pub struct ListenCommand {
     #[arg(short, long)]
     pub calibrate: bool,
     /// Whether to repeat the audio back
+    #[cfg(feature = "transcription")]
     #[arg(short, long)]
     pub parrot: bool,
     /// Where to store the recorded audio
     pub file: Option<PathBuf>,
 }
 
+#[cfg(feature = "collection")]
+#[derive(Debug, Args)]
+pub struct AudioCommand {
+    /// What to measure
+    #[clap(subcommand)]
+    pub command: AudioSubcommand,
+}
+
+#[cfg(feature = "collection")]
+#[derive(Debug, Subcommand)]
+pub enum AudioSubcommand {
+    /// Play a sweep and record it to estimate the impulse response of the room
+    MeasureRir(MeasureRirCommand),
+    /// Create the voice profile of an assistant from recordings of its responses
+    VoiceProfile(VoiceProfileCommand),
+}
+
+#[cfg(feature = "collection")]
+#[derive(Debug, Args)]
+pub struct MeasureRirCommand {
+    /// The session to store the impulse response with
+    #[arg(long)]
+    pub session: Option<i32>,
+    /// The duration of the sweep in seconds
+    #[arg(short, long, default_value_t = 5)]
+    pub duration: u32,
+    /// The length of the impulse response in milliseconds
+    #[arg(short, long, default_value_t = 1000)]
+    pub length: u32,
+    /// Where to store the impulse response
+    pub file: PathBuf,
+}
+
+#[cfg(feature = "collection")]
+#[derive(Debug, Args)]
+pub struct VoiceProfileCommand {
+    /// Where to store the voice profile
+    pub file: PathBuf,
+    /// The `.opus` recordings of responses of the assistant
+    #[arg(required(true))]
+    pub recordings: Vec<PathBuf>,
+}
+
+#[cfg(feature = "collection")]
+#[derive(Debug, Args)]
+pub struct DevicesCommand {
+    /// What to do with the devices
+    #[clap(subcommand)]
+    pub command: DevicesSubcommand,
+}
+
+#[cfg(feature = "collection")]
+#[derive(Debug, Subcommand)]
+pub enum DevicesSubcommand {
+    /// List all audio input devices with their index and the configurations they support
+    List,
+}
+
 #[derive(Debug, Args)]
 pub struct SniffCommand {
     /// The duration in seconds to listen for
@@ -94,6 +228,7 @@ pub struct SniffCommand {
     pub file: PathBuf,
 }
 
+#[cfg(feature = "collection")]
 #[derive(Debug, Args)]
 pub struct RunCommand {
     /// The MAC address of the assistant
@@ -101,34 +236,515 @@ pub struct RunCommand {
     pub mac: String,
     /// Which voice assistant to interact with
     pub assistant: String,
-    /// The file with queries to ask the assistant
+    /// The file with queries to ask the assistant, `dataset:<name>` for a dataset stored in the database, or
+    /// `session:<id>` to replay a collected session once in the same order and with the same timings, storing the new
+    /// session as paired with it
+    pub queries: PathBuf,
+    /// The directory in which to store data files
+    pub data_dir: PathBuf,
+    /// Allow the query file to list a query more than once, to ask it more often
+    #[arg(long)]
+    pub allow_repeats: bool,
+    /// The name or index of the microphone to record with, see `varys devices list`, instead of the default one
+    #[arg(long)]
+    pub input_device: Option<String>,
+    /// The name or index of a loopback or monitor device that records what the speaker plays, which is stored as the
+    /// `query-loopback` audio of every interaction
+    #[arg(long)]
+    pub loopback_device: Option<String>,
+    /// Remove the echo of what the speaker plays, recorded with the loopback device, from the recorded responses
+    #[arg(long, requires = "loopback_device")]
+    pub echo_cancellation: bool,
+    /// Scale the recorded queries and responses to this RMS level in dBFS, e.g. -20, so recordings made at different
+    /// microphone gains are comparable
+    #[arg(long, allow_negative_numbers = true)]
+    pub normalise: Option<f32>,
+    /// Continuously adjust the gain of the recordings towards the level of `--normalise` instead of scaling them as a
+    /// whole
+    #[arg(long, requires = "normalise")]
+    pub gain_control: bool,
+    /// How much silence to keep before the recorded responses in milliseconds when their silence is trimmed
+    #[arg(long, default_value_t = 100)]
+    pub leading_padding: u64,
+    /// How much silence to keep after the recorded responses in milliseconds when their silence is trimmed
+    #[arg(long, default_value_t = 100)]
+    pub trailing_padding: u64,
+    /// A command that writes frames observed over the air, e.g. by a software defined radio, to its standard output,
+    /// one per line with tab separated fields, which is run during every interaction to capture Zigbee or Thread
+    /// traffic next to the network traffic
+    #[arg(long)]
+    pub radio_sniffer: Option<String>,
+    /// How many channels to record, e.g. 2 for a stereo audio interface with one microphone pointed at the assistant
+    /// and one at the speaker
+    #[arg(long)]
+    pub channels: Option<u16>,
+    /// Also store every channel of the recorded queries and responses in its own file
+    #[arg(long)]
+    pub export_channels: bool,
+    /// Also store a lossless FLAC copy of the responses to queries in categories marked with `exemplar = true`
+    #[arg(long)]
+    pub lossless_exemplars: bool,
+    /// The channel of the recorded responses to transcribe, starting at 0, instead of the average of all channels
+    #[arg(long)]
+    pub recognition_channel: Option<u8>,
+    /// Capture the traffic of a whole session at once and split it per interaction afterwards
+    #[arg(long)]
+    pub combined_capture: bool,
+    /// Alternate the microphone of the assistant between unmuted and muted after this many interactions
+    #[arg(long)]
+    pub mute_blocks: Option<usize>,
+    /// The url to request to mute the microphone, e.g. of a smart plug. If omitted, muting is done manually
+    #[arg(long, requires_all = ["mute_blocks", "unmute_url"])]
+    pub mute_url: Option<String>,
+    /// The url to request to unmute the microphone
+    #[arg(long, requires_all = ["mute_blocks", "mute_url"])]
+    pub unmute_url: Option<String>,
+    /// Sweep through these speaking rates relative to the normal rate, e.g. 0.8,1,1.25, changing the rate in blocks of
+    /// interactions
+    #[arg(long, value_delimiter = ',', requires = "rate_blocks")]
+    pub rate_sweep: Vec<f32>,
+    /// Change the speaking rate of the rate sweep after this many interactions
+    #[arg(long)]
+    pub rate_blocks: Option<usize>,
+    /// Play the queries from recordings in this directory instead of synthesising them, e.g. recorded by humans
+    #[arg(long)]
+    pub recordings: Option<PathBuf>,
+    /// Type the queries with this command instead of speaking them, e.g. "osascript data/type-to-siri.applescript"
+    #[arg(long, value_name = "COMMAND", conflicts_with = "recordings")]
+    pub type_with: Option<TypingCommand>,
+    /// Calibrate the sensitivity again from the ambient noise after this many interactions
+    #[arg(long)]
+    pub recalibrate_every: Option<usize>,
+    /// Calibrate the sensitivity again after this many consecutive failed interactions
+    #[arg(long)]
+    pub recalibrate_after_failures: Option<usize>,
+    /// Pause collection while the assistant receives more than this many kB/s in the background, e.g. during updates
+    #[arg(long)]
+    pub pause_above: Option<f64>,
+    /// Do not speak during these hours in local time, e.g. `22:00-07:00`, sessions pause until they are over
+    #[arg(long)]
+    pub quiet_hours: Option<QuietHours>,
+    /// Capture the idle traffic of the assistant during the quiet hours
+    #[arg(long, requires = "quiet_hours")]
+    pub idle_capture: bool,
+    /// The smart plug that powers the assistant, as `tasmota:<host>` or `kasa:<host>`
+    #[arg(long)]
+    pub plug: Option<SmartPlug>,
+    /// Power-cycle the assistant with the smart plug before every session
+    #[arg(long, requires = "plug")]
+    pub power_cycle_sessions: bool,
+    /// Power-cycle the assistant with the smart plug after this many consecutive failed interactions
+    #[arg(long, requires = "plug")]
+    pub power_cycle_failures: Option<usize>,
+    /// Ask queries whose interaction failed again, up to this many attempts in total
+    #[arg(long)]
+    pub max_attempts: Option<u32>,
+    /// How long to wait before retrying a failed query in seconds, doubling with every further attempt
+    #[arg(long, default_value_t = 5, requires = "max_attempts")]
+    pub retry_delay: u64,
+    /// Retry failed queries at the end of the session instead of right away
+    #[arg(long, requires = "max_attempts")]
+    pub requeue_failed: bool,
+    /// The longest time a single interaction may take in seconds, longer interactions are stopped and count as failed
+    #[arg(long)]
+    pub interaction_timeout: Option<u64>,
+    /// Ask the queries in the order of the queries file instead of shuffling them for every session
+    #[arg(long)]
+    pub no_shuffle: bool,
+    /// Draw the order of the queries and all random choices of the interactions from this seed, so running the same
+    /// queries again makes the same choices
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// The shortest random idle time between two queries in seconds
+    #[arg(long, requires = "max_idle_gap")]
+    pub min_idle_gap: Option<u64>,
+    /// The longest random idle time between two queries in seconds, the idle time waited is stored per interaction
+    #[arg(long, requires = "min_idle_gap")]
+    pub max_idle_gap: Option<u64>,
+    /// How often to log the temperature and throttling state of the host in seconds, 0 to never log it
+    #[arg(long, default_value_t = 60)]
+    pub thermal_interval: u64,
+    /// The temperature of the host in °C above which an alert is raised
+    #[arg(long, default_value_t = 80.0)]
+    pub temperature_limit: f32,
+    /// Mix an inaudible watermark generated from this seed into spoken queries and detect it in their recordings
+    #[arg(long)]
+    pub watermark: Option<u64>,
+    /// Recompute the sensitivity from the ambient noise at the start of every session, as `deviation:<k>` for the mean
+    /// plus k standard deviations or `percentile:<p>`
+    #[arg(long)]
+    pub silence_threshold: Option<SilenceThreshold>,
+    /// How to decide which parts of a recording are sound when waiting for the end of a response, `energy` for the
+    /// level only, `hybrid` for the level and zero-crossing rate, which ignores steady noise like fans, or `spectral`
+    /// for the level and octave bands, which ignores low-frequency hum like that of a HomePod
+    #[arg(long, default_value_t = SilenceDetection::Energy)]
+    pub silence_detection: SilenceDetection,
+    /// Prepend this many seconds of audio from before each query to its recording, so the onset of the wake word is not
+    /// clipped. Times measured on the recording of the query include the pre-roll
+    #[arg(long)]
+    pub pre_roll: Option<f32>,
+    /// How many times to try finding the microphone again if it is disconnected during the session before the
+    /// session fails
+    #[arg(long, default_value_t = Listener::DEFAULT_RECONNECT_ATTEMPTS)]
+    pub reconnect_attempts: u32,
+    /// Condition the transcription of each response on its query, so names are spelled like in the query
+    #[arg(long)]
+    pub prime_transcription: bool,
+    /// Condition the transcription of responses on the names in the queries, so they are spelled like in the queries
+    #[arg(long)]
+    pub bias_vocabulary: bool,
+    /// Check that every response sounds like the voice profile of the assistant stored in this file and flag those
+    /// that do not
+    #[arg(long)]
+    pub voice_profile: Option<PathBuf>,
+    /// The similarity to the voice profile below which a response is flagged
+    #[arg(long, default_value_t = DEFAULT_MIN_SIMILARITY, requires = "voice_profile")]
+    pub min_voice_similarity: f32,
+    /// Remove the names, phone numbers, addresses and patterns configured in this TOML file from transcripts before
+    /// they are stored
+    #[arg(long)]
+    pub redact: Option<PathBuf>,
+    /// Continue the interrupted session with this id before starting new ones, skipping the queries it already
+    /// completed
+    #[arg(long, conflicts_with = "rig")]
+    pub resume: Option<i32>,
+    /// Coordinate with the other rigs in the database under this unique name, asking only a block of the queries in
+    /// time slots of its own
+    #[arg(long, requires = "room")]
+    pub rig: Option<String>,
+    /// The room the rig is set up in
+    #[arg(long, requires = "rig")]
+    pub room: Option<String>,
+    /// The length in minutes of the time slots the rigs take turns in, which has to be the same for all rigs
+    #[arg(long, default_value_t = 30, requires = "rig")]
+    pub slot_minutes: u64,
+}
+
+#[cfg(feature = "collection")]
+#[derive(Debug, Args)]
+pub struct FleetCommand {
+    /// Which voice assistant to interact with
+    pub assistant: String,
+    /// The file with queries to ask the assistants, or `dataset:<name>` for a dataset stored in the database
     pub queries: PathBuf,
     /// The directory in which to store data files
     pub data_dir: PathBuf,
+    /// The TOML file with a `[[member]]` table per assistant, with its `name`, `interface`, `mac`, `voices` and
+    /// optionally `input_device` and `output_device`
+    pub fleet: PathBuf,
+    /// Allow the query file to list a query more than once, to ask it more often
+    #[arg(long)]
+    pub allow_repeats: bool,
+}
+
+#[cfg(feature = "transcription")]
+#[derive(Debug, Args)]
+pub struct TranscribeCommand {
+    /// Transcribe all responses that were queued for transcription but never transcribed
+    #[arg(long, required_unless_present = "ids")]
+    pub pending: bool,
+    /// The directory in which data files are stored
+    pub data_dir: PathBuf,
+    /// The ids of the interactions whose responses to transcribe
+    #[arg(conflicts_with = "pending")]
+    pub ids: Vec<i32>,
+    /// Condition the transcription on the names in the queries, so they are spelled like in the queries
+    #[arg(long)]
+    pub bias_vocabulary: bool,
+    /// Remove the names, phone numbers, addresses and patterns configured in this TOML file from transcripts before
+    /// they are stored
+    #[arg(long)]
+    pub redact: Option<PathBuf>,
+}
+
+#[cfg(feature = "collection")]
+#[derive(Debug, Args)]
+pub struct SimulateCommand {
+    /// The id of the session to simulate
+    pub session_id: i32,
+    /// How many times faster than the collected session to run, which does not change the measured durations
+    #[arg(long, default_value_t = 1.0)]
+    pub speed: f32,
+    /// The shortest random idle time between two queries in seconds, which should match the collected session
+    #[arg(long, requires = "max_idle_gap")]
+    pub min_idle_gap: Option<u64>,
+    /// The longest random idle time between two queries in seconds, which should match the collected session
+    #[arg(long, requires = "min_idle_gap")]
+    pub max_idle_gap: Option<u64>,
+}
+
+#[derive(Debug, Args)]
+pub struct DoctorCommand {
+    /// The directory in which data files are stored
+    pub data_dir: PathBuf,
+}
+
+#[cfg(feature = "collection")]
+#[derive(Debug, Args)]
+pub struct SelftestCommand {
+    /// The directory in which data files are stored, whose `models` directory holds the tiny whisper model
+    #[arg(default_value = "data")]
+    pub data_dir: PathBuf,
+}
+
+#[derive(Debug, Args)]
+pub struct RelocateCommand {
+    /// The directory the data was moved from
+    #[arg(long)]
+    pub from: PathBuf,
+    /// The directory the data was moved to
+    #[arg(long)]
+    pub to: PathBuf,
+    /// Only check the files at the new location without updating any paths
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct AssistantsCommand {
+    /// What to do with the registered assistants
+    #[clap(subcommand)]
+    pub command: AssistantsSubcommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AssistantsSubcommand {
+    /// List all registered voice assistants with their wake words
+    List,
+    /// Show how often each voice triggered the wake word of each assistant across all sessions and which voices to
+    /// drop from the rotation
+    Triggers,
 }
 
+#[derive(Debug, Args)]
+pub struct ScheduleCommand {
+    /// What to do with schedules
+    #[clap(subcommand)]
+    pub command: ScheduleSubcommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ScheduleSubcommand {
+    /// Add a schedule that runs a session with a file of queries at the times of a cron expression
+    Add {
+        /// A unique name for the schedule
+        name: String,
+        /// When to run, as a cron expression in local time, e.g. `"0 */6 * * *"` for every 6 hours
+        expression: String,
+        /// The MAC address of the assistant
+        #[arg(long, required(true))]
+        mac: String,
+        /// Which voice assistant to interact with
+        assistant: String,
+        /// The file with queries to ask the assistant, e.g. `data/queries-small.toml`, or `dataset:<name>` for a dataset
+        /// stored in the database
+        queries: PathBuf,
+        /// The directory in which to store data files
+        data_dir: PathBuf,
+        /// Only ask this many queries per session, those with the fewest valid, unflagged interactions first, so
+        /// the number of samples of all queries converges
+        #[arg(long)]
+        balance: Option<u32>,
+        /// Do not speak during these hours in local time, e.g. `22:00-07:00`, sessions pause until they are over
+        #[arg(long)]
+        quiet_hours: Option<QuietHours>,
+        /// Capture the idle traffic of the assistant during the quiet hours
+        #[arg(long, requires = "quiet_hours")]
+        idle_capture: bool,
+        /// Allow the query file to list a query more than once, to ask it more often
+        #[arg(long)]
+        allow_repeats: bool,
+    },
+    /// List all schedules with the time they run next
+    List,
+    /// Show the past runs of a schedule with their sessions
+    History {
+        /// The name of the schedule
+        name: String,
+    },
+    /// Remove a schedule and its history
+    Remove {
+        /// The name of the schedule
+        name: String,
+    },
+    /// Run the sessions of all schedules when they are due, one at a time
+    #[cfg(feature = "collection")]
+    Run,
+}
+
+#[derive(Debug, Args)]
+pub struct SessionCommand {
+    /// What to do with sessions
+    #[clap(subcommand)]
+    pub command: SessionSubcommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SessionSubcommand {
+    /// Add a timestamped note to a session, e.g. to record changes to the physical setup
+    Note {
+        /// The id of the session
+        id: i32,
+        /// The text of the note
+        note: String,
+    },
+    /// Show all notes of a session
+    Notes {
+        /// The id of the session
+        id: i32,
+    },
+    /// Show all events recorded during a session, e.g. pauses for background downloads
+    Events {
+        /// The id of the session
+        id: i32,
+    },
+    /// Show all room impulse responses measured for a session
+    Rooms {
+        /// The id of the session
+        id: i32,
+    },
+    /// Show the versions of the queries asked in all sessions, to find sessions of different datasets
+    Versions,
+}
+
+#[derive(Debug, Args)]
+pub struct DatasetCommand {
+    /// What to do with datasets
+    #[clap(subcommand)]
+    pub command: DatasetSubcommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum DatasetSubcommand {
+    /// Create an empty dataset
+    Create {
+        /// A unique name for the dataset
+        name: String,
+        /// A description of the dataset, e.g. what it was curated for
+        #[arg(long)]
+        note: Option<String>,
+        /// The locale the queries of the dataset are asked in unless they have their own, e.g. de-DE
+        #[arg(long)]
+        locale: Option<String>,
+    },
+    /// Add a query to a dataset
+    Add {
+        /// The name of the dataset
+        name: String,
+        /// The text of the query
+        query: String,
+        /// The category of the query
+        #[arg(long)]
+        category: String,
+        /// A note on the query, e.g. why it was added
+        #[arg(long)]
+        note: Option<String>,
+        /// The locale the query is asked in, e.g. de-DE, if it differs from the locale of the dataset
+        #[arg(long)]
+        locale: Option<String>,
+    },
+    /// Show all datasets, or the queries of one
+    List {
+        /// The name of the dataset to show the queries of
+        name: Option<String>,
+    },
+}
+
+#[cfg(feature = "analysis")]
 #[derive(Debug, Args)]
 pub struct AnalyseCommand {
     /// The dataset to use
     #[arg(short, long, value_enum, default_value_t)]
     pub dataset: DatasetSize,
+    /// Load the dataset from a TOML, YAML or CSV file instead of using a built-in one
+    #[arg(long, conflicts_with = "dataset")]
+    pub dataset_file: Option<PathBuf>,
+    /// The voice assistant the dataset file was collected with, whose wake word its queries must not start with
+    #[arg(long, requires = "dataset_file")]
+    pub assistant: Option<String>,
+    /// Only use the queries of these categories of the dataset, e.g. calls,weather
+    #[arg(long, value_delimiter = ',')]
+    pub categories: Vec<String>,
+    /// Use a random sample of this many interactions from every query category
+    #[arg(long)]
+    pub per_category: Option<usize>,
+    /// The seed the interactions of every category are sampled with
+    #[arg(long, default_value_t = 0, requires = "per_category")]
+    pub sample_seed: u64,
     /// What type of analysis to perform
     #[clap(subcommand)]
     pub command: AnalyseSubcommand,
 }
 
+#[cfg(feature = "analysis")]
 #[derive(Debug, Subcommand)]
 pub enum AnalyseSubcommand {
     /// Train varys traffic fingerprinting
     Train {
         /// The directory in which data files are stored
         data_dir: PathBuf,
+        /// Also log how often the correct query is among the k most likely ones
+        #[arg(long)]
+        top_k: Option<usize>,
+        /// Stream the dataset from shards of this many traces on disk instead of loading it into memory
+        #[arg(long)]
+        shard_size: Option<usize>,
+        /// Use the largest batch size that fits into the memory of the GPU instead of the default one
+        #[arg(long)]
+        auto_batch_size: bool,
+        /// Split the dataset so that every partition contains the capture-environment regimes of the sessions in the
+        /// same proportions, see the `regimes` command
+        #[arg(long, conflicts_with = "shard_size")]
+        stratify_regimes: bool,
+        /// Train on bursts of consecutive packets in the same direction instead of single packets
+        #[arg(long)]
+        bursts: bool,
+    },
+    /// Distil a trained model into a smaller one for live inference
+    ///
+    /// The smaller model is trained on the labels and on the predictions of the larger one, and is used for inference
+    /// from then on.
+    Distil {
+        /// The directory in which data files are stored
+        data_dir: PathBuf,
+        /// The name of the training run to distil, the newest one by default
+        #[arg(long)]
+        teacher: Option<String>,
     },
     /// Test varys traffic fingerprinting
     Test {
         /// The directory in which data files are stored
         data_dir: PathBuf,
     },
+    /// Create the file that maps the queries of the dataset to the labels of the model, with a hash of the labels
+    ///
+    /// Training creates this file as well, testing and inference check that the model was trained with it.
+    Labels {
+        /// The directory in which data files are stored
+        data_dir: PathBuf,
+    },
+    /// Serve a trained model over HTTP, recognising the queries of traces sent to `POST /infer`
+    Serve {
+        /// The directory in which data files are stored
+        data_dir: PathBuf,
+        /// The address to listen on
+        #[arg(long, default_value = "0.0.0.0:8080")]
+        address: String,
+    },
+    /// Train on the traces of one voice assistant and test on the same queries asked to another one
+    ///
+    /// Interactions are assigned to an assistant by the wake word they were asked with, so Google Assistant has to be
+    /// given by the wake word it was asked with, e.g. "OK Google".
+    Transfer {
+        /// The directory in which data files are stored
+        data_dir: PathBuf,
+        /// The name or wake word of the voice assistant to train on
+        #[arg(long)]
+        source: String,
+        /// The name or wake word of the voice assistant to test on
+        #[arg(long)]
+        target: String,
+    },
     /// Run a demo on a pre-trained model
     Demo {
         /// The directory in which data files are stored
@@ -136,7 +752,15 @@ pub enum AnalyseSubcommand {
         /// The MAC address of the assistant
         mac: String,
     },
-    /// Compile training logs into a training and validation `.csv` summary
+    /// Remove the checkpoints of old training runs, keeping their best and final models and their metrics
+    Gc {
+        /// The directory in which data files are stored
+        data_dir: PathBuf,
+        /// How many of the newest runs to keep all checkpoints of
+        #[arg(long, default_value_t = 1)]
+        keep: usize,
+    },
+    /// Compile the training logs of the newest run into a training and validation `.csv` summary
     CompileLogs {
         /// The directory in which data files are stored
         data_dir: PathBuf,
@@ -148,17 +772,156 @@ pub enum AnalyseSubcommand {
         /// The directory in which data files are stored
         data_dir: PathBuf,
     },
+    /// Show the trace of an interaction as sparklines alongside its transcript and plot it to a `.png` file
+    Show {
+        /// The directory in which data files are stored
+        data_dir: PathBuf,
+        /// The id of the interaction to show
+        #[arg(long)]
+        interaction: i32,
+        /// The number of characters of each sparkline
+        #[arg(long, default_value_t = 80)]
+        width: usize,
+    },
+    /// Replay stored captures through the live classification path and compare the predictions to offline ones
+    ///
+    /// Captures are replayed at their original packet pacing, so this takes as long as the captures themselves.
+    Replay {
+        /// The directory in which data files are stored
+        data_dir: PathBuf,
+        /// The MAC address of the assistant
+        mac: String,
+        /// The directory with the `.pcap` files to replay
+        #[arg(long)]
+        pcap: PathBuf,
+    },
+    /// Show how often each query category is answered, asked to be clarified, fails or is redirected to web results
+    ///
+    /// Transcribed responses without a type are classified first.
+    Responses {
+        /// Classify all transcribed responses again, e.g. after the classifier has changed, and mark the interactions
+        /// whose response shows that the assistant did not respond as failed
+        #[arg(long)]
+        reclassify: bool,
+    },
+    /// Cluster sessions into capture-environment regimes, e.g. before and after a change of the router
+    ///
+    /// Sessions are clustered by the mean trace length, capture size and latencies of their interactions. Previous
+    /// regimes are replaced.
+    Regimes {
+        /// The directory in which data files are stored
+        data_dir: PathBuf,
+        /// The maximum number of regimes to find
+        #[arg(long, default_value_t = 4)]
+        max_regimes: usize,
+        /// Only remove the existing regimes
+        #[arg(long)]
+        clear: bool,
+    },
+    /// Flag interactions with unusual trace lengths, capture sizes or response durations for their query
+    ///
+    /// Flagged interactions are excluded from all datasets. Previous flags are replaced.
+    Outliers {
+        /// The directory in which data files are stored
+        data_dir: PathBuf,
+        /// How many interquartile ranges a value must lie outside the quartiles to be an outlier
+        #[arg(short, long, default_value_t = 1.5)]
+        factor: f64,
+        /// Only remove the existing flags, except those of responses that did not sound like the assistant or were
+        /// truncated
+        #[arg(long)]
+        clear: bool,
+    },
+    /// Correlate the durations of spoken queries and responses with the bytes sent and received by the assistant, over
+    /// all queries and per query
+    Timing {
+        /// The directory in which data files are stored
+        data_dir: PathBuf,
+        /// Also write the correlations to this CSV file
+        #[arg(long)]
+        csv: Option<PathBuf>,
+    },
+    /// Transcribe the recorded queries and relabel interactions whose query was not spoken or heard as intended
+    ///
+    /// Datasets use the new label of reassigned interactions and exclude invalidated ones. Previous relabels are
+    /// replaced.
+    #[cfg(feature = "transcription")]
+    Relabel {
+        /// The directory in which data files are stored
+        data_dir: PathBuf,
+        /// The similarity from 0 to 1 a transcript needs to count as a match of a query
+        #[arg(short, long, default_value_t = 0.75)]
+        threshold: f32,
+        /// What to do with interactions that do not match their query
+        #[arg(short, long, value_enum, default_value_t)]
+        action: RelabelAction,
+        /// Only remove all existing relabels
+        #[arg(long)]
+        clear: bool,
+    },
 }
 
+#[cfg(feature = "analysis")]
 #[derive(Debug, Args)]
 pub struct ExportCommand {
     /// The dataset to use
     #[arg(short, long, value_enum, default_value_t)]
     pub dataset: DatasetSize,
+    /// Load the dataset from a TOML, YAML or CSV file instead of using a built-in one
+    #[arg(long, conflicts_with = "dataset")]
+    pub dataset_file: Option<PathBuf>,
+    /// Only use the queries of these categories of the dataset, e.g. calls,weather
+    #[arg(long, value_delimiter = ',')]
+    pub categories: Vec<String>,
+    /// Use a random sample of this many interactions from every query category
+    #[arg(long)]
+    pub per_category: Option<usize>,
+    /// The seed the interactions of every category are sampled with
+    #[arg(long, default_value_t = 0, requires = "per_category")]
+    pub sample_seed: u64,
     /// The format in which to export the data
     pub format: ExportType,
     /// The directory in which data files are stored
     pub data_dir: PathBuf,
     /// Which voice assistant to export data for
     pub assistant: String,
+    /// Remove the names, phone numbers, addresses and patterns configured in this TOML file from exported transcripts
+    #[arg(long)]
+    pub redact: Option<PathBuf>,
+}
+
+#[cfg(feature = "viewer")]
+#[derive(Debug, Args)]
+pub struct ReviewCommand {
+    /// The directory in which data files are stored
+    pub data_dir: PathBuf,
+    /// The address to serve the viewer on
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    pub address: String,
+    /// Only review the interactions of this session
+    #[arg(long)]
+    pub session: Option<i32>,
+}
+
+#[cfg(feature = "analysis")]
+#[derive(Debug, Args)]
+pub struct PipelineCommand {
+    /// What to do with the pipeline
+    #[clap(subcommand)]
+    pub command: PipelineSubcommand,
+}
+
+#[cfg(feature = "analysis")]
+#[derive(Debug, Subcommand)]
+pub enum PipelineSubcommand {
+    /// Run the stages of a pipeline, continuing after the last stage that completed
+    ///
+    /// The stages are collect, extract, snapshot, train and report.
+    Run {
+        /// The TOML file the pipeline is configured in
+        config: PathBuf,
+        /// Run all stages again, even those that already completed
+        #[arg(long)]
+        restart: bool,
+    },
 }
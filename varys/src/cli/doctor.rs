@@ -0,0 +1,273 @@
+use std::path::Path;
+use std::{env, fs, process};
+
+use colored::Colorize;
+use sysinfo::{DiskExt, System, SystemExt};
+#[cfg(feature = "collection")]
+use varys_audio::listen::Listener;
+#[cfg(feature = "collection")]
+use varys_audio::tts::Speaker;
+use varys_database::database;
+use varys_network::index;
+use varys_network::sniff::{self, Sniffer};
+
+use crate::error::Error;
+
+/// The free space in bytes below which the disk of the data directory is considered full.
+const MIN_FREE_SPACE: u64 = 10_000_000_000;
+
+/// The outcome of checking one part of the environment.
+struct Check {
+    /// What was checked.
+    name: &'static str,
+    /// What was found if the check passed, or how to fix the problem otherwise.
+    result: Result<String, String>,
+}
+
+/// Check that this machine has everything varys needs and print how to fix any problems.
+///
+/// Unlike the selftest, this checks the real database, network interface, audio devices and voices.
+///
+/// # Arguments
+///
+/// * `interface`: The network interface traffic is captured on.
+/// * `voices`: The voices queries are spoken with.
+/// * `model`: The path to the whisper model.
+/// * `data_dir`: The directory in which data files are stored.
+pub async fn run(
+    interface: &str,
+    voices: &[String],
+    model: &Path,
+    data_dir: &Path,
+) -> Result<(), Error> {
+    let checks = [
+        check_database().await,
+        check_capture(interface),
+        check_microphone(),
+        check_voices(voices),
+        check_model(model),
+        check_disk_space(data_dir),
+    ];
+
+    for check in &checks {
+        match &check.result {
+            Ok(found) => println!("{} {}: {found}", "✓".green(), check.name),
+            Err(fix) => println!("{} {}: {fix}", "✗".red(), check.name.red()),
+        }
+    }
+
+    let problems = checks.iter().filter(|check| check.result.is_err()).count();
+    if problems == 0 {
+        println!("{}", "Everything is set up correctly".green());
+
+        Ok(())
+    } else {
+        Err(Error::ProblemsFound(problems))
+    }
+}
+
+/// Check that the database in `DATABASE_URL` can be connected to.
+async fn check_database() -> Check {
+    let result = if env::var("DATABASE_URL").is_err() {
+        Err(
+            "DATABASE_URL is not set, add it to the environment or a .env file, \
+            e.g. DATABASE_URL=postgres://postgres:<password>@localhost:5432/varys"
+                .to_string(),
+        )
+    } else {
+        match database::connect().await {
+            Ok(_) => Ok("Connected and migrated".to_string()),
+            Err(error @ varys_database::error::Error::DatabaseMigration(_)) => Err(format!(
+                "Could not migrate ({error}), check that DATABASE_URL points to a database created by varys or an \
+                empty one"
+            )),
+            Err(error) => Err(format!(
+                "Could not connect ({error}), start the database with `docker compose up -d` and check that \
+                DATABASE_URL points to it"
+            )),
+        }
+    };
+
+    Check {
+        name: "Database",
+        result,
+    }
+}
+
+/// Check that traffic can be captured on a network interface by capturing on it briefly.
+///
+/// # Arguments
+///
+/// * `interface`: The network interface to check.
+fn check_capture(interface: &str) -> Check {
+    let name = "Packet capture";
+    let device = match sniff::device_by_name(interface) {
+        Ok(device) => device,
+        Err(error) => {
+            let available = sniff::all_devices()
+                .map(|devices| {
+                    devices
+                        .into_iter()
+                        .map(|device| device.name)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                })
+                .unwrap_or_default();
+
+            return Check {
+                name,
+                result: Err(format!(
+                    "{error}, pass one of the available interfaces with --interface: {available}"
+                )),
+            };
+        }
+    };
+
+    let capture_path = env::temp_dir().join(format!("varys_doctor_{}.pcap", process::id()));
+    let result = Sniffer::from(device)
+        .start(&capture_path)
+        .and_then(|instance| instance.stop())
+        .map(|_| format!("Can capture traffic on {interface}"))
+        .map_err(|error| {
+            format!(
+                "Cannot capture traffic on {interface} ({error}), run varys as root or allow your user to capture \
+                packets, e.g. with `sudo chmod o+r /dev/bpf*` on macOS or \
+                `sudo setcap cap_net_raw,cap_net_admin=eip <path to varys>` on Linux"
+            )
+        });
+    let _ = fs::remove_file(&capture_path);
+    let _ = fs::remove_file(index::index_path(&capture_path));
+
+    Check { name, result }
+}
+
+/// Check that there is a microphone to record queries and responses with.
+fn check_microphone() -> Check {
+    #[cfg(feature = "collection")]
+    let result = Listener::new()
+        .map(|_| "Found a default input device".to_string())
+        .map_err(|error| {
+            format!("{error}, connect a microphone and select it as the default input device")
+        });
+    #[cfg(not(feature = "collection"))]
+    let result = Ok("Not needed, varys was built without collection".to_string());
+
+    Check {
+        name: "Microphone",
+        result,
+    }
+}
+
+/// Check that all voices queries are spoken with are installed.
+///
+/// # Arguments
+///
+/// * `voices`: The voices to check.
+fn check_voices(voices: &[String]) -> Check {
+    #[cfg(feature = "collection")]
+    let result = {
+        let missing: Vec<&str> = voices
+            .iter()
+            .filter(|voice| Speaker::with_voice(voice).is_err())
+            .map(String::as_str)
+            .collect();
+
+        if missing.is_empty() {
+            Ok(format!("{} are installed", voices.join(", ")))
+        } else {
+            Err(format!(
+                "{} are not installed, download them in the system settings (Accessibility > Spoken Content on \
+                macOS) or pass installed voices with --voices",
+                missing.join(", ")
+            ))
+        }
+    };
+    #[cfg(not(feature = "collection"))]
+    let result = Ok(format!(
+        "Not needed for {} voices, varys was built without collection",
+        voices.len()
+    ));
+
+    Check {
+        name: "Voices",
+        result,
+    }
+}
+
+/// Check that the whisper model exists and is not empty, e.g. from an interrupted download.
+///
+/// # Arguments
+///
+/// * `model`: The path to the whisper model.
+fn check_model(model: &Path) -> Check {
+    let download = "download a model from https://huggingface.co/ggerganov/whisper.cpp/tree/main";
+    let result = match fs::metadata(model) {
+        Ok(metadata) if metadata.len() > 0 => Ok(format!(
+            "{} ({:.0} MB)",
+            model.display(),
+            metadata.len() as f64 / 1_000_000.0
+        )),
+        Ok(_) => Err(format!("{} is empty, {download}", model.display())),
+        Err(_) => Err(format!(
+            "{} does not exist, {download} and pass its path with --model",
+            model.display()
+        )),
+    };
+
+    Check {
+        name: "Whisper model",
+        result,
+    }
+}
+
+/// Check that the disk of the data directory has enough free space for new sessions.
+///
+/// # Arguments
+///
+/// * `data_dir`: The directory in which data files are stored.
+fn check_disk_space(data_dir: &Path) -> Check {
+    let name = "Disk space";
+    let Some(path) = env::current_dir()
+        .map(|current_dir| current_dir.join(data_dir))
+        .ok()
+        .and_then(|path| {
+            path.ancestors()
+                .find_map(|ancestor| ancestor.canonicalize().ok())
+        })
+    else {
+        return Check {
+            name,
+            result: Err(format!("Cannot find {}", data_dir.display())),
+        };
+    };
+
+    let mut system = System::new();
+    system.refresh_disks_list();
+    let Some(disk) = system
+        .disks()
+        .iter()
+        .filter(|disk| path.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len())
+    else {
+        return Check {
+            name,
+            result: Err(format!("Cannot find the disk of {}", path.display())),
+        };
+    };
+
+    let available = disk.available_space() as f64 / 1_000_000_000.0;
+    let result = if disk.available_space() >= MIN_FREE_SPACE {
+        Ok(format!(
+            "{available:.1} GB free on {}",
+            disk.mount_point().display()
+        ))
+    } else {
+        Err(format!(
+            "Only {available:.1} GB free on {}, free up space or move the data to a larger disk and update it \
+            with `varys relocate`",
+            disk.mount_point().display()
+        ))
+    };
+
+    Check { name, result }
+}
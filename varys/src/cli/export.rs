@@ -1,8 +1,10 @@
 use std::{
+    collections::HashSet,
     fs::{self, File},
     io::Write,
     path::Path,
     str::FromStr,
+    sync::Arc,
 };
 
 use chrono::{DateTime, Utc};
@@ -10,15 +12,29 @@ use clap::ValueEnum;
 use regex::Regex;
 use serde::Serialize;
 use varys_analysis::trace::TrafficTrace;
-use varys_database::{database::interaction::Interaction, file};
+use varys_database::{
+    database::{self, interaction::Interaction, session_note::SessionNote},
+    file,
+};
 use varys_network::{address::MacAddress, packet};
 
-use crate::{assistant::VoiceAssistant, cli, dataset::DatasetSize, error::Error};
+use crate::{
+    assistant::VoiceAssistant, cli, dataset::Dataset, error::Error, redact::Redactor,
+};
+
+mod health;
+mod labels;
 
 #[derive(ValueEnum, Clone, Debug)]
 pub enum ExportType {
     Wang,
     Ahmed,
+    /// Label tracks with the query, response and transcript of each interaction for Audacity
+    Audacity,
+    /// Annotation documents with the query, response and transcript of each interaction for ELAN
+    Elan,
+    /// The number of samples, failure rate and mean latencies of each query per day, to audit campaigns for drift
+    Health,
 }
 
 #[derive(Serialize, Clone, Debug)]
@@ -42,8 +58,9 @@ impl ExportType {
     pub async fn export<P: AsRef<Path>>(
         &self,
         data_dir: P,
-        dataset_size: &DatasetSize,
-        voice_assistant: Box<dyn VoiceAssistant>,
+        dataset_size: &Dataset,
+        voice_assistant: Arc<dyn VoiceAssistant>,
+        redactor: Option<&Redactor>,
     ) -> Result<(), Error> {
         let export_dir = data_dir
             .as_ref()
@@ -51,6 +68,9 @@ impl ExportType {
             .join(match self {
                 ExportType::Wang => "wang",
                 ExportType::Ahmed => "ahmed",
+                ExportType::Audacity => "audacity",
+                ExportType::Elan => "elan",
+                ExportType::Health => "health",
             })
             .join(dataset_size.to_string());
 
@@ -69,17 +89,118 @@ impl ExportType {
                 )
                 .await
             }
+            ExportType::Audacity | ExportType::Elan => {
+                Self::export_labels(&export_dir, dataset_size, self, redactor).await
+            }
+            ExportType::Health => Self::export_health(&export_dir, dataset_size).await,
+        }?;
+
+        Self::export_session_notes(&export_dir, dataset_size).await
+    }
+
+    /// Export the label tracks of all interactions in the dataset, one file per interaction.
+    ///
+    /// Transcripts are redacted with the redactor, if one is given.
+    async fn export_labels(
+        export_dir: &Path,
+        dataset_size: &Dataset,
+        format: &ExportType,
+        redactor: Option<&Redactor>,
+    ) -> Result<(), Error> {
+        let interactions = Self::get_interactions(dataset_size).await?;
+
+        fs::create_dir_all(export_dir)?;
+
+        for mut interaction in interactions {
+            if let (Some(redactor), Some(response)) = (redactor, &interaction.response) {
+                interaction.response = Some(redactor.redact(response));
+            }
+            let interaction = &interaction;
+            let labels = labels::interaction_labels(interaction);
+            if labels.is_empty() {
+                log::warn!("Skipping incomplete interaction: {:?}", interaction.id);
+                continue;
+            }
+
+            let (extension, content) = match format {
+                ExportType::Elan => ("eaf", labels::to_elan(&labels, "varys")),
+                _ => ("txt", labels::to_audacity(&labels)),
+            };
+            let labels_path = export_dir.join(format!(
+                "s{}i{}-labels.{}",
+                interaction.session_id, interaction.id, extension
+            ));
+
+            fs::write(&labels_path, content)?;
+            log::trace!("Exported {:?}", labels_path);
+        }
+
+        Ok(())
+    }
+
+    /// Export the collection metrics of all interactions in the dataset per day and query to `health.csv`.
+    ///
+    /// Unlike the other formats, this includes the interactions that are excluded from datasets, e.g. because the
+    /// assistant did not respond, so their share can be audited.
+    async fn export_health(export_dir: &Path, dataset_size: &Dataset) -> Result<(), Error> {
+        let connection = database::connect().await?;
+        let interactions = dataset_size.filter(Interaction::get_all(&connection).await?);
+
+        fs::create_dir_all(export_dir)?;
+        let health_path = export_dir.join("health.csv");
+        let mut csv = csv::Writer::from_path(&health_path)?;
+        for day in health::daily_health(&interactions) {
+            csv.serialize(day)?;
+        }
+        csv.flush()?;
+
+        log::info!("Exported collection health to {:?}", health_path);
+
+        Ok(())
+    }
+
+    /// Export the notes of all sessions in the dataset to `session_notes.csv`.
+    async fn export_session_notes(
+        export_dir: &Path,
+        dataset_size: &Dataset,
+    ) -> Result<(), Error> {
+        let connection = database::connect().await?;
+        let session_ids: HashSet<i32> = Self::get_interactions(dataset_size)
+            .await?
+            .iter()
+            .map(|interaction| interaction.session_id)
+            .collect();
+        fs::create_dir_all(export_dir)?;
+        let notes_path = export_dir.join("session_notes.csv");
+        let mut csv = File::create(&notes_path)?;
+
+        writeln!(csv, "session_id,created,note")?;
+        for note in SessionNote::get_all(&connection)
+            .await?
+            .iter()
+            .filter(|note| session_ids.contains(&note.session_id))
+        {
+            writeln!(
+                csv,
+                "{},{},\"{}\"",
+                note.session_id,
+                note.created.to_rfc3339(),
+                note.note.replace('"', "\"\"")
+            )?;
         }
+
+        log::info!("Exported session notes to {:?}", notes_path);
+
+        Ok(())
     }
 
     async fn export_ahmed<P: AsRef<Path>>(
         data_dir: P,
         export_dir: P,
-        dataset_size: &DatasetSize,
-        voice_assistant: Box<dyn VoiceAssistant>,
+        dataset_size: &Dataset,
+        voice_assistant: Arc<dyn VoiceAssistant>,
     ) -> Result<(), Error> {
         let interactions = Self::get_interactions(dataset_size).await?;
-        let valid_greetings = vec!["Hey Siri. ", "Alexa. "];
 
         log::info!("Loaded interactions: {}", interactions.len());
 
@@ -93,14 +214,7 @@ impl ExportType {
         fs::create_dir_all(&captures_dir)?;
 
         for query in dataset_size.queries().iter() {
-            let valid_queries: Vec<String> = valid_greetings.iter()
-                .map(|greeting| format!("{}{}", greeting, query))
-                .collect();
-
-            let query_stripped = query
-                .strip_prefix(&format!("{}. ", voice_assistant.wake_word()))
-                .unwrap_or(query);
-            let label = query_stripped.to_lowercase().replace(' ', "-");
+            let label = query.to_lowercase().replace(' ', "-");
             let label = Regex::new(r"[^a-zA-Z0-9\-]")
                 .expect("Invalid label regex")
                 .replace_all(&label, "")
@@ -113,7 +227,7 @@ impl ExportType {
             log::info!("Exporting interactions for \"{}\" to {:?}", query, query_dir);
 
             for interaction in interactions.iter().filter(|interaction| {
-                valid_queries.iter().any(|valid_query| interaction.query == *valid_query) && interaction.capture_file.is_some()
+                interaction.query == *query && interaction.capture_file.is_some()
             }) {
                 log::info!("Processing interaction: {:?}", interaction.id);
 
@@ -140,7 +254,7 @@ impl ExportType {
                     ));
                     let ahmed_interaction = AhmedInteraction {
                         va: voice_assistant.name(),
-                        invoke_phrase: query_stripped.to_string(),
+                        invoke_phrase: query.to_string(),
                         wake_word: voice_assistant.wake_word(),
                         audio_fp: String::default(),
                         label: label.clone(),
@@ -169,10 +283,9 @@ impl ExportType {
     async fn export_wang<P: AsRef<Path>>(
         data_dir: P,
         export_dir: P,
-        dataset_size: &DatasetSize,
+        dataset_size: &Dataset,
     ) -> Result<(), Error> {
         let interactions = Self::get_interactions(dataset_size).await?;
-        let valid_greetings = vec!["Hey Siri. ", "Alexa. "];
     
         log::info!("Loaded interactions: {}", interactions.len());
     
@@ -187,14 +300,10 @@ impl ExportType {
     
             log::info!("Exporting interactions for \"{}\" to {:?}", query, query_dir);
     
-            let valid_queries: Vec<String> = valid_greetings.iter()
-                .map(|greeting| format!("{}{}", greeting, query))
-                .collect();
-    
             for (index, interaction) in interactions
                 .iter()
                 .filter(|interaction| {
-                    valid_queries.iter().any(|valid_query| interaction.query == *valid_query) && interaction.capture_file.is_some()
+                    interaction.query == *query && interaction.capture_file.is_some()
                 })
                 .enumerate()
             {
@@ -256,7 +365,7 @@ impl ExportType {
         datetime.timestamp() as f64 + datetime.timestamp_subsec_nanos() as f64 * 1e-9
     }
 
-    async fn get_interactions(dataset_size: &DatasetSize) -> Result<Vec<Interaction>, Error> {
+    async fn get_interactions(dataset_size: &Dataset) -> Result<Vec<Interaction>, Error> {
         let interactions = cli::get_filtered_interactions(dataset_size).await?;
         log::info!("Number of interactions: {}", interactions.len());
         Ok(interactions)
@@ -0,0 +1,76 @@
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+use serde::Serialize;
+use varys_database::database::interaction::Interaction;
+
+use crate::response::InteractionStatus;
+
+/// The collection metrics of one query on one day, to find systematic drift in long campaigns.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DailyHealth {
+    /// The day the interactions were started on, in UTC.
+    pub day: String,
+    pub query: String,
+    /// The number of interactions with the query.
+    pub interactions: usize,
+    /// The number of interactions the assistant responded to, which are the samples of the query.
+    pub samples: usize,
+    /// The share of interactions that failed, were aborted or timed out.
+    pub failure_rate: f64,
+    /// The mean time from the end of the query to the start of the audible response in milliseconds.
+    pub mean_latency_ms: Option<f64>,
+    /// The mean time from the end of the query to the first large packet sent to the assistant in milliseconds.
+    pub mean_network_latency_ms: Option<f64>,
+}
+
+/// Aggregate the collection metrics of interactions per day and query, ordered by day and then query.
+///
+/// # Arguments
+///
+/// * `interactions`: The interactions to aggregate, including the failed ones.
+pub fn daily_health(interactions: &[Interaction]) -> Vec<DailyHealth> {
+    let mut groups: BTreeMap<(NaiveDate, &str), Vec<&Interaction>> = BTreeMap::new();
+    for interaction in interactions {
+        groups
+            .entry((interaction.started.date_naive(), &interaction.query))
+            .or_default()
+            .push(interaction);
+    }
+
+    groups
+        .into_iter()
+        .map(|((day, query), interactions)| {
+            let samples = interactions
+                .iter()
+                .filter(|interaction| interaction.status == InteractionStatus::Valid.to_string())
+                .count();
+
+            DailyHealth {
+                day: day.format("%Y-%m-%d").to_string(),
+                query: query.to_string(),
+                interactions: interactions.len(),
+                samples,
+                failure_rate: 1. - samples as f64 / interactions.len() as f64,
+                mean_latency_ms: mean(
+                    interactions
+                        .iter()
+                        .filter_map(|interaction| interaction.response_latency_ms),
+                ),
+                mean_network_latency_ms: mean(
+                    interactions
+                        .iter()
+                        .filter_map(|interaction| interaction.response_network_latency_ms),
+                ),
+            }
+        })
+        .collect()
+}
+
+fn mean(values: impl Iterator<Item = i32>) -> Option<f64> {
+    let (sum, count) = values.fold((0., 0), |(sum, count), value| {
+        (sum + f64::from(value), count + 1)
+    });
+
+    (count > 0).then(|| sum / count as f64)
+}
@@ -0,0 +1,149 @@
+use varys_database::database::interaction::Interaction;
+
+/// A label spanning part of an interaction.
+///
+/// Times are in milliseconds on the timeline of the interaction: the query starts at 0 and the response directly
+/// follows it.
+pub struct Label {
+    /// The tier this label belongs to, e.g. `query`.
+    pub tier: &'static str,
+    pub start: i32,
+    pub end: i32,
+    pub text: String,
+}
+
+/// The tiers of labels exported for each interaction.
+pub const TIERS: [&str; 3] = ["query", "response", "transcript"];
+
+/// Get the labels for the query span, the response span and the transcript of an interaction.
+///
+/// The query is labelled with its text and the response with the name of its audio file.
+/// The transcript is a single segment spanning the whole response, since the timing of individual segments is not
+/// stored. Spans with an unknown duration are left out.
+///
+/// # Arguments
+///
+/// * `interaction`: The interaction to get the labels for.
+pub fn interaction_labels(interaction: &Interaction) -> Vec<Label> {
+    let mut labels = Vec::new();
+    let query_duration = match interaction.query_duration {
+        Some(duration) => duration,
+        None => return labels,
+    };
+
+    labels.push(Label {
+        tier: TIERS[0],
+        start: 0,
+        end: query_duration,
+        text: interaction.query.clone(),
+    });
+
+    if let Some(response_duration) = interaction.response_duration {
+        let end = query_duration + response_duration;
+
+        labels.push(Label {
+            tier: TIERS[1],
+            start: query_duration,
+            end,
+            text: interaction
+                .response_file
+                .clone()
+                .unwrap_or("response".to_string()),
+        });
+        if let Some(response) = &interaction.response {
+            labels.push(Label {
+                tier: TIERS[2],
+                start: query_duration,
+                end,
+                text: response.trim().to_string(),
+            });
+        }
+    }
+
+    labels
+}
+
+/// Format labels as an Audacity label track.
+///
+/// Each line contains the start and end in seconds and the text of the label, separated by tabs. Since Audacity label
+/// tracks have no tiers, the text is prefixed with the tier.
+///
+/// # Arguments
+///
+/// * `labels`: The labels to format.
+pub fn to_audacity(labels: &[Label]) -> String {
+    labels
+        .iter()
+        .map(|label| {
+            format!(
+                "{:.3}\t{:.3}\t{}: {}\n",
+                label.start as f64 / 1000.0,
+                label.end as f64 / 1000.0,
+                label.tier,
+                label.text.replace(['\t', '\n'], " ")
+            )
+        })
+        .collect()
+}
+
+/// Format labels as an ELAN annotation document (`.eaf`) with one tier per label tier.
+///
+/// # Arguments
+///
+/// * `labels`: The labels to format.
+/// * `author`: The author stored in the document.
+pub fn to_elan(labels: &[Label], author: &str) -> String {
+    let time_slots: String = labels
+        .iter()
+        .enumerate()
+        .map(|(index, label)| {
+            format!(
+                "        <TIME_SLOT TIME_SLOT_ID=\"ts{}\" TIME_VALUE=\"{}\"/>\n        <TIME_SLOT TIME_SLOT_ID=\"ts{}\" TIME_VALUE=\"{}\"/>\n",
+                2 * index + 1,
+                label.start,
+                2 * index + 2,
+                label.end
+            )
+        })
+        .collect();
+    let tiers: String = TIERS
+        .iter()
+        .map(|tier| {
+            let annotations: String = labels
+                .iter()
+                .enumerate()
+                .filter(|(_, label)| label.tier == *tier)
+                .map(|(index, label)| {
+                    format!(
+                        "        <ANNOTATION>\n            <ALIGNABLE_ANNOTATION ANNOTATION_ID=\"a{}\" TIME_SLOT_REF1=\"ts{}\" TIME_SLOT_REF2=\"ts{}\">\n                <ANNOTATION_VALUE>{}</ANNOTATION_VALUE>\n            </ALIGNABLE_ANNOTATION>\n        </ANNOTATION>\n",
+                        index + 1,
+                        2 * index + 1,
+                        2 * index + 2,
+                        escape_xml(&label.text)
+                    )
+                })
+                .collect();
+
+            format!("    <TIER LINGUISTIC_TYPE_REF=\"default\" TIER_ID=\"{tier}\">\n{annotations}    </TIER>\n")
+        })
+        .collect();
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<ANNOTATION_DOCUMENT AUTHOR="{}" FORMAT="3.0" VERSION="3.0">
+    <HEADER MEDIA_FILE="" TIME_UNITS="milliseconds"/>
+    <TIME_ORDER>
+{time_slots}    </TIME_ORDER>
+{tiers}    <LINGUISTIC_TYPE GRAPHIC_REFERENCES="false" LINGUISTIC_TYPE_ID="default" TIME_ALIGNABLE="true"/>
+</ANNOTATION_DOCUMENT>
+"#,
+        escape_xml(author)
+    )
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,381 @@
+use std::collections::BTreeSet;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+use log::info;
+use serde::{Deserialize, Serialize};
+use varys_analysis::ml::data::{NumericTraceDataset, TraceRepresentation};
+use varys_analysis::ml::metric::{MetricPlugins, TopKAccuracy};
+use varys_analysis::ml::run::RunDir;
+use varys_analysis::ml::{self, TrainingOptions};
+#[cfg(all(feature = "collection", feature = "transcription"))]
+use varys_audio::stt::transcriber::Transcriber;
+#[cfg(all(feature = "collection", feature = "transcription"))]
+use varys_audio::stt::Recogniser;
+
+use crate::assistant;
+#[cfg(feature = "collection")]
+use crate::assistant::interactor::Interactor;
+#[cfg(feature = "collection")]
+use crate::assistant::shutdown::Shutdown;
+use crate::dataset::DatasetSize;
+use crate::error::Error;
+#[cfg(feature = "collection")]
+use crate::query::{validation, Query};
+
+/// The stages of a pipeline, in the order they run in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Stage {
+    /// Run a collection session.
+    Collect,
+    /// Create the label map and the dataset of numeric traces.
+    Extract,
+    /// Record which interactions the dataset is made of.
+    Snapshot,
+    /// Train a model on the dataset.
+    Train,
+    /// Compile the metrics of the training run into a report.
+    Report,
+}
+
+impl Stage {
+    const ALL: [Stage; 5] = [
+        Stage::Collect,
+        Stage::Extract,
+        Stage::Snapshot,
+        Stage::Train,
+        Stage::Report,
+    ];
+}
+
+impl Display for Stage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Stage::Collect => write!(f, "collect"),
+            Stage::Extract => write!(f, "extract"),
+            Stage::Snapshot => write!(f, "snapshot"),
+            Stage::Train => write!(f, "train"),
+            Stage::Report => write!(f, "report"),
+        }
+    }
+}
+
+/// A whole experiment, read from a TOML file:
+///
+/// ```toml
+/// name = "small-siri"
+/// data_dir = "data"
+///
+/// # optional, the stage is skipped without it
+/// [collect]
+/// assistant = "Siri"
+/// mac = "00:11:22:33:44:55"
+/// queries = "small"
+///
+/// [dataset]
+/// preset = "small"
+///
+/// [train]
+/// bursts = false
+/// ```
+#[derive(Debug, Deserialize)]
+pub struct PipelineConfig {
+    /// The name of the pipeline, which names the directory of its checkpoints and report.
+    pub name: String,
+    /// The directory in which data files are stored.
+    pub data_dir: PathBuf,
+    pub collect: Option<CollectConfig>,
+    #[serde(default)]
+    pub dataset: DatasetConfig,
+    #[serde(default)]
+    pub train: TrainConfig,
+}
+
+/// The collection session of a pipeline.
+#[derive(Debug, Deserialize)]
+pub struct CollectConfig {
+    /// The name of the voice assistant to interact with.
+    pub assistant: String,
+    /// The MAC address of the assistant.
+    pub mac: String,
+    /// The queries to ask, in any form accepted by `varys run --queries`.
+    pub queries: String,
+    /// Whether the queries may list a query more than once, to ask it more often.
+    #[serde(default)]
+    pub allow_repeats: bool,
+}
+
+/// The dataset a pipeline trains on, like the dataset options of `varys analyse`.
+#[derive(Debug, Default, Deserialize)]
+pub struct DatasetConfig {
+    /// The name of a built-in dataset, `full` by default.
+    pub preset: Option<String>,
+    /// A TOML, YAML or CSV file to load the dataset from instead of using a built-in one.
+    pub file: Option<PathBuf>,
+    /// The voice assistant the dataset file was collected with, the one of the collection session by default.
+    pub assistant: Option<String>,
+    /// Only use the queries of these categories.
+    #[serde(default)]
+    pub categories: Vec<String>,
+    /// Use a random sample of this many interactions from every query category.
+    pub per_category: Option<usize>,
+    /// The seed the interactions of every category are sampled with.
+    #[serde(default)]
+    pub sample_seed: u64,
+}
+
+/// The training of a pipeline, like the options of `varys analyse train`.
+#[derive(Debug, Default, Deserialize)]
+pub struct TrainConfig {
+    /// Also log how often the correct query is among the k most likely ones.
+    pub top_k: Option<usize>,
+    /// Use the largest batch size that fits into the memory of the GPU.
+    #[serde(default)]
+    pub auto_batch_size: bool,
+    /// Train on bursts of packets instead of single packets.
+    #[serde(default)]
+    pub bursts: bool,
+}
+
+/// The progress of a pipeline, saved after every stage so an interrupted pipeline continues where it stopped.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Checkpoint {
+    completed: BTreeSet<Stage>,
+    session_id: Option<i32>,
+    run: Option<String>,
+}
+
+/// Which interactions a dataset was made of, so it can be created again.
+#[derive(Debug, Serialize)]
+struct Snapshot {
+    dataset: String,
+    label_map_hash: String,
+    interactions: Vec<i32>,
+    sessions: BTreeSet<i32>,
+}
+
+/// The summary of a pipeline, written by its last stage.
+#[derive(Debug, Serialize)]
+struct Report {
+    name: String,
+    session_id: Option<i32>,
+    run: Option<String>,
+    best_epoch: Option<usize>,
+    interactions: usize,
+}
+
+/// The machine-specific options a pipeline collects with, taken from the global arguments of varys.
+#[cfg(feature = "collection")]
+pub struct Rig {
+    pub interface: String,
+    pub voices: Vec<String>,
+    pub sensitivity: f32,
+    pub model: PathBuf,
+}
+
+/// Run all stages of a pipeline that have not completed yet.
+///
+/// The progress is saved to `pipelines/<name>/checkpoint.json` in the data directory after every stage, next to the
+/// snapshot and report of the pipeline.
+///
+/// # Arguments
+///
+/// * `config_path`: The path to the TOML configuration of the pipeline.
+/// * `restart`: Whether to run all stages again, ignoring the saved progress.
+/// * `rig`: The options to collect with.
+pub async fn run(
+    config_path: &Path,
+    restart: bool,
+    #[cfg(feature = "collection")] rig: Rig,
+) -> Result<(), Error> {
+    let config: PipelineConfig = toml::from_str(&fs::read_to_string(config_path)?)?;
+    let pipeline_path = config.data_dir.join("pipelines").join(&config.name);
+    fs::create_dir_all(&pipeline_path)?;
+    let checkpoint_path = pipeline_path.join("checkpoint.json");
+
+    let mut checkpoint: Checkpoint = if restart || !checkpoint_path.exists() {
+        Checkpoint::default()
+    } else {
+        serde_json::from_str(&fs::read_to_string(&checkpoint_path)?)?
+    };
+    let dataset = super::dataset(
+        config
+            .dataset
+            .preset
+            .as_deref()
+            .map(|preset| <DatasetSize as ValueEnum>::from_str(preset, true))
+            .transpose()
+            .map_err(Error::InvalidPipeline)?
+            .unwrap_or_default(),
+        config.dataset.file.clone(),
+        config
+            .dataset
+            .assistant
+            .as_ref()
+            .or(config.collect.as_ref().map(|collect| &collect.assistant))
+            .map(|assistant| assistant::from(assistant).wake_word())
+            .as_deref(),
+        config.dataset.categories.clone(),
+        config.dataset.per_category,
+        config.dataset.sample_seed,
+    )?;
+
+    for stage in Stage::ALL {
+        if checkpoint.completed.contains(&stage) {
+            info!("Skipping the {stage} stage, it already completed");
+            continue;
+        }
+
+        info!("Running the {stage} stage of pipeline {}", config.name);
+
+        match stage {
+            Stage::Collect => {
+                if let Some(collect) = &config.collect {
+                    #[cfg(feature = "collection")]
+                    {
+                        checkpoint.session_id =
+                            Some(collect_session(&config, collect, &rig).await?);
+                    }
+                    #[cfg(not(feature = "collection"))]
+                    {
+                        let _ = collect;
+                        return Err(Error::FeatureDisabled("collection".to_string()));
+                    }
+                }
+            }
+            Stage::Extract => {
+                let interactions = super::get_filtered_interactions(&dataset).await?;
+                let label_map =
+                    ml::create_label_map(&config.data_dir, &interactions, &dataset.to_string())?;
+                NumericTraceDataset::new(&config.data_dir, interactions, label_map)?
+                    .save(&config.data_dir)?;
+            }
+            Stage::Snapshot => {
+                let traces = NumericTraceDataset::load(&config.data_dir)?;
+                let interactions = super::get_filtered_interactions(&dataset).await?;
+                let snapshot = Snapshot {
+                    dataset: traces.label_map.preset.clone(),
+                    label_map_hash: traces.label_map.hash.clone(),
+                    interactions: interactions
+                        .iter()
+                        .map(|interaction| interaction.id)
+                        .collect(),
+                    sessions: interactions
+                        .iter()
+                        .map(|interaction| interaction.session_id)
+                        .collect(),
+                };
+                serde_json::to_writer_pretty(
+                    fs::File::create(pipeline_path.join("snapshot.json"))?,
+                    &snapshot,
+                )?;
+            }
+            Stage::Train => {
+                let mut metrics = MetricPlugins::new();
+                if let Some(k) = config.train.top_k {
+                    metrics = metrics.with(move || TopKAccuracy::new(k));
+                }
+
+                ml::train(
+                    &config.data_dir,
+                    super::get_filtered_interactions(&dataset).await?,
+                    &dataset.to_string(),
+                    &metrics,
+                    &TrainingOptions {
+                        auto_batch_size: config.train.auto_batch_size,
+                        representation: if config.train.bursts {
+                            TraceRepresentation::Bursts
+                        } else {
+                            TraceRepresentation::Packets
+                        },
+                        ..TrainingOptions::default()
+                    },
+                )?;
+                checkpoint.run = Some(RunDir::latest(&config.data_dir)?.name());
+            }
+            Stage::Report => {
+                let run = match &checkpoint.run {
+                    Some(name) => RunDir::named(&config.data_dir, name)?,
+                    None => RunDir::latest(&config.data_dir)?,
+                };
+                ml::compile_all_logs(&config.data_dir, &config.name)?;
+                let report = Report {
+                    name: config.name.clone(),
+                    session_id: checkpoint.session_id,
+                    run: checkpoint.run.clone(),
+                    best_epoch: run.best_epoch(),
+                    interactions: NumericTraceDataset::load(&config.data_dir)?.items.len(),
+                };
+                serde_json::to_writer_pretty(
+                    fs::File::create(pipeline_path.join("report.json"))?,
+                    &report,
+                )?;
+
+                println!(
+                    "Pipeline {} trained run {} on {} traces",
+                    report.name,
+                    run.name(),
+                    report.interactions
+                );
+            }
+        }
+
+        checkpoint.completed.insert(stage);
+        fs::write(&checkpoint_path, serde_json::to_string_pretty(&checkpoint)?)?;
+    }
+
+    Ok(())
+}
+
+/// Run the collection session of a pipeline.
+///
+/// Returns the id of the session.
+///
+/// # Arguments
+///
+/// * `config`: The configuration of the pipeline.
+/// * `collect`: The configuration of the session.
+/// * `rig`: The options to collect with.
+#[cfg(feature = "collection")]
+async fn collect_session(
+    config: &PipelineConfig,
+    collect: &CollectConfig,
+    rig: &Rig,
+) -> Result<i32, Error> {
+    let mut interactor = Interactor::new(
+        rig.interface.clone(),
+        rig.voices.clone(),
+        rig.sensitivity,
+        rig.model.to_string_lossy().to_string(),
+        config.data_dir.clone(),
+        collect.mac.clone(),
+    )?;
+    interactor.set_shutdown(Some(Shutdown::on_ctrl_c()));
+
+    let assistant = assistant::from(&collect.assistant);
+    let mut queries = Query::read(&collect.queries).await?;
+    validation::validate(
+        queries.iter().map(|query| query.text.as_str()),
+        Some(&assistant.wake_word()),
+    )
+    .allow_repeats(collect.allow_repeats)
+    .check()?;
+
+    #[cfg(feature = "transcription")]
+    let transcriber_handle = {
+        let (transcriber, transcriber_handle) =
+            Transcriber::new(Recogniser::with_model_path(&rig.model.to_string_lossy())?);
+
+        let _ = std::thread::spawn(move || transcriber.start());
+        Some(transcriber_handle)
+    };
+    #[cfg(not(feature = "transcription"))]
+    let transcriber_handle = None;
+
+    interactor
+        .start(&mut queries, assistant.as_ref(), transcriber_handle)
+        .await
+}
@@ -0,0 +1,164 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use clap::ValueEnum;
+use log::{info, warn};
+use varys_audio::stt::Recogniser;
+use varys_database::database;
+use varys_database::database::interaction::Interaction;
+use varys_database::database::relabel::Relabel;
+use varys_database::file;
+
+use crate::assistant::conditions::Conditions;
+use crate::dataset::Dataset;
+use crate::error::Error;
+use crate::query;
+
+/// What to do with an interaction whose recorded query does not match the query it was supposed to ask.
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+pub enum RelabelAction {
+    /// Exclude the interaction from datasets
+    #[default]
+    Invalidate,
+    /// Reassign the interaction to the query that was actually heard, or exclude it if no query matches
+    Reassign,
+}
+
+/// Transcribe the recorded queries of a dataset and relabel interactions whose query was not spoken or heard as
+/// intended, replacing previous relabels.
+///
+/// # Arguments
+///
+/// * `data_dir`: The directory in which data files are stored.
+/// * `model`: The path to the speech recognition model.
+/// * `dataset_size`: The dataset to check.
+/// * `threshold`: The similarity from `0` to `1` a transcript needs to count as a match of a query.
+/// * `action`: What to do with interactions that do not match their query.
+/// * `clear`: Whether to only remove the previous relabels.
+pub async fn run(
+    data_dir: &Path,
+    model: &Path,
+    dataset_size: &Dataset,
+    threshold: f32,
+    action: RelabelAction,
+    clear: bool,
+) -> Result<(), Error> {
+    let connection = database::connect().await?;
+    let removed = Relabel::delete_all(&connection).await?;
+
+    info!("Removed {removed} previous relabels");
+
+    if clear {
+        return Ok(());
+    }
+
+    let interactions: Vec<Interaction> = dataset_size
+        .filter(Interaction::get_all(&connection).await?)
+        .into_iter()
+        .filter(|interaction| interaction.is_complete())
+        .collect();
+    let queries: BTreeSet<&str> = interactions
+        .iter()
+        .map(|interaction| interaction.query.as_str())
+        .collect();
+    let recogniser = Recogniser::with_model_path(&model.to_string_lossy())?;
+    let mut relabelled = 0;
+
+    info!(
+        "Checking the recorded queries of {} interactions...",
+        interactions.len()
+    );
+
+    for interaction in &interactions {
+        let Some(query_file) = &interaction.query_file else {
+            continue;
+        };
+        let query_path = file::session_path(data_dir, interaction.session_id).join(query_file);
+        let transcript = match varys_audio::file::read_opus(&query_path)
+            .and_then(|mut audio| recogniser.recognise(&mut audio))
+        {
+            Ok(transcript) => transcript,
+            Err(error) => {
+                warn!("Could not transcribe the query of {interaction}: {error}");
+                continue;
+            }
+        };
+
+        // the recorded query includes the wake word it was asked with
+        let locale = locale(interaction);
+        let similarity =
+            query::similarity(&transcript, &interaction.spoken_query(), locale.as_deref());
+        if similarity >= threshold {
+            continue;
+        }
+
+        let label = match action {
+            RelabelAction::Invalidate => None,
+            RelabelAction::Reassign => closest_query(
+                &transcript,
+                &queries,
+                interaction.wake_word.as_deref(),
+                locale.as_deref(),
+                threshold,
+            ),
+        };
+        let relabel = Relabel::create(
+            &connection,
+            interaction.id,
+            transcript.trim(),
+            similarity,
+            label,
+        )
+        .await?;
+        relabelled += 1;
+
+        println!("{relabel}");
+    }
+
+    info!(
+        "Relabelled {relabelled} of {} interactions",
+        interactions.len()
+    );
+
+    Ok(())
+}
+
+/// Get the query that is most similar to a transcript, if it is at least as similar as the threshold.
+///
+/// # Arguments
+///
+/// * `transcript`: The transcript to match.
+/// * `queries`: The queries to choose from, without the wake word.
+/// * `wake_word`: The wake word the transcribed query was asked with, if any.
+/// * `locale`: The locale the transcribed query was asked in, if any.
+/// * `threshold`: The similarity from `0` to `1` the query needs to have.
+fn closest_query(
+    transcript: &str,
+    queries: &BTreeSet<&str>,
+    wake_word: Option<&str>,
+    locale: Option<&str>,
+    threshold: f32,
+) -> Option<String> {
+    queries
+        .iter()
+        .map(|query| {
+            let spoken = match wake_word {
+                Some(wake_word) => format!("{wake_word}. {query}"),
+                None => query.to_string(),
+            };
+
+            (query, query::similarity(transcript, &spoken, locale))
+        })
+        .filter(|(_, similarity)| *similarity >= threshold)
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(query, _)| query.to_string())
+}
+
+/// Get the locale an interaction was asked in from its conditions, see [`Conditions::locale`].
+fn locale(interaction: &Interaction) -> Option<String> {
+    interaction
+        .conditions
+        .as_deref()
+        .and_then(|conditions| serde_json::from_str::<Conditions>(conditions).ok())
+        .and_then(|conditions| conditions.locale)
+}
@@ -0,0 +1,125 @@
+use std::path::{Path, PathBuf};
+
+use log::info;
+use varys_database::connection::DatabaseConnection;
+use varys_database::database;
+use varys_database::database::interaction::Interaction;
+use varys_database::database::session::Session;
+use varys_database::file;
+
+use crate::error::Error;
+
+/// Rewrite the stored paths of all sessions inside `from` to point inside `to` instead.
+///
+/// Before a session is updated, all of its data files are checked to exist at the new location. Sessions with missing
+/// files are left unchanged and reported.
+///
+/// # Arguments
+///
+/// * `from`: The directory the data was moved from.
+/// * `to`: The directory the data was moved to.
+/// * `dry_run`: Whether to only check the files without updating any paths.
+pub async fn run(from: &Path, to: &Path, dry_run: bool) -> Result<(), Error> {
+    let connection = database::connect().await?;
+    let mut relocated = 0;
+    let mut incomplete = 0;
+
+    for mut session in Session::get_all(&connection).await? {
+        let Some(data_dir) = session
+            .data_dir
+            .as_ref()
+            .and_then(|data_dir| file::relocate_path(data_dir, from, to))
+        else {
+            continue;
+        };
+
+        let mut interactions = session.interactions(&connection).await?;
+        let missing = missing_files(Path::new(&data_dir), &mut interactions, from, to);
+
+        if !missing.is_empty() {
+            incomplete += 1;
+            println!("{session} cannot be relocated, files are missing:");
+            for path in missing {
+                println!("    {}", path.display());
+            }
+            continue;
+        }
+
+        if dry_run {
+            println!("{session} can be relocated to {data_dir}");
+        } else {
+            update(&connection, &mut session, data_dir, &mut interactions).await?;
+            println!(
+                "Relocated {session} to {}",
+                session.data_dir.as_deref().unwrap_or_default()
+            );
+        }
+        relocated += 1;
+    }
+
+    info!("{relocated} sessions can be relocated, {incomplete} are missing files");
+
+    if incomplete > 0 {
+        return Err(Error::RelocationIncomplete(incomplete));
+    }
+
+    Ok(())
+}
+
+/// Map the stored file paths of interactions to the new location and find all files that do not exist there.
+///
+/// File names are resolved inside the new session directory. Full paths inside `from` are updated in place.
+fn missing_files(
+    session_dir: &Path,
+    interactions: &mut [Interaction],
+    from: &Path,
+    to: &Path,
+) -> Vec<PathBuf> {
+    let mut missing = Vec::new();
+
+    if !session_dir.is_dir() {
+        missing.push(session_dir.to_path_buf());
+    }
+
+    for interaction in interactions {
+        for stored in [
+            &mut interaction.query_file,
+            &mut interaction.query_loopback_file,
+            &mut interaction.response_file,
+            &mut interaction.response_lossless_file,
+            &mut interaction.capture_file,
+            &mut interaction.radio_capture_file,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            if let Some(relocated) = file::relocate_path(stored, from, to) {
+                *stored = relocated;
+            }
+
+            let path = session_dir.join(stored.as_str());
+            if !path.exists() {
+                missing.push(path);
+            }
+        }
+    }
+
+    missing
+}
+
+/// Store the new data directory of a session and the mapped file paths of its interactions.
+async fn update(
+    connection: &DatabaseConnection,
+    session: &mut Session,
+    data_dir: String,
+    interactions: &mut [Interaction],
+) -> Result<(), Error> {
+    session.data_dir = Some(data_dir);
+    session.update(connection).await?;
+
+    for interaction in interactions {
+        interaction.update(connection).await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,89 @@
+use std::thread;
+use std::time::Duration;
+
+use log::info;
+use varys_audio::audio::OPUS_SAMPLE_RATE;
+use varys_audio::impulse;
+use varys_audio::listen::Listener;
+use varys_audio::tts::Speaker;
+use varys_database::database;
+use varys_database::database::room_response::RoomResponse;
+use varys_database::database::session::Session;
+
+use crate::cli::arguments::MeasureRirCommand;
+use crate::error::Error;
+
+/// The frequency at which measurement sweeps start in Hz.
+const SWEEP_START_FREQUENCY: f32 = 20.0;
+/// The frequency at which measurement sweeps end in Hz.
+const SWEEP_END_FREQUENCY: f32 = 20000.0;
+/// How long to keep recording after a sweep has been played, so the reverberation of its end is captured.
+const RECORDING_TAIL: Duration = Duration::from_secs(1);
+
+/// Measure the impulse response of the room by playing a sweep and recording it.
+///
+/// The impulse response is stored as a `.wav` file and, if a session is given, recorded with the session to
+/// characterise its acoustic conditions.
+///
+/// # Arguments
+///
+/// * `command`: The measurement to take.
+pub async fn measure_rir(command: MeasureRirCommand) -> Result<(), Error> {
+    let session = match command.session {
+        Some(id) => {
+            let connection = database::connect().await?;
+            let session = Session::get(&connection, id)
+                .await?
+                .ok_or(Error::SessionNotFound(id))?;
+            Some((connection, session))
+        }
+        None => None,
+    };
+
+    let speaker = Speaker::new()?;
+    let listener = Listener::new()?;
+    let sweep = impulse::sweep(
+        SWEEP_START_FREQUENCY,
+        SWEEP_END_FREQUENCY,
+        command.duration * 1000,
+        OPUS_SAMPLE_RATE as u32,
+    );
+
+    info!("Playing a {}s sweep...", command.duration);
+
+    let instance = listener.start()?;
+    speaker.play(&sweep)?;
+    thread::sleep(RECORDING_TAIL);
+    let mut recording = instance.stop()?;
+    recording.convert_to_mono();
+
+    let (response, delay_ms) = impulse::impulse_response(&sweep, &recording, command.length);
+    let reverberation_ms = impulse::reverberation_time(&response);
+
+    let mut file = command.file;
+    file.set_extension("wav");
+    varys_audio::file::write_wav(&file, &response)?;
+
+    info!(
+        "Measured a delay of {delay_ms}ms and a reverberation time of {}, stored the impulse response in {}",
+        reverberation_ms.map_or("unknown".to_string(), |reverberation_ms| format!(
+            "{reverberation_ms}ms"
+        )),
+        file.display()
+    );
+
+    if let Some((connection, session)) = session {
+        let room_response = RoomResponse::create(
+            &connection,
+            session.id,
+            &file.to_string_lossy(),
+            delay_ms,
+            reverberation_ms,
+        )
+        .await?;
+
+        println!("{room_response}");
+    }
+
+    Ok(())
+}
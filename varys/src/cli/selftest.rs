@@ -0,0 +1,291 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use std::{env, fs, process, thread};
+
+use colored::Colorize;
+#[cfg(feature = "transcription")]
+use log::debug;
+use log::{info, warn};
+use varys_audio::listen::fake::FakeListener;
+use varys_audio::stt::fake::FakeRecogniser;
+use varys_audio::stt::transcriber::{Transcriber, TranscriberHandle};
+#[cfg(feature = "transcription")]
+use varys_audio::stt::Recogniser;
+use varys_audio::tts::fake::FakeSpeaker;
+use varys_database::connection::DatabaseConnection;
+use varys_database::database;
+use varys_database::database::interaction::Interaction;
+use varys_database::database::session::Session;
+use varys_database::file;
+use varys_network::sniff::fake::FakeSniffer;
+
+use crate::assistant;
+use crate::assistant::interactor::{Backends, Interactor, TranscribeInteraction};
+use crate::error::Error;
+use crate::query::Query;
+
+/// The tiny whisper model used to transcribe the response, in the `models` directory of the data directory, which is
+/// downloaded on the first selftest.
+#[cfg(feature = "transcription")]
+const TINY_MODEL: &str = "ggml-tiny.en.bin";
+
+/// Where to download the [`TINY_MODEL`] from.
+#[cfg(feature = "transcription")]
+const TINY_MODEL_URL: &str =
+    "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-tiny.en.bin";
+
+/// The response the fake recogniser transcribes if the tiny whisper model is not available.
+const FAKE_RESPONSE: &str = "This is a varys selftest.";
+
+/// The name of the model of the fake recogniser.
+const FAKE_MODEL: &str = "fake";
+
+/// Run a complete interaction with fake hardware to validate the setup of this machine.
+///
+/// The interaction is stored in a temporary database on the Postgres server specified in `DATABASE_URL`, since the
+/// database layer of varys only supports Postgres, and its files are written to a temporary data directory. Both are
+/// removed again afterwards. Without a reachable Postgres server, the selftest is skipped and says so.
+///
+/// The response is transcribed with the tiny whisper model at `models/ggml-tiny.en.bin` in the data directory, which is
+/// downloaded if it does not exist yet, and the selftest fails if it cannot be loaded. If it cannot be downloaded or
+/// varys was built without transcription, a fake recogniser is used instead and the result says so.
+///
+/// # Arguments
+///
+/// * `sensitivity`: The sensitivity of the listener.
+/// * `data_dir`: The data directory whose `models` directory holds the tiny whisper model.
+pub async fn run<P: AsRef<Path>>(sensitivity: f32, data_dir: P) -> Result<(), Error> {
+    let started = Instant::now();
+    let name = format!("varys_selftest_{}", process::id());
+    let temporary_dir = env::temp_dir().join(&name);
+    let database_url = match database::create_temporary(&name).await {
+        Ok(database_url) => database_url,
+        Err(error) => {
+            println!(
+                "{}: the interaction needs a Postgres server at DATABASE_URL ({error})",
+                "Selftest skipped".yellow()
+            );
+
+            return Ok(());
+        }
+    };
+
+    let result = match database::connect_to(&database_url).await {
+        Ok(connection) => {
+            interact_and_validate(sensitivity, data_dir.as_ref(), &temporary_dir, connection).await
+        }
+        Err(error) => Err(error.into()),
+    };
+
+    if let Err(error) = database::drop_temporary(&database_url).await {
+        warn!("Unable to drop the temporary database: {error}");
+    }
+    if let Err(error) = fs::remove_dir_all(&temporary_dir) {
+        warn!("Unable to remove the temporary data directory: {error}");
+    }
+
+    match result {
+        Ok(model) => {
+            println!(
+                "{} (took {:.1}s)",
+                "Selftest passed".green(),
+                started.elapsed().as_secs_f32()
+            );
+            if model == FAKE_MODEL {
+                println!(
+                    "{}: the response was transcribed by a fake recogniser",
+                    "Transcription skipped".yellow()
+                );
+            }
+
+            Ok(())
+        }
+        Err(error) => {
+            println!("{}: {error}", "Selftest failed".red());
+
+            Err(error)
+        }
+    }
+}
+
+/// Run the selftest interaction and check what was stored.
+///
+/// Returns the name of the model the response was transcribed with.
+///
+/// # Arguments
+///
+/// * `sensitivity`: The sensitivity of the listener.
+/// * `data_dir`: The data directory whose `models` directory holds the tiny whisper model.
+/// * `temporary_dir`: The temporary data directory the interaction is stored in.
+/// * `connection`: The connection to the temporary database.
+async fn interact_and_validate(
+    sensitivity: f32,
+    data_dir: &Path,
+    temporary_dir: &Path,
+    connection: DatabaseConnection,
+) -> Result<String, Error> {
+    let (transcriber, transcriber_handle, model) = transcriber(data_dir).await?;
+    let speaker = FakeSpeaker::new();
+    let mut interactor = Interactor::with_backends(
+        Backends {
+            listener: Box::new(FakeListener::with_tone(Duration::from_secs(2), 440.0)),
+            speaker: Box::new(speaker.clone()),
+            sniffer: Box::new(FakeSniffer::new()),
+        },
+        "selftest".to_string(),
+        vec!["Selftest".to_string()],
+        sensitivity,
+        model.clone(),
+        PathBuf::from(temporary_dir),
+        "00:00:00:00:00:00".to_string(),
+    );
+    let assistant = assistant::from("Siri");
+    let mut queries = vec![Query {
+        text: "What is the weather like today?".to_string(),
+        category: "selftest".to_string(),
+        recording_timeout: None,
+        follow_ups: Vec::new(),
+        locale: None,
+        exemplar: false,
+    }];
+
+    interactor.set_database(Some(connection.clone()));
+
+    let transcriber_thread = thread::spawn(move || transcriber.start());
+
+    interactor
+        .start(&mut queries, assistant.as_ref(), Some(transcriber_handle))
+        .await?;
+    transcriber_thread
+        .join()
+        .map_err(|_| failed("the transcriber crashed"))??;
+
+    // validate what was stored
+    let sessions = Session::get_all(&connection).await?;
+    let session = match sessions.as_slice() {
+        [session] if session.ended.is_some() => session,
+        _ => return Err(failed("expected exactly one completed session")),
+    };
+    let interactions = Interaction::get_by_session(&connection, session.id).await?;
+    let interaction = match interactions.as_slice() {
+        [interaction] if interaction.is_complete() => interaction,
+        _ => return Err(failed("expected exactly one completed interaction")),
+    };
+
+    if !speaker.spoken().contains(&interaction.spoken_query()) {
+        return Err(failed("the query was not spoken"));
+    }
+    if interaction.response.is_none() {
+        return Err(failed("the response was not transcribed"));
+    }
+    for file in [
+        &interaction.query_file,
+        &interaction.response_file,
+        &interaction.capture_file,
+    ] {
+        let path = file
+            .as_ref()
+            .map(|file| file::session_path(temporary_dir, session.id).join(file))
+            .ok_or_else(|| failed("a file of the interaction is missing"))?;
+
+        if !path.exists() {
+            return Err(failed(&format!("{} does not exist", path.display())));
+        }
+    }
+
+    info!(
+        "Selftest interaction transcribed as \"{}\"",
+        interaction.response.as_deref().unwrap_or_default()
+    );
+
+    Ok(model)
+}
+
+/// A transcriber for the interaction, with its handle and the name of the model it uses.
+type SelftestTranscriber = (
+    Transcriber<TranscribeInteraction>,
+    TranscriberHandle<TranscribeInteraction>,
+    String,
+);
+
+/// Create a transcriber using the tiny whisper model, downloading it first if necessary.
+///
+/// Falls back to a fake recogniser if the model cannot be downloaded, e.g. because this machine is offline.
+///
+/// # Arguments
+///
+/// * `data_dir`: The data directory whose `models` directory holds the tiny whisper model.
+#[cfg(feature = "transcription")]
+async fn transcriber(data_dir: &Path) -> Result<SelftestTranscriber, Error> {
+    let model = match tiny_model(data_dir).await {
+        Ok(model) => model,
+        Err(Error::ModelDownloadFailed(error)) => {
+            warn!("Unable to download the tiny whisper model, using a fake recogniser: {error}");
+
+            return Ok(fake_transcriber());
+        }
+        Err(error) => return Err(error),
+    };
+    let (transcriber, handle) = Transcriber::new(Recogniser::with_model_path(&model)?);
+
+    Ok((transcriber, handle, model))
+}
+
+/// Create a transcriber using a fake recogniser, since varys was built without transcription.
+///
+/// # Arguments
+///
+/// * `_data_dir`: The data directory, which is not needed without transcription.
+#[cfg(not(feature = "transcription"))]
+async fn transcriber(_data_dir: &Path) -> Result<SelftestTranscriber, Error> {
+    warn!("varys was built without transcription, using a fake recogniser");
+
+    Ok(fake_transcriber())
+}
+
+/// Create a transcriber using a fake recogniser that always transcribes [`FAKE_RESPONSE`].
+fn fake_transcriber() -> SelftestTranscriber {
+    let (transcriber, handle) = Transcriber::new(FakeRecogniser::new(FAKE_RESPONSE));
+
+    (transcriber, handle, FAKE_MODEL.to_string())
+}
+
+/// Get the path to the tiny whisper model, downloading it if it does not exist yet.
+///
+/// # Arguments
+///
+/// * `data_dir`: The data directory whose `models` directory holds the model.
+#[cfg(feature = "transcription")]
+async fn tiny_model(data_dir: &Path) -> Result<String, Error> {
+    let path = data_dir.join("models").join(TINY_MODEL);
+
+    if path.exists() {
+        debug!("Using tiny whisper model at {}", path.display());
+
+        return Ok(path.to_string_lossy().to_string());
+    }
+
+    info!("Downloading tiny whisper model from {TINY_MODEL_URL}...");
+
+    let model = reqwest::get(TINY_MODEL_URL)
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(Error::ModelDownloadFailed)?
+        .bytes()
+        .await
+        .map_err(Error::ModelDownloadFailed)?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    // write to a partial file first, so an interrupted download is not mistaken for the model
+    let partial = path.with_extension("part");
+    fs::write(&partial, model)?;
+    fs::rename(partial, &path)?;
+
+    Ok(path.to_string_lossy().to_string())
+}
+
+fn failed(reason: &str) -> Error {
+    Error::SelftestFailed(reason.to_string())
+}
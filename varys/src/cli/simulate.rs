@@ -0,0 +1,223 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use std::{env, fs, process, thread};
+
+use colored::Colorize;
+use log::warn;
+use varys_audio::listen::SilenceThreshold;
+use varys_audio::stt::transcriber::Transcriber;
+use varys_database::connection::DatabaseConnection;
+use varys_database::database;
+use varys_database::database::interaction::Interaction;
+use varys_database::database::session::Session;
+use varys_network::sniff::fake::FakeSniffer;
+
+use crate::assistant;
+use crate::assistant::conditions::Seeds;
+use crate::assistant::idle_gap::IdleGap;
+use crate::assistant::interactor::{Backends, Interactor};
+use crate::assistant::replay::{self, Replay};
+use crate::assistant::retry::RetryPolicy;
+use crate::assistant::simulation::Simulation;
+use crate::error::Error;
+
+/// Replay a collected session against simulated backends and compare the simulated interactions to the collected ones.
+///
+/// The queries of the session are asked again in the order they were asked in, with the seeds they were asked with,
+/// while the speaker, listener and recogniser replay how long the queries took and when and how the assistant
+/// responded, see [`Simulation`]. Since no hardware is involved, changes to the scheduling, timing and database
+/// logic can be regression-tested against the workload of a real session.
+///
+/// The simulated session is stored in a temporary database next to the one specified in `DATABASE_URL`, and its
+/// files are written to a temporary data directory. Both are removed again afterwards.
+///
+/// Returns an error if the status of any simulated interaction differs from the collected one.
+///
+/// # Arguments
+///
+/// * `session_id`: The id of the session to simulate.
+/// * `speed`: How many times faster than the collected session to run.
+/// * `idle_gap`: The idle time to wait before each query, which is drawn from the replayed seeds.
+/// * `sensitivity`: The sensitivity to use if the session did not store its silence threshold.
+pub async fn run(
+    session_id: i32,
+    speed: f32,
+    idle_gap: Option<IdleGap>,
+    sensitivity: f32,
+) -> Result<(), Error> {
+    let connection = database::connect().await?;
+    let session = Session::get(&connection, session_id)
+        .await?
+        .ok_or(Error::SessionNotFound(session_id))?;
+    let voice = session
+        .config(&connection)
+        .await?
+        .map(|config| config.voice)
+        .unwrap_or_default();
+    let mut interactions = session.interactions(&connection).await?;
+    interactions.sort_by_key(|interaction| interaction.started);
+    if interactions.is_empty() {
+        return Err(Error::NothingToSimulate(session_id));
+    }
+
+    let name = format!("varys_simulation_{}", process::id());
+    let data_dir = env::temp_dir().join(&name);
+    let database_url = database::create_temporary(&name).await?;
+
+    let result = match database::connect_to(&database_url).await {
+        Ok(simulation_connection) => {
+            simulate(
+                &interactions,
+                voice,
+                session.silence_threshold.unwrap_or(sensitivity),
+                speed,
+                idle_gap,
+                &data_dir,
+                simulation_connection,
+            )
+            .await
+        }
+        Err(error) => Err(error.into()),
+    };
+
+    if let Err(error) = database::drop_temporary(&database_url).await {
+        warn!("Unable to drop the temporary database: {error}");
+    }
+    if let Err(error) = fs::remove_dir_all(&data_dir) {
+        warn!("Unable to remove the temporary data directory: {error}");
+    }
+
+    let simulated = result?;
+    let mismatches = compare(&interactions, &simulated);
+    if mismatches > 0 {
+        return Err(Error::SimulationMismatch(mismatches));
+    }
+
+    println!(
+        "{}",
+        format!("The simulation of session {session_id} matches the collected session").green()
+    );
+
+    Ok(())
+}
+
+/// Run the simulated session and get its interactions, ordered by when they started.
+async fn simulate(
+    interactions: &[Interaction],
+    voice: String,
+    sensitivity: f32,
+    speed: f32,
+    idle_gap: Option<IdleGap>,
+    data_dir: &Path,
+    connection: DatabaseConnection,
+) -> Result<Vec<Interaction>, Error> {
+    let simulation = Simulation::new(interactions, speed);
+    let replay = Replay::new(interactions[0].session_id, interactions);
+    let mut interactor = Interactor::with_backends(
+        Backends {
+            listener: Box::new(simulation.listener()),
+            speaker: Box::new(simulation.speaker()),
+            sniffer: Box::new(FakeSniffer::new()),
+        },
+        "simulation".to_string(),
+        vec![voice],
+        sensitivity,
+        String::new(),
+        PathBuf::from(data_dir),
+        interactions[0].assistant_mac.clone(),
+    );
+    interactor.set_silence_threshold(SilenceThreshold::Static(sensitivity));
+    interactor.set_shuffle_queries(false);
+    interactor.set_seeds(Seeds::Replayed(replay::attempt_seeds(interactions)));
+    interactor.set_idle_gap(idle_gap);
+    interactor.set_database(Some(connection.clone()));
+    let max_attempts = interactions
+        .iter()
+        .map(|interaction| interaction.attempt)
+        .max()
+        .unwrap_or(1) as u32;
+    interactor.set_retry_policy((max_attempts > 1).then(|| RetryPolicy {
+        max_attempts,
+        initial_delay: Duration::ZERO,
+        max_delay: Duration::ZERO,
+        requeue: false,
+    }));
+
+    let assistant = assistant::from(
+        interactions
+            .iter()
+            .find_map(|interaction| interaction.wake_word.as_deref())
+            .unwrap_or_default(),
+    );
+    let mut queries = replay.queries;
+
+    let (transcriber, transcriber_handle) = Transcriber::new(simulation.recogniser());
+    let transcriber_thread = thread::spawn(move || transcriber.start());
+
+    interactor
+        .start(&mut queries, assistant.as_ref(), Some(transcriber_handle))
+        .await?;
+    transcriber_thread
+        .join()
+        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))?;
+
+    let mut simulated = Interaction::get_all(&connection).await?;
+    simulated.sort_by_key(|interaction| interaction.started);
+
+    Ok(simulated)
+}
+
+/// Print the collected and simulated interactions next to each other.
+///
+/// Returns how many simulated interactions have a different status than the collected ones.
+fn compare(collected: &[Interaction], simulated: &[Interaction]) -> usize {
+    let millis = |value: Option<i32>| {
+        value
+            .map(|value| format!("{value}ms"))
+            .unwrap_or_else(|| "-".to_string())
+    };
+    let mut mismatches = collected.len().abs_diff(simulated.len());
+
+    println!(
+        "{:<40} {:>22} {:>18} {:>18}",
+        "Query", "Status", "Latency", "Duration"
+    );
+    for (collected, simulated) in collected.iter().zip(simulated) {
+        let line = format!(
+            "{:<40} {:>22} {:>18} {:>18}",
+            collected.query.chars().take(40).collect::<String>(),
+            format!("{} / {}", collected.status, simulated.status),
+            format!(
+                "{} / {}",
+                millis(collected.response_latency_ms),
+                millis(simulated.response_latency_ms)
+            ),
+            format!(
+                "{} / {}",
+                millis(collected.response_duration),
+                millis(simulated.response_duration)
+            ),
+        );
+
+        if collected.query != simulated.query || collected.status != simulated.status {
+            mismatches += 1;
+            println!("{}", line.red());
+        } else {
+            println!("{line}");
+        }
+    }
+
+    if collected.len() != simulated.len() {
+        println!(
+            "{}",
+            format!(
+                "The collected session has {} interactions, the simulated one {}",
+                collected.len(),
+                simulated.len()
+            )
+            .red()
+        );
+    }
+
+    mismatches
+}
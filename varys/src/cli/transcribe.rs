@@ -0,0 +1,106 @@
+use std::path::Path;
+
+use log::{info, warn};
+use varys_audio::stt::{DecodingParams, Recogniser};
+use varys_database::database;
+use varys_database::database::interaction::Interaction;
+use varys_database::database::transcription_job::TranscriptionJob;
+use varys_database::file;
+
+use crate::error::Error;
+use crate::query;
+use crate::redact::Redactor;
+use crate::response::{InteractionStatus, ResponseType};
+
+/// Transcribe the recorded responses of interactions and complete them.
+///
+/// Responses that cannot be recognised are completed without a transcript, like in the background transcriber.
+/// Responses whose recording cannot be read are skipped, so they can be retried.
+///
+/// # Arguments
+///
+/// * `data_dir`: The directory in which data files are stored.
+/// * `model`: The path to the speech recognition model.
+/// * `pending`: Whether to transcribe all responses whose transcription is still pending.
+/// * `ids`: The ids of the interactions to transcribe, if not transcribing pending responses.
+/// * `bias_vocabulary`: Whether to condition the transcription on the names in the queries of the interactions.
+/// * `redactor`: What to remove from the transcripts before they are stored.
+pub async fn run(
+    data_dir: &Path,
+    model: &Path,
+    pending: bool,
+    ids: &[i32],
+    bias_vocabulary: bool,
+    redactor: Option<&Redactor>,
+) -> Result<(), Error> {
+    let connection = database::connect().await?;
+    let ids: Vec<i32> = if pending {
+        TranscriptionJob::get_all(&connection)
+            .await?
+            .iter()
+            .map(|job| job.interaction_id)
+            .collect()
+    } else {
+        ids.to_vec()
+    };
+    let mut interactions = Vec::new();
+    for id in &ids {
+        match Interaction::get(&connection, *id).await? {
+            Some(interaction) => interactions.push(interaction),
+            None => warn!("Interaction {id} does not exist"),
+        }
+    }
+    let mut recogniser = Recogniser::with_model_path(&model.to_string_lossy())?;
+    if bias_vocabulary {
+        recogniser.set_decoding_params(DecodingParams {
+            initial_prompt: query::vocabulary_prompt(
+                interactions
+                    .iter()
+                    .map(|interaction| interaction.query.as_str()),
+            ),
+            ..DecodingParams::default()
+        });
+    }
+    let mut transcribed = 0;
+
+    info!("Transcribing {} responses...", interactions.len());
+
+    for mut interaction in interactions {
+        let Some(response_file) = interaction.response_file.clone() else {
+            warn!("{interaction} has no recorded response");
+            continue;
+        };
+        let response_path =
+            file::session_path(data_dir, interaction.session_id).join(response_file);
+        let mut audio = match varys_audio::file::read_opus(&response_path) {
+            Ok(audio) => audio,
+            Err(error) => {
+                warn!("Could not read the response of {interaction}: {error}");
+                continue;
+            }
+        };
+
+        match recogniser.recognise(&mut audio) {
+            Ok(text) => {
+                interaction.response_type = Some(ResponseType::classify(&text).to_string());
+                InteractionStatus::validate(&mut interaction, &text);
+                interaction.response = Some(match redactor {
+                    Some(redactor) => redactor.redact(&text),
+                    None => text,
+                });
+            }
+            Err(error) => warn!("Could not recognise the response of {interaction}: {error}"),
+        }
+        if interaction.is_complete() {
+            interaction.update(&connection).await?;
+        } else {
+            interaction.complete(&connection).await?;
+        }
+        TranscriptionJob::delete_by_interaction(&connection, interaction.id).await?;
+        transcribed += 1;
+    }
+
+    info!("Transcribed {transcribed} of {} responses", ids.len());
+
+    Ok(())
+}
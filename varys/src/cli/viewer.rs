@@ -0,0 +1,340 @@
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use tiny_http::{Header, Method, Request, Response, Server};
+use tokio::sync::mpsc;
+use tokio::task;
+use varys_analysis::trace::TrafficTrace;
+use varys_audio::file::read_audio;
+use varys_database::connection::DatabaseConnection;
+use varys_database::database;
+use varys_database::database::interaction::Interaction;
+use varys_database::database::review::Review;
+use varys_database::file;
+use varys_network::address::MacAddress;
+
+use crate::error::Error;
+
+/// The page of the viewer, which loads everything else from the endpoints of [`Viewer`].
+const INDEX: &str = include_str!("viewer/index.html");
+/// How many bars the waveform of a response is drawn with.
+const WAVEFORM_BARS: usize = 600;
+
+/// An interaction in the list of the viewer.
+#[derive(Serialize, Debug)]
+struct Summary {
+    id: i32,
+    session_id: i32,
+    query: String,
+    status: String,
+    accepted: Option<bool>,
+}
+
+/// Everything the viewer shows about an interaction.
+#[derive(Serialize, Debug)]
+struct Details {
+    id: i32,
+    session_id: i32,
+    query: String,
+    query_category: String,
+    wake_word: Option<String>,
+    channel: String,
+    status: String,
+    attempt: i32,
+    started: String,
+    ended: Option<String>,
+    response: Option<String>,
+    response_type: Option<String>,
+    response_duration: Option<i32>,
+    response_latency_ms: Option<i32>,
+    accepted: Option<bool>,
+    /// Whether the response audio can be played from `/api/interactions/<id>/audio`.
+    audio: bool,
+    /// The peak level of every bar of the response waveform.
+    waveform: Vec<f32>,
+    /// The time in seconds and the signed size of every packet, negative for packets sent by the assistant.
+    trace: Vec<(f32, f32)>,
+}
+
+/// The request body of `POST /api/interactions/<id>/review`.
+#[derive(Deserialize, Debug)]
+struct ReviewRequest {
+    /// Whether the interaction is accepted, or `None` to remove its review.
+    accepted: Option<bool>,
+}
+
+/// A local web viewer for manual quality control of interactions.
+///
+/// It shows the waveform, transcript, traffic trace and metadata of every interaction and writes the accept or reject
+/// decisions back to the database as [`Review`]s. The viewer answers to these endpoints:
+///
+/// * `GET /`: The page of the viewer.
+/// * `GET /api/interactions`: All interactions with their review.
+/// * `GET /api/interactions/<id>`: The details of an interaction.
+/// * `GET /api/interactions/<id>/audio`: The recorded response of an interaction.
+/// * `POST /api/interactions/<id>/review`: Review an interaction with `{"accepted": true}`, `{"accepted": false}` or
+///   remove its review with `{"accepted": null}`.
+struct Viewer {
+    connection: DatabaseConnection,
+    data_dir: PathBuf,
+    session_id: Option<i32>,
+}
+
+/// Serve the viewer until the process is stopped.
+///
+/// # Arguments
+///
+/// * `data_dir`: The directory in which data files are stored.
+/// * `address`: The address to listen on, e.g. `127.0.0.1:8080`.
+/// * `session_id`: Only show the interactions of this session.
+pub async fn run(data_dir: &Path, address: &str, session_id: Option<i32>) -> Result<(), Error> {
+    let viewer = Viewer {
+        connection: database::connect().await?,
+        data_dir: data_dir.to_path_buf(),
+        session_id,
+    };
+    let server = Server::http(address).map_err(|error| Error::ViewerFailed(error.to_string()))?;
+    let (request_sender, mut requests) = mpsc::unbounded_channel();
+
+    // waiting for requests blocks, so they are forwarded from a blocking task
+    task::spawn_blocking(move || {
+        for request in server.incoming_requests() {
+            if request_sender.send(request).is_err() {
+                break;
+            }
+        }
+    });
+
+    info!("Serving the viewer on http://{address}");
+    println!("Open http://{address} to review interactions");
+
+    while let Some(mut request) = requests.recv().await {
+        debug!("{} {}", request.method(), request.url());
+
+        let response = match viewer.handle(&mut request).await {
+            Ok(response) => response,
+            Err((status, message)) => {
+                warn!(
+                    "Could not handle {} {}: {message}",
+                    request.method(),
+                    request.url()
+                );
+                json_response(serde_json::json!({ "error": message }).to_string())
+                    .with_status_code(status)
+            }
+        };
+
+        if let Err(error) = request.respond(response) {
+            warn!("Could not send response: {error}");
+        }
+    }
+
+    Ok(())
+}
+
+impl Viewer {
+    /// Handle a request, returning the response or its status code with an error message.
+    async fn handle(
+        &self,
+        request: &mut Request,
+    ) -> Result<Response<Cursor<Vec<u8>>>, (u16, String)> {
+        let method = request.method().clone();
+        let url = request.url().to_string();
+        let path = url.split('?').next().unwrap_or_default();
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+        match (method, segments.as_slice()) {
+            (Method::Get, [""]) => {
+                Ok(Response::from_string(INDEX)
+                    .with_header(content_type("text/html; charset=utf-8")))
+            }
+            (Method::Get, ["api", "interactions"]) => {
+                to_json(&self.summaries().await.map_err(internal)?)
+            }
+            (Method::Get, ["api", "interactions", id]) => {
+                let interaction = self.interaction(id).await?;
+                to_json(&self.details(interaction).await.map_err(internal)?)
+            }
+            (Method::Get, ["api", "interactions", id, "audio"]) => {
+                let interaction = self.interaction(id).await?;
+                let audio_path = self
+                    .response_path(&interaction)
+                    .ok_or((404, format!("Interaction {id} has no response audio")))?;
+                let audio = std::fs::read(&audio_path).map_err(|error| (404, error.to_string()))?;
+                let audio_type = match audio_path
+                    .extension()
+                    .and_then(|extension| extension.to_str())
+                {
+                    Some("wav") => "audio/wav",
+                    _ => "audio/ogg",
+                };
+
+                Ok(Response::from_data(audio).with_header(content_type(audio_type)))
+            }
+            (Method::Post, ["api", "interactions", id, "review"]) => {
+                let interaction = self.interaction(id).await?;
+                let mut body = String::new();
+                request
+                    .as_reader()
+                    .read_to_string(&mut body)
+                    .map_err(|error| (400, error.to_string()))?;
+                let review: ReviewRequest =
+                    serde_json::from_str(&body).map_err(|error| (400, error.to_string()))?;
+
+                match review.accepted {
+                    Some(accepted) => {
+                        Review::set(&self.connection, interaction.id, accepted)
+                            .await
+                            .map_err(|error| internal(error.into()))?;
+                        info!(
+                            "Interaction {} was {}",
+                            interaction.id,
+                            if accepted { "accepted" } else { "rejected" }
+                        );
+                    }
+                    None => {
+                        Review::delete(&self.connection, interaction.id)
+                            .await
+                            .map_err(|error| internal(error.into()))?;
+                        info!("The review of interaction {} was removed", interaction.id);
+                    }
+                }
+
+                to_json(&serde_json::json!({ "accepted": review.accepted }))
+            }
+            _ => Err((404, format!("Unknown endpoint {path}"))),
+        }
+    }
+
+    /// Get all interactions shown by the viewer with their review, ordered by id.
+    async fn summaries(&self) -> Result<Vec<Summary>, Error> {
+        let mut interactions = match self.session_id {
+            Some(session_id) => Interaction::get_by_session(&self.connection, session_id).await?,
+            None => Interaction::get_all(&self.connection).await?,
+        };
+        interactions.sort_by_key(|interaction| interaction.id);
+        let reviews = self.reviews().await?;
+
+        Ok(interactions
+            .into_iter()
+            .map(|interaction| Summary {
+                accepted: reviews.get(&interaction.id).copied(),
+                id: interaction.id,
+                session_id: interaction.session_id,
+                query: interaction.query,
+                status: interaction.status,
+            })
+            .collect())
+    }
+
+    /// Collect the details of an interaction, leaving out the waveform or trace if their files cannot be read.
+    async fn details(&self, interaction: Interaction) -> Result<Details, Error> {
+        let accepted = self.reviews().await?.get(&interaction.id).copied();
+        let waveform = self
+            .response_path(&interaction)
+            .map(|path| read_audio(&path))
+            .transpose()
+            .unwrap_or_else(|error| {
+                debug!(
+                    "Could not read the response of interaction {}: {error}",
+                    interaction.id
+                );
+                None
+            })
+            .map(|audio| {
+                let bar_size = audio.data.len().div_ceil(WAVEFORM_BARS).max(1);
+                audio
+                    .data
+                    .chunks(bar_size)
+                    .map(|bar| {
+                        bar.iter()
+                            .fold(0., |peak: f32, sample| sample.abs().max(peak))
+                    })
+                    .collect()
+            });
+        let trace = match (
+            TrafficTrace::load_interaction(&self.data_dir, &interaction),
+            MacAddress::from_str(&interaction.assistant_mac),
+        ) {
+            (Ok(trace), Ok(assistant_mac)) => trace
+                .as_packet_times(&assistant_mac)
+                .into_iter()
+                .zip(trace.as_numeric_trace(&assistant_mac).0)
+                .collect(),
+            _ => {
+                debug!("Could not load the trace of interaction {}", interaction.id);
+                Vec::new()
+            }
+        };
+
+        Ok(Details {
+            id: interaction.id,
+            session_id: interaction.session_id,
+            query: interaction.query,
+            query_category: interaction.query_category,
+            wake_word: interaction.wake_word,
+            channel: interaction.channel,
+            status: interaction.status,
+            attempt: interaction.attempt,
+            started: interaction.started.to_rfc3339(),
+            ended: interaction.ended.map(|ended| ended.to_rfc3339()),
+            response: interaction.response,
+            response_type: interaction.response_type,
+            response_duration: interaction.response_duration,
+            response_latency_ms: interaction.response_latency_ms,
+            accepted,
+            audio: waveform.is_some(),
+            waveform: waveform.unwrap_or_default(),
+            trace,
+        })
+    }
+
+    /// Get the interaction with an id from a path segment.
+    async fn interaction(&self, id: &str) -> Result<Interaction, (u16, String)> {
+        let id: i32 = id
+            .parse()
+            .map_err(|_| (400, format!("{id} is not an interaction id")))?;
+
+        Interaction::get(&self.connection, id)
+            .await
+            .map_err(|error| internal(error.into()))?
+            .ok_or((404, Error::InteractionNotFound(id).to_string()))
+    }
+
+    /// Get whether every reviewed interaction was accepted.
+    async fn reviews(&self) -> Result<HashMap<i32, bool>, Error> {
+        Ok(Review::get_all(&self.connection)
+            .await?
+            .into_iter()
+            .map(|review| (review.interaction_id, review.accepted))
+            .collect())
+    }
+
+    fn response_path(&self, interaction: &Interaction) -> Option<PathBuf> {
+        interaction.response_file.as_ref().map(|response_file| {
+            file::session_path(&self.data_dir, interaction.session_id).join(response_file)
+        })
+    }
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<Response<Cursor<Vec<u8>>>, (u16, String)> {
+    serde_json::to_string(value)
+        .map(json_response)
+        .map_err(|error| (500, error.to_string()))
+}
+
+fn json_response(body: String) -> Response<Cursor<Vec<u8>>> {
+    Response::from_string(body).with_header(content_type("application/json"))
+}
+
+fn content_type(value: &str) -> Header {
+    Header::from_bytes("Content-Type", value).expect("The content type header is valid")
+}
+
+fn internal(error: Error) -> (u16, String) {
+    (500, error.to_string())
+}
@@ -0,0 +1,161 @@
+use std::sync::{Mutex, PoisonError};
+use std::thread::ThreadId;
+use std::time::Duration;
+use std::{panic, process, thread};
+
+use log::{error, info, warn};
+
+use varys_database::database;
+use varys_database::database::crash::Crash;
+
+use crate::monitoring;
+
+/// How long reporting a crash may take before varys aborts anyway.
+const REPORT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The session and interaction that are currently running on each thread, included in crash reports.
+///
+/// Sessions are tracked per thread, since a fleet of interactors runs several at once.
+static IN_FLIGHT: Mutex<Vec<(ThreadId, InFlight)>> = Mutex::new(Vec::new());
+
+#[derive(Copy, Clone, Debug, Default)]
+struct InFlight {
+    session_id: Option<i32>,
+    interaction_id: Option<i32>,
+}
+
+/// Install a panic hook that reports crashes before aborting.
+///
+/// When varys panics, the panic message is logged as usual and then stored in the database
+/// together with the in-flight session and interaction of the panicking thread (see [`set_session`]
+/// and [`set_interaction`]), or the only in-flight session if the panic happened on another thread.
+/// The monitoring service is notified as well. Afterwards, the process is
+/// aborted, so a crash in any thread stops varys instead of leaving it running in a broken state.
+pub fn install_hook() {
+    let default_hook = panic::take_hook();
+
+    panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+
+        let message = info.to_string();
+        let in_flight = {
+            let in_flight = IN_FLIGHT.lock().unwrap_or_else(PoisonError::into_inner);
+            let current = thread::current().id();
+
+            match in_flight.iter().find(|(thread, _)| *thread == current) {
+                Some((_, in_flight)) => *in_flight,
+                None if in_flight.len() == 1 => in_flight[0].1,
+                None => InFlight::default(),
+            }
+        };
+
+        error!("varys crashed during {in_flight:?}, reporting the crash before aborting...");
+
+        // reporting is done on a separate thread since the panicking thread might be inside a
+        // runtime already
+        let _ = thread::spawn(move || report(message, in_flight)).join();
+
+        process::abort();
+    }));
+}
+
+/// Set the session that is currently running on this thread.
+///
+/// This also resets the current interaction.
+///
+/// # Arguments
+///
+/// * `session_id`: The id of the running session or `None` if no session is running.
+pub fn set_session(session_id: Option<i32>) {
+    update_in_flight(thread::current().id(), |in_flight| {
+        in_flight.session_id = session_id;
+        in_flight.interaction_id = None;
+    });
+}
+
+/// Set the interaction that is currently running on this thread, until the returned guard is dropped.
+///
+/// # Arguments
+///
+/// * `interaction_id`: The id of the running interaction.
+pub fn set_interaction(interaction_id: i32) -> InteractionGuard {
+    let thread = thread::current().id();
+    update_in_flight(thread, |in_flight| {
+        in_flight.interaction_id = Some(interaction_id)
+    });
+
+    InteractionGuard { thread }
+}
+
+/// Resets the interaction set with [`set_interaction`] when it is dropped, so crashes after the interaction are not
+/// attributed to it.
+#[must_use = "the interaction is reset right away if the guard is not kept"]
+pub struct InteractionGuard {
+    /// The thread the interaction was set on, which might differ from the one the guard is dropped on.
+    thread: ThreadId,
+}
+
+impl Drop for InteractionGuard {
+    fn drop(&mut self) {
+        update_in_flight(self.thread, |in_flight| in_flight.interaction_id = None);
+    }
+}
+
+fn update_in_flight<F: FnOnce(&mut InFlight)>(thread: ThreadId, update: F) {
+    let mut in_flight = IN_FLIGHT.lock().unwrap_or_else(PoisonError::into_inner);
+
+    let index = match in_flight.iter().position(|(other, _)| *other == thread) {
+        Some(index) => index,
+        None => {
+            in_flight.push((thread, InFlight::default()));
+            in_flight.len() - 1
+        }
+    };
+    update(&mut in_flight[index].1);
+
+    // forget threads without a running session
+    if in_flight[index].1.session_id.is_none() && in_flight[index].1.interaction_id.is_none() {
+        in_flight.remove(index);
+    }
+}
+
+fn report(message: String, in_flight: InFlight) {
+    let runtime = match tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(runtime) => runtime,
+        Err(error) => {
+            error!("Unable to report crash: {error}");
+            return;
+        }
+    };
+
+    runtime.block_on(async {
+        let stored = tokio::time::timeout(REPORT_TIMEOUT, async {
+            let connection = database::connect().await?;
+            Crash::create(
+                &connection,
+                in_flight.session_id,
+                in_flight.interaction_id,
+                &message,
+                crate::version(),
+            )
+            .await
+        })
+        .await;
+
+        match stored {
+            Ok(Ok(crash)) => info!("Stored {crash}"),
+            Ok(Err(error)) => error!("Unable to store crash in the database: {error}"),
+            Err(_) => error!("Timed out storing crash in the database"),
+        }
+
+        let ping_message = format!("varys crashed: {message}");
+        match tokio::time::timeout(REPORT_TIMEOUT, monitoring::ping(&ping_message)).await {
+            Ok(Err(error)) => warn!("Failed to notify monitoring about crash: {error}"),
+            Err(_) => warn!("Timed out notifying monitoring about crash"),
+            _ => {}
+        }
+    });
+}
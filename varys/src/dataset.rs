@@ -1,9 +1,19 @@
 use clap::ValueEnum;
+use log::{info, warn};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::fmt::{Display, Formatter};
-use log::info;
+use std::fs;
+use std::path::Path;
 
 use varys_database::database::interaction::Interaction;
 
+use crate::error::Error;
+use crate::query::validation;
+
 #[derive(ValueEnum, Default, Clone, Debug)]
 pub enum DatasetSize {
     /// The full, unchanged dataset.
@@ -22,37 +32,24 @@ impl DatasetSize {
     ///
     /// * `interactions`: The interactions to filter.
     pub fn filter(&self, interactions: Vec<Interaction>) -> Vec<Interaction> {
-        // Log the initial number of interactions
-        info!("Starting filter process. Total interactions: {}", interactions.len());
-    
-        // Define valid greetings
-        let valid_greetings = vec!["Hey Siri. ", "Alexa. "];
-    
-        // Merge each query with each greeting
-        let valid_queries: Vec<String> = self.queries().iter()
-            .flat_map(|query| {
-                valid_greetings.iter().map(move |greeting| format!("{}{}", greeting, query))
-            })
-            .collect();
-    
-        // Filter the interactions and log the ones that are kept
-        let filtered_interactions: Vec<Interaction> = interactions
-            .into_iter()
-            .filter(|interaction| {
-                valid_queries.iter().any(|valid_query| interaction.query == *valid_query)
-            })
-            .collect();
-    
-        // Log the number of filtered interactions
-        info!("Filtering complete. Number of interactions kept: {}", filtered_interactions.len());
-    
-        filtered_interactions
+        filter_by_queries(&self.queries(), interactions)
     }
 
     /// All queries that are used for this dataset size.
-    ///
-    /// This returns an empty vector if the dataset size is `DatasetSize::Full`.
     pub fn queries(&self) -> Vec<&str> {
+        self.categorised_queries()
+            .into_iter()
+            .map(|(query, _)| query)
+            .collect()
+    }
+
+    /// All query categories of this dataset size, sorted alphabetically.
+    pub fn categories(&self) -> Vec<&str> {
+        categories(&self.categorised_queries())
+    }
+
+    /// All queries that are used for this dataset size with their category.
+    pub fn categorised_queries(&self) -> Vec<(&str, &str)> {
         match self {
             DatasetSize::Full => vec![
                 // the 13 queries from the small dataset are excluded since their higher number of
@@ -70,250 +67,310 @@ impl DatasetSize {
                 // "Translate car from English to Spanish",
                 // "Roll a die",
                 // "Is there a God?",
-                "What are 130 miles in yards?",
-                "What's 200 pounds in kilograms?",
-                "What's 45 miles per hour in meters per second?",
-                "What are 3 gigabytes in megabytes?",
-                "Convert 4.2 acres to square meters.",
-                "Convert 250 milliliters to cups.",
-                "Convert 180 degrees Celsius to Fahrenheit.",
-                "Convert 3000 calories to kilojoules.",
-                "Convert 75 miles per gallon to kilometers per liter.",
-                "What’s 9 plus 53?",
-                "What is 2 to the power of 17?",
-                "What is the result of 25 to the power of 4?",
-                "What is 244 plus 5%?",
-                "What is $200 minus 21%?",
-                "What is 9 percent of 63?",
-                "What is the area of a circle with a radius of 2 meters?",
-                "What is the remainder when 27 is divided by 5?",
-                "Calculate the hypotenuse of a right triangle with legs 3 and 4.",
-                "Find the greatest common divisor of 48 and 36.",
-                "What date is 90 days before December 17?",
-                "What year is 39 years after 1994?",
-                "How many years until 2049?",
-                "How many days until Easter?",
-                "How many days until Christmas?",
-                "What are two hours five minutes and 39 seconds in seconds?",
-                "What is the time zone in London?",
-                "What time is it in London?",
-                "Current time?",
-                "Turn the lights blue",
-                "Turn off the radio",
-                "I’m home",
-                "Set the brightness of the downstairs lights to 50%",
-                "Lock the front door",
-                "Open the garage",
-                "John is my brother",
-                "That’s not how you say John Doe",
-                "Show John Doe",
-                "When is John’s birthday?",
-                "How old is my brother?",
-                "Whose phone is this?",
-                "Learn to pronounce my name",
-                "Call John",
-                "Call 408 555 1212",
-                "Call my brother on speakerphone",
-                "Call the nearest restaurant",
-                "When did my brother call me?",
-                "Play voicemail from John",
-                "Get my call history",
-                "Redial my last number",
-                "Call back my last missed call",
-                "Any new voicemail?",
-                "Play me my latest voicemail",
-                "Show me new messages from John Doe",
-                "Show me my messages",
-                "Read my messages",
-                "Text John Doe I’m in a meeting",
-                "Message my brother I’ll be late",
-                "Send John see you later",
-                "Tell John I’m on the way",
-                "Ask my brother Where are you?",
-                "Any new email from John Doe?",
-                "Show me the email from John Doe yesterday",
-                "Send an email to John Doe Protocol",
-                "Check email",
-                "Read my last email",
-                "Post to Facebook I’m eating a sandwich",
-                "Post to Twitter Happy New Year!",
-                "Tweet with my location very hot here",
-                "Show me tweets from Twitter",
-                "Show me the latest tweets",
-                "Schedule an event Party in New York Wednesday at 10 PM",
-                "Schedule a meeting at 1 PM tomorrow for 2 hours",
-                "Create a recurring event every Saturday at 2:30 PM called Party",
-                "Set up a meeting with John for today at 3 PM",
-                "Show me my next appointment",
-                "Where is my next meeting?",
-                "Show me the appointments for this afternoon",
-                "What does my calendar look like on Monday?",
-                "When am I meeting with John Doe?",
-                "Cancel my Party in New York event from tomorrow",
-                "Cancel my event with John Doe",
-                "Move my Monday meeting with John to 3 o’clock",
-                "Remind me on Friday at 10 PM to wash the car",
-                "Add Milk to the Grocery list",
-                "Remind me to wash the car when I leave home today",
-                "Remind me to buy milk next time I’m here",
-                "Remind me to wash the car every second week",
-                "Delete the reminder wash the car",
-                "Show me my Grocery list",
-                "Note 12 Dollars for pizza",
-                "Note Interesting Movies",
-                "Add 10 Dollars for food to Outcomes note",
-                "Add Star Wars to Interesting Movies note",
-                "Show me my notes",
-                "Show me my note Interesting Movies",
-                "Show me my notes from last week",
-                "Tell me about the traffic in New York",
-                "What are some attractions around here?",
-                "Where is Big Ben?",
-                "Is the Central Park open now?",
-                "Distance between here and New York?",
-                "How far away is Boston?",
-                "What is the nearest restaurant?",
-                "Find a Starbucks",
-                "Good Mexican restaurants around here",
-                "Table for two in Palo Alto tonight",
-                "Make a reservation at a romantic Italian restaurant tonight at 7 PM",
-                "Show me the reviews for Alexander’s Steakhouse in Cupertino",
-                "Turn off my alarm",
-                "Delete all alarms",
-                "Turn off my Good Morning alarm",
-                "Show me my alarms",
-                "Is Ian McKellen still alive?",
-                "How tall is Ian McKellen?",
-                "Where was Ian McKellen born?",
-                "Who is Ian McKellen married to?",
-                "Who wrote Harry Potter?",
-                "Who invented the iPhone?",
-                "How far away is the moon?",
-                "How high is Mount Everest?",
-                "What is the population of Switzerland?",
-                "How many calories in a bagel?",
-                "How long do dogs live?",
-                "How many teeth does a dog have?",
-                "What type of Pokémon is Pikachu?",
-                "Spell necessary",
-                "What’s the weather like?",
-                "Do I need an umbrella for tomorrow?",
-                "What’s the weather going to be like in Madrid tomorrow?",
-                "Is there is a chance of rain tomorrow?",
-                "What’s the perceived temperature outside?",
-                "What’s the dew point outside?",
-                "Is it windy outside?",
-                "What’s the pressure outside?",
-                "What’s the visibility outside?",
-                "What is the KP Index?",
-                "How humid is it outside?",
-                "When is the sunrise?",
-                "When is the sunset tomorrow?",
-                "When is the sunrise on Friday?",
-                "When is the sunset in New York?",
-                "What’s the Apple stock price?",
-                "Compare Apple with Alphabet",
-                "Define airplane",
-                "What is the definition of airplane?",
-                "What does the French word maison mean in English?",
-                "Find books by Charles Dickens",
-                "Find movies by Christopher Nolan",
-                "What is the movie Indiana Jones about?",
-                "When was Indiana Jones released?",
-                "Runtime of Indiana Jones?",
-                "Who acted in Indiana Jones?",
-                "Movies with Scarlett Johansson",
-                "Best thriller movies?",
-                "Which movie won Best Picture in 1966?",
-                "What movies are playing this evening?",
-                "Buy three tickets to see The Lego Movie tonight in Sacramento",
-                "Find some movie theaters near my home",
-                "Shuffle my gym playlist",
-                "What’s this song?",
-                "Who sings this?",
-                "I like this song",
-                "What is the point spread in the NFL game?",
-                "How is Chelsea doing?",
-                "Results from Liverpool last game?",
-                "Who’s going to win the Vikings game?",
-                "When is the next Liverpool game?",
-                "What Channel is the Royals game on?",
-                "When is the Super Bowl?",
-                "Flip a coin",
-                "Pick a card",
-                "Roll a twenty-sided die",
-                "Random number between 30 and 60",
-                "See you on the seventh",
-                "What is 1 million divided by 0?",
-                "What is 0 divided by 0?",
-                "What is infinity times infinity?",
-                "Rock paper scissors",
-                "Sudo make me a sandwich",
-                "Tell me a joke",
-                "Tell haiku",
-                "Tell me a tongue twister",
-                "Tell me a story",
-                "Tell me a poem",
-                "Tell me a secret",
-                "Tell me a bedtime story",
-                "Sing me a lullaby",
-                "Beam me up",
-                "Guess what",
-                "Who’s on first?",
-                "Open the pod bay doors",
-                "Sing me a song now",
-                "When is your birthday?",
-                "What’s your sign?",
-                "What’s your favourite animal?",
-                "What color is your hair?",
-                "How much do you weigh?",
-                "Are you smart?",
-                "Are you perfect?",
-                "Do you think I look fat in this?",
-                "Will you marry me?",
-                "May the force be with you",
-                "Can I call you Jarvis?",
-                "When do you sleep?",
-                "How is it to be you?",
-                "Have you seen Star Wars?",
-                "What is your favourite colour?",
-                "What are you going to be for Halloween?",
-                "Do you know pick up lines?",
-                "Mirror mirror on the wall, who’s the fairest of them all?",
-                "What does the fox say?",
-                "Who let the dogs out?",
-                "How much wood could a woodchuck chuck if a woodchuck could chuck wood?",
-                "What is the airspeed velocity of an unladen swallow?",
-                "Why are fire trucks red?",
-                "Why did the chicken cross the road?",
-                "What is the meaning of life?",
-                "When is the end of the world?",
-                "What’s the best phone?",
-                "Can I borrow some money?",
-                "supercalifragilisticexpialidocious",
-                "Rap Beatbox",
-                "Can I call you Cortana?",
-                "You’re the best",
-                "Meow",
-                "I’m sleepy",
-                "How many languages do you speak?",
+                ("What are 130 miles in yards?", "conversion"),
+                ("What's 200 pounds in kilograms?", "conversion"),
+                (
+                    "What's 45 miles per hour in meters per second?",
+                    "conversion",
+                ),
+                ("What are 3 gigabytes in megabytes?", "conversion"),
+                ("Convert 4.2 acres to square meters.", "conversion"),
+                ("Convert 250 milliliters to cups.", "conversion"),
+                ("Convert 180 degrees Celsius to Fahrenheit.", "conversion"),
+                ("Convert 3000 calories to kilojoules.", "conversion"),
+                (
+                    "Convert 75 miles per gallon to kilometers per liter.",
+                    "conversion",
+                ),
+                ("What’s 9 plus 53?", "mathematics"),
+                ("What is 2 to the power of 17?", "mathematics"),
+                ("What is the result of 25 to the power of 4?", "mathematics"),
+                ("What is 244 plus 5%?", "mathematics"),
+                ("What is $200 minus 21%?", "mathematics"),
+                ("What is 9 percent of 63?", "mathematics"),
+                (
+                    "What is the area of a circle with a radius of 2 meters?",
+                    "mathematics",
+                ),
+                (
+                    "What is the remainder when 27 is divided by 5?",
+                    "mathematics",
+                ),
+                (
+                    "Calculate the hypotenuse of a right triangle with legs 3 and 4.",
+                    "mathematics",
+                ),
+                (
+                    "Find the greatest common divisor of 48 and 36.",
+                    "mathematics",
+                ),
+                ("What date is 90 days before December 17?", "time"),
+                ("What year is 39 years after 1994?", "time"),
+                ("How many years until 2049?", "time"),
+                ("How many days until Easter?", "time"),
+                ("How many days until Christmas?", "time"),
+                (
+                    "What are two hours five minutes and 39 seconds in seconds?",
+                    "time",
+                ),
+                ("What is the time zone in London?", "time"),
+                ("What time is it in London?", "time"),
+                ("Current time?", "time"),
+                ("Turn the lights blue", "home"),
+                ("Turn off the radio", "home"),
+                ("I’m home", "home"),
+                ("Set the brightness of the downstairs lights to 50%", "home"),
+                ("Lock the front door", "home"),
+                ("Open the garage", "home"),
+                ("John is my brother", "contacts"),
+                ("That’s not how you say John Doe", "contacts"),
+                ("Show John Doe", "contacts"),
+                ("When is John’s birthday?", "contacts"),
+                ("How old is my brother?", "contacts"),
+                ("Whose phone is this?", "contacts"),
+                ("Learn to pronounce my name", "contacts"),
+                ("Call John", "calls"),
+                ("Call 408 555 1212", "calls"),
+                ("Call my brother on speakerphone", "calls"),
+                ("Call the nearest restaurant", "calls"),
+                ("When did my brother call me?", "calls"),
+                ("Play voicemail from John", "calls"),
+                ("Get my call history", "calls"),
+                ("Redial my last number", "calls"),
+                ("Call back my last missed call", "calls"),
+                ("Any new voicemail?", "calls"),
+                ("Play me my latest voicemail", "calls"),
+                ("Show me new messages from John Doe", "messages"),
+                ("Show me my messages", "messages"),
+                ("Read my messages", "messages"),
+                ("Text John Doe I’m in a meeting", "messages"),
+                ("Message my brother I’ll be late", "messages"),
+                ("Send John see you later", "messages"),
+                ("Tell John I’m on the way", "messages"),
+                ("Ask my brother Where are you?", "messages"),
+                ("Any new email from John Doe?", "email"),
+                ("Show me the email from John Doe yesterday", "email"),
+                ("Send an email to John Doe Protocol", "email"),
+                ("Check email", "email"),
+                ("Read my last email", "email"),
+                ("Post to Facebook I’m eating a sandwich", "social"),
+                ("Post to Twitter Happy New Year!", "social"),
+                ("Tweet with my location very hot here", "social"),
+                ("Show me tweets from Twitter", "social"),
+                ("Show me the latest tweets", "social"),
+                (
+                    "Schedule an event Party in New York Wednesday at 10 PM",
+                    "calendar",
+                ),
+                (
+                    "Schedule a meeting at 1 PM tomorrow for 2 hours",
+                    "calendar",
+                ),
+                (
+                    "Create a recurring event every Saturday at 2:30 PM called Party",
+                    "calendar",
+                ),
+                ("Set up a meeting with John for today at 3 PM", "calendar"),
+                ("Show me my next appointment", "calendar"),
+                ("Where is my next meeting?", "calendar"),
+                ("Show me the appointments for this afternoon", "calendar"),
+                ("What does my calendar look like on Monday?", "calendar"),
+                ("When am I meeting with John Doe?", "calendar"),
+                (
+                    "Cancel my Party in New York event from tomorrow",
+                    "calendar",
+                ),
+                ("Cancel my event with John Doe", "calendar"),
+                ("Move my Monday meeting with John to 3 o’clock", "calendar"),
+                ("Remind me on Friday at 10 PM to wash the car", "reminders"),
+                ("Add Milk to the Grocery list", "reminders"),
+                (
+                    "Remind me to wash the car when I leave home today",
+                    "reminders",
+                ),
+                ("Remind me to buy milk next time I’m here", "reminders"),
+                ("Remind me to wash the car every second week", "reminders"),
+                ("Delete the reminder wash the car", "reminders"),
+                ("Show me my Grocery list", "reminders"),
+                ("Note 12 Dollars for pizza", "notes"),
+                ("Note Interesting Movies", "notes"),
+                ("Add 10 Dollars for food to Outcomes note", "notes"),
+                ("Add Star Wars to Interesting Movies note", "notes"),
+                ("Show me my notes", "notes"),
+                ("Show me my note Interesting Movies", "notes"),
+                ("Show me my notes from last week", "notes"),
+                ("Tell me about the traffic in New York", "maps"),
+                ("What are some attractions around here?", "maps"),
+                ("Where is Big Ben?", "maps"),
+                ("Is the Central Park open now?", "maps"),
+                ("Distance between here and New York?", "maps"),
+                ("How far away is Boston?", "maps"),
+                ("What is the nearest restaurant?", "maps"),
+                ("Find a Starbucks", "maps"),
+                ("Good Mexican restaurants around here", "maps"),
+                ("Table for two in Palo Alto tonight", "maps"),
+                (
+                    "Make a reservation at a romantic Italian restaurant tonight at 7 PM",
+                    "maps",
+                ),
+                (
+                    "Show me the reviews for Alexander’s Steakhouse in Cupertino",
+                    "maps",
+                ),
+                ("Turn off my alarm", "alarms"),
+                ("Delete all alarms", "alarms"),
+                ("Turn off my Good Morning alarm", "alarms"),
+                ("Show me my alarms", "alarms"),
+                ("Is Ian McKellen still alive?", "trivia"),
+                ("How tall is Ian McKellen?", "trivia"),
+                ("Where was Ian McKellen born?", "trivia"),
+                ("Who is Ian McKellen married to?", "trivia"),
+                ("Who wrote Harry Potter?", "trivia"),
+                ("Who invented the iPhone?", "trivia"),
+                ("How far away is the moon?", "trivia"),
+                ("How high is Mount Everest?", "trivia"),
+                ("What is the population of Switzerland?", "trivia"),
+                ("How many calories in a bagel?", "trivia"),
+                ("How long do dogs live?", "trivia"),
+                ("How many teeth does a dog have?", "trivia"),
+                ("What type of Pokémon is Pikachu?", "trivia"),
+                ("Spell necessary", "dictionary"),
+                ("What’s the weather like?", "weather"),
+                ("Do I need an umbrella for tomorrow?", "weather"),
+                (
+                    "What’s the weather going to be like in Madrid tomorrow?",
+                    "weather",
+                ),
+                ("Is there is a chance of rain tomorrow?", "weather"),
+                ("What’s the perceived temperature outside?", "weather"),
+                ("What’s the dew point outside?", "weather"),
+                ("Is it windy outside?", "weather"),
+                ("What’s the pressure outside?", "weather"),
+                ("What’s the visibility outside?", "weather"),
+                ("What is the KP Index?", "weather"),
+                ("How humid is it outside?", "weather"),
+                ("When is the sunrise?", "weather"),
+                ("When is the sunset tomorrow?", "weather"),
+                ("When is the sunrise on Friday?", "weather"),
+                ("When is the sunset in New York?", "weather"),
+                ("What’s the Apple stock price?", "stocks"),
+                ("Compare Apple with Alphabet", "stocks"),
+                ("Define airplane", "dictionary"),
+                ("What is the definition of airplane?", "dictionary"),
+                (
+                    "What does the French word maison mean in English?",
+                    "translation",
+                ),
+                ("Find books by Charles Dickens", "media"),
+                ("Find movies by Christopher Nolan", "media"),
+                ("What is the movie Indiana Jones about?", "media"),
+                ("When was Indiana Jones released?", "media"),
+                ("Runtime of Indiana Jones?", "media"),
+                ("Who acted in Indiana Jones?", "media"),
+                ("Movies with Scarlett Johansson", "media"),
+                ("Best thriller movies?", "media"),
+                ("Which movie won Best Picture in 1966?", "media"),
+                ("What movies are playing this evening?", "media"),
+                (
+                    "Buy three tickets to see The Lego Movie tonight in Sacramento",
+                    "media",
+                ),
+                ("Find some movie theaters near my home", "media"),
+                ("Shuffle my gym playlist", "music"),
+                ("What’s this song?", "music"),
+                ("Who sings this?", "music"),
+                ("I like this song", "music"),
+                ("What is the point spread in the NFL game?", "sports"),
+                ("How is Chelsea doing?", "sports"),
+                ("Results from Liverpool last game?", "sports"),
+                ("Who’s going to win the Vikings game?", "sports"),
+                ("When is the next Liverpool game?", "sports"),
+                ("What Channel is the Royals game on?", "sports"),
+                ("When is the Super Bowl?", "sports"),
+                ("Flip a coin", "randomness"),
+                ("Pick a card", "randomness"),
+                ("Roll a twenty-sided die", "randomness"),
+                ("Random number between 30 and 60", "randomness"),
+                ("See you on the seventh", "banter"),
+                ("What is 1 million divided by 0?", "mathematics"),
+                ("What is 0 divided by 0?", "mathematics"),
+                ("What is infinity times infinity?", "mathematics"),
+                ("Rock paper scissors", "banter"),
+                ("Sudo make me a sandwich", "banter"),
+                ("Tell me a joke", "banter"),
+                ("Tell haiku", "banter"),
+                ("Tell me a tongue twister", "banter"),
+                ("Tell me a story", "banter"),
+                ("Tell me a poem", "banter"),
+                ("Tell me a secret", "banter"),
+                ("Tell me a bedtime story", "banter"),
+                ("Sing me a lullaby", "banter"),
+                ("Beam me up", "banter"),
+                ("Guess what", "banter"),
+                ("Who’s on first?", "banter"),
+                ("Open the pod bay doors", "banter"),
+                ("Sing me a song now", "banter"),
+                ("When is your birthday?", "banter"),
+                ("What’s your sign?", "banter"),
+                ("What’s your favourite animal?", "banter"),
+                ("What color is your hair?", "banter"),
+                ("How much do you weigh?", "banter"),
+                ("Are you smart?", "banter"),
+                ("Are you perfect?", "banter"),
+                ("Do you think I look fat in this?", "banter"),
+                ("Will you marry me?", "banter"),
+                ("May the force be with you", "banter"),
+                ("Can I call you Jarvis?", "banter"),
+                ("When do you sleep?", "banter"),
+                ("How is it to be you?", "banter"),
+                ("Have you seen Star Wars?", "banter"),
+                ("What is your favourite colour?", "banter"),
+                ("What are you going to be for Halloween?", "banter"),
+                ("Do you know pick up lines?", "banter"),
+                (
+                    "Mirror mirror on the wall, who’s the fairest of them all?",
+                    "banter",
+                ),
+                ("What does the fox say?", "banter"),
+                ("Who let the dogs out?", "banter"),
+                (
+                    "How much wood could a woodchuck chuck if a woodchuck could chuck wood?",
+                    "banter",
+                ),
+                (
+                    "What is the airspeed velocity of an unladen swallow?",
+                    "banter",
+                ),
+                ("Why are fire trucks red?", "banter"),
+                ("Why did the chicken cross the road?", "banter"),
+                ("What is the meaning of life?", "banter"),
+                ("When is the end of the world?", "banter"),
+                ("What’s the best phone?", "banter"),
+                ("Can I borrow some money?", "banter"),
+                ("supercalifragilisticexpialidocious", "banter"),
+                ("Rap Beatbox", "banter"),
+                ("Can I call you Cortana?", "banter"),
+                ("You’re the best", "banter"),
+                ("Meow", "banter"),
+                ("I’m sleepy", "banter"),
+                ("How many languages do you speak?", "banter"),
             ],
             DatasetSize::Small => vec![
-                "What is the factorial of 6?", // mathematics
-                "What day was 90 days ago?",   // time
-                "What is the temperature in living room?", // home
-                "Any missed calls?",           // calls
-                "Read Calendar",               // calendar
-                "Remind me to wash the car",   // reminders
-                "How far is New York from Boston", // maps
-                "How old is Ian McKellen?",    // trivia
-                "What’s the temperature outside?", // weather
-                "Translate car from English to Spanish", // translation
-                "Roll a die",                  // randomness
-                "Is there a God?",             // banter
-                "What’s 2330 dollars in euros?", // conversion
+                ("What is the factorial of 6?", "mathematics"),
+                ("What day was 90 days ago?", "time"),
+                ("What is the temperature in living room?", "home"),
+                ("Any missed calls?", "calls"),
+                ("Read Calendar", "calendar"),
+                ("Remind me to wash the car", "reminders"),
+                ("How far is New York from Boston", "maps"),
+                ("How old is Ian McKellen?", "trivia"),
+                ("What’s the temperature outside?", "weather"),
+                ("Translate car from English to Spanish", "translation"),
+                ("Roll a die", "randomness"),
+                ("Is there a God?", "banter"),
+                ("What’s 2330 dollars in euros?", "conversion"),
             ],
-            DatasetSize::Binary => vec!["Call John Doe", "Call Mary Poppins"],
+            DatasetSize::Binary => vec![("Call John Doe", "calls"), ("Call Mary Poppins", "calls")],
         }
     }
 }
@@ -331,3 +388,319 @@ impl Display for DatasetSize {
         )
     }
 }
+
+/// A query of a dataset loaded from a file.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct DatasetQuery {
+    /// The query, without the wake word.
+    pub text: String,
+    /// The category of the query, e.g. `calls`.
+    #[serde(default)]
+    pub category: String,
+    /// The locale the query is asked in, e.g. `en-US`.
+    #[serde(default)]
+    pub locale: Option<String>,
+}
+
+/// The queries of a dataset, either a built-in [`DatasetSize`] or a list loaded from a file.
+#[derive(Clone, Debug)]
+pub enum Dataset {
+    /// One of the built-in datasets.
+    Preset(DatasetSize),
+    /// A dataset loaded with [`Dataset::from_file`].
+    File {
+        /// The name of the dataset, which names its label map and exports.
+        name: String,
+        /// The queries of the dataset.
+        queries: Vec<DatasetQuery>,
+    },
+    /// Another dataset restricted to the queries of some of its categories.
+    Categories {
+        /// The dataset to restrict.
+        dataset: Box<Dataset>,
+        /// The categories whose queries are kept.
+        categories: Vec<String>,
+    },
+    /// Another dataset with a balanced sample of its interactions in every query category, see [`DatasetSampler`].
+    Sampled {
+        /// The dataset to sample from.
+        dataset: Box<Dataset>,
+        /// How the interactions are sampled.
+        sampler: DatasetSampler,
+    },
+}
+
+impl Dataset {
+    /// Load a dataset from a TOML, YAML or CSV file, chosen by its extension.
+    ///
+    /// TOML and YAML files list the queries with an optional name of the dataset, which defaults to the file name:
+    ///
+    /// ```toml
+    /// name = "calls"
+    ///
+    /// [[queries]]
+    /// text = "Call John Doe"
+    /// category = "calls"
+    /// locale = "en-US"
+    /// ```
+    ///
+    /// CSV files have a header with the columns `text`, `category` and `locale`, of which only `text` is required.
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: The path to the file.
+    /// * `wake_word`: The wake word of the assistant the queries were asked to, which they must not start with, or
+    ///   `None` to not check for it.
+    pub fn from_file<P: AsRef<Path>>(path: P, wake_word: Option<&str>) -> Result<Self, Error> {
+        #[derive(Deserialize)]
+        struct DatasetFile {
+            name: Option<String>,
+            queries: Vec<DatasetQuery>,
+        }
+
+        let path = path.as_ref();
+        let file_name = path
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        let extension = path
+            .extension()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_lowercase();
+
+        let (name, queries) = match extension.as_str() {
+            "toml" => {
+                let file: DatasetFile = toml::from_str(&fs::read_to_string(path)?)?;
+                (file.name, file.queries)
+            }
+            "yaml" | "yml" => {
+                let file: DatasetFile = serde_yaml::from_str(&fs::read_to_string(path)?)?;
+                (file.name, file.queries)
+            }
+            "csv" => (
+                None,
+                csv::Reader::from_path(path)?
+                    .deserialize()
+                    .collect::<Result<_, _>>()?,
+            ),
+            _ => return Err(Error::UnsupportedDatasetFile(path.display().to_string())),
+        };
+
+        info!("Loaded {} queries from {}", queries.len(), path.display());
+        validation::validate(queries.iter().map(|query| query.text.as_str()), wake_word).check()?;
+
+        Ok(Dataset::File {
+            name: name.unwrap_or(file_name),
+            queries,
+        })
+    }
+
+    /// Filter out all interactions that should not be used for this dataset.
+    ///
+    /// # Arguments
+    ///
+    /// * `interactions`: The interactions to filter.
+    pub fn filter(&self, interactions: Vec<Interaction>) -> Vec<Interaction> {
+        match self {
+            Dataset::Categories { dataset, .. } => {
+                filter_by_queries(&self.queries(), dataset.filter(interactions))
+            }
+            Dataset::Sampled { dataset, sampler } => sampler.sample(dataset.filter(interactions)),
+            _ => filter_by_queries(&self.queries(), interactions),
+        }
+    }
+
+    /// All queries that are used for this dataset.
+    pub fn queries(&self) -> Vec<&str> {
+        self.categorised_queries()
+            .into_iter()
+            .map(|(query, _)| query)
+            .collect()
+    }
+
+    /// All query categories of this dataset, sorted alphabetically.
+    pub fn categories(&self) -> Vec<&str> {
+        categories(&self.categorised_queries())
+    }
+
+    /// All queries that are used for this dataset with their category.
+    pub fn categorised_queries(&self) -> Vec<(&str, &str)> {
+        match self {
+            Dataset::Preset(dataset_size) => dataset_size.categorised_queries(),
+            Dataset::File { queries, .. } => queries
+                .iter()
+                .map(|query| (query.text.as_str(), query.category.as_str()))
+                .collect(),
+            Dataset::Categories {
+                dataset,
+                categories,
+            } => dataset
+                .categorised_queries()
+                .into_iter()
+                .filter(|(_, category)| categories.iter().any(|kept| kept == category))
+                .collect(),
+            Dataset::Sampled { dataset, .. } => dataset.categorised_queries(),
+        }
+    }
+
+    /// Only use the queries of some categories of this dataset.
+    ///
+    /// Returns [`Error::UnknownCategory`] if the dataset has no query in one of the categories.
+    ///
+    /// # Arguments
+    ///
+    /// * `categories`: The categories whose queries are kept.
+    pub fn in_categories(self, categories: Vec<String>) -> Result<Self, Error> {
+        if let Some(category) = categories
+            .iter()
+            .find(|category| !self.categories().contains(&category.as_str()))
+        {
+            return Err(Error::UnknownCategory(category.clone()));
+        }
+
+        Ok(Dataset::Categories {
+            dataset: Box::new(self),
+            categories,
+        })
+    }
+
+    /// Use a balanced sample of the interactions of this dataset in every query category.
+    ///
+    /// # Arguments
+    ///
+    /// * `sampler`: How the interactions are sampled.
+    pub fn sampled(self, sampler: DatasetSampler) -> Self {
+        Dataset::Sampled {
+            dataset: Box::new(self),
+            sampler,
+        }
+    }
+}
+
+impl From<DatasetSize> for Dataset {
+    fn from(value: DatasetSize) -> Self {
+        Dataset::Preset(value)
+    }
+}
+
+impl Display for Dataset {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Dataset::Preset(dataset_size) => write!(f, "{dataset_size}"),
+            Dataset::File { name, .. } => write!(f, "{name}"),
+            Dataset::Categories {
+                dataset,
+                categories,
+            } => write!(f, "{dataset}-{}", categories.join("-")),
+            Dataset::Sampled { dataset, sampler } => write!(
+                f,
+                "{dataset}-{}-per-category-{}",
+                sampler.per_category, sampler.seed
+            ),
+        }
+    }
+}
+
+/// Picks the same number of interactions from every query category, so no category dominates a dataset.
+///
+/// The sample is drawn with a seeded random number generator, so the same seed always picks the same interactions.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DatasetSampler {
+    /// The number of interactions to pick from every category.
+    pub per_category: usize,
+    /// The seed of the random number generator the interactions are picked with.
+    pub seed: u64,
+}
+
+impl DatasetSampler {
+    /// Pick up to [`DatasetSampler::per_category`] interactions from every query category.
+    ///
+    /// Categories with fewer interactions are kept completely. The sampled interactions are returned ordered by id.
+    ///
+    /// # Arguments
+    ///
+    /// * `interactions`: The interactions to sample from.
+    pub fn sample(&self, interactions: Vec<Interaction>) -> Vec<Interaction> {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut categories: BTreeMap<String, Vec<Interaction>> = BTreeMap::new();
+        for interaction in interactions {
+            categories
+                .entry(interaction.query_category.clone())
+                .or_default()
+                .push(interaction);
+        }
+
+        let mut sampled: Vec<Interaction> = categories
+            .into_iter()
+            .flat_map(|(category, mut interactions)| {
+                if interactions.len() < self.per_category {
+                    warn!(
+                        "Category {category} only has {} of {} interactions",
+                        interactions.len(),
+                        self.per_category
+                    );
+                }
+
+                // the order the interactions were loaded in must not change the sample
+                interactions.sort_by_key(|interaction| interaction.id);
+                interactions.shuffle(&mut rng);
+                interactions.truncate(self.per_category);
+                interactions
+            })
+            .collect();
+        sampled.sort_by_key(|interaction| interaction.id);
+
+        info!(
+            "Sampled {} interactions with up to {} per category",
+            sampled.len(),
+            self.per_category
+        );
+
+        sampled
+    }
+}
+
+/// The distinct categories of queries, sorted alphabetically.
+///
+/// # Arguments
+///
+/// * `queries`: The queries with their category.
+fn categories<'a>(queries: &[(&'a str, &'a str)]) -> Vec<&'a str> {
+    queries
+        .iter()
+        .map(|(_, category)| *category)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+/// Filter out all interactions whose query is not one of the given ones, asked to any of the supported assistants.
+///
+/// # Arguments
+///
+/// * `queries`: The queries to keep, without the wake word.
+/// * `interactions`: The interactions to filter.
+fn filter_by_queries(queries: &[&str], interactions: Vec<Interaction>) -> Vec<Interaction> {
+    // Log the initial number of interactions
+    info!(
+        "Starting filter process. Total interactions: {}",
+        interactions.len()
+    );
+
+    // Filter the interactions and log the ones that are kept, queries are stored without the wake word
+    let queries: HashSet<&str> = queries.iter().copied().collect();
+    let filtered_interactions: Vec<Interaction> = interactions
+        .into_iter()
+        .filter(|interaction| queries.contains(interaction.query.as_str()))
+        .collect();
+
+    // Log the number of filtered interactions
+    info!(
+        "Filtering complete. Number of interactions kept: {}",
+        filtered_interactions.len()
+    );
+
+    filtered_interactions
+}
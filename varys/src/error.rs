@@ -1,5 +1,7 @@
 use thiserror::Error;
 
+use crate::query::validation::ValidationReport;
+
 #[derive(Error, Debug)]
 pub enum Error {
     #[error(transparent)]
@@ -8,6 +10,7 @@ pub enum Error {
     AudioError(#[from] varys_audio::error::Error),
     #[error(transparent)]
     NetworkError(#[from] varys_network::error::Error),
+    #[cfg(feature = "analysis")]
     #[error(transparent)]
     AnalysisError(#[from] varys_analysis::error::Error),
 
@@ -17,8 +20,81 @@ pub enum Error {
     Dotenv(String),
     #[error(transparent)]
     TomlDeserializeError(#[from] toml::de::Error),
+    #[error(transparent)]
+    JsonError(#[from] serde_json::Error),
+    #[cfg(feature = "analysis")]
+    #[error(transparent)]
+    YamlError(#[from] serde_yaml::Error),
+    #[cfg(feature = "analysis")]
+    #[error(transparent)]
+    CsvError(#[from] csv::Error),
+    #[error("Datasets can only be loaded from .toml, .yaml and .csv files, not {0}")]
+    UnsupportedDatasetFile(String),
+    #[error("Dataset {0} does not exist")]
+    DatasetNotFound(String),
+    #[error("Dataset {0} has no queries")]
+    EmptyDataset(String),
+    #[error("The dataset has no queries in category {0}")]
+    UnknownCategory(String),
+    #[error("The queries are invalid, {0}")]
+    InvalidQueries(ValidationReport),
     #[error("At least one voice is required")]
     NoVoiceProvided,
+    #[error("\"{0}\" is not a wake word of {1}")]
+    UnknownWakeWord(String, String),
+    #[error("The query template \"{0}\" has an unclosed variable")]
+    InvalidTemplate(String),
+    #[error("The query template variable {0} has no values")]
+    UnknownTemplateVariable(String),
+    #[error("The redaction pattern {0} is not a valid regular expression")]
+    InvalidRedactionPattern(String),
+    #[error("{0}")]
+    SelftestFailed(String),
+    #[error("Could not download the whisper model: {0}")]
+    ModelDownloadFailed(reqwest::Error),
+    #[error("Found {0} problems with the setup of this machine")]
+    ProblemsFound(usize),
+    #[error("Session {0} does not exist")]
+    SessionNotFound(i32),
+    #[error("Interaction {0} does not exist")]
+    InteractionNotFound(i32),
+    #[error("Session {0} is already completed")]
+    SessionAlreadyCompleted(i32),
+    #[error("The interaction was aborted because varys is shutting down")]
+    InteractionAborted,
+    #[error("The interaction took longer than its time budget of {0}s")]
+    InteractionTimedOut(u64),
+    #[error("There is no recording of the query \"{0}\"")]
+    QueryRecordingMissing(String),
+    #[error("Typing the query failed: {0}")]
+    TypingFailed(String),
+    #[error("{0} sessions could not be relocated because files are missing")]
+    RelocationIncomplete(usize),
+    #[error("{0} replayed captures were classified differently than offline")]
+    ReplayMismatch(usize),
+    #[error("Session {0} has no interactions to simulate")]
+    NothingToSimulate(i32),
+    #[error("Session {0} has no interactions to replay")]
+    NothingToReplay(i32),
+    #[error("Invalid session id {0}, expected session:<ID>")]
+    InvalidSessionReference(String),
+    #[error("{0} simulated interactions ended differently than the collected ones")]
+    SimulationMismatch(usize),
+
+    #[error("varys was built without the {0} feature")]
+    FeatureDisabled(String),
+    #[error("The pipeline configuration is invalid: {0}")]
+    InvalidPipeline(String),
+    #[error("The viewer could not be started: {0}")]
+    ViewerFailed(String),
+
+    // scheduling
+    #[error("The schedule expression {0} is invalid")]
+    InvalidScheduleExpression(String),
+    #[error("Schedule {0} does not exist")]
+    ScheduleNotFound(String),
+    #[error("The quiet hours are invalid: {0}")]
+    InvalidQuietHours(String),
 
     // monitoring
     #[error("Connection to monitoring failed: {0}")]
@@ -27,4 +103,24 @@ pub enum Error {
     MissingMonitoringUrl,
     #[error("The monitoring url {0} is invalid")]
     InvalidMonitoringUrl(String),
+
+    // mute experiment
+    #[error("Could not mute or unmute the microphone: {0}")]
+    MuteControlFailed(reqwest::Error),
+    #[error("The mute url {0} is invalid")]
+    InvalidMuteUrl(String),
+
+    // rate sweep
+    #[error("The speaking rate {0} is invalid, it has to be positive")]
+    InvalidSpeakingRate(f32),
+    #[error("A rate sweep needs at least one speaking rate")]
+    NoSpeakingRates,
+
+    // power cycling
+    #[error("Could not switch the smart plug: {0}")]
+    SmartPlugFailed(String),
+
+    // voice verification
+    #[error("None of the recordings contain any sound")]
+    NoVoiceRecorded,
 }
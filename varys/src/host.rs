@@ -0,0 +1,249 @@
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::time::{Duration, Instant};
+
+use log::{info, warn};
+use sysinfo::{ComponentExt, CpuExt, Pid, ProcessExt, System, SystemExt};
+use tokio::task::JoinHandle;
+use varys_database::connection::DatabaseConnection;
+use varys_database::database::session_event::SessionEvent;
+
+use crate::monitoring;
+
+/// The kind of the session events recorded while the host is too hot or throttled.
+pub const THERMAL_ALERT_EVENT: &str = "thermal_alert";
+
+/// The file in which the firmware of a Raspberry Pi reports whether it is throttled.
+const THROTTLED_PATH: &str = "/sys/devices/platform/soc/soc:firmware/get_throttled";
+
+/// Measures the resource usage of the host over a period of time, e.g. an interaction.
+pub struct HostMonitor {
+    system: System,
+    pid: Option<Pid>,
+    started: Instant,
+}
+
+/// The resource usage of the host measured by a [`HostMonitor`].
+#[derive(Debug)]
+pub struct HostUsage {
+    /// The average usage of all CPUs in percent.
+    pub cpu_usage: f32,
+    /// The average CPU usage of varys in percent, where 100% is one fully used core.
+    pub process_cpu_usage: f32,
+    /// The memory used by varys in bytes.
+    pub memory_usage: u64,
+    /// How many bytes varys has written to disk.
+    pub disk_written: u64,
+    /// How many bytes varys has written to disk per second.
+    pub disk_write_rate: f64,
+}
+
+impl HostMonitor {
+    /// Start measuring the resource usage of the host.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys::host::HostMonitor;
+    /// let monitor = HostMonitor::start();
+    ///
+    /// let usage = monitor.finish();
+    /// assert!(usage.memory_usage > 0);
+    /// ```
+    pub fn start() -> Self {
+        let mut system = System::new();
+        let pid = sysinfo::get_current_pid().ok();
+
+        system.refresh_cpu();
+        if let Some(pid) = pid {
+            system.refresh_process(pid);
+        }
+
+        HostMonitor {
+            system,
+            pid,
+            started: Instant::now(),
+        }
+    }
+
+    /// Stop measuring and get the resource usage since the monitor was started.
+    pub fn finish(mut self) -> HostUsage {
+        let elapsed = self.started.elapsed().as_secs_f64();
+
+        self.system.refresh_cpu();
+        if let Some(pid) = self.pid {
+            self.system.refresh_process(pid);
+        }
+        let process = self.pid.and_then(|pid| self.system.process(pid));
+        let disk_written = process
+            .map(|process| process.disk_usage().written_bytes)
+            .unwrap_or_default();
+
+        HostUsage {
+            cpu_usage: self.system.global_cpu_info().cpu_usage(),
+            process_cpu_usage: process
+                .map(|process| process.cpu_usage())
+                .unwrap_or_default(),
+            memory_usage: process.map(|process| process.memory()).unwrap_or_default(),
+            disk_written,
+            disk_write_rate: if elapsed > 0. {
+                disk_written as f64 / elapsed
+            } else {
+                0.
+            },
+        }
+    }
+}
+
+/// The temperature and throttling state of the host at one point in time.
+#[derive(Debug)]
+pub struct ThermalState {
+    /// The highest temperature of all sensors in degrees Celsius, if the host has any sensors.
+    pub temperature: Option<f32>,
+    /// The throttling flags reported by the firmware of a Raspberry Pi, if available.
+    ///
+    /// The lowest four bits are set while the host is under-voltage, frequency capped, throttled or at its soft
+    /// temperature limit. The same conditions are reported in bits 16 to 19 if they have occurred since booting.
+    pub throttled: Option<u32>,
+}
+
+impl ThermalState {
+    /// Read the current temperature and throttling state of the host.
+    pub fn read() -> Self {
+        let mut system = System::new();
+        system.refresh_components_list();
+
+        ThermalState {
+            temperature: system
+                .components()
+                .iter()
+                .map(|component| component.temperature())
+                .filter(|temperature| temperature.is_finite())
+                .reduce(f32::max),
+            throttled: fs::read_to_string(THROTTLED_PATH).ok().and_then(|flags| {
+                u32::from_str_radix(flags.trim().trim_start_matches("0x"), 16).ok()
+            }),
+        }
+    }
+
+    /// Whether the host is currently throttled in any way.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys::host::ThermalState;
+    /// let previously_throttled = ThermalState {
+    ///     temperature: Some(60.0),
+    ///     throttled: Some(0x40000),
+    /// };
+    /// let throttled = ThermalState {
+    ///     temperature: Some(82.0),
+    ///     throttled: Some(0x40004),
+    /// };
+    ///
+    /// assert!(!previously_throttled.is_throttled());
+    /// assert!(throttled.is_throttled());
+    /// ```
+    pub fn is_throttled(&self) -> bool {
+        self.throttled.is_some_and(|flags| flags & 0xf != 0)
+    }
+
+    /// Whether the host is throttled or hotter than a limit.
+    ///
+    /// # Arguments
+    ///
+    /// * `temperature_limit`: The highest acceptable temperature in degrees Celsius.
+    pub fn is_critical(&self, temperature_limit: f32) -> bool {
+        self.is_throttled()
+            || self
+                .temperature
+                .is_some_and(|temperature| temperature > temperature_limit)
+    }
+}
+
+impl Display for ThermalState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.temperature {
+            Some(temperature) => write!(f, "{temperature:.1}°C")?,
+            None => write!(f, "unknown temperature")?,
+        }
+        match self.throttled {
+            Some(flags) if self.is_throttled() => write!(f, ", throttled (0x{flags:x})"),
+            Some(_) => write!(f, ", not throttled"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Periodically logs the temperature and throttling state of the host during a session.
+///
+/// Capture rigs like a Raspberry Pi can throttle hours into a session and start dropping packets without obvious
+/// symptoms, so critical states are reported to monitoring and recorded as session events.
+pub struct ThermalMonitor {
+    /// How often to check the state of the host.
+    pub interval: Duration,
+    /// The highest acceptable temperature in degrees Celsius.
+    pub temperature_limit: f32,
+}
+
+impl ThermalMonitor {
+    /// Start checking the state of the host in the background until the returned handle is dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `session_id`: The id of the session to record events for.
+    /// * `connection`: The connection to use.
+    pub fn start(&self, session_id: i32, connection: DatabaseConnection) -> ThermalMonitorHandle {
+        let interval = self.interval;
+        let temperature_limit = self.temperature_limit;
+
+        ThermalMonitorHandle(tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            let mut alert: Option<SessionEvent> = None;
+
+            loop {
+                interval.tick().await;
+                let state = ThermalState::read();
+                info!("Host state: {state}");
+
+                match (state.is_critical(temperature_limit), alert.take()) {
+                    (true, None) => {
+                        warn!("The host is in a critical state: {state}");
+
+                        if let Err(error) =
+                            monitoring::ping(&format!("Host is in a critical state: {state}")).await
+                        {
+                            warn!("Failed to notify monitoring about host state: {error}");
+                        }
+                        alert = SessionEvent::create(
+                            &connection,
+                            session_id,
+                            THERMAL_ALERT_EVENT,
+                            &state.to_string(),
+                        )
+                        .await
+                        .map_err(|error| warn!("Failed to record host state: {error}"))
+                        .ok();
+                    }
+                    (false, Some(mut event)) => {
+                        info!("The host has recovered: {state}");
+
+                        if let Err(error) = event.end(&connection).await {
+                            warn!("Failed to record host state: {error}");
+                        }
+                    }
+                    (_, event) => alert = event,
+                }
+            }
+        }))
+    }
+}
+
+/// A handle to a running [`ThermalMonitor`], which stops it when dropped.
+pub struct ThermalMonitorHandle(JoinHandle<()>);
+
+impl Drop for ThermalMonitorHandle {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
@@ -2,10 +2,17 @@ use clap::crate_version;
 
 pub mod assistant;
 pub mod cli;
-mod dataset;
+pub mod crash;
+#[cfg(feature = "analysis")]
+pub mod dataset;
 pub mod error;
+#[cfg(feature = "collection")]
+pub mod host;
 pub mod monitoring;
 pub mod query;
+pub mod redact;
+pub mod response;
+pub mod schedule;
 
 pub fn version() -> String {
     crate_version!().to_string()
@@ -1,7 +1,7 @@
 use log::error;
 
-use varys::cli;
 use varys::error::Error;
+use varys::{cli, crash};
 
 #[tokio::main]
 async fn main() -> Result<(), Error> {
@@ -15,6 +15,7 @@ async fn main() -> Result<(), Error> {
 async fn main_fallible() -> Result<(), Error> {
     dotenvy::dotenv().map_err(|error| Error::Dotenv(error.to_string()))?;
     pretty_env_logger::init();
+    crash::install_hook();
 
     cli::run().await
 }
@@ -1,19 +1,117 @@
+use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use log::{debug, info, warn};
-use toml::Table;
+use sha2::{Digest, Sha256};
+use toml::{Table, Value};
+use varys_database::connection::DatabaseConnection;
+use varys_database::database;
+use varys_database::database::dataset::NamedDataset;
 
+use crate::assistant::replay::Replay;
 use crate::error::Error;
+use crate::query::typing::{TypingCommand, TEXT_CHANNEL, VOICE_CHANNEL};
+
+pub mod normalisation;
+pub mod typing;
+pub mod validation;
+
+/// The prefix of the queries argument of sessions that asks the queries of a named dataset stored in the database, e.g.
+/// `dataset:smart-home`.
+pub const DATASET_PREFIX: &str = "dataset:";
+/// The prefix of the queries argument of sessions that asks the queries of a collected session again in the same order,
+/// e.g. `session:42`, see [`Replay`].
+pub const SESSION_PREFIX: &str = "session:";
+
+/// The maximum length of a vocabulary prompt in characters, which keeps it well below the token limit of whisper
+/// prompts.
+const MAX_VOCABULARY_PROMPT_LENGTH: usize = 600;
 
 #[derive(Debug, Clone)]
 pub struct Query {
     pub text: String,
     pub category: String,
+    /// How long to record the response for at most, overriding the recording timeout of the assistant.
+    ///
+    /// Some categories, like stories or songs, have responses that last minutes while others end after seconds.
+    pub recording_timeout: Option<Duration>,
+    /// The queries asked right after this one in the same conversation, which can refer back to earlier turns.
+    ///
+    /// If this is empty, the query is asked on its own.
+    pub follow_ups: Vec<String>,
+    /// The locale the query is asked in, e.g. `de-DE`, which selects the voice that speaks it and the language its
+    /// response is transcribed in. If this is `None`, the query is asked in the voice of the session.
+    pub locale: Option<String>,
+    /// Whether the query is an exemplar sample, e.g. for the artefact release of a paper, whose responses can
+    /// additionally be stored losslessly.
+    pub exemplar: bool,
 }
 
 impl Query {
+    /// Read queries from a TOML file, from a named dataset stored in the database if the path is the name of the
+    /// dataset prefixed with [`DATASET_PREFIX`], or from a collected session if the path is its id prefixed with
+    /// [`SESSION_PREFIX`].
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: The path to the TOML file, `dataset:<name>` or `session:<id>`.
+    pub async fn read<P: AsRef<Path>>(path: P) -> Result<Vec<Self>, Error> {
+        if let Some(session_id) = session_reference(&path)? {
+            return Ok(Replay::load(&database::connect().await?, session_id)
+                .await?
+                .queries);
+        }
+
+        match path
+            .as_ref()
+            .to_str()
+            .and_then(|path| path.strip_prefix(DATASET_PREFIX))
+        {
+            Some(name) => Self::read_dataset(&database::connect().await?, name).await,
+            None => Self::read_toml(path),
+        }
+    }
+
+    /// Read the queries of a named dataset stored in the database.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection`: The connection to use.
+    /// * `name`: The name of the dataset.
+    pub async fn read_dataset(
+        connection: &DatabaseConnection,
+        name: &str,
+    ) -> Result<Vec<Self>, Error> {
+        info!("Reading queries from dataset {name}");
+
+        let dataset = NamedDataset::get_by_name(connection, name)
+            .await?
+            .ok_or_else(|| Error::DatasetNotFound(name.to_string()))?;
+        let queries: Vec<Self> = dataset
+            .queries(connection)
+            .await?
+            .into_iter()
+            .map(|query| Query {
+                text: query.text,
+                category: query.category,
+                recording_timeout: None,
+                follow_ups: Vec::new(),
+                locale: query.locale.or_else(|| dataset.locale.clone()),
+                exemplar: false,
+            })
+            .collect();
+        if queries.is_empty() {
+            return Err(Error::EmptyDataset(dataset.name));
+        }
+
+        debug!("Found {} queries", queries.len());
+
+        Ok(queries)
+    }
+
     /// Read queries from a TOML file.
     ///
     /// The TOML file should have the following format:
@@ -21,8 +119,26 @@ impl Query {
     /// ```toml
     /// category_1 = ["query_1", "query_2"]
     /// category_2 = ["query_3"]
+    ///
+    /// [category_3]
+    /// timeout = 120
+    /// locale = "de-DE"
+    /// exemplar = true
+    /// queries = ["query_4"]
+    /// conversations = [["query_5", "follow_up_1", "follow_up_2"]]
+    /// templates = ["Call {contact}.", "Text {contact} that I am late."]
+    ///
+    /// [variables]
+    /// contact = ["John Doe", "Mary Poppins"]
     /// ```
     ///
+    /// Categories given as tables can set a `timeout` in seconds, which overrides the recording timeout of the
+    /// assistant for their responses, the `locale` their queries are asked in, and whether their queries are `exemplar`
+    /// samples whose responses can be stored losslessly. They can also contain
+    /// `conversations`, whose first query is followed by the others as follow-ups, and `templates`, which are expanded
+    /// into one query for every combination of the values of their variables, see [`expand_template`]. The
+    /// `variables` table is shared by all templates and is not a category.
+    ///
     /// # Arguments
     ///
     /// * `path`: The path to the TOML file.
@@ -37,37 +153,453 @@ impl Query {
     /// assert!(queries
     ///     .first()
     ///     .is_some_and(|query| query.category == "test_category_jokes"
-    ///         && query.text == "Tell me a machine learning joke."));
+    ///         && query.text == "Tell me a machine learning joke."
+    ///         && query.recording_timeout.is_none()
+    ///         && !query.exemplar));
+    /// assert!(queries
+    ///     .last()
+    ///     .is_some_and(|query| query.category == "test_category_stories"
+    ///         && query.recording_timeout == Some(std::time::Duration::from_secs(120))
+    ///         && query.follow_ups == ["Tell me another one."]
+    ///         && query.exemplar));
     /// ```
     pub fn read_toml<P: AsRef<Path>>(path: P) -> Result<Vec<Self>, Error> {
         info!("Reading queries from {}", path.as_ref().display());
 
         let mut queries = Vec::new();
-        let toml = fs::read_to_string(path)
+        let mut toml = fs::read_to_string(path)
             .map_err(|e| {
                 warn!("Could not read queries file");
 
                 Error::Io(e)
             })?
             .parse::<Table>()?;
+        let variables: HashMap<String, Vec<String>> = match toml.remove("variables") {
+            Some(Value::Table(table)) => table
+                .into_iter()
+                .map(|(name, values)| {
+                    let values = values
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .filter_map(Value::as_str)
+                        .map(str::to_string)
+                        .collect();
+
+                    (name, values)
+                })
+                .collect(),
+            _ => HashMap::new(),
+        };
 
         for (category, value) in toml {
-            if let Some(array) = value.as_array() {
+            let (array, conversations, templates, recording_timeout, locale, exemplar) =
+                match &value {
+                    Value::Table(table) => (
+                        table.get("queries").and_then(Value::as_array),
+                        table.get("conversations").and_then(Value::as_array),
+                        table.get("templates").and_then(Value::as_array),
+                        table
+                            .get("timeout")
+                            .and_then(Value::as_integer)
+                            .map(|timeout| Duration::from_secs(timeout.max(0) as u64)),
+                        table
+                            .get("locale")
+                            .and_then(Value::as_str)
+                            .map(str::to_string),
+                        table
+                            .get("exemplar")
+                            .and_then(Value::as_bool)
+                            .unwrap_or_default(),
+                    ),
+                    value => (value.as_array(), None, None, None, None, false),
+                };
+
+            if let Some(array) = array {
                 for query in array {
                     if let Some(query) = query.as_str() {
                         queries.push(Query {
                             text: query.to_string(),
                             category: category.to_string(),
+                            recording_timeout,
+                            follow_ups: Vec::new(),
+                            locale: locale.clone(),
+                            exemplar,
                         })
                     }
                 }
             }
+
+            for template in templates.into_iter().flatten().filter_map(Value::as_str) {
+                for query in expand_template(template, &variables)? {
+                    queries.push(Query {
+                        text: query,
+                        category: category.to_string(),
+                        recording_timeout,
+                        follow_ups: Vec::new(),
+                        locale: locale.clone(),
+                        exemplar,
+                    })
+                }
+            }
+
+            for conversation in conversations.into_iter().flatten() {
+                let mut turns = conversation
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(Value::as_str)
+                    .map(str::to_string);
+
+                if let Some(query) = turns.next() {
+                    queries.push(Query {
+                        text: query,
+                        category: category.to_string(),
+                        recording_timeout,
+                        follow_ups: turns.collect(),
+                        locale: locale.clone(),
+                        exemplar,
+                    })
+                }
+            }
         }
 
         debug!("Found {} queries", queries.len());
 
         Ok(queries)
     }
+
+    /// Get the turns of the conversation started by this query: the query itself followed by its follow-ups.
+    ///
+    /// All turns share the category, recording timeout, locale and exemplar flag of the query.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys::query::Query;
+    /// let query = Query {
+    ///     text: "Who is Ian McKellen?".to_string(),
+    ///     category: "people".to_string(),
+    ///     recording_timeout: None,
+    ///     follow_ups: vec!["How old is he?".to_string()],
+    ///     locale: None,
+    ///     exemplar: false,
+    /// };
+    /// let turns = query.turns();
+    ///
+    /// assert_eq!(turns.len(), 2);
+    /// assert_eq!(turns[1].text, "How old is he?");
+    /// assert_eq!(turns[1].category, "people");
+    /// assert!(turns.iter().all(|turn| turn.follow_ups.is_empty()));
+    /// ```
+    pub fn turns(&self) -> Vec<Query> {
+        std::iter::once(&self.text)
+            .chain(&self.follow_ups)
+            .map(|text| Query {
+                text: text.clone(),
+                category: self.category.clone(),
+                recording_timeout: self.recording_timeout,
+                follow_ups: Vec::new(),
+                locale: self.locale.clone(),
+                exemplar: self.exemplar,
+            })
+            .collect()
+    }
+
+    /// Whether this query starts a conversation with follow-up queries.
+    pub fn is_conversation(&self) -> bool {
+        !self.follow_ups.is_empty()
+    }
+
+    /// The language the query is asked in as a two-letter code, see [`language`].
+    pub fn language(&self) -> Option<String> {
+        self.locale.as_deref().map(language)
+    }
+}
+
+/// Pick the queries with the fewest samples, so repeated sessions converge toward the same number of samples of every
+/// query.
+///
+/// Queries are ordered by their number of samples, keeping the order of queries with the same number, and only the
+/// first `limit` are kept. Queries without samples come first.
+///
+/// # Arguments
+///
+/// * `queries`: The queries to pick from.
+/// * `samples`: The number of samples of every query by its text.
+/// * `limit`: How many queries to pick.
+///
+/// # Examples
+///
+/// ```
+/// # use std::collections::HashMap;
+/// # use varys::query::{least_sampled, Query};
+/// let query = |text: &str| Query {
+///     text: text.to_string(),
+///     category: "test".to_string(),
+///     recording_timeout: None,
+///     follow_ups: Vec::new(),
+///     locale: None,
+///     exemplar: false,
+/// };
+/// let queries = vec![query("Call John Doe."), query("Call Mary Poppins."), query("Roll a die.")];
+/// let samples = HashMap::from([
+///     ("Call John Doe.".to_string(), 12),
+///     ("Call Mary Poppins.".to_string(), 3),
+/// ]);
+///
+/// let picked = least_sampled(queries, &samples, 2);
+///
+/// assert_eq!(picked[0].text, "Roll a die.");
+/// assert_eq!(picked[1].text, "Call Mary Poppins.");
+/// assert_eq!(picked.len(), 2);
+/// ```
+pub fn least_sampled(
+    mut queries: Vec<Query>,
+    samples: &HashMap<String, i64>,
+    limit: usize,
+) -> Vec<Query> {
+    queries.sort_by_key(|query| samples.get(&query.text).copied().unwrap_or(0));
+    queries.truncate(limit);
+
+    queries
+}
+
+/// Get the id of the collected session the queries argument of a session refers to with [`SESSION_PREFIX`].
+///
+/// Returns `None` if the argument does not refer to a session, and an error if the id is not a number.
+///
+/// # Arguments
+///
+/// * `path`: The queries argument, e.g. `session:42`.
+///
+/// # Examples
+///
+/// ```
+/// # use varys::query::session_reference;
+/// assert_eq!(session_reference("session:42").unwrap(), Some(42));
+/// assert_eq!(session_reference("queries.toml").unwrap(), None);
+/// assert!(session_reference("session:latest").is_err());
+/// ```
+pub fn session_reference<P: AsRef<Path>>(path: P) -> Result<Option<i32>, Error> {
+    path.as_ref()
+        .to_str()
+        .and_then(|path| path.strip_prefix(SESSION_PREFIX))
+        .map(|id| {
+            id.parse()
+                .map_err(|_| Error::InvalidSessionReference(id.to_string()))
+        })
+        .transpose()
+}
+
+/// Get the version of a list of queries asked with this version of varys, e.g. `0.12.1-3f2a9c01d4e5b6a7`.
+///
+/// The version is the varys version followed by the start of a SHA-256 hash of the text, category, locale and
+/// follow-ups of every query. It does not depend on the order of the queries, since sessions shuffle them.
+///
+/// # Arguments
+///
+/// * `queries`: The queries to get the version of.
+///
+/// # Examples
+///
+/// ```
+/// # use varys::query::{dataset_version, Query};
+/// let query = |text: &str| Query {
+///     text: text.to_string(),
+///     category: "test".to_string(),
+///     recording_timeout: None,
+///     follow_ups: Vec::new(),
+///     locale: None,
+///     exemplar: false,
+/// };
+///
+/// assert_eq!(
+///     dataset_version(&[query("Call John Doe."), query("Roll a die.")]),
+///     dataset_version(&[query("Roll a die."), query("Call John Doe.")])
+/// );
+/// assert_ne!(
+///     dataset_version(&[query("Call John Doe."), query("Roll a die.")]),
+///     dataset_version(&[query("Call John Doe.")])
+/// );
+/// assert!(dataset_version(&[]).starts_with(&varys::version()));
+/// ```
+pub fn dataset_version(queries: &[Query]) -> String {
+    let mut lines: Vec<String> = queries
+        .iter()
+        .map(|query| {
+            format!(
+                "{}\t{}\t{}\t{}",
+                query.text,
+                query.category,
+                query.locale.as_deref().unwrap_or_default(),
+                query.follow_ups.join("\t")
+            )
+        })
+        .collect();
+    lines.sort();
+
+    let mut hasher = Sha256::new();
+    for line in lines {
+        hasher.update(line.as_bytes());
+        hasher.update(b"\n");
+    }
+    let hash = format!("{:x}", hasher.finalize());
+
+    format!("{}-{}", crate::version(), &hash[..16])
+}
+
+/// Get the language of a locale, e.g. `de` for `de-DE` or `de_CH`.
+///
+/// # Arguments
+///
+/// * `locale`: The locale, with the language and region separated by `-` or `_`.
+///
+/// # Examples
+///
+/// ```
+/// # use varys::query::language;
+/// assert_eq!(language("de-DE"), "de");
+/// assert_eq!(language("fr_CA"), "fr");
+/// assert_eq!(language("EN"), "en");
+/// ```
+pub fn language(locale: &str) -> String {
+    locale
+        .split(['-', '_'])
+        .next()
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+/// Calculate how similar two texts are based on the words they contain.
+///
+/// Case and punctuation are ignored, and numbers and amounts of money are compared in the same way no matter how they
+/// are written in the locale, see [`normalisation::words`]. The similarity is one minus the word-level edit distance
+/// divided by the number of words in the longer text, so `1` means the texts contain the same words in the same order.
+///
+/// # Arguments
+///
+/// * `text`: The first text.
+/// * `other`: The second text.
+/// * `locale`: The locale both texts are written in, e.g. `de-DE`, or `None` for English.
+///
+/// # Examples
+///
+/// ```
+/// # use varys::query::similarity;
+/// assert_eq!(similarity("Hey Siri. What's the time?", "hey siri whats the time", None), 1.0);
+/// assert_eq!(similarity("Set a timer now.", "Set a reminder now.", None), 0.75);
+/// assert_eq!(similarity("", "Play some music.", None), 0.0);
+/// assert_eq!(similarity("Ist 1.000 € viel?", "Ist 1000 Euro viel?", Some("de-DE")), 1.0);
+/// ```
+pub fn similarity(text: &str, other: &str, locale: Option<&str>) -> f32 {
+    let text = normalisation::words(text, locale);
+    let other = normalisation::words(other, locale);
+    let longest = text.len().max(other.len());
+
+    if longest == 0 {
+        return 1.0;
+    }
+
+    // the edit distances of all prefixes of text to the current prefix of other
+    let mut distances: Vec<usize> = (0..=text.len()).collect();
+    for (j, other_word) in other.iter().enumerate() {
+        let mut diagonal = distances[0];
+        distances[0] = j + 1;
+
+        for (i, word) in text.iter().enumerate() {
+            let substitution = diagonal + usize::from(word != other_word);
+            diagonal = distances[i + 1];
+            distances[i + 1] = substitution.min(distances[i] + 1).min(diagonal + 1);
+        }
+    }
+
+    1.0 - distances[text.len()] as f32 / longest as f32
+}
+
+/// Find the names in texts, like people, places or brands, which speech recognition tends to misspell.
+///
+/// Names are runs of capitalised words that do not start a sentence. Each name is only returned once, in the order it
+/// is first found.
+///
+/// # Arguments
+///
+/// * `texts`: The texts to search, e.g. the queries of a query set.
+///
+/// # Examples
+///
+/// ```
+/// # use varys::query::vocabulary;
+/// let queries = [
+///     "Who played Gandalf besides Ian McKellen?",
+///     "How did Liverpool play? Tell me the score.",
+///     "What is the weather like in New York, Siri?",
+///     "Call Ian McKellen.",
+/// ];
+///
+/// assert_eq!(
+///     vocabulary(queries),
+///     vec!["Gandalf", "Ian McKellen", "Liverpool", "New York", "Siri"]
+/// );
+/// ```
+pub fn vocabulary<'a>(texts: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    let mut add = |name: &mut Vec<&str>| {
+        let joined = name.join(" ");
+        if !joined.is_empty() && !names.contains(&joined) {
+            names.push(joined);
+        }
+        name.clear();
+    };
+
+    for text in texts {
+        let mut name = Vec::new();
+        let mut sentence_start = true;
+
+        for word in text.split_whitespace() {
+            let trimmed = word.trim_matches(|c: char| !c.is_alphanumeric());
+            let capitalised = trimmed.chars().next().is_some_and(char::is_uppercase);
+
+            if capitalised && !sentence_start && trimmed.len() > 1 {
+                name.push(trimmed);
+            } else {
+                add(&mut name);
+            }
+            // punctuation after a word ends the name
+            if trimmed.len() < word.len() && !word.ends_with(trimmed) {
+                add(&mut name);
+            }
+            sentence_start = word.ends_with(['.', '?', '!']);
+        }
+        add(&mut name);
+    }
+
+    names
+}
+
+/// Create a prompt from the names in texts to condition speech recognition on, see [`vocabulary`].
+///
+/// The prompt lists as many names as fit into a prompt.
+///
+/// # Arguments
+///
+/// * `texts`: The texts to search for names, e.g. the queries of a query set.
+///
+/// Returns `None` if there are no names in the texts.
+pub fn vocabulary_prompt<'a>(texts: impl IntoIterator<Item = &'a str>) -> Option<String> {
+    let mut prompt = String::new();
+
+    for name in vocabulary(texts) {
+        if prompt.len() + name.len() + 2 > MAX_VOCABULARY_PROMPT_LENGTH {
+            break;
+        }
+        if !prompt.is_empty() {
+            prompt.push_str(", ");
+        }
+        prompt.push_str(&name);
+    }
+
+    (!prompt.is_empty()).then(|| format!("{prompt}."))
 }
 
 impl Display for Query {
@@ -75,3 +607,153 @@ impl Display for Query {
         write!(f, "{} ({})", self.text, self.category)
     }
 }
+
+/// Expand a query template into one query for every combination of the values of its variables.
+///
+/// Variables are written as `{name}` and every occurrence is expanded on its own, so a variable used twice is combined
+/// with itself.
+///
+/// Returns [`Error::UnknownTemplateVariable`] if a variable has no values and [`Error::InvalidTemplate`] if a `{` is
+/// not closed.
+///
+/// # Arguments
+///
+/// * `template`: The template to expand.
+/// * `variables`: The values of every variable.
+///
+/// # Examples
+///
+/// ```
+/// # use std::collections::HashMap;
+/// # use varys::query::expand_template;
+/// let variables = HashMap::from([
+///     ("contact".to_string(), vec!["John Doe".to_string(), "Mary Poppins".to_string()]),
+///     ("time".to_string(), vec!["today".to_string()]),
+/// ]);
+///
+/// assert_eq!(
+///     expand_template("Call {contact} {time}.", &variables).unwrap(),
+///     vec!["Call John Doe today.", "Call Mary Poppins today."]
+/// );
+/// assert_eq!(
+///     expand_template("Roll a die.", &variables).unwrap(),
+///     vec!["Roll a die."]
+/// );
+/// assert!(expand_template("Call {friend}.", &variables).is_err());
+/// ```
+pub fn expand_template(
+    template: &str,
+    variables: &HashMap<String, Vec<String>>,
+) -> Result<Vec<String>, Error> {
+    let mut expanded = vec![String::new()];
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..]
+            .find('}')
+            .map(|end| start + end)
+            .ok_or_else(|| Error::InvalidTemplate(template.to_string()))?;
+        let name = &rest[start + 1..end];
+        let values = variables
+            .get(name)
+            .filter(|values| !values.is_empty())
+            .ok_or_else(|| Error::UnknownTemplateVariable(name.to_string()))?;
+        let literal = &rest[..start];
+
+        expanded = expanded
+            .iter()
+            .flat_map(|prefix| {
+                values
+                    .iter()
+                    .map(move |value| format!("{prefix}{literal}{value}"))
+            })
+            .collect();
+        rest = &rest[end + 1..];
+    }
+
+    Ok(expanded
+        .into_iter()
+        .map(|prefix| format!("{prefix}{rest}"))
+        .collect())
+}
+
+/// Where the audio of spoken queries comes from.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum QuerySource {
+    /// Queries are synthesised with text-to-speech in the voice of the session.
+    #[default]
+    Synthesised,
+    /// Queries are played from recordings in a directory, e.g. spoken by humans.
+    ///
+    /// Each recording is a `.wav` or `.opus` file named after its query with the wake word, see [`recording_name`].
+    Recorded(PathBuf),
+    /// Queries are typed to the assistant with a command instead of being spoken, see [`TypingCommand`].
+    ///
+    /// Typed queries are asked without the wake word and are stored with the text channel, so their traffic can be
+    /// compared with that of spoken queries.
+    Typed(TypingCommand),
+}
+
+impl QuerySource {
+    /// Find the recording of a query.
+    ///
+    /// Returns `None` if queries are synthesised or there is no recording of the query.
+    ///
+    /// # Arguments
+    ///
+    /// * `text`: The text of the query.
+    pub fn recording(&self, text: &str) -> Option<PathBuf> {
+        let QuerySource::Recorded(dir) = self else {
+            return None;
+        };
+        let name = recording_name(text);
+
+        ["wav", "opus"]
+            .iter()
+            .map(|extension| dir.join(format!("{name}.{extension}")))
+            .find(|path| path.is_file())
+    }
+
+    /// The channel queries are asked through, [`TEXT_CHANNEL`] if they are typed and [`VOICE_CHANNEL`] otherwise.
+    pub fn channel(&self) -> &'static str {
+        match self {
+            QuerySource::Typed(_) => TEXT_CHANNEL,
+            _ => VOICE_CHANNEL,
+        }
+    }
+}
+
+impl Display for QuerySource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QuerySource::Synthesised => write!(f, "synthesised"),
+            QuerySource::Recorded(dir) => write!(f, "recorded in {}", dir.display()),
+            QuerySource::Typed(command) => write!(f, "typed with {command}"),
+        }
+    }
+}
+
+/// Get the name of the file a query is recorded in, without its extension.
+///
+/// The text is lowercased and every run of characters other than letters and digits is replaced by a single `_`.
+///
+/// # Arguments
+///
+/// * `text`: The text of the query.
+///
+/// # Examples
+///
+/// ```
+/// # use varys::query::recording_name;
+/// assert_eq!(
+///     recording_name("Hey Siri. What's the weather like?"),
+///     "hey_siri_what_s_the_weather_like"
+/// );
+/// ```
+pub fn recording_name(text: &str) -> String {
+    text.to_lowercase()
+        .split(|char: char| !char.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("_")
+}
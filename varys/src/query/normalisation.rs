@@ -0,0 +1,262 @@
+use crate::query::language;
+
+/// Currency symbols and the codes they are compared as.
+const CURRENCY_SYMBOLS: &[(char, &str)] = &[
+    ('€', "eur"),
+    ('$', "usd"),
+    ('£', "gbp"),
+    ('¥', "jpy"),
+    ('₹', "inr"),
+    ('₩', "krw"),
+    ('₽', "rub"),
+];
+/// Names of currencies in the languages queries are asked in and the codes they are compared as.
+const CURRENCY_NAMES: &[(&str, &str)] = &[
+    ("eur", "eur"),
+    ("euro", "eur"),
+    ("euros", "eur"),
+    ("usd", "usd"),
+    ("dollar", "usd"),
+    ("dollars", "usd"),
+    ("dólar", "usd"),
+    ("dólares", "usd"),
+    ("dollari", "usd"),
+    ("gbp", "gbp"),
+    ("pound", "gbp"),
+    ("pounds", "gbp"),
+    ("pfund", "gbp"),
+    ("jpy", "jpy"),
+    ("yen", "jpy"),
+    ("chf", "chf"),
+    ("franc", "chf"),
+    ("francs", "chf"),
+    ("franken", "chf"),
+    ("inr", "inr"),
+    ("rupee", "inr"),
+    ("rupees", "inr"),
+];
+/// Spaces that group digits in some locales, e.g. `1 000` in French, and are not written as a regular space.
+const GROUPING_SPACES: &[char] = &['\u{00A0}', '\u{202F}', '\u{2009}'];
+
+/// How a locale writes numbers, e.g. `1.234,5` in German and `1,234.5` in English.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NumberFormat {
+    /// The character that separates the integer part of a number from its fraction.
+    pub decimal_separator: char,
+    /// The characters that group the digits of the integer part by thousands.
+    pub group_separators: &'static [char],
+}
+
+impl NumberFormat {
+    /// Get the number format of a locale, which is the English one for unknown locales or `None`.
+    ///
+    /// # Arguments
+    ///
+    /// * `locale`: The locale, e.g. `de-DE`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys::query::normalisation::NumberFormat;
+    /// assert_eq!(NumberFormat::of(Some("de-DE")).decimal_separator, ',');
+    /// assert_eq!(NumberFormat::of(Some("de-CH")).decimal_separator, '.');
+    /// assert_eq!(NumberFormat::of(None).decimal_separator, '.');
+    /// ```
+    pub fn of(locale: Option<&str>) -> Self {
+        let Some(locale) = locale else {
+            return NumberFormat {
+                decimal_separator: '.',
+                group_separators: &[','],
+            };
+        };
+        let language = language(locale);
+        let swiss = locale
+            .split(['-', '_'])
+            .nth(1)
+            .is_some_and(|region| region.eq_ignore_ascii_case("ch"));
+
+        match language.as_str() {
+            "de" | "it" if swiss => NumberFormat {
+                decimal_separator: '.',
+                group_separators: &['\'', '’'],
+            },
+            "de" | "es" | "it" | "pt" | "nl" | "da" | "tr" | "id" => NumberFormat {
+                decimal_separator: ',',
+                group_separators: &['.'],
+            },
+            "fr" | "sv" | "nb" | "nn" | "no" | "fi" | "pl" | "cs" | "sk" | "ru" | "uk" | "hu" => {
+                NumberFormat {
+                    decimal_separator: ',',
+                    group_separators: &[' '],
+                }
+            }
+            _ => NumberFormat::of(None),
+        }
+    }
+
+    /// Whether the digits are grouped with spaces, so a number may be split into several words.
+    fn groups_with_spaces(&self) -> bool {
+        self.group_separators.contains(&' ')
+    }
+
+    /// Parse a number written in this format into its canonical form, without grouping and with `.` before the
+    /// fraction.
+    ///
+    /// Returns `None` if the word is not a number in this format.
+    ///
+    /// # Arguments
+    ///
+    /// * `word`: The word to parse.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys::query::normalisation::NumberFormat;
+    /// let german = NumberFormat::of(Some("de-DE"));
+    /// let english = NumberFormat::of(Some("en-US"));
+    ///
+    /// assert_eq!(german.parse("1.234,50").as_deref(), Some("1234.50"));
+    /// assert_eq!(english.parse("1,234.50").as_deref(), Some("1234.50"));
+    /// assert_eq!(english.parse("12,34"), None);
+    /// assert_eq!(english.parse("twelve"), None);
+    /// ```
+    pub fn parse(&self, word: &str) -> Option<String> {
+        let (integer, fraction) = match word.rsplit_once(self.decimal_separator) {
+            Some((integer, fraction)) => (integer, Some(fraction)),
+            None => (word, None),
+        };
+        let is_digits = |part: &str| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit());
+
+        let groups: Vec<&str> = integer
+            .split(|c| self.group_separators.contains(&c) || GROUPING_SPACES.contains(&c))
+            .collect();
+        let grouped = match groups.as_slice() {
+            [] => false,
+            [integer] => is_digits(integer),
+            [first, rest @ ..] => {
+                is_digits(first)
+                    && first.len() <= 3
+                    && rest
+                        .iter()
+                        .all(|group| is_digits(group) && group.len() == 3)
+            }
+        };
+        if !grouped || fraction.is_some_and(|fraction| !is_digits(fraction)) {
+            return None;
+        }
+
+        let integer = groups.concat();
+        Some(match fraction {
+            Some(fraction) => format!("{integer}.{fraction}"),
+            None => integer,
+        })
+    }
+}
+
+/// Split a text into the words it is compared by, ignoring case and punctuation.
+///
+/// Numbers are written in their canonical form, see [`NumberFormat::parse`], and currency symbols and names are
+/// replaced by their code after the amount, so the same amount is compared as the same words no matter how the query
+/// or the speech recognition wrote it in the locale.
+///
+/// # Arguments
+///
+/// * `text`: The text to split.
+/// * `locale`: The locale the text is written in, e.g. `de-DE`, or `None` for English.
+///
+/// # Examples
+///
+/// ```
+/// # use varys::query::normalisation::words;
+/// assert_eq!(words("What's 3,50 €?", Some("de-DE")), vec!["whats", "3.50", "eur"]);
+/// assert_eq!(words("what's €3.50", None), vec!["whats", "3.50", "eur"]);
+/// assert_eq!(words("Ça coûte 1 000 euros.", Some("fr-FR")), vec!["ça", "coûte", "1000", "eur"]);
+/// ```
+pub fn words(text: &str, locale: Option<&str>) -> Vec<String> {
+    let format = NumberFormat::of(locale);
+    let mut spaced = String::with_capacity(text.len());
+    for character in text.chars() {
+        match CURRENCY_SYMBOLS
+            .iter()
+            .find(|(symbol, _)| *symbol == character)
+        {
+            Some((_, code)) => {
+                spaced.push(' ');
+                spaced.push_str(code);
+                spaced.push(' ');
+            }
+            None => spaced.push(character),
+        }
+    }
+
+    // numbers grouped with spaces are split into several words, which are joined again
+    let mut tokens: Vec<String> = Vec::new();
+    for token in spaced
+        .split(|c: char| c.is_whitespace() && !GROUPING_SPACES.contains(&c))
+        .map(|token| token.trim_matches(|c: char| !c.is_alphanumeric()))
+        .filter(|token| !token.is_empty())
+    {
+        match tokens.last_mut() {
+            Some(previous)
+                if format.groups_with_spaces()
+                    && continues_number(previous, token, format.decimal_separator) =>
+            {
+                previous.push(' ');
+                previous.push_str(token);
+            }
+            _ => tokens.push(token.to_string()),
+        }
+    }
+
+    let mut words: Vec<String> = tokens
+        .iter()
+        .filter_map(|token| {
+            let word = format.parse(token).unwrap_or_else(|| {
+                token
+                    .chars()
+                    .filter(|c| c.is_alphanumeric())
+                    .flat_map(char::to_lowercase)
+                    .collect()
+            });
+
+            match CURRENCY_NAMES.iter().find(|(name, _)| *name == word) {
+                Some((_, code)) => Some(code.to_string()),
+                None => (!word.is_empty()).then_some(word),
+            }
+        })
+        .collect();
+
+    // amounts are compared with the currency after them
+    let is_code = |word: &str| CURRENCY_NAMES.iter().any(|(_, code)| *code == word);
+    let mut index = 1;
+    while index < words.len() {
+        if is_code(&words[index - 1]) && words[index].starts_with(|c: char| c.is_ascii_digit()) {
+            words.swap(index - 1, index);
+            index += 1;
+        }
+        index += 1;
+    }
+
+    words
+}
+
+/// Whether a word continues a number grouped with spaces, e.g. `000` after `1`.
+///
+/// # Arguments
+///
+/// * `previous`: The number so far.
+/// * `word`: The following word.
+/// * `decimal_separator`: The character that separates the integer part of a number from its fraction.
+fn continues_number(previous: &str, word: &str, decimal_separator: char) -> bool {
+    let integer = |text: &str| !text.is_empty() && text.chars().all(|c| c.is_ascii_digit());
+    let (group, fraction) = match word.split_once(decimal_separator) {
+        Some((group, fraction)) => (group, Some(fraction)),
+        None => (word, None),
+    };
+
+    !previous.contains(decimal_separator)
+        && previous.split(' ').all(integer)
+        && group.len() == 3
+        && integer(group)
+        && fraction.is_none_or(integer)
+}
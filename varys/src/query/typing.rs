@@ -0,0 +1,97 @@
+use std::fmt::{Display, Formatter};
+use std::process::Command;
+use std::str::FromStr;
+use std::time::Instant;
+
+use log::info;
+
+use crate::error::Error;
+
+/// The channel of interactions whose queries are spoken to the assistant.
+pub const VOICE_CHANNEL: &str = "voice";
+
+/// The channel of interactions whose queries are typed to the assistant.
+pub const TEXT_CHANNEL: &str = "text";
+
+/// A command that types a query to the assistant instead of speaking it, e.g. automating *Type to Siri* on macOS.
+///
+/// The query is passed to the command as its last argument. The command should return once the query was submitted,
+/// since the response is recorded from then on. An example for Siri on macOS is `data/type-to-siri.applescript`, run
+/// with `osascript data/type-to-siri.applescript`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TypingCommand {
+    /// The program to run.
+    pub program: String,
+    /// The arguments passed to the program before the query.
+    pub arguments: Vec<String>,
+}
+
+impl TypingCommand {
+    /// Type a query to the assistant by running the command and return how long it took in milliseconds.
+    ///
+    /// # Arguments
+    ///
+    /// * `text`: The text of the query.
+    pub fn type_query(&self, text: &str) -> Result<i32, Error> {
+        info!("Typing \"{text}\" with {self}");
+
+        let start = Instant::now();
+        let output = Command::new(&self.program)
+            .args(&self.arguments)
+            .arg(text)
+            .output()
+            .map_err(|err| Error::TypingFailed(format!("{self}: {err}")))?;
+
+        if !output.status.success() {
+            return Err(Error::TypingFailed(format!(
+                "{self} exited with {}: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        Ok(start.elapsed().as_millis() as i32)
+    }
+}
+
+impl FromStr for TypingCommand {
+    type Err = String;
+
+    /// Parse a typing command from the program and its arguments separated by whitespace.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys::query::typing::TypingCommand;
+    /// assert_eq!(
+    ///     "osascript data/type-to-siri.applescript".parse(),
+    ///     Ok(TypingCommand {
+    ///         program: "osascript".to_string(),
+    ///         arguments: vec!["data/type-to-siri.applescript".to_string()],
+    ///     })
+    /// );
+    /// assert!(" ".parse::<TypingCommand>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split_whitespace().map(str::to_string);
+        let program = parts
+            .next()
+            .ok_or_else(|| "Expected a command to type queries with".to_string())?;
+
+        Ok(TypingCommand {
+            program,
+            arguments: parts.collect(),
+        })
+    }
+}
+
+impl Display for TypingCommand {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.program)?;
+        for argument in &self.arguments {
+            write!(f, " {argument}")?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+use log::warn;
+
+use crate::error::Error;
+
+/// The maximum length of a query in characters, without the wake word.
+///
+/// Longer queries take so long to say that assistants tend to interrupt them or stop listening.
+pub const MAX_QUERY_LENGTH: usize = 150;
+
+/// A problem with a query of a query list.
+#[derive(Clone, Debug, PartialEq)]
+pub enum QueryIssue {
+    /// The query is spelled differently than an earlier one, e.g. with a typographic apostrophe, so the same query
+    /// would be collected under two labels.
+    Duplicate {
+        /// The query.
+        text: String,
+        /// The earlier query it duplicates.
+        first: String,
+    },
+    /// The query was already listed with the same text.
+    ///
+    /// Query lists can repeat queries on purpose to ask them more often, see [`ValidationReport::allow_repeats`].
+    Repeated {
+        /// The query.
+        text: String,
+    },
+    /// The query is longer than [`MAX_QUERY_LENGTH`].
+    TooLong {
+        /// The query.
+        text: String,
+        /// The length of the query in characters.
+        length: usize,
+    },
+    /// The query starts with the wake word of the assistant, which is added again when the query is asked.
+    WakeWord {
+        /// The query.
+        text: String,
+        /// The wake word the query starts with.
+        wake_word: String,
+    },
+}
+
+impl Display for QueryIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QueryIssue::Duplicate { text, first } => {
+                write!(f, "\"{text}\" is a duplicate of \"{first}\"")
+            }
+            QueryIssue::Repeated { text } => write!(f, "\"{text}\" is listed more than once"),
+            QueryIssue::TooLong { text, length } => write!(
+                f,
+                "\"{text}\" has {length} characters, more than {MAX_QUERY_LENGTH}"
+            ),
+            QueryIssue::WakeWord { text, wake_word } => {
+                write!(f, "\"{text}\" already starts with {wake_word}")
+            }
+        }
+    }
+}
+
+/// The problems found in a query list.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValidationReport {
+    /// The number of queries that were validated.
+    pub queries: usize,
+    /// The problems found, in the order of the queries.
+    pub issues: Vec<QueryIssue>,
+}
+
+impl ValidationReport {
+    /// Whether no problems were found.
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Ignore queries that are listed more than once with the same text, if the query list repeats them on purpose.
+    ///
+    /// # Arguments
+    ///
+    /// * `allow`: Whether queries are repeated on purpose, otherwise the report is returned unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys::query::validation::validate;
+    /// let queries = ["Roll a die", "Roll a die"];
+    ///
+    /// assert!(!validate(queries, None).allow_repeats(false).is_valid());
+    /// assert!(validate(queries, None).allow_repeats(true).is_valid());
+    /// ```
+    pub fn allow_repeats(mut self, allow: bool) -> Self {
+        if allow {
+            self.issues
+                .retain(|issue| !matches!(issue, QueryIssue::Repeated { .. }));
+        }
+
+        self
+    }
+
+    /// Log every problem and return [`Error::InvalidQueries`] if any were found, so no data is collected with them.
+    pub fn check(self) -> Result<(), Error> {
+        for issue in &self.issues {
+            warn!("{issue}");
+        }
+
+        if self.is_valid() {
+            Ok(())
+        } else {
+            Err(Error::InvalidQueries(self))
+        }
+    }
+}
+
+impl Display for ValidationReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "found {} problems in {} queries",
+            self.issues.len(),
+            self.queries
+        )
+    }
+}
+
+/// Find duplicates, overly long queries and queries that start with the wake word of the assistant in a query list.
+///
+/// Queries are duplicates if they are spelled differently but the same after [`normalise`], e.g. *"What’s the
+/// weather?"* and *"What's the weather"*. Queries listed again with the same text are reported as repeated.
+///
+/// # Arguments
+///
+/// * `queries`: The texts of the queries.
+/// * `wake_word`: The wake word of the assistant the queries are asked to, or `None` to not check for it.
+///
+/// # Examples
+///
+/// ```
+/// # use varys::query::validation::{validate, QueryIssue};
+/// let report = validate(["What’s 9 plus 53?", "What's 9 plus 53?"], Some("Hey Siri"));
+///
+/// assert_eq!(
+///     report.issues,
+///     vec![QueryIssue::Duplicate {
+///         text: "What's 9 plus 53?".to_string(),
+///         first: "What’s 9 plus 53?".to_string(),
+///     }]
+/// );
+/// assert!(!validate(["Alexa. What is 2 to the power of 17?"], Some("Alexa")).is_valid());
+/// assert_eq!(
+///     validate(["Roll a die", "Roll a die"], None).issues,
+///     vec![QueryIssue::Repeated {
+///         text: "Roll a die".to_string()
+///     }]
+/// );
+/// ```
+pub fn validate<'a>(
+    queries: impl IntoIterator<Item = &'a str>,
+    wake_word: Option<&str>,
+) -> ValidationReport {
+    let mut seen: HashMap<String, &str> = HashMap::new();
+    let mut issues = Vec::new();
+    let mut count = 0;
+
+    for text in queries {
+        count += 1;
+
+        match seen.get(&normalise(text)) {
+            Some(first) if *first != text => issues.push(QueryIssue::Duplicate {
+                text: text.to_string(),
+                first: first.to_string(),
+            }),
+            Some(_) => issues.push(QueryIssue::Repeated {
+                text: text.to_string(),
+            }),
+            None => {
+                seen.insert(normalise(text), text);
+            }
+        }
+
+        let length = text.chars().count();
+        if length > MAX_QUERY_LENGTH {
+            issues.push(QueryIssue::TooLong {
+                text: text.to_string(),
+                length,
+            });
+        }
+
+        if let Some(wake_word) =
+            wake_word.filter(|wake_word| starts_with_wake_word(text, wake_word))
+        {
+            issues.push(QueryIssue::WakeWord {
+                text: text.to_string(),
+                wake_word: wake_word.to_string(),
+            });
+        }
+    }
+
+    ValidationReport {
+        queries: count,
+        issues,
+    }
+}
+
+/// Check whether a query starts with a wake word, ignoring case and what separates it from the rest of the query.
+///
+/// # Arguments
+///
+/// * `text`: The text of the query.
+/// * `wake_word`: The wake word.
+fn starts_with_wake_word(text: &str, wake_word: &str) -> bool {
+    let text = normalise(text);
+    let wake_word = normalise(wake_word);
+
+    text.strip_prefix(&wake_word)
+        .is_some_and(|rest| rest.is_empty() || !rest.starts_with(char::is_alphanumeric))
+}
+
+/// Normalise the text of a query to compare it with others, ignoring case, typographic apostrophes and quotation marks,
+/// repeated whitespace and punctuation at the end.
+///
+/// # Arguments
+///
+/// * `text`: The text of the query.
+///
+/// # Examples
+///
+/// ```
+/// # use varys::query::validation::normalise;
+/// assert_eq!(normalise("What’s  the weather?"), "what's the weather");
+/// assert_eq!(normalise("What's the weather"), "what's the weather");
+/// assert_eq!(normalise("Call “Mom”."), "call \"mom\"");
+/// ```
+pub fn normalise(text: &str) -> String {
+    let text: String = text
+        .chars()
+        .map(|character| match character {
+            '\u{2018}' | '\u{2019}' | '\u{02BC}' | '`' | '\u{00B4}' => '\'',
+            '\u{201C}' | '\u{201D}' => '"',
+            character => character,
+        })
+        .collect();
+
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_end_matches(['.', '?', '!'])
+        .to_lowercase()
+}
@@ -0,0 +1,136 @@
+use std::fs;
+use std::path::Path;
+
+use log::{debug, info};
+use regex::Regex;
+use toml::{Table, Value};
+
+use crate::error::Error;
+
+/// Matches phone numbers with at least eight digits, optionally separated by spaces, dots, dashes or brackets.
+const PHONE_NUMBER_PATTERN: &str = r"\+?\d(?:[\s().-]?\d){7,}";
+/// Matches email addresses.
+const EMAIL_PATTERN: &str = r"[\w.+-]+@[\w-]+(?:\.[\w-]+)+";
+/// Matches street addresses like `221B Baker Street`.
+const ADDRESS_PATTERN: &str = r"(?i)\b\d{1,5}[a-z]?\s+(?:[a-z]+\s+){1,3}(?:street|st|avenue|ave|road|rd|boulevard|blvd|lane|ln|drive|dr|court|ct|way|place|pl|square|sq)\b\.?";
+
+/// Removes personal information like names, phone numbers and addresses from transcripts.
+///
+/// Each kind of information is matched by a regular expression and replaced with a placeholder, e.g. `[phone number]`.
+#[derive(Debug, Default)]
+pub struct Redactor {
+    patterns: Vec<(Regex, String)>,
+}
+
+impl Redactor {
+    /// Read what to redact from a TOML file.
+    ///
+    /// The TOML file should have the following format, where every key is optional:
+    ///
+    /// ```toml
+    /// phone_numbers = true
+    /// emails = true
+    /// addresses = true
+    /// names = ["Alice", "Bob Miller"]
+    ///
+    /// [patterns]
+    /// '\bPIN \d+' = "[PIN]"
+    /// ```
+    ///
+    /// The `patterns` table maps additional regular expressions to the text they are replaced with.
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: The path to the TOML file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys::redact::Redactor;
+    /// let redactor = Redactor::read_toml("../data/test_redaction.toml").unwrap();
+    ///
+    /// assert_eq!(
+    ///     redactor.redact("Calling Bob Miller at +41 79 123 45 67, your PIN 1234 is ready."),
+    ///     "Calling [name] at [phone number], your [PIN] is ready."
+    /// );
+    /// assert_eq!(
+    ///     redactor.redact("Alice lives at 221B Baker Street. Write to alice@example.com."),
+    ///     "[name] lives at [address] Write to [email]."
+    /// );
+    /// ```
+    pub fn read_toml<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        info!("Reading redactions from {}", path.as_ref().display());
+
+        let toml = fs::read_to_string(path)?.parse::<Table>()?;
+        let enabled = |key: &str| toml.get(key).and_then(Value::as_bool).unwrap_or(false);
+        let mut redactor = Redactor::default();
+
+        if enabled("phone_numbers") {
+            redactor = redactor.with_pattern(PHONE_NUMBER_PATTERN, "[phone number]")?;
+        }
+        if enabled("emails") {
+            redactor = redactor.with_pattern(EMAIL_PATTERN, "[email]")?;
+        }
+        if enabled("addresses") {
+            redactor = redactor.with_pattern(ADDRESS_PATTERN, "[address]")?;
+        }
+        if let Some(names) = toml.get("names").and_then(Value::as_array) {
+            redactor = redactor.with_names(names.iter().filter_map(Value::as_str))?;
+        }
+        if let Some(patterns) = toml.get("patterns").and_then(Value::as_table) {
+            for (pattern, replacement) in patterns {
+                redactor =
+                    redactor.with_pattern(pattern, replacement.as_str().unwrap_or("[redacted]"))?;
+            }
+        }
+
+        debug!("Redacting {} patterns", redactor.patterns.len());
+
+        Ok(redactor)
+    }
+
+    /// Replace everything that matches a regular expression.
+    ///
+    /// Returns an error if the regular expression is invalid.
+    ///
+    /// # Arguments
+    ///
+    /// * `pattern`: The regular expression to match.
+    /// * `replacement`: The text to replace matches with.
+    pub fn with_pattern(mut self, pattern: &str, replacement: &str) -> Result<Self, Error> {
+        let regex =
+            Regex::new(pattern).map_err(|_| Error::InvalidRedactionPattern(pattern.to_string()))?;
+        self.patterns.push((regex, replacement.to_string()));
+
+        Ok(self)
+    }
+
+    /// Replace names with `[name]`, ignoring case.
+    ///
+    /// # Arguments
+    ///
+    /// * `names`: The names to replace.
+    pub fn with_names<'a>(self, names: impl IntoIterator<Item = &'a str>) -> Result<Self, Error> {
+        let names: Vec<String> = names.into_iter().map(regex::escape).collect();
+        if names.is_empty() {
+            return Ok(self);
+        }
+
+        self.with_pattern(&format!(r"(?i)\b(?:{})\b", names.join("|")), "[name]")
+    }
+
+    /// Remove all configured information from a text.
+    ///
+    /// # Arguments
+    ///
+    /// * `text`: The text to redact.
+    pub fn redact(&self, text: &str) -> String {
+        self.patterns
+            .iter()
+            .fold(text.to_string(), |text, (regex, replacement)| {
+                regex
+                    .replace_all(&text, regex::NoExpand(replacement))
+                    .to_string()
+            })
+    }
+}
@@ -0,0 +1,324 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use varys_database::database::interaction::Interaction;
+
+pub mod truncation;
+
+/// Phrases with which assistants report that they failed to handle a query, which they start their response with.
+const ERROR_PHRASES: &[&str] = &[
+    "something went wrong",
+    "it looks like something went wrong",
+    "i'm having trouble",
+    "i'm having a problem",
+    "there was a problem",
+    "there's a problem",
+    "please try again later",
+    "i'm not able to connect",
+    "i can't connect",
+    "i cannot connect",
+    "i can't help with that",
+    "i can't do that",
+    "i don't know that",
+    "i don't know about that",
+    "i'm not sure",
+];
+/// Interjections that may precede the phrases in [`ERROR_PHRASES`].
+const LEADING_INTERJECTIONS: &[&str] = &["i'm sorry", "sorry", "hmm", "oops", "uh-oh", "uh oh"];
+/// Phrases with which assistants ask the user to clarify or repeat a query.
+const CLARIFICATION_PHRASES: &[&str] = &[
+    "did you mean",
+    "which one",
+    "what would you like",
+    "who would you like",
+    "who do you want",
+    "what do you want",
+    "where would you like",
+    "can you repeat",
+    "could you repeat",
+    "say that again",
+    "can you say",
+];
+/// Phrases with which assistants offer web results instead of an answer.
+const WEB_RESULTS_PHRASES: &[&str] = &[
+    "on the web",
+    "from the web",
+    "web results",
+    "here's what i found",
+    "i found this",
+    "i found some",
+    "on your iphone",
+    "on your phone",
+    "in the google home app",
+    "in the alexa app",
+];
+
+/// Phrases with which assistants report that they did not hear or understand the query at all.
+const NO_RESPONSE_PHRASES: &[&str] = &[
+    "i didn't get that",
+    "i didn't catch that",
+    "i didn't quite get that",
+    "i didn't quite catch that",
+    "sorry, i missed that",
+    "i didn't hear",
+    "i didn't understand",
+];
+
+/// The kind of a response of a voice assistant, classified from its transcript.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ResponseType {
+    /// The assistant answered the query or did what it was asked.
+    Answered,
+    /// The assistant asked the user to clarify or repeat the query.
+    Clarification,
+    /// The assistant reported that it could not handle the query.
+    Error,
+    /// The assistant offered web results instead of answering.
+    WebResults,
+    /// Nothing was said in response, or only that the query was not heard or understood.
+    NoResponse,
+}
+
+impl ResponseType {
+    /// All response types, in the order they are reported in.
+    pub const ALL: [ResponseType; 5] = [
+        ResponseType::Answered,
+        ResponseType::Clarification,
+        ResponseType::Error,
+        ResponseType::WebResults,
+        ResponseType::NoResponse,
+    ];
+
+    /// Classify a response from its transcript.
+    ///
+    /// The transcript is normalised to lower case and checked for phrases typical of each type. Responses saying that
+    /// the query was not heard or understood take precedence over errors, which take precedence over web results,
+    /// which take precedence over clarification requests. Responses that only consist of a question are clarification
+    /// requests as well.
+    ///
+    /// Error phrases only count at the start of the response, optionally after an interjection like "sorry", so an
+    /// answer that merely hedges, e.g. "It's probably 18 degrees, but I'm not sure", is not an error.
+    ///
+    /// # Arguments
+    ///
+    /// * `transcript`: The transcript of the response.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys::response::ResponseType;
+    /// assert_eq!(
+    ///     ResponseType::classify("It's currently 18 degrees and sunny."),
+    ///     ResponseType::Answered
+    /// );
+    /// assert_eq!(
+    ///     ResponseType::classify("Which Springfield did you mean?"),
+    ///     ResponseType::Clarification
+    /// );
+    /// assert_eq!(
+    ///     ResponseType::classify("Sorry, something went wrong. Please try again."),
+    ///     ResponseType::Error
+    /// );
+    /// assert_eq!(
+    ///     ResponseType::classify("Hmm, I'm not sure about that."),
+    ///     ResponseType::Error
+    /// );
+    /// assert_eq!(
+    ///     ResponseType::classify("It's probably 18 degrees, but I'm not sure."),
+    ///     ResponseType::Answered
+    /// );
+    /// assert_eq!(
+    ///     ResponseType::classify("Here’s what I found on the web for “Rust lifetimes”."),
+    ///     ResponseType::WebResults
+    /// );
+    /// assert_eq!(
+    ///     ResponseType::classify("Sorry, I didn't get that."),
+    ///     ResponseType::NoResponse
+    /// );
+    /// assert_eq!(ResponseType::classify(" "), ResponseType::NoResponse);
+    /// ```
+    pub fn classify(transcript: &str) -> Self {
+        let text = transcript.trim().to_lowercase().replace('’', "'");
+        let contains_any = |phrases: &[&str]| phrases.iter().any(|phrase| text.contains(phrase));
+
+        if text.chars().all(|c| !c.is_alphanumeric()) || contains_any(NO_RESPONSE_PHRASES) {
+            ResponseType::NoResponse
+        } else if starts_with_error(&text) {
+            ResponseType::Error
+        } else if contains_any(WEB_RESULTS_PHRASES) {
+            ResponseType::WebResults
+        } else if contains_any(CLARIFICATION_PHRASES)
+            || (text.ends_with('?') && text.matches(['.', '!', '?']).count() == 1)
+        {
+            ResponseType::Clarification
+        } else {
+            ResponseType::Answered
+        }
+    }
+}
+
+impl Display for ResponseType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                ResponseType::Answered => "answered",
+                ResponseType::Clarification => "clarification",
+                ResponseType::Error => "error",
+                ResponseType::WebResults => "web_results",
+                ResponseType::NoResponse => "no_response",
+            }
+        )
+    }
+}
+
+impl FromStr for ResponseType {
+    type Err = String;
+
+    /// Parse a response type as it is stored in the database.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys::response::ResponseType;
+    /// assert_eq!("web_results".parse(), Ok(ResponseType::WebResults));
+    /// assert_eq!(ResponseType::Clarification.to_string().parse(), Ok(ResponseType::Clarification));
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ResponseType::ALL
+            .into_iter()
+            .find(|response_type| response_type.to_string() == s)
+            .ok_or_else(|| format!("Unknown response type {s}"))
+    }
+}
+
+/// Whether an interaction is a usable sample, stored as its `status`.
+///
+/// Interactions the assistant did not respond to do not contain the traffic of a response, so analysis excludes them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum InteractionStatus {
+    /// The assistant responded to the query.
+    Valid,
+    /// The assistant did not respond, or only said that it did not hear or understand the query.
+    FailedNoResponse,
+    /// The interaction was stopped before it was done, e.g. because varys was stopped with Ctrl-C.
+    Aborted,
+    /// The interaction took longer than its time budget and was stopped.
+    TimedOut,
+}
+
+impl InteractionStatus {
+    /// All interaction statuses.
+    pub const ALL: [InteractionStatus; 4] = [
+        InteractionStatus::Valid,
+        InteractionStatus::FailedNoResponse,
+        InteractionStatus::Aborted,
+        InteractionStatus::TimedOut,
+    ];
+
+    /// Validate an interaction from the transcript of its response.
+    ///
+    /// The interaction failed if nothing was said or the response is one of the phrases with which assistants report
+    /// that they did not get the query.
+    ///
+    /// # Arguments
+    ///
+    /// * `transcript`: The transcript of the response.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys::response::InteractionStatus;
+    /// assert_eq!(
+    ///     InteractionStatus::from_transcript("It's 18 degrees."),
+    ///     InteractionStatus::Valid
+    /// );
+    /// assert_eq!(
+    ///     InteractionStatus::from_transcript("Sorry, I didn't get that."),
+    ///     InteractionStatus::FailedNoResponse
+    /// );
+    /// assert_eq!(
+    ///     InteractionStatus::from_transcript(""),
+    ///     InteractionStatus::FailedNoResponse
+    /// );
+    /// ```
+    pub fn from_transcript(transcript: &str) -> Self {
+        if ResponseType::classify(transcript) == ResponseType::NoResponse {
+            InteractionStatus::FailedNoResponse
+        } else {
+            InteractionStatus::Valid
+        }
+    }
+
+    /// Mark an interaction as failed if the transcript of its response shows that the assistant did not respond.
+    ///
+    /// Interactions with a muted microphone are not expected to get a response and are left as they are, as are those
+    /// that were already marked as failed.
+    ///
+    /// # Arguments
+    ///
+    /// * `interaction`: The interaction to validate.
+    /// * `transcript`: The transcript of its response.
+    pub fn validate(interaction: &mut Interaction, transcript: &str) {
+        let status = InteractionStatus::from_transcript(transcript);
+
+        if status != InteractionStatus::Valid && !interaction.mic_muted {
+            interaction.status = status.to_string();
+        }
+    }
+}
+
+impl Display for InteractionStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                InteractionStatus::Valid => "valid",
+                InteractionStatus::FailedNoResponse => "failed_no_response",
+                InteractionStatus::Aborted => "aborted",
+                InteractionStatus::TimedOut => "timed_out",
+            }
+        )
+    }
+}
+
+impl FromStr for InteractionStatus {
+    type Err = String;
+
+    /// Parse an interaction status as it is stored in the database.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use varys::response::InteractionStatus;
+    /// assert_eq!("failed_no_response".parse(), Ok(InteractionStatus::FailedNoResponse));
+    /// assert!("failed".parse::<InteractionStatus>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        InteractionStatus::ALL
+            .into_iter()
+            .find(|status| status.to_string() == s)
+            .ok_or_else(|| format!("Unknown interaction status {s}"))
+    }
+}
+
+/// Whether a normalised transcript starts with one of the [`ERROR_PHRASES`], optionally after some
+/// [`LEADING_INTERJECTIONS`].
+///
+/// # Arguments
+///
+/// * `text`: The transcript in lower case.
+fn starts_with_error(text: &str) -> bool {
+    let mut text = text.trim_start();
+    while let Some(rest) = LEADING_INTERJECTIONS
+        .iter()
+        .find_map(|interjection| text.strip_prefix(interjection))
+        .filter(|rest| rest.starts_with([',', '.', '!', ' ']))
+    {
+        text = rest.trim_start_matches([',', '.', '!', ' ']);
+    }
+
+    ERROR_PHRASES.iter().any(|phrase| text.starts_with(phrase))
+}
@@ -0,0 +1,103 @@
+use std::fmt::{Display, Formatter};
+
+use chrono::{DateTime, Utc};
+use varys_database::database::outlier::{
+    TRAFFIC_AFTER_RESPONSE_METRIC, TRUNCATED_AT_TIMEOUT_METRIC,
+};
+
+/// The longest time in milliseconds between two large packets of the traffic burst of a response.
+pub const BURST_GAP_MS: i64 = 500;
+/// How long in milliseconds the traffic burst of a response may continue after the recorded response ended before the
+/// response is considered truncated.
+///
+/// Assistants usually receive their whole response before they finish saying it, so the burst rarely outlasts it.
+pub const TRAFFIC_TOLERANCE_MS: i64 = 1000;
+
+/// A sign that the recording of a response was cut off before the assistant finished responding.
+///
+/// Truncated responses are flagged as outliers, so they are excluded from datasets and can be collected again.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Truncation {
+    /// The assistant was still talking when the recording timeout stopped the recording.
+    AtTimeout {
+        /// The duration of the recorded response in milliseconds.
+        response_duration_ms: i32,
+    },
+    /// The traffic burst of the response continued after the recorded response ended.
+    TrafficAfterResponse {
+        /// How long the burst continued in milliseconds.
+        overhang_ms: i64,
+    },
+}
+
+impl Truncation {
+    /// Detect whether the traffic burst of a response continued for longer than [`TRAFFIC_TOLERANCE_MS`] after the
+    /// recorded response ended.
+    ///
+    /// # Arguments
+    ///
+    /// * `response_ended`: When the recorded response ended.
+    /// * `burst_ended`: When the traffic burst of the response ended, or `None` if there was no burst.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use chrono::{Duration, Utc};
+    /// # use varys::response::truncation::Truncation;
+    /// let response_ended = Utc::now();
+    ///
+    /// assert_eq!(
+    ///     Truncation::from_traffic(response_ended, Some(response_ended + Duration::seconds(3))),
+    ///     Some(Truncation::TrafficAfterResponse { overhang_ms: 3000 })
+    /// );
+    /// assert_eq!(
+    ///     Truncation::from_traffic(response_ended, Some(response_ended - Duration::seconds(3))),
+    ///     None
+    /// );
+    /// assert_eq!(Truncation::from_traffic(response_ended, None), None);
+    /// ```
+    pub fn from_traffic(
+        response_ended: DateTime<Utc>,
+        burst_ended: Option<DateTime<Utc>>,
+    ) -> Option<Self> {
+        let overhang_ms = (burst_ended? - response_ended).num_milliseconds();
+
+        (overhang_ms > TRAFFIC_TOLERANCE_MS)
+            .then_some(Truncation::TrafficAfterResponse { overhang_ms })
+    }
+
+    /// The metric the interaction is flagged as an outlier in.
+    pub fn metric(&self) -> &'static str {
+        match self {
+            Truncation::AtTimeout { .. } => TRUNCATED_AT_TIMEOUT_METRIC,
+            Truncation::TrafficAfterResponse { .. } => TRAFFIC_AFTER_RESPONSE_METRIC,
+        }
+    }
+
+    /// The value of the metric.
+    pub fn value(&self) -> f64 {
+        match self {
+            Truncation::AtTimeout {
+                response_duration_ms,
+            } => *response_duration_ms as f64,
+            Truncation::TrafficAfterResponse { overhang_ms } => *overhang_ms as f64,
+        }
+    }
+}
+
+impl Display for Truncation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Truncation::AtTimeout {
+                response_duration_ms,
+            } => write!(
+                f,
+                "The response was still going on when the recording timeout stopped it after {response_duration_ms}ms"
+            ),
+            Truncation::TrafficAfterResponse { overhang_ms } => write!(
+                f,
+                "The traffic of the response continued {overhang_ms}ms after the recorded response ended"
+            ),
+        }
+    }
+}
@@ -0,0 +1,284 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use chrono::{DateTime, Local, Utc};
+#[cfg(feature = "collection")]
+use log::{error, info};
+#[cfg(feature = "collection")]
+use std::path::PathBuf;
+#[cfg(feature = "collection")]
+use std::time::Duration;
+#[cfg(all(feature = "collection", feature = "transcription"))]
+use varys_audio::stt::transcriber::Transcriber;
+#[cfg(all(feature = "collection", feature = "transcription"))]
+use varys_audio::stt::Recogniser;
+#[cfg(feature = "collection")]
+use varys_database::connection::DatabaseConnection;
+#[cfg(feature = "collection")]
+use varys_database::database;
+#[cfg(feature = "collection")]
+use varys_database::database::interaction::Interaction;
+#[cfg(feature = "collection")]
+use varys_database::database::schedule::{Schedule, ScheduleRun};
+
+#[cfg(feature = "collection")]
+use crate::assistant;
+#[cfg(feature = "collection")]
+use crate::assistant::interactor::Interactor;
+#[cfg(feature = "collection")]
+use crate::assistant::shutdown::Shutdown;
+use crate::error::Error;
+#[cfg(feature = "collection")]
+use crate::query::{self, validation, Query};
+#[cfg(feature = "collection")]
+use crate::response::InteractionStatus;
+
+/// How long the scheduler waits at most before checking the schedules again, so new and removed schedules are noticed.
+#[cfg(feature = "collection")]
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A cron expression of the times a schedule runs at, evaluated in local time.
+///
+/// Both the standard five fields (minute, hour, day of month, month, day of week) and an additional leading seconds
+/// field are supported. Days of the week are best given as names, e.g. `Mon-Fri`.
+#[derive(Clone, Debug)]
+pub struct Expression {
+    text: String,
+    schedule: cron::Schedule,
+}
+
+impl Expression {
+    /// The first time the expression matches after a given time.
+    ///
+    /// Returns `None` if the expression never matches again.
+    ///
+    /// # Arguments
+    ///
+    /// * `after`: The time after which to find a match.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use chrono::{Duration, Timelike, Utc};
+    /// # use varys::schedule::Expression;
+    /// let every_six_hours: Expression = "0 */6 * * *".parse().unwrap();
+    /// let now = Utc::now();
+    /// let next_run = every_six_hours.next_after(now).unwrap();
+    ///
+    /// assert!(next_run > now && next_run <= now + Duration::hours(6));
+    /// assert_eq!(next_run.minute(), 0);
+    /// assert!("every six hours".parse::<Expression>().is_err());
+    /// ```
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.schedule
+            .after(&after.with_timezone(&Local))
+            .next()
+            .map(|time| time.with_timezone(&Utc))
+    }
+}
+
+impl FromStr for Expression {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let text = text.trim();
+        let with_seconds = if text.split_whitespace().count() == 5 {
+            format!("0 {text}")
+        } else {
+            text.to_string()
+        };
+
+        Ok(Expression {
+            text: text.to_string(),
+            schedule: cron::Schedule::from_str(&with_seconds)
+                .map_err(|error| Error::InvalidScheduleExpression(format!("{text} ({error})")))?,
+        })
+    }
+}
+
+impl Display for Expression {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+/// Runs the sessions of all schedules stored in the database when they are due.
+///
+/// Only one session runs at a time. If a schedule is due while another session runs or while the scheduler is not
+/// running, it runs once as soon as possible and then continues at its next time.
+#[cfg(feature = "collection")]
+pub struct Scheduler {
+    interface: String,
+    voices: Vec<String>,
+    sensitivity: f32,
+    model: PathBuf,
+}
+
+#[cfg(feature = "collection")]
+impl Scheduler {
+    /// Create a scheduler.
+    ///
+    /// # Arguments
+    ///
+    /// * `interface`: The network interface to capture traffic on.
+    /// * `voices`: The voices to ask queries with, one random voice is used per session.
+    /// * `sensitivity`: The sensitivity of the listener.
+    /// * `model`: The path to the whisper model used to transcribe responses.
+    pub fn new(interface: String, voices: Vec<String>, sensitivity: f32, model: PathBuf) -> Self {
+        Scheduler {
+            interface,
+            voices,
+            sensitivity,
+            model,
+        }
+    }
+
+    /// Wait for schedules to become due and run their sessions, until Ctrl-C is pressed or an error occurs outside of
+    /// a session.
+    ///
+    /// Errors during a session are stored in the history of the schedule and do not stop the scheduler. Pressing
+    /// Ctrl-C during a session completes it before stopping.
+    pub async fn run(&self) -> Result<(), Error> {
+        let connection = database::connect().await?;
+        let shutdown = Shutdown::on_ctrl_c();
+
+        while !shutdown.is_requested() {
+            let Some(mut schedule) = Schedule::get_all(&connection).await?.into_iter().next()
+            else {
+                info!("No schedules found, waiting for one to be added...");
+                shutdown.sleep(POLL_INTERVAL).await;
+                continue;
+            };
+
+            let wait = (schedule.next_run - Utc::now())
+                .to_std()
+                .unwrap_or_default();
+            if wait > Duration::ZERO {
+                info!("Waiting for {schedule}");
+                shutdown.sleep(wait.min(POLL_INTERVAL)).await;
+                continue;
+            }
+
+            self.run_schedule(&schedule, &connection, &shutdown).await?;
+
+            let expression: Expression = schedule.expression.parse()?;
+            match expression.next_after(Utc::now()) {
+                Some(next_run) => {
+                    schedule.next_run = next_run;
+                    schedule.update(&connection).await?;
+                }
+                None => {
+                    info!("{schedule} does not run again");
+                    schedule.delete(&connection).await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Run one session of a schedule and store it in its history.
+    ///
+    /// # Arguments
+    ///
+    /// * `schedule`: The schedule to run.
+    /// * `connection`: The connection to use.
+    /// * `shutdown`: The shutdown that stops the session early.
+    async fn run_schedule(
+        &self,
+        schedule: &Schedule,
+        connection: &DatabaseConnection,
+        shutdown: &Shutdown,
+    ) -> Result<(), Error> {
+        info!("Running {schedule}");
+
+        let mut run = ScheduleRun::create(connection, schedule.id).await?;
+        let result = self.run_session(schedule, connection, shutdown).await;
+        match &result {
+            Ok(session_id) => info!("Schedule {} ran session {session_id}", schedule.name),
+            Err(error) => error!("Schedule {} failed: {error}", schedule.name),
+        }
+        run.complete(
+            connection,
+            result.as_ref().ok().copied(),
+            result.err().map(|error| error.to_string()),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Run the session of a schedule.
+    ///
+    /// Returns the id of the session.
+    ///
+    /// # Arguments
+    ///
+    /// * `schedule`: The schedule to run.
+    /// * `connection`: The connection to use.
+    /// * `shutdown`: The shutdown that stops the session early.
+    async fn run_session(
+        &self,
+        schedule: &Schedule,
+        connection: &DatabaseConnection,
+        shutdown: &Shutdown,
+    ) -> Result<i32, Error> {
+        let mut interactor = Interactor::new(
+            self.interface.clone(),
+            self.voices.clone(),
+            self.sensitivity,
+            self.model.to_string_lossy().to_string(),
+            PathBuf::from(&schedule.data_dir),
+            schedule.mac.clone(),
+        )?;
+        interactor.set_database(Some(connection.clone()));
+        interactor.set_shutdown(Some(shutdown.clone()));
+        interactor.set_quiet_hours(
+            schedule
+                .quiet_hours
+                .as_deref()
+                .map(str::parse)
+                .transpose()
+                .map_err(Error::InvalidQuietHours)?,
+        );
+        interactor.set_idle_capture(schedule.idle_capture);
+
+        let assistant = assistant::from(&schedule.assistant);
+        let mut queries = Query::read(&schedule.queries).await?;
+        validation::validate(
+            queries.iter().map(|query| query.text.as_str()),
+            Some(&assistant.wake_word()),
+        )
+        .allow_repeats(schedule.allow_repeats)
+        .check()?;
+        if let Some(balance) = schedule.balance {
+            let samples = Interaction::count_by_query(
+                connection,
+                &schedule.mac,
+                &InteractionStatus::Valid.to_string(),
+            )
+            .await?;
+            queries = query::least_sampled(queries, &samples, balance.max(0) as usize);
+
+            info!(
+                "Asking the {} queries with the fewest samples",
+                queries.len()
+            );
+        }
+
+        #[cfg(feature = "transcription")]
+        let transcriber_handle = {
+            let (transcriber, transcriber_handle) =
+                Transcriber::new(Recogniser::with_model_path(&self.model.to_string_lossy())?);
+
+            let _ = std::thread::spawn(move || transcriber.start());
+            Some(transcriber_handle)
+        };
+        #[cfg(not(feature = "transcription"))]
+        let transcriber_handle = None;
+
+        interactor
+            .start(&mut queries, assistant.as_ref(), transcriber_handle)
+            .await
+    }
+}